@@ -0,0 +1,40 @@
+CREATE TABLE event_type_stats (
+    bucket_size  TEXT NOT NULL,
+    bucket_start TIMESTAMPTZ NOT NULL,
+    event_type   TEXT NOT NULL,
+    event_count  BIGINT NOT NULL,
+    PRIMARY KEY (bucket_size, bucket_start, event_type)
+);
+
+CREATE TABLE delivery_stats (
+    bucket_size    TEXT NOT NULL,
+    bucket_start   TIMESTAMPTZ NOT NULL,
+    status         TEXT NOT NULL,
+    job_count      BIGINT NOT NULL,
+    total_attempts BIGINT NOT NULL,
+    PRIMARY KEY (bucket_size, bucket_start, status)
+);
+
+CREATE TABLE daily_summaries (
+    bucket_size   TEXT NOT NULL,
+    bucket_start  TIMESTAMPTZ NOT NULL,
+    source        TEXT NOT NULL,
+    direction     TEXT NOT NULL,
+    currency      TEXT NOT NULL,
+    status        TEXT NOT NULL,
+    payment_count BIGINT NOT NULL,
+    total_amount  BIGINT NOT NULL,
+    PRIMARY KEY (bucket_size, bucket_start, source, direction, currency, status)
+);
+
+CREATE TABLE rollup_watermarks (
+    rollup      TEXT NOT NULL,
+    bucket_size TEXT NOT NULL,
+    watermark   TIMESTAMPTZ NOT NULL,
+    updated_at  TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (rollup, bucket_size)
+);
+
+CREATE INDEX idx_provider_events_received_at ON provider_events(received_at);
+CREATE INDEX idx_payment_jobs_created_at     ON payment_jobs(created_at);
+CREATE INDEX idx_payments_created_at         ON payments(created_at);
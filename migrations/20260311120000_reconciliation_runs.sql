@@ -0,0 +1,25 @@
+CREATE TABLE reconciliation_runs (
+    id               UUID PRIMARY KEY DEFAULT uuidv7(),
+    source           TEXT NOT NULL,
+    window_start     TIMESTAMPTZ NOT NULL,
+    window_end       TIMESTAMPTZ NOT NULL,
+    status           TEXT NOT NULL DEFAULT 'running'
+                     CHECK (status IN ('running', 'completed', 'failed')),
+    payments_checked INT NOT NULL DEFAULT 0,
+    discrepancies    INT NOT NULL DEFAULT 0,
+    error            TEXT,
+    started_at       TIMESTAMPTZ NOT NULL DEFAULT now(),
+    finished_at      TIMESTAMPTZ
+);
+
+CREATE INDEX idx_reconciliation_runs_started_at ON reconciliation_runs(started_at);
+
+-- Provider-side reconciliation can find objects we never stored, so the
+-- payment link becomes optional and the provider's id is kept alongside.
+ALTER TABLE reconciliations ALTER COLUMN payment_id DROP NOT NULL;
+ALTER TABLE reconciliations ADD COLUMN run_id UUID REFERENCES reconciliation_runs(id);
+ALTER TABLE reconciliations ADD COLUMN external_id TEXT;
+ALTER TABLE reconciliations ADD COLUMN kind TEXT;
+
+CREATE INDEX idx_reconciliations_run_id      ON reconciliations(run_id);
+CREATE INDEX idx_reconciliations_external_id ON reconciliations(external_id);
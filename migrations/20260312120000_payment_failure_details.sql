@@ -0,0 +1,18 @@
+ALTER TABLE payments ADD COLUMN failure_code        TEXT;
+ALTER TABLE payments ADD COLUMN decline_code        TEXT;
+ALTER TABLE payments ADD COLUMN failure_message     TEXT;
+ALTER TABLE payments ADD COLUMN network_advice_code TEXT;
+
+CREATE INDEX idx_payments_decline_code ON payments(decline_code) WHERE decline_code IS NOT NULL;
+
+CREATE TABLE failure_reason_stats (
+    bucket_size   TEXT NOT NULL,
+    bucket_start  TIMESTAMPTZ NOT NULL,
+    source        TEXT NOT NULL,
+    status        TEXT NOT NULL,
+    failure_code  TEXT NOT NULL,
+    decline_code  TEXT NOT NULL,
+    payment_count BIGINT NOT NULL,
+    total_amount  BIGINT NOT NULL,
+    PRIMARY KEY (bucket_size, bucket_start, source, status, failure_code, decline_code)
+);
@@ -0,0 +1,15 @@
+CREATE TABLE dispute_stats (
+    bucket_size     TEXT NOT NULL,
+    bucket_start    TIMESTAMPTZ NOT NULL,
+    currency        TEXT NOT NULL,
+    card_brand      TEXT NOT NULL,
+    dispute_count   BIGINT NOT NULL,
+    disputed_amount BIGINT NOT NULL,
+    fee_amount      BIGINT NOT NULL,
+    won_count       BIGINT NOT NULL,
+    lost_count      BIGINT NOT NULL,
+    PRIMARY KEY (bucket_size, bucket_start, currency, card_brand)
+);
+
+CREATE INDEX idx_provider_events_disputes ON provider_events(provider_ts)
+    WHERE event_type LIKE 'charge.dispute.%';
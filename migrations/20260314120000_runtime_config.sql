@@ -0,0 +1,8 @@
+-- Single-row table: the live runtime config and its version.
+CREATE TABLE runtime_config (
+    id         BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+    version    BIGINT NOT NULL,
+    config     JSONB NOT NULL,
+    updated_by TEXT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
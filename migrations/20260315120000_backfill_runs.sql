@@ -0,0 +1,21 @@
+CREATE TABLE backfill_runs (
+    id           UUID PRIMARY KEY DEFAULT uuidv7(),
+    source       TEXT NOT NULL,
+    window_start TIMESTAMPTZ NOT NULL,
+    window_end   TIMESTAMPTZ NOT NULL,
+    status       TEXT NOT NULL DEFAULT 'running'
+                 CHECK (status IN ('running', 'completed', 'failed')),
+    -- Provider listing position of the next page; NULL before the first page.
+    cursor       TEXT,
+    pages        INT NOT NULL DEFAULT 0,
+    created      INT NOT NULL DEFAULT 0,
+    updated      INT NOT NULL DEFAULT 0,
+    unchanged    INT NOT NULL DEFAULT 0,
+    skipped      INT NOT NULL DEFAULT 0,
+    error        TEXT,
+    started_at   TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at   TIMESTAMPTZ NOT NULL DEFAULT now(),
+    finished_at  TIMESTAMPTZ
+);
+
+CREATE INDEX idx_backfill_runs_started_at ON backfill_runs(started_at);
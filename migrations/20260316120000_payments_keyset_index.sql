@@ -0,0 +1,4 @@
+-- Keyset pagination orders by (created_at, external_id); the composite index
+-- also serves the plain created_at range scans the old index covered.
+DROP INDEX idx_payments_created_at;
+CREATE INDEX idx_payments_created_at_external_id ON payments(created_at, external_id);
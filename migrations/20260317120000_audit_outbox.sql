@@ -0,0 +1,30 @@
+-- Audit entries waiting to be shipped to the separate audit database.
+-- Filled by trigger in the same transaction as the audit_log insert, so the
+-- pipeline stays atomic; the relay deletes rows once the target has them.
+CREATE TABLE audit_outbox (
+    seq         BIGSERIAL PRIMARY KEY,
+    audit_id    UUID NOT NULL,
+    enqueued_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- Present once an audit target has been registered. Until then nothing is
+-- queued, so deployments without a separate audit DB pay no cost.
+CREATE TABLE audit_relay_state (
+    id              BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+    enabled_at      TIMESTAMPTZ NOT NULL DEFAULT now(),
+    shipped_total   BIGINT NOT NULL DEFAULT 0,
+    last_shipped_at TIMESTAMPTZ
+);
+
+CREATE FUNCTION audit_log_enqueue() RETURNS trigger AS $$
+BEGIN
+    IF EXISTS (SELECT 1 FROM audit_relay_state) THEN
+        INSERT INTO audit_outbox (audit_id) VALUES (NEW.id);
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER audit_log_outbox
+    AFTER INSERT ON audit_log
+    FOR EACH ROW EXECUTE FUNCTION audit_log_enqueue();
@@ -0,0 +1,4 @@
+-- Which provider a job came from, so the worker can fetch from the right one.
+-- Existing jobs all came from Stripe.
+ALTER TABLE payment_jobs ADD COLUMN source TEXT NOT NULL DEFAULT 'stripe';
+ALTER TABLE payment_jobs ALTER COLUMN source DROP DEFAULT;
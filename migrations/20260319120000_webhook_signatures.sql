@@ -0,0 +1,13 @@
+-- Signatures of accepted webhook deliveries, so a captured delivery can't be
+-- replayed while it is still inside the provider's signature tolerance.
+-- Rows older than the configured max age are pruned by the reaper; anything
+-- signed before that is rejected as stale anyway.
+CREATE TABLE webhook_signatures (
+    source    TEXT        NOT NULL,
+    signature TEXT        NOT NULL,
+    signed_at TIMESTAMPTZ NOT NULL,
+    seen_at   TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (source, signature)
+);
+
+CREATE INDEX idx_webhook_signatures_signed_at ON webhook_signatures (signed_at);
@@ -0,0 +1,8 @@
+-- Which worker instance (`<hostname>/<instance id>`) last claimed a job.
+-- NULL for jobs never claimed.
+ALTER TABLE payment_jobs ADD COLUMN claimed_by TEXT;
+
+-- Delivery stats per claiming worker; '' for jobs no worker has claimed yet.
+ALTER TABLE delivery_stats ADD COLUMN claimed_by TEXT NOT NULL DEFAULT '';
+ALTER TABLE delivery_stats DROP CONSTRAINT delivery_stats_pkey;
+ALTER TABLE delivery_stats ADD PRIMARY KEY (bucket_size, bucket_start, status, claimed_by);
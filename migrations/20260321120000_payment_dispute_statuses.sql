@@ -0,0 +1,7 @@
+-- Disputes are stored as outbound rows (`dp_xxx`) linked to the disputed
+-- payment through parent_external_id, with their own statuses.
+ALTER TABLE payments DROP CONSTRAINT chk_payments_status;
+ALTER TABLE payments ADD CONSTRAINT chk_payments_status CHECK (status IN (
+    'pending', 'succeeded', 'failed', 'refunded',
+    'disputed', 'dispute_won', 'dispute_lost'
+));
@@ -0,0 +1,18 @@
+-- Payments whose webhook lifecycle has a hole: terminal without an opening
+-- event, or open and silent past the timeout. One row per payment and kind;
+-- a gap that closes gets resolved_at, and is reopened if it comes back.
+CREATE TABLE event_gaps (
+    id                UUID PRIMARY KEY DEFAULT uuidv7(),
+    source            TEXT NOT NULL,
+    external_id       TEXT NOT NULL,
+    kind              TEXT NOT NULL,
+    status            TEXT NOT NULL,
+    refetch_requested BOOLEAN NOT NULL DEFAULT false,
+    detected_at       TIMESTAMPTZ NOT NULL DEFAULT now(),
+    resolved_at       TIMESTAMPTZ,
+
+    CONSTRAINT uq_event_gaps_object_kind UNIQUE (external_id, kind),
+    CONSTRAINT chk_event_gaps_kind CHECK (kind IN ('missing_opening', 'missing_terminal'))
+);
+
+CREATE INDEX idx_event_gaps_open ON event_gaps(detected_at) WHERE resolved_at IS NULL;
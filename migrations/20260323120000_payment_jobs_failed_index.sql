@@ -0,0 +1,2 @@
+-- Dead-letter listing and bulk requeue scan failed jobs by when they failed.
+CREATE INDEX idx_payment_jobs_failed ON payment_jobs(updated_at) WHERE status = 'failed';
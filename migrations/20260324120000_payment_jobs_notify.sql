@@ -0,0 +1,14 @@
+-- Wake listening workers whenever a job becomes claimable: new jobs, retries,
+-- requeues and reaped jobs all land in 'pending'. Postgres folds identical
+-- notifications within a transaction into one.
+CREATE FUNCTION payment_jobs_notify() RETURNS trigger AS $$
+BEGIN
+    PERFORM pg_notify('payment_jobs', '');
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER payment_jobs_pending
+    AFTER INSERT OR UPDATE OF status ON payment_jobs
+    FOR EACH ROW WHEN (NEW.status = 'pending')
+    EXECUTE FUNCTION payment_jobs_notify();
@@ -0,0 +1,10 @@
+-- Auth/capture payments: the amount held by the last uncaptured
+-- authorization, and a terminal status for authorizations that lapsed.
+ALTER TABLE payments ADD COLUMN authorized_amount BIGINT
+    CONSTRAINT chk_payments_authorized_amount CHECK (authorized_amount >= 0);
+
+ALTER TABLE payments DROP CONSTRAINT chk_payments_status;
+ALTER TABLE payments ADD CONSTRAINT chk_payments_status CHECK (status IN (
+    'pending', 'succeeded', 'failed', 'refunded',
+    'disputed', 'dispute_won', 'dispute_lost', 'expired'
+));
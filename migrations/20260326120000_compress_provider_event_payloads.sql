@@ -0,0 +1,8 @@
+-- Provider event payloads are stored encoded: a format byte, then the body.
+-- 0 = JSON text as-is, 1 = zstd-compressed JSON (see payload_codec.rs).
+-- Existing rows carry format 0 until `fin_sync compress-payloads` rewrites them.
+ALTER TABLE provider_events ADD COLUMN payload_encoded BYTEA;
+UPDATE provider_events SET payload_encoded = '\x00'::bytea || convert_to(payload::text, 'UTF8');
+ALTER TABLE provider_events DROP COLUMN payload;
+ALTER TABLE provider_events RENAME COLUMN payload_encoded TO payload;
+ALTER TABLE provider_events ALTER COLUMN payload SET NOT NULL;
@@ -0,0 +1,15 @@
+-- One row per time a worker picked up a job. Opened at claim time, closed
+-- when the worker finishes with it (or the reaper gives up on it), so the
+-- history survives `attempts` being reset by a requeue.
+CREATE TABLE job_attempts (
+    id          UUID PRIMARY KEY DEFAULT uuidv7(),
+    job_id      UUID NOT NULL REFERENCES payment_jobs(id) ON DELETE CASCADE,
+    attempt     INT NOT NULL,
+    worker      TEXT NOT NULL,
+    started_at  TIMESTAMPTZ NOT NULL DEFAULT now(),
+    finished_at TIMESTAMPTZ,
+    -- NULL while the attempt is running.
+    outcome     TEXT CHECK (outcome IN ('succeeded', 'discarded', 'failed', 'abandoned')),
+    error       TEXT,
+    UNIQUE (job_id, attempt)
+);
@@ -0,0 +1,20 @@
+-- Support search: "the ~$49.99 payment referencing order 1234". The amount
+-- range filter gets an index of its own, and the references support quotes
+-- (order and invoice ids, statement descriptor, description) are folded into
+-- one lowercased column with a trigram index for substring and fuzzy matches.
+CREATE EXTENSION IF NOT EXISTS pg_trgm WITH SCHEMA public;
+
+CREATE INDEX idx_payments_amount ON payments(amount);
+
+ALTER TABLE payments ADD COLUMN search_text TEXT GENERATED ALWAYS AS (
+    lower(
+        coalesce(metadata->>'order_id', '') || ' ' ||
+        coalesce(metadata->>'reference', '') || ' ' ||
+        coalesce(metadata->>'invoice_id', '') || ' ' ||
+        coalesce(metadata->>'custom_id', '') || ' ' ||
+        coalesce(metadata->>'statement_descriptor', '') || ' ' ||
+        coalesce(metadata->>'description', '')
+    )
+) STORED;
+
+CREATE INDEX idx_payments_search_text ON payments USING gin (search_text gin_trgm_ops);
@@ -0,0 +1,5 @@
+-- Any ISO 4217 code is accepted now; the application validates it against
+-- its registry, the database only checks the shape.
+ALTER TABLE payments DROP CONSTRAINT chk_payments_currency;
+ALTER TABLE payments
+    ADD CONSTRAINT chk_payments_currency CHECK (currency ~ '^[a-z]{3}$');
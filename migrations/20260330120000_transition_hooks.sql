@@ -0,0 +1,41 @@
+-- Transition hooks registered by the embedding application. A hook is
+-- subscribed when a dispatcher starts with it; from then on every payment
+-- transition is queued for it, whether or not a dispatcher is running.
+CREATE TABLE hook_subscriptions (
+    hook          TEXT PRIMARY KEY,
+    subscribed_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- One row per (hook, transition) still to deliver. Filled by trigger in the
+-- same transaction as the audit entry that records the transition, so a
+-- hook only ever sees committed state; the dispatcher deletes a row once
+-- its hook has accepted it and reschedules it when the hook fails.
+CREATE TABLE hook_outbox (
+    seq             BIGSERIAL PRIMARY KEY,
+    hook            TEXT NOT NULL REFERENCES hook_subscriptions (hook) ON DELETE CASCADE,
+    audit_id        UUID NOT NULL,
+    attempts        INT NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    last_error      TEXT,
+    -- Set when the hook has failed every attempt; the row is kept for inspection.
+    failed_at       TIMESTAMPTZ
+);
+
+CREATE INDEX idx_hook_outbox_due ON hook_outbox (hook, next_attempt_at)
+    WHERE failed_at IS NULL;
+
+CREATE FUNCTION hook_outbox_enqueue() RETURNS trigger AS $$
+BEGIN
+    IF NEW.entity_type = 'payment'
+       AND (NEW.action IN ('created', 'status_changed')
+            OR (NEW.action = 'event_received' AND NEW.detail->>'anomaly' = 'true')) THEN
+        INSERT INTO hook_outbox (hook, audit_id)
+        SELECT hook, NEW.id FROM hook_subscriptions;
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER audit_log_hook_outbox
+    AFTER INSERT ON audit_log
+    FOR EACH ROW EXECUTE FUNCTION hook_outbox_enqueue();
@@ -0,0 +1,7 @@
+-- What the customer saw: the statement descriptor their bank shows and the
+-- receipt they were sent. receipt_email is stored as RECEIPT_EMAIL_STORAGE
+-- allows (full, masked or not at all).
+ALTER TABLE payments
+    ADD COLUMN statement_descriptor TEXT,
+    ADD COLUMN receipt_email TEXT,
+    ADD COLUMN receipt_url TEXT;
@@ -0,0 +1,16 @@
+-- API keys for everything but the webhooks. Only a SHA-256 of each key is
+-- kept; `prefix` (the key's first characters) tells keys apart in listings.
+CREATE TABLE api_keys (
+    id           UUID PRIMARY KEY,
+    name         TEXT NOT NULL UNIQUE,
+    prefix       TEXT NOT NULL,
+    key_hash     TEXT NOT NULL UNIQUE,
+    scopes       TEXT[] NOT NULL,
+    created_by   TEXT NOT NULL,
+    created_at   TIMESTAMPTZ NOT NULL DEFAULT now(),
+    last_used_at TIMESTAMPTZ,
+    revoked_at   TIMESTAMPTZ,
+    CONSTRAINT chk_api_keys_scopes CHECK (
+        cardinality(scopes) > 0 AND scopes <@ ARRAY['read', 'replay', 'admin']
+    )
+);
@@ -0,0 +1,35 @@
+-- Cancellations (abandoned checkouts, canceled refunds) get their own
+-- terminal status instead of counting as failures.
+ALTER TABLE payments DROP CONSTRAINT chk_payments_status;
+ALTER TABLE payments ADD CONSTRAINT chk_payments_status CHECK (status IN (
+    'pending', 'succeeded', 'failed', 'refunded',
+    'disputed', 'dispute_won', 'dispute_lost', 'expired', 'cancelled'
+));
+
+-- Reclassify failures whose last event was a cancellation. Backfilled rows
+-- don't keep the provider object, so those stay failed.
+WITH reclassified AS (
+    UPDATE payments
+    SET status = 'cancelled', updated_at = now()
+    WHERE status = 'failed'
+      AND (
+          (source = 'stripe' AND (
+              event_type = 'payment_intent.canceled'
+              OR raw_event #>> '{data,object,status}' = 'canceled'
+          ))
+          OR (source = 'paypal' AND raw_event #>> '{resource,status}' = 'CANCELLED')
+      )
+    RETURNING id, external_id, last_event_id
+)
+INSERT INTO audit_log (id, entity_type, entity_id, external_id, event_id, action, actor, detail)
+SELECT uuidv7(), 'payment', id, external_id, 'reclassify_cancelled:' || external_id,
+       'status_reclassified', 'migration:cancelled_status',
+       jsonb_build_object(
+           'old_status', 'failed',
+           'new_status', 'cancelled',
+           'last_event_id', last_event_id
+       )
+FROM reclassified;
+
+-- Rebuild the status-keyed rollups from the retention horizon on their next run.
+DELETE FROM rollup_watermarks WHERE rollup IN ('daily_summaries', 'failure_reason_stats');
@@ -0,0 +1,31 @@
+-- Money leaving Stripe: payouts to our bank accounts (`po_`) and transfers
+-- to connected accounts (`tr_`). Kept apart from payments so they don't
+-- count in payment reports; deduplicated through provider_events and
+-- audited like payments.
+CREATE TABLE payouts (
+    id               UUID PRIMARY KEY DEFAULT uuidv7(),
+    external_id      TEXT NOT NULL UNIQUE,
+    source           TEXT NOT NULL,
+    kind             TEXT NOT NULL,
+    amount           BIGINT NOT NULL,
+    currency         TEXT NOT NULL,
+    status           TEXT NOT NULL,
+    amount_reversed  BIGINT NOT NULL DEFAULT 0,
+    destination      TEXT,
+    arrival_date     TIMESTAMPTZ,
+    failure_code     TEXT,
+    failure_message  TEXT,
+    metadata         JSONB NOT NULL DEFAULT '{}',
+    last_event_id    TEXT NOT NULL,
+    last_provider_ts BIGINT NOT NULL,
+    created_at       TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at       TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+    CONSTRAINT chk_payouts_kind   CHECK (kind IN ('payout', 'transfer')),
+    CONSTRAINT chk_payouts_status CHECK (status IN ('pending', 'paid', 'failed', 'cancelled')),
+    CONSTRAINT chk_payouts_currency CHECK (currency ~ '^[a-z]{3}$'),
+    CONSTRAINT chk_payouts_amount CHECK (amount >= 0 AND amount_reversed BETWEEN 0 AND amount)
+);
+
+CREATE INDEX idx_payouts_created_at ON payouts(created_at, external_id);
+CREATE INDEX idx_payouts_status ON payouts(status);
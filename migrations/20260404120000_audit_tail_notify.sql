@@ -0,0 +1,18 @@
+-- Announce every audit entry on the `audit_log` channel for live tails
+-- (GET /admin/audit/tail). The payload carries what tails filter on, so a
+-- tail only reads back the entries it shows.
+CREATE FUNCTION audit_log_notify() RETURNS trigger AS $$
+BEGIN
+    PERFORM pg_notify('audit_log', json_build_object(
+        'id', NEW.id,
+        'external_id', NEW.external_id,
+        'action', NEW.action,
+        'actor', left(NEW.actor, 200)
+    )::text);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER audit_log_notify
+    AFTER INSERT ON audit_log
+    FOR EACH ROW EXECUTE FUNCTION audit_log_notify();
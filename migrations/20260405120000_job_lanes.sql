@@ -0,0 +1,22 @@
+-- Refund jobs get a worker lane of their own, so a backlog of routine
+-- payment updates never holds up refund status.
+ALTER TABLE payment_jobs ADD COLUMN lane TEXT NOT NULL DEFAULT 'standard';
+ALTER TABLE payment_jobs ADD CONSTRAINT chk_payment_jobs_lane
+    CHECK (lane IN ('standard', 'refund'));
+
+UPDATE payment_jobs SET lane = 'refund'
+WHERE object_id LIKE 're\_%' OR object_id LIKE 'pp\_ref\_%';
+
+DROP INDEX idx_payment_jobs_claimable;
+CREATE INDEX idx_payment_jobs_claimable
+    ON payment_jobs (lane, scheduled_at)
+    WHERE status = 'pending';
+
+-- Name the lane in the notification, so each lane's worker only wakes for
+-- its own jobs.
+CREATE OR REPLACE FUNCTION payment_jobs_notify() RETURNS trigger AS $$
+BEGIN
+    PERFORM pg_notify('payment_jobs', NEW.lane);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
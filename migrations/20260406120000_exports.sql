@@ -0,0 +1,27 @@
+-- Snapshot exports of payments and audit entries written to disk or object
+-- storage, each tracked from start to finish.
+CREATE TABLE exports (
+    id           UUID PRIMARY KEY DEFAULT uuidv7(),
+    kind         TEXT NOT NULL CHECK (kind IN ('payments', 'audit')),
+    format       TEXT NOT NULL CHECK (format IN ('csv', 'ndjson')),
+    window_start TIMESTAMPTZ NOT NULL,
+    window_end   TIMESTAMPTZ NOT NULL,
+    trigger      TEXT NOT NULL CHECK (trigger IN ('schedule', 'manual')),
+    requested_by TEXT NOT NULL,
+    status       TEXT NOT NULL DEFAULT 'running'
+                 CHECK (status IN ('running', 'completed', 'failed')),
+    location     TEXT,
+    row_count    BIGINT,
+    byte_count   BIGINT,
+    error        TEXT,
+    started_at   TIMESTAMPTZ NOT NULL DEFAULT now(),
+    finished_at  TIMESTAMPTZ
+);
+
+CREATE INDEX idx_exports_started_at ON exports(started_at);
+
+-- One scheduled export per kind and window across replicas; a failed one
+-- doesn't count, so the next tick tries again.
+CREATE UNIQUE INDEX idx_exports_scheduled
+    ON exports (kind, format, window_start, window_end)
+    WHERE trigger = 'schedule' AND status <> 'failed';
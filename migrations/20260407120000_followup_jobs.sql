@@ -0,0 +1,35 @@
+-- Follow-up work the pipeline queues in the transaction that made it
+-- necessary, run by the job worker once that commits. `dedup_key` names the
+-- work itself, so queueing it again is a no-op.
+CREATE TABLE followup_jobs (
+    id           UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    kind         TEXT NOT NULL,
+    dedup_key    TEXT NOT NULL UNIQUE,
+    payload      JSONB NOT NULL,
+    status       TEXT NOT NULL DEFAULT 'pending'
+                 CHECK (status IN ('pending', 'processing', 'completed', 'failed')),
+    attempts     INT NOT NULL DEFAULT 0,
+    max_attempts INT NOT NULL DEFAULT 5,
+    last_error   TEXT,
+    claimed_by   TEXT,
+    scheduled_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    created_at   TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at   TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_followup_jobs_claimable
+    ON followup_jobs (scheduled_at)
+    WHERE status = 'pending';
+
+-- The standard lane's worker runs follow-ups, so wake it.
+CREATE FUNCTION followup_jobs_notify() RETURNS trigger AS $$
+BEGIN
+    PERFORM pg_notify('payment_jobs', 'standard');
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER followup_jobs_pending
+    AFTER INSERT OR UPDATE OF status ON followup_jobs
+    FOR EACH ROW WHEN (NEW.status = 'pending')
+    EXECUTE FUNCTION followup_jobs_notify();
@@ -0,0 +1,39 @@
+-- Every audit entry, numbered for consumers that poll GET /outbox/events
+-- with an offset of their own. The trigger appends a row, unnumbered, in
+-- the transaction that writes the entry; readers number committed rows in
+-- batches under an advisory lock. Numbering at insert would let one
+-- transaction commit seq N+1 while another, still running, holds seq N,
+-- and a consumer past N+1 would never see N.
+CREATE TABLE outbox_events (
+    id         BIGSERIAL PRIMARY KEY,
+    audit_id   UUID NOT NULL,
+    seq        BIGINT UNIQUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_outbox_events_unsequenced ON outbox_events (id) WHERE seq IS NULL;
+
+-- Entries written before the outbox existed, numbered in the order written.
+INSERT INTO outbox_events (audit_id, seq, created_at)
+SELECT id, row_number() OVER (ORDER BY created_at, id), created_at
+FROM audit_log;
+
+-- The offset each named consumer has committed: the last seq it processed.
+CREATE TABLE consumer_offsets (
+    consumer   TEXT PRIMARY KEY,
+    last_seq   BIGINT NOT NULL CHECK (last_seq >= 0),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- Long polls wake on `outbox_events` once the entry is committed.
+CREATE FUNCTION outbox_events_enqueue() RETURNS trigger AS $$
+BEGIN
+    INSERT INTO outbox_events (audit_id) VALUES (NEW.id);
+    PERFORM pg_notify('outbox_events', '');
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER audit_log_outbox_events
+    AFTER INSERT ON audit_log
+    FOR EACH ROW EXECUTE FUNCTION outbox_events_enqueue();
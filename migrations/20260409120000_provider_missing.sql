@@ -0,0 +1,10 @@
+-- A job whose object the provider doesn't have (deleted, or never existed)
+-- is completed as `provider_missing` instead of retried into the
+-- dead-letter queue.
+ALTER TABLE job_attempts DROP CONSTRAINT job_attempts_outcome_check;
+ALTER TABLE job_attempts ADD CONSTRAINT job_attempts_outcome_check
+    CHECK (outcome IN ('succeeded', 'discarded', 'failed', 'abandoned', 'provider_missing'));
+
+-- Discrepancies flagged outside a reconciliation run, waiting for review.
+CREATE INDEX idx_reconciliations_review ON reconciliations (created_at)
+    WHERE run_id IS NULL AND resolved_at IS NULL;
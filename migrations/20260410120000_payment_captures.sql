@@ -0,0 +1,25 @@
+-- Captures of an authorized payment, one row per capture event. A
+-- multicapture PaymentIntent is captured in several parts; each is kept
+-- here against the payment, by external id since capture events can
+-- arrive before the payment itself is recorded. Deduplicated through
+-- provider_events like every other event.
+CREATE TABLE payment_captures (
+    id                  UUID PRIMARY KEY DEFAULT uuidv7(),
+    payment_external_id TEXT NOT NULL,
+    source              TEXT NOT NULL,
+    charge_id           TEXT NOT NULL,
+    amount              BIGINT NOT NULL,
+    currency            TEXT NOT NULL,
+    -- As the charge reported it after this capture.
+    captured_total      BIGINT NOT NULL,
+    authorized_amount   BIGINT NOT NULL,
+    event_id            TEXT NOT NULL UNIQUE,
+    provider_ts         BIGINT NOT NULL,
+    created_at          TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+    CONSTRAINT chk_payment_captures_currency CHECK (currency ~ '^[a-z]{3}$'),
+    CONSTRAINT chk_payment_captures_amount
+        CHECK (amount > 0 AND captured_total >= amount AND authorized_amount >= 0)
+);
+
+CREATE INDEX idx_payment_captures_payment ON payment_captures(payment_external_id);
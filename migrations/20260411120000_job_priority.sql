@@ -0,0 +1,11 @@
+-- Within a lane, jobs are claimed by priority first (2 high, 1 normal,
+-- 0 low), then by when they are due, so events that move money aren't
+-- stuck behind a backlog of ones that don't.
+ALTER TABLE payment_jobs ADD COLUMN priority SMALLINT NOT NULL DEFAULT 1;
+ALTER TABLE payment_jobs ADD CONSTRAINT chk_payment_jobs_priority
+    CHECK (priority BETWEEN 0 AND 2);
+
+DROP INDEX idx_payment_jobs_claimable;
+CREATE INDEX idx_payment_jobs_claimable
+    ON payment_jobs (lane, priority DESC, scheduled_at)
+    WHERE status = 'pending';
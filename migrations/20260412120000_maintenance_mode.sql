@@ -0,0 +1,10 @@
+-- Single-row table: maintenance mode, while it lasts. Webhooks get 503 and
+-- workers claim nothing until the row is removed or `expires_at` passes,
+-- so a forgotten switch turns itself off.
+CREATE TABLE maintenance_mode (
+    id         BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+    reason     TEXT NOT NULL,
+    enabled_by TEXT NOT NULL,
+    enabled_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    expires_at TIMESTAMPTZ NOT NULL
+);
@@ -0,0 +1,41 @@
+-- Target of the payments schema overhaul: hash-partitioned on external_id,
+-- with the id, amounts and provider timestamp renamed to say what they
+-- hold. Filled in alongside `payments` while the runtime config's
+-- `payments_v2` stage is `dual_write` or `read_new`, and by backfill for
+-- rows older than that. Carries no search column or secondary indexes
+-- beyond what the point reads need yet.
+CREATE TABLE payments_v2 (
+    external_id             TEXT NOT NULL,
+    payment_id              UUID NOT NULL,
+    source                  TEXT NOT NULL,
+    event_type              TEXT NOT NULL,
+    direction               TEXT NOT NULL,
+    amount_minor            BIGINT NOT NULL,
+    currency_code           TEXT NOT NULL,
+    status                  TEXT NOT NULL,
+    metadata                JSONB NOT NULL,
+    raw_event               JSONB NOT NULL,
+    last_event_id           TEXT NOT NULL,
+    parent_external_id      TEXT,
+    last_event_ts           BIGINT NOT NULL,
+    failure_code            TEXT,
+    decline_code            TEXT,
+    failure_message         TEXT,
+    network_advice_code     TEXT,
+    authorized_amount_minor BIGINT,
+    statement_descriptor    TEXT,
+    receipt_email           TEXT,
+    receipt_url             TEXT,
+    received_at             TIMESTAMPTZ NOT NULL,
+    created_at              TIMESTAMPTZ NOT NULL,
+    updated_at              TIMESTAMPTZ NOT NULL,
+
+    PRIMARY KEY (external_id)
+) PARTITION BY HASH (external_id);
+
+CREATE TABLE payments_v2_p0 PARTITION OF payments_v2 FOR VALUES WITH (MODULUS 4, REMAINDER 0);
+CREATE TABLE payments_v2_p1 PARTITION OF payments_v2 FOR VALUES WITH (MODULUS 4, REMAINDER 1);
+CREATE TABLE payments_v2_p2 PARTITION OF payments_v2 FOR VALUES WITH (MODULUS 4, REMAINDER 2);
+CREATE TABLE payments_v2_p3 PARTITION OF payments_v2 FOR VALUES WITH (MODULUS 4, REMAINDER 3);
+
+CREATE INDEX idx_payments_v2_payment_id ON payments_v2(payment_id);
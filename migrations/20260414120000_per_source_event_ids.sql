@@ -0,0 +1,33 @@
+-- Provider event ids are only unique within one provider. Events, jobs and
+-- the audit entries recording an event are deduplicated on (source,
+-- event_id) instead. Indexes lead with event_id so lookups by event id
+-- alone still use them.
+
+-- Existing events take the source of their job, else of the payment or
+-- payout they are about; the rest are PayPal's (`evt_pp_` ids) or Stripe's.
+ALTER TABLE provider_events ADD COLUMN source TEXT;
+UPDATE provider_events e
+SET source = COALESCE(
+    (SELECT j.source FROM payment_jobs j WHERE j.event_id = e.event_id),
+    (SELECT p.source FROM payments p WHERE p.external_id = e.object_id),
+    (SELECT p.source FROM payouts p WHERE p.external_id = e.object_id),
+    CASE WHEN e.event_id LIKE 'evt\_pp\_%' THEN 'paypal' END,
+    'stripe'
+);
+ALTER TABLE provider_events ALTER COLUMN source SET NOT NULL;
+ALTER TABLE provider_events DROP CONSTRAINT provider_events_pkey;
+ALTER TABLE provider_events ADD PRIMARY KEY (event_id, source);
+
+ALTER TABLE payment_jobs DROP CONSTRAINT payment_jobs_event_id_key;
+ALTER TABLE payment_jobs ADD CONSTRAINT payment_jobs_event_id_source_key
+    UNIQUE (event_id, source);
+
+-- Set on entries recording a provider event; entries we generate ourselves
+-- (config changes, key rotations, ...) have none and stay unique by id.
+ALTER TABLE audit_log ADD COLUMN source TEXT;
+UPDATE audit_log a SET source = e.source
+FROM provider_events e
+WHERE e.event_id = a.event_id;
+DROP INDEX idx_audit_log_event_id;
+CREATE UNIQUE INDEX idx_audit_log_event_id_source
+    ON audit_log(event_id, source) NULLS NOT DISTINCT;
@@ -0,0 +1,5 @@
+-- Who a key is for: `support`, `finance` or `engineering`. Decides which
+-- fields read responses show. Existing keys keep seeing everything.
+ALTER TABLE api_keys ADD COLUMN role TEXT NOT NULL DEFAULT 'engineering'
+    CONSTRAINT chk_api_keys_role CHECK (role IN ('support', 'finance', 'engineering'));
+ALTER TABLE api_keys ALTER COLUMN role DROP DEFAULT;
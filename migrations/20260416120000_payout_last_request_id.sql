@@ -0,0 +1,4 @@
+-- The API request behind the last event applied to a payout, to order
+-- events within the same second. Rows from before have none and are
+-- ordered as if Stripe raised their last event on its own.
+ALTER TABLE payouts ADD COLUMN last_request_id TEXT;
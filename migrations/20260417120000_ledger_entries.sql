@@ -0,0 +1,31 @@
+-- Double-entry projection of payment state. Each status change that moves
+-- money posts one balanced entry, written in the same transaction as the
+-- payment update, so accounting reads entries instead of reinterpreting
+-- payment rows.
+CREATE TABLE ledger_entries (
+    id          UUID PRIMARY KEY DEFAULT uuidv7(),
+    payment_id  UUID NOT NULL REFERENCES payments(id),
+    external_id TEXT NOT NULL,
+    source      TEXT NOT NULL,
+    event_id    TEXT NOT NULL,
+    currency    TEXT NOT NULL,
+    from_status TEXT,
+    to_status   TEXT NOT NULL,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+    CONSTRAINT chk_ledger_entries_currency CHECK (currency ~ '^[a-z]{3}$')
+);
+
+CREATE INDEX idx_ledger_entries_payment ON ledger_entries(payment_id);
+CREATE INDEX idx_ledger_entries_external_id ON ledger_entries(external_id);
+
+-- One line per account an entry touches; an entry's debits equal its
+-- credits.
+CREATE TABLE ledger_lines (
+    entry_id UUID NOT NULL REFERENCES ledger_entries(id),
+    account  TEXT NOT NULL
+             CHECK (account IN ('cash', 'revenue', 'refunds', 'disputes', 'payments_out')),
+    side     TEXT NOT NULL CHECK (side IN ('debit', 'credit')),
+    amount   BIGINT NOT NULL CHECK (amount > 0),
+    PRIMARY KEY (entry_id, account)
+);
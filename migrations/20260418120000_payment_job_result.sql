@@ -0,0 +1,5 @@
+-- What processing a job came to, so a client that got "accepted" from the
+-- webhook can wait for it: the pipeline's result and the payment it touched.
+-- Unset until the job succeeds.
+ALTER TABLE payment_jobs ADD COLUMN result TEXT;
+ALTER TABLE payment_jobs ADD COLUMN payment_id UUID;
@@ -0,0 +1,26 @@
+-- The Stripe connected account (`acct_xxx`) an event was sent for; NULL
+-- for the platform's own account, and for every row from before. Events,
+-- jobs and the audit entries recording an event are deduplicated per
+-- tenant, so two accounts can't shadow each other's deliveries.
+ALTER TABLE payments ADD COLUMN tenant_id TEXT;
+CREATE INDEX idx_payments_tenant_id ON payments(tenant_id, created_at)
+    WHERE tenant_id IS NOT NULL;
+
+ALTER TABLE provider_events ADD COLUMN tenant_id TEXT;
+ALTER TABLE provider_events DROP CONSTRAINT provider_events_pkey;
+CREATE UNIQUE INDEX idx_provider_events_event_id_source_tenant
+    ON provider_events(event_id, source, tenant_id) NULLS NOT DISTINCT;
+
+ALTER TABLE payment_jobs ADD COLUMN tenant_id TEXT;
+ALTER TABLE payment_jobs DROP CONSTRAINT payment_jobs_event_id_source_key;
+CREATE UNIQUE INDEX idx_payment_jobs_event_id_source_tenant
+    ON payment_jobs(event_id, source, tenant_id) NULLS NOT DISTINCT;
+
+ALTER TABLE audit_log ADD COLUMN tenant_id TEXT;
+DROP INDEX idx_audit_log_event_id_source;
+CREATE UNIQUE INDEX idx_audit_log_event_id_source_tenant
+    ON audit_log(event_id, source, tenant_id) NULLS NOT DISTINCT;
+
+-- A key limited to one tenant only reads that tenant's payments and
+-- events. Existing keys see every tenant.
+ALTER TABLE api_keys ADD COLUMN tenant_id TEXT;
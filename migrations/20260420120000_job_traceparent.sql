@@ -0,0 +1,4 @@
+-- W3C `traceparent` of the span that queued the job (the webhook request,
+-- usually), so the worker's processing continues the same trace. NULL when
+-- traces aren't exported, and for jobs from before.
+ALTER TABLE payment_jobs ADD COLUMN traceparent TEXT;
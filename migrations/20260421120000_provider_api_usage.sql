@@ -0,0 +1,17 @@
+-- Provider API calls per source, operation and UTC hour or day, counted by
+-- `BudgetedProvider` before each call. A call over a hard budget isn't
+-- made; it counts in `refused` instead. The alert timestamps make sure
+-- each limit is alerted on once per window, across replicas.
+CREATE TABLE provider_api_usage (
+    source TEXT NOT NULL,
+    operation TEXT NOT NULL CHECK (operation IN ('fetch', 'list', 'lookup')),
+    period TEXT NOT NULL CHECK (period IN ('hour', 'day')),
+    period_start TIMESTAMPTZ NOT NULL,
+    calls BIGINT NOT NULL DEFAULT 0,
+    refused BIGINT NOT NULL DEFAULT 0,
+    soft_alerted_at TIMESTAMPTZ,
+    hard_alerted_at TIMESTAMPTZ,
+    PRIMARY KEY (source, operation, period, period_start)
+);
+
+CREATE INDEX provider_api_usage_period_start_idx ON provider_api_usage (period_start);
@@ -0,0 +1,5 @@
+-- The audit log newest first, and its anomalies (events the state machine
+-- refused) on their own, for the GraphQL listings.
+CREATE INDEX idx_audit_log_created_id ON audit_log (created_at DESC, id DESC);
+CREATE INDEX idx_audit_log_anomalies ON audit_log (created_at DESC, id DESC)
+    WHERE detail->>'anomaly' = 'true';
@@ -0,0 +1,23 @@
+-- Labels ops put on payments ("chargeback-review", "vip-customer") to keep
+-- working lists without a column per use case. Tags are lowercase slugs.
+CREATE TABLE payment_tags (
+    payment_id UUID NOT NULL REFERENCES payments(id) ON DELETE CASCADE,
+    tag        TEXT NOT NULL CHECK (tag ~ '^[a-z0-9][a-z0-9_:-]{0,63}$'),
+    tagged_by  TEXT NOT NULL,
+    tagged_at  TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (payment_id, tag)
+);
+
+-- `GET /payments?tag=`: the payments carrying a tag.
+CREATE INDEX payment_tags_tag_idx ON payment_tags (tag, payment_id);
+
+-- Named payment listings for the admin dashboard: `GET /payments` filters
+-- stored as JSON, run by name.
+CREATE TABLE saved_filters (
+    name        TEXT PRIMARY KEY CHECK (name ~ '^[a-z0-9][a-z0-9_-]{0,63}$'),
+    description TEXT NOT NULL DEFAULT '',
+    filters     JSONB NOT NULL,
+    updated_by  TEXT NOT NULL,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at  TIMESTAMPTZ NOT NULL DEFAULT now()
+);
@@ -0,0 +1,15 @@
+-- Where each feed of a payment stands: the latest event received from each
+-- provider account (the platform's, or a connected account's) that sends
+-- events about it. The pipeline merges feeds by these, so a lagging feed
+-- can't roll back what a fresher one already applied.
+CREATE TABLE payment_feeds (
+    payment_id       UUID NOT NULL REFERENCES payments(id) ON DELETE CASCADE,
+    feed             TEXT NOT NULL,
+    last_provider_ts BIGINT NOT NULL,
+    last_event_id    TEXT NOT NULL,
+    events           BIGINT NOT NULL DEFAULT 1,
+    -- Events from this feed that arrived behind another feed.
+    conflicts        BIGINT NOT NULL DEFAULT 0,
+    updated_at       TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (payment_id, feed)
+);
@@ -0,0 +1,22 @@
+-- Small persistent settings, one JSON value per key, for subsystems that
+-- need a switch or a handful of values rather than a table of their own.
+-- Every change is announced on the `settings` channel with its key, so
+-- replicas caching a value drop it.
+CREATE TABLE settings (
+    key        TEXT PRIMARY KEY CHECK (key ~ '^[a-z0-9_.:-]{1,100}$'),
+    value      JSONB NOT NULL,
+    version    BIGINT NOT NULL DEFAULT 1,
+    updated_by TEXT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE FUNCTION settings_notify() RETURNS trigger AS $$
+BEGIN
+    PERFORM pg_notify('settings', COALESCE(NEW.key, OLD.key));
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER settings_notify
+    AFTER INSERT OR UPDATE OR DELETE ON settings
+    FOR EACH ROW EXECUTE FUNCTION settings_notify();
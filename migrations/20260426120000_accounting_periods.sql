@@ -0,0 +1,45 @@
+-- Closed accounting periods. Each close freezes everything up to the end of
+-- its business day, under the end-of-day cutoff in force when it was made;
+-- the latest close not reopened is the lock the pipeline checks.
+CREATE TABLE accounting_periods (
+    id             UUID PRIMARY KEY DEFAULT uuidv7(),
+    business_date  DATE NOT NULL,
+    closed_through TIMESTAMPTZ NOT NULL,
+    reason         TEXT NOT NULL,
+    closed_by      TEXT NOT NULL,
+    closed_at      TIMESTAMPTZ NOT NULL DEFAULT now(),
+    reopened_by    TEXT,
+    reopened_at    TIMESTAMPTZ,
+    reopen_reason  TEXT
+);
+
+CREATE INDEX idx_accounting_periods_closed ON accounting_periods(closed_through)
+    WHERE reopened_at IS NULL;
+
+-- Status changes that arrived dated inside a closed period. They are held
+-- here instead of touching the payment, until an operator applies one in
+-- the open period or dismisses it.
+CREATE TABLE period_adjustments (
+    id                UUID PRIMARY KEY DEFAULT uuidv7(),
+    payment_id        UUID NOT NULL REFERENCES payments(id) ON DELETE CASCADE,
+    external_id       TEXT NOT NULL,
+    source            TEXT NOT NULL,
+    tenant_id         TEXT,
+    event_id          TEXT NOT NULL,
+    event_type        TEXT NOT NULL,
+    provider_ts       BIGINT NOT NULL,
+    from_status       TEXT NOT NULL,
+    to_status         TEXT NOT NULL,
+    closed_through    TIMESTAMPTZ NOT NULL,
+    status            TEXT NOT NULL DEFAULT 'pending',
+    resolved_by       TEXT,
+    resolved_at       TIMESTAMPTZ,
+    resolution_reason TEXT,
+    created_at        TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+    CONSTRAINT chk_period_adjustments_status CHECK (status IN ('pending', 'applied', 'dismissed'))
+);
+
+CREATE INDEX idx_period_adjustments_pending ON period_adjustments(created_at)
+    WHERE status = 'pending';
+CREATE INDEX idx_period_adjustments_payment ON period_adjustments(payment_id);
@@ -0,0 +1,4 @@
+-- Claims skip objects another worker is processing a job for; this finds
+-- them without scanning the queue.
+CREATE INDEX idx_payment_jobs_processing_object ON payment_jobs(object_id)
+    WHERE status = 'processing';
@@ -0,0 +1,21 @@
+-- Which currencies a provider accepts and what it charges for them, as
+-- effective-dated versions: each row holds from `valid_from` until
+-- `valid_to` (open-ended while NULL), and a source and currency's rows
+-- never overlap. Past payments are read under the version in force when
+-- they happened, so changes are scheduled ahead rather than edited in.
+CREATE TABLE currency_terms (
+    id              UUID PRIMARY KEY DEFAULT uuidv7(),
+    source          TEXT NOT NULL,
+    currency        TEXT NOT NULL,
+    accepted        BOOLEAN NOT NULL,
+    fee_bps         INTEGER NOT NULL CHECK (fee_bps BETWEEN 0 AND 10000),
+    fixed_fee_minor BIGINT NOT NULL CHECK (fixed_fee_minor >= 0),
+    valid_from      TIMESTAMPTZ NOT NULL,
+    valid_to        TIMESTAMPTZ CHECK (valid_to > valid_from),
+    reason          TEXT NOT NULL,
+    created_by      TEXT NOT NULL,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT now(),
+    UNIQUE (source, currency, valid_from)
+);
+
+CREATE INDEX idx_currency_terms_in_force ON currency_terms(valid_from, valid_to);
@@ -0,0 +1,24 @@
+-- Inbound payments grouped into the checkouts they were attempts at: a
+-- customer whose payment fails and who pays again with a new payment
+-- intent makes two attempts at one checkout. A checkout is named by the
+-- merchant's reference when the payments carry one, and otherwise by its
+-- first attempt. One row per inbound payment, so a payment taken at the
+-- first go is a checkout of one attempt.
+CREATE TABLE checkout_attempts (
+    payment_id   UUID PRIMARY KEY REFERENCES payments(id) ON DELETE CASCADE,
+    tenant_id    TEXT,
+    checkout_key TEXT NOT NULL,
+    attempt      INTEGER NOT NULL CHECK (attempt > 0),
+    source       TEXT NOT NULL,
+    -- The provider's customer and the merchant's reference, as the
+    -- payment carried them; what later attempts are matched on.
+    customer     TEXT,
+    reference    TEXT,
+    -- How the attempt joined the checkout; NULL for the first.
+    linked_by    TEXT CHECK (linked_by IN ('reference', 'customer')),
+    attempted_at TIMESTAMPTZ NOT NULL,
+    UNIQUE NULLS NOT DISTINCT (tenant_id, checkout_key, attempt)
+);
+
+CREATE INDEX idx_checkout_attempts_customer
+    ON checkout_attempts(source, customer, attempted_at) WHERE customer IS NOT NULL;
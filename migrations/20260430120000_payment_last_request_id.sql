@@ -0,0 +1,4 @@
+-- The API request behind the last event applied to a payment, to order
+-- events within the same second as payouts are. Rows from before have none
+-- and are ordered as if Stripe raised their last event on its own.
+ALTER TABLE payments ADD COLUMN last_request_id TEXT;
@@ -0,0 +1,16 @@
+-- Calls to one source and operation are counted on one of several shard
+-- rows per window instead of a single row every replica updates, so a
+-- burst doesn't queue on one row lock. Each shard takes its share of the
+-- hard limit; the window's count is the sum. The alert timestamps and
+-- refusals stay on shard 0.
+ALTER TABLE provider_api_usage ADD COLUMN shard SMALLINT NOT NULL DEFAULT 0;
+ALTER TABLE provider_api_usage DROP CONSTRAINT provider_api_usage_pkey;
+ALTER TABLE provider_api_usage
+    ADD PRIMARY KEY (source, operation, period, period_start, shard);
+
+-- A job refused by an exhausted API budget is put off to the end of the
+-- window without counting the attempt.
+ALTER TABLE job_attempts DROP CONSTRAINT job_attempts_outcome_check;
+ALTER TABLE job_attempts ADD CONSTRAINT job_attempts_outcome_check
+    CHECK (outcome IN ('succeeded', 'discarded', 'failed', 'abandoned', 'provider_missing',
+                       'deferred'));
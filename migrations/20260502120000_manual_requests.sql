@@ -0,0 +1,12 @@
+-- What each `POST /payments` request came to, by its idempotency key (as
+-- the synthetic event id), so a retry gets the answer the first attempt
+-- got: the same status code, not a blanket 200. `request_hash` tells a
+-- retry from a different request under the same key.
+CREATE TABLE manual_requests (
+    event_id TEXT PRIMARY KEY,
+    request_hash TEXT NOT NULL,
+    outcome TEXT NOT NULL CHECK (outcome IN ('created', 'applied', 'rejected')),
+    -- The status the payment was in when the request was rejected.
+    rejected_from TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
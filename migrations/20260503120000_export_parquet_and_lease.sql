@@ -0,0 +1,12 @@
+-- Exports can be written as Parquet, and a running export holds a lease
+-- its replica renews while writing. One whose replica died is failed once
+-- the lease runs out, so the scheduled window it holds is tried again
+-- instead of staying `running` forever.
+ALTER TABLE exports DROP CONSTRAINT exports_format_check;
+ALTER TABLE exports ADD CONSTRAINT exports_format_check
+    CHECK (format IN ('csv', 'ndjson', 'parquet'));
+
+ALTER TABLE exports ADD COLUMN lease_until TIMESTAMPTZ;
+
+CREATE INDEX idx_exports_running_lease ON exports (lease_until)
+    WHERE status = 'running';
@@ -0,0 +1,8 @@
+-- Rejected deliveries are counted on their signature's row instead of
+-- audited each time, so replaying one captured delivery can't grow
+-- audit_log: the first rejection of a signature is audited, later ones
+-- only bump `rejections`. A stale or early delivery gets a row too. A row
+-- is kept while its signature keeps being rejected within the max age.
+ALTER TABLE webhook_signatures
+    ADD COLUMN rejections       INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN last_rejected_at TIMESTAMPTZ;
@@ -0,0 +1,13 @@
+-- How far each retained table has been archived and pruned: rows up to
+-- `pruned_through` may be gone. Rollups built from a pruned table don't
+-- recompute windows at or before it, which would otherwise come out empty.
+CREATE TABLE retention_horizons (
+    table_name     TEXT PRIMARY KEY CHECK (table_name IN ('provider_events', 'audit_log')),
+    pruned_through TIMESTAMPTZ NOT NULL,
+    updated_at     TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- Dispute events are kept: the dispute rollup reads each dispute's latest
+-- state from them. Index what retention still takes.
+CREATE INDEX idx_provider_events_retained ON provider_events (received_at)
+    WHERE event_type NOT LIKE 'charge.dispute.%';
@@ -0,0 +1,6 @@
+-- A payment first seen through an event dated in a closed period is
+-- created at pending, holding nothing, and its move to the status the
+-- event carried is held here like any other. The state machine already
+-- admitted that move, and has no path from pending into a dispute, so
+-- applying it doesn't ask again.
+ALTER TABLE period_adjustments ADD COLUMN on_create BOOLEAN NOT NULL DEFAULT false;
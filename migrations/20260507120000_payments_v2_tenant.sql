@@ -0,0 +1,11 @@
+-- payments_v2 carries the tenant and the last event's API request itself,
+-- instead of reading them back from payments, so reads switched over to it
+-- don't depend on the old table.
+ALTER TABLE payments_v2
+    ADD COLUMN tenant_id       TEXT,
+    ADD COLUMN last_request_id TEXT;
+
+UPDATE payments_v2 n
+SET tenant_id = p.tenant_id, last_request_id = p.last_request_id
+FROM payments p
+WHERE p.id = n.payment_id;
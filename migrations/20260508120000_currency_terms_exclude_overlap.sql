@@ -0,0 +1,7 @@
+-- A source and currency's versions never overlap: scheduling takes an
+-- advisory lock, and this holds whatever writes the table.
+CREATE EXTENSION IF NOT EXISTS btree_gist;
+
+ALTER TABLE currency_terms
+    ADD CONSTRAINT currency_terms_no_overlap
+    EXCLUDE USING gist (source WITH =, currency WITH =, tstzrange(valid_from, valid_to) WITH &&);
@@ -0,0 +1,23 @@
+-- Each dispute's state as of its latest `charge.dispute.*` event, written
+-- as the event is stored, so the dispute rollup reads columns instead of
+-- decoding payloads. Disputes from before this table are recorded by the
+-- next rollup recompute, from their stored events.
+CREATE TABLE disputes (
+    dispute_id  TEXT PRIMARY KEY,
+    opened_at   TIMESTAMPTZ NOT NULL,
+    currency    TEXT NOT NULL,
+    card_brand  TEXT NOT NULL,
+    amount      BIGINT NOT NULL,
+    -- Net of the fee movements on its balance transactions.
+    fee         BIGINT NOT NULL,
+    status      TEXT NOT NULL,
+    -- The recorded event's `provider_ts`; an older event never overwrites.
+    provider_ts BIGINT NOT NULL,
+    updated_at  TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_disputes_opened_at ON disputes(opened_at);
+
+-- Retention now takes a dispute event once its dispute is recorded here,
+-- so it scans by time alone (`idx_provider_events_received_at`).
+DROP INDEX idx_provider_events_retained;
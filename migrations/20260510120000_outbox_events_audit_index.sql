@@ -0,0 +1,3 @@
+-- Retention keeps an audit entry while a feed consumer has yet to read it,
+-- and drops its outbox row with it once pruned; both look rows up by entry.
+CREATE INDEX idx_outbox_events_audit_id ON outbox_events (audit_id);
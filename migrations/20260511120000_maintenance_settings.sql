@@ -0,0 +1,17 @@
+-- Maintenance mode moves to the `settings` table, under
+-- `ingestion.maintenance`, so replicas cache it and hear of changes like
+-- any other setting. A window still running carries over.
+INSERT INTO settings (key, value, updated_by, updated_at)
+SELECT 'ingestion.maintenance',
+       jsonb_build_object(
+           'reason', reason,
+           'enabled_by', enabled_by,
+           'enabled_at', enabled_at,
+           'expires_at', expires_at
+       ),
+       enabled_by,
+       enabled_at
+FROM maintenance_mode
+WHERE expires_at > now();
+
+DROP TABLE maintenance_mode;
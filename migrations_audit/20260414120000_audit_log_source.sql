@@ -0,0 +1,5 @@
+-- Mirrors the primary: entries are unique per (source, event_id).
+ALTER TABLE audit_log ADD COLUMN source TEXT;
+DROP INDEX idx_audit_log_event_id;
+CREATE UNIQUE INDEX idx_audit_log_event_id_source
+    ON audit_log(event_id, source) NULLS NOT DISTINCT;
@@ -0,0 +1,5 @@
+-- Mirrors the primary: entries are unique per (source, tenant, event_id).
+ALTER TABLE audit_log ADD COLUMN tenant_id TEXT;
+DROP INDEX idx_audit_log_event_id_source;
+CREATE UNIQUE INDEX idx_audit_log_event_id_source_tenant
+    ON audit_log(event_id, source, tenant_id) NULLS NOT DISTINCT;
@@ -1 +1,2 @@
+pub mod paypal;
 pub mod stripe;
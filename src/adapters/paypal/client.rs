@@ -0,0 +1,679 @@
+use {
+    crate::domain::{
+        error::PortError,
+        id::ExternalId,
+        money::{Currency, Money},
+        payment::{PaymentDirection, PaymentFailure, PaymentStatus},
+        provider::{FetchedPayment, ListCursor, PaymentPage, PaymentProvider},
+        webhook::SignedDelivery,
+    },
+    crate::error::PipelineError,
+    chrono::{DateTime, TimeDelta, Utc},
+    serde::Deserialize,
+    std::{
+        future::Future,
+        pin::Pin,
+        time::{Duration, Instant},
+    },
+    tokio::sync::Mutex,
+};
+
+/// Value of `payments.source` for PayPal objects.
+pub const PAYPAL_SOURCE: &str = "paypal";
+
+/// PayPal ids carry no type prefix, so ours add one: captures are inbound
+/// payments, refunds outbound.
+pub const CAPTURE_PREFIX: &str = "pp_cap_";
+pub const REFUND_PREFIX: &str = "pp_ref_";
+
+/// Headers PayPal signs each webhook delivery with.
+#[derive(Debug, Clone)]
+pub struct TransmissionHeaders {
+    pub transmission_id: String,
+    pub transmission_time: String,
+    pub transmission_sig: String,
+    pub cert_url: String,
+    pub auth_algo: String,
+}
+
+impl TransmissionHeaders {
+    /// Replay key: the transmission signature, timestamped by
+    /// `PAYPAL-TRANSMISSION-TIME`.
+    pub fn signed_delivery(&self) -> Result<SignedDelivery, PipelineError> {
+        let signed_at = DateTime::parse_from_rfc3339(&self.transmission_time)
+            .map_err(|e| {
+                PipelineError::WebhookSignature(format!("invalid PAYPAL-TRANSMISSION-TIME: {e}"))
+            })?
+            .with_timezone(&Utc);
+        Ok(SignedDelivery {
+            source: PAYPAL_SOURCE,
+            signature: self.transmission_sig.clone(),
+            signed_at,
+        })
+    }
+}
+
+pub struct PaypalProvider {
+    http: reqwest::Client,
+    base_url: String,
+    client_id: String,
+    client_secret: String,
+    webhook_id: String,
+    token: Mutex<Option<AccessToken>>,
+}
+
+struct AccessToken {
+    value: String,
+    expires_at: Instant,
+}
+
+impl PaypalProvider {
+    /// `base_url` is `https://api-m.paypal.com` in production and
+    /// `https://api-m.sandbox.paypal.com` for the sandbox.
+    pub fn new(base_url: &str, client_id: &str, client_secret: &str, webhook_id: &str) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            client_id: client_id.to_string(),
+            client_secret: client_secret.to_string(),
+            webhook_id: webhook_id.to_string(),
+            token: Mutex::new(None),
+        }
+    }
+}
+
+impl PaymentProvider for PaypalProvider {
+    fn source(&self) -> &'static str {
+        PAYPAL_SOURCE
+    }
+
+    fn fetch_payment(
+        &self,
+        id: &ExternalId,
+    ) -> Pin<Box<dyn Future<Output = Result<FetchedPayment, PortError>> + Send + '_>> {
+        let id = id.clone();
+        Box::pin(async move { Ok(self.fetch_payment_inner(&id).await?) })
+    }
+
+    fn list_payments(
+        &self,
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+        cursor: Option<ListCursor>,
+    ) -> Pin<Box<dyn Future<Output = Result<PaymentPage, PortError>> + Send + '_>> {
+        Box::pin(async move { Ok(self.list_payments_inner(since, until, cursor).await?) })
+    }
+
+    fn payment_from_event(
+        &self,
+        payload: &serde_json::Value,
+    ) -> Result<Option<FetchedPayment>, PortError> {
+        let event_type = payload["event_type"].as_str().unwrap_or_default();
+        Ok(convert_event(event_type, &payload["resource"]).transpose()?)
+    }
+}
+
+impl PaypalProvider {
+    /// Ask PayPal whether a webhook delivery is genuine. `raw_body` is sent
+    /// back verbatim: the signature covers the exact bytes, and re-serializing
+    /// could reorder keys.
+    pub async fn verify_webhook(
+        &self,
+        headers: &TransmissionHeaders,
+        raw_body: &str,
+    ) -> Result<(), PipelineError> {
+        // The body is spliced into the request verbatim; anything but one
+        // JSON document could rewrite the fields around it.
+        serde_json::from_str::<serde::de::IgnoredAny>(raw_body).map_err(|e| {
+            PipelineError::WebhookSignature(format!("webhook body is not JSON: {e}"))
+        })?;
+        let mut body = serde_json::to_string(&serde_json::json!({
+            "auth_algo": headers.auth_algo,
+            "cert_url": headers.cert_url,
+            "transmission_id": headers.transmission_id,
+            "transmission_sig": headers.transmission_sig,
+            "transmission_time": headers.transmission_time,
+            "webhook_id": self.webhook_id,
+        }))?;
+        body.pop();
+        body.push_str(",\"webhook_event\":");
+        body.push_str(raw_body);
+        body.push('}');
+
+        #[derive(Deserialize)]
+        struct Verification {
+            verification_status: String,
+        }
+        let token = self.access_token().await?;
+        let verification: Verification = self
+            .http
+            .post(format!(
+                "{}/v1/notifications/verify-webhook-signature",
+                self.base_url
+            ))
+            .bearer_auth(token)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(api_error)?
+            .json()
+            .await
+            .map_err(api_error)?;
+
+        if verification.verification_status != "SUCCESS" {
+            return Err(PipelineError::WebhookSignature(format!(
+                "PayPal verification status: {}",
+                verification.verification_status
+            )));
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "paypal_fetch", skip_all, fields(external_id = %id))]
+    async fn fetch_payment_inner(&self, id: &ExternalId) -> Result<FetchedPayment, PipelineError> {
+        let raw = id.as_str();
+        if let Some(capture_id) = raw.strip_prefix(CAPTURE_PREFIX) {
+            let capture = self
+                .get(&format!("/v2/payments/captures/{capture_id}"), &[])
+                .await?;
+            convert_capture(&capture)
+        } else if let Some(refund_id) = raw.strip_prefix(REFUND_PREFIX) {
+            let refund = self
+                .get(&format!("/v2/payments/refunds/{refund_id}"), &[])
+                .await?;
+            convert_refund(&refund)
+        } else {
+            Err(PipelineError::Provider(format!(
+                "unknown external_id prefix: {raw}"
+            )))
+        }
+    }
+
+    /// Lists via the Transaction Search API, which only accepts windows of up
+    /// to 31 days. The cursor is `<chunk start (unix secs)>:<page>`.
+    async fn list_payments_inner(
+        &self,
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+        cursor: Option<ListCursor>,
+    ) -> Result<PaymentPage, PipelineError> {
+        let (chunk_start, page) = match &cursor {
+            Some(c) => parse_list_cursor(c)?,
+            None => (since, 1),
+        };
+        let chunk_end = (chunk_start + TimeDelta::days(SEARCH_WINDOW_DAYS)).min(until);
+
+        let response = self
+            .get(
+                "/v1/reporting/transactions",
+                &[
+                    ("start_date", chunk_start.to_rfc3339()),
+                    ("end_date", chunk_end.to_rfc3339()),
+                    ("fields", "transaction_info".into()),
+                    ("page_size", LIST_PAGE_SIZE.to_string()),
+                    ("page", page.to_string()),
+                ],
+            )
+            .await?;
+        let search: TransactionSearch = serde_json::from_value(response)?;
+
+        let payments = search
+            .transaction_details
+            .iter()
+            .filter_map(|d| match convert_transaction(&d.transaction_info) {
+                Ok(p) => p,
+                Err(e) => {
+                    tracing::warn!(error = %e, "skipping listed transaction");
+                    None
+                }
+            })
+            .collect();
+
+        let next_cursor = if page < search.total_pages {
+            Some(list_cursor(chunk_start, page + 1))
+        } else if chunk_end < until {
+            Some(list_cursor(chunk_end, 1))
+        } else {
+            None
+        };
+        Ok(PaymentPage {
+            payments,
+            next_cursor,
+        })
+    }
+
+    async fn get(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> Result<serde_json::Value, PipelineError> {
+        let token = self.access_token().await?;
+        self.http
+            .get(format!("{}{path}", self.base_url))
+            .bearer_auth(token)
+            .query(query)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|e| match e.status() {
+                // Only reached with a token, so the base URL is right and
+                // it's the object that is missing.
+                Some(reqwest::StatusCode::NOT_FOUND) => {
+                    PipelineError::ProviderMissing(format!("PayPal API: {e}"))
+                }
+                _ => api_error(e),
+            })?
+            .json()
+            .await
+            .map_err(api_error)
+    }
+
+    /// OAuth client-credentials token, cached until shortly before it expires.
+    async fn access_token(&self) -> Result<String, PipelineError> {
+        let mut cached = self.token.lock().await;
+        if let Some(token) = cached.as_ref().filter(|t| t.expires_at > Instant::now()) {
+            return Ok(token.value.clone());
+        }
+
+        #[derive(Deserialize)]
+        struct TokenResponse {
+            access_token: String,
+            expires_in: u64,
+        }
+        let response: TokenResponse = self
+            .http
+            .post(format!("{}/v1/oauth2/token", self.base_url))
+            .basic_auth(&self.client_id, Some(&self.client_secret))
+            .form(&[("grant_type", "client_credentials")])
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(api_error)?
+            .json()
+            .await
+            .map_err(api_error)?;
+
+        let ttl = Duration::from_secs(response.expires_in.saturating_sub(60));
+        *cached = Some(AccessToken {
+            value: response.access_token.clone(),
+            expires_at: Instant::now() + ttl,
+        });
+        Ok(response.access_token)
+    }
+}
+
+const LIST_PAGE_SIZE: u32 = 100;
+const SEARCH_WINDOW_DAYS: i64 = 31;
+
+fn api_error(e: reqwest::Error) -> PipelineError {
+    PipelineError::Provider(format!("PayPal API: {e}"))
+}
+
+fn list_cursor(chunk_start: DateTime<Utc>, page: u32) -> ListCursor {
+    ListCursor::new(format!("{}:{page}", chunk_start.timestamp()))
+}
+
+fn parse_list_cursor(cursor: &ListCursor) -> Result<(DateTime<Utc>, u32), PipelineError> {
+    let invalid = || PipelineError::Validation(format!("invalid list cursor: {}", cursor.as_str()));
+    let (start, page) = cursor.as_str().split_once(':').ok_or_else(invalid)?;
+    let start = start
+        .parse()
+        .ok()
+        .and_then(|s| DateTime::from_timestamp(s, 0))
+        .ok_or_else(invalid)?;
+    let page = page.parse().map_err(|_| invalid())?;
+    Ok((start, page))
+}
+
+// ── Resource conversion ─────────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+struct Amount {
+    currency_code: String,
+    value: String,
+}
+
+#[derive(Deserialize)]
+struct StatusDetails {
+    reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Link {
+    rel: String,
+    href: String,
+}
+
+#[derive(Deserialize)]
+struct Capture {
+    id: String,
+    status: String,
+    amount: Amount,
+    custom_id: Option<String>,
+    invoice_id: Option<String>,
+    status_details: Option<StatusDetails>,
+    create_time: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Refund {
+    id: String,
+    status: String,
+    amount: Amount,
+    invoice_id: Option<String>,
+    status_details: Option<StatusDetails>,
+    create_time: Option<String>,
+    #[serde(default)]
+    links: Vec<Link>,
+}
+
+/// The capture or refund in a webhook's `resource`; `None` for event types
+/// that carry neither.
+pub fn convert_event(
+    event_type: &str,
+    resource: &serde_json::Value,
+) -> Option<Result<FetchedPayment, PipelineError>> {
+    match event_type {
+        "PAYMENT.CAPTURE.REFUNDED" | "PAYMENT.CAPTURE.REVERSED" => Some(convert_refund(resource)),
+        t if t.starts_with("PAYMENT.CAPTURE.") => Some(convert_capture(resource)),
+        _ => None,
+    }
+}
+
+/// A v2 capture (`/v2/payments/captures/{id}`, or a `PAYMENT.CAPTURE.*`
+/// webhook resource).
+pub fn convert_capture(raw: &serde_json::Value) -> Result<FetchedPayment, PipelineError> {
+    let capture: Capture = serde_json::from_value(raw.clone())?;
+    let status = convert_capture_status(&capture.status);
+    let failure = match status {
+        PaymentStatus::Failed => failure_from(capture.status_details),
+        _ => None,
+    };
+
+    Ok(FetchedPayment {
+        external_id: ExternalId::new(format!("{CAPTURE_PREFIX}{}", capture.id))?,
+        direction: PaymentDirection::Inbound,
+        status,
+        money: convert_amount(&capture.amount)?,
+        metadata: metadata(capture.custom_id, capture.invoice_id),
+        parent_external_id: None,
+        failure,
+        authorized_amount: None,
+        receipt: None,
+        customer: None,
+        created_at: parse_time(capture.create_time.as_deref()),
+    })
+}
+
+/// A v2 refund (`/v2/payments/refunds/{id}`, or the resource of a
+/// `PAYMENT.CAPTURE.REFUNDED` / `REVERSED` webhook). The parent capture
+/// comes from the `up` link.
+pub fn convert_refund(raw: &serde_json::Value) -> Result<FetchedPayment, PipelineError> {
+    let refund: Refund = serde_json::from_value(raw.clone())?;
+    let status = convert_refund_status(&refund.status);
+    let parent = refund
+        .links
+        .iter()
+        .find(|l| l.rel == "up")
+        .and_then(|l| l.href.rsplit_once("/captures/"))
+        .map(|(_, capture_id)| ExternalId::new(format!("{CAPTURE_PREFIX}{capture_id}")))
+        .transpose()?;
+    let failure = match status {
+        PaymentStatus::Failed => failure_from(refund.status_details),
+        _ => None,
+    };
+
+    Ok(FetchedPayment {
+        external_id: ExternalId::new(format!("{REFUND_PREFIX}{}", refund.id))?,
+        direction: PaymentDirection::Outbound,
+        status,
+        money: convert_amount(&refund.amount)?,
+        metadata: metadata(None, refund.invoice_id),
+        parent_external_id: parent,
+        failure,
+        authorized_amount: None,
+        receipt: None,
+        customer: None,
+        created_at: parse_time(refund.create_time.as_deref()),
+    })
+}
+
+#[derive(Deserialize)]
+struct TransactionSearch {
+    #[serde(default)]
+    transaction_details: Vec<TransactionDetail>,
+    total_pages: u32,
+}
+
+#[derive(Deserialize)]
+struct TransactionDetail {
+    transaction_info: TransactionInfo,
+}
+
+#[derive(Deserialize)]
+struct TransactionInfo {
+    transaction_id: String,
+    transaction_event_code: String,
+    transaction_status: String,
+    transaction_amount: Amount,
+    paypal_reference_id: Option<String>,
+    custom_field: Option<String>,
+    invoice_id: Option<String>,
+    transaction_initiation_date: Option<String>,
+}
+
+/// A PayPal timestamp: RFC 3339 in v2 resources, `+0000`-style offsets in
+/// Transaction Search. `None` when absent or unreadable.
+fn parse_time(raw: Option<&str>) -> Option<DateTime<Utc>> {
+    let raw = raw?;
+    DateTime::parse_from_rfc3339(raw)
+        .or_else(|_| DateTime::parse_from_str(raw, "%Y-%m-%dT%H:%M:%S%z"))
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+/// A Transaction Search row. Payments (`T00xx`) become captures, refunds
+/// (`T11xx`) refunds; fees, transfers and the like are `None`.
+fn convert_transaction(info: &TransactionInfo) -> Result<Option<FetchedPayment>, PipelineError> {
+    let code = info.transaction_event_code.as_str();
+    let (external_id, direction, parent) = if code.starts_with("T00") {
+        (
+            format!("{CAPTURE_PREFIX}{}", info.transaction_id),
+            PaymentDirection::Inbound,
+            None,
+        )
+    } else if code.starts_with("T11") {
+        let parent = info
+            .paypal_reference_id
+            .as_ref()
+            .map(|id| ExternalId::new(format!("{CAPTURE_PREFIX}{id}")))
+            .transpose()?;
+        (
+            format!("{REFUND_PREFIX}{}", info.transaction_id),
+            PaymentDirection::Outbound,
+            parent,
+        )
+    } else {
+        return Ok(None);
+    };
+
+    // S = success, P = pending, D = denied, V = reversed (refunded payment).
+    let status = match (info.transaction_status.as_str(), &direction) {
+        ("P", _) => PaymentStatus::Pending,
+        ("D", _) => PaymentStatus::Failed,
+        ("S", PaymentDirection::Outbound) => PaymentStatus::Refunded,
+        ("S" | "V", PaymentDirection::Inbound) => PaymentStatus::Succeeded,
+        (other, _) => {
+            return Err(PipelineError::Validation(format!(
+                "unknown transaction status: {other}"
+            )));
+        }
+    };
+
+    let amount = Amount {
+        currency_code: info.transaction_amount.currency_code.clone(),
+        value: info
+            .transaction_amount
+            .value
+            .trim_start_matches('-')
+            .to_string(),
+    };
+    Ok(Some(FetchedPayment {
+        external_id: ExternalId::new(external_id)?,
+        direction,
+        status,
+        money: convert_amount(&amount)?,
+        metadata: metadata(info.custom_field.clone(), info.invoice_id.clone()),
+        parent_external_id: parent,
+        failure: None,
+        authorized_amount: None,
+        receipt: None,
+        customer: None,
+        created_at: parse_time(info.transaction_initiation_date.as_deref()),
+    }))
+}
+
+fn convert_capture_status(status: &str) -> PaymentStatus {
+    match status {
+        // A refunded capture was still paid; the refund is its own row.
+        "COMPLETED" | "REFUNDED" | "PARTIALLY_REFUNDED" => PaymentStatus::Succeeded,
+        "DECLINED" | "FAILED" => PaymentStatus::Failed,
+        "PENDING" => PaymentStatus::Pending,
+        other => {
+            tracing::warn!("unknown PayPal capture status: {other}, defaulting to Pending");
+            PaymentStatus::Pending
+        }
+    }
+}
+
+fn convert_refund_status(status: &str) -> PaymentStatus {
+    match status {
+        "COMPLETED" => PaymentStatus::Refunded,
+        "FAILED" => PaymentStatus::Failed,
+        "CANCELLED" => PaymentStatus::Cancelled,
+        _ => PaymentStatus::Pending,
+    }
+}
+
+fn failure_from(details: Option<StatusDetails>) -> Option<PaymentFailure> {
+    PaymentFailure::from_parts(details.and_then(|d| d.reason), None, None, None)
+}
+
+fn metadata(custom_id: Option<String>, invoice_id: Option<String>) -> serde_json::Value {
+    let mut map = serde_json::Map::new();
+    if let Some(v) = custom_id {
+        map.insert("custom_id".into(), v.into());
+    }
+    if let Some(v) = invoice_id {
+        map.insert("invoice_id".into(), v.into());
+    }
+    serde_json::Value::Object(map)
+}
+
+/// PayPal amounts are decimal strings in major units ("10.99", "1000" for JPY).
+fn convert_amount(amount: &Amount) -> Result<Money, PipelineError> {
+    let currency = Currency::try_from(amount.currency_code.as_str())?;
+    Ok(Money::from_major(&amount.value, currency)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn amount(currency_code: &str, value: &str) -> Amount {
+        Amount {
+            currency_code: currency_code.into(),
+            value: value.into(),
+        }
+    }
+
+    #[test]
+    fn transmission_time_stamps_the_delivery() {
+        let mut headers = TransmissionHeaders {
+            transmission_id: "tx-1".into(),
+            transmission_time: "2026-03-19T10:00:00Z".into(),
+            transmission_sig: "c2lnbmF0dXJl".into(),
+            cert_url: "https://api.paypal.com/cert.pem".into(),
+            auth_algo: "SHA256withRSA".into(),
+        };
+        let d = headers.signed_delivery().unwrap();
+        assert_eq!(d.signed_at.to_rfc3339(), "2026-03-19T10:00:00+00:00");
+        assert_eq!(d.signature, "c2lnbmF0dXJl");
+
+        headers.transmission_time = "yesterday".into();
+        assert!(headers.signed_delivery().is_err());
+    }
+
+    #[test]
+    fn amounts_convert_to_minor_units() {
+        let cents = |c, v| convert_amount(&amount(c, v)).map(|m| m.amount().cents());
+        assert_eq!(cents("USD", "10.99").unwrap(), 1099);
+        assert_eq!(cents("EUR", "7.5").unwrap(), 750);
+        assert_eq!(cents("GBP", "3").unwrap(), 300);
+        assert_eq!(cents("JPY", "1200").unwrap(), 1200);
+        for bad in [
+            ("USD", "1.999"),
+            ("USD", "-1.00"),
+            ("USD", ".50"),
+            ("JPY", "1.5"),
+        ] {
+            assert!(cents(bad.0, bad.1).is_err(), "{bad:?}");
+        }
+        assert_eq!(cents("KWD", "1.250").unwrap(), 1250);
+        assert!(cents("XYZ", "1.00").is_err());
+    }
+
+    #[test]
+    fn capture_and_refund_resources_convert() {
+        let capture = convert_capture(&serde_json::json!({
+            "id": "2GG279541U471931P",
+            "status": "DECLINED",
+            "amount": {"currency_code": "USD", "value": "25.00"},
+            "invoice_id": "INV-7",
+            "status_details": {"reason": "BUYER_COMPLAINT"},
+            "create_time": "2026-03-19T10:00:00Z",
+        }))
+        .unwrap();
+        assert_eq!(capture.external_id.as_str(), "pp_cap_2GG279541U471931P");
+        assert_eq!(capture.status, PaymentStatus::Failed);
+        assert_eq!(capture.metadata["invoice_id"], "INV-7");
+        assert_eq!(
+            capture.failure.and_then(|f| f.code).as_deref(),
+            Some("BUYER_COMPLAINT")
+        );
+        let created = DateTime::from_timestamp(1_773_914_400, 0);
+        assert_eq!(capture.created_at, created);
+        // Transaction Search writes its offsets without the colon.
+        assert_eq!(parse_time(Some("2026-03-19T10:00:00+0000")), created);
+        assert_eq!(parse_time(Some("yesterday")), None);
+
+        let refund = convert_refund(&serde_json::json!({
+            "id": "1JU08902781691411",
+            "status": "COMPLETED",
+            "amount": {"currency_code": "USD", "value": "10.00"},
+            "links": [
+                {"rel": "self", "href": "https://api-m.paypal.com/v2/payments/refunds/1JU08902781691411"},
+                {"rel": "up", "href": "https://api-m.paypal.com/v2/payments/captures/2GG279541U471931P"},
+            ],
+        }))
+        .unwrap();
+        assert_eq!(refund.external_id.as_str(), "pp_ref_1JU08902781691411");
+        assert_eq!(refund.direction, PaymentDirection::Outbound);
+        assert_eq!(refund.status, PaymentStatus::Refunded);
+        assert_eq!(refund.money.amount().cents(), 1000);
+        assert_eq!(
+            refund.parent_external_id.as_ref().map(|p| p.as_str()),
+            Some("pp_cap_2GG279541U471931P")
+        );
+    }
+
+    #[test]
+    fn list_cursor_round_trips() {
+        let start = DateTime::from_timestamp(1_773_000_000, 0).unwrap();
+        let cursor = list_cursor(start, 3);
+        assert_eq!(parse_list_cursor(&cursor).unwrap(), (start, 3));
+        assert!(parse_list_cursor(&ListCursor::new("3")).is_err());
+    }
+}
@@ -0,0 +1,165 @@
+use {
+    crate::{
+        AppState,
+        adapters::paypal::client::{PAYPAL_SOURCE, TransmissionHeaders, convert_event},
+        domain::{id::EventId, job::JobPriority, payment::PassthroughEvent},
+        error::PipelineError,
+        infra::{postgres::job_repo, redact::redacted},
+        services::{jobs, payment::pipeline::handle_passthrough, webhook_guard},
+        transport::http::errors::ApiError,
+    },
+    axum::{Json, extract::State, http::HeaderMap},
+    chrono::{DateTime, Utc},
+    serde::Deserialize,
+};
+
+const ACTOR: &str = "webhook:paypal";
+
+#[derive(Deserialize)]
+struct PaypalEvent {
+    id: String,
+    event_type: String,
+    create_time: DateTime<Utc>,
+    #[serde(default)]
+    resource: serde_json::Value,
+}
+
+/// Captures and refunds are enqueued like Stripe events; the worker fetches
+/// their current state through the `paypal` provider. The embedded resource
+/// is only used to find the object id.
+#[tracing::instrument(
+    name = "paypal_webhook",
+    skip_all,
+    fields(event_id = tracing::field::Empty, event_type = tracing::field::Empty)
+)]
+pub async fn paypal_wh_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let paypal = state
+        .paypal
+        .as_ref()
+        .ok_or_else(|| ApiError::not_found("paypal is not configured"))?;
+
+    let transmission = transmission_headers(&headers)?;
+    paypal.verify_webhook(&transmission, &body).await?;
+
+    let delivery = transmission.signed_delivery()?;
+    webhook_guard::admit(&state.pool, &state.config, &delivery).await?;
+
+    let result = accept_event(&state, &body).await;
+    if result.is_err() {
+        webhook_guard::release(&state.pool, &delivery).await;
+    }
+    result
+}
+
+async fn accept_event(state: &AppState, body: &str) -> Result<Json<serde_json::Value>, ApiError> {
+    let malformed =
+        |e: serde_json::Error| PipelineError::Validation(format!("malformed event: {e}"));
+    let raw_event: serde_json::Value = serde_json::from_str(body).map_err(malformed)?;
+    let event: PaypalEvent = serde_json::from_value(raw_event.clone()).map_err(malformed)?;
+
+    tracing::Span::current()
+        .record("event_id", tracing::field::display(&event.id))
+        .record("event_type", tracing::field::display(&event.event_type));
+
+    let event_id = EventId::new(format!("evt_pp_{}", event.id))?;
+    let provider_ts = event.create_time.timestamp();
+
+    // Types we don't process are logged as they came, like unknown ones.
+    let converted = if state
+        .config
+        .current()
+        .config
+        .accepts_event(&event.event_type)
+    {
+        convert_event(&event.event_type, &event.resource)
+    } else {
+        None
+    };
+
+    match converted {
+        Some(Ok(fetched)) => {
+            let queued = job_repo::enqueue(
+                &state.pool,
+                PAYPAL_SOURCE,
+                None,
+                event_id.as_str(),
+                fetched.external_id.as_str(),
+                &event.event_type,
+                provider_ts,
+                &raw_event,
+                JobPriority::Normal,
+            )
+            .await?;
+
+            // The token is the job's id; `GET /events/{event_id}/status`
+            // reports it once processed.
+            if let Some(token) = queued {
+                tracing::info!("payment event enqueued for async processing");
+                Ok(Json(
+                    serde_json::json!({"status": "accepted", "token": token}),
+                ))
+            } else {
+                tracing::info!("duplicate event, already enqueued");
+                let token = jobs::get_event_status(
+                    &state.pool,
+                    event_id.as_str(),
+                    Some(PAYPAL_SOURCE),
+                    None,
+                )
+                .await?
+                .map(|s| s.token);
+                Ok(Json(
+                    serde_json::json!({"status": "duplicate", "token": token}),
+                ))
+            }
+        }
+        Some(Err(PipelineError::Validation(msg))) => {
+            tracing::warn!(
+                payload = %redacted(&raw_event),
+                "skipping invalid PayPal resource: {msg}"
+            );
+            Ok(Json(serde_json::json!({"status": "ignored_invalid_data"})))
+        }
+        Some(Err(e)) => Err(e.into()),
+        None => {
+            let passthrough = PassthroughEvent {
+                external_id: None,
+                source: PAYPAL_SOURCE.into(),
+                tenant_id: None,
+                event_id,
+                event_type: event.event_type,
+                provider_ts,
+                raw_payload: raw_event,
+                actor: ACTOR.into(),
+            };
+            if handle_passthrough(&state.pool, &passthrough).await? {
+                tracing::info!("passthrough event logged");
+                Ok(Json(serde_json::json!({"status": "logged"})))
+            } else {
+                tracing::info!("duplicate event, already processed");
+                Ok(Json(serde_json::json!({"status": "duplicate"})))
+            }
+        }
+    }
+}
+
+fn transmission_headers(headers: &HeaderMap) -> Result<TransmissionHeaders, PipelineError> {
+    let get = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+            .ok_or_else(|| PipelineError::WebhookSignature(format!("missing {name} header")))
+    };
+    Ok(TransmissionHeaders {
+        transmission_id: get("PAYPAL-TRANSMISSION-ID")?,
+        transmission_time: get("PAYPAL-TRANSMISSION-TIME")?,
+        transmission_sig: get("PAYPAL-TRANSMISSION-SIG")?,
+        cert_url: get("PAYPAL-CERT-URL")?,
+        auth_algo: get("PAYPAL-AUTH-ALGO")?,
+    })
+}
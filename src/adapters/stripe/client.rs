@@ -1,131 +1,948 @@
 use {
     crate::domain::{
-        error::PipelineError,
-        id::ExternalId,
+        capture::NewCapture,
+        error::PortError,
+        event_order,
+        id::{EventId, ExternalId, PayoutId, TenantId},
         money::{Currency, Money, MoneyAmount},
-        payment::{PaymentDirection, PaymentStatus},
-        provider::{FetchedPayment, PaymentProvider},
+        payment::{
+            PaymentDirection, PaymentFailure, PaymentReceipt, PaymentStatus, ReceiptEmailPolicy,
+        },
+        payout::{NewPayout, PayoutKind, PayoutStatus},
+        provider::{FetchedPayment, ListCursor, PaymentPage, PaymentProvider},
+        webhook_endpoint::{EndpointTarget, WebhookEndpoint},
     },
-    std::{future::Future, pin::Pin},
+    crate::error::PipelineError,
+    crate::retry::{self, Backoff, Jitter, Policy, TokioClock, Verdict},
+    chrono::{DateTime, Utc},
+    reqwest::{StatusCode, header::RETRY_AFTER},
+    serde::de::DeserializeOwned,
+    std::{future::Future, pin::Pin, sync::Arc, time::Duration},
 };
 
+/// The API version the `stripe` crate's types were generated for.
+const API_VERSION: stripe::ApiVersion = stripe::ApiVersion::V2023_10_16;
+
+/// Retries of one request after a rate limit, a 5xx or a network error:
+/// from 500ms, doubled for each one after, unless Stripe sends
+/// `Retry-After` (waited out up to 30s). Past these the error is returned,
+/// and the job retried on its own backoff.
+const RETRY: Policy<ApiFailure> = Policy {
+    max_attempts: 4,
+    backoff: Backoff::Exponential(Duration::from_millis(500)),
+    max_delay: Duration::from_secs(30),
+    jitter: Jitter::Half,
+    retryable: |failure| match (failure.retry, failure.retry_after) {
+        (false, _) => Verdict::GiveUp,
+        (true, Some(wait)) => Verdict::RetryAfter(wait),
+        (true, None) => Verdict::Retry,
+    },
+};
+
+/// Reads from the Stripe API over plain HTTP, so that rate limits and
+/// retry hints in the response headers can be acted on; responses are
+/// decoded into the `stripe` crate's types.
+#[derive(Clone)]
 pub struct StripeProvider {
-    client: stripe::Client,
+    http: reqwest::Client,
+    api_base: String,
+    secret_key: String,
+    receipt_email: ReceiptEmailPolicy,
+    /// Connected account requests are made for (`Stripe-Account`); `None`
+    /// for the platform's own.
+    account: Option<TenantId>,
 }
 
 impl StripeProvider {
     pub fn new(secret_key: &str) -> Self {
         Self {
-            client: stripe::Client::new(secret_key),
+            http: reqwest::Client::new(),
+            api_base: "https://api.stripe.com".to_string(),
+            secret_key: secret_key.to_string(),
+            receipt_email: ReceiptEmailPolicy::default(),
+            account: None,
         }
     }
+
+    /// Send requests to `api_base` instead of `https://api.stripe.com`,
+    /// e.g. stripe-mock or a stand-in in tests.
+    pub fn with_api_base(mut self, api_base: &str) -> Self {
+        self.api_base = api_base.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// How much of a receipt email to hand on for storage; masked by default.
+    pub fn with_receipt_email(mut self, policy: ReceiptEmailPolicy) -> Self {
+        self.receipt_email = policy;
+        self
+    }
+
+    fn redact(&self, mut payment: FetchedPayment) -> FetchedPayment {
+        payment.receipt = payment
+            .receipt
+            .and_then(|r| r.redact_email(self.receipt_email));
+        payment
+    }
 }
 
 impl PaymentProvider for StripeProvider {
+    fn source(&self) -> &'static str {
+        "stripe"
+    }
+
     fn fetch_payment(
         &self,
         id: &ExternalId,
-    ) -> Pin<Box<dyn Future<Output = Result<FetchedPayment, PipelineError>> + Send + '_>> {
+    ) -> Pin<Box<dyn Future<Output = Result<FetchedPayment, PortError>> + Send + '_>> {
         let id = id.clone();
-        Box::pin(async move { self.fetch_payment_inner(&id).await })
+        Box::pin(async move {
+            let payment = self.fetch_payment_inner(&id).await?;
+            Ok(self.redact(payment))
+        })
+    }
+
+    fn list_payments(
+        &self,
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+        cursor: Option<ListCursor>,
+    ) -> Pin<Box<dyn Future<Output = Result<PaymentPage, PortError>> + Send + '_>> {
+        Box::pin(async move {
+            let mut page = self.list_payments_inner(since, until, cursor).await?;
+            page.payments = page.payments.into_iter().map(|p| self.redact(p)).collect();
+            Ok(page)
+        })
+    }
+
+    fn payment_from_event(
+        &self,
+        payload: &serde_json::Value,
+    ) -> Result<Option<FetchedPayment>, PortError> {
+        let object = &payload["data"]["object"];
+        Ok(match object["object"].as_str() {
+            Some("payment_intent") => Some(self.redact(convert_payment_intent(object)?)),
+            Some("refund") => Some(convert_refund(&serde_json::from_value(object.clone())?)?),
+            Some("dispute") => Some(convert_dispute(&serde_json::from_value(object.clone())?)?),
+            _ => None,
+        })
+    }
+
+    fn payment_from_payload(
+        &self,
+        payload: &serde_json::Value,
+    ) -> Result<Option<FetchedPayment>, PortError> {
+        let object = &payload["data"]["object"];
+        if let Some(field) = missing_payload_field(object) {
+            tracing::debug!(field, "webhook object incomplete, fetching instead");
+            return Ok(None);
+        }
+        let mut payment = self.payment_from_event(payload)?;
+        // Events never expand the latest charge, so the receipt they'd give
+        // is only the intent's guess; the stored one stays until a charge
+        // event or a fetch brings the charge's.
+        if !object["latest_charge"].is_object()
+            && let Some(payment) = payment.as_mut()
+        {
+            payment.receipt = None;
+        }
+        Ok(payment)
+    }
+
+    fn payment_for_event<'a>(
+        &'a self,
+        payload: &'a serde_json::Value,
+    ) -> Pin<Box<dyn Future<Output = Result<Option<ExternalId>, PortError>> + Send + 'a>> {
+        Box::pin(async move {
+            let pi = match intent_ref(&payload["data"]["object"]) {
+                IntentRef::Known(pi) => pi.map(str::to_string),
+                IntentRef::Invoice(invoice_id) => self.invoice_payment_intent(invoice_id).await?,
+            };
+            Ok(pi.map(ExternalId::new).transpose()?)
+        })
+    }
+
+    fn for_tenant(&self, tenant: &TenantId) -> Result<Arc<dyn PaymentProvider>, PortError> {
+        Ok(Arc::new(Self {
+            account: Some(tenant.clone()),
+            ..self.clone()
+        }))
     }
 }
 
 impl StripeProvider {
+    /// `GET /v1{path}`, retried on `RETRY`: rate limits, 5xx and network
+    /// errors are sent again, after `Retry-After` when Stripe sends one and
+    /// with backoff otherwise; `Stripe-Should-Retry` overrides the guess
+    /// either way. See `classify` for what comes back on failure.
+    async fn get<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> Result<T, PipelineError> {
+        let url = format!("{}/v1{path}", self.api_base);
+        self.call(path, || self.http.get(&url).query(query)).await
+    }
+
+    /// `POST /v1{path}` with a form body, retried as `get` is. Every
+    /// attempt carries the same `Idempotency-Key`, so one whose response
+    /// was lost isn't applied twice.
+    async fn post<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        form: &[(&str, String)],
+    ) -> Result<T, PipelineError> {
+        let url = format!("{}/v1{path}", self.api_base);
+        let key = uuid::Uuid::new_v4().to_string();
+        self.call(path, || {
+            self.http
+                .post(&url)
+                .header("Idempotency-Key", &key)
+                .form(form)
+        })
+        .await
+    }
+
+    /// `DELETE /v1{path}`, retried as `get` is.
+    async fn delete(&self, path: &str) -> Result<(), PipelineError> {
+        let url = format!("{}/v1{path}", self.api_base);
+        let _: serde_json::Value = self.call(path, || self.http.delete(&url)).await?;
+        Ok(())
+    }
+
+    async fn call<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        request: impl Fn() -> reqwest::RequestBuilder,
+    ) -> Result<T, PipelineError> {
+        retry::run(&RETRY, &TokioClock, path, || self.send(request()))
+            .await
+            .map_err(|failure| failure.error)
+    }
+
+    async fn send<T: DeserializeOwned>(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> Result<T, ApiFailure> {
+        let mut request = request
+            .bearer_auth(&self.secret_key)
+            .header("Stripe-Version", API_VERSION.as_str());
+        if let Some(account) = &self.account {
+            request = request.header("Stripe-Account", account.as_str());
+        }
+        let sent = request.send().await;
+        match sent {
+            Ok(response) if response.status().is_success() => {
+                let body = response.bytes().await.map_err(network_failure)?;
+                serde_json::from_slice(&body).map_err(|e| ApiFailure {
+                    error: e.into(),
+                    retry: false,
+                    retry_after: None,
+                })
+            }
+            Ok(response) => Err(failure(response).await),
+            Err(e) => Err(network_failure(e)),
+        }
+    }
+
+    #[tracing::instrument(name = "stripe_fetch", skip_all, fields(external_id = %id))]
     async fn fetch_payment_inner(&self, id: &ExternalId) -> Result<FetchedPayment, PipelineError> {
         let raw = id.as_str();
         if raw.starts_with("pi_") {
             let pi_id = raw
                 .parse::<stripe::PaymentIntentId>()
                 .map_err(|e| PipelineError::Provider(format!("invalid PaymentIntent id: {e}")))?;
-            let pi = stripe::PaymentIntent::retrieve(&self.client, &pi_id, &[])
-                .await
-                .map_err(|e| PipelineError::Provider(format!("Stripe API: {e}")))?;
-
-            let currency = convert_currency(pi.currency)?;
-            let amount = convert_amount(pi.amount)?;
-            let status = convert_pi_status(pi.status);
-            let metadata = serde_json::to_value(&pi.metadata)?;
-
-            Ok(FetchedPayment {
-                external_id: id.clone(),
-                direction: PaymentDirection::Inbound,
-                status,
-                money: Money::new(amount, currency),
-                metadata,
-                parent_external_id: None,
-            })
+            // Retrieved as raw JSON: the typed struct drops newer
+            // `last_payment_error` fields such as `network_advice_code`.
+            // The latest charge carries the descriptor and receipt.
+            let pi: serde_json::Value = self
+                .get(
+                    &format!("/payment_intents/{pi_id}"),
+                    &[("expand[]", "latest_charge".to_string())],
+                )
+                .await?;
+            convert_payment_intent(&pi)
         } else if raw.starts_with("re_") {
             let refund_id = raw
                 .parse::<stripe::RefundId>()
                 .map_err(|e| PipelineError::Provider(format!("invalid Refund id: {e}")))?;
-            let refund = stripe::Refund::retrieve(&self.client, &refund_id, &[])
-                .await
-                .map_err(|e| PipelineError::Provider(format!("Stripe API: {e}")))?;
-
-            let currency = convert_currency(refund.currency)?;
-            let amount = convert_amount(refund.amount)?;
-            let status = convert_refund_status(refund.status.as_deref());
-            let metadata = refund
-                .metadata
-                .as_ref()
-                .map(serde_json::to_value)
-                .transpose()?
-                .unwrap_or(serde_json::Value::Null);
-
-            let parent_pi_id = refund
-                .payment_intent
-                .as_ref()
-                .map(|e| {
-                    ExternalId::new(match e {
-                        stripe::Expandable::Id(id) => id.to_string(),
-                        stripe::Expandable::Object(pi) => pi.id.to_string(),
-                    })
-                })
-                .transpose()?;
-
-            Ok(FetchedPayment {
-                external_id: id.clone(),
-                direction: PaymentDirection::Outbound,
-                status,
-                money: Money::new(amount, currency),
-                metadata,
-                parent_external_id: parent_pi_id,
-            })
+            let refund: stripe::Refund = self.get(&format!("/refunds/{refund_id}"), &[]).await?;
+            convert_refund(&refund)
+        } else if raw.starts_with("dp_") {
+            let dispute_id = raw
+                .parse::<stripe::DisputeId>()
+                .map_err(|e| PipelineError::Provider(format!("invalid Dispute id: {e}")))?;
+            let dispute: stripe::Dispute =
+                self.get(&format!("/disputes/{dispute_id}"), &[]).await?;
+            convert_dispute(&dispute)
         } else {
             Err(PipelineError::Provider(format!(
                 "unknown external_id prefix: {raw}"
             )))
         }
     }
+
+    /// The PaymentIntent that paid an invoice. The API version the client
+    /// pins still has `payment_intent` on invoices.
+    async fn invoice_payment_intent(
+        &self,
+        invoice_id: &str,
+    ) -> Result<Option<String>, PipelineError> {
+        let invoice_id = invoice_id
+            .parse::<stripe::InvoiceId>()
+            .map_err(|e| PipelineError::Provider(format!("invalid Invoice id: {e}")))?;
+        let invoice: serde_json::Value = self.get(&format!("/invoices/{invoice_id}"), &[]).await?;
+        Ok(expandable_id(&invoice["payment_intent"]).map(str::to_string))
+    }
+
+    /// Lists PaymentIntents first, then Refunds. The cursor is the last object
+    /// id seen (`pi_…` or `re_…`), or `re_` alone to start the refund phase.
+    async fn list_payments_inner(
+        &self,
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+        cursor: Option<ListCursor>,
+    ) -> Result<PaymentPage, PipelineError> {
+        let mut query = vec![
+            ("created[gte]", since.timestamp().to_string()),
+            ("created[lte]", until.timestamp().to_string()),
+            ("limit", LIST_PAGE_SIZE.to_string()),
+        ];
+        let cursor = cursor.as_ref().map(|c| c.as_str());
+
+        match cursor {
+            Some(c) if c.starts_with("re_") => {
+                if c != REFUND_PHASE {
+                    let after = c.parse::<stripe::RefundId>().map_err(|e| {
+                        PipelineError::Validation(format!("invalid list cursor: {e}"))
+                    })?;
+                    query.push(("starting_after", after.to_string()));
+                }
+                let list: stripe::List<stripe::Refund> = self.get("/refunds", &query).await?;
+
+                let next_cursor = match (list.has_more, list.data.last()) {
+                    (true, Some(last)) => Some(ListCursor::new(last.id.to_string())),
+                    _ => None,
+                };
+                Ok(PaymentPage {
+                    payments: convert_listed(list.data.iter(), convert_refund),
+                    next_cursor,
+                })
+            }
+            Some(c) if !c.starts_with("pi_") => Err(PipelineError::Validation(format!(
+                "invalid list cursor: {c}"
+            ))),
+            _ => {
+                query.push(("expand[]", "data.latest_charge".to_string()));
+                if let Some(c) = cursor {
+                    let after = c.parse::<stripe::PaymentIntentId>().map_err(|e| {
+                        PipelineError::Validation(format!("invalid list cursor: {e}"))
+                    })?;
+                    query.push(("starting_after", after.to_string()));
+                }
+                let list: stripe::List<serde_json::Value> =
+                    self.get("/payment_intents", &query).await?;
+
+                let last_id = list.data.last().and_then(|pi| pi["id"].as_str());
+                let next_cursor = match (list.has_more, last_id) {
+                    (true, Some(last)) => ListCursor::new(last),
+                    _ => ListCursor::new(REFUND_PHASE),
+                };
+                Ok(PaymentPage {
+                    payments: convert_listed(list.data.iter(), convert_payment_intent),
+                    next_cursor: Some(next_cursor),
+                })
+            }
+        }
+    }
+}
+
+/// Webhook endpoint management, for `services::webhook_setup`. These are
+/// the platform's endpoints, whatever account the client was scoped to.
+impl StripeProvider {
+    /// Every webhook endpoint on the account (Stripe allows 16, one page).
+    pub async fn webhook_endpoints(&self) -> Result<Vec<WebhookEndpoint>, PipelineError> {
+        let list: stripe::List<WebhookEndpoint> = self
+            .platform()
+            .get("/webhook_endpoints", &[("limit", "100".to_string())])
+            .await?;
+        Ok(list.data)
+    }
+
+    pub async fn webhook_endpoint(&self, id: &str) -> Result<WebhookEndpoint, PipelineError> {
+        self.platform()
+            .get(&format!("/webhook_endpoints/{id}"), &[])
+            .await
+    }
+
+    /// A new endpoint sending `target`'s events to its URL, on the API
+    /// version the client decodes. Its `secret` is set.
+    pub async fn create_webhook_endpoint(
+        &self,
+        target: &EndpointTarget,
+    ) -> Result<WebhookEndpoint, PipelineError> {
+        let mut form = endpoint_form(target);
+        form.push(("api_version", API_VERSION.as_str().to_string()));
+        form.push(("description", "fin_sync".to_string()));
+        self.platform().post("/webhook_endpoints", &form).await
+    }
+
+    /// Point endpoint `id` at `target`, enabling it if it was disabled.
+    pub async fn update_webhook_endpoint(
+        &self,
+        id: &str,
+        target: &EndpointTarget,
+    ) -> Result<WebhookEndpoint, PipelineError> {
+        let mut form = endpoint_form(target);
+        form.push(("disabled", "false".to_string()));
+        self.platform()
+            .post(&format!("/webhook_endpoints/{id}"), &form)
+            .await
+    }
+
+    pub async fn delete_webhook_endpoint(&self, id: &str) -> Result<(), PipelineError> {
+        self.platform()
+            .delete(&format!("/webhook_endpoints/{id}"))
+            .await
+    }
+
+    fn platform(&self) -> Self {
+        Self {
+            account: None,
+            ..self.clone()
+        }
+    }
+}
+
+fn endpoint_form(target: &EndpointTarget) -> Vec<(&'static str, String)> {
+    let mut form = vec![("url", target.url.clone())];
+    form.extend(
+        target
+            .enabled_events
+            .iter()
+            .map(|e| ("enabled_events[]", e.clone())),
+    );
+    form
+}
+
+const LIST_PAGE_SIZE: u64 = 100;
+const REFUND_PHASE: &str = "re_";
+
+/// A failed request: the error to return, and whether sending it again
+/// might get a different answer.
+#[derive(Debug)]
+struct ApiFailure {
+    error: PipelineError,
+    retry: bool,
+    retry_after: Option<Duration>,
+}
+
+impl std::fmt::Display for ApiFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+fn network_failure(e: reqwest::Error) -> ApiFailure {
+    ApiFailure {
+        error: PipelineError::Provider(format!("Stripe API: {e}")),
+        retry: true,
+        retry_after: None,
+    }
+}
+
+async fn failure(response: reqwest::Response) -> ApiFailure {
+    let status = response.status();
+    let headers = response.headers();
+    let should_retry = headers
+        .get("Stripe-Should-Retry")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok());
+    // Only the delay-seconds form; Stripe doesn't send dates.
+    let retry_after = headers
+        .get(RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse().ok())
+        .map(Duration::from_secs);
+    let body: serde_json::Value = response.json().await.unwrap_or_default();
+    let message = body["error"]["message"].as_str().unwrap_or_default();
+    classify(status, message, should_retry, retry_after)
+}
+
+/// Permanent or not, by status: 404 means Stripe doesn't have the object
+/// (`ProviderMissing`); other 4xx but 401, 409 and 429 mean it won't take
+/// the request as sent (`ProviderRejected`). Rate limits, conflicts, 5xx and
+/// a bad API key (ours to fix, not the job's) are `Provider` errors, and all
+/// but the last are retried, unless `Stripe-Should-Retry` says otherwise.
+fn classify(
+    status: StatusCode,
+    message: &str,
+    should_retry: Option<bool>,
+    retry_after: Option<Duration>,
+) -> ApiFailure {
+    let text = format!("Stripe API: {status}: {message}");
+    let error = match status.as_u16() {
+        404 => PipelineError::ProviderMissing(text),
+        401 | 409 | 429 => PipelineError::Provider(text),
+        400..=499 => PipelineError::ProviderRejected(text),
+        _ => PipelineError::Provider(text),
+    };
+    let transient = matches!(status.as_u16(), 409 | 429) || status.is_server_error();
+    ApiFailure {
+        error,
+        retry: should_retry.unwrap_or(transient),
+        retry_after,
+    }
+}
+
+/// Where the PaymentIntent behind a checkout session or invoice is found.
+#[derive(Debug, PartialEq)]
+enum IntentRef<'a> {
+    /// In the object itself, or known not to exist.
+    Known(Option<&'a str>),
+    /// On this invoice, which has to be fetched.
+    Invoice(&'a str),
+}
+
+fn intent_ref(object: &serde_json::Value) -> IntentRef<'_> {
+    if let Some(pi) = expandable_id(&object["payment_intent"]) {
+        return IntentRef::Known(Some(pi));
+    }
+    match object["object"].as_str() {
+        // Subscription-mode sessions pay through their first invoice.
+        Some("checkout.session") => {
+            expandable_id(&object["invoice"]).map_or(IntentRef::Known(None), IntentRef::Invoice)
+        }
+        // Events sent with API versions from 2025 on leave the intent off
+        // invoices; a `null` one means nothing was charged.
+        Some("invoice") if object.get("payment_intent").is_none() => object["id"]
+            .as_str()
+            .map_or(IntentRef::Known(None), IntentRef::Invoice),
+        _ => IntentRef::Known(None),
+    }
+}
+
+/// Fields each object applied from a webhook must carry, as a fetch would.
+const PAYLOAD_FIELDS: &[(&str, &[&str])] = &[
+    (
+        "payment_intent",
+        &["id", "amount", "currency", "status", "metadata", "created"],
+    ),
+    ("refund", &["id", "amount", "currency", "status"]),
+    (
+        "dispute",
+        &[
+            "id",
+            "amount",
+            "currency",
+            "status",
+            "reason",
+            "payment_intent",
+        ],
+    ),
+];
+
+/// The first field `object` lacks (or has as `null`) of those a fetch
+/// would fill, or `"object"` for a kind not applied from webhooks at all.
+/// Thin events and objects Stripe truncated fail here.
+fn missing_payload_field(object: &serde_json::Value) -> Option<&'static str> {
+    let Some((_, fields)) = PAYLOAD_FIELDS
+        .iter()
+        .find(|(kind, _)| object["object"].as_str() == Some(kind))
+    else {
+        return Some("object");
+    };
+    fields.iter().copied().find(|f| object[*f].is_null())
+}
+
+/// The id of an expandable field, whether it came as an id or an object.
+pub(crate) fn expandable_id(value: &serde_json::Value) -> Option<&str> {
+    value.as_str().or_else(|| value["id"].as_str())
+}
+
+/// Convert listed objects, skipping (with a warning) any we can't represent,
+/// e.g. unsupported currencies — one odd object shouldn't sink a whole page.
+fn convert_listed<'a, T: 'a>(
+    items: impl Iterator<Item = &'a T>,
+    convert: fn(&T) -> Result<FetchedPayment, PipelineError>,
+) -> Vec<FetchedPayment> {
+    items
+        .filter_map(|item| match convert(item) {
+            Ok(p) => Some(p),
+            Err(e) => {
+                tracing::warn!(error = %e, "skipping listed object");
+                None
+            }
+        })
+        .collect()
+}
+
+fn convert_payment_intent(raw: &serde_json::Value) -> Result<FetchedPayment, PipelineError> {
+    let mut typed = raw.clone();
+    // The typed struct would have to parse an expanded charge in full; only
+    // the receipt fields are read from it, below.
+    if let Some(charge_id) = raw["latest_charge"]["id"].as_str() {
+        typed["latest_charge"] = charge_id.into();
+    }
+    let pi: stripe::PaymentIntent = serde_json::from_value(typed)?;
+    let currency = convert_currency(pi.currency)?;
+    let amount = convert_amount(pi.amount, currency)?;
+    // A canceled intent whose authorization lapsed uncaptured is expired, not failed.
+    let status = match raw.get("cancellation_reason").and_then(|r| r.as_str()) {
+        Some("expired") if pi.status == stripe::PaymentIntentStatus::Canceled => {
+            PaymentStatus::Expired
+        }
+        _ => convert_pi_status(pi.status),
+    };
+    let authorized_amount = (pi.status == stripe::PaymentIntentStatus::RequiresCapture)
+        .then(|| convert_amount(pi.amount_capturable, currency))
+        .transpose()?;
+    let mut metadata = serde_json::to_value(&pi.metadata)?;
+    // References support searches by that Stripe keeps outside metadata.
+    // Keys the merchant set themselves win.
+    if let Some(fields) = metadata.as_object_mut() {
+        let descriptor = pi
+            .statement_descriptor
+            .as_ref()
+            .or(pi.statement_descriptor_suffix.as_ref());
+        for (key, value) in [
+            ("statement_descriptor", descriptor),
+            ("description", pi.description.as_ref()),
+        ] {
+            if let Some(value) = value {
+                fields.entry(key).or_insert_with(|| value.clone().into());
+            }
+        }
+    }
+    // The customer, expanded or not.
+    let customer = raw["customer"]
+        .as_str()
+        .or(raw["customer"]["id"].as_str())
+        .map(str::to_string);
+
+    Ok(FetchedPayment {
+        external_id: ExternalId::new(pi.id.to_string())?,
+        direction: PaymentDirection::Inbound,
+        status,
+        money: Money::new(amount, currency),
+        metadata,
+        parent_external_id: None,
+        failure: convert_failure(raw.get("last_payment_error")),
+        authorized_amount,
+        receipt: convert_receipt(raw),
+        customer,
+        created_at: DateTime::from_timestamp(pi.created, 0),
+    })
+}
+
+/// Descriptor and receipt of a PaymentIntent. The charge's
+/// `calculated_statement_descriptor` is what the customer's bank shows;
+/// it's only there once the latest charge is expanded (or embedded under
+/// `charges`, in older API versions), else the intent's own descriptor is
+/// the best we know.
+fn convert_receipt(raw: &serde_json::Value) -> Option<PaymentReceipt> {
+    let charge = match &raw["latest_charge"] {
+        charge @ serde_json::Value::Object(_) => Some(charge),
+        _ => raw["charges"]["data"].get(0),
+    };
+    let text = |v: &serde_json::Value| v.as_str().filter(|s| !s.is_empty()).map(String::from);
+    let from_charge = |key: &str| charge.and_then(|c| text(&c[key]));
+
+    PaymentReceipt::from_parts(
+        from_charge("calculated_statement_descriptor")
+            .or_else(|| text(&raw["statement_descriptor"]))
+            .or_else(|| text(&raw["statement_descriptor_suffix"])),
+        text(&raw["receipt_email"]).or_else(|| from_charge("receipt_email")),
+        from_charge("receipt_url"),
+    )
+}
+
+#[derive(serde::Deserialize)]
+struct LastPaymentError {
+    code: Option<String>,
+    decline_code: Option<String>,
+    message: Option<String>,
+    network_advice_code: Option<String>,
+}
+
+fn convert_failure(raw: Option<&serde_json::Value>) -> Option<PaymentFailure> {
+    let err: LastPaymentError = serde_json::from_value(raw?.clone()).ok()?;
+    PaymentFailure::from_parts(
+        err.code,
+        err.decline_code,
+        err.message,
+        err.network_advice_code,
+    )
+}
+
+fn convert_refund(refund: &stripe::Refund) -> Result<FetchedPayment, PipelineError> {
+    let currency = convert_currency(refund.currency)?;
+    let amount = convert_amount(refund.amount, currency)?;
+    let status = convert_refund_status(refund.status.as_deref());
+    let metadata = refund
+        .metadata
+        .as_ref()
+        .map(serde_json::to_value)
+        .transpose()?
+        .unwrap_or(serde_json::Value::Null);
+
+    let parent_pi_id = refund
+        .payment_intent
+        .as_ref()
+        .map(|e| {
+            ExternalId::new(match e {
+                stripe::Expandable::Id(id) => id.to_string(),
+                stripe::Expandable::Object(pi) => pi.id.to_string(),
+            })
+        })
+        .transpose()?;
+
+    Ok(FetchedPayment {
+        external_id: ExternalId::new(refund.id.to_string())?,
+        direction: PaymentDirection::Outbound,
+        status,
+        money: Money::new(amount, currency),
+        metadata,
+        parent_external_id: parent_pi_id,
+        failure: PaymentFailure::from_parts(refund.failure_reason.clone(), None, None, None),
+        authorized_amount: None,
+        receipt: None,
+        customer: None,
+        created_at: DateTime::from_timestamp(refund.created, 0),
+    })
+}
+
+/// A dispute is money pulled back from the disputed payment, so it's stored
+/// as an outbound row under that payment. Charges without a PaymentIntent
+/// have no row to link to.
+fn convert_dispute(dispute: &stripe::Dispute) -> Result<FetchedPayment, PipelineError> {
+    let currency = convert_currency(dispute.currency)?;
+    let amount = convert_amount(dispute.amount, currency)?;
+    let mut metadata = serde_json::to_value(&dispute.metadata)?;
+    if let Some(fields) = metadata.as_object_mut() {
+        fields.insert("dispute_reason".into(), dispute.reason.clone().into());
+    }
+
+    let parent_pi_id = dispute
+        .payment_intent
+        .as_ref()
+        .map(|e| {
+            ExternalId::new(match e {
+                stripe::Expandable::Id(id) => id.to_string(),
+                stripe::Expandable::Object(pi) => pi.id.to_string(),
+            })
+        })
+        .transpose()?;
+
+    Ok(FetchedPayment {
+        external_id: ExternalId::new(dispute.id.to_string())?,
+        direction: PaymentDirection::Outbound,
+        status: convert_dispute_status(dispute.status),
+        money: Money::new(amount, currency),
+        metadata,
+        parent_external_id: parent_pi_id,
+        failure: None,
+        authorized_amount: None,
+        receipt: None,
+        customer: None,
+        created_at: DateTime::from_timestamp(dispute.created, 0),
+    })
 }
 
 // ── Conversion helpers (moved from stripe_webhook.rs) ───────────────────────
 
-fn convert_currency(c: stripe::Currency) -> Result<Currency, PipelineError> {
-    match c {
-        stripe::Currency::USD => Ok(Currency::Usd),
-        stripe::Currency::EUR => Ok(Currency::Eur),
-        stripe::Currency::GBP => Ok(Currency::Gbp),
-        stripe::Currency::JPY => Ok(Currency::Jpy),
+/// A payout or transfer from the object embedded in one of its events
+/// (`payout.*`, `transfer.*`). Events carry the whole object, so nothing is
+/// fetched.
+pub fn convert_payout_event(
+    event_id: EventId,
+    event_type: &str,
+    provider_ts: i64,
+    raw_event: &serde_json::Value,
+) -> Result<NewPayout, PipelineError> {
+    let object = &raw_event["data"]["object"];
+    let new = |external_id: &str,
+               kind,
+               money,
+               status,
+               amount_reversed,
+               destination|
+     -> Result<NewPayout, PipelineError> {
+        Ok(NewPayout {
+            external_id: PayoutId::new(external_id)?,
+            source: "stripe".into(),
+            kind,
+            money,
+            status,
+            amount_reversed,
+            destination,
+            arrival_date: None,
+            failure_code: None,
+            failure_message: None,
+            metadata: serde_json::json!({}),
+            event_id: event_id.clone(),
+            event_type: event_type.to_string(),
+            provider_ts,
+            request_id: event_order::request_id(raw_event).map(str::to_string),
+            raw_event: raw_event.clone(),
+        })
+    };
+    match object["object"].as_str() {
+        Some("payout") => {
+            let payout: stripe::Payout = serde_json::from_value(object.clone())?;
+            let currency = convert_currency(payout.currency)?;
+            let destination = payout.destination.as_ref().map(|d| d.id().to_string());
+            let mut new = new(
+                payout.id.as_str(),
+                PayoutKind::Payout,
+                Money::new(convert_amount(payout.amount, currency)?, currency),
+                convert_payout_status(&payout.status),
+                MoneyAmount::new(0)?,
+                destination,
+            )?;
+            new.arrival_date = DateTime::from_timestamp(payout.arrival_date, 0);
+            new.failure_code = payout.failure_code;
+            new.failure_message = payout.failure_message;
+            new.metadata = serde_json::to_value(&payout.metadata)?;
+            Ok(new)
+        }
+        Some("transfer") => {
+            let transfer: stripe::Transfer = serde_json::from_value(object.clone())?;
+            let currency = convert_currency(transfer.currency)?;
+            let destination = transfer.destination.as_ref().map(|d| d.id().to_string());
+            // A transfer is paid when it is created; reversals take money
+            // back without undoing it.
+            let mut new = new(
+                transfer.id.as_str(),
+                PayoutKind::Transfer,
+                Money::new(convert_amount(transfer.amount, currency)?, currency),
+                PayoutStatus::Paid,
+                convert_amount(transfer.amount_reversed, currency)?,
+                destination,
+            )?;
+            new.metadata = serde_json::to_value(&transfer.metadata)?;
+            Ok(new)
+        }
         other => Err(PipelineError::Validation(format!(
-            "unsupported currency: {other:?}"
+            "{event_type} event carries {other:?}, not a payout or transfer"
         ))),
     }
 }
 
-fn convert_amount(amount: i64) -> Result<MoneyAmount, PipelineError> {
+/// The connected account an event happened on: Connect events carry it as
+/// `account`. `None` for the platform's own events.
+pub fn event_tenant(raw_event: &serde_json::Value) -> Result<Option<TenantId>, PipelineError> {
+    Ok(raw_event["account"]
+        .as_str()
+        .map(TenantId::new)
+        .transpose()?)
+}
+
+/// `in_transit` is still pending for us: the money hasn't arrived.
+fn convert_payout_status(status: &str) -> PayoutStatus {
+    match status {
+        "paid" => PayoutStatus::Paid,
+        "failed" => PayoutStatus::Failed,
+        "canceled" => PayoutStatus::Cancelled,
+        "pending" | "in_transit" => PayoutStatus::Pending,
+        other => {
+            tracing::warn!("unknown payout status: {other}, defaulting to Pending");
+            PayoutStatus::Pending
+        }
+    }
+}
+
+/// A capture from a `charge.captured` event. The charge reports what has
+/// been captured in total (`amount_captured`); what this capture took is
+/// worked out against the captures already recorded, under the payment's
+/// lock, when it is stored.
+pub fn convert_capture_event(
+    event_id: EventId,
+    event_type: &str,
+    provider_ts: i64,
+    raw_event: &serde_json::Value,
+) -> Result<NewCapture, PipelineError> {
+    #[derive(serde::Deserialize)]
+    struct CapturedCharge {
+        id: String,
+        amount: i64,
+        amount_captured: i64,
+        currency: stripe::Currency,
+        #[serde(default)]
+        payment_intent: serde_json::Value,
+    }
+
+    let object = &raw_event["data"]["object"];
+    if object["object"].as_str() != Some("charge") {
+        return Err(PipelineError::Validation(format!(
+            "{event_type} event carries {:?}, not a charge",
+            object["object"].as_str()
+        )));
+    }
+    let charge: CapturedCharge = serde_json::from_value(object.clone())?;
+    let payment_intent = expandable_id(&charge.payment_intent).ok_or_else(|| {
+        PipelineError::Validation(format!("charge {} has no PaymentIntent", charge.id))
+    })?;
+    let currency = convert_currency(charge.currency)?;
+    if charge.amount_captured <= 0 {
+        return Err(PipelineError::Validation(format!(
+            "charge {} has captured nothing",
+            charge.id
+        )));
+    }
+    Ok(NewCapture {
+        payment_external_id: ExternalId::new(payment_intent.to_string())?,
+        source: "stripe".into(),
+        charge_id: charge.id,
+        currency,
+        captured_total: convert_amount(charge.amount_captured, currency)?,
+        authorized: convert_amount(charge.amount, currency)?,
+        event_id,
+        event_type: event_type.to_string(),
+        provider_ts,
+        raw_event: raw_event.clone(),
+        tenant_id: event_tenant(raw_event)?,
+    })
+}
+
+fn convert_currency(c: stripe::Currency) -> Result<Currency, PipelineError> {
+    Ok(Currency::try_from(c.to_string().as_str())?)
+}
+
+/// Currencies whose Stripe amounts don't follow ISO 4217 minor units: ISK
+/// is sent in hundredths though it has none, MGA in whole ariary.
+const STRIPE_MINOR_UNITS: &[(&str, u32)] = &[("isk", 2), ("mga", 0)];
+
+/// A Stripe amount, rescaled to ISO minor units where Stripe differs.
+fn convert_amount(amount: i64, currency: Currency) -> Result<MoneyAmount, PipelineError> {
     if amount < 0 {
         return Err(PipelineError::Validation("negative amount".into()));
     }
-    MoneyAmount::new(amount)
+    let stripe_units = STRIPE_MINOR_UNITS
+        .iter()
+        .find(|(code, _)| *code == currency.as_str())
+        .map_or(currency.minor_units(), |(_, units)| *units);
+    let iso_units = currency.minor_units();
+    let minor = if stripe_units > iso_units {
+        let factor = 10_i64.pow(stripe_units - iso_units);
+        if amount % factor != 0 {
+            return Err(PipelineError::Validation(format!(
+                "{currency} amount {amount} has a fractional minor unit"
+            )));
+        }
+        amount / factor
+    } else {
+        amount
+            .checked_mul(10_i64.pow(iso_units - stripe_units))
+            .ok_or_else(|| PipelineError::Validation(format!("amount too large: {amount}")))?
+    };
+    Ok(MoneyAmount::new(minor)?)
 }
 
 fn convert_pi_status(status: stripe::PaymentIntentStatus) -> PaymentStatus {
     #[allow(unreachable_patterns)]
     match status {
         stripe::PaymentIntentStatus::Succeeded => PaymentStatus::Succeeded,
-        stripe::PaymentIntentStatus::Canceled => PaymentStatus::Failed,
+        stripe::PaymentIntentStatus::Canceled => PaymentStatus::Cancelled,
         stripe::PaymentIntentStatus::Processing
         | stripe::PaymentIntentStatus::RequiresAction
         | stripe::PaymentIntentStatus::RequiresCapture
@@ -141,7 +958,259 @@ fn convert_pi_status(status: stripe::PaymentIntentStatus) -> PaymentStatus {
 fn convert_refund_status(status: Option<&str>) -> PaymentStatus {
     match status {
         Some("succeeded") => PaymentStatus::Refunded,
-        Some("failed") | Some("canceled") => PaymentStatus::Failed,
+        Some("failed") => PaymentStatus::Failed,
+        Some("canceled") => PaymentStatus::Cancelled,
         _ => PaymentStatus::Pending,
     }
 }
+
+/// Inquiries closed without escalating count as won: no funds moved.
+fn convert_dispute_status(status: stripe::DisputeStatus) -> PaymentStatus {
+    match status {
+        stripe::DisputeStatus::Won | stripe::DisputeStatus::WarningClosed => {
+            PaymentStatus::DisputeWon
+        }
+        stripe::DisputeStatus::Lost => PaymentStatus::DisputeLost,
+        stripe::DisputeStatus::NeedsResponse
+        | stripe::DisputeStatus::UnderReview
+        | stripe::DisputeStatus::WarningNeedsResponse
+        | stripe::DisputeStatus::WarningUnderReview => PaymentStatus::Disputed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn amounts_are_rescaled_to_iso_minor_units() {
+        let minor = |amount, c: stripe::Currency| {
+            convert_amount(amount, convert_currency(c).unwrap()).map(|a| a.cents())
+        };
+        assert_eq!(minor(1099, stripe::Currency::USD).unwrap(), 1099);
+        assert_eq!(minor(1200, stripe::Currency::JPY).unwrap(), 1200);
+        assert_eq!(minor(50_000, stripe::Currency::ISK).unwrap(), 500);
+        assert!(minor(50_050, stripe::Currency::ISK).is_err());
+        assert_eq!(minor(700, stripe::Currency::MGA).unwrap(), 70_000);
+        assert!(minor(-1, stripe::Currency::USD).is_err());
+    }
+
+    #[test]
+    fn failures_are_classified_by_status_and_retry_hint() {
+        let classify = |status: u16, should_retry| {
+            classify(
+                StatusCode::from_u16(status).unwrap(),
+                "boom",
+                should_retry,
+                None,
+            )
+        };
+        let missing = classify(404, None);
+        assert!(matches!(missing.error, PipelineError::ProviderMissing(_)));
+        assert!(!missing.retry);
+        for status in [400, 403] {
+            let rejected = classify(status, None);
+            assert!(matches!(rejected.error, PipelineError::ProviderRejected(_)));
+            assert!(!rejected.retry);
+        }
+        for status in [429, 500, 503] {
+            let transient = classify(status, None);
+            assert!(matches!(transient.error, PipelineError::Provider(_)));
+            assert!(transient.retry);
+        }
+        assert!(!classify(401, None).retry);
+        assert!(!classify(500, Some(false)).retry);
+        assert!(classify(400, Some(true)).retry);
+        assert_eq!(
+            classify(429, None).error.to_string(),
+            "provider: Stripe API: 429 Too Many Requests: boom"
+        );
+    }
+
+    #[test]
+    fn cancellations_are_not_failures() {
+        assert_eq!(
+            convert_pi_status(stripe::PaymentIntentStatus::Canceled),
+            PaymentStatus::Cancelled
+        );
+        assert_eq!(
+            convert_refund_status(Some("canceled")),
+            PaymentStatus::Cancelled
+        );
+        assert_eq!(convert_refund_status(Some("failed")), PaymentStatus::Failed);
+    }
+
+    #[test]
+    fn payouts_and_transfers_come_from_the_event_object() {
+        let event = |object: serde_json::Value| {
+            serde_json::json!({"id": "evt_1", "type": "payout.paid", "created": 1_700_000_000,
+                "data": {"object": object}})
+        };
+        let payout = event(serde_json::json!({
+            "id": "po_1", "object": "payout", "amount": 50_000, "arrival_date": 1_700_086_400,
+            "automatic": true, "created": 1_700_000_000, "currency": "usd", "livemode": false,
+            "destination": "ba_1", "method": "standard", "reconciliation_status": "completed",
+            "source_type": "card", "status": "in_transit", "type": "bank_account",
+            "metadata": {"batch": "42"},
+        }));
+        let new = convert_payout_event(EventId::new("evt_1").unwrap(), "payout.paid", 1, &payout)
+            .unwrap();
+        assert_eq!(new.kind, PayoutKind::Payout);
+        assert_eq!(new.status, PayoutStatus::Pending);
+        assert_eq!(new.money.amount().cents(), 50_000);
+        assert_eq!(new.destination.as_deref(), Some("ba_1"));
+        assert_eq!(new.arrival_date.unwrap().timestamp(), 1_700_086_400);
+        assert_eq!(new.metadata["batch"], "42");
+        assert_eq!(new.request_id, None);
+
+        let mut transfer = event(serde_json::json!({
+            "id": "tr_1", "object": "transfer", "amount": 1_000, "amount_reversed": 250,
+            "created": 1_700_000_000, "currency": "eur", "destination": "acct_1",
+            "livemode": false, "metadata": {}, "reversed": false,
+            "reversals": {"object": "list", "data": [], "has_more": false,
+                "url": "/v1/transfers/tr_1/reversals"},
+        }));
+        transfer["request"] = serde_json::json!({"id": "req_1", "idempotency_key": null});
+        let new = convert_payout_event(
+            EventId::new("evt_1").unwrap(),
+            "transfer.reversed",
+            1,
+            &transfer,
+        )
+        .unwrap();
+        assert_eq!(new.kind, PayoutKind::Transfer);
+        assert_eq!(new.status, PayoutStatus::Paid);
+        assert_eq!(new.amount_reversed.cents(), 250);
+        assert_eq!(new.destination.as_deref(), Some("acct_1"));
+        assert_eq!(new.request_id.as_deref(), Some("req_1"));
+
+        assert_eq!(convert_payout_status("canceled"), PayoutStatus::Cancelled);
+        assert_eq!(convert_payout_status("failed"), PayoutStatus::Failed);
+    }
+
+    #[test]
+    fn a_capture_carries_the_charges_captured_total() {
+        let event = |captured: i64| {
+            let event = serde_json::json!({"id": "evt_1", "type": "charge.captured",
+                "created": 1_700_000_000, "data": {"object": {
+                    "id": "ch_1", "object": "charge", "amount": 1_000,
+                    "amount_captured": captured, "currency": "usd",
+                    "payment_intent": "pi_multi", "captured": true}}});
+            convert_capture_event(EventId::new("evt_1").unwrap(), "charge.captured", 1, &event)
+        };
+
+        let capture = event(750).unwrap();
+        assert_eq!(capture.payment_external_id.as_str(), "pi_multi");
+        assert_eq!(capture.charge_id, "ch_1");
+        assert_eq!(capture.currency, Currency::USD);
+        assert_eq!(capture.captured_total.cents(), 750);
+        assert_eq!(capture.authorized.cents(), 1_000);
+
+        assert!(matches!(event(0), Err(PipelineError::Validation(_))));
+    }
+
+    #[test]
+    fn checkout_and_invoice_events_lead_to_their_intent() {
+        use serde_json::json;
+        let session = |extra: serde_json::Value| {
+            let mut object = json!({"id": "cs_1", "object": "checkout.session"});
+            object
+                .as_object_mut()
+                .unwrap()
+                .extend(extra.as_object().unwrap().clone());
+            object
+        };
+        assert_eq!(
+            intent_ref(&session(json!({"payment_intent": "pi_1", "invoice": null}))),
+            IntentRef::Known(Some("pi_1"))
+        );
+        assert_eq!(
+            intent_ref(&session(json!({"payment_intent": {"id": "pi_1"}}))),
+            IntentRef::Known(Some("pi_1"))
+        );
+        assert_eq!(
+            intent_ref(&session(json!({"payment_intent": null, "invoice": "in_1"}))),
+            IntentRef::Invoice("in_1")
+        );
+        // Setup mode: nothing was paid.
+        assert_eq!(
+            intent_ref(&session(json!({"payment_intent": null, "invoice": null}))),
+            IntentRef::Known(None)
+        );
+
+        let invoice = json!({"id": "in_1", "object": "invoice", "payment_intent": "pi_2"});
+        assert_eq!(intent_ref(&invoice), IntentRef::Known(Some("pi_2")));
+        let zero = json!({"id": "in_1", "object": "invoice", "payment_intent": null});
+        assert_eq!(intent_ref(&zero), IntentRef::Known(None));
+        let newer = json!({"id": "in_1", "object": "invoice", "payments": {"data": []}});
+        assert_eq!(intent_ref(&newer), IntentRef::Invoice("in_1"));
+    }
+
+    #[test]
+    fn only_complete_objects_stand_in_for_a_fetch() {
+        use serde_json::json;
+        let provider = StripeProvider::new("sk_test");
+        let event = |object: serde_json::Value| json!({"data": {"object": object}});
+        let intent = json!({
+            "id": "pi_1", "object": "payment_intent", "amount": 1500,
+            "amount_capturable": 0, "amount_received": 1500,
+            "capture_method": "automatic", "confirmation_method": "automatic",
+            "created": 1_700_000_000, "currency": "usd", "livemode": false,
+            "metadata": {}, "payment_method_types": ["card"],
+            "status": "succeeded", "statement_descriptor_suffix": "ORDER 42",
+            "latest_charge": "ch_1",
+        });
+        let payment = provider
+            .payment_from_payload(&event(intent.clone()))
+            .unwrap()
+            .unwrap();
+        assert_eq!(payment.status, PaymentStatus::Succeeded);
+        // The unexpanded charge's receipt is left to a fetch or charge event.
+        assert!(payment.receipt.is_none());
+
+        let mut truncated = intent;
+        truncated["amount"] = serde_json::Value::Null;
+        assert_eq!(missing_payload_field(&truncated), Some("amount"));
+        assert!(
+            provider
+                .payment_from_payload(&event(truncated))
+                .unwrap()
+                .is_none()
+        );
+        // Thin events carry no object at all.
+        let thin = json!({"data": {"related_object": {"id": "pi_1"}}});
+        assert!(provider.payment_from_payload(&thin).unwrap().is_none());
+        let charge = json!({"id": "ch_1", "object": "charge", "amount": 1500});
+        assert_eq!(missing_payload_field(&charge), Some("object"));
+    }
+
+    #[test]
+    fn receipt_prefers_what_the_charge_reports() {
+        let pi = serde_json::json!({
+            "statement_descriptor_suffix": "ORDER 42",
+            "receipt_email": "jane@example.com",
+            "latest_charge": {
+                "id": "ch_1",
+                "calculated_statement_descriptor": "ACME* ORDER 42",
+                "receipt_url": "https://pay.stripe.com/receipts/x",
+            },
+        });
+        let receipt = convert_receipt(&pi).unwrap();
+        assert_eq!(
+            receipt.statement_descriptor.as_deref(),
+            Some("ACME* ORDER 42")
+        );
+        assert_eq!(
+            receipt.receipt_url.as_deref(),
+            Some("https://pay.stripe.com/receipts/x")
+        );
+
+        // Unexpanded charge: the intent's own descriptor is all there is.
+        let pi =
+            serde_json::json!({"statement_descriptor_suffix": "ORDER 42", "latest_charge": "ch_1"});
+        let receipt = convert_receipt(&pi).unwrap();
+        assert_eq!(receipt.statement_descriptor.as_deref(), Some("ORDER 42"));
+        assert_eq!(receipt.receipt_url, None);
+        assert!(convert_receipt(&serde_json::json!({"latest_charge": null})).is_none());
+    }
+}
@@ -0,0 +1,227 @@
+use crate::domain::{
+    job::JobPriority,
+    webhook::{EventRoute, EventStrategy},
+};
+
+/// Which strategy handles each Stripe event type. Routes are tried in order
+/// and the first matching pattern wins; a type nothing matches is logged as
+/// a passthrough.
+#[derive(Debug, Clone)]
+pub struct EventRouter {
+    routes: Vec<EventRoute>,
+}
+
+impl Default for EventRouter {
+    fn default() -> Self {
+        use EventStrategy::*;
+        let routes = [
+            ("payment_intent.*", Payment),
+            ("refund.*", Payment),
+            ("charge.dispute.*", Payment),
+            // Checkout and Billing collect through a PaymentIntent.
+            ("checkout.session.completed", RelatedPayment),
+            ("checkout.session.async_payment_succeeded", RelatedPayment),
+            ("checkout.session.async_payment_failed", RelatedPayment),
+            ("invoice.paid", RelatedPayment),
+            ("invoice.payment_succeeded", RelatedPayment),
+            ("invoice.payment_failed", RelatedPayment),
+            // Other charge events are logged against their intent.
+            ("charge.captured", Capture),
+            ("payout.*", Payout),
+            ("transfer.*", Payout),
+        ];
+        Self {
+            routes: routes
+                .into_iter()
+                .map(|(pattern, strategy)| EventRoute::new(pattern, strategy))
+                .collect(),
+        }
+    }
+}
+
+impl EventRouter {
+    /// The built-in routes, with `overrides` tried ahead of them.
+    pub fn with_overrides(overrides: &[EventRoute]) -> Self {
+        let mut router = Self::default();
+        router.routes.splice(0..0, overrides.iter().cloned());
+        router
+    }
+
+    pub fn route(&self, event_type: &str) -> EventStrategy {
+        self.routes
+            .iter()
+            .find(|r| r.matches(event_type))
+            .map_or(EventStrategy::Passthrough, |r| r.strategy)
+    }
+
+    /// The event types worth having Stripe send: those of `EVENT_TYPES`
+    /// routed to anything but a passthrough or ignore. Stripe takes no
+    /// wildcards short of `*`, so patterns are matched against the list.
+    pub fn enabled_events(&self) -> Vec<&'static str> {
+        EVENT_TYPES
+            .iter()
+            .copied()
+            .filter(|t| {
+                !matches!(
+                    self.route(t),
+                    EventStrategy::Passthrough | EventStrategy::Ignore
+                )
+            })
+            .collect()
+    }
+}
+
+/// The Stripe event types a route could reasonably take: every type of the
+/// objects the built-in routes handle, and their neighbours an override
+/// might pick up.
+const EVENT_TYPES: &[&str] = &[
+    "payment_intent.amount_capturable_updated",
+    "payment_intent.canceled",
+    "payment_intent.created",
+    "payment_intent.partially_funded",
+    "payment_intent.payment_failed",
+    "payment_intent.processing",
+    "payment_intent.requires_action",
+    "payment_intent.succeeded",
+    "refund.created",
+    "refund.failed",
+    "refund.updated",
+    "charge.captured",
+    "charge.expired",
+    "charge.failed",
+    "charge.pending",
+    "charge.refunded",
+    "charge.succeeded",
+    "charge.updated",
+    "charge.refund.updated",
+    "charge.dispute.closed",
+    "charge.dispute.created",
+    "charge.dispute.funds_reinstated",
+    "charge.dispute.funds_withdrawn",
+    "charge.dispute.updated",
+    "checkout.session.async_payment_failed",
+    "checkout.session.async_payment_succeeded",
+    "checkout.session.completed",
+    "checkout.session.expired",
+    "invoice.created",
+    "invoice.finalized",
+    "invoice.paid",
+    "invoice.payment_failed",
+    "invoice.payment_succeeded",
+    "invoice.voided",
+    "payout.canceled",
+    "payout.created",
+    "payout.failed",
+    "payout.paid",
+    "payout.updated",
+    "transfer.created",
+    "transfer.reversed",
+    "transfer.updated",
+];
+
+/// Events that settle an outcome: money captured, failed, refunded or
+/// disputed.
+const HIGH_PRIORITY: &[&str] = &[
+    "payment_intent.succeeded",
+    "payment_intent.payment_failed",
+    "payment_intent.canceled",
+    "payment_intent.amount_capturable_updated",
+    "refund.updated",
+    "refund.failed",
+    "charge.dispute.created",
+    "charge.dispute.closed",
+    "checkout.session.completed",
+    "checkout.session.async_payment_succeeded",
+    "checkout.session.async_payment_failed",
+    "invoice.paid",
+    "invoice.payment_succeeded",
+    "invoice.payment_failed",
+];
+
+/// Steps on the way that leave nothing settled.
+const LOW_PRIORITY: &[&str] = &[
+    "payment_intent.created",
+    "payment_intent.processing",
+    "payment_intent.requires_action",
+    "payment_intent.partially_funded",
+];
+
+/// The queue priority of a Stripe event's job.
+pub fn job_priority(event_type: &str) -> JobPriority {
+    if HIGH_PRIORITY.contains(&event_type) {
+        JobPriority::High
+    } else if LOW_PRIORITY.contains(&event_type) {
+        JobPriority::Low
+    } else {
+        JobPriority::Normal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_matching_route_wins() {
+        let router = EventRouter::default();
+        assert_eq!(
+            router.route("payment_intent.succeeded"),
+            EventStrategy::Payment
+        );
+        assert_eq!(
+            router.route("charge.dispute.created"),
+            EventStrategy::Payment
+        );
+        assert_eq!(
+            router.route("charge.refund.updated"),
+            EventStrategy::Passthrough
+        );
+        assert_eq!(router.route("invoice.paid"), EventStrategy::RelatedPayment);
+        assert_eq!(router.route("invoice.created"), EventStrategy::Passthrough);
+        assert_eq!(router.route("transfer.reversed"), EventStrategy::Payout);
+        assert_eq!(router.route("charge.captured"), EventStrategy::Capture);
+        assert_eq!(router.route("charge.updated"), EventStrategy::Passthrough);
+
+        let router = EventRouter::with_overrides(&[
+            "invoice.*=ignore".parse().unwrap(),
+            "charge.dispute.funds_withdrawn=passthrough"
+                .parse()
+                .unwrap(),
+        ]);
+        assert_eq!(router.route("invoice.paid"), EventStrategy::Ignore);
+        assert_eq!(
+            router.route("charge.dispute.funds_withdrawn"),
+            EventStrategy::Passthrough
+        );
+        assert_eq!(
+            router.route("charge.dispute.created"),
+            EventStrategy::Payment
+        );
+    }
+
+    #[test]
+    fn only_routed_events_are_enabled() {
+        let enabled = EventRouter::default().enabled_events();
+        assert!(enabled.contains(&"payment_intent.succeeded"));
+        assert!(enabled.contains(&"invoice.paid"));
+        assert!(enabled.contains(&"transfer.reversed"));
+        assert!(!enabled.contains(&"charge.updated"));
+        assert!(!enabled.contains(&"invoice.created"));
+
+        let router = EventRouter::with_overrides(&[
+            "invoice.*=ignore".parse().unwrap(),
+            "charge.refund.updated=payment".parse().unwrap(),
+        ]);
+        let enabled = router.enabled_events();
+        assert!(!enabled.contains(&"invoice.paid"));
+        assert!(enabled.contains(&"charge.refund.updated"));
+    }
+
+    #[test]
+    fn outcomes_are_queued_ahead_of_intermediate_steps() {
+        assert_eq!(job_priority("payment_intent.succeeded"), JobPriority::High);
+        assert_eq!(job_priority("refund.updated"), JobPriority::High);
+        assert_eq!(job_priority("charge.dispute.updated"), JobPriority::Normal);
+        assert_eq!(job_priority("payment_intent.created"), JobPriority::Low);
+    }
+}
@@ -1,141 +1,485 @@
 use {
+    super::{
+        client::{convert_capture_event, convert_payout_event, event_tenant, expandable_id},
+        router::job_priority,
+    },
     crate::{
         AppState,
         domain::{
-            error::PipelineError,
+            error::DomainError,
             id::{EventId, ExternalId},
-            payment::{PassthroughEvent, PaymentTrigger, WebhookTrigger},
+            payment::{PassthroughEvent, PaymentTrigger, ProcessResult, WebhookTrigger},
+            webhook::{EventStrategy, SignedDelivery},
+            webhook_endpoint::SigningSecret,
+        },
+        error::PipelineError,
+        infra::{postgres::job_repo, redact::redacted},
+        services::{
+            capture, jobs,
+            payment::pipeline::{
+                fetch_and_process_payment, handle_passthrough, process_fetched_payment,
+            },
+            payout, webhook_guard, webhook_setup,
         },
-        infra::postgres::job_repo,
-        services::payment::pipeline::handle_passthrough,
         transport::http::errors::ApiError,
     },
-    axum::{Json, extract::State, http::HeaderMap},
+    axum::{
+        Json,
+        extract::State,
+        http::{HeaderMap, StatusCode},
+    },
+    chrono::DateTime,
+    hmac::{Hmac, Mac},
+    serde::Deserialize,
+    sha2::Sha256,
 };
 
+/// Objects a `payment` route takes as the payment itself.
+const PAYMENT_OBJECTS: &[&str] = &["payment_intent", "refund", "dispute"];
+
+/// A webhook response. Stripe counts any 2xx as delivered; the status
+/// code tells apart outcomes a dashboard would otherwise have to read
+/// from the body.
+type Reply = (StatusCode, Json<serde_json::Value>);
+
+fn reply(body: serde_json::Value) -> Reply {
+    (StatusCode::OK, Json(body))
+}
+
+/// The response for an event processed inline: 202 Accepted for one
+/// received but ignored as older than what was applied, or held back as
+/// dated in a closed period, 200 otherwise (including one that repeated
+/// the current status).
+fn processed(result: &ProcessResult) -> Reply {
+    let status = match result {
+        ProcessResult::StaleIgnored(_) | ProcessResult::Diverted(_) => StatusCode::ACCEPTED,
+        _ => StatusCode::OK,
+    };
+    (status, Json(serde_json::json!({"status": result.as_str()})))
+}
+
+/// Just enough of an event to decide whether to parse the rest.
+#[derive(Deserialize)]
+struct Envelope {
+    id: String,
+    #[serde(rename = "type")]
+    event_type: String,
+    created: i64,
+}
+
 #[tracing::instrument(
     name = "webhook",
     skip_all,
-    fields(event_id = tracing::field::Empty, event_type = tracing::field::Empty)
+    fields(
+        event_id = tracing::field::Empty,
+        event_type = tracing::field::Empty,
+        secret_version = tracing::field::Empty
+    )
 )]
 pub async fn wh_handler(
     State(state): State<AppState>,
     headers: HeaderMap,
     body: String,
-) -> Result<Json<serde_json::Value>, ApiError> {
+) -> Result<Reply, ApiError> {
     let sig = headers
         .get("Stripe-Signature")
         .and_then(|v| v.to_str().ok())
         .ok_or_else(|| PipelineError::WebhookSignature("missing Stripe-Signature header".into()))?;
 
-    let event = stripe::Webhook::construct_event(&body, sig, &state.stripe_webhook_secret)
-        .map_err(|e| PipelineError::WebhookSignature(e.to_string()))?;
+    let secrets = webhook_setup::signing_secrets(
+        &state.settings_store,
+        &state.settings.stripe.webhook_secret,
+    )
+    .await?;
+    let secret_version = verify_signature(&body, sig, &secrets)?;
+    tracing::Span::current().record("secret_version", secret_version);
+    if secrets.first().is_some_and(|s| s.version != secret_version) {
+        tracing::info!(
+            secret_version,
+            "webhook signed with a secret being rotated out"
+        );
+    }
+
+    let delivery = signed_delivery(sig)?;
+    webhook_guard::admit(&state.pool, &state.config, &delivery).await?;
+
+    let result = accept_event(&state, &body, Delivery::Signed).await;
+    if result.is_err() {
+        webhook_guard::release(&state.pool, &delivery).await;
+    }
+    result
+}
+
+/// `POST /dev/simulate` — a canned Stripe event (the body `/webhook` would
+/// get), taken without a signature and routed as `/webhook` routes it.
+/// Payment events are applied at once from the object they carry rather
+/// than queued, so the response has the pipeline's result and no Stripe
+/// account is needed. Only mounted with `DEV_ROUTES`.
+#[tracing::instrument(
+    name = "dev_simulate",
+    skip_all,
+    fields(event_id = tracing::field::Empty, event_type = tracing::field::Empty)
+)]
+pub async fn dev_simulate_handler(
+    State(state): State<AppState>,
+    body: String,
+) -> Result<Reply, ApiError> {
+    accept_event(&state, &body, Delivery::Simulated).await
+}
 
-    let event_id = event.id.to_string();
-    let stripe_created = event.created;
-    let raw_event: serde_json::Value = serde_json::from_str(&body).map_err(PipelineError::from)?;
-    let event_type = raw_event
-        .get("type")
-        .and_then(|v| v.as_str())
-        .unwrap_or("unknown")
-        .to_string();
+/// How an event came in.
+#[derive(Clone, Copy, PartialEq)]
+enum Delivery {
+    /// From Stripe, signed: payment events are queued for the worker.
+    Signed,
+    /// From `/dev/simulate`: payment events are processed inline.
+    Simulated,
+}
+
+async fn accept_event(state: &AppState, body: &str, delivery: Delivery) -> Result<Reply, ApiError> {
+    // The signature checked out (or nobody signs simulated events), so this
+    // is Stripe's body, but not one we can read: refuse it rather than fail
+    // as if we were at fault.
+    let malformed =
+        |e: serde_json::Error| PipelineError::Validation(format!("malformed event: {e}"));
+    let raw_event: serde_json::Value = serde_json::from_str(body).map_err(malformed)?;
+    let envelope = Envelope::deserialize(&raw_event).map_err(malformed)?;
+    let event_type = envelope.event_type.as_str();
 
     tracing::Span::current()
-        .record("event_id", tracing::field::display(&event_id))
-        .record("event_type", tracing::field::display(&event_type));
-
-    let trigger = match event.data.object {
-        stripe::EventObject::PaymentIntent(ref pi) => {
-            let external_id = match ExternalId::new(pi.id.to_string()) {
-                Ok(id) => id,
-                Err(PipelineError::Validation(msg)) => {
-                    tracing::warn!(event_type = %event_type, "skipping invalid PI id: {msg}");
-                    return Ok(Json(serde_json::json!({"status": "ignored_invalid_data"})));
-                }
-                Err(e) => return Err(e.into()),
-            };
-            WebhookTrigger::Payment(PaymentTrigger {
-                event_id: EventId::new(event_id.clone())?,
-                event_type: event_type.clone(),
-                external_id,
-                raw_event,
-                provider_ts: stripe_created,
-            })
+        .record("event_id", tracing::field::display(&envelope.id))
+        .record("event_type", tracing::field::display(event_type));
+
+    // Types we don't process are logged as they came, without looking at
+    // the object inside.
+    let strategy = if state.config.current().config.accepts_event(event_type) {
+        state.stripe_routes.route(event_type)
+    } else {
+        EventStrategy::Passthrough
+    };
+    let object = &raw_event["data"]["object"];
+    let object_type = object["object"].as_str().unwrap_or_default();
+
+    let trigger = match strategy {
+        EventStrategy::Ignore => {
+            tracing::debug!("event ignored by route");
+            return Ok(reply(serde_json::json!({"status": "ignored"})));
         }
-        stripe::EventObject::Refund(ref refund) if !event_type.starts_with("charge.refund") => {
-            let external_id = match ExternalId::new(refund.id.to_string()) {
-                Ok(id) => id,
-                Err(PipelineError::Validation(msg)) => {
-                    tracing::warn!(event_type = %event_type, "skipping invalid refund id: {msg}");
-                    return Ok(Json(serde_json::json!({"status": "ignored_invalid_data"})));
+        EventStrategy::Payment if PAYMENT_OBJECTS.contains(&object_type) => {
+            match ExternalId::new(object["id"].as_str().unwrap_or_default().to_string()) {
+                Ok(external_id) => payment_trigger(envelope, external_id, raw_event)?,
+                Err(DomainError::Validation(msg)) => {
+                    tracing::warn!(
+                        event_type,
+                        payload = %redacted(&raw_event),
+                        "skipping invalid {object_type} id: {msg}"
+                    );
+                    return Ok(reply(serde_json::json!({"status": "ignored_invalid_data"})));
                 }
-                Err(e) => return Err(e.into()),
-            };
-            WebhookTrigger::Payment(PaymentTrigger {
-                event_id: EventId::new(event_id.clone())?,
-                event_type: event_type.clone(),
-                external_id,
-                raw_event,
-                provider_ts: stripe_created,
-            })
+            }
         }
-        stripe::EventObject::Charge(ref charge) => {
-            let pi_id = charge
-                .payment_intent
-                .as_ref()
-                .map(|e| match e {
-                    stripe::Expandable::Id(id) => ExternalId::new(id.to_string()),
-                    stripe::Expandable::Object(pi) => ExternalId::new(pi.id.to_string()),
-                })
+        EventStrategy::Payment => {
+            tracing::warn!(
+                object_type,
+                "payment route for a non-payment object, logging it"
+            );
+            passthrough(envelope, None, raw_event)?
+        }
+        // Checkout and Billing collect through a PaymentIntent; their payment
+        // events are processed as events of that intent. The object isn't
+        // parsed as a typed event: invoice payloads change shape across API
+        // versions.
+        EventStrategy::RelatedPayment => {
+            let payment = state
+                .providers
+                .get_scoped("stripe", event_tenant(&raw_event)?.as_ref())?
+                .payment_for_event(&raw_event)
+                .await?;
+            match payment {
+                Some(external_id) => payment_trigger(envelope, external_id, raw_event)?,
+                None => passthrough(envelope, None, raw_event)?,
+            }
+        }
+        EventStrategy::Payout => match convert_payout_event(
+            EventId::new(envelope.id.clone())?,
+            event_type,
+            envelope.created,
+            &raw_event,
+        ) {
+            Ok(new) => WebhookTrigger::Payout(Box::new(new)),
+            Err(PipelineError::Validation(msg)) => {
+                tracing::warn!(
+                    event_type,
+                    payload = %redacted(&raw_event),
+                    "skipping invalid payout: {msg}"
+                );
+                return Ok(reply(serde_json::json!({"status": "ignored_invalid_data"})));
+            }
+            Err(e) => return Err(e.into()),
+        },
+        EventStrategy::Capture => match convert_capture_event(
+            EventId::new(envelope.id.clone())?,
+            event_type,
+            envelope.created,
+            &raw_event,
+        ) {
+            Ok(new) => WebhookTrigger::Capture(Box::new(new)),
+            Err(PipelineError::Validation(msg)) => {
+                tracing::warn!(
+                    event_type,
+                    payload = %redacted(&raw_event),
+                    "skipping invalid capture: {msg}"
+                );
+                return Ok(reply(serde_json::json!({"status": "ignored_invalid_data"})));
+            }
+            Err(e) => return Err(e.into()),
+        },
+        // Charge events are logged against their PaymentIntent.
+        EventStrategy::Passthrough if object_type == "charge" => {
+            let pi_id = expandable_id(&object["payment_intent"])
+                .map(|id| ExternalId::new(id.to_string()))
                 .transpose()?;
-            WebhookTrigger::Passthrough(PassthroughEvent {
-                external_id: pi_id,
-                event_id: EventId::new(event_id.clone())?,
-                event_type: event_type.clone(),
-                provider_ts: stripe_created,
-                raw_payload: raw_event,
-                actor: "webhook:stripe".into(),
-            })
+            passthrough(envelope, pi_id, raw_event)?
         }
-        _ => WebhookTrigger::Passthrough(PassthroughEvent {
-            external_id: None,
-            event_id: EventId::new(event_id.clone())?,
-            event_type: event_type.clone(),
-            provider_ts: stripe_created,
-            raw_payload: raw_event,
-            actor: "webhook:stripe".into(),
-        }),
+        EventStrategy::Passthrough => passthrough(envelope, None, raw_event)?,
     };
+    match trigger {
+        WebhookTrigger::Payment(t) if delivery == Delivery::Simulated => {
+            simulate_payment(state, t).await
+        }
+        trigger => dispatch(state, trigger).await,
+    }
+}
 
+/// Apply a simulated payment event inline: from its own object, or, for
+/// an invoice or checkout session, from the PaymentIntent fetched behind it.
+async fn simulate_payment(state: &AppState, trigger: PaymentTrigger) -> Result<Reply, ApiError> {
+    let provider = state
+        .providers
+        .get_scoped(&trigger.source, trigger.tenant_id.as_ref())?;
+    let result = match provider.payment_from_event(&trigger.raw_event)? {
+        Some(fetched) if fetched.external_id == trigger.external_id => {
+            process_fetched_payment(&state.pool, fetched, trigger, "dev:simulate").await?
+        }
+        _ => {
+            fetch_and_process_payment(&state.pool, provider.as_ref(), trigger, "dev:simulate")
+                .await?
+        }
+    };
+    tracing::info!(
+        result = result.as_str(),
+        "simulated payment event processed"
+    );
+    Ok(processed(&result))
+}
+
+fn payment_trigger(
+    envelope: Envelope,
+    external_id: ExternalId,
+    raw_event: serde_json::Value,
+) -> Result<WebhookTrigger, PipelineError> {
+    Ok(WebhookTrigger::Payment(PaymentTrigger {
+        source: "stripe".into(),
+        tenant_id: event_tenant(&raw_event)?,
+        event_id: EventId::new(envelope.id)?,
+        event_type: envelope.event_type,
+        external_id,
+        raw_event,
+        provider_ts: envelope.created,
+    }))
+}
+
+fn passthrough(
+    envelope: Envelope,
+    external_id: Option<ExternalId>,
+    raw_event: serde_json::Value,
+) -> Result<WebhookTrigger, PipelineError> {
+    Ok(WebhookTrigger::Passthrough(PassthroughEvent {
+        external_id,
+        source: "stripe".into(),
+        tenant_id: event_tenant(&raw_event)?,
+        event_id: EventId::new(envelope.id)?,
+        event_type: envelope.event_type,
+        provider_ts: envelope.created,
+        raw_payload: raw_event,
+        actor: "webhook:stripe".into(),
+    }))
+}
+
+async fn dispatch(state: &AppState, trigger: WebhookTrigger) -> Result<Reply, ApiError> {
     match trigger {
         WebhookTrigger::Payment(t) => {
-            let inserted = job_repo::enqueue(
+            let queued = job_repo::enqueue(
                 &state.pool,
+                &t.source,
+                t.tenant_id.as_ref().map(|t| t.as_str()),
                 t.event_id.as_str(),
                 t.external_id.as_str(),
                 &t.event_type,
                 t.provider_ts,
                 &t.raw_event,
+                job_priority(&t.event_type),
             )
             .await?;
 
-            if inserted {
+            // The token is the job's id; `GET /events/{event_id}/status`
+            // reports it once processed.
+            if let Some(token) = queued {
                 tracing::info!("payment event enqueued for async processing");
-                Ok(Json(serde_json::json!({"status": "accepted"})))
+                Ok(reply(
+                    serde_json::json!({"status": "accepted", "token": token}),
+                ))
             } else {
                 tracing::info!("duplicate event, already enqueued");
-                Ok(Json(serde_json::json!({"status": "duplicate"})))
+                let token = jobs::get_event_status(
+                    &state.pool,
+                    t.event_id.as_str(),
+                    Some(t.source.as_str()),
+                    t.tenant_id.as_ref(),
+                )
+                .await?
+                .map(|s| s.token);
+                Ok(reply(
+                    serde_json::json!({"status": "duplicate", "token": token}),
+                ))
             }
         }
+        WebhookTrigger::Payout(new) => {
+            let result = payout::process_payout_event(&state.pool, &new, "webhook:stripe").await?;
+            tracing::info!(
+                external_id = %new.external_id.as_str(),
+                ?result,
+                "payout event processed"
+            );
+            Ok(processed(&result))
+        }
+        WebhookTrigger::Capture(new) => {
+            let result =
+                capture::process_capture_event(&state.pool, &new, "webhook:stripe").await?;
+            tracing::info!(
+                external_id = %new.payment_external_id.as_str(),
+                ?result,
+                "capture event processed"
+            );
+            Ok(processed(&result))
+        }
         WebhookTrigger::Passthrough(event) => {
             let is_new = handle_passthrough(&state.pool, &event).await?;
             if is_new {
-                tracing::info!(event_type = %event_type, "passthrough event logged");
-                Ok(Json(serde_json::json!({"status": "logged"})))
+                tracing::info!(event_type = %event.event_type, "passthrough event logged");
+                Ok(reply(serde_json::json!({"status": "logged"})))
             } else {
-                tracing::info!(event_id = %event_id, "duplicate event, already processed");
-                Ok(Json(serde_json::json!({"status": "duplicate"})))
+                tracing::info!(event_id = %event.event_id, "duplicate event, already processed");
+                Ok(reply(serde_json::json!({"status": "duplicate"})))
             }
         }
     }
 }
+
+/// Check a `Stripe-Signature` header against the raw body: some `v1` entry
+/// must be the HMAC-SHA256 of `<t>.<body>` under one of `secrets`, tried in
+/// turn, and the version of the one that matched is returned. During a
+/// rotation Stripe signs each delivery with its endpoint's secret, so either
+/// may match. Whether `t` is recent enough is left to
+/// [`webhook_guard::admit`], which audits the rejection; a bad signature
+/// isn't worth an audit entry.
+fn verify_signature(
+    body: &str,
+    header: &str,
+    secrets: &[SigningSecret],
+) -> Result<i64, PipelineError> {
+    let mut timestamp = None;
+    let mut signatures = Vec::new();
+    for (key, value) in header.split(',').filter_map(|kv| kv.trim().split_once('=')) {
+        match key {
+            "t" => timestamp = Some(value),
+            "v1" => signatures.extend(hex::decode(value).ok()),
+            _ => {}
+        }
+    }
+    let invalid = |msg: &str| PipelineError::WebhookSignature(msg.into());
+    let t = timestamp.ok_or_else(|| invalid("Stripe-Signature has no timestamp"))?;
+
+    for secret in secrets {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.secret.as_bytes())
+            .map_err(|_| invalid("invalid webhook secret"))?;
+        mac.update(t.as_bytes());
+        mac.update(b".");
+        mac.update(body.as_bytes());
+        if signatures
+            .iter()
+            .any(|sig| mac.clone().verify_slice(sig).is_ok())
+        {
+            return Ok(secret.version);
+        }
+    }
+    Err(invalid("no v1 signature matches the payload"))
+}
+
+/// The whole `Stripe-Signature` header (`t=<unix secs>,v1=<hex>,...`) is the
+/// replay key: a replay sends it back unchanged.
+fn signed_delivery(header: &str) -> Result<SignedDelivery, PipelineError> {
+    let signed_at = header
+        .split(',')
+        .find_map(|kv| kv.trim().strip_prefix("t="))
+        .and_then(|t| t.parse::<i64>().ok())
+        .and_then(|t| DateTime::from_timestamp(t, 0))
+        .ok_or_else(|| {
+            PipelineError::WebhookSignature("Stripe-Signature has no valid timestamp".into())
+        })?;
+    Ok(SignedDelivery {
+        source: "stripe",
+        signature: header.to_string(),
+        signed_at,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signature_header_timestamp_is_parsed() {
+        let d = signed_delivery("t=1700000000,v1=abc,v0=def").unwrap();
+        assert_eq!(d.signed_at.timestamp(), 1_700_000_000);
+        assert_eq!(d.signature, "t=1700000000,v1=abc,v0=def");
+        assert!(signed_delivery("v1=abc").is_err());
+        assert!(signed_delivery("t=soon,v1=abc").is_err());
+    }
+
+    #[test]
+    fn signature_is_checked_against_body() {
+        let sign = |t: i64, body: &str| {
+            let mut mac = Hmac::<Sha256>::new_from_slice(b"whsec_test").unwrap();
+            mac.update(format!("{t}.{body}").as_bytes());
+            format!(
+                "t={t},v1=00ff,v1={}",
+                hex::encode(mac.finalize().into_bytes())
+            )
+        };
+        let body = r#"{"id":"evt_1","type":"customer.created","created":1700000000}"#;
+        let header = sign(1_700_000_000, body);
+
+        let secrets = |list: &[&str]| -> Vec<SigningSecret> {
+            list.iter()
+                .zip(1..)
+                .map(|(secret, version)| SigningSecret {
+                    version,
+                    secret: secret.to_string(),
+                    valid_until: None,
+                })
+                .collect()
+        };
+        let test = secrets(&["whsec_test"]);
+
+        assert_eq!(verify_signature(body, &header, &test).unwrap(), 1);
+        assert!(verify_signature(&body.replace("customer", "charge"), &header, &test).is_err());
+        assert!(verify_signature(body, &header, &secrets(&["whsec_other"])).is_err());
+        assert!(verify_signature(body, "v1=00ff", &test).is_err());
+        assert!(verify_signature(body, &header, &[]).is_err());
+
+        // Mid-rotation, whichever secret signed it.
+        let both = secrets(&["whsec_new", "whsec_test"]);
+        assert_eq!(verify_signature(body, &header, &both).unwrap(), 2);
+    }
+}
@@ -1,6 +1,43 @@
+pub mod adjustment;
+pub mod alert;
 pub mod audit;
+pub mod backfill;
+pub mod budget;
+pub mod capture;
+pub mod checkout;
+pub mod config;
+pub mod currency_terms;
 pub mod error;
+pub mod event_gap;
+pub mod event_order;
+pub mod export;
+pub mod feed;
+pub mod followup;
+pub mod hook;
 pub mod id;
+pub mod ingest;
+pub mod job;
+pub mod ledger;
+pub mod lock;
+pub mod maintenance;
+pub mod manual;
 pub mod money;
+pub mod outbox;
 pub mod payment;
+pub mod payout;
+pub mod period;
 pub mod provider;
+pub mod reconciliation;
+pub mod refund;
+pub mod replay;
+pub mod report;
+pub mod retention;
+pub mod rollup;
+pub mod setting;
+pub mod shadow;
+pub mod tag;
+pub mod timeline;
+pub mod transition;
+pub mod vector;
+pub mod webhook;
+pub mod webhook_endpoint;
@@ -0,0 +1,136 @@
+use {
+    super::{
+        error::DomainError,
+        id::EventId,
+        money::{Money, MoneyAmount},
+        payment::{NewPayment, NewPaymentParams, PaymentStatus, PaymentView},
+    },
+    serde::{Deserialize, Serialize},
+    uuid::Uuid,
+};
+
+/// Event type recorded for operator corrections.
+pub const ADJUSTMENT_EVENT_TYPE: &str = "admin.transition";
+
+/// Body of `POST /admin/payments/{external_id}/transition`: move a payment
+/// to a status confirmed out of band, e.g. a failure the provider reported
+/// only to support.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TransitionRequest {
+    pub status: PaymentStatus,
+    pub reason: String,
+    /// Apply the transition even if the state machine refuses it.
+    #[serde(default)]
+    pub force: bool,
+}
+
+impl TransitionRequest {
+    /// Pipeline input moving `current` to the requested status. Everything
+    /// else about the payment (amount, metadata, failure details) is kept;
+    /// the request and operator are stored as the synthetic event's payload.
+    pub fn to_new_payment(
+        &self,
+        current: &PaymentView,
+        metadata: serde_json::Value,
+        operator: &str,
+        received_at: i64,
+    ) -> Result<NewPayment, DomainError> {
+        if self.reason.trim().is_empty() {
+            return Err(DomainError::Validation(
+                "a reason is required for a manual transition".into(),
+            ));
+        }
+        Ok(NewPayment::new(NewPaymentParams {
+            external_id: current.id.clone(),
+            source: current.source.clone(),
+            event_type: ADJUSTMENT_EVENT_TYPE.to_string(),
+            direction: current.direction.clone(),
+            money: Money::new(MoneyAmount::new(current.amount)?, current.currency),
+            status: self.status.clone(),
+            metadata,
+            raw_event: serde_json::json!({
+                "status": self.status,
+                "reason": self.reason,
+                "force": self.force,
+                "operator": operator,
+            }),
+            last_event_id: EventId::new(format!("evt_admin_{}", Uuid::now_v7().simple()))?,
+            parent_external_id: current.parent_external_id.clone(),
+            provider_ts: received_at,
+            failure: current.failure.clone(),
+            authorized_amount: None,
+            receipt: None,
+            tenant_id: current.tenant_id.clone(),
+            customer: None,
+        }))
+    }
+}
+
+/// Result of a manual transition.
+#[derive(Debug)]
+pub enum TransitionOutcome {
+    /// Moved to the new status, possibly by override.
+    Applied(PaymentView),
+    /// Already in the requested status; nothing changed.
+    Unchanged(PaymentView),
+    /// The state machine refused and `force` wasn't set. Logged as an anomaly.
+    Rejected { current: PaymentStatus },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{
+        id::ExternalId,
+        money::Currency,
+        payment::{PaymentDirection, PaymentFailure},
+    };
+
+    #[test]
+    fn transition_keeps_the_payment_and_records_the_request() {
+        let current = PaymentView {
+            id: ExternalId::new("pi_adj_1").unwrap(),
+            source: "stripe".into(),
+            tenant_id: None,
+            status: PaymentStatus::Pending,
+            amount: 4200,
+            currency: Currency::USD,
+            direction: PaymentDirection::Inbound,
+            parent_external_id: None,
+            failure: Some(PaymentFailure {
+                code: Some("card_declined".into()),
+                ..Default::default()
+            }),
+            authorized_amount: None,
+            receipt: None,
+            updated_at: chrono::Utc::now(),
+            created_at: chrono::Utc::now(),
+        };
+        let request = TransitionRequest {
+            status: PaymentStatus::Failed,
+            reason: "confirmed failed by Stripe support".into(),
+            force: false,
+        };
+        let p = request
+            .to_new_payment(&current, serde_json::json!({"order": 7}), "admin:ana", 100)
+            .unwrap();
+        assert_eq!(p.source(), "stripe");
+        assert_eq!(p.event_type(), ADJUSTMENT_EVENT_TYPE);
+        assert_eq!(p.money().amount().cents(), 4200);
+        assert_eq!(p.metadata()["order"], 7);
+        assert!(p.last_event_id().starts_with("evt_admin_"));
+        assert_eq!(p.raw_event()["operator"], "admin:ana");
+        assert_eq!(p.failure(), current.failure.as_ref());
+
+        let blank = TransitionRequest {
+            reason: "  ".into(),
+            ..request
+        };
+        assert!(
+            blank
+                .to_new_payment(&current, serde_json::json!({}), "admin:ana", 100)
+                .is_err()
+        );
+    }
+}
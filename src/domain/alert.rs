@@ -0,0 +1,334 @@
+use {
+    super::{
+        error::{DomainError, PortError},
+        payment::PaymentStatus,
+    },
+    handlebars::Handlebars,
+    serde::{Deserialize, Serialize},
+    std::{collections::BTreeMap, future::Future, pin::Pin},
+};
+
+/// Every kind of alert the service sends, for routing and test fires.
+pub const ALERT_KINDS: [&str; 3] = [
+    "payment_anomaly",
+    "provider_budget",
+    "reconciliation_summary",
+];
+
+/// A message meant for people: a subject line, a plain-text body, and the
+/// structured data behind it for sinks that can use it.
+#[derive(Debug, Clone, Serialize)]
+pub struct Alert {
+    /// What produced it, e.g. `reconciliation_summary`.
+    pub kind: &'static str,
+    pub subject: String,
+    pub text: String,
+    pub detail: serde_json::Value,
+}
+
+/// A notification channel: a chat or email webhook, or just the log.
+pub trait AlertSink: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Whether alerts of `kind` are routed here. Defaults to all of them.
+    fn accepts(&self, _kind: &str) -> bool {
+        true
+    }
+
+    fn send<'a>(
+        &'a self,
+        alert: &'a Alert,
+    ) -> Pin<Box<dyn Future<Output = Result<(), PortError>> + Send + 'a>>;
+}
+
+/// An event asked a payment for a transition the state machine refused.
+#[derive(Debug, Clone, Serialize)]
+pub struct PaymentAnomaly {
+    pub external_id: String,
+    pub source: String,
+    pub amount: i64,
+    pub currency: String,
+    pub event_id: String,
+    pub actor: String,
+    pub current_status: PaymentStatus,
+    pub incoming_status: PaymentStatus,
+    pub audit_url: String,
+}
+
+impl PaymentAnomaly {
+    pub fn to_alert(&self) -> Alert {
+        Alert {
+            kind: "payment_anomaly",
+            subject: format!(
+                "Anomaly on {}: {} -> {} refused",
+                self.external_id, self.current_status, self.incoming_status
+            ),
+            text: format!(
+                "{} payment {} ({} {}) is {}; event {} from {} asked for {}, which the state \
+                 machine doesn't allow. The event was logged and not applied.\nAudit trail: {}\n",
+                self.source,
+                self.external_id,
+                self.amount,
+                self.currency.to_uppercase(),
+                self.current_status,
+                self.event_id,
+                self.actor,
+                self.incoming_status,
+                self.audit_url
+            ),
+            detail: serde_json::json!(self),
+        }
+    }
+}
+
+/// A webhook alerts are posted to besides the log, as declared in
+/// `ALERT_DESTINATIONS_FILE`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AlertDestination {
+    pub name: String,
+    pub url: String,
+    /// Alert kinds sent here; empty means all of them.
+    #[serde(default)]
+    pub kinds: Vec<String>,
+    #[serde(default)]
+    pub format: AlertFormat,
+    /// Values templates can use as `{{vars.<name>}}`, e.g. a routing key.
+    #[serde(default)]
+    pub vars: BTreeMap<String, String>,
+    /// Extra request headers, e.g. an `Authorization` the tool wants.
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+}
+
+/// The body posted for an alert.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertFormat {
+    /// The alert itself as JSON: `kind`, `subject`, `text`, `detail`.
+    #[default]
+    Raw,
+    /// A Slack incoming-webhook message.
+    Slack,
+    /// A PagerDuty Events API v2 trigger; needs `vars.routing_key`, and
+    /// takes `vars.severity` (default `warning`).
+    Pagerduty,
+    /// Any JSON document. Its strings are Handlebars templates over `kind`,
+    /// `subject`, `text`, `detail` and `vars`; a string that is a single
+    /// `{{path}}` is replaced by the value itself, objects included.
+    Template(serde_json::Value),
+}
+
+impl AlertFormat {
+    fn template(&self) -> Option<serde_json::Value> {
+        match self {
+            Self::Raw => None,
+            Self::Slack => Some(serde_json::json!({"text": "*{{subject}}*\n{{text}}"})),
+            Self::Pagerduty => Some(serde_json::json!({
+                "routing_key": "{{vars.routing_key}}",
+                "event_action": "trigger",
+                "dedup_key": "{{kind}}:{{subject}}",
+                "payload": {
+                    "summary": "{{subject}}",
+                    "source": "fin_sync",
+                    "severity": "{{#if vars.severity}}{{vars.severity}}{{else}}warning{{/if}}",
+                    "component": "{{kind}}",
+                    "custom_details": "{{detail}}",
+                },
+            })),
+            Self::Template(template) => Some(template.clone()),
+        }
+    }
+}
+
+impl AlertDestination {
+    pub fn accepts(&self, kind: &str) -> bool {
+        self.kinds.is_empty() || self.kinds.iter().any(|k| k == kind)
+    }
+
+    /// Reject what would only fail at send time: unknown kinds, templates
+    /// that don't compile, a PagerDuty format without a routing key.
+    pub fn validate(&self) -> Result<(), DomainError> {
+        let invalid = |msg: String| DomainError::Validation(format!("{}: {msg}", self.name));
+        if self.name.trim().is_empty() {
+            return Err(DomainError::Validation(
+                "alert destination without a name".into(),
+            ));
+        }
+        if !self.url.starts_with("http://") && !self.url.starts_with("https://") {
+            return Err(invalid(format!("url must be http(s): {}", self.url)));
+        }
+        if let Some(kind) = self
+            .kinds
+            .iter()
+            .find(|k| !ALERT_KINDS.contains(&k.as_str()))
+        {
+            return Err(invalid(format!("unknown alert kind: {kind}")));
+        }
+        if matches!(self.format, AlertFormat::Pagerduty) && !self.vars.contains_key("routing_key") {
+            return Err(invalid("pagerduty format needs vars.routing_key".into()));
+        }
+        if let Some(template) = self.format.template() {
+            let mut invalid_template = None;
+            visit_strings(&template, &mut |s| {
+                if let Err(e) = handlebars::Template::compile(s) {
+                    invalid_template.get_or_insert(e.to_string());
+                }
+            });
+            if let Some(e) = invalid_template {
+                return Err(invalid(format!("invalid template: {e}")));
+            }
+        }
+        Ok(())
+    }
+
+    /// The body to post for `alert`.
+    pub fn render(&self, alert: &Alert) -> Result<serde_json::Value, DomainError> {
+        let Some(template) = self.format.template() else {
+            return Ok(serde_json::json!(alert));
+        };
+        let context = serde_json::json!({
+            "kind": alert.kind,
+            "subject": alert.subject,
+            "text": alert.text,
+            "detail": alert.detail,
+            "vars": self.vars,
+        });
+        let mut handlebars = Handlebars::new();
+        handlebars.register_escape_fn(handlebars::no_escape);
+        render_value(&handlebars, &template, &context)
+    }
+}
+
+fn render_value(
+    handlebars: &Handlebars<'_>,
+    template: &serde_json::Value,
+    context: &serde_json::Value,
+) -> Result<serde_json::Value, DomainError> {
+    use serde_json::Value;
+    Ok(match template {
+        Value::String(s) => match whole_value_path(s) {
+            Some(path) => path
+                .split('.')
+                .try_fold(context, |v, key| v.get(key))
+                .cloned()
+                .unwrap_or(Value::Null),
+            None => Value::String(
+                handlebars
+                    .render_template(s, context)
+                    .map_err(|e| DomainError::Validation(format!("alert template: {e}")))?,
+            ),
+        },
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|v| render_value(handlebars, v, context))
+                .collect::<Result<_, _>>()?,
+        ),
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(k, v)| Ok((k.clone(), render_value(handlebars, v, context)?)))
+                .collect::<Result<_, DomainError>>()?,
+        ),
+        other => other.clone(),
+    })
+}
+
+/// `detail.external_id` for a template string that is exactly
+/// `{{detail.external_id}}`.
+fn whole_value_path(s: &str) -> Option<&str> {
+    let path = s.strip_prefix("{{")?.strip_suffix("}}")?.trim();
+    path.chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+        .then_some(path)
+        .filter(|p| !p.is_empty())
+}
+
+fn visit_strings(value: &serde_json::Value, f: &mut impl FnMut(&str)) {
+    match value {
+        serde_json::Value::String(s) => f(s),
+        serde_json::Value::Array(items) => items.iter().for_each(|v| visit_strings(v, f)),
+        serde_json::Value::Object(fields) => fields.values().for_each(|v| visit_strings(v, f)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn anomaly() -> Alert {
+        PaymentAnomaly {
+            external_id: "pi_1".into(),
+            source: "stripe".into(),
+            amount: 5000,
+            currency: "usd".into(),
+            event_id: "evt_1".into(),
+            actor: "worker".into(),
+            current_status: PaymentStatus::Succeeded,
+            incoming_status: PaymentStatus::Pending,
+            audit_url: "https://fin-sync.example.com/payments/pi_1/audit".into(),
+        }
+        .to_alert()
+    }
+
+    fn destination(format: serde_json::Value) -> AlertDestination {
+        serde_json::from_value(serde_json::json!({
+            "name": "ops",
+            "url": "https://alerts.example.com/hook",
+            "kinds": ["payment_anomaly"],
+            "format": format,
+            "vars": {"routing_key": "R0UT1NG"},
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn each_destination_gets_its_own_shape() {
+        let alert = anomaly();
+
+        let slack = destination("slack".into()).render(&alert).unwrap();
+        assert_eq!(
+            slack["text"],
+            "*Anomaly on pi_1: succeeded -> pending refused*\n".to_string() + &alert.text
+        );
+
+        let pagerduty = destination("pagerduty".into()).render(&alert).unwrap();
+        assert_eq!(pagerduty["routing_key"], "R0UT1NG");
+        assert_eq!(pagerduty["payload"]["severity"], "warning");
+        // Whole values keep their JSON type.
+        assert_eq!(pagerduty["payload"]["custom_details"]["amount"], 5000);
+
+        let custom = destination(serde_json::json!({"template": {
+            "title": "{{detail.external_id}} <{{detail.incoming_status}}>",
+            "tags": ["fin_sync", "{{kind}}"],
+        }}))
+        .render(&alert)
+        .unwrap();
+        assert_eq!(custom["title"], "pi_1 <pending>");
+        assert_eq!(custom["tags"][1], "payment_anomaly");
+
+        let raw = destination("raw".into()).render(&alert).unwrap();
+        assert_eq!(raw["subject"], alert.subject);
+    }
+
+    #[test]
+    fn destinations_are_checked_up_front() {
+        assert!(destination("slack".into()).validate().is_ok());
+        assert!(destination("slack".into()).accepts("payment_anomaly"));
+        assert!(!destination("slack".into()).accepts("reconciliation_summary"));
+
+        let mut d = destination(serde_json::json!({"template": {"text": "{{#if kind}}"}}));
+        assert!(d.validate().is_err());
+        d = destination("pagerduty".into());
+        d.vars.clear();
+        assert!(d.validate().is_err());
+        d = destination("slack".into());
+        d.kinds = vec!["payment_anomalies".into()];
+        assert!(d.validate().is_err());
+        d = destination("slack".into());
+        d.url = "alerts.example.com".into();
+        assert!(d.validate().is_err());
+    }
+}
@@ -1,4 +1,9 @@
-use uuid::Uuid;
+use {
+    super::error::DomainError,
+    serde::{Deserialize, Serialize},
+    std::fmt,
+    uuid::Uuid,
+};
 
 pub struct NewAuditEntry {
     pub id: Uuid,
@@ -6,7 +11,193 @@ pub struct NewAuditEntry {
     pub entity_id: Option<Uuid>,
     pub external_id: Option<String>,
     pub event_id: String,
+    /// Provider the event came from; `None` for entries we generate
+    /// ourselves. Entries are unique per `(source, tenant_id, event_id)`.
+    pub source: Option<String>,
+    /// Connected account the event was sent for; see `TenantId`.
+    pub tenant_id: Option<String>,
     pub action: String,
     pub actor: String,
     pub detail: serde_json::Value,
 }
+
+/// A committed audit_log row, as shipped to the separate audit database
+/// and streamed to audit tails.
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditRecord {
+    pub id: Uuid,
+    pub entity_type: String,
+    pub entity_id: Option<Uuid>,
+    pub external_id: Option<String>,
+    pub event_id: String,
+    pub source: Option<String>,
+    pub tenant_id: Option<String>,
+    pub action: String,
+    pub actor: String,
+    pub detail: serde_json::Value,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// One entry in a payment's audit trail, as returned by the API.
+#[derive(Debug, Serialize)]
+pub struct AuditEntryView {
+    pub event_id: String,
+    pub action: String,
+    pub actor: String,
+    pub detail: serde_json::Value,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct AuditFilters {
+    pub action: Option<String>,
+    pub limit: Option<u64>,
+    pub offset: Option<i64>,
+}
+
+/// Filters for the audit log as a whole, newest first.
+#[derive(Debug, Default)]
+pub struct AuditLogFilters {
+    pub external_id: Option<String>,
+    pub source: Option<String>,
+    pub action: Option<String>,
+    /// Matched as a prefix, as for tails.
+    pub actor: Option<String>,
+    /// Only events the state machine refused.
+    pub anomalies_only: bool,
+    pub limit: Option<u64>,
+    pub cursor: Option<AuditCursor>,
+}
+
+/// Keyset position in the audit log listing, which is ordered by
+/// `(created_at, id)` descending. Serialized as `<micros>:<id>`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AuditCursor {
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub id: Uuid,
+}
+
+impl AuditCursor {
+    /// Cursor pointing just past `last`, the final entry of a page.
+    pub fn after(last: &AuditRecord) -> Self {
+        Self {
+            created_at: last.created_at,
+            id: last.id,
+        }
+    }
+}
+
+impl fmt::Display for AuditCursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.created_at.timestamp_micros(), self.id)
+    }
+}
+
+impl TryFrom<String> for AuditCursor {
+    type Error = DomainError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        let invalid = || DomainError::Validation(format!("invalid audit cursor: {s}"));
+        let (micros, id) = s.split_once(':').ok_or_else(invalid)?;
+        let created_at = micros
+            .parse::<i64>()
+            .ok()
+            .and_then(chrono::DateTime::from_timestamp_micros)
+            .ok_or_else(invalid)?;
+        let id = id.parse().map_err(|_| invalid())?;
+        Ok(Self { created_at, id })
+    }
+}
+
+/// One page of the audit log. `next_cursor` is `None` on the last page.
+#[derive(Debug)]
+pub struct AuditPage {
+    pub entries: Vec<AuditRecord>,
+    pub next_cursor: Option<AuditCursor>,
+}
+
+/// Filters for `GET /admin/audit/tail`. `actor` matches as a prefix, so
+/// `admin:alice` follows everything Alice does with any key.
+#[derive(Debug, Default, Deserialize)]
+pub struct AuditTailFilter {
+    pub external_id: Option<String>,
+    pub action: Option<String>,
+    pub actor: Option<String>,
+}
+
+/// What the `audit_log` notification says about a new entry.
+#[derive(Debug, Deserialize)]
+pub struct AuditNotice {
+    pub id: Uuid,
+    pub external_id: Option<String>,
+    pub action: String,
+    pub actor: String,
+}
+
+impl AuditTailFilter {
+    pub fn matches(&self, notice: &AuditNotice) -> bool {
+        self.external_id
+            .as_ref()
+            .is_none_or(|id| notice.external_id.as_ref() == Some(id))
+            && self.action.as_ref().is_none_or(|a| &notice.action == a)
+            && self
+                .actor
+                .as_ref()
+                .is_none_or(|a| notice.actor.starts_with(a.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tail_filters_combine() {
+        let notice = AuditNotice {
+            id: Uuid::now_v7(),
+            external_id: Some("pi_1".into()),
+            action: "status_changed".into(),
+            actor: "admin:alice (key ops)".into(),
+        };
+        assert!(AuditTailFilter::default().matches(&notice));
+        let filter = AuditTailFilter {
+            external_id: Some("pi_1".into()),
+            action: Some("status_changed".into()),
+            actor: Some("admin:alice".into()),
+        };
+        assert!(filter.matches(&notice));
+        let other_payment = AuditTailFilter {
+            external_id: Some("pi_2".into()),
+            ..Default::default()
+        };
+        assert!(!other_payment.matches(&notice));
+        let other_actor = AuditTailFilter {
+            actor: Some("worker".into()),
+            ..Default::default()
+        };
+        assert!(!other_actor.matches(&notice));
+    }
+
+    #[test]
+    fn audit_cursor_roundtrips_and_rejects_garbage() {
+        let cursor = AuditCursor {
+            created_at: chrono::DateTime::from_timestamp_micros(1_773_000_000_123_456).unwrap(),
+            id: Uuid::nil(),
+        };
+        let encoded = cursor.to_string();
+        assert_eq!(
+            encoded,
+            "1773000000123456:00000000-0000-0000-0000-000000000000"
+        );
+        assert_eq!(AuditCursor::try_from(encoded).unwrap(), cursor);
+
+        for bad in [
+            "",
+            "123",
+            "x:00000000-0000-0000-0000-000000000000",
+            "123:pi_1",
+        ] {
+            assert!(AuditCursor::try_from(bad.to_string()).is_err());
+        }
+    }
+}
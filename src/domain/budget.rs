@@ -0,0 +1,308 @@
+use {
+    super::{alert::Alert, error::DomainError},
+    chrono::{DateTime, DurationRound, TimeDelta, Utc},
+    serde::{Deserialize, Serialize},
+    std::str::FromStr,
+};
+
+/// A kind of provider API call, as budgets count them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiOperation {
+    /// One object by id, e.g. for a job or a batch of them.
+    Fetch,
+    /// One page of a listing: backfills and reconciliation.
+    List,
+    /// Finding the payment behind a related object (an invoice). Counted
+    /// on every call, including the ones the event answers on its own.
+    Lookup,
+}
+
+impl ApiOperation {
+    pub const ALL: [Self; 3] = [Self::Fetch, Self::List, Self::Lookup];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Fetch => "fetch",
+            Self::List => "list",
+            Self::Lookup => "lookup",
+        }
+    }
+}
+
+impl FromStr for ApiOperation {
+    type Err = DomainError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fetch" => Ok(Self::Fetch),
+            "list" => Ok(Self::List),
+            "lookup" => Ok(Self::Lookup),
+            other => Err(DomainError::Validation(format!(
+                "expected fetch, list or lookup, got: {other}"
+            ))),
+        }
+    }
+}
+
+/// The window a budget is counted over: the current UTC hour or day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BudgetPeriod {
+    Hour,
+    Day,
+}
+
+impl BudgetPeriod {
+    pub const ALL: [Self; 2] = [Self::Hour, Self::Day];
+
+    /// Also the `date_trunc` field the window starts at.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Hour => "hour",
+            Self::Day => "day",
+        }
+    }
+
+    /// Start of the window `at` falls in.
+    pub fn start(self, at: DateTime<Utc>) -> DateTime<Utc> {
+        at.duration_trunc(self.length()).unwrap_or(at)
+    }
+
+    /// End of the window `at` falls in: the start of the next one.
+    pub fn end(self, at: DateTime<Utc>) -> DateTime<Utc> {
+        self.start(at) + self.length()
+    }
+
+    fn length(self) -> TimeDelta {
+        match self {
+            Self::Hour => TimeDelta::hours(1),
+            Self::Day => TimeDelta::days(1),
+        }
+    }
+}
+
+impl FromStr for BudgetPeriod {
+    type Err = DomainError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hour" => Ok(Self::Hour),
+            "day" => Ok(Self::Day),
+            other => Err(DomainError::Validation(format!(
+                "expected hour or day, got: {other}"
+            ))),
+        }
+    }
+}
+
+/// Limits on calls of one operation to one provider per period. Past the
+/// soft limit an alert goes out, once per window; at the hard limit further
+/// calls are refused until the next window.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProviderBudget {
+    /// Provider `source`, e.g. `stripe`.
+    pub source: String,
+    pub operation: ApiOperation,
+    pub period: BudgetPeriod,
+    #[serde(default)]
+    pub soft_limit: Option<u64>,
+    #[serde(default)]
+    pub hard_limit: Option<u64>,
+}
+
+impl ProviderBudget {
+    pub fn validate(&self) -> Result<(), DomainError> {
+        let invalid = |msg: &str| {
+            DomainError::Validation(format!(
+                "provider budget {}/{}/{}: {msg}",
+                self.source,
+                self.operation.as_str(),
+                self.period.as_str()
+            ))
+        };
+        if self.source.trim().is_empty() {
+            return Err(DomainError::Validation(
+                "provider budget without a source".into(),
+            ));
+        }
+        match (self.soft_limit, self.hard_limit) {
+            (None, None) => Err(invalid("needs a soft_limit or a hard_limit")),
+            (Some(0), _) | (_, Some(0)) => Err(invalid("limits must be at least 1")),
+            (Some(soft), Some(hard)) if soft >= hard => {
+                Err(invalid("soft_limit must be below hard_limit"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn applies_to(&self, source: &str, operation: ApiOperation, period: BudgetPeriod) -> bool {
+        self.source == source && self.operation == operation && self.period == period
+    }
+}
+
+/// Reject budgets that don't make sense, or two for the same calls.
+pub fn validate_budgets(budgets: &[ProviderBudget]) -> Result<(), DomainError> {
+    for (i, budget) in budgets.iter().enumerate() {
+        budget.validate()?;
+        if budgets[..i]
+            .iter()
+            .any(|b| b.applies_to(&budget.source, budget.operation, budget.period))
+        {
+            return Err(DomainError::Validation(format!(
+                "provider budget {}/{}/{} is set twice",
+                budget.source,
+                budget.operation.as_str(),
+                budget.period.as_str()
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Calls counted in one window, with the budget that applies.
+#[derive(Debug, Clone, Serialize)]
+pub struct BudgetUsage {
+    pub source: String,
+    pub operation: ApiOperation,
+    pub period: BudgetPeriod,
+    pub period_start: DateTime<Utc>,
+    pub calls: i64,
+    /// Calls refused at the hard limit.
+    pub refused: i64,
+    pub soft_limit: Option<u64>,
+    pub hard_limit: Option<u64>,
+    /// Calls left before the hard limit, if there is one.
+    pub remaining: Option<i64>,
+}
+
+impl BudgetUsage {
+    pub fn with_budget(mut self, budget: Option<&ProviderBudget>) -> Self {
+        self.soft_limit = budget.and_then(|b| b.soft_limit);
+        self.hard_limit = budget.and_then(|b| b.hard_limit);
+        self.remaining = self
+            .hard_limit
+            .map(|hard| (hard as i64 - self.calls).max(0));
+        self
+    }
+}
+
+/// Which limit a budget alert is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BudgetLevel {
+    /// Approaching the hard limit, or past a budget with no hard limit.
+    Soft,
+    /// Calls are being refused.
+    Hard,
+}
+
+impl BudgetLevel {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Soft => "soft",
+            Self::Hard => "hard",
+        }
+    }
+}
+
+/// A provider's API calls crossed a budget limit in the current window.
+#[derive(Debug, Clone, Serialize)]
+pub struct BudgetAlert {
+    pub source: String,
+    pub operation: ApiOperation,
+    pub period: BudgetPeriod,
+    pub period_start: DateTime<Utc>,
+    pub level: BudgetLevel,
+    pub calls: i64,
+    pub limit: u64,
+    pub hard_limit: Option<u64>,
+    pub usage_url: String,
+}
+
+impl BudgetAlert {
+    pub fn to_alert(&self) -> Alert {
+        let (operation, period) = (self.operation.as_str(), self.period.as_str());
+        let (subject, consequence) = match self.level {
+            BudgetLevel::Soft => (
+                format!(
+                    "{} {operation} calls past the soft limit this {period}",
+                    self.source
+                ),
+                match self.hard_limit {
+                    Some(hard) => format!("Calls are refused at {hard}."),
+                    None => "There is no hard limit.".to_string(),
+                },
+            ),
+            BudgetLevel::Hard => (
+                format!(
+                    "{} {operation} calls refused: hard limit reached this {period}",
+                    self.source
+                ),
+                "Further calls fail until the window ends; jobs are retried.".to_string(),
+            ),
+        };
+        Alert {
+            kind: "provider_budget",
+            subject,
+            text: format!(
+                "{} {operation} calls to {} since {}, against a {} limit of {}. {consequence}\n\
+                 Usage: {}\n",
+                self.calls,
+                self.source,
+                self.period_start.to_rfc3339(),
+                self.level.as_str(),
+                self.limit,
+                self.usage_url
+            ),
+            detail: serde_json::json!(self),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn budget(soft: Option<u64>, hard: Option<u64>) -> ProviderBudget {
+        ProviderBudget {
+            source: "stripe".into(),
+            operation: ApiOperation::Fetch,
+            period: BudgetPeriod::Hour,
+            soft_limit: soft,
+            hard_limit: hard,
+        }
+    }
+
+    #[test]
+    fn limits_must_be_ordered_and_unique() {
+        validate_budgets(&[budget(Some(80), Some(100)), budget(None, Some(5))]).unwrap_err();
+        validate_budgets(&[budget(Some(80), Some(100))]).unwrap();
+        validate_budgets(&[budget(Some(80), None)]).unwrap();
+        assert!(budget(None, None).validate().is_err());
+        assert!(budget(Some(100), Some(100)).validate().is_err());
+        assert!(budget(None, Some(0)).validate().is_err());
+    }
+
+    #[test]
+    fn remaining_is_what_is_left_under_the_hard_limit() {
+        let usage = BudgetUsage {
+            source: "stripe".into(),
+            operation: ApiOperation::Fetch,
+            period: BudgetPeriod::Hour,
+            period_start: Utc::now(),
+            calls: 120,
+            refused: 20,
+            soft_limit: None,
+            hard_limit: None,
+            remaining: None,
+        };
+        let limited = usage
+            .clone()
+            .with_budget(Some(&budget(Some(80), Some(100))));
+        assert_eq!(limited.remaining, Some(0));
+        assert_eq!(limited.soft_limit, Some(80));
+        assert_eq!(usage.with_budget(None).remaining, None);
+    }
+}
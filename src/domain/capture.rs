@@ -0,0 +1,180 @@
+use {
+    super::{
+        audit::NewAuditEntry,
+        error::DomainError,
+        id::{EventId, ExternalId, TenantId},
+        money::{Currency, Money, MoneyAmount},
+    },
+    chrono::{DateTime, Utc},
+    serde::Serialize,
+    uuid::Uuid,
+};
+
+/// One capture of an authorized payment, as the provider's capture event
+/// describes it. A multicapture authorization is captured in several
+/// parts, each recorded on its own against the payment, as refunds are.
+#[derive(Debug, Clone)]
+pub struct NewCapture {
+    /// The payment (PaymentIntent) captured.
+    pub payment_external_id: ExternalId,
+    pub source: String,
+    /// The charge the money was captured on.
+    pub charge_id: String,
+    pub currency: Currency,
+    /// Captured on the charge so far, this capture included. What the
+    /// capture itself took is the rise over the highest total recorded
+    /// for the charge before it.
+    pub captured_total: MoneyAmount,
+    /// What the authorization held before anything was captured.
+    pub authorized: MoneyAmount,
+    pub event_id: EventId,
+    pub event_type: String,
+    pub provider_ts: i64,
+    pub raw_event: serde_json::Value,
+    /// The connected account the capture happened on, if any.
+    pub tenant_id: Option<TenantId>,
+}
+
+impl NewCapture {
+    /// What this capture took, given the charge's highest captured total
+    /// already recorded. `None` when that total already covers this one:
+    /// the event arrived after a later capture of the same charge.
+    pub fn amount_after(&self, recorded: i64) -> Option<Money> {
+        let rise = self.captured_total.cents() - recorded;
+        let amount = MoneyAmount::new(rise).ok().filter(|_| rise > 0)?;
+        Some(Money::new(amount, self.currency))
+    }
+
+    /// Audit entry on the payment for a capture of `amount` (zero for one
+    /// already covered), with its capture totals after this one.
+    pub fn audit_entry(
+        &self,
+        payment_id: Option<Uuid>,
+        actor: &str,
+        amount: i64,
+        summary: &CaptureSummary,
+    ) -> NewAuditEntry {
+        let mut detail = serde_json::json!({
+            "event_type": self.event_type,
+            "charge_id": self.charge_id,
+            "amount": amount,
+            "captured_total": self.captured_total.cents(),
+            "currency": self.currency.as_str(),
+            "captured": summary.captured,
+            "authorized": summary.authorized,
+        });
+        if summary.over_captured {
+            detail["anomaly"] = true.into();
+        }
+        NewAuditEntry {
+            id: Uuid::now_v7(),
+            entity_type: "payment".to_string(),
+            entity_id: payment_id,
+            external_id: Some(self.payment_external_id.as_str().to_string()),
+            event_id: self.event_id.as_str().to_string(),
+            source: Some(self.source.clone()),
+            tenant_id: self.tenant_id.as_ref().map(|t| t.to_string()),
+            action: "captured".to_string(),
+            actor: actor.to_string(),
+            detail,
+        }
+    }
+}
+
+/// One `payment_captures` row, as returned by the API.
+#[derive(Debug, Serialize)]
+pub struct CaptureView {
+    pub charge_id: String,
+    pub amount: i64,
+    pub currency: String,
+    pub authorized_amount: i64,
+    pub event_id: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Captures of a payment against its authorization. `capturable` is what
+/// the authorization still holds; `over_captured` means the captures add
+/// up to more than was authorized.
+#[derive(Debug, Serialize)]
+pub struct CaptureSummary {
+    pub currency: Currency,
+    pub authorized: i64,
+    pub captured: i64,
+    pub capturable: i64,
+    pub over_captured: bool,
+    /// Oldest first.
+    pub captures: Vec<CaptureView>,
+}
+
+impl CaptureSummary {
+    /// Totals `captures`, oldest first, against the authorization the
+    /// latest one reports. `None` without captures; captures in another
+    /// currency than the latest are rejected.
+    pub fn new(captures: Vec<CaptureView>) -> Result<Option<Self>, DomainError> {
+        let Some(latest) = captures.last() else {
+            return Ok(None);
+        };
+        let currency = Currency::try_from(latest.currency.as_str())?;
+        let authorized = Money::new(MoneyAmount::new(latest.authorized_amount)?, currency);
+        let mut captured = Money::zero(currency);
+        for capture in &captures {
+            let amount = Money::new(
+                MoneyAmount::new(capture.amount)?,
+                Currency::try_from(capture.currency.as_str())?,
+            );
+            captured = captured.try_add(&amount)?;
+        }
+        let over_captured = captured.try_cmp(&authorized)?.is_gt();
+        let capturable = if over_captured {
+            Money::zero(currency)
+        } else {
+            authorized.try_sub(&captured)?
+        };
+        Ok(Some(Self {
+            currency,
+            authorized: authorized.amount().cents(),
+            captured: captured.amount().cents(),
+            capturable: capturable.amount().cents(),
+            over_captured,
+            captures,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn capture(amount: i64, currency: &str) -> CaptureView {
+        CaptureView {
+            charge_id: "ch_1".into(),
+            amount,
+            currency: currency.into(),
+            authorized_amount: 1000,
+            event_id: format!("evt_{amount}"),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn captures_accumulate_against_the_authorization() {
+        assert!(CaptureSummary::new(Vec::new()).unwrap().is_none());
+
+        let summary = CaptureSummary::new(vec![capture(300, "usd"), capture(500, "usd")])
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            (summary.authorized, summary.captured, summary.capturable),
+            (1000, 800, 200)
+        );
+        assert!(!summary.over_captured);
+
+        let summary = CaptureSummary::new(vec![capture(600, "usd"), capture(500, "usd")])
+            .unwrap()
+            .unwrap();
+        assert_eq!((summary.captured, summary.capturable), (1100, 0));
+        assert!(summary.over_captured);
+
+        assert!(CaptureSummary::new(vec![capture(300, "eur"), capture(500, "usd")]).is_err());
+    }
+}
@@ -0,0 +1,320 @@
+use {
+    super::{
+        error::DomainError,
+        id::TenantId,
+        payment::{NewPayment, PaymentDirection, PaymentFailure, PaymentStatus},
+    },
+    chrono::{DateTime, TimeDelta, Utc},
+    serde::{Deserialize, Serialize},
+    uuid::Uuid,
+};
+
+/// Metadata keys naming the merchant's checkout, first found wins. Payments
+/// carrying the same one are attempts at the same checkout, whenever made.
+pub const REFERENCE_KEYS: [&str; 3] = ["checkout_id", "order_id", "invoice_id"];
+
+/// How long after a failed attempt the same customer paying the same amount
+/// counts as retrying it rather than buying again.
+pub const RETRY_WINDOW: TimeDelta = TimeDelta::hours(1);
+
+/// How an attempt joined a checkout begun by an earlier one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkedBy {
+    /// Carries the same merchant reference.
+    Reference,
+    /// Same customer, amount and currency, within [`RETRY_WINDOW`] of an
+    /// attempt that failed.
+    Customer,
+}
+
+impl LinkedBy {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Reference => "reference",
+            Self::Customer => "customer",
+        }
+    }
+}
+
+impl TryFrom<&str> for LinkedBy {
+    type Error = DomainError;
+
+    fn try_from(s: &str) -> Result<Self, DomainError> {
+        match s {
+            "reference" => Ok(Self::Reference),
+            "customer" => Ok(Self::Customer),
+            other => Err(DomainError::Validation(format!(
+                "unknown linked_by: {other}"
+            ))),
+        }
+    }
+}
+
+/// What a new inbound payment is matched to earlier attempts by.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttemptSignature {
+    pub source: String,
+    pub tenant_id: Option<TenantId>,
+    pub customer: Option<String>,
+    pub reference: Option<String>,
+    pub attempted_at: DateTime<Utc>,
+}
+
+impl AttemptSignature {
+    /// `None` for anything but an inbound payment: refunds and disputes
+    /// aren't attempts at anything.
+    pub fn of(payment: &NewPayment) -> Option<Self> {
+        if *payment.direction() != PaymentDirection::Inbound || payment.status().is_dispute() {
+            return None;
+        }
+        let text = |key: &str| {
+            payment.metadata()[key]
+                .as_str()
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+        };
+        Some(Self {
+            source: payment.source().to_string(),
+            tenant_id: payment.tenant_id().cloned(),
+            customer: payment
+                .customer()
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(str::to_string),
+            reference: REFERENCE_KEYS.iter().find_map(|key| text(key)),
+            attempted_at: DateTime::from_timestamp(payment.provider_ts(), 0)
+                .unwrap_or_else(Utc::now),
+        })
+    }
+
+    /// The advisory lock attempts sharing a reference, or else a customer,
+    /// take so two can't both become the next. `None` when neither is known:
+    /// such an attempt starts a checkout of its own.
+    pub fn lock_key(&self) -> Option<String> {
+        let tenant = self.tenant_id.as_ref().map_or("", |t| t.as_str());
+        match (&self.reference, &self.customer) {
+            (Some(reference), _) => Some(format!("checkout:{tenant}:reference:{reference}")),
+            (None, Some(customer)) => Some(format!(
+                "checkout:{tenant}:customer:{}:{customer}",
+                self.source
+            )),
+            (None, None) => None,
+        }
+    }
+}
+
+/// A checkout's latest attempt so far, which a new one would follow.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PriorAttempt {
+    pub checkout_key: String,
+    pub attempt: i32,
+}
+
+/// A failed attempt, by a customer and without a reference, that ends its
+/// checkout so far. A retry made within [`RETRY_WINDOW`] after it follows it
+/// even when recorded first: the retry's checkout is then folded into this
+/// one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailedAttempt {
+    pub checkout_key: String,
+    pub attempt: i32,
+    pub attempted_at: DateTime<Utc>,
+}
+
+/// One `checkout_attempts` row to insert.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewCheckoutAttempt {
+    pub payment_id: Uuid,
+    pub tenant_id: Option<TenantId>,
+    pub checkout_key: String,
+    pub attempt: i32,
+    pub source: String,
+    pub customer: Option<String>,
+    pub reference: Option<String>,
+    pub linked_by: Option<LinkedBy>,
+    pub attempted_at: DateTime<Utc>,
+}
+
+impl NewCheckoutAttempt {
+    /// Place `payment` in a checkout. A reference names the checkout even
+    /// for its first attempt; without one, the payment follows `prior`
+    /// (the checkout of the customer's failed attempt it retries) or
+    /// starts a checkout named after itself.
+    pub fn new(
+        payment: &NewPayment,
+        signature: AttemptSignature,
+        prior: Option<PriorAttempt>,
+    ) -> Self {
+        let (checkout_key, attempt, linked_by) = match (&signature.reference, prior) {
+            (Some(reference), prior) => (
+                reference.clone(),
+                prior.as_ref().map_or(1, |p| p.attempt + 1),
+                prior.map(|_| LinkedBy::Reference),
+            ),
+            (None, Some(prior)) => (
+                prior.checkout_key,
+                prior.attempt + 1,
+                Some(LinkedBy::Customer),
+            ),
+            (None, None) => (payment.external_id().to_string(), 1, None),
+        };
+        Self {
+            payment_id: payment.id(),
+            tenant_id: signature.tenant_id,
+            checkout_key,
+            attempt,
+            source: signature.source,
+            customer: signature.customer,
+            reference: signature.reference,
+            linked_by,
+            attempted_at: signature.attempted_at,
+        }
+    }
+}
+
+/// Query of `GET /checkouts/{key}/attempts`.
+#[derive(Debug, Default, Deserialize)]
+pub struct CheckoutQuery {
+    /// Connected account; forced to the key's own for tenant keys.
+    pub tenant_id: Option<String>,
+}
+
+/// One attempt as read back, with its payment's current status and why it
+/// failed, if it did.
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckoutAttempt {
+    pub attempt: i32,
+    pub external_id: String,
+    pub source: String,
+    pub status: PaymentStatus,
+    pub amount: i64,
+    pub currency: String,
+    pub linked_by: Option<LinkedBy>,
+    pub attempted_at: DateTime<Utc>,
+    pub failure: Option<PaymentFailure>,
+}
+
+/// A checkout and every attempt at it, in order.
+#[derive(Debug, Clone, Serialize)]
+pub struct Checkout {
+    pub key: String,
+    pub attempts: Vec<CheckoutAttempt>,
+    /// The attempt that succeeded, if one has.
+    pub converted_on: Option<i32>,
+    /// Attempts that failed, were cancelled or expired before it.
+    pub failed_attempts: usize,
+}
+
+impl Checkout {
+    /// `None` for no attempts, i.e. no such checkout.
+    pub fn new(key: String, attempts: Vec<CheckoutAttempt>) -> Option<Self> {
+        if attempts.is_empty() {
+            return None;
+        }
+        let converted_on = attempts
+            .iter()
+            .find(|a| a.status == PaymentStatus::Succeeded)
+            .map(|a| a.attempt);
+        let failed_attempts = attempts
+            .iter()
+            .filter(|a| converted_on.is_none_or(|on| a.attempt < on))
+            .filter(|a| is_failed(&a.status, a.failure.as_ref()))
+            .count();
+        Some(Self {
+            key,
+            attempts,
+            converted_on,
+            failed_attempts,
+        })
+    }
+}
+
+/// Whether an attempt failed: declined outright, cancelled or expired, or
+/// still open with the provider's error on it (a Stripe intent back at
+/// `requires_payment_method` after `payment_intent.payment_failed`).
+pub fn is_failed(status: &PaymentStatus, failure: Option<&PaymentFailure>) -> bool {
+    match status {
+        PaymentStatus::Failed | PaymentStatus::Cancelled | PaymentStatus::Expired => true,
+        PaymentStatus::Pending => failure.is_some(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::domain::{
+            id::{EventId, ExternalId},
+            money::{Currency, Money, MoneyAmount},
+            payment::NewPaymentParams,
+        },
+    };
+
+    fn payment(external_id: &str, customer: &str, metadata: serde_json::Value) -> NewPayment {
+        NewPayment::new(NewPaymentParams {
+            external_id: ExternalId::new(external_id).unwrap(),
+            source: "stripe".into(),
+            event_type: "payment_intent.created".into(),
+            direction: PaymentDirection::Inbound,
+            money: Money::new(MoneyAmount::new(5000).unwrap(), Currency::USD),
+            status: PaymentStatus::Pending,
+            metadata,
+            raw_event: serde_json::json!({}),
+            last_event_id: EventId::new("evt_1").unwrap(),
+            parent_external_id: None,
+            provider_ts: 1_700_000_000,
+            failure: None,
+            authorized_amount: None,
+            receipt: None,
+            tenant_id: None,
+            customer: Some(customer.into()),
+        })
+    }
+
+    #[test]
+    fn attempts_join_by_reference_then_customer() {
+        let prior = || {
+            Some(PriorAttempt {
+                checkout_key: "pi_1".into(),
+                attempt: 2,
+            })
+        };
+
+        let referenced = payment("pi_3", "cus_1", serde_json::json!({"order_id": "ord_9"}));
+        let signature = AttemptSignature::of(&referenced).unwrap();
+        assert_eq!(signature.lock_key().unwrap(), "checkout::reference:ord_9");
+        let first = NewCheckoutAttempt::new(&referenced, signature.clone(), None);
+        assert_eq!(
+            (first.checkout_key.as_str(), first.attempt, first.linked_by),
+            ("ord_9", 1, None)
+        );
+        let next = NewCheckoutAttempt::new(&referenced, signature, prior());
+        assert_eq!(
+            (next.attempt, next.linked_by),
+            (3, Some(LinkedBy::Reference))
+        );
+
+        // A `customer` the merchant put in metadata is theirs, not the
+        // provider's.
+        let customer = payment("pi_3", "cus_1", serde_json::json!({"customer": "cus_9"}));
+        let signature = AttemptSignature::of(&customer).unwrap();
+        assert_eq!(
+            signature.lock_key().unwrap(),
+            "checkout::customer:stripe:cus_1"
+        );
+        let retry = NewCheckoutAttempt::new(&customer, signature, prior());
+        assert_eq!(
+            (retry.checkout_key.as_str(), retry.attempt, retry.linked_by),
+            ("pi_1", 3, Some(LinkedBy::Customer))
+        );
+
+        let anonymous = payment("pi_4", " ", serde_json::json!({}));
+        let signature = AttemptSignature::of(&anonymous).unwrap();
+        assert_eq!(signature.lock_key(), None);
+        let own = NewCheckoutAttempt::new(&anonymous, signature, None);
+        assert_eq!((own.checkout_key.as_str(), own.attempt), ("pi_4", 1));
+    }
+}
@@ -0,0 +1,440 @@
+use {
+    super::{
+        audit::NewAuditEntry,
+        budget::{ApiOperation, BudgetPeriod, ProviderBudget, validate_budgets},
+        error::DomainError,
+        feed::MergePolicy,
+        job::JobLane,
+        shadow::ShadowStage,
+        webhook::{event_type_matches, is_event_type_pattern},
+    },
+    chrono::TimeDelta,
+    serde::{Deserialize, Serialize},
+    std::time::Duration,
+    uuid::Uuid,
+};
+
+/// Operational knobs that can change without a restart. Consumers read the
+/// current value on every iteration instead of caching it at startup.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RuntimeConfig {
+    /// Jobs claimed per worker poll.
+    pub worker_batch_size: i64,
+    /// Claimed jobs a worker processes at the same time.
+    #[serde(default = "default_worker_concurrency")]
+    pub worker_concurrency: usize,
+    /// First pause between worker polls once the queue is idle; a job
+    /// notification wakes the worker sooner.
+    pub worker_poll_interval_ms: u64,
+    /// Claimed refund jobs the refund lane's worker processes at the same
+    /// time; its batches are `worker_batch_size` too.
+    #[serde(default = "default_refund_worker_concurrency")]
+    pub refund_worker_concurrency: usize,
+    /// First idle pause between refund lane polls.
+    #[serde(default = "default_refund_worker_poll_interval_ms")]
+    pub refund_worker_poll_interval_ms: u64,
+    /// Longest pause between polls. Each wakeup that claims nothing doubles
+    /// a lane's pause, from its poll interval up to this (never below the
+    /// poll interval); a wakeup that claims work starts it over.
+    #[serde(default = "default_worker_idle_poll_max_ms")]
+    pub worker_idle_poll_max_ms: u64,
+    /// Pause between stale-job reaper passes.
+    pub reaper_interval_secs: u64,
+    /// How long a job may sit in `processing` before the reaper resets it.
+    pub stale_job_timeout_secs: i64,
+    /// On shutdown, how long in-flight jobs get to finish before the worker
+    /// gives up on them and puts them back to `pending`.
+    #[serde(default = "default_worker_drain_timeout_secs")]
+    pub worker_drain_timeout_secs: u64,
+    /// How long a worker reuses an object it fetched from the provider, for
+    /// jobs whose events are older than the fetch. 0 fetches every time.
+    #[serde(default = "default_provider_cache_ttl_secs")]
+    pub provider_cache_ttl_secs: u64,
+    /// Webhook deliveries whose signature timestamp is further than this
+    /// from now, either way, are rejected, and seen signatures are
+    /// remembered this long.
+    #[serde(default = "default_webhook_max_age_secs")]
+    pub webhook_max_age_secs: i64,
+    /// A payment still open with no webhook for this long is flagged as
+    /// missing its terminal event.
+    #[serde(default = "default_event_gap_timeout_secs")]
+    pub event_gap_timeout_secs: i64,
+    /// Answer read API requests that don't ask for a format with JSON:API
+    /// documents instead of plain JSON.
+    #[serde(default)]
+    pub jsonapi_by_default: bool,
+    /// Webhook event types accepted for processing, exactly or by prefix
+    /// (`payment_intent.*`). Anything else is logged as a passthrough
+    /// without parsing its object. Empty accepts every type.
+    #[serde(default)]
+    pub accepted_event_types: Vec<String>,
+    /// Webhook event types, exactly or by prefix, whose payment is taken
+    /// from the object inside the event rather than fetched from the
+    /// provider. An object the provider finds truncated is fetched anyway.
+    /// Empty fetches for every type.
+    #[serde(default)]
+    pub payload_event_types: Vec<String>,
+    /// Stage of the move from `payments` to `payments_v2`. Changes one
+    /// step forward at a time; see `ShadowStage`.
+    #[serde(default)]
+    pub payments_v2: ShadowStage,
+    /// Limits on provider API calls per operation and hour or day; calls
+    /// are counted either way. See `services::budget`.
+    #[serde(default)]
+    pub provider_budgets: Vec<ProviderBudget>,
+    /// How events about one payment from more than one feed (the
+    /// platform's and a connected account's, say) are merged.
+    #[serde(default)]
+    pub feed_merge_policy: MergePolicy,
+}
+
+fn default_worker_concurrency() -> usize {
+    4
+}
+
+fn default_refund_worker_concurrency() -> usize {
+    2
+}
+
+fn default_refund_worker_poll_interval_ms() -> u64 {
+    1_000
+}
+
+fn default_worker_idle_poll_max_ms() -> u64 {
+    30_000
+}
+
+fn default_worker_drain_timeout_secs() -> u64 {
+    20
+}
+
+fn default_provider_cache_ttl_secs() -> u64 {
+    10
+}
+
+/// Stripe's own libraries allow five minutes.
+fn default_webhook_max_age_secs() -> i64 {
+    300
+}
+
+fn default_event_gap_timeout_secs() -> i64 {
+    21_600
+}
+
+impl Default for RuntimeConfig {
+    fn default() -> Self {
+        Self {
+            worker_batch_size: 10,
+            worker_concurrency: default_worker_concurrency(),
+            worker_poll_interval_ms: 5_000,
+            refund_worker_concurrency: default_refund_worker_concurrency(),
+            refund_worker_poll_interval_ms: default_refund_worker_poll_interval_ms(),
+            worker_idle_poll_max_ms: default_worker_idle_poll_max_ms(),
+            reaper_interval_secs: 60,
+            stale_job_timeout_secs: 120,
+            worker_drain_timeout_secs: default_worker_drain_timeout_secs(),
+            provider_cache_ttl_secs: default_provider_cache_ttl_secs(),
+            webhook_max_age_secs: default_webhook_max_age_secs(),
+            event_gap_timeout_secs: default_event_gap_timeout_secs(),
+            jsonapi_by_default: false,
+            accepted_event_types: Vec::new(),
+            payload_event_types: Vec::new(),
+            payments_v2: ShadowStage::Off,
+            provider_budgets: Vec::new(),
+            feed_merge_policy: MergePolicy::Freshest,
+        }
+    }
+}
+
+impl RuntimeConfig {
+    pub fn validate(&self) -> Result<(), DomainError> {
+        let check = |ok: bool, msg: &str| {
+            if ok {
+                Ok(())
+            } else {
+                Err(DomainError::Validation(msg.to_string()))
+            }
+        };
+        check(
+            (1..=1_000).contains(&self.worker_batch_size),
+            "worker_batch_size must be between 1 and 1000",
+        )?;
+        check(
+            (1..=64).contains(&self.worker_concurrency),
+            "worker_concurrency must be between 1 and 64",
+        )?;
+        check(
+            (50..=60_000).contains(&self.worker_poll_interval_ms),
+            "worker_poll_interval_ms must be between 50 and 60000",
+        )?;
+        check(
+            (1..=64).contains(&self.refund_worker_concurrency),
+            "refund_worker_concurrency must be between 1 and 64",
+        )?;
+        check(
+            (50..=60_000).contains(&self.refund_worker_poll_interval_ms),
+            "refund_worker_poll_interval_ms must be between 50 and 60000",
+        )?;
+        check(
+            (50..=600_000).contains(&self.worker_idle_poll_max_ms),
+            "worker_idle_poll_max_ms must be between 50 and 600000",
+        )?;
+        check(
+            (1..=3_600).contains(&self.reaper_interval_secs),
+            "reaper_interval_secs must be between 1 and 3600",
+        )?;
+        check(
+            (10..=86_400).contains(&self.stale_job_timeout_secs),
+            "stale_job_timeout_secs must be between 10 and 86400",
+        )?;
+        check(
+            (1..=300).contains(&self.worker_drain_timeout_secs),
+            "worker_drain_timeout_secs must be between 1 and 300",
+        )?;
+        check(
+            self.provider_cache_ttl_secs <= 300,
+            "provider_cache_ttl_secs must be at most 300",
+        )?;
+        check(
+            (60..=86_400).contains(&self.webhook_max_age_secs),
+            "webhook_max_age_secs must be between 60 and 86400",
+        )?;
+        check(
+            (300..=604_800).contains(&self.event_gap_timeout_secs),
+            "event_gap_timeout_secs must be between 300 and 604800",
+        )?;
+        check(
+            self.accepted_event_types
+                .iter()
+                .all(|t| is_event_type_pattern(t)),
+            "accepted_event_types entries must be event types or prefixes ending in *",
+        )?;
+        check(
+            self.payload_event_types
+                .iter()
+                .all(|t| is_event_type_pattern(t)),
+            "payload_event_types entries must be event types or prefixes ending in *",
+        )?;
+        validate_budgets(&self.provider_budgets)
+    }
+
+    /// The budget on `operation` calls to `source` per `period`, if any.
+    pub fn provider_budget(
+        &self,
+        source: &str,
+        operation: ApiOperation,
+        period: BudgetPeriod,
+    ) -> Option<&ProviderBudget> {
+        self.provider_budgets
+            .iter()
+            .find(|b| b.applies_to(source, operation, period))
+    }
+
+    /// Whether webhooks of `event_type` are processed, per `accepted_event_types`.
+    pub fn accepts_event(&self, event_type: &str) -> bool {
+        self.accepted_event_types.is_empty()
+            || self
+                .accepted_event_types
+                .iter()
+                .any(|t| event_type_matches(t, event_type))
+    }
+
+    /// Whether webhooks of `event_type` are applied from their own object
+    /// when it's complete, per `payload_event_types`.
+    pub fn uses_payload(&self, event_type: &str) -> bool {
+        self.payload_event_types
+            .iter()
+            .any(|t| event_type_matches(t, event_type))
+    }
+
+    pub fn worker_poll_interval(&self) -> Duration {
+        Duration::from_millis(self.worker_poll_interval_ms)
+    }
+
+    /// Concurrency and first idle poll pause for the worker of `lane`.
+    pub fn lane_worker(&self, lane: JobLane) -> (usize, Duration) {
+        match lane {
+            JobLane::Standard => (self.worker_concurrency, self.worker_poll_interval()),
+            JobLane::Refund => (
+                self.refund_worker_concurrency,
+                Duration::from_millis(self.refund_worker_poll_interval_ms),
+            ),
+        }
+    }
+
+    pub fn worker_idle_poll_max(&self) -> Duration {
+        Duration::from_millis(self.worker_idle_poll_max_ms)
+    }
+
+    pub fn reaper_interval(&self) -> Duration {
+        Duration::from_secs(self.reaper_interval_secs)
+    }
+
+    pub fn worker_drain_timeout(&self) -> Duration {
+        Duration::from_secs(self.worker_drain_timeout_secs)
+    }
+
+    pub fn provider_cache_ttl(&self) -> TimeDelta {
+        TimeDelta::seconds(self.provider_cache_ttl_secs as i64)
+    }
+
+    pub fn webhook_max_age(&self) -> TimeDelta {
+        TimeDelta::seconds(self.webhook_max_age_secs)
+    }
+
+    pub fn event_gap_timeout(&self) -> TimeDelta {
+        TimeDelta::seconds(self.event_gap_timeout_secs)
+    }
+
+    /// Fields that differ between `self` and `next`, as `{field: {from, to}}`.
+    pub fn changes(&self, next: &Self) -> serde_json::Value {
+        let (before, after) = (serde_json::json!(self), serde_json::json!(next));
+        let mut out = serde_json::Map::new();
+        if let (Some(before), Some(after)) = (before.as_object(), after.as_object()) {
+            for (key, to) in after {
+                let from = &before[key];
+                if from != to {
+                    out.insert(key.clone(), serde_json::json!({"from": from, "to": to}));
+                }
+            }
+        }
+        serde_json::Value::Object(out)
+    }
+
+    /// Audit entry for moving from `self` to `next` as config `version`.
+    pub fn audit_entry(&self, next: &Self, version: i64, actor: &str) -> NewAuditEntry {
+        NewAuditEntry {
+            id: Uuid::now_v7(),
+            entity_type: "runtime_config".to_string(),
+            entity_id: None,
+            external_id: None,
+            event_id: format!("config:{version}"),
+            source: None,
+            tenant_id: None,
+            action: "config_changed".to_string(),
+            actor: actor.to_string(),
+            detail: serde_json::json!({
+                "version": version,
+                "changes": self.changes(next),
+            }),
+        }
+    }
+}
+
+/// A stored config with its version; versions only go up.
+#[derive(Debug, Clone, Serialize)]
+pub struct VersionedConfig {
+    pub version: i64,
+    pub config: RuntimeConfig,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_are_valid() {
+        RuntimeConfig::default().validate().unwrap();
+    }
+
+    #[test]
+    fn out_of_range_values_are_rejected() {
+        let cfg = RuntimeConfig {
+            worker_batch_size: 0,
+            ..Default::default()
+        };
+        assert!(cfg.validate().is_err());
+        let cfg = RuntimeConfig {
+            worker_poll_interval_ms: 10,
+            ..Default::default()
+        };
+        assert!(cfg.validate().is_err());
+        let cfg = RuntimeConfig {
+            refund_worker_concurrency: 0,
+            ..Default::default()
+        };
+        assert!(cfg.validate().is_err());
+    }
+
+    #[test]
+    fn event_type_allowlist_matches_exactly_or_by_prefix() {
+        assert!(RuntimeConfig::default().accepts_event("customer.created"));
+        let cfg = RuntimeConfig {
+            accepted_event_types: vec!["payment_intent.*".into(), "refund.updated".into()],
+            ..Default::default()
+        };
+        cfg.validate().unwrap();
+        assert!(cfg.accepts_event("payment_intent.succeeded"));
+        assert!(cfg.accepts_event("refund.updated"));
+        assert!(!cfg.accepts_event("refund.created"));
+        assert!(!cfg.accepts_event("customer.created"));
+
+        for bad in ["", "*", "pay*ment"] {
+            let cfg = RuntimeConfig {
+                accepted_event_types: vec![bad.into()],
+                ..Default::default()
+            };
+            assert!(cfg.validate().is_err(), "{bad:?}");
+            let cfg = RuntimeConfig {
+                payload_event_types: vec![bad.into()],
+                ..Default::default()
+            };
+            assert!(cfg.validate().is_err(), "{bad:?}");
+        }
+
+        // Unlike the allowlist, an empty payload list matches nothing.
+        assert!(!RuntimeConfig::default().uses_payload("payment_intent.succeeded"));
+        let cfg = RuntimeConfig {
+            payload_event_types: vec!["payment_intent.*".into()],
+            ..Default::default()
+        };
+        assert!(cfg.uses_payload("payment_intent.succeeded"));
+        assert!(!cfg.uses_payload("charge.refunded"));
+    }
+
+    #[test]
+    fn changes_lists_only_modified_fields() {
+        let before = RuntimeConfig::default();
+        let after = RuntimeConfig {
+            worker_batch_size: 25,
+            ..Default::default()
+        };
+        let changes = before.changes(&after);
+        assert_eq!(
+            changes,
+            serde_json::json!({"worker_batch_size": {"from": 10, "to": 25}})
+        );
+        assert_eq!(before.changes(&before), serde_json::json!({}));
+    }
+
+    #[test]
+    fn unknown_fields_are_rejected() {
+        let err = serde_json::from_value::<RuntimeConfig>(serde_json::json!({
+            "worker_batch_size": 10,
+            "worker_poll_interval_ms": 1000,
+            "reaper_interval_secs": 60,
+            "stale_job_timeout_secs": 120,
+            "worker_batch": 5,
+        }));
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn configs_stored_before_new_knobs_still_load() {
+        let cfg: RuntimeConfig = serde_json::from_value(serde_json::json!({
+            "worker_batch_size": 10,
+            "worker_poll_interval_ms": 1000,
+            "reaper_interval_secs": 60,
+            "stale_job_timeout_secs": 120,
+        }))
+        .unwrap();
+        assert_eq!(
+            cfg,
+            RuntimeConfig {
+                worker_poll_interval_ms: 1000,
+                ..Default::default()
+            }
+        );
+    }
+}
@@ -0,0 +1,193 @@
+use {
+    super::{
+        audit::NewAuditEntry,
+        error::DomainError,
+        money::{Currency, Money, MoneyAmount},
+        period::validate_reason,
+    },
+    chrono::{DateTime, NaiveTime, Utc},
+    serde::{Deserialize, Serialize},
+    uuid::Uuid,
+};
+
+/// Body of `POST /admin/currency-terms`: a provider's terms for one
+/// currency from `valid_from` until the next version, if any.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScheduleTerms {
+    pub source: String,
+    pub currency: String,
+    /// Whether payments in the currency are taken at all.
+    pub accepted: bool,
+    #[serde(default)]
+    pub fee_bps: u32,
+    /// Charged per payment on top of `fee_bps`, in minor units.
+    #[serde(default)]
+    pub fixed_fee_minor: i64,
+    /// A UTC midnight, so each day's report is priced under one version.
+    pub valid_from: DateTime<Utc>,
+    pub reason: String,
+}
+
+impl ScheduleTerms {
+    pub fn validate(&self) -> Result<Currency, DomainError> {
+        if self.source.trim().is_empty() {
+            return Err(DomainError::Validation("source must not be empty".into()));
+        }
+        if self.fee_bps > 10_000 {
+            return Err(DomainError::Validation(
+                "fee_bps must be at most 10000".into(),
+            ));
+        }
+        if self.fixed_fee_minor < 0 {
+            return Err(DomainError::Validation(
+                "fixed_fee_minor must not be negative".into(),
+            ));
+        }
+        if self.valid_from.time() != NaiveTime::MIN {
+            return Err(DomainError::Validation(
+                "valid_from must be the start of a UTC day".into(),
+            ));
+        }
+        validate_reason(&self.reason)?;
+        Currency::try_from(self.currency.as_str())
+    }
+}
+
+/// One `currency_terms` row: a version in force over
+/// `[valid_from, valid_to)`.
+#[derive(Debug, Clone, Serialize)]
+pub struct CurrencyTerms {
+    pub id: Uuid,
+    pub source: String,
+    pub currency: String,
+    pub accepted: bool,
+    pub fee_bps: i32,
+    pub fixed_fee_minor: i64,
+    pub valid_from: DateTime<Utc>,
+    /// `None` while no later version is scheduled.
+    pub valid_to: Option<DateTime<Utc>>,
+    pub reason: String,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl CurrencyTerms {
+    pub fn in_force_at(&self, at: DateTime<Utc>) -> bool {
+        self.valid_from <= at && self.valid_to.is_none_or(|to| at < to)
+    }
+
+    /// Whether scheduling `request` again would change nothing, so a
+    /// retried request is answered with this version.
+    pub fn matches(&self, request: &ScheduleTerms) -> bool {
+        self.accepted == request.accepted
+            && i64::from(self.fee_bps) == i64::from(request.fee_bps)
+            && self.fixed_fee_minor == request.fixed_fee_minor
+    }
+
+    /// What `payment_count` payments totalling `total` cost under these
+    /// terms: the percentage on the total, rounded half up, plus the fixed
+    /// fee per payment.
+    pub fn expected_fee(&self, total: Money, payment_count: i64) -> Result<Money, DomainError> {
+        let (percentage, _) = total.split_fee(self.fee_bps.unsigned_abs())?;
+        let fixed = self
+            .fixed_fee_minor
+            .checked_mul(payment_count)
+            .ok_or_else(|| DomainError::Validation("fixed fee total overflows".into()))?;
+        percentage.try_add(&Money::new(MoneyAmount::new(fixed)?, *total.currency()))
+    }
+
+    /// `currency_terms_scheduled` or `currency_terms_cancelled`.
+    pub fn audit_entry(&self, action: &str, actor: &str) -> NewAuditEntry {
+        NewAuditEntry {
+            id: Uuid::now_v7(),
+            entity_type: "currency_terms".to_string(),
+            entity_id: Some(self.id),
+            external_id: Some(format!("{}:{}", self.source, self.currency)),
+            event_id: format!("{action}:{}", self.id),
+            source: Some(self.source.clone()),
+            tenant_id: None,
+            action: action.to_string(),
+            actor: actor.to_string(),
+            detail: serde_json::json!({
+                "accepted": self.accepted,
+                "fee_bps": self.fee_bps,
+                "fixed_fee_minor": self.fixed_fee_minor,
+                "valid_from": self.valid_from,
+                "valid_to": self.valid_to,
+                "reason": self.reason,
+            }),
+        }
+    }
+}
+
+/// A source's versions, for looking many payments up against one read.
+#[derive(Debug, Default)]
+pub struct TermsTimeline(Vec<CurrencyTerms>);
+
+impl TermsTimeline {
+    pub fn new(versions: Vec<CurrencyTerms>) -> Self {
+        Self(versions)
+    }
+
+    /// The version of `source`'s terms for `currency` in force at `at`;
+    /// `None` if none was, in which case nothing restricts the currency.
+    pub fn at(&self, source: &str, currency: &str, at: DateTime<Utc>) -> Option<&CurrencyTerms> {
+        self.0
+            .iter()
+            .find(|t| t.source == source && t.currency == currency && t.in_force_at(at))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ts(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    fn terms(valid_from: &str, valid_to: Option<&str>, fee_bps: i32) -> CurrencyTerms {
+        CurrencyTerms {
+            id: Uuid::now_v7(),
+            source: "stripe".into(),
+            currency: "usd".into(),
+            accepted: true,
+            fee_bps,
+            fixed_fee_minor: 30,
+            valid_from: ts(valid_from),
+            valid_to: valid_to.map(ts),
+            reason: "contract".into(),
+            created_by: "ops".into(),
+            created_at: ts(valid_from),
+        }
+    }
+
+    #[test]
+    fn versions_hold_from_their_start_until_the_next() {
+        let timeline = TermsTimeline::new(vec![
+            terms("2026-01-01T00:00:00Z", Some("2026-04-01T00:00:00Z"), 290),
+            terms("2026-04-01T00:00:00Z", None, 250),
+        ]);
+        let fee_at = |at| timeline.at("stripe", "usd", ts(at)).map(|t| t.fee_bps);
+        assert_eq!(fee_at("2025-12-31T23:59:59Z"), None);
+        assert_eq!(fee_at("2026-03-31T23:59:59Z"), Some(290));
+        assert_eq!(fee_at("2026-04-01T00:00:00Z"), Some(250));
+        assert_eq!(fee_at("2030-01-01T00:00:00Z"), Some(250));
+        assert!(
+            timeline
+                .at("paypal", "usd", ts("2026-05-01T00:00:00Z"))
+                .is_none()
+        );
+
+        // 2.9% of 100.00 plus 0.30 on each of 4 payments.
+        let total = Money::new(MoneyAmount::new(10_000).unwrap(), Currency::USD);
+        let fee = timeline
+            .at("stripe", "usd", ts("2026-02-01T00:00:00Z"))
+            .unwrap();
+        assert_eq!(
+            fee.expected_fee(total, 4).unwrap().amount().cents(),
+            290 + 120
+        );
+    }
+}
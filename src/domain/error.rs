@@ -1,19 +1,57 @@
-use thiserror::Error;
+use {
+    chrono::{DateTime, Utc},
+    thiserror::Error,
+};
 
-#[derive(Debug, Error)]
-pub enum PipelineError {
+/// A domain rule was violated. Free of infra types, so domain values and
+/// rules don't pull in sqlx or axum; callers lift it into `PipelineError`
+/// with `?`.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum DomainError {
     #[error("validation: {0}")]
     Validation(String),
+}
+
+/// How a call through one of the ports the domain declares for adapters
+/// (`provider::PaymentProvider`, `hook::TransitionHook`,
+/// `alert::AlertSink`) failed. Adapters map their own errors into it at
+/// the boundary; the worker's retries tell the variants apart, and callers
+/// lift it into `PipelineError` with `?`.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum PortError {
+    /// The call didn't get through or failed on the other side (a
+    /// timeout, a 5xx, the adapter's own storage); trying again may work.
+    #[error("unavailable: {0}")]
+    Unavailable(String),
 
-    #[error("database: {0}")]
-    Database(#[from] sqlx::Error),
+    /// The provider has no such object: deleted, or never created.
+    /// Asking again won't change that.
+    #[error("missing: {0}")]
+    Missing(String),
 
-    #[error("serialization: {0}")]
-    Serialization(#[from] serde_json::Error),
+    /// The provider refused the request itself (a malformed id, a
+    /// permission it won't grant); sending it again gets the same answer.
+    #[error("rejected: {0}")]
+    Rejected(String),
 
-    #[error("webhook signature: {0}")]
-    WebhookSignature(String),
+    /// What was asked for or what came back doesn't make sense, e.g. an
+    /// object that doesn't convert.
+    #[error("invalid: {0}")]
+    Invalid(String),
+
+    /// A provider API budget is at its hard limit for the current window;
+    /// the call wasn't made. Fine again once the window ends, `until`.
+    #[error("budget exhausted until {until}: {reason}")]
+    BudgetExhausted {
+        reason: String,
+        until: DateTime<Utc>,
+    },
+}
 
-    #[error("provider: {0}")]
-    Provider(String),
+impl From<DomainError> for PortError {
+    fn from(err: DomainError) -> Self {
+        match err {
+            DomainError::Validation(msg) => PortError::Invalid(msg),
+        }
+    }
 }
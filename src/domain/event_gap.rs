@@ -0,0 +1,169 @@
+use {
+    super::{
+        payment::{PaymentDirection, PaymentStatus},
+        transition,
+    },
+    chrono::{DateTime, TimeDelta, Utc},
+    serde::Serialize,
+    uuid::Uuid,
+};
+
+/// A webhook we expected for a payment but never received.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GapKind {
+    /// Reached a terminal status without its opening event ever arriving.
+    MissingOpening,
+    /// Opened but went quiet: no terminal status and no event for longer
+    /// than the timeout.
+    MissingTerminal,
+}
+
+impl GapKind {
+    pub const ALL: [Self; 2] = [Self::MissingOpening, Self::MissingTerminal];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::MissingOpening => "missing_opening",
+            Self::MissingTerminal => "missing_terminal",
+        }
+    }
+}
+
+/// The events a provider sends for every object of one kind. Objects with no
+/// lifecycle here (PayPal captures, manual payments) are never checked.
+pub struct Lifecycle {
+    pub source: &'static str,
+    pub id_prefix: &'static str,
+    /// Event types, any of which opens the object's lifecycle.
+    pub opening: &'static [&'static str],
+}
+
+pub const LIFECYCLES: &[Lifecycle] = &[
+    Lifecycle {
+        source: "stripe",
+        id_prefix: "pi_",
+        opening: &["payment_intent.created"],
+    },
+    Lifecycle {
+        source: "stripe",
+        id_prefix: "re_",
+        opening: &["refund.created"],
+    },
+    Lifecycle {
+        source: "stripe",
+        id_prefix: "dp_",
+        opening: &["charge.dispute.created"],
+    },
+];
+
+pub fn lifecycle_for(source: &str, external_id: &str) -> Option<&'static Lifecycle> {
+    LIFECYCLES
+        .iter()
+        .find(|l| l.source == source && external_id.starts_with(l.id_prefix))
+}
+
+/// One payment and the webhook events received for it (backfill imports and
+/// refetches excluded).
+#[derive(Debug, Clone)]
+pub struct ObservedLifecycle {
+    pub external_id: String,
+    pub source: String,
+    pub tenant_id: Option<String>,
+    pub direction: PaymentDirection,
+    pub status: PaymentStatus,
+    pub event_types: Vec<String>,
+    pub last_event_at: DateTime<Utc>,
+}
+
+impl ObservedLifecycle {
+    /// The gap this payment shows at `now`, if any.
+    pub fn gap(&self, now: DateTime<Utc>, timeout: TimeDelta) -> Option<GapKind> {
+        let lifecycle = lifecycle_for(&self.source, &self.external_id)?;
+        let terminal =
+            transition::for_source(&self.source).is_terminal(&self.direction, &self.status);
+        let opened = self
+            .event_types
+            .iter()
+            .any(|t| lifecycle.opening.contains(&t.as_str()));
+
+        if terminal && !opened {
+            Some(GapKind::MissingOpening)
+        } else if !terminal && now - self.last_event_at > timeout {
+            Some(GapKind::MissingTerminal)
+        } else {
+            None
+        }
+    }
+}
+
+/// One `event_gaps` row.
+#[derive(Debug, Serialize)]
+pub struct EventGapView {
+    pub id: Uuid,
+    pub source: String,
+    pub external_id: String,
+    pub kind: String,
+    /// Payment status when the gap was (last) detected.
+    pub status: String,
+    pub refetch_requested: bool,
+    pub detected_at: DateTime<Utc>,
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+/// Outcome of one detection pass.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct GapScan {
+    pub checked: usize,
+    pub opened: u64,
+    pub resolved: u64,
+    pub refetches: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn observed(id: &str, status: PaymentStatus, events: &[&str]) -> ObservedLifecycle {
+        ObservedLifecycle {
+            external_id: id.into(),
+            source: "stripe".into(),
+            tenant_id: None,
+            direction: PaymentDirection::Inbound,
+            status,
+            event_types: events.iter().map(|t| t.to_string()).collect(),
+            last_event_at: DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn gaps_follow_the_lifecycle() {
+        let timeout = TimeDelta::hours(6);
+        let soon = DateTime::from_timestamp(1_700_000_060, 0).unwrap();
+        let later = soon + timeout;
+
+        let complete = observed(
+            "pi_1",
+            PaymentStatus::Succeeded,
+            &["payment_intent.created", "payment_intent.succeeded"],
+        );
+        assert_eq!(complete.gap(later, timeout), None);
+
+        let unopened = observed(
+            "pi_2",
+            PaymentStatus::Succeeded,
+            &["payment_intent.succeeded"],
+        );
+        assert_eq!(unopened.gap(soon, timeout), Some(GapKind::MissingOpening));
+
+        let open = observed("pi_3", PaymentStatus::Pending, &["payment_intent.created"]);
+        assert_eq!(open.gap(soon, timeout), None);
+        assert_eq!(open.gap(later, timeout), Some(GapKind::MissingTerminal));
+
+        let untracked = ObservedLifecycle {
+            source: "paypal".into(),
+            ..observed("pp_cap_1", PaymentStatus::Pending, &[])
+        };
+        assert_eq!(untracked.gap(later, timeout), None);
+    }
+}
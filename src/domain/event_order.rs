@@ -0,0 +1,76 @@
+use std::cmp::Ordering;
+
+/// Where a provider event falls among the events for one object. Stripe's
+/// `created` has one-second resolution, so a burst (a payout updated and
+/// paid within the same second) ties on it; the event id and the API request
+/// behind each event break the tie.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EventOrder<'a> {
+    pub provider_ts: i64,
+    pub event_id: &'a str,
+    /// `request.id` of the API call that caused the event; `None` for events
+    /// the provider raised on its own (a payout being paid, say).
+    pub request_id: Option<&'a str>,
+}
+
+impl EventOrder<'_> {
+    /// By `created`, then within a second by event id, which Stripe issues
+    /// in lexical order. Events caused by two different API requests in the
+    /// same second raced each other, so their ids say nothing: those are
+    /// left unordered (`None`) for the state machine to decide.
+    pub fn compare(&self, other: &EventOrder<'_>) -> Option<Ordering> {
+        match self.provider_ts.cmp(&other.provider_ts) {
+            Ordering::Equal => {}
+            ordering => return Some(ordering),
+        }
+        match (self.request_id, other.request_id) {
+            (Some(a), Some(b)) if a != b => None,
+            _ => Some(self.event_id.cmp(other.event_id)),
+        }
+    }
+
+    /// Whether this event is known to come before `other`.
+    pub fn is_before(&self, other: &EventOrder<'_>) -> bool {
+        self.compare(other) == Some(Ordering::Less)
+    }
+}
+
+/// The API request behind a provider event: Stripe's `request.id`, or the
+/// bare string older API versions send. `None` for events raised on the
+/// provider's own and for providers that don't say.
+pub fn request_id(raw_event: &serde_json::Value) -> Option<&str> {
+    let request = &raw_event["request"];
+    request["id"].as_str().or_else(|| request.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event<'a>(
+        provider_ts: i64,
+        event_id: &'a str,
+        request_id: Option<&'a str>,
+    ) -> EventOrder<'a> {
+        EventOrder {
+            provider_ts,
+            event_id,
+            request_id,
+        }
+    }
+
+    #[test]
+    fn same_second_ties_break_on_event_id_unless_requests_differ() {
+        // Seconds first, whatever the ids say.
+        assert!(event(99, "evt_B", None).is_before(&event(100, "evt_A", None)));
+        // Same second: automatic events and events of one request by id.
+        assert!(event(100, "evt_A", None).is_before(&event(100, "evt_B", None)));
+        assert!(event(100, "evt_A", Some("req_1")).is_before(&event(100, "evt_B", Some("req_1"))));
+        assert!(event(100, "evt_A", None).is_before(&event(100, "evt_B", Some("req_1"))));
+        // Two requests racing within a second can't be ordered.
+        let a = event(100, "evt_A", Some("req_1"));
+        let b = event(100, "evt_B", Some("req_2"));
+        assert_eq!(a.compare(&b), None);
+        assert!(!a.is_before(&b) && !b.is_before(&a));
+    }
+}
@@ -0,0 +1,313 @@
+use {
+    super::{audit::AuditRecord, error::DomainError, payment::PaymentView},
+    chrono::{DateTime, NaiveDate, TimeDelta, Utc},
+    serde::{Deserialize, Serialize},
+    std::io::Write,
+    uuid::Uuid,
+};
+
+/// Wire format of `GET /payments/export` and of stored exports.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Ndjson,
+    /// Needs the `parquet` feature.
+    Parquet,
+}
+
+/// `?format=` for an export; the other query parameters are `PaymentFilters`.
+#[derive(Debug, Default, Deserialize)]
+pub struct ExportParams {
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+/// A record exports can hold.
+pub trait ExportRow: Serialize {
+    /// Column names, newline-terminated.
+    const CSV_HEADER: &'static str;
+
+    /// Columns, by position in `CSV_HEADER`, that Parquet stores as 64-bit
+    /// integers; the rest are strings.
+    const PARQUET_INT64: &'static [usize] = &[];
+
+    fn csv_fields(&self) -> Vec<String>;
+}
+
+impl ExportRow for PaymentView {
+    const CSV_HEADER: &'static str = "external_id,source,status,amount,currency,direction,\
+parent_external_id,failure_code,decline_code,failure_message,network_advice_code,\
+created_at,updated_at\n";
+
+    const PARQUET_INT64: &'static [usize] = &[3];
+
+    fn csv_fields(&self) -> Vec<String> {
+        let failure = self.failure.as_ref();
+        let text = |s: Option<&String>| s.cloned().unwrap_or_default();
+        vec![
+            self.id.as_str().to_string(),
+            self.source.clone(),
+            self.status.as_str().to_string(),
+            self.amount.to_string(),
+            self.currency.as_str().to_string(),
+            self.direction.as_str().to_string(),
+            self.parent_external_id
+                .as_ref()
+                .map_or_else(String::new, |id| id.as_str().to_string()),
+            text(failure.and_then(|f| f.code.as_ref())),
+            text(failure.and_then(|f| f.decline_code.as_ref())),
+            text(failure.and_then(|f| f.message.as_ref())),
+            text(failure.and_then(|f| f.network_advice_code.as_ref())),
+            self.created_at.to_rfc3339(),
+            self.updated_at.to_rfc3339(),
+        ]
+    }
+}
+
+impl ExportRow for AuditRecord {
+    const CSV_HEADER: &'static str = "id,entity_type,entity_id,external_id,event_id,action,actor,detail,created_at,source,\
+         tenant_id\n";
+
+    fn csv_fields(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.entity_type.clone(),
+            self.entity_id.map(|id| id.to_string()).unwrap_or_default(),
+            self.external_id.clone().unwrap_or_default(),
+            self.event_id.clone(),
+            self.action.clone(),
+            self.actor.clone(),
+            self.detail.to_string(),
+            self.created_at.to_rfc3339(),
+            self.source.clone().unwrap_or_default(),
+            self.tenant_id.clone().unwrap_or_default(),
+        ]
+    }
+}
+
+impl ExportFormat {
+    pub const ALL: [Self; 3] = [Self::Csv, Self::Ndjson, Self::Parquet];
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Ndjson => "application/x-ndjson",
+            Self::Parquet => "application/vnd.apache.parquet",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Ndjson => "ndjson",
+            Self::Parquet => "parquet",
+        }
+    }
+
+    /// Bytes written before the first row.
+    pub fn header<R: ExportRow>(self) -> &'static [u8] {
+        match self {
+            Self::Csv => R::CSV_HEADER.as_bytes(),
+            Self::Ndjson | Self::Parquet => b"",
+        }
+    }
+
+    /// Appends one row, newline-terminated. Parquet rows are encoded a row
+    /// group at a time instead, by `infra::parquet`.
+    pub fn write_row<R: ExportRow>(
+        self,
+        buf: &mut Vec<u8>,
+        row: &R,
+    ) -> Result<(), serde_json::Error> {
+        match self {
+            Self::Csv => {
+                for (i, field) in row.csv_fields().iter().enumerate() {
+                    if i > 0 {
+                        buf.push(b',');
+                    }
+                    write_csv_field(buf, field);
+                }
+                buf.push(b'\n');
+            }
+            Self::Ndjson => {
+                serde_json::to_writer(&mut *buf, row)?;
+                buf.push(b'\n');
+            }
+            Self::Parquet => {
+                return Err(serde::ser::Error::custom(
+                    "parquet rows are encoded a row group at a time",
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What a stored export holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportKind {
+    /// Payments created in the window, as they are now.
+    Payments,
+    /// Audit entries written in the window.
+    Audit,
+}
+
+impl ExportKind {
+    pub const ALL: [Self; 2] = [Self::Payments, Self::Audit];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Payments => "payments",
+            Self::Audit => "audit",
+        }
+    }
+}
+
+impl std::str::FromStr for ExportKind {
+    type Err = DomainError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "payments" => Ok(Self::Payments),
+            "audit" => Ok(Self::Audit),
+            other => Err(DomainError::Validation(format!(
+                "expected payments or audit, got: {other}"
+            ))),
+        }
+    }
+}
+
+/// Body of `POST /admin/exports`. The window defaults to the previous UTC
+/// day.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExportRequest {
+    pub kind: ExportKind,
+    #[serde(default)]
+    pub format: ExportFormat,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+/// An export about to start, as recorded in `exports`.
+#[derive(Debug, Clone, Copy)]
+pub struct NewExport<'a> {
+    pub kind: ExportKind,
+    pub format: ExportFormat,
+    pub since: DateTime<Utc>,
+    pub until: DateTime<Utc>,
+    /// `schedule` or `manual`.
+    pub trigger: &'a str,
+    pub requested_by: &'a str,
+}
+
+/// The inclusive window `[since, until]` of one UTC day.
+pub fn day_window(day: NaiveDate) -> (DateTime<Utc>, DateTime<Utc>) {
+    let since = day.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+    (
+        since,
+        since + TimeDelta::days(1) - TimeDelta::microseconds(1),
+    )
+}
+
+/// One `exports` row.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportView {
+    pub id: Uuid,
+    pub kind: String,
+    pub format: String,
+    pub window_start: DateTime<Utc>,
+    pub window_end: DateTime<Utc>,
+    /// `schedule` or `manual`.
+    pub trigger: String,
+    pub requested_by: String,
+    /// `running`, `completed` or `failed`.
+    pub status: String,
+    /// Path or `s3://bucket/key` of the file, once completed.
+    pub location: Option<String>,
+    pub row_count: Option<i64>,
+    pub byte_count: Option<i64>,
+    pub error: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+impl ExportView {
+    /// Name of the exported file, e.g.
+    /// `payments-20260401T000000Z-20260401T235959Z-<id>.csv`.
+    pub fn file_name(&self) -> String {
+        let stamp = |t: DateTime<Utc>| t.format("%Y%m%dT%H%M%SZ").to_string();
+        format!(
+            "{}-{}-{}-{}.{}",
+            self.kind,
+            stamp(self.window_start),
+            stamp(self.window_end),
+            self.id.simple(),
+            self.format
+        )
+    }
+}
+
+/// RFC 4180: quote fields holding a delimiter, quote or line break, and
+/// double any quotes inside.
+fn write_csv_field(buf: &mut Vec<u8>, field: &str) {
+    if field.contains([',', '"', '\n', '\r']) {
+        // Writing to a Vec can't fail.
+        let _ = write!(buf, "\"{}\"", field.replace('"', "\"\""));
+    } else {
+        buf.extend_from_slice(field.as_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_fields_are_quoted_only_when_needed() {
+        let mut buf = Vec::new();
+        write_csv_field(&mut buf, "card_declined");
+        buf.push(b',');
+        write_csv_field(&mut buf, "Your card was declined, \"insufficient funds\".");
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            r#"card_declined,"Your card was declined, ""insufficient funds"".""#
+        );
+    }
+
+    #[test]
+    fn audit_rows_keep_their_detail_as_json() {
+        let record = AuditRecord {
+            id: Uuid::nil(),
+            entity_type: "payment".into(),
+            entity_id: None,
+            external_id: Some("pi_1".into()),
+            event_id: "evt_1".into(),
+            source: Some("stripe".into()),
+            tenant_id: None,
+            action: "status_changed".into(),
+            actor: "webhook:stripe".into(),
+            detail: serde_json::json!({"from": "pending", "to": "succeeded"}),
+            created_at: DateTime::from_timestamp(1_775_000_000, 0).unwrap(),
+        };
+        let mut buf = ExportFormat::Csv.header::<AuditRecord>().to_vec();
+        ExportFormat::Csv.write_row(&mut buf, &record).unwrap();
+        let csv = String::from_utf8(buf).unwrap();
+        assert_eq!(
+            csv.lines().nth(1).unwrap(),
+            "00000000-0000-0000-0000-000000000000,payment,,pi_1,evt_1,status_changed,\
+             webhook:stripe,\"{\"\"from\"\":\"\"pending\"\",\"\"to\"\":\"\"succeeded\"\"}\",\
+             2026-03-31T23:33:20+00:00,stripe,"
+        );
+
+        let (since, until) = day_window(NaiveDate::from_ymd_opt(2026, 4, 1).unwrap());
+        assert_eq!(since.to_rfc3339(), "2026-04-01T00:00:00+00:00");
+        assert_eq!(
+            until - since,
+            TimeDelta::days(1) - TimeDelta::microseconds(1)
+        );
+    }
+}
@@ -0,0 +1,157 @@
+use {
+    super::{audit::NewAuditEntry, error::DomainError, id::TenantId, payment::NewPayment},
+    serde::{Deserialize, Serialize},
+};
+
+/// The feed an event about a payment arrives on: `<source>` for the
+/// platform's own events, `<source>@<tenant>` for a connected account's.
+/// Events within one feed are ordered by the provider; across feeds they
+/// race, and the merge policy decides.
+pub fn feed_key(source: &str, tenant: Option<&TenantId>) -> String {
+    match tenant {
+        Some(tenant) => format!("{source}@{tenant}"),
+        None => source.to_string(),
+    }
+}
+
+/// How the pipeline merges events about one payment from more than one
+/// feed. Set as `feed_merge_policy` in the runtime config.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MergePolicy {
+    /// An event older than the latest one received from another feed is
+    /// recorded but not applied: the fresher feed's state stands.
+    #[default]
+    Freshest,
+    /// Every event goes to the state machine as if there were one feed;
+    /// conflicts are only recorded.
+    StateMachine,
+}
+
+impl MergePolicy {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Freshest => "freshest",
+            Self::StateMachine => "state_machine",
+        }
+    }
+}
+
+impl TryFrom<&str> for MergePolicy {
+    type Error = DomainError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s {
+            "freshest" => Ok(Self::Freshest),
+            "state_machine" => Ok(Self::StateMachine),
+            other => Err(DomainError::Validation(format!(
+                "unknown feed merge policy: {other}"
+            ))),
+        }
+    }
+}
+
+/// The latest event received on one feed of a payment.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FeedPosition {
+    pub feed: String,
+    pub provider_ts: i64,
+    pub event_id: String,
+}
+
+/// What the merge policy makes of an incoming event, given where the
+/// payment's feeds stand.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Merge {
+    pub policy: MergePolicy,
+    pub feed: String,
+    pub provider_ts: i64,
+    /// The freshest other feed the event is behind, if any.
+    pub conflict: Option<FeedPosition>,
+}
+
+impl Merge {
+    pub fn new(policy: MergePolicy, payment: &NewPayment, positions: &[FeedPosition]) -> Self {
+        let feed = payment.feed();
+        let conflict = positions
+            .iter()
+            .filter(|p| p.feed != feed && p.provider_ts > payment.provider_ts())
+            .max_by_key(|p| p.provider_ts)
+            .cloned();
+        Self {
+            policy,
+            feed,
+            provider_ts: payment.provider_ts(),
+            conflict,
+        }
+    }
+
+    /// The event is recorded but not applied.
+    pub fn supersedes(&self) -> bool {
+        self.conflict.is_some() && self.policy == MergePolicy::Freshest
+    }
+
+    /// Add the feed, the policy and the conflict to `audit`'s detail, if
+    /// the event is behind another feed.
+    pub fn mark(&self, audit: &mut NewAuditEntry) {
+        let Some(ahead) = &self.conflict else {
+            return;
+        };
+        audit.detail["feed"] = self.feed.as_str().into();
+        audit.detail["merge_policy"] = self.policy.as_str().into();
+        audit.detail["conflict"] = serde_json::json!({
+            "feed": ahead.feed,
+            "provider_ts": ahead.provider_ts,
+            "event_id": ahead.event_id,
+            "behind_secs": ahead.provider_ts - self.provider_ts,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{domain::payment::PaymentStatus, testing::make_payment},
+    };
+
+    fn position(feed: &str, provider_ts: i64) -> FeedPosition {
+        FeedPosition {
+            feed: feed.into(),
+            provider_ts,
+            event_id: format!("evt_{provider_ts}"),
+        }
+    }
+
+    #[test]
+    fn only_other_feeds_ahead_conflict() {
+        let incoming = make_payment("pi_1", "evt_1", PaymentStatus::Pending, 100);
+        assert_eq!(incoming.feed(), "stripe");
+
+        // Behind its own feed: that's for the state machine.
+        let own = Merge::new(MergePolicy::Freshest, &incoming, &[position("stripe", 200)]);
+        assert_eq!(own.conflict, None);
+        assert!(!own.supersedes());
+
+        // Behind a connected account's feed, the freshest of them counts.
+        let positions = [
+            position("stripe", 200),
+            position("stripe@acct_1", 150),
+            position("settlement", 100),
+        ];
+        let merge = Merge::new(MergePolicy::Freshest, &incoming, &positions);
+        assert_eq!(merge.conflict, Some(position("stripe@acct_1", 150)));
+        assert!(merge.supersedes());
+        let mut audit = incoming.audit_entry("test", "event_received");
+        merge.mark(&mut audit);
+        assert_eq!(audit.detail["merge_policy"], "freshest");
+        assert_eq!(audit.detail["conflict"]["behind_secs"], 50);
+
+        let lenient = Merge::new(MergePolicy::StateMachine, &incoming, &positions);
+        assert!(lenient.conflict.is_some() && !lenient.supersedes());
+        assert_eq!(
+            MergePolicy::try_from("state_machine"),
+            Ok(MergePolicy::StateMachine)
+        );
+    }
+}
@@ -0,0 +1,65 @@
+use {
+    super::id::TenantId,
+    serde::{Deserialize, Serialize},
+};
+
+/// Work the pipeline queues in the transaction that made it necessary, run
+/// by the job worker once that transaction commits. Stored as JSON tagged
+/// with its `kind`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FollowUp {
+    /// A refund or dispute was recorded before the payment it belongs to:
+    /// fetch that payment from `source`, so the two are linked. A connected
+    /// account's payment is fetched as that account.
+    FetchParent {
+        source: String,
+        external_id: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        tenant_id: Option<TenantId>,
+    },
+}
+
+impl FollowUp {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::FetchParent { .. } => "fetch_parent",
+        }
+    }
+
+    /// Names the work itself: the same follow-up queued twice runs once.
+    pub fn dedup_key(&self) -> String {
+        match self {
+            Self::FetchParent {
+                source,
+                external_id,
+                ..
+            } => format!("fetch_parent:{source}:{external_id}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn followups_are_stored_tagged_by_kind() {
+        let followup = FollowUp::FetchParent {
+            source: "stripe".into(),
+            external_id: "pi_1".into(),
+            tenant_id: None,
+        };
+        let stored = serde_json::json!(followup);
+        assert_eq!(
+            stored,
+            serde_json::json!({"kind": "fetch_parent", "source": "stripe", "external_id": "pi_1"})
+        );
+        assert_eq!(stored["kind"], followup.kind());
+        assert_eq!(
+            serde_json::from_value::<FollowUp>(stored).unwrap(),
+            followup
+        );
+        assert_eq!(followup.dedup_key(), "fetch_parent:stripe:pi_1");
+    }
+}
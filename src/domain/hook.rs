@@ -0,0 +1,183 @@
+use {
+    super::{
+        error::PortError,
+        payment::{PaymentStatus, PaymentView},
+    },
+    chrono::{DateTime, Utc},
+    std::{collections::BTreeMap, future::Future, pin::Pin, sync::Arc},
+};
+
+pub type HookFuture<'a> = Pin<Box<dyn Future<Output = Result<(), PortError>> + Send + 'a>>;
+
+/// Extension point for applications embedding the service: told about every
+/// payment transition once it is committed. Each method defaults to doing
+/// nothing, so a hook implements only what it cares about.
+///
+/// Delivery is at least once. An `Err` is retried later with backoff, and a
+/// hook sees one payment's transitions in order, so a later one waits while
+/// an earlier one is still failing.
+pub trait TransitionHook: Send + Sync {
+    /// Stable name; pending deliveries are queued under it across restarts.
+    fn name(&self) -> &'static str;
+
+    fn on_created<'a>(&'a self, _notice: &'a TransitionNotice) -> HookFuture<'a> {
+        Box::pin(async { Ok(()) })
+    }
+
+    fn on_status_changed<'a>(
+        &'a self,
+        _notice: &'a TransitionNotice,
+        _from: PaymentStatus,
+        _to: PaymentStatus,
+    ) -> HookFuture<'a> {
+        Box::pin(async { Ok(()) })
+    }
+
+    /// An event asked for a transition the state machine refused.
+    fn on_anomaly<'a>(
+        &'a self,
+        _notice: &'a TransitionNotice,
+        _current: PaymentStatus,
+        _incoming: PaymentStatus,
+    ) -> HookFuture<'a> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// What a hook is told, besides the transition itself.
+#[derive(Debug)]
+pub struct TransitionNotice {
+    /// The provider (or synthetic) event behind the transition.
+    pub event_id: String,
+    pub actor: String,
+    pub occurred_at: DateTime<Utc>,
+    /// The payment as committed, read when the notice is delivered.
+    pub payment: PaymentView,
+}
+
+/// A transition worth telling hooks about, as recorded in the audit log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Transition {
+    Created,
+    StatusChanged {
+        from: PaymentStatus,
+        to: PaymentStatus,
+    },
+    Anomaly {
+        current: PaymentStatus,
+        incoming: PaymentStatus,
+    },
+}
+
+impl Transition {
+    /// The transition an audit entry records, from its `action` and
+    /// `detail`. `None` for entries that record none.
+    pub fn from_audit(action: &str, detail: &serde_json::Value) -> Option<Self> {
+        let status = |key: &str| {
+            detail[key]
+                .as_str()
+                .and_then(|s| PaymentStatus::try_from(s).ok())
+        };
+        match action {
+            "created" => Some(Self::Created),
+            "status_changed" => Some(Self::StatusChanged {
+                from: status("old_status")?,
+                to: status("new_status")?,
+            }),
+            "event_received" if detail["anomaly"] == true => Some(Self::Anomaly {
+                current: status("current_status")?,
+                incoming: status("incoming_status")?,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// A queued `hook_outbox` row with the audit entry it points at.
+#[derive(Debug)]
+pub struct HookDelivery {
+    pub seq: i64,
+    pub hook: String,
+    pub attempts: i32,
+    pub external_id: Option<String>,
+    pub event_id: String,
+    pub action: String,
+    pub actor: String,
+    pub detail: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+/// The hooks an embedding application registered, keyed by name.
+#[derive(Clone, Default)]
+pub struct HookRegistry {
+    hooks: BTreeMap<&'static str, Arc<dyn TransitionHook>>,
+}
+
+impl HookRegistry {
+    /// Add a hook under its own `name()`, replacing any previous one.
+    pub fn register(&mut self, hook: Arc<dyn TransitionHook>) {
+        self.hooks.insert(hook.name(), hook);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Arc<dyn TransitionHook>> {
+        self.hooks.get(name)
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.hooks.keys().map(|n| n.to_string()).collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+}
+
+/// Call the method of `hook` that matches `transition`.
+pub async fn deliver(
+    hook: &dyn TransitionHook,
+    transition: Transition,
+    notice: &TransitionNotice,
+) -> Result<(), PortError> {
+    match transition {
+        Transition::Created => hook.on_created(notice).await,
+        Transition::StatusChanged { from, to } => hook.on_status_changed(notice, from, to).await,
+        Transition::Anomaly { current, incoming } => {
+            hook.on_anomaly(notice, current, incoming).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, serde_json::json};
+
+    #[test]
+    fn transitions_are_read_from_audit_entries() {
+        assert_eq!(
+            Transition::from_audit("created", &json!({"status": "pending"})),
+            Some(Transition::Created)
+        );
+        assert_eq!(
+            Transition::from_audit(
+                "status_changed",
+                &json!({"old_status": "pending", "new_status": "succeeded"})
+            ),
+            Some(Transition::StatusChanged {
+                from: PaymentStatus::Pending,
+                to: PaymentStatus::Succeeded,
+            })
+        );
+        assert_eq!(
+            Transition::from_audit(
+                "event_received",
+                &json!({"current_status": "failed", "incoming_status": "succeeded", "anomaly": true})
+            ),
+            Some(Transition::Anomaly {
+                current: PaymentStatus::Failed,
+                incoming: PaymentStatus::Succeeded,
+            })
+        );
+        assert_eq!(Transition::from_audit("event_received", &json!({})), None);
+        assert_eq!(Transition::from_audit("over_refund", &json!({})), None);
+    }
+}
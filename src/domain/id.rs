@@ -1,19 +1,23 @@
 use derive_more::Display;
 use serde::{Deserialize, Serialize};
 
-use super::error::PipelineError;
+use super::error::DomainError;
 
-/// Payment-intent or refund identifier (`pi_xxx`, `re_xxx`).
-#[derive(Debug, Clone, PartialEq, Eq, Display, Serialize, Deserialize)]
+/// Payment identifier: Stripe payment intent, refund or dispute (`pi_xxx`,
+/// `re_xxx`, `dp_xxx`), PayPal capture or refund (`pp_cap_xxx`, `pp_ref_xxx`), or manual (`mp_xxx`).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Display, Serialize, Deserialize)]
 #[serde(transparent)]
 pub struct ExternalId(String);
 
 impl ExternalId {
-    pub fn new(id: impl Into<String>) -> Result<Self, PipelineError> {
+    pub fn new(id: impl Into<String>) -> Result<Self, DomainError> {
         let id = id.into();
-        if !(id.starts_with("pi_") || id.starts_with("re_")) {
-            return Err(PipelineError::Validation(format!(
-                "ExternalId must start with pi_ or re_, got: {id}"
+        if !["pi_", "re_", "dp_", "pp_", "mp_"]
+            .iter()
+            .any(|p| id.starts_with(p))
+        {
+            return Err(DomainError::Validation(format!(
+                "ExternalId must start with pi_, re_, dp_, pp_ or mp_, got: {id}"
             )));
         }
         Ok(Self(id))
@@ -28,16 +32,38 @@ impl ExternalId {
     }
 }
 
+/// Payout identifier: a Stripe payout to a bank account (`po_xxx`) or a
+/// transfer to a connected account (`tr_xxx`).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Display, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PayoutId(String);
+
+impl PayoutId {
+    pub fn new(id: impl Into<String>) -> Result<Self, DomainError> {
+        let id = id.into();
+        if !id.starts_with("po_") && !id.starts_with("tr_") {
+            return Err(DomainError::Validation(format!(
+                "PayoutId must start with po_ or tr_, got: {id}"
+            )));
+        }
+        Ok(Self(id))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
 /// Stripe event identifier (`evt_xxx`).
-#[derive(Debug, Clone, PartialEq, Eq, Display, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Display, Serialize, Deserialize)]
 #[serde(transparent)]
 pub struct EventId(String);
 
 impl EventId {
-    pub fn new(id: impl Into<String>) -> Result<Self, PipelineError> {
+    pub fn new(id: impl Into<String>) -> Result<Self, DomainError> {
         let id = id.into();
         if !id.starts_with("evt_") {
-            return Err(PipelineError::Validation(format!(
+            return Err(DomainError::Validation(format!(
                 "EventId must start with evt_, got: {id}"
             )));
         }
@@ -52,3 +78,25 @@ impl EventId {
         self.0
     }
 }
+
+/// Tenant identifier: the Stripe connected account (`acct_xxx`) an event
+/// was sent for. Events of the platform's own account carry none.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Display, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TenantId(String);
+
+impl TenantId {
+    pub fn new(id: impl Into<String>) -> Result<Self, DomainError> {
+        let id = id.into();
+        if !id.starts_with("acct_") {
+            return Err(DomainError::Validation(format!(
+                "TenantId must start with acct_, got: {id}"
+            )));
+        }
+        Ok(Self(id))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
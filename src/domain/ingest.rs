@@ -0,0 +1,332 @@
+use {
+    super::{
+        error::DomainError,
+        id::{EventId, ExternalId},
+        money::{Currency, Money, MoneyAmount},
+        payment::{NewPayment, NewPaymentParams, PaymentDirection, PaymentStatus, ProcessResult},
+    },
+    chrono::{DateTime, Utc},
+    serde::{Deserialize, Serialize},
+    serde_json::{Map, Value},
+};
+
+/// Most rows one batch may carry.
+pub const MAX_BATCH_ROWS: usize = 10_000;
+
+/// Body format of `POST /ingest/batch`, picked by `Content-Type`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchFormat {
+    Csv,
+    Ndjson,
+}
+
+impl BatchFormat {
+    pub fn from_content_type(content_type: Option<&str>) -> Result<Self, DomainError> {
+        let mime = content_type
+            .and_then(|ct| ct.split(';').next())
+            .map(str::trim)
+            .unwrap_or("");
+        match mime {
+            "text/csv" => Ok(Self::Csv),
+            "application/x-ndjson" | "application/jsonl" => Ok(Self::Ndjson),
+            _ => Err(DomainError::Validation(format!(
+                "batch must be text/csv or application/x-ndjson, got: {mime:?}"
+            ))),
+        }
+    }
+}
+
+/// One normalized payment event from a settlement export: a CSV record
+/// (header names as below, `metadata` as a JSON object) or an NDJSON line.
+/// `event_id` identifies the row across uploads, so re-sending a file only
+/// yields duplicates.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IngestEvent {
+    pub event_id: String,
+    pub external_id: ExternalId,
+    pub source: String,
+    pub direction: PaymentDirection,
+    pub amount: i64,
+    pub currency: Currency,
+    pub status: PaymentStatus,
+    /// When the provider says it happened; orders events for one payment.
+    pub occurred_at: DateTime<Utc>,
+    #[serde(default)]
+    pub event_type: Option<String>,
+    #[serde(default)]
+    pub parent_external_id: Option<ExternalId>,
+    #[serde(default = "empty_metadata")]
+    pub metadata: Value,
+}
+
+fn empty_metadata() -> Value {
+    serde_json::json!({})
+}
+
+impl IngestEvent {
+    /// Pipeline input for this row. Its `event_id` becomes the synthetic
+    /// event id (`evt_ingest_<event_id>`), so the pipeline's dedup makes
+    /// re-ingesting a row a no-op. The row itself is stored as the raw event.
+    pub fn to_new_payment(&self) -> Result<NewPayment, DomainError> {
+        let valid_id = !self.event_id.is_empty()
+            && self.event_id.len() <= 128
+            && self
+                .event_id
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_');
+        if !valid_id {
+            return Err(DomainError::Validation(format!(
+                "event_id must be 1-128 of [A-Za-z0-9_-], got: {}",
+                self.event_id
+            )));
+        }
+        if self.source.trim().is_empty() {
+            return Err(DomainError::Validation("source must be set".into()));
+        }
+        let money = Money::new(MoneyAmount::new(self.amount)?, self.currency);
+
+        Ok(NewPayment::new(NewPaymentParams {
+            external_id: self.external_id.clone(),
+            source: self.source.clone(),
+            event_type: self
+                .event_type
+                .clone()
+                .unwrap_or_else(|| format!("ingest.{}", self.status.as_str())),
+            direction: self.direction.clone(),
+            money,
+            status: self.status.clone(),
+            metadata: self.metadata.clone(),
+            raw_event: serde_json::json!(self),
+            last_event_id: EventId::new(format!("evt_ingest_{}", self.event_id))?,
+            parent_external_id: self.parent_external_id.clone(),
+            provider_ts: self.occurred_at.timestamp(),
+            failure: None,
+            authorized_amount: None,
+            receipt: None,
+            tenant_id: None,
+            customer: None,
+        }))
+    }
+}
+
+/// A row of the batch, numbered from 1 in the order it appeared (data
+/// rows only for CSV), with the event it holds or why it couldn't be read.
+pub type ParsedRow = (usize, Result<IngestEvent, DomainError>);
+
+/// Split a batch into rows. A row that can't be read is returned as an
+/// error in its place; only an unusable batch as a whole (no CSV header,
+/// too many rows) fails outright.
+pub fn parse_batch(format: BatchFormat, body: &str) -> Result<Vec<ParsedRow>, DomainError> {
+    // Spreadsheet exports often start with a byte order mark.
+    let body = body.strip_prefix('\u{feff}').unwrap_or(body);
+    let rows: Vec<ParsedRow> = match format {
+        BatchFormat::Ndjson => body
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .enumerate()
+            .map(|(i, line)| {
+                let event = serde_json::from_str(line)
+                    .map_err(|e| DomainError::Validation(format!("invalid row: {e}")));
+                (i + 1, event)
+            })
+            .collect(),
+        BatchFormat::Csv => {
+            let mut records = read_csv(body)?.into_iter();
+            let header: Vec<String> = records
+                .next()
+                .ok_or_else(|| DomainError::Validation("CSV batch has no header".into()))?
+                .iter()
+                .map(|name| name.trim().to_string())
+                .collect();
+            records
+                .enumerate()
+                .map(|(i, record)| (i + 1, csv_event(&header, record)))
+                .collect()
+        }
+    };
+    if rows.len() > MAX_BATCH_ROWS {
+        return Err(DomainError::Validation(format!(
+            "batch has {} rows, at most {MAX_BATCH_ROWS} are accepted",
+            rows.len()
+        )));
+    }
+    Ok(rows)
+}
+
+/// A CSV record as an event. Empty cells count as absent; `amount` is read
+/// as a number and `metadata` as JSON, everything else as text.
+fn csv_event(header: &[String], record: Vec<String>) -> Result<IngestEvent, DomainError> {
+    if record.len() != header.len() {
+        return Err(DomainError::Validation(format!(
+            "row has {} fields, header has {}",
+            record.len(),
+            header.len()
+        )));
+    }
+    let mut fields = Map::new();
+    for (name, cell) in header.iter().zip(record) {
+        if cell.is_empty() {
+            continue;
+        }
+        let value =
+            match name.as_str() {
+                "amount" => cell.trim().parse::<i64>().map(Value::from).map_err(|_| {
+                    DomainError::Validation(format!("amount is not a number: {cell}"))
+                })?,
+                "metadata" => serde_json::from_str(&cell)
+                    .map_err(|e| DomainError::Validation(format!("metadata is not JSON: {e}")))?,
+                _ => Value::String(cell),
+            };
+        fields.insert(name.clone(), value);
+    }
+    serde_json::from_value(Value::Object(fields))
+        .map_err(|e| DomainError::Validation(format!("invalid row: {e}")))
+}
+
+/// RFC 4180 records: comma-separated, fields optionally quoted, quotes
+/// doubled inside quoted fields, which may span lines. Blank lines are
+/// skipped; `\r\n` and `\n` both end a record.
+fn read_csv(body: &str) -> Result<Vec<Vec<String>>, DomainError> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = body.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (quoted, c) {
+            (true, '"') if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            (true, '"') => quoted = false,
+            (true, c) => field.push(c),
+            (false, '"') if field.is_empty() => quoted = true,
+            (false, ',') => record.push(std::mem::take(&mut field)),
+            (false, '\r') if chars.peek() == Some(&'\n') => {}
+            (false, '\n') => {
+                record.push(std::mem::take(&mut field));
+                let blank = record.len() == 1 && record[0].is_empty();
+                let done = std::mem::take(&mut record);
+                if !blank {
+                    records.push(done);
+                }
+            }
+            (false, c) => field.push(c),
+        }
+    }
+    if quoted {
+        return Err(DomainError::Validation(
+            "CSV batch ends inside a quoted field".into(),
+        ));
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// What happened to one row.
+#[derive(Debug, Serialize)]
+pub struct RowResult {
+    pub row: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub external_id: Option<String>,
+    /// `created`, `updated`, `unchanged`, `stale_ignored`, `anomaly`,
+    /// `duplicate`, `diverted` or `error`.
+    pub result: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct IngestCounts {
+    pub created: u64,
+    pub updated: u64,
+    pub unchanged: u64,
+    pub stale_ignored: u64,
+    pub anomalies: u64,
+    pub duplicates: u64,
+    pub diverted: u64,
+    pub errors: u64,
+}
+
+impl IngestCounts {
+    pub fn record(&mut self, result: &ProcessResult) {
+        match result {
+            ProcessResult::Created(_) => self.created += 1,
+            ProcessResult::Updated(_) => self.updated += 1,
+            ProcessResult::Unchanged(_) => self.unchanged += 1,
+            ProcessResult::StaleIgnored(_) => self.stale_ignored += 1,
+            ProcessResult::Anomaly(_) => self.anomalies += 1,
+            ProcessResult::Duplicate => self.duplicates += 1,
+            ProcessResult::Diverted(_) => self.diverted += 1,
+            ProcessResult::Logged => {}
+        }
+    }
+}
+
+/// Result of `POST /ingest/batch`. Rows are applied one by one, each in its
+/// own transaction, so a bad row doesn't hold back the rest.
+#[derive(Debug, Serialize)]
+pub struct BatchSummary {
+    pub rows: usize,
+    pub counts: IngestCounts,
+    pub results: Vec<RowResult>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str =
+        "event_id,external_id,source,direction,amount,currency,status,occurred_at,metadata";
+
+    #[test]
+    fn csv_rows_are_read_with_quoting() {
+        let body = format!(
+            "{HEADER}\r\n\
+             stl-1,pi_stl_1,stripe,inbound,4999,usd,succeeded,2026-03-01T10:00:00Z,\"{{\"\"order_id\"\": \"\"1,234\"\"}}\"\r\n\
+             \r\n\
+             stl-2,pi_stl_2,stripe,inbound,lots,usd,succeeded,2026-03-01T10:00:00Z,\n\
+             stl-3,pi_stl_3,stripe\n"
+        );
+        let rows = parse_batch(BatchFormat::Csv, &body).unwrap();
+        assert_eq!(rows.len(), 3);
+        let (n, first) = &rows[0];
+        let first = first.as_ref().unwrap();
+        assert_eq!(*n, 1);
+        assert_eq!(first.amount, 4999);
+        assert_eq!(first.metadata["order_id"], "1,234");
+        assert!(first.event_type.is_none());
+        assert!(rows[1].1.is_err());
+        assert!(rows[2].1.is_err());
+
+        let p = first.to_new_payment().unwrap();
+        assert_eq!(p.last_event_id(), "evt_ingest_stl-1");
+        assert_eq!(p.event_type(), "ingest.succeeded");
+        assert_eq!(p.provider_ts(), 1_772_359_200);
+    }
+
+    #[test]
+    fn ndjson_rows_and_formats() {
+        let body = r#"{"event_id":"n1","external_id":"re_n1","source":"stripe","direction":"outbound","amount":100,"currency":"eur","status":"pending","occurred_at":"2026-03-01T10:00:00Z","parent_external_id":"pi_n0"}
+
+{"event_id":"n2"}"#;
+        let rows = parse_batch(BatchFormat::Ndjson, body).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1].0, 2);
+        assert!(rows[0].1.is_ok() && rows[1].1.is_err());
+
+        assert_eq!(
+            BatchFormat::from_content_type(Some("text/csv; charset=utf-8")),
+            Ok(BatchFormat::Csv)
+        );
+        assert!(BatchFormat::from_content_type(Some("application/json")).is_err());
+        assert!(parse_batch(BatchFormat::Csv, "").is_err());
+        assert!(parse_batch(BatchFormat::Csv, "a,\"b\n").is_err());
+    }
+}
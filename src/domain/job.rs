@@ -0,0 +1,291 @@
+use {
+    super::audit::NewAuditEntry,
+    chrono::{DateTime, Utc},
+    serde::{Deserialize, Serialize},
+    uuid::Uuid,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Pending,
+    Processing,
+    Completed,
+    /// Out of attempts: the dead-letter queue.
+    Failed,
+}
+
+impl JobStatus {
+    pub const ALL: [Self; 4] = [
+        Self::Pending,
+        Self::Processing,
+        Self::Completed,
+        Self::Failed,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Processing => "processing",
+            Self::Completed => "completed",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+/// Which worker claims a job. Refunds are customer-facing, so they have a
+/// lane of their own and never wait behind routine payment updates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobLane {
+    Standard,
+    Refund,
+}
+
+impl JobLane {
+    pub const ALL: [Self; 2] = [Self::Standard, Self::Refund];
+
+    /// The refund lane for Stripe and PayPal refunds, whatever the event.
+    pub fn for_object(object_id: &str) -> Self {
+        if object_id.starts_with("re_") || object_id.starts_with("pp_ref_") {
+            Self::Refund
+        } else {
+            Self::Standard
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Standard => "standard",
+            Self::Refund => "refund",
+        }
+    }
+}
+
+/// Which pending jobs of a lane are claimed first. Within a priority, jobs
+/// are claimed in the order they are due. The provider adapter picks it
+/// from the event type, so a backlog of events that move no money doesn't
+/// hold up the ones that do.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobPriority {
+    /// Intermediate steps with nothing to settle, e.g. an intent created.
+    Low,
+    #[default]
+    Normal,
+    /// Events that move money or settle a payment's outcome.
+    High,
+}
+
+impl JobPriority {
+    pub const ALL: [Self; 3] = [Self::Low, Self::Normal, Self::High];
+
+    /// The `payment_jobs.priority` value; higher is claimed first.
+    pub fn value(self) -> i16 {
+        match self {
+            Self::Low => 0,
+            Self::Normal => 1,
+            Self::High => 2,
+        }
+    }
+}
+
+/// How one attempt at a job ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AttemptOutcome {
+    Succeeded,
+    /// Completed without effect: garbage ids or a payload that will never
+    /// validate. Not retried.
+    Discarded,
+    /// Scheduled for retry, or dead-lettered if it was the last one.
+    Failed,
+    /// The worker went quiet and the reaper put the job back.
+    Abandoned,
+    /// Completed because the provider doesn't have the object. Not retried.
+    ProviderMissing,
+    /// Put off until an exhausted provider API budget has room again. Not
+    /// counted as an attempt.
+    Deferred,
+}
+
+impl AttemptOutcome {
+    pub const ALL: [Self; 6] = [
+        Self::Succeeded,
+        Self::Discarded,
+        Self::Failed,
+        Self::Abandoned,
+        Self::ProviderMissing,
+        Self::Deferred,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Succeeded => "succeeded",
+            Self::Discarded => "discarded",
+            Self::Failed => "failed",
+            Self::Abandoned => "abandoned",
+            Self::ProviderMissing => "provider_missing",
+            Self::Deferred => "deferred",
+        }
+    }
+}
+
+/// One `payment_jobs` row, as returned by the admin API.
+#[derive(Debug, Serialize)]
+pub struct JobView {
+    pub id: Uuid,
+    pub source: String,
+    pub event_id: String,
+    pub object_id: String,
+    pub event_type: String,
+    pub lane: String,
+    /// `JobPriority::value`: 0 low, 1 normal, 2 high.
+    pub priority: i16,
+    pub status: String,
+    pub attempts: i32,
+    pub max_attempts: i32,
+    pub last_error: Option<String>,
+    pub claimed_by: Option<String>,
+    pub scheduled_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// `GET /events/{event_id}/status`: where a queued event stands, for a
+/// client that got "accepted" from the webhook and wants to see it
+/// processed. `done` once the job is completed or dead-lettered; `result`
+/// (the pipeline's `ProcessResult`) and `payment_id` are only set if it
+/// succeeded, and `outcome` says how the latest attempt ended either way.
+#[derive(Debug, Serialize)]
+pub struct EventStatus {
+    /// The job's id, as returned on enqueue.
+    pub token: Uuid,
+    pub event_id: String,
+    pub source: String,
+    pub tenant_id: Option<String>,
+    pub status: String,
+    pub done: bool,
+    pub result: Option<String>,
+    pub payment_id: Option<Uuid>,
+    pub outcome: Option<String>,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// One `job_attempts` row. `outcome` and `finished_at` are unset while the
+/// attempt is running.
+#[derive(Debug, Serialize)]
+pub struct JobAttemptView {
+    pub attempt: i32,
+    pub worker: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub outcome: Option<String>,
+    pub error: Option<String>,
+}
+
+/// A job with every attempt at it, oldest first. The same error on each
+/// attempt points at a systemic failure; differing ones, at a flaky one.
+#[derive(Debug, Serialize)]
+pub struct JobDetail {
+    #[serde(flatten)]
+    pub job: JobView,
+    pub history: Vec<JobAttemptView>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct JobFilters {
+    pub status: Option<JobStatus>,
+    pub source: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// Which dead-lettered jobs a bulk requeue picks up. Both bounds optional:
+/// an empty body requeues every failed job.
+#[derive(Debug, Default, Deserialize)]
+pub struct RequeueFilter {
+    pub source: Option<String>,
+    /// Only jobs that failed at or after this time, e.g. the start of an outage.
+    pub failed_since: Option<DateTime<Utc>>,
+}
+
+impl JobView {
+    /// Audit entry for putting this dead-lettered job back in the queue.
+    pub fn retry_audit_entry(&self, actor: &str) -> NewAuditEntry {
+        let id = Uuid::now_v7();
+        NewAuditEntry {
+            id,
+            entity_type: "payment_job".to_string(),
+            entity_id: Some(self.id),
+            external_id: Some(self.object_id.clone()),
+            event_id: format!("job_retry:{id}"),
+            source: None,
+            tenant_id: None,
+            action: "job_retried".to_string(),
+            actor: actor.to_string(),
+            detail: serde_json::json!({
+                "event_id": self.event_id,
+                "attempts": self.attempts,
+                "last_error": self.last_error,
+            }),
+        }
+    }
+}
+
+/// A job completed because the provider doesn't have its object.
+pub struct MissingObject {
+    pub job_id: Uuid,
+    pub source: String,
+    pub event_id: String,
+    pub object_id: String,
+    pub event_type: String,
+    pub error: String,
+}
+
+impl MissingObject {
+    /// Keyed by the job's event, so a redelivery of it adds no second entry.
+    pub fn audit_entry(&self, payment_id: Option<Uuid>, actor: &str) -> NewAuditEntry {
+        NewAuditEntry {
+            id: Uuid::now_v7(),
+            entity_type: "payment".to_string(),
+            entity_id: payment_id,
+            external_id: Some(self.object_id.clone()),
+            event_id: format!("provider_missing:{}", self.event_id),
+            source: Some(self.source.clone()),
+            tenant_id: None,
+            action: "provider_missing".to_string(),
+            actor: actor.to_string(),
+            detail: serde_json::json!({
+                "job_id": self.job_id,
+                "event_id": self.event_id,
+                "event_type": self.event_type,
+                "error": self.error,
+            }),
+        }
+    }
+}
+
+impl RequeueFilter {
+    /// Audit entry for a bulk requeue that put `requeued` jobs back.
+    pub fn audit_entry(&self, requeued: u64, actor: &str) -> NewAuditEntry {
+        let id = Uuid::now_v7();
+        NewAuditEntry {
+            id,
+            entity_type: "payment_job".to_string(),
+            entity_id: None,
+            external_id: None,
+            event_id: format!("job_requeue:{id}"),
+            source: None,
+            tenant_id: None,
+            action: "jobs_requeued".to_string(),
+            actor: actor.to_string(),
+            detail: serde_json::json!({
+                "requeued": requeued,
+                "source": self.source,
+                "failed_since": self.failed_since,
+            }),
+        }
+    }
+}
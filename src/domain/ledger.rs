@@ -0,0 +1,285 @@
+use {
+    super::{
+        error::DomainError,
+        payment::{NewPayment, PaymentDirection, PaymentStatus},
+    },
+    chrono::{DateTime, Utc},
+    serde::Serialize,
+    std::{collections::BTreeMap, fmt},
+    uuid::Uuid,
+};
+
+/// The accounts payments post to. Balances are kept per payment, in its
+/// currency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LedgerAccount {
+    /// Money held at the provider or the bank.
+    Cash,
+    /// Settled inbound payments.
+    Revenue,
+    /// Money returned to customers (contra-revenue).
+    Refunds,
+    /// Money taken back by disputes, while open and once lost.
+    Disputes,
+    /// Settled outbound payments other than refunds.
+    PaymentsOut,
+}
+
+impl LedgerAccount {
+    pub const ALL: [Self; 5] = [
+        Self::Cash,
+        Self::Revenue,
+        Self::Refunds,
+        Self::Disputes,
+        Self::PaymentsOut,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Cash => "cash",
+            Self::Revenue => "revenue",
+            Self::Refunds => "refunds",
+            Self::Disputes => "disputes",
+            Self::PaymentsOut => "payments_out",
+        }
+    }
+}
+
+impl fmt::Display for LedgerAccount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl TryFrom<&str> for LedgerAccount {
+    type Error = DomainError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s {
+            "cash" => Ok(Self::Cash),
+            "revenue" => Ok(Self::Revenue),
+            "refunds" => Ok(Self::Refunds),
+            "disputes" => Ok(Self::Disputes),
+            "payments_out" => Ok(Self::PaymentsOut),
+            other => Err(DomainError::Validation(format!(
+                "unknown ledger account: {other}"
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Side {
+    Debit,
+    Credit,
+}
+
+impl Side {
+    pub const ALL: [Self; 2] = [Self::Debit, Self::Credit];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Debit => "debit",
+            Self::Credit => "credit",
+        }
+    }
+}
+
+impl TryFrom<&str> for Side {
+    type Error = DomainError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s {
+            "debit" => Ok(Self::Debit),
+            "credit" => Ok(Self::Credit),
+            other => Err(DomainError::Validation(format!(
+                "unknown ledger side: {other}"
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LedgerLine {
+    pub account: LedgerAccount,
+    pub side: Side,
+    /// Minor units, always positive.
+    pub amount: i64,
+}
+
+/// A payment's balance on one account: debits minus credits.
+pub type Balance = (LedgerAccount, i64);
+
+/// The balances a payment in `status` stands for, debits positive. Only
+/// settled money posts: pending, failed, expired and cancelled payments
+/// hold nothing, and a dispute won gives the money back.
+pub fn position(direction: &PaymentDirection, status: &PaymentStatus, amount: i64) -> Vec<Balance> {
+    use {LedgerAccount::*, PaymentStatus::*};
+    let (debit, credit) = match (direction, status) {
+        (_, Disputed | DisputeLost) => (Disputes, Cash),
+        (_, Refunded) => (Refunds, Cash),
+        (PaymentDirection::Inbound, Succeeded) => (Cash, Revenue),
+        (PaymentDirection::Outbound, Succeeded) => (PaymentsOut, Cash),
+        _ => return Vec::new(),
+    };
+    vec![(debit, amount), (credit, -amount)]
+}
+
+/// One balanced posting for a payment's status change.
+#[derive(Debug, Clone)]
+pub struct NewLedgerEntry {
+    pub id: Uuid,
+    pub payment_id: Uuid,
+    pub external_id: String,
+    pub source: String,
+    pub event_id: String,
+    pub currency: String,
+    pub from_status: Option<PaymentStatus>,
+    pub to_status: PaymentStatus,
+    pub lines: Vec<LedgerLine>,
+}
+
+impl NewLedgerEntry {
+    /// The entry moving a payment from its `current` balances to the ones
+    /// `payment`'s status calls for, or `None` if nothing moves. Posting the
+    /// difference rather than the transition keeps the ledger right when an
+    /// amount changed or a status was forced, and each entry balances since
+    /// both ends do.
+    pub fn for_change(
+        payment_id: Uuid,
+        payment: &NewPayment,
+        from_status: Option<&PaymentStatus>,
+        current: &[Balance],
+    ) -> Option<Self> {
+        let mut delta: BTreeMap<LedgerAccount, i64> = BTreeMap::new();
+        let target = position(
+            payment.direction(),
+            payment.status(),
+            payment.money().amount().cents(),
+        );
+        for (account, amount) in target {
+            *delta.entry(account).or_default() += amount;
+        }
+        for &(account, amount) in current {
+            *delta.entry(account).or_default() -= amount;
+        }
+        let lines: Vec<_> = delta
+            .into_iter()
+            .filter(|&(_, amount)| amount != 0)
+            .map(|(account, amount)| LedgerLine {
+                account,
+                side: if amount > 0 {
+                    Side::Debit
+                } else {
+                    Side::Credit
+                },
+                amount: amount.abs(),
+            })
+            .collect();
+        if lines.is_empty() {
+            return None;
+        }
+        Some(Self {
+            id: Uuid::now_v7(),
+            payment_id,
+            external_id: payment.external_id().to_string(),
+            source: payment.source().to_string(),
+            event_id: payment.last_event_id().to_string(),
+            currency: payment.money().currency().as_str().to_string(),
+            from_status: from_status.cloned(),
+            to_status: payment.status().clone(),
+            lines,
+        })
+    }
+}
+
+/// `GET /payments/{id}/ledger`: one posted entry with its lines.
+#[derive(Debug, Serialize)]
+pub struct LedgerEntryView {
+    pub id: Uuid,
+    pub event_id: String,
+    pub currency: String,
+    pub from_status: Option<String>,
+    pub to_status: String,
+    pub created_at: DateTime<Utc>,
+    pub lines: Vec<LedgerLine>,
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::domain::{
+            id::{EventId, ExternalId},
+            money::{Currency, Money, MoneyAmount},
+            payment::NewPaymentParams,
+        },
+    };
+
+    fn payment(direction: PaymentDirection, status: PaymentStatus, amount: i64) -> NewPayment {
+        NewPayment::new(NewPaymentParams {
+            external_id: ExternalId::new("pi_ledger").unwrap(),
+            source: "stripe".into(),
+            event_type: "payment_intent.succeeded".into(),
+            direction,
+            money: Money::new(MoneyAmount::new(amount).unwrap(), Currency::USD),
+            status,
+            metadata: serde_json::json!({}),
+            raw_event: serde_json::json!({}),
+            last_event_id: EventId::new("evt_1").unwrap(),
+            parent_external_id: None,
+            provider_ts: 1,
+            failure: None,
+            authorized_amount: None,
+            receipt: None,
+            tenant_id: None,
+            customer: None,
+        })
+    }
+
+    fn balances(entry: &NewLedgerEntry) -> Vec<Balance> {
+        entry
+            .lines
+            .iter()
+            .map(|l| match l.side {
+                Side::Debit => (l.account, l.amount),
+                Side::Credit => (l.account, -l.amount),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn status_changes_post_balanced_differences() {
+        use {LedgerAccount::*, PaymentDirection::*, PaymentStatus::*};
+        let id = Uuid::now_v7();
+
+        // Pending posts nothing; settling does.
+        assert!(
+            NewLedgerEntry::for_change(id, &payment(Inbound, Pending, 500), None, &[]).is_none()
+        );
+        let settled = payment(Inbound, Succeeded, 500);
+        let entry = NewLedgerEntry::for_change(id, &settled, Some(&Pending), &[]).unwrap();
+        assert_eq!(balances(&entry), [(Cash, 500), (Revenue, -500)]);
+        assert_eq!(entry.lines.iter().map(|l| l.amount).sum::<i64>(), 1000);
+
+        // A bounce reverses what was posted.
+        let bounced = payment(Inbound, Failed, 500);
+        let held = balances(&entry);
+        let reversal = NewLedgerEntry::for_change(id, &bounced, Some(&Succeeded), &held).unwrap();
+        assert_eq!(balances(&reversal), [(Cash, -500), (Revenue, 500)]);
+
+        // Posting the same state again moves nothing.
+        assert!(NewLedgerEntry::for_change(id, &settled, Some(&Succeeded), &held).is_none());
+
+        // Refunds and disputes draw on cash.
+        let refund = payment(Outbound, Refunded, 200);
+        let entry = NewLedgerEntry::for_change(id, &refund, Some(&Pending), &[]).unwrap();
+        assert_eq!(balances(&entry), [(Cash, -200), (Refunds, 200)]);
+        let won = payment(Outbound, DisputeWon, 300);
+        let held = position(&Outbound, &Disputed, 300);
+        let entry = NewLedgerEntry::for_change(id, &won, Some(&Disputed), &held).unwrap();
+        assert_eq!(balances(&entry), [(Cash, 300), (Disputes, -300)]);
+    }
+}
@@ -0,0 +1,170 @@
+use {
+    super::{audit::NewAuditEntry, error::DomainError, id::TenantId, payment::lock_key},
+    chrono::{DateTime, Utc},
+    serde::{Deserialize, Serialize},
+    uuid::Uuid,
+};
+
+/// Tables a transaction holding up payment processing would have locked.
+pub const PAYMENT_TABLES: &[&str] = &[
+    "payments",
+    "payments_v2",
+    "provider_events",
+    "payment_jobs",
+    "payment_captures",
+    "ledger_entries",
+    "ledger_lines",
+];
+
+/// How long a transaction has to have been open before it counts as
+/// long-running, and before it may be terminated, unless the request says.
+pub const DEFAULT_MIN_AGE_SECS: u64 = 30;
+
+/// `?min_age_secs=` can't go below this: a transaction a second old is
+/// doing its job, not stuck.
+pub const MIN_AGE_FLOOR_SECS: u64 = 1;
+
+/// `GET /admin/locks` query.
+#[derive(Debug, Default, Deserialize)]
+pub struct LockFilters {
+    /// Only the advisory lock that processing this payment takes.
+    pub external_id: Option<String>,
+    /// The payment's connected account, for `external_id`.
+    pub tenant_id: Option<TenantId>,
+    /// Transactions open at least this long are listed; default 30.
+    pub min_age_secs: Option<u64>,
+}
+
+impl LockFilters {
+    /// The advisory lock key asked for, as the pipeline builds it.
+    pub fn lock_key(&self) -> Option<String> {
+        let external_id = self.external_id.as_deref()?;
+        Some(lock_key(self.tenant_id.as_ref(), external_id))
+    }
+}
+
+/// The age threshold `requested` asks for, once checked.
+pub fn min_age_secs(requested: Option<u64>) -> Result<u64, DomainError> {
+    let secs = requested.unwrap_or(DEFAULT_MIN_AGE_SECS);
+    if !(MIN_AGE_FLOOR_SECS..=86_400).contains(&secs) {
+        return Err(DomainError::Validation(format!(
+            "min_age_secs must be between {MIN_AGE_FLOOR_SECS} and 86400"
+        )));
+    }
+    Ok(secs)
+}
+
+/// Body of `POST /admin/locks/{pid}/terminate`.
+#[derive(Debug, Deserialize)]
+pub struct TerminateRequest {
+    pub reason: String,
+    /// The backend's transaction must be open at least this long; default 30.
+    pub min_age_secs: Option<u64>,
+}
+
+impl TerminateRequest {
+    /// The age threshold, once the request is checked.
+    pub fn min_age_secs(&self) -> Result<u64, DomainError> {
+        if self.reason.trim().is_empty() || self.reason.len() > 500 {
+            return Err(DomainError::Validation(
+                "reason must be 1 to 500 characters".into(),
+            ));
+        }
+        min_age_secs(self.min_age_secs)
+    }
+}
+
+/// A database connection, from `pg_stat_activity`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Backend {
+    pub pid: i32,
+    pub application_name: String,
+    pub client_addr: Option<String>,
+    /// `active`, `idle in transaction`, ...
+    pub state: Option<String>,
+    pub xact_start: Option<DateTime<Utc>>,
+    pub xact_age_secs: Option<i64>,
+    pub wait_event_type: Option<String>,
+    pub wait_event: Option<String>,
+    /// The statement running, or the last one run when idle.
+    pub query: Option<String>,
+}
+
+/// An advisory lock held or awaited in this database. Payment processing
+/// locks `hashtextextended(<lock key>, 0)`, see `payment::lock_key`; the
+/// key can't be turned back into the payment, so look one up with
+/// `?external_id=`.
+#[derive(Debug, Clone, Serialize)]
+pub struct AdvisoryLock {
+    pub key: i64,
+    /// `false` while the backend is still waiting for it.
+    pub granted: bool,
+    pub backend: Backend,
+}
+
+/// A transaction open past the threshold with locks on `PAYMENT_TABLES`.
+#[derive(Debug, Clone, Serialize)]
+pub struct LongTransaction {
+    pub backend: Backend,
+    pub tables: Vec<String>,
+}
+
+/// `GET /admin/locks`.
+#[derive(Debug, Serialize)]
+pub struct LockReport {
+    /// The key looked up with `?external_id=`, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<i64>,
+    pub advisory_locks: Vec<AdvisoryLock>,
+    pub long_transactions: Vec<LongTransaction>,
+}
+
+impl Backend {
+    /// Audit entry for terminating this backend.
+    pub fn termination_audit_entry(&self, reason: &str, actor: &str) -> NewAuditEntry {
+        let id = Uuid::now_v7();
+        NewAuditEntry {
+            id,
+            entity_type: "backend".to_string(),
+            entity_id: None,
+            external_id: None,
+            event_id: format!("backend_terminated:{id}"),
+            source: None,
+            tenant_id: None,
+            action: "backend_terminated".to_string(),
+            actor: actor.to_string(),
+            detail: serde_json::json!({
+                "pid": self.pid,
+                "reason": reason,
+                "application_name": self.application_name,
+                "client_addr": self.client_addr,
+                "state": self.state,
+                "xact_start": self.xact_start,
+                "query": self.query,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lookups_use_the_pipeline_lock_key() {
+        let filters = LockFilters {
+            external_id: Some("pi_1".into()),
+            tenant_id: Some(TenantId::new("acct_1").unwrap()),
+            min_age_secs: None,
+        };
+        assert_eq!(filters.lock_key().as_deref(), Some("acct_1:pi_1"));
+        assert_eq!(LockFilters::default().lock_key(), None);
+    }
+
+    #[test]
+    fn min_age_is_bounded() {
+        assert_eq!(min_age_secs(None).unwrap(), DEFAULT_MIN_AGE_SECS);
+        assert!(min_age_secs(Some(0)).is_err());
+        assert!(min_age_secs(Some(86_401)).is_err());
+    }
+}
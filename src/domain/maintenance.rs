@@ -0,0 +1,79 @@
+use {
+    super::error::DomainError,
+    chrono::{DateTime, Utc},
+    serde::{Deserialize, Serialize},
+};
+
+/// How long maintenance lasts when the request doesn't say.
+pub const DEFAULT_DURATION_SECS: u64 = 3600;
+
+/// Longest maintenance window one request can open; a longer one has to
+/// be extended before it runs out.
+pub const MAX_DURATION_SECS: u64 = 24 * 3600;
+
+/// Body of `PUT /admin/maintenance`. Enabling again replaces the reason
+/// and restarts the window from now.
+#[derive(Debug, Deserialize)]
+pub struct MaintenanceRequest {
+    pub reason: String,
+    /// Seconds until maintenance ends on its own; default 1h, at most 24h.
+    pub duration_secs: Option<u64>,
+}
+
+impl MaintenanceRequest {
+    /// The window's length, once the request is checked.
+    pub fn duration_secs(&self) -> Result<u64, DomainError> {
+        if self.reason.trim().is_empty() || self.reason.len() > 500 {
+            return Err(DomainError::Validation(
+                "reason must be 1 to 500 characters".into(),
+            ));
+        }
+        let secs = self.duration_secs.unwrap_or(DEFAULT_DURATION_SECS);
+        if !(1..=MAX_DURATION_SECS).contains(&secs) {
+            return Err(DomainError::Validation(format!(
+                "duration_secs must be between 1 and {MAX_DURATION_SECS}"
+            )));
+        }
+        Ok(secs)
+    }
+}
+
+/// A maintenance window, as stored under `services::maintenance::MAINTENANCE`.
+/// It stays stored once it runs out; readers skip it from `expires_at`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceWindow {
+    pub reason: String,
+    pub enabled_by: String,
+    pub enabled_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl MaintenanceWindow {
+    pub fn is_live(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at > now
+    }
+
+    /// Whole seconds until the window expires, at least 1: what a refused
+    /// sender is told to wait.
+    pub fn retry_after_secs(&self, now: DateTime<Utc>) -> i64 {
+        (self.expires_at - now).num_seconds().max(1)
+    }
+}
+
+/// `GET /admin/maintenance`: whether ingestion is paused, and if so the
+/// window it is paused for.
+#[derive(Debug, Serialize)]
+pub struct MaintenanceStatus {
+    pub active: bool,
+    #[serde(flatten)]
+    pub window: Option<MaintenanceWindow>,
+}
+
+impl From<Option<MaintenanceWindow>> for MaintenanceStatus {
+    fn from(window: Option<MaintenanceWindow>) -> Self {
+        Self {
+            active: window.is_some(),
+            window,
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use {
+    super::{
+        error::DomainError,
+        id::{EventId, ExternalId},
+        money::{Currency, Money, MoneyAmount},
+        payment::{NewPayment, NewPaymentParams, PaymentDirection, PaymentStatus, PaymentView},
+    },
+    serde::{Deserialize, Serialize},
+    sha2::{Digest, Sha256},
+};
+
+/// Source recorded on payments created through `POST /payments`.
+pub const MANUAL_SOURCE: &str = "manual";
+
+/// Prefix for manually created payments, kept apart from provider ids.
+pub const MANUAL_ID_PREFIX: &str = "mp_";
+
+/// Caller-chosen key that makes `POST /payments` safe to retry. It becomes the
+/// synthetic event id, so the pipeline's event dedup enforces it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdempotencyKey(String);
+
+impl IdempotencyKey {
+    pub fn new(key: impl Into<String>) -> Result<Self, DomainError> {
+        let key = key.into();
+        let valid = !key.is_empty()
+            && key.len() <= 128
+            && key
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_');
+        if !valid {
+            return Err(DomainError::Validation(format!(
+                "idempotency key must be 1-128 of [A-Za-z0-9_-], got: {key}"
+            )));
+        }
+        Ok(Self(key))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn event_id(&self) -> EventId {
+        EventId::new(format!("evt_manual_{}", self.0)).expect("evt_ prefix is always present")
+    }
+}
+
+/// Body of `POST /payments`: a payment recorded by an internal system (cash,
+/// bank transfer) rather than reported by a provider. Posting again for the
+/// same `external_id` with a new status moves it through the state machine.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ManualPaymentRequest {
+    pub external_id: ExternalId,
+    pub direction: PaymentDirection,
+    pub amount: i64,
+    pub currency: Currency,
+    pub status: PaymentStatus,
+    #[serde(default)]
+    pub parent_external_id: Option<ExternalId>,
+    #[serde(default = "empty_metadata")]
+    pub metadata: serde_json::Value,
+}
+
+fn empty_metadata() -> serde_json::Value {
+    serde_json::json!({})
+}
+
+impl ManualPaymentRequest {
+    /// Pipeline input for this request. The request itself is stored as the
+    /// raw event so a replayed key can be checked against what it first carried.
+    pub fn to_new_payment(
+        &self,
+        key: &IdempotencyKey,
+        received_at: i64,
+    ) -> Result<NewPayment, DomainError> {
+        if !self.external_id.as_str().starts_with(MANUAL_ID_PREFIX) {
+            return Err(DomainError::Validation(format!(
+                "manual payment ids must start with {MANUAL_ID_PREFIX}, got: {}",
+                self.external_id
+            )));
+        }
+        if let Some(parent) = &self.parent_external_id {
+            ExternalId::new(parent.as_str())?;
+        }
+        let money = Money::new(MoneyAmount::new(self.amount)?, self.currency);
+
+        Ok(NewPayment::new(NewPaymentParams {
+            external_id: self.external_id.clone(),
+            source: MANUAL_SOURCE.to_string(),
+            event_type: format!("manual.{}", self.status.as_str()),
+            direction: self.direction.clone(),
+            money,
+            status: self.status.clone(),
+            metadata: self.metadata.clone(),
+            raw_event: serde_json::json!(self),
+            last_event_id: key.event_id(),
+            parent_external_id: self.parent_external_id.clone(),
+            provider_ts: received_at,
+            failure: None,
+            authorized_amount: None,
+            receipt: None,
+            tenant_id: None,
+            customer: None,
+        }))
+    }
+}
+
+impl ManualPaymentRequest {
+    /// Fingerprint of the request, telling a retry from a different
+    /// request sent under the same key.
+    pub fn hash(&self) -> String {
+        hex::encode(Sha256::digest(serde_json::json!(self).to_string()))
+    }
+}
+
+/// Result of `POST /payments`.
+#[derive(Debug)]
+pub enum ManualOutcome {
+    /// First time this payment was seen.
+    Created(PaymentView),
+    /// Status advanced, or already in it.
+    Applied(PaymentView),
+    /// The key was already used for this exact request; nothing changed.
+    /// Holds what the request came to the first time, with the payment as
+    /// it is now.
+    Replayed(Box<ManualOutcome>),
+    /// The key was already used for a different request.
+    KeyReused,
+    /// The state machine refused the new status.
+    Rejected { current: PaymentStatus },
+}
+
+impl ManualOutcome {
+    /// Stored in `manual_requests.outcome`; `None` for outcomes that
+    /// aren't a first answer to a request.
+    pub fn as_str(&self) -> Option<&'static str> {
+        match self {
+            Self::Created(_) => Some("created"),
+            Self::Applied(_) => Some("applied"),
+            Self::Rejected { .. } => Some("rejected"),
+            Self::Replayed(_) | Self::KeyReused => None,
+        }
+    }
+}
+
+/// A `manual_requests` row: what a request first came to.
+#[derive(Debug)]
+pub struct StoredManualRequest {
+    pub request_hash: String,
+    pub outcome: String,
+    pub rejected_from: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(external_id: &str) -> ManualPaymentRequest {
+        ManualPaymentRequest {
+            external_id: ExternalId::new(external_id).unwrap(),
+            direction: PaymentDirection::Inbound,
+            amount: 2500,
+            currency: Currency::EUR,
+            status: PaymentStatus::Succeeded,
+            parent_external_id: None,
+            metadata: empty_metadata(),
+        }
+    }
+
+    #[test]
+    fn idempotency_key_maps_to_event_id() {
+        let key = IdempotencyKey::new("erp-42_a").unwrap();
+        assert_eq!(key.event_id().as_str(), "evt_manual_erp-42_a");
+        for bad in ["", "has space", "semi;colon", &"x".repeat(129)] {
+            assert!(IdempotencyKey::new(bad).is_err(), "{bad:?}");
+        }
+    }
+
+    #[test]
+    fn manual_payment_uses_manual_source_and_ids() {
+        let key = IdempotencyKey::new("k1").unwrap();
+        let p = request("mp_cash_1").to_new_payment(&key, 100).unwrap();
+        assert_eq!(p.source(), MANUAL_SOURCE);
+        assert_eq!(p.event_type(), "manual.succeeded");
+        assert_eq!(p.last_event_id(), "evt_manual_k1");
+        assert_eq!(p.raw_event()["external_id"], "mp_cash_1");
+
+        assert!(request("pi_cash_1").to_new_payment(&key, 100).is_err());
+    }
+}
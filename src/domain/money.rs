@@ -1,17 +1,20 @@
 use {
-    super::error::PipelineError,
+    super::error::DomainError,
     serde::{Deserialize, Serialize},
-    std::fmt,
-    std::ops::{Add, Sub},
+    std::{
+        cmp::Ordering,
+        fmt,
+        ops::{Add, Sub},
+    },
 };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct MoneyAmount(i64);
 
 impl MoneyAmount {
-    pub fn new(cents: i64) -> Result<Self, PipelineError> {
+    pub fn new(cents: i64) -> Result<Self, DomainError> {
         if cents < 0 {
-            return Err(PipelineError::Validation(format!(
+            return Err(DomainError::Validation(format!(
                 "MoneyAmount cannot be negative, got: {cents}"
             )));
         }
@@ -56,26 +59,79 @@ impl fmt::Display for MoneyAmount {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
-pub enum Currency {
-    Usd,
-    Eur,
-    Gbp,
-    Jpy,
+/// An ISO 4217 currency, written as its lowercase alphabetic code (`usd`),
+/// the way providers and the database spell it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Currency {
+    code: &'static str,
+    minor_units: u32,
 }
 
 impl Currency {
+    pub const USD: Self = Self::known("usd", 2);
+    pub const EUR: Self = Self::known("eur", 2);
+    pub const GBP: Self = Self::known("gbp", 2);
+    pub const JPY: Self = Self::known("jpy", 0);
+
+    const fn known(code: &'static str, minor_units: u32) -> Self {
+        Self { code, minor_units }
+    }
+
     pub fn as_str(&self) -> &'static str {
-        match self {
-            Self::Usd => "usd",
-            Self::Eur => "eur",
-            Self::Gbp => "gbp",
-            Self::Jpy => "jpy",
-        }
+        self.code
+    }
+
+    /// Every currency `TryFrom` accepts, by code.
+    pub fn all() -> impl Iterator<Item = Self> {
+        ISO_4217
+            .iter()
+            .map(|&(code, minor_units)| Self::known(code, minor_units))
+    }
+
+    /// Decimal places in the currency's minor unit: 2 for cents, 0 for JPY,
+    /// 3 for KWD. Every amount in this service is counted in minor units.
+    pub fn minor_units(&self) -> u32 {
+        self.minor_units
     }
 }
 
+/// ISO 4217 codes with their minor units, sorted by code. Besides the
+/// active list this keeps the retired codes Stripe still accepts (`eek`,
+/// `hrk`, `ltl`, `lvl`, `mro`, `sll`, `std`, `vef`).
+#[rustfmt::skip]
+const ISO_4217: &[(&str, u32)] = &[
+    ("aed", 2), ("afn", 2), ("all", 2), ("amd", 2), ("ang", 2), ("aoa", 2),
+    ("ars", 2), ("aud", 2), ("awg", 2), ("azn", 2), ("bam", 2), ("bbd", 2),
+    ("bdt", 2), ("bgn", 2), ("bhd", 3), ("bif", 0), ("bmd", 2), ("bnd", 2),
+    ("bob", 2), ("bov", 2), ("brl", 2), ("bsd", 2), ("btn", 2), ("bwp", 2),
+    ("byn", 2), ("bzd", 2), ("cad", 2), ("cdf", 2), ("che", 2), ("chf", 2),
+    ("chw", 2), ("clf", 4), ("clp", 0), ("cny", 2), ("cop", 2), ("cou", 2),
+    ("crc", 2), ("cuc", 2), ("cup", 2), ("cve", 2), ("czk", 2), ("djf", 0),
+    ("dkk", 2), ("dop", 2), ("dzd", 2), ("eek", 2), ("egp", 2), ("ern", 2),
+    ("etb", 2), ("eur", 2), ("fjd", 2), ("fkp", 2), ("gbp", 2), ("gel", 2),
+    ("ghs", 2), ("gip", 2), ("gmd", 2), ("gnf", 0), ("gtq", 2), ("gyd", 2),
+    ("hkd", 2), ("hnl", 2), ("hrk", 2), ("htg", 2), ("huf", 2), ("idr", 2),
+    ("ils", 2), ("inr", 2), ("iqd", 3), ("irr", 2), ("isk", 0), ("jmd", 2),
+    ("jod", 3), ("jpy", 0), ("kes", 2), ("kgs", 2), ("khr", 2), ("kmf", 0),
+    ("kpw", 2), ("krw", 0), ("kwd", 3), ("kyd", 2), ("kzt", 2), ("lak", 2),
+    ("lbp", 2), ("lkr", 2), ("lrd", 2), ("lsl", 2), ("ltl", 2), ("lvl", 2),
+    ("lyd", 3), ("mad", 2), ("mdl", 2), ("mga", 2), ("mkd", 2), ("mmk", 2),
+    ("mnt", 2), ("mop", 2), ("mro", 2), ("mru", 2), ("mur", 2), ("mvr", 2),
+    ("mwk", 2), ("mxn", 2), ("mxv", 2), ("myr", 2), ("mzn", 2), ("nad", 2),
+    ("ngn", 2), ("nio", 2), ("nok", 2), ("npr", 2), ("nzd", 2), ("omr", 3),
+    ("pab", 2), ("pen", 2), ("pgk", 2), ("php", 2), ("pkr", 2), ("pln", 2),
+    ("pyg", 0), ("qar", 2), ("ron", 2), ("rsd", 2), ("rub", 2), ("rwf", 0),
+    ("sar", 2), ("sbd", 2), ("scr", 2), ("sdg", 2), ("sek", 2), ("sgd", 2),
+    ("shp", 2), ("sle", 2), ("sll", 2), ("sos", 2), ("srd", 2), ("ssp", 2),
+    ("std", 2), ("stn", 2), ("svc", 2), ("syp", 2), ("szl", 2), ("thb", 2),
+    ("tjs", 2), ("tmt", 2), ("tnd", 3), ("top", 2), ("try", 2), ("ttd", 2),
+    ("twd", 2), ("tzs", 2), ("uah", 2), ("ugx", 0), ("usd", 2), ("usn", 2),
+    ("uyi", 0), ("uyu", 2), ("uyw", 4), ("uzs", 2), ("ved", 2), ("vef", 2),
+    ("ves", 2), ("vnd", 0), ("vuv", 0), ("wst", 2), ("xaf", 0), ("xcd", 2),
+    ("xof", 0), ("xpf", 0), ("yer", 2), ("zar", 2), ("zmw", 2), ("zwg", 2),
+    ("zwl", 2),
+];
+
 impl fmt::Display for Currency {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.as_str())
@@ -83,18 +139,36 @@ impl fmt::Display for Currency {
 }
 
 impl TryFrom<&str> for Currency {
-    type Error = PipelineError;
+    type Error = DomainError;
 
+    /// Case-insensitive: `USD` and `usd` are the same currency.
     fn try_from(s: &str) -> Result<Self, Self::Error> {
-        match s {
-            "usd" => Ok(Self::Usd),
-            "eur" => Ok(Self::Eur),
-            "gbp" => Ok(Self::Gbp),
-            "jpy" => Ok(Self::Jpy),
-            other => Err(PipelineError::Validation(format!(
-                "unknown currency: {other}"
-            ))),
-        }
+        let code = s.to_ascii_lowercase();
+        ISO_4217
+            .binary_search_by(|(c, _)| (*c).cmp(code.as_str()))
+            .map(|i| Self::known(ISO_4217[i].0, ISO_4217[i].1))
+            .map_err(|_| DomainError::Validation(format!("unknown currency: {s}")))
+    }
+}
+
+impl TryFrom<String> for Currency {
+    type Error = DomainError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Self::try_from(s.as_str())
+    }
+}
+
+impl Serialize for Currency {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.code)
+    }
+}
+
+impl<'de> Deserialize<'de> for Currency {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let code = String::deserialize(deserializer)?;
+        Self::try_from(code).map_err(serde::de::Error::custom)
     }
 }
 
@@ -116,4 +190,216 @@ impl Money {
     pub fn currency(&self) -> &Currency {
         &self.currency
     }
+
+    /// Parse a decimal amount in major units (`"10.99"`, `"1000"` for JPY)
+    /// into minor units. More decimals than the currency has are rejected
+    /// rather than rounded.
+    pub fn from_major(value: &str, currency: Currency) -> Result<Self, DomainError> {
+        let places = currency.minor_units() as usize;
+        let invalid = || DomainError::Validation(format!("invalid {currency} amount: {value}"));
+
+        let (whole, frac) = value.split_once('.').unwrap_or((value, ""));
+        if whole.is_empty()
+            || frac.len() > places
+            || !whole
+                .bytes()
+                .chain(frac.bytes())
+                .all(|b| b.is_ascii_digit())
+        {
+            return Err(invalid());
+        }
+        let minor: i64 = format!("{whole}{frac:0<places$}")
+            .parse()
+            .map_err(|_| invalid())?;
+        Ok(Self::new(MoneyAmount::new(minor)?, currency))
+    }
+
+    pub fn zero(currency: Currency) -> Self {
+        Self::new(MoneyAmount(0), currency)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.amount.0 == 0
+    }
+
+    /// Sum of two amounts in the same currency.
+    pub fn try_add(&self, other: &Money) -> Result<Money, DomainError> {
+        self.ensure_same_currency(other)?;
+        let amount = self
+            .amount
+            .checked_add(other.amount)
+            .ok_or_else(|| DomainError::Validation(format!("{self} + {other} overflows")))?;
+        Ok(Self::new(amount, self.currency))
+    }
+
+    /// Difference of two amounts in the same currency; going below zero is
+    /// an error, not a negative amount.
+    pub fn try_sub(&self, other: &Money) -> Result<Money, DomainError> {
+        self.ensure_same_currency(other)?;
+        let amount = self
+            .amount
+            .checked_sub(other.amount)
+            .ok_or_else(|| DomainError::Validation(format!("{self} - {other} is negative")))?;
+        Ok(Self::new(amount, self.currency))
+    }
+
+    /// Order two amounts; amounts in different currencies don't compare.
+    pub fn try_cmp(&self, other: &Money) -> Result<Ordering, DomainError> {
+        self.ensure_same_currency(other)?;
+        Ok(self.amount.0.cmp(&other.amount.0))
+    }
+
+    /// Total of `items`, all in `currency`.
+    pub fn try_sum<'a>(
+        currency: Currency,
+        items: impl IntoIterator<Item = &'a Money>,
+    ) -> Result<Money, DomainError> {
+        items
+            .into_iter()
+            .try_fold(Self::zero(currency), |total, m| total.try_add(m))
+    }
+
+    /// Split into parts proportional to `weights`, summing exactly to the
+    /// whole. Minor units lost to rounding go to the parts with the largest
+    /// remainders, the earlier part winning a tie, so the same input always
+    /// splits the same way.
+    pub fn allocate(&self, weights: &[u64]) -> Result<Vec<Money>, DomainError> {
+        let total: u128 = weights.iter().map(|&w| u128::from(w)).sum();
+        if total == 0 {
+            return Err(DomainError::Validation(
+                "allocation needs at least one non-zero weight".into(),
+            ));
+        }
+        let whole = self.amount.0 as u128;
+        let mut parts: Vec<u128> = Vec::with_capacity(weights.len());
+        let mut remainders: Vec<(u128, usize)> = Vec::with_capacity(weights.len());
+        for (i, &w) in weights.iter().enumerate() {
+            let share = whole * u128::from(w);
+            parts.push(share / total);
+            remainders.push((share % total, i));
+        }
+        let left = whole - parts.iter().sum::<u128>();
+        remainders.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        for &(_, i) in remainders.iter().take(left as usize) {
+            parts[i] += 1;
+        }
+        // Every part is at most the whole, so it fits back into an i64.
+        Ok(parts
+            .into_iter()
+            .map(|p| Self::new(MoneyAmount(p as i64), self.currency))
+            .collect())
+    }
+
+    /// Split off a fee of `bps` basis points, rounded half up to the minor
+    /// unit. Returns `(fee, rest)`; the two always add up to `self`.
+    pub fn split_fee(&self, bps: u32) -> Result<(Money, Money), DomainError> {
+        if bps > 10_000 {
+            return Err(DomainError::Validation(format!(
+                "fee must be at most 10000 bps, got: {bps}"
+            )));
+        }
+        let fee = (i128::from(self.amount.0) * i128::from(bps) + 5_000) / 10_000;
+        let fee = Self::new(MoneyAmount(fee as i64), self.currency);
+        let rest = self.try_sub(&fee)?;
+        Ok((fee, rest))
+    }
+
+    fn ensure_same_currency(&self, other: &Money) -> Result<(), DomainError> {
+        if self.currency != other.currency {
+            return Err(DomainError::Validation(format!(
+                "currency mismatch: {} vs {}",
+                self.currency, other.currency
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.amount, self.currency)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn currencies_know_their_minor_units() {
+        let units = |code: &str| Currency::try_from(code).map(|c| c.minor_units());
+        assert_eq!(units("usd"), Ok(2));
+        assert_eq!(units("JPY"), Ok(0));
+        assert_eq!(units("kwd"), Ok(3));
+        assert_eq!(units("clf"), Ok(4));
+        assert!(units("xyz").is_err() && units("us").is_err());
+        assert!(ISO_4217.windows(2).all(|w| w[0].0 < w[1].0));
+
+        let chf: Currency = serde_json::from_str(r#""CHF""#).unwrap();
+        assert_eq!(serde_json::to_string(&chf).unwrap(), r#""chf""#);
+
+        let major = |v: &str, c: &str| {
+            Money::from_major(v, Currency::try_from(c).unwrap()).map(|m| m.amount().cents())
+        };
+        assert_eq!(major("12.345", "kwd"), Ok(12_345));
+        assert_eq!(major("12.5", "kwd"), Ok(12_500));
+        assert!(major("1.5", "jpy").is_err());
+    }
+
+    fn money(cents: i64, currency: Currency) -> Money {
+        Money::new(MoneyAmount::new(cents).unwrap(), currency)
+    }
+
+    #[test]
+    fn arithmetic_refuses_mixed_currencies() {
+        let usd = money(1000, Currency::USD);
+        assert_eq!(
+            usd.try_add(&money(250, Currency::USD)),
+            Ok(money(1250, Currency::USD))
+        );
+        assert_eq!(
+            usd.try_sub(&money(250, Currency::USD)),
+            Ok(money(750, Currency::USD))
+        );
+        assert!(usd.try_sub(&money(1001, Currency::USD)).is_err());
+        assert!(usd.try_add(&money(i64::MAX, Currency::USD)).is_err());
+
+        let eur = money(250, Currency::EUR);
+        assert!(usd.try_add(&eur).is_err());
+        assert!(usd.try_sub(&eur).is_err());
+        assert!(usd.try_cmp(&eur).is_err());
+        assert_eq!(
+            usd.try_cmp(&money(999, Currency::USD)),
+            Ok(Ordering::Greater)
+        );
+
+        let items = [usd.clone(), money(5, Currency::USD)];
+        assert_eq!(
+            Money::try_sum(Currency::USD, &items),
+            Ok(money(1005, Currency::USD))
+        );
+        assert!(Money::try_sum(Currency::EUR, &items).is_err());
+        assert!(Money::zero(Currency::JPY).is_zero());
+    }
+
+    #[test]
+    fn allocation_keeps_every_minor_unit() {
+        let cents =
+            |parts: Vec<Money>| parts.iter().map(|m| m.amount().cents()).collect::<Vec<_>>();
+        let usd = money(100, Currency::USD);
+        assert_eq!(cents(usd.allocate(&[1, 1, 1]).unwrap()), [34, 33, 33]);
+        assert_eq!(cents(usd.allocate(&[1, 2]).unwrap()), [33, 67]);
+        assert_eq!(cents(usd.allocate(&[0, 3]).unwrap()), [0, 100]);
+        assert_eq!(
+            cents(money(5, Currency::USD).allocate(&[3, 3, 1]).unwrap()),
+            [2, 2, 1]
+        );
+        assert!(usd.allocate(&[]).is_err() && usd.allocate(&[0]).is_err());
+
+        let (fee, rest) = money(1999, Currency::USD).split_fee(290).unwrap();
+        assert_eq!((fee.amount().cents(), rest.amount().cents()), (58, 1941));
+        let (fee, _) = money(50, Currency::USD).split_fee(100).unwrap();
+        assert_eq!(fee.amount().cents(), 1);
+        assert!(usd.split_fee(10_001).is_err());
+    }
 }
@@ -0,0 +1,77 @@
+use {
+    super::{audit::AuditRecord, error::DomainError},
+    chrono::{DateTime, Utc},
+    serde::{Deserialize, Serialize},
+};
+
+/// An audit entry with its place in the outbox.
+#[derive(Debug, Clone, Serialize)]
+pub struct OutboxEvent {
+    pub seq: i64,
+    #[serde(flatten)]
+    pub entry: AuditRecord,
+}
+
+/// One page of `GET /outbox/events`. `last_seq` is the offset to ask from
+/// next: the last event's seq, or the requested offset when there are none.
+#[derive(Debug, Serialize)]
+pub struct OutboxPage {
+    pub events: Vec<OutboxEvent>,
+    pub last_seq: i64,
+}
+
+/// `GET /outbox/events?after_seq=&consumer=&limit=&wait=`. Without
+/// `after_seq`, a named consumer reads from its committed offset. `wait`
+/// holds an empty answer open for up to that many seconds.
+#[derive(Debug, Default, Deserialize)]
+pub struct OutboxQuery {
+    pub after_seq: Option<i64>,
+    pub consumer: Option<String>,
+    pub limit: Option<i64>,
+    pub wait: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConsumerOffset {
+    pub consumer: String,
+    pub last_seq: i64,
+    /// Numbered events past `last_seq`.
+    pub lag: i64,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Body of `PUT /outbox/consumers/{consumer}/offset`.
+#[derive(Debug, Deserialize)]
+pub struct OffsetCommit {
+    pub last_seq: i64,
+}
+
+/// Consumer names are chosen by the consumers themselves: up to 100 ASCII
+/// letters, digits, `.`, `_` and `-`.
+pub fn validate_consumer(name: &str) -> Result<(), DomainError> {
+    let valid = !name.is_empty()
+        && name.len() <= 100
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'.' | b'_' | b'-'));
+    if valid {
+        Ok(())
+    } else {
+        Err(DomainError::Validation(format!(
+            "invalid consumer name: {name:?}"
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn consumer_names_are_plain_identifiers() {
+        assert!(validate_consumer("erp-sync.v2").is_ok());
+        assert!(validate_consumer("").is_err());
+        assert!(validate_consumer("erp sync").is_err());
+        assert!(validate_consumer(&"x".repeat(101)).is_err());
+    }
+}
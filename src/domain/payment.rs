@@ -1,13 +1,19 @@
 use {
     super::{
         audit::NewAuditEntry,
-        error::PipelineError,
-        id::{EventId, ExternalId},
-        money::Money,
+        capture::{CaptureSummary, NewCapture},
+        error::DomainError,
+        event_order::{self, EventOrder},
+        feed,
+        id::{EventId, ExternalId, TenantId},
+        money::{Money, MoneyAmount},
+        payout::NewPayout,
+        tag::Tag,
+        transition,
     },
     crate::domain::money::Currency,
     serde::{Deserialize, Serialize},
-    std::fmt,
+    std::{fmt, str::FromStr},
     uuid::Uuid,
 };
 
@@ -19,40 +25,128 @@ pub enum ProcessResult {
     Created(Uuid),
     /// Existing payment row updated (status advanced).
     Updated(Uuid),
-    /// Event is older than what we've already processed — no state change.
-    Stale(Uuid),
+    /// Event repeats the status the payment already has; its details are
+    /// kept, but nothing moves.
+    Unchanged(Uuid),
+    /// Event is older than what we've already applied (out of order, or
+    /// behind a fresher feed) — recorded, not applied.
+    StaleIgnored(Uuid),
     /// Stripe event was already processed (duplicate delivery).
     Duplicate,
     /// Transition is not valid per state machine — logged as anomaly.
     Anomaly(Uuid),
+    /// Status change dated inside a closed accounting period — held as a
+    /// period adjustment for an operator, not applied.
+    Diverted(Uuid),
     /// Passthrough event (charge, unknown) — audit-logged only, no payment row.
     Logged,
 }
 
+impl ProcessResult {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Created(_) => "created",
+            Self::Updated(_) => "updated",
+            Self::Unchanged(_) => "unchanged",
+            Self::StaleIgnored(_) => "stale_ignored",
+            Self::Duplicate => "duplicate",
+            Self::Anomaly(_) => "anomaly",
+            Self::Diverted(_) => "diverted",
+            Self::Logged => "logged",
+        }
+    }
+
+    /// The payment row the event was applied to, if it reached one.
+    pub fn payment_id(&self) -> Option<Uuid> {
+        match self {
+            Self::Created(id)
+            | Self::Updated(id)
+            | Self::Unchanged(id)
+            | Self::StaleIgnored(id)
+            | Self::Anomaly(id)
+            | Self::Diverted(id) => Some(*id),
+            Self::Duplicate | Self::Logged => None,
+        }
+    }
+}
+
+/// The advisory lock key for payment `external_id` of `tenant`: the id
+/// itself for the platform's own objects, `<tenant>:<id>` otherwise.
+pub fn lock_key(tenant: Option<&TenantId>, external_id: &str) -> String {
+    match tenant {
+        Some(tenant) => format!("{tenant}:{external_id}"),
+        None => external_id.to_string(),
+    }
+}
+
 // ── Existing payment (read model for decisions) ──────────────────────────────
 
 /// Current state of a payment row, returned by repo for decision-making.
 pub struct ExistingPayment {
     pub id: Uuid,
     pub status: PaymentStatus,
+    pub tenant_id: Option<String>,
+    /// `created` of the newest event applied or touched so far.
+    pub last_provider_ts: i64,
+    /// That event's id, and the API request behind it if any.
+    pub last_event_id: String,
+    pub last_request_id: Option<String>,
 }
 
 // ── Decision types ───────────────────────────────────────────────────────────
 
 pub enum PaymentAction {
-    Advance { old_status: PaymentStatus },
+    Advance {
+        old_status: PaymentStatus,
+    },
     SameStatus,
-    LogAnomaly { current: PaymentStatus },
+    /// The same status, from an event older than the newest one seen.
+    Stale,
+    LogAnomaly {
+        current: PaymentStatus,
+    },
 }
 
 impl ExistingPayment {
+    /// Whether `incoming` may change this payment: its own account's events
+    /// do, and so do the platform's, which sees all its connected accounts'.
+    /// A connected account's events never touch the platform's payments or
+    /// another account's.
+    pub fn admits(&self, incoming: &NewPayment) -> bool {
+        incoming
+            .tenant_id()
+            .is_none_or(|t| self.tenant_id.as_deref() == Some(t.as_str()))
+    }
+
+    fn last_event(&self) -> EventOrder<'_> {
+        EventOrder {
+            provider_ts: self.last_provider_ts,
+            event_id: &self.last_event_id,
+            request_id: self.last_request_id.as_deref(),
+        }
+    }
+
     /// Pure decision: what action to take given an incoming payment event.
     /// Called only when an existing row is found — the `None` (insert) case
     /// is handled by the caller before reaching this method.
+    ///
+    /// An event older than the last one only repeats what's known if its
+    /// status is the same; with another status the state machine decides.
+    /// Within the same second, though, a burst's events are ordered as
+    /// [`EventOrder::compare`] says, and one behind the last is stale
+    /// whatever its status.
     pub fn decide(&self, incoming: &NewPayment) -> PaymentAction {
-        if *incoming.status() == self.status {
+        let same_status = *incoming.status() == self.status;
+        let same_second = incoming.provider_ts() == self.last_provider_ts;
+        if (same_status || same_second) && incoming.order().is_before(&self.last_event()) {
+            PaymentAction::Stale
+        } else if same_status {
             PaymentAction::SameStatus
-        } else if !self.status.can_transition_to(incoming.status()) {
+        } else if !transition::for_source(incoming.source()).allows(
+            incoming.direction(),
+            &self.status,
+            incoming.status(),
+        ) {
             PaymentAction::LogAnomaly {
                 current: self.status.clone(),
             }
@@ -66,8 +160,12 @@ impl ExistingPayment {
 
 // ── Webhook trigger ──────────────────────────────────────────────────────────
 
-/// Payment event data extracted from a webhook (PI or Refund).
+/// Payment event data extracted from a webhook (PI, Refund or Dispute).
 pub struct PaymentTrigger {
+    /// Provider the event came from; event ids are only unique within one.
+    pub source: String,
+    /// Connected account the event was sent for; `None` for the platform's.
+    pub tenant_id: Option<TenantId>,
     pub event_id: EventId,
     pub event_type: String,
     pub external_id: ExternalId,
@@ -76,19 +174,41 @@ pub struct PaymentTrigger {
 }
 
 /// Signal extracted from a webhook event. The handler builds this to dispatch
-/// between enqueue (payment) and sync processing (passthrough).
+/// between enqueue (payment) and sync processing (payout, capture,
+/// passthrough).
 pub enum WebhookTrigger {
-    /// PI or Refund — enqueue for async processing.
+    /// PI, Refund or Dispute — enqueue for async processing.
     Payment(PaymentTrigger),
+    /// Payout or transfer — processed at once from the object in the event.
+    Payout(Box<NewPayout>),
+    /// Charge capture — recorded at once against its PaymentIntent.
+    Capture(Box<NewCapture>),
     /// Charge / unknown — log only.
     Passthrough(PassthroughEvent),
 }
 
+// ── Provider event ───────────────────────────────────────────────────────────
+
+/// A `provider_events` row to record for dedup.
+#[derive(Debug, Clone, Copy)]
+pub struct NewProviderEvent<'a> {
+    pub source: &'a str,
+    pub tenant_id: Option<&'a str>,
+    pub event_id: &'a str,
+    /// The object the event is about; empty when it names none.
+    pub object_id: &'a str,
+    pub event_type: &'a str,
+    pub provider_ts: i64,
+    pub payload: &'a serde_json::Value,
+}
+
 // ── Passthrough event ────────────────────────────────────────────────────────
 
 /// Event that we log but don't process as a payment (charges, unknown types).
 pub struct PassthroughEvent {
     pub external_id: Option<ExternalId>,
+    pub source: String,
+    pub tenant_id: Option<TenantId>,
     pub event_id: EventId,
     pub event_type: String,
     pub provider_ts: i64,
@@ -103,29 +223,67 @@ pub enum PaymentStatus {
     Failed,
     Pending,
     Refunded,
+    Disputed,
+    DisputeWon,
+    DisputeLost,
+    /// An authorization that lapsed before it was captured.
+    Expired,
+    /// Called off before it completed (an abandoned checkout, a canceled
+    /// refund); unlike `Failed`, nothing was declined.
+    Cancelled,
 }
 
 impl PaymentStatus {
+    pub const ALL: [Self; 9] = [
+        Self::Pending,
+        Self::Succeeded,
+        Self::Failed,
+        Self::Refunded,
+        Self::Disputed,
+        Self::DisputeWon,
+        Self::DisputeLost,
+        Self::Expired,
+        Self::Cancelled,
+    ];
+
     pub fn as_str(&self) -> &'static str {
         match self {
             Self::Succeeded => "succeeded",
             Self::Failed => "failed",
             Self::Pending => "pending",
             Self::Refunded => "refunded",
+            Self::Disputed => "disputed",
+            Self::DisputeWon => "dispute_won",
+            Self::DisputeLost => "dispute_lost",
+            Self::Expired => "expired",
+            Self::Cancelled => "cancelled",
         }
     }
 
+    /// Statuses only dispute rows (`dp_xxx`) take.
+    pub fn is_dispute(&self) -> bool {
+        matches!(self, Self::Disputed | Self::DisputeWon | Self::DisputeLost)
+    }
+
     /// Exhaustive transition table. Every allowed edge is listed explicitly.
     /// If it's not here, it's not allowed.
     ///
-    /// PI rows (pi_xxx):  Pending → Succeeded | Failed
-    /// Refund rows (re_xxx): Pending → Refunded | Failed
+    /// PI rows (pi_xxx):  Pending → Succeeded | Failed | Expired | Cancelled
+    /// Refund rows (re_xxx): Pending → Refunded | Failed | Cancelled
+    /// Dispute rows (dp_xxx): Disputed → DisputeWon | DisputeLost
+    ///
+    /// Stripe, manual payments and sources without a `TransitionPolicy` of
+    /// their own follow this table.
     pub fn can_transition_to(&self, new: &Self) -> bool {
         matches!(
             (self, new),
             (Self::Pending, Self::Succeeded)
                 | (Self::Pending, Self::Failed)
                 | (Self::Pending, Self::Refunded)
+                | (Self::Pending, Self::Expired)
+                | (Self::Pending, Self::Cancelled)
+                | (Self::Disputed, Self::DisputeWon)
+                | (Self::Disputed, Self::DisputeLost)
         )
     }
 }
@@ -137,7 +295,7 @@ impl fmt::Display for PaymentStatus {
 }
 
 impl TryFrom<&str> for PaymentStatus {
-    type Error = PipelineError;
+    type Error = DomainError;
 
     fn try_from(s: &str) -> Result<Self, Self::Error> {
         match s {
@@ -145,7 +303,12 @@ impl TryFrom<&str> for PaymentStatus {
             "failed" => Ok(Self::Failed),
             "pending" => Ok(Self::Pending),
             "refunded" => Ok(Self::Refunded),
-            other => Err(PipelineError::Validation(format!(
+            "disputed" => Ok(Self::Disputed),
+            "dispute_won" => Ok(Self::DisputeWon),
+            "dispute_lost" => Ok(Self::DisputeLost),
+            "expired" => Ok(Self::Expired),
+            "cancelled" => Ok(Self::Cancelled),
+            other => Err(DomainError::Validation(format!(
                 "unknown payment status: {other}"
             ))),
         }
@@ -160,6 +323,8 @@ pub enum PaymentDirection {
 }
 
 impl PaymentDirection {
+    pub const ALL: [Self; 2] = [Self::Inbound, Self::Outbound];
+
     pub fn as_str(&self) -> &'static str {
         match self {
             Self::Inbound => "inbound",
@@ -169,46 +334,360 @@ impl PaymentDirection {
 }
 
 impl TryFrom<&str> for PaymentDirection {
-    type Error = PipelineError;
+    type Error = DomainError;
 
     fn try_from(s: &str) -> Result<Self, Self::Error> {
         match s {
             "inbound" => Ok(Self::Inbound),
             "outbound" => Ok(Self::Outbound),
-            other => Err(PipelineError::Validation(format!(
+            other => Err(DomainError::Validation(format!(
                 "unknown payment direction: {other}"
             ))),
         }
     }
 }
 
+/// Why the provider declined or failed the last attempt (Stripe's
+/// `last_payment_error`). All fields are optional — providers fill what they know.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PaymentFailure {
+    pub code: Option<String>,
+    pub decline_code: Option<String>,
+    pub message: Option<String>,
+    pub network_advice_code: Option<String>,
+}
+
+impl PaymentFailure {
+    /// `None` when every field is empty, so callers don't store blank failures.
+    pub fn from_parts(
+        code: Option<String>,
+        decline_code: Option<String>,
+        message: Option<String>,
+        network_advice_code: Option<String>,
+    ) -> Option<Self> {
+        let f = Self {
+            code,
+            decline_code,
+            message,
+            network_advice_code,
+        };
+        (f != Self::default()).then_some(f)
+    }
+}
+
+/// What the customer saw of a payment: the descriptor their bank statement
+/// shows and the receipt they were sent. Support matches statements against
+/// these.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PaymentReceipt {
+    pub statement_descriptor: Option<String>,
+    pub receipt_email: Option<String>,
+    pub receipt_url: Option<String>,
+}
+
+impl PaymentReceipt {
+    /// `None` when every field is empty, so callers don't store blank receipts.
+    pub fn from_parts(
+        statement_descriptor: Option<String>,
+        receipt_email: Option<String>,
+        receipt_url: Option<String>,
+    ) -> Option<Self> {
+        let r = Self {
+            statement_descriptor,
+            receipt_email,
+            receipt_url,
+        };
+        (r != Self::default()).then_some(r)
+    }
+
+    /// Apply `policy` to the receipt email before it is stored.
+    pub fn redact_email(self, policy: ReceiptEmailPolicy) -> Option<Self> {
+        Self::from_parts(
+            self.statement_descriptor,
+            self.receipt_email.and_then(|email| policy.apply(&email)),
+            self.receipt_url,
+        )
+    }
+}
+
+/// How much of a receipt email is stored (`RECEIPT_EMAIL_STORAGE`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReceiptEmailPolicy {
+    /// The address as the provider sent it.
+    Full,
+    /// First character and domain only (`j***@example.com`): enough to
+    /// confirm an address a customer reads out, not to contact them.
+    #[default]
+    Masked,
+    /// Not stored at all.
+    Omit,
+}
+
+impl ReceiptEmailPolicy {
+    pub fn apply(self, email: &str) -> Option<String> {
+        match self {
+            Self::Full => Some(email.to_string()),
+            Self::Masked => Some(match email.split_once('@') {
+                Some((local, domain)) => {
+                    let first: String = local.chars().take(1).collect();
+                    format!("{first}***@{domain}")
+                }
+                None => "***".to_string(),
+            }),
+            Self::Omit => None,
+        }
+    }
+}
+
+impl FromStr for ReceiptEmailPolicy {
+    type Err = DomainError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "full" => Ok(Self::Full),
+            "masked" => Ok(Self::Masked),
+            "omit" => Ok(Self::Omit),
+            other => Err(DomainError::Validation(format!(
+                "expected full, masked or omit, got: {other}"
+            ))),
+        }
+    }
+}
+
 // ── Response ────────────────────────────────────────────────────────────
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct PaymentView {
     pub id: ExternalId,
     pub source: String,
+    /// Connected account the payment belongs to; omitted for the platform's.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tenant_id: Option<TenantId>,
     pub status: PaymentStatus,
     pub amount: i64,
     pub currency: Currency,
     pub direction: PaymentDirection,
+    pub parent_external_id: Option<ExternalId>,
+    pub failure: Option<PaymentFailure>,
+    /// Last amount held by an authorization awaiting capture.
+    pub authorized_amount: Option<i64>,
+    pub receipt: Option<PaymentReceipt>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
     pub created_at: chrono::DateTime<chrono::Utc>,
 }
 
+/// One page of the payment listing. `next_cursor` is `None` on the last page.
+#[derive(Debug, Serialize)]
+pub struct PaymentPageView {
+    pub payments: Vec<PaymentView>,
+    pub next_cursor: Option<PaymentCursor>,
+}
+
+/// Latest audit activity for a payment, so callers can see how it got to
+/// its current state without querying the log.
+#[derive(Debug, Serialize)]
+pub struct AuditSummary {
+    pub entries: i64,
+    pub last_action: Option<String>,
+    pub last_actor: Option<String>,
+    pub last_event_id: Option<String>,
+    pub last_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PaymentDetail {
+    #[serde(flatten)]
+    pub payment: PaymentView,
+    pub audit: AuditSummary,
+    /// Refund totals; inbound payments only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refunds: Option<RefundableBalance>,
+    /// Captures against the authorization; inbound payments captured
+    /// through capture events only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub captures: Option<CaptureSummary>,
+    /// Labels ops put on the payment, sorted.
+    pub tags: Vec<String>,
+}
+
+/// Refund headroom for an inbound payment. `refundable` is what can still be
+/// refunded once settled and in-flight refunds are accounted for;
+/// `over_refunded` means those refunds already add up to more than the
+/// payment.
+#[derive(Debug, Serialize)]
+pub struct RefundableBalance {
+    pub id: ExternalId,
+    pub currency: Currency,
+    pub amount: i64,
+    pub refunded: i64,
+    pub pending_refunds: i64,
+    pub refundable: i64,
+    pub over_refunded: bool,
+}
+
+impl RefundableBalance {
+    /// Only succeeded payments are refundable; anything else has zero headroom.
+    /// Refunds must be in the payment's currency.
+    pub fn new(
+        id: ExternalId,
+        status: &PaymentStatus,
+        money: &Money,
+        refunded: &Money,
+        pending_refunds: &Money,
+    ) -> Result<Self, DomainError> {
+        let outstanding = refunded.try_add(pending_refunds)?;
+        let over_refunded = outstanding.try_cmp(money)?.is_gt();
+        let refundable = match status {
+            PaymentStatus::Succeeded if !over_refunded => money.try_sub(&outstanding)?,
+            _ => Money::zero(*money.currency()),
+        };
+        Ok(Self {
+            id,
+            currency: *money.currency(),
+            amount: money.amount().cents(),
+            refunded: refunded.amount().cents(),
+            pending_refunds: pending_refunds.amount().cents(),
+            refundable: refundable.amount().cents(),
+            over_refunded,
+        })
+    }
+
+    /// Reject a refund request that would exceed the remaining headroom.
+    pub fn ensure_covers(&self, amount: MoneyAmount) -> Result<(), DomainError> {
+        if amount.cents() > self.refundable {
+            return Err(DomainError::Validation(format!(
+                "refund of {amount} exceeds refundable balance {} for {}",
+                self.refundable, self.id
+            )));
+        }
+        Ok(())
+    }
+}
+
 // ── Filters ─────────────────────────────────────────────────────────────
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 pub struct PaymentFilters {
     pub source: Option<String>,
+    /// Connected account; forced to the key's own for tenant keys.
+    pub tenant_id: Option<String>,
     pub status: Option<PaymentStatus>,
     pub amount: Option<i64>,
     pub amount_min: Option<i64>,
     pub amount_max: Option<i64>,
     pub currency: Option<Currency>,
     pub direction: Option<PaymentDirection>,
+    pub decline_code: Option<String>,
+    pub parent_external_id: Option<String>,
     pub start_date: Option<chrono::DateTime<chrono::Utc>>,
     pub end_date: Option<chrono::DateTime<chrono::Utc>>,
+    /// Free text matched against the payment's references (order and invoice
+    /// ids, statement descriptor, description), by substring or fuzzily.
+    pub reference: Option<String>,
+    /// Comma-separated tags, every one of which a payment must carry.
+    pub tag: Option<String>,
     pub limit: Option<u64>,
     pub offset: Option<i64>,
+    /// Keyset position from a previous page's `next_cursor`. Exclusive of `offset`.
+    pub cursor: Option<PaymentCursor>,
+}
+
+/// Shortest `reference` worth searching for: below it there are no trigrams
+/// to match on.
+pub const MIN_REFERENCE_LEN: usize = 3;
+
+impl PaymentFilters {
+    /// Trim and lowercase `reference` the way the indexed text is; reject
+    /// terms too short to search for. A blank term counts as none.
+    pub fn normalize_reference(&mut self) -> Result<(), DomainError> {
+        let Some(term) = self.reference.take() else {
+            return Ok(());
+        };
+        let term = term.trim().to_lowercase();
+        if term.is_empty() {
+            return Ok(());
+        }
+        if term.chars().count() < MIN_REFERENCE_LEN {
+            return Err(DomainError::Validation(format!(
+                "reference must be at least {MIN_REFERENCE_LEN} characters"
+            )));
+        }
+        self.reference = Some(term);
+        Ok(())
+    }
+
+    /// Normalize each tag in `tag` as tags are stored, rejoined with `,`;
+    /// reject any that couldn't be one. A blank `tag` counts as none.
+    pub fn normalize_tags(&mut self) -> Result<(), DomainError> {
+        let Some(tags) = self.tag.take() else {
+            return Ok(());
+        };
+        let mut tags = tags
+            .split(',')
+            .filter(|t| !t.trim().is_empty())
+            .map(|t| Tag::new(t).map(String::from))
+            .collect::<Result<Vec<_>, _>>()?;
+        // Each tag once: the query counts the ones a payment matches.
+        tags.sort();
+        tags.dedup();
+        if !tags.is_empty() {
+            self.tag = Some(tags.join(","));
+        }
+        Ok(())
+    }
+}
+
+/// Keyset position in the payment listing, which is ordered by
+/// `(created_at, external_id)` descending. Serialized as `<micros>:<external_id>`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct PaymentCursor {
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub external_id: String,
+}
+
+impl PaymentCursor {
+    /// Cursor pointing just past `last`, the final row of a page.
+    pub fn after(last: &PaymentView) -> Self {
+        Self {
+            created_at: last.created_at,
+            external_id: last.id.as_str().to_string(),
+        }
+    }
+}
+
+impl fmt::Display for PaymentCursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}",
+            self.created_at.timestamp_micros(),
+            self.external_id
+        )
+    }
+}
+
+impl TryFrom<String> for PaymentCursor {
+    type Error = DomainError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        let invalid = || DomainError::Validation(format!("invalid payment cursor: {s}"));
+        let (micros, external_id) = s.split_once(':').ok_or_else(invalid)?;
+        let created_at = micros
+            .parse::<i64>()
+            .ok()
+            .and_then(chrono::DateTime::from_timestamp_micros)
+            .ok_or_else(invalid)?;
+        let external_id = ExternalId::new(external_id).map_err(|_| invalid())?;
+        Ok(Self {
+            created_at,
+            external_id: external_id.into_inner(),
+        })
+    }
+}
+
+impl From<PaymentCursor> for String {
+    fn from(c: PaymentCursor) -> Self {
+        c.to_string()
+    }
 }
 
 /// Named params for constructing a NewPayment. All fields explicit at the call site.
@@ -224,6 +703,11 @@ pub struct NewPaymentParams {
     pub last_event_id: EventId,
     pub parent_external_id: Option<ExternalId>,
     pub provider_ts: i64,
+    pub failure: Option<PaymentFailure>,
+    pub authorized_amount: Option<MoneyAmount>,
+    pub receipt: Option<PaymentReceipt>,
+    pub tenant_id: Option<TenantId>,
+    pub customer: Option<String>,
 }
 
 /// For INSERT — id auto-generated via Uuid::now_v7().
@@ -241,6 +725,11 @@ pub struct NewPayment {
     last_event_id: EventId,
     parent_external_id: Option<ExternalId>,
     provider_ts: i64,
+    failure: Option<PaymentFailure>,
+    authorized_amount: Option<MoneyAmount>,
+    receipt: Option<PaymentReceipt>,
+    tenant_id: Option<TenantId>,
+    customer: Option<String>,
 }
 
 impl NewPayment {
@@ -258,6 +747,11 @@ impl NewPayment {
             last_event_id: p.last_event_id,
             parent_external_id: p.parent_external_id,
             provider_ts: p.provider_ts,
+            failure: p.failure,
+            authorized_amount: p.authorized_amount,
+            receipt: p.receipt,
+            tenant_id: p.tenant_id,
+            customer: p.customer,
         }
     }
 
@@ -309,6 +803,76 @@ impl NewPayment {
         self.provider_ts
     }
 
+    /// The API request behind the event, if the provider says.
+    pub fn request_id(&self) -> Option<&str> {
+        event_order::request_id(&self.raw_event)
+    }
+
+    pub fn order(&self) -> EventOrder<'_> {
+        EventOrder {
+            provider_ts: self.provider_ts,
+            event_id: self.last_event_id(),
+            request_id: self.request_id(),
+        }
+    }
+
+    pub fn failure(&self) -> Option<&PaymentFailure> {
+        self.failure.as_ref()
+    }
+
+    pub fn authorized_amount(&self) -> Option<&MoneyAmount> {
+        self.authorized_amount.as_ref()
+    }
+
+    pub fn receipt(&self) -> Option<&PaymentReceipt> {
+        self.receipt.as_ref()
+    }
+
+    pub fn tenant_id(&self) -> Option<&TenantId> {
+        self.tenant_id.as_ref()
+    }
+
+    /// The provider's customer, as the provider names it.
+    pub fn customer(&self) -> Option<&str> {
+        self.customer.as_deref()
+    }
+
+    /// This payment as first recorded when its creating event is dated in a
+    /// closed period: at pending, holding nothing, so the move to its status
+    /// can be held for review like any later one.
+    pub fn held(&self) -> Self {
+        Self {
+            status: PaymentStatus::Pending,
+            failure: None,
+            ..self.clone()
+        }
+    }
+
+    /// The advisory lock key serializing all processing for this payment.
+    /// A connected account's objects lock within their tenant, so accounts
+    /// never wait on each other.
+    pub fn lock_key(&self) -> String {
+        lock_key(self.tenant_id.as_ref(), self.external_id())
+    }
+
+    /// The `provider_events` row this event is recorded under.
+    pub fn provider_event(&self) -> NewProviderEvent<'_> {
+        NewProviderEvent {
+            source: &self.source,
+            tenant_id: self.tenant_id.as_ref().map(|t| t.as_str()),
+            event_id: self.last_event_id(),
+            object_id: self.external_id(),
+            event_type: &self.event_type,
+            provider_ts: self.provider_ts,
+            payload: &self.raw_event,
+        }
+    }
+
+    /// The feed this event arrived on; see `feed::feed_key`.
+    pub fn feed(&self) -> String {
+        feed::feed_key(&self.source, self.tenant_id.as_ref())
+    }
+
     pub fn audit_entry(&self, actor: &str, action: &str) -> NewAuditEntry {
         NewAuditEntry {
             id: Uuid::now_v7(),
@@ -316,6 +880,8 @@ impl NewPayment {
             entity_id: Some(self.id),
             external_id: Some(self.external_id.clone().into_inner()),
             event_id: self.last_event_id.clone().into_inner(),
+            source: Some(self.source.clone()),
+            tenant_id: self.tenant_id.as_ref().map(|t| t.as_str().to_string()),
             action: action.to_string(),
             actor: actor.to_string(),
             detail: serde_json::json!({
@@ -340,6 +906,8 @@ mod tests {
         assert!(Pending.can_transition_to(&Succeeded));
         assert!(Pending.can_transition_to(&Failed));
         assert!(Pending.can_transition_to(&Refunded));
+        assert!(Pending.can_transition_to(&Expired));
+        assert!(Pending.can_transition_to(&Cancelled));
     }
 
     #[test]
@@ -358,6 +926,8 @@ mod tests {
         // terminal
         assert!(!Refunded.can_transition_to(&Pending));
         assert!(!Refunded.can_transition_to(&Succeeded));
+        assert!(!Cancelled.can_transition_to(&Succeeded));
+        assert!(!Cancelled.can_transition_to(&Failed));
     }
 
     #[test]
@@ -367,6 +937,8 @@ mod tests {
             PaymentStatus::Succeeded,
             PaymentStatus::Failed,
             PaymentStatus::Refunded,
+            PaymentStatus::Expired,
+            PaymentStatus::Cancelled,
         ];
         for s in &statuses {
             let parsed = PaymentStatus::try_from(s.as_str()).unwrap();
@@ -376,7 +948,7 @@ mod tests {
 
     #[test]
     fn status_try_from_unknown_is_err() {
-        let result = PaymentStatus::try_from("cancelled");
+        let result = PaymentStatus::try_from("voided");
         assert!(result.is_err());
     }
 
@@ -397,6 +969,16 @@ mod tests {
         assert!(PaymentDirection::try_from("lateral").is_err());
     }
 
+    #[test]
+    fn payment_failure_from_empty_parts_is_none() {
+        assert_eq!(PaymentFailure::from_parts(None, None, None, None), None);
+        let f = PaymentFailure::from_parts(None, Some("insufficient_funds".into()), None, None);
+        assert_eq!(
+            f.unwrap().decline_code.as_deref(),
+            Some("insufficient_funds")
+        );
+    }
+
     #[test]
     fn new_payment_audit_entry() {
         use crate::domain::id::{EventId, ExternalId};
@@ -406,13 +988,18 @@ mod tests {
             source: "stripe".into(),
             event_type: "payment_intent.succeeded".into(),
             direction: PaymentDirection::Inbound,
-            money: Money::new(MoneyAmount::new(5000).unwrap(), Currency::Eur),
+            money: Money::new(MoneyAmount::new(5000).unwrap(), Currency::EUR),
             status: PaymentStatus::Succeeded,
             metadata: serde_json::json!({}),
             raw_event: serde_json::json!({"id": "evt_1"}),
             last_event_id: EventId::new("evt_1").unwrap(),
             parent_external_id: None,
             provider_ts: 1709136000,
+            failure: None,
+            authorized_amount: None,
+            receipt: None,
+            tenant_id: None,
+            customer: None,
         });
 
         let audit = p.audit_entry("webhook:stripe", "created");
@@ -424,4 +1011,92 @@ mod tests {
         assert_eq!(audit.detail["currency"], "eur");
         assert_eq!(audit.detail["amount"], 5000);
     }
+
+    fn balance(status: PaymentStatus, refunded: i64, pending: i64) -> RefundableBalance {
+        RefundableBalance::new(
+            ExternalId::new("pi_rb").unwrap(),
+            &status,
+            &usd(5000),
+            &usd(refunded),
+            &usd(pending),
+        )
+        .unwrap()
+    }
+
+    fn usd(cents: i64) -> Money {
+        Money::new(MoneyAmount::new(cents).unwrap(), Currency::USD)
+    }
+
+    #[test]
+    fn receipt_email_is_stored_as_configured() {
+        let receipt = PaymentReceipt::from_parts(
+            Some("ACME* ORDER 42".into()),
+            Some("jane@example.com".into()),
+            None,
+        )
+        .unwrap();
+        let email = |policy| {
+            receipt
+                .clone()
+                .redact_email(policy)
+                .and_then(|r| r.receipt_email)
+        };
+        assert_eq!(
+            email(ReceiptEmailPolicy::Full).as_deref(),
+            Some("jane@example.com")
+        );
+        assert_eq!(
+            email(ReceiptEmailPolicy::Masked).as_deref(),
+            Some("j***@example.com")
+        );
+        assert_eq!(email(ReceiptEmailPolicy::Omit), None);
+        assert_eq!("omit".parse(), Ok(ReceiptEmailPolicy::Omit));
+        assert!("none".parse::<ReceiptEmailPolicy>().is_err());
+
+        // Nothing left once the only field is dropped.
+        let only_email = PaymentReceipt::from_parts(None, Some("a@b.c".into()), None).unwrap();
+        assert_eq!(only_email.redact_email(ReceiptEmailPolicy::Omit), None);
+    }
+
+    #[test]
+    fn refundable_balance_subtracts_settled_and_pending() {
+        let b = balance(PaymentStatus::Succeeded, 1000, 1500);
+        assert_eq!(b.refundable, 2500);
+        assert!(b.ensure_covers(MoneyAmount::new(2500).unwrap()).is_ok());
+        assert!(b.ensure_covers(MoneyAmount::new(2501).unwrap()).is_err());
+    }
+
+    #[test]
+    fn refundable_balance_never_negative() {
+        let b = balance(PaymentStatus::Succeeded, 4000, 2000);
+        assert_eq!(b.refundable, 0);
+        assert!(b.over_refunded);
+        assert!(!balance(PaymentStatus::Succeeded, 3000, 2000).over_refunded);
+    }
+
+    #[test]
+    fn refundable_balance_zero_unless_succeeded() {
+        for status in [
+            PaymentStatus::Pending,
+            PaymentStatus::Failed,
+            PaymentStatus::Refunded,
+        ] {
+            assert_eq!(balance(status, 0, 0).refundable, 0);
+        }
+    }
+
+    #[test]
+    fn payment_cursor_roundtrips_and_rejects_garbage() {
+        let cursor = PaymentCursor {
+            created_at: chrono::DateTime::from_timestamp_micros(1_773_000_000_123_456).unwrap(),
+            external_id: "pi_abc".into(),
+        };
+        let encoded = cursor.to_string();
+        assert_eq!(encoded, "1773000000123456:pi_abc");
+        assert_eq!(PaymentCursor::try_from(encoded).unwrap(), cursor);
+
+        for bad in ["", "pi_abc", "x:pi_abc", "123:ch_abc"] {
+            assert!(PaymentCursor::try_from(bad.to_string()).is_err());
+        }
+    }
 }
@@ -0,0 +1,331 @@
+use {
+    super::{
+        audit::NewAuditEntry,
+        error::DomainError,
+        event_order::EventOrder,
+        id::{EventId, PayoutId},
+        money::{Money, MoneyAmount},
+    },
+    chrono::{DateTime, Utc},
+    serde::{Deserialize, Serialize},
+    std::fmt,
+    uuid::Uuid,
+};
+
+/// Money leaving the provider balance: a payout to one of our bank accounts
+/// or a transfer to a connected account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PayoutKind {
+    Payout,
+    Transfer,
+}
+
+impl PayoutKind {
+    pub const ALL: [Self; 2] = [Self::Payout, Self::Transfer];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Payout => "payout",
+            Self::Transfer => "transfer",
+        }
+    }
+}
+
+impl TryFrom<&str> for PayoutKind {
+    type Error = DomainError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s {
+            "payout" => Ok(Self::Payout),
+            "transfer" => Ok(Self::Transfer),
+            other => Err(DomainError::Validation(format!(
+                "unknown payout kind: {other}"
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PayoutStatus {
+    /// Created or on its way to the bank.
+    Pending,
+    Paid,
+    Failed,
+    Cancelled,
+}
+
+impl PayoutStatus {
+    pub const ALL: [Self; 4] = [Self::Pending, Self::Paid, Self::Failed, Self::Cancelled];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Paid => "paid",
+            Self::Failed => "failed",
+            Self::Cancelled => "cancelled",
+        }
+    }
+
+    /// Pending → Paid | Failed | Cancelled, and Paid → Failed: a bank can
+    /// return a payout days after it was reported paid.
+    pub fn can_transition_to(self, new: Self) -> bool {
+        matches!(
+            (self, new),
+            (Self::Pending, Self::Paid | Self::Failed | Self::Cancelled)
+                | (Self::Paid, Self::Failed)
+        )
+    }
+}
+
+impl fmt::Display for PayoutStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl TryFrom<&str> for PayoutStatus {
+    type Error = DomainError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s {
+            "pending" => Ok(Self::Pending),
+            "paid" => Ok(Self::Paid),
+            "failed" => Ok(Self::Failed),
+            "cancelled" => Ok(Self::Cancelled),
+            other => Err(DomainError::Validation(format!(
+                "unknown payout status: {other}"
+            ))),
+        }
+    }
+}
+
+/// A payout or transfer as one provider event describes it.
+#[derive(Debug, Clone)]
+pub struct NewPayout {
+    pub external_id: PayoutId,
+    pub source: String,
+    pub kind: PayoutKind,
+    pub money: Money,
+    pub status: PayoutStatus,
+    /// Part of a transfer taken back since; always zero for payouts.
+    pub amount_reversed: MoneyAmount,
+    /// Bank account or card (payouts), connected account (transfers).
+    pub destination: Option<String>,
+    /// When the payout is expected to reach the bank.
+    pub arrival_date: Option<DateTime<Utc>>,
+    pub failure_code: Option<String>,
+    pub failure_message: Option<String>,
+    pub metadata: serde_json::Value,
+    pub event_id: EventId,
+    pub event_type: String,
+    pub provider_ts: i64,
+    /// `request.id` of the API call behind the event, if any.
+    pub request_id: Option<String>,
+    pub raw_event: serde_json::Value,
+}
+
+impl NewPayout {
+    pub fn order(&self) -> EventOrder<'_> {
+        EventOrder {
+            provider_ts: self.provider_ts,
+            event_id: self.event_id.as_str(),
+            request_id: self.request_id.as_deref(),
+        }
+    }
+
+    pub fn audit_entry(&self, id: Uuid, actor: &str, action: &str) -> NewAuditEntry {
+        NewAuditEntry {
+            id: Uuid::now_v7(),
+            entity_type: "payout".to_string(),
+            entity_id: Some(id),
+            external_id: Some(self.external_id.as_str().to_string()),
+            event_id: self.event_id.as_str().to_string(),
+            source: Some(self.source.clone()),
+            tenant_id: None,
+            action: action.to_string(),
+            actor: actor.to_string(),
+            detail: serde_json::json!({
+                "event_type": self.event_type,
+                "kind": self.kind.as_str(),
+                "status": self.status.as_str(),
+                "amount": self.money.amount().cents(),
+                "currency": self.money.currency().as_str(),
+            }),
+        }
+    }
+}
+
+/// Current state of a payout row, for deciding what an event does.
+pub struct ExistingPayout {
+    pub id: Uuid,
+    pub status: PayoutStatus,
+    pub last_provider_ts: i64,
+    pub last_event_id: String,
+    pub last_request_id: Option<String>,
+}
+
+pub enum PayoutAction {
+    Advance {
+        old_status: PayoutStatus,
+    },
+    /// Same status; details such as the reversed amount may have moved.
+    Refresh,
+    /// Older than the last event applied.
+    Stale,
+    LogAnomaly {
+        current: PayoutStatus,
+    },
+}
+
+impl ExistingPayout {
+    fn last_event(&self) -> EventOrder<'_> {
+        EventOrder {
+            provider_ts: self.last_provider_ts,
+            event_id: &self.last_event_id,
+            request_id: self.last_request_id.as_deref(),
+        }
+    }
+
+    /// Payout events carry the whole object, so an event older than the one
+    /// last applied has nothing to add. Events in the same second are
+    /// ordered as [`EventOrder::compare`] says, falling back to the state
+    /// machine when it can't tell.
+    pub fn decide(&self, incoming: &NewPayout) -> PayoutAction {
+        if incoming.order().is_before(&self.last_event()) {
+            PayoutAction::Stale
+        } else if incoming.status == self.status {
+            PayoutAction::Refresh
+        } else if self.status.can_transition_to(incoming.status) {
+            PayoutAction::Advance {
+                old_status: self.status,
+            }
+        } else {
+            PayoutAction::LogAnomaly {
+                current: self.status,
+            }
+        }
+    }
+}
+
+/// One `payouts` row, as returned by the API.
+#[derive(Debug, Serialize)]
+pub struct PayoutView {
+    pub external_id: String,
+    pub source: String,
+    pub kind: String,
+    pub amount: i64,
+    pub currency: String,
+    pub status: String,
+    pub amount_reversed: i64,
+    pub destination: Option<String>,
+    pub arrival_date: Option<DateTime<Utc>>,
+    pub failure_code: Option<String>,
+    pub failure_message: Option<String>,
+    pub metadata: serde_json::Value,
+    pub last_event_id: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Filters for `GET /payouts`.
+#[derive(Debug, Default, Deserialize)]
+pub struct PayoutFilters {
+    pub kind: Option<String>,
+    pub status: Option<String>,
+    pub limit: Option<i64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::domain::money::{Currency, MoneyAmount},
+    };
+
+    fn payout(status: PayoutStatus, provider_ts: i64) -> NewPayout {
+        burst(status, provider_ts, "evt_1", None)
+    }
+
+    fn burst(
+        status: PayoutStatus,
+        provider_ts: i64,
+        event_id: &str,
+        request_id: Option<&str>,
+    ) -> NewPayout {
+        NewPayout {
+            external_id: PayoutId::new("po_1").unwrap(),
+            source: "stripe".into(),
+            kind: PayoutKind::Payout,
+            money: Money::new(MoneyAmount::new(10_000).unwrap(), Currency::USD),
+            status,
+            amount_reversed: MoneyAmount::new(0).unwrap(),
+            destination: Some("ba_1".into()),
+            arrival_date: None,
+            failure_code: None,
+            failure_message: None,
+            metadata: serde_json::json!({}),
+            event_id: EventId::new(event_id).unwrap(),
+            event_type: "payout.updated".into(),
+            provider_ts,
+            request_id: request_id.map(Into::into),
+            raw_event: serde_json::json!({}),
+        }
+    }
+
+    #[test]
+    fn payouts_settle_once_but_can_bounce() {
+        use PayoutStatus::*;
+        assert!(Pending.can_transition_to(Paid));
+        assert!(Pending.can_transition_to(Cancelled));
+        assert!(Paid.can_transition_to(Failed));
+        assert!(!Paid.can_transition_to(Pending));
+        assert!(!Failed.can_transition_to(Paid));
+        assert!(!Cancelled.can_transition_to(Pending));
+
+        let existing = ExistingPayout {
+            id: Uuid::now_v7(),
+            status: Paid,
+            last_provider_ts: 100,
+            last_event_id: "evt_M".into(),
+            last_request_id: None,
+        };
+        assert!(matches!(
+            existing.decide(&payout(Failed, 120)),
+            PayoutAction::Advance { old_status: Paid }
+        ));
+        // A late `payout.updated` from before it was paid.
+        assert!(matches!(
+            existing.decide(&payout(Pending, 90)),
+            PayoutAction::Stale
+        ));
+        assert!(matches!(
+            existing.decide(&payout(Pending, 130)),
+            PayoutAction::LogAnomaly { current: Paid }
+        ));
+        assert!(matches!(
+            existing.decide(&payout(Paid, 130)),
+            PayoutAction::Refresh
+        ));
+
+        // Same second as the last event applied: the event id decides,
+        // unless another API request raced it.
+        assert!(matches!(
+            existing.decide(&burst(Pending, 100, "evt_A", None)),
+            PayoutAction::Stale
+        ));
+        assert!(matches!(
+            existing.decide(&burst(Pending, 100, "evt_Z", None)),
+            PayoutAction::LogAnomaly { current: Paid }
+        ));
+        let raced = ExistingPayout {
+            last_request_id: Some("req_1".into()),
+            ..existing
+        };
+        assert!(matches!(
+            raced.decide(&burst(Pending, 100, "evt_A", Some("req_2"))),
+            PayoutAction::LogAnomaly { current: Paid }
+        ));
+    }
+}
@@ -0,0 +1,257 @@
+use {
+    super::{audit::NewAuditEntry, error::DomainError},
+    chrono::{DateTime, NaiveDate, NaiveTime, TimeDelta, Utc},
+    serde::{Deserialize, Serialize},
+    uuid::Uuid,
+};
+
+/// When the business day ends. Events dated at or after a day's end belong
+/// to the next day, so with `hour: 17` a payment that fails at 18:00 is
+/// booked the day after.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EodCutoff {
+    /// Local hour the day ends at, 1 to 24; 24 is midnight.
+    pub hour: u32,
+    /// The books' offset from UTC, in minutes.
+    pub utc_offset_minutes: i32,
+}
+
+/// Midnight UTC.
+impl Default for EodCutoff {
+    fn default() -> Self {
+        Self {
+            hour: 24,
+            utc_offset_minutes: 0,
+        }
+    }
+}
+
+impl EodCutoff {
+    pub fn validate(&self) -> Result<(), DomainError> {
+        if !(1..=24).contains(&self.hour) {
+            return Err(DomainError::Validation(
+                "hour must be between 1 and 24".into(),
+            ));
+        }
+        if !(-840..=840).contains(&self.utc_offset_minutes) {
+            return Err(DomainError::Validation(
+                "utc_offset_minutes must be between -840 and 840".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// The instant business day `date` ends.
+    pub fn end_of(&self, date: NaiveDate) -> DateTime<Utc> {
+        date.and_time(NaiveTime::MIN).and_utc() + TimeDelta::hours(self.hour.into())
+            - TimeDelta::minutes(self.utc_offset_minutes.into())
+    }
+}
+
+/// Body of `POST /admin/periods/close`: close the books through the end of
+/// `business_date`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CloseRequest {
+    pub business_date: NaiveDate,
+    pub reason: String,
+}
+
+/// Body of `POST /admin/periods/reopen` and of resolving an adjustment.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReasonRequest {
+    pub reason: String,
+}
+
+/// A reason an operator gave, once checked.
+pub fn validate_reason(reason: &str) -> Result<(), DomainError> {
+    if reason.trim().is_empty() || reason.len() > 500 {
+        return Err(DomainError::Validation(
+            "reason must be 1 to 500 characters".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// One `accounting_periods` row.
+#[derive(Debug, Clone, Serialize)]
+pub struct PeriodClose {
+    pub id: Uuid,
+    pub business_date: NaiveDate,
+    pub closed_through: DateTime<Utc>,
+    pub reason: String,
+    pub closed_by: String,
+    pub closed_at: DateTime<Utc>,
+    pub reopened_by: Option<String>,
+    pub reopened_at: Option<DateTime<Utc>>,
+    pub reopen_reason: Option<String>,
+}
+
+impl PeriodClose {
+    /// `period_closed` or `period_reopened`.
+    pub fn audit_entry(&self, action: &str, actor: &str) -> NewAuditEntry {
+        NewAuditEntry {
+            id: Uuid::now_v7(),
+            entity_type: "accounting_period".to_string(),
+            entity_id: Some(self.id),
+            external_id: Some(self.business_date.to_string()),
+            event_id: format!("{action}:{}", self.id),
+            source: None,
+            tenant_id: None,
+            action: action.to_string(),
+            actor: actor.to_string(),
+            detail: serde_json::json!({
+                "business_date": self.business_date,
+                "closed_through": self.closed_through,
+                "reason": self.reopen_reason.as_ref().unwrap_or(&self.reason),
+            }),
+        }
+    }
+}
+
+/// `GET /admin/periods`: the lock in force and the closes behind it,
+/// newest first.
+#[derive(Debug, Serialize)]
+pub struct PeriodStatus {
+    /// Events dated before this don't change payments; `None` while no
+    /// period is closed.
+    pub closed_through: Option<DateTime<Utc>>,
+    pub eod_cutoff: EodCutoff,
+    pub periods: Vec<PeriodClose>,
+}
+
+/// Whether an event dated `provider_ts` (Unix seconds) falls in a period
+/// closed through `closed_through`.
+pub fn in_closed_period(provider_ts: i64, closed_through: Option<DateTime<Utc>>) -> bool {
+    closed_through.is_some_and(|through| provider_ts < through.timestamp())
+}
+
+/// What became of a diverted status change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdjustmentStatus {
+    Pending,
+    Applied,
+    Dismissed,
+}
+
+impl AdjustmentStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Applied => "applied",
+            Self::Dismissed => "dismissed",
+        }
+    }
+}
+
+impl TryFrom<&str> for AdjustmentStatus {
+    type Error = DomainError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s {
+            "pending" => Ok(Self::Pending),
+            "applied" => Ok(Self::Applied),
+            "dismissed" => Ok(Self::Dismissed),
+            other => Err(DomainError::Validation(format!(
+                "unknown adjustment status: {other}"
+            ))),
+        }
+    }
+}
+
+/// One `period_adjustments` row: a status change held back because its
+/// event was dated inside a closed period.
+#[derive(Debug, Clone, Serialize)]
+pub struct PeriodAdjustment {
+    pub id: Uuid,
+    pub payment_id: Uuid,
+    pub external_id: String,
+    pub source: String,
+    pub tenant_id: Option<String>,
+    pub event_id: String,
+    pub event_type: String,
+    pub provider_ts: i64,
+    pub from_status: String,
+    pub to_status: String,
+    pub closed_through: DateTime<Utc>,
+    /// The payment was first seen through this event and created at
+    /// pending; the move was admitted then, so applying it doesn't ask the
+    /// state machine again.
+    pub on_create: bool,
+    pub status: String,
+    pub resolved_by: Option<String>,
+    pub resolved_at: Option<DateTime<Utc>>,
+    pub resolution_reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl PeriodAdjustment {
+    /// `period_adjustment_applied` or `period_adjustment_dismissed`, on the
+    /// payment.
+    pub fn audit_entry(
+        &self,
+        status: AdjustmentStatus,
+        reason: &str,
+        actor: &str,
+    ) -> NewAuditEntry {
+        let action = format!("period_adjustment_{}", status.as_str());
+        NewAuditEntry {
+            id: Uuid::now_v7(),
+            entity_type: "payment".to_string(),
+            entity_id: Some(self.payment_id),
+            external_id: Some(self.external_id.clone()),
+            event_id: format!("{action}:{}", self.id),
+            source: Some(self.source.clone()),
+            tenant_id: self.tenant_id.clone(),
+            action,
+            actor: actor.to_string(),
+            detail: serde_json::json!({
+                "adjustment_id": self.id,
+                "event_id": self.event_id,
+                "from_status": self.from_status,
+                "to_status": self.to_status,
+                "closed_through": self.closed_through,
+                "reason": reason,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn business_days_end_at_the_cutoff() {
+        let midnight = EodCutoff::default();
+        assert_eq!(
+            midnight.end_of(date("2026-03-31")).to_rfc3339(),
+            "2026-04-01T00:00:00+00:00"
+        );
+        // 17:00 in New York (UTC-5) is 22:00 UTC.
+        let new_york = EodCutoff {
+            hour: 17,
+            utc_offset_minutes: -300,
+        };
+        let end = new_york.end_of(date("2026-03-31"));
+        assert_eq!(end.to_rfc3339(), "2026-03-31T22:00:00+00:00");
+
+        assert!(in_closed_period(end.timestamp() - 1, Some(end)));
+        assert!(!in_closed_period(end.timestamp(), Some(end)));
+        assert!(!in_closed_period(0, None));
+
+        for bad in [(0, 0), (25, 0), (24, 900)] {
+            let cutoff = EodCutoff {
+                hour: bad.0,
+                utc_offset_minutes: bad.1,
+            };
+            assert!(cutoff.validate().is_err(), "{bad:?}");
+        }
+    }
+}
@@ -1,12 +1,21 @@
 use {
-    super::error::PipelineError,
-    super::id::ExternalId,
-    super::money::Money,
-    super::payment::{PaymentDirection, PaymentStatus},
-    std::{future::Future, pin::Pin},
+    super::error::PortError,
+    super::id::{ExternalId, TenantId},
+    super::money::{Money, MoneyAmount},
+    super::payment::{PaymentDirection, PaymentFailure, PaymentReceipt, PaymentStatus},
+    chrono::{DateTime, Utc},
+    futures_util::{StreamExt, stream},
+    serde::{Deserialize, Serialize},
+    std::{
+        collections::{BTreeMap, HashMap},
+        future::Future,
+        pin::Pin,
+        sync::Arc,
+    },
 };
 
 /// What the service layer gets back after fetching from the provider API.
+#[derive(Debug, Clone)]
 pub struct FetchedPayment {
     pub external_id: ExternalId,
     pub direction: PaymentDirection,
@@ -14,11 +23,336 @@ pub struct FetchedPayment {
     pub money: Money,
     pub metadata: serde_json::Value,
     pub parent_external_id: Option<ExternalId>,
+    pub failure: Option<PaymentFailure>,
+    /// Amount still held by an uncaptured authorization; `None` when nothing
+    /// is awaiting capture.
+    pub authorized_amount: Option<MoneyAmount>,
+    pub receipt: Option<PaymentReceipt>,
+    /// The provider's customer, when it names one; links a retry to the
+    /// attempt it follows (see `domain::checkout`).
+    pub customer: Option<String>,
+    /// When the provider created the payment, if it says; reconciliation
+    /// reads currency terms as of this rather than when it was recorded.
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+/// Result of a batch fetch, per requested id.
+pub type FetchedBatch = HashMap<ExternalId, Result<FetchedPayment, PortError>>;
+
+/// Opaque position in a provider listing. Callers pass `next_cursor` back
+/// unchanged; only the provider that issued it knows what's inside.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ListCursor(String);
+
+impl ListCursor {
+    pub fn new(cursor: impl Into<String>) -> Self {
+        Self(cursor.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// One page of a provider listing. `next_cursor` is `None` on the last page.
+#[derive(Debug)]
+pub struct PaymentPage {
+    pub payments: Vec<FetchedPayment>,
+    pub next_cursor: Option<ListCursor>,
 }
 
 pub trait PaymentProvider: Send + Sync {
+    /// Value stored in `payments.source` for objects from this provider.
+    fn source(&self) -> &'static str;
+
     fn fetch_payment(
         &self,
         id: &ExternalId,
-    ) -> Pin<Box<dyn Future<Output = Result<FetchedPayment, PipelineError>> + Send + '_>>;
+    ) -> Pin<Box<dyn Future<Output = Result<FetchedPayment, PortError>> + Send + '_>>;
+
+    /// Fetch several objects at once, e.g. a worker batch. By default
+    /// `fetch_payment` for each, at most `concurrency` in flight; a provider
+    /// with a multi-object endpoint can do better. Neither Stripe nor PayPal
+    /// lists objects by id, so both keep the default.
+    fn fetch_payments_batch<'a>(
+        &'a self,
+        ids: &'a [ExternalId],
+        concurrency: usize,
+    ) -> Pin<Box<dyn Future<Output = FetchedBatch> + Send + 'a>> {
+        Box::pin(async move {
+            let fetches: Vec<_> = ids
+                .iter()
+                .map(|id| async move { (id.clone(), self.fetch_payment(id).await) })
+                .collect();
+            stream::iter(fetches)
+                .buffer_unordered(concurrency.max(1))
+                .collect()
+                .await
+        })
+    }
+
+    /// List payment objects created in `[since, until]`, one page at a time.
+    fn list_payments(
+        &self,
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+        cursor: Option<ListCursor>,
+    ) -> Pin<Box<dyn Future<Output = Result<PaymentPage, PortError>> + Send + '_>>;
+
+    /// The object embedded in one of this provider's stored webhook events
+    /// (`provider_events.payload`), as it was when the event was sent.
+    /// `None` if the event carries no payment object.
+    fn payment_from_event(
+        &self,
+        _payload: &serde_json::Value,
+    ) -> Result<Option<FetchedPayment>, PortError> {
+        Ok(None)
+    }
+
+    /// The payment in a live webhook's own object, to apply instead of a
+    /// fetch. `None` unless the object carries every field a fetch would
+    /// (a truncated or thin event doesn't), in which case it's fetched.
+    fn payment_from_payload(
+        &self,
+        _payload: &serde_json::Value,
+    ) -> Result<Option<FetchedPayment>, PortError> {
+        Ok(None)
+    }
+
+    /// The payment settled by an event about a related object (a checkout
+    /// session, an invoice), looked up through the API when the event
+    /// doesn't name it. `None` if nothing was paid, e.g. a session that only
+    /// saved a card.
+    fn payment_for_event<'a>(
+        &'a self,
+        _payload: &'a serde_json::Value,
+    ) -> Pin<Box<dyn Future<Output = Result<Option<ExternalId>, PortError>> + Send + 'a>> {
+        Box::pin(async { Ok(None) })
+    }
+
+    /// This provider acting for connected account `tenant`, to fetch that
+    /// account's objects. Providers without connected accounts refuse.
+    fn for_tenant(&self, tenant: &TenantId) -> Result<Arc<dyn PaymentProvider>, PortError> {
+        Err(PortError::Invalid(format!(
+            "{} has no connected accounts, got {tenant}",
+            self.source()
+        )))
+    }
+}
+
+/// The providers configured in this deployment, keyed by `source`. Jobs,
+/// runs and payments carry a source; this is how they find their provider.
+#[derive(Clone, Default)]
+pub struct ProviderRegistry {
+    providers: BTreeMap<&'static str, Arc<dyn PaymentProvider>>,
+}
+
+impl ProviderRegistry {
+    /// Add a provider under its own `source()`, replacing any previous one.
+    pub fn register(&mut self, provider: Arc<dyn PaymentProvider>) {
+        self.providers.insert(provider.source(), provider);
+    }
+
+    pub fn get(&self, source: &str) -> Result<&Arc<dyn PaymentProvider>, PortError> {
+        self.providers.get(source).ok_or_else(|| {
+            PortError::Invalid(format!("no provider configured for source: {source}"))
+        })
+    }
+
+    /// The provider for `source`, acting for `tenant` when there is one.
+    pub fn get_scoped(
+        &self,
+        source: &str,
+        tenant: Option<&TenantId>,
+    ) -> Result<Arc<dyn PaymentProvider>, PortError> {
+        let provider = self.get(source)?;
+        match tenant {
+            Some(tenant) => provider.for_tenant(tenant),
+            None => Ok(Arc::clone(provider)),
+        }
+    }
+
+    /// Every registered provider, in source order.
+    pub fn all(&self) -> impl Iterator<Item = &Arc<dyn PaymentProvider>> {
+        self.providers.values()
+    }
+}
+
+/// Walks a provider listing page by page, so callers don't hand-roll cursor
+/// loops. `cursor()` is the position of the next page; persist it to resume
+/// a long walk (e.g. a backfill) after a restart.
+pub struct PaymentPager<'a> {
+    provider: &'a dyn PaymentProvider,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+    cursor: Option<ListCursor>,
+    done: bool,
+}
+
+impl<'a> PaymentPager<'a> {
+    pub fn new(
+        provider: &'a dyn PaymentProvider,
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Self {
+        Self::resume(provider, since, until, None)
+    }
+
+    /// Continue from a cursor saved by an earlier walk over the same window.
+    pub fn resume(
+        provider: &'a dyn PaymentProvider,
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+        cursor: Option<ListCursor>,
+    ) -> Self {
+        Self {
+            provider,
+            since,
+            until,
+            cursor,
+            done: false,
+        }
+    }
+
+    pub fn cursor(&self) -> Option<&ListCursor> {
+        self.cursor.as_ref()
+    }
+
+    /// Next page, or `None` once the listing is exhausted. A provider that
+    /// hands back the cursor it was given would loop forever, so that is an error.
+    pub async fn next_page(&mut self) -> Result<Option<Vec<FetchedPayment>>, PortError> {
+        if self.done {
+            return Ok(None);
+        }
+        let page = self
+            .provider
+            .list_payments(self.since, self.until, self.cursor.clone())
+            .await?;
+        if page.next_cursor.is_some() && page.next_cursor == self.cursor {
+            return Err(PortError::Unavailable(format!(
+                "listing cursor did not advance: {}",
+                page.next_cursor.as_ref().map_or("", |c| c.as_str())
+            )));
+        }
+        self.done = page.next_cursor.is_none();
+        self.cursor = page.next_cursor;
+        Ok(Some(page.payments))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::money::Currency;
+
+    /// Serves `total` payments `per_page` at a time; the cursor is an offset.
+    struct CountingProvider {
+        total: usize,
+        per_page: usize,
+        stuck: bool,
+    }
+
+    impl PaymentProvider for CountingProvider {
+        fn source(&self) -> &'static str {
+            "test"
+        }
+
+        fn fetch_payment(
+            &self,
+            _id: &ExternalId,
+        ) -> Pin<Box<dyn Future<Output = Result<FetchedPayment, PortError>> + Send + '_>> {
+            Box::pin(async { Err(PortError::Unavailable("not supported".into())) })
+        }
+
+        fn list_payments(
+            &self,
+            _since: DateTime<Utc>,
+            _until: DateTime<Utc>,
+            cursor: Option<ListCursor>,
+        ) -> Pin<Box<dyn Future<Output = Result<PaymentPage, PortError>> + Send + '_>> {
+            let start: usize = cursor.map_or(0, |c| c.as_str().parse().unwrap());
+            let end = (start + self.per_page).min(self.total);
+            let payments = (start..end)
+                .map(|i| FetchedPayment {
+                    external_id: ExternalId::new(format!("pi_{i}")).unwrap(),
+                    direction: PaymentDirection::Inbound,
+                    status: PaymentStatus::Succeeded,
+                    money: Money::new(MoneyAmount::new(100).unwrap(), Currency::USD),
+                    metadata: serde_json::json!({}),
+                    parent_external_id: None,
+                    failure: None,
+                    authorized_amount: None,
+                    receipt: None,
+                    customer: None,
+                    created_at: None,
+                })
+                .collect();
+            let next = if self.stuck { start } else { end };
+            let next_cursor = (end < self.total).then(|| ListCursor::new(next.to_string()));
+            Box::pin(async move {
+                Ok(PaymentPage {
+                    payments,
+                    next_cursor,
+                })
+            })
+        }
+    }
+
+    #[test]
+    fn registry_finds_providers_by_source() {
+        let mut registry = ProviderRegistry::default();
+        registry.register(Arc::new(CountingProvider {
+            total: 0,
+            per_page: 1,
+            stuck: false,
+        }));
+        assert_eq!(registry.get("test").unwrap().source(), "test");
+        assert!(registry.get("stripe").is_err());
+        assert_eq!(registry.all().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn pager_walks_every_page_then_stops() {
+        let provider = CountingProvider {
+            total: 5,
+            per_page: 2,
+            stuck: false,
+        };
+        let now = Utc::now();
+        let mut pager = PaymentPager::new(&provider, now, now);
+        let mut sizes = Vec::new();
+        while let Some(page) = pager.next_page().await.unwrap() {
+            sizes.push(page.len());
+        }
+        assert_eq!(sizes, vec![2, 2, 1]);
+        assert!(pager.cursor().is_none());
+        assert!(pager.next_page().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn pager_resumes_from_saved_cursor() {
+        let provider = CountingProvider {
+            total: 5,
+            per_page: 2,
+            stuck: false,
+        };
+        let now = Utc::now();
+        let mut pager = PaymentPager::resume(&provider, now, now, Some(ListCursor::new("4")));
+        let page = pager.next_page().await.unwrap().unwrap();
+        assert_eq!(page[0].external_id.as_str(), "pi_4");
+        assert!(pager.next_page().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn pager_rejects_a_cursor_that_does_not_advance() {
+        let provider = CountingProvider {
+            total: 5,
+            per_page: 2,
+            stuck: true,
+        };
+        let now = Utc::now();
+        let mut pager = PaymentPager::resume(&provider, now, now, Some(ListCursor::new("0")));
+        assert!(pager.next_page().await.is_err());
+    }
 }
@@ -0,0 +1,539 @@
+use {
+    super::{
+        alert::Alert, audit::NewAuditEntry, currency_terms::CurrencyTerms, error::DomainError,
+        money::Currency, payment::PaymentStatus, provider::FetchedPayment,
+    },
+    chrono::{DateTime, Utc},
+    serde::{Deserialize, Serialize},
+    std::{
+        collections::{BTreeMap, BTreeSet},
+        fmt,
+    },
+    uuid::Uuid,
+};
+
+/// What we hold locally for a payment the provider reported.
+pub struct LocalSnapshot {
+    pub id: Uuid,
+    pub external_id: String,
+    pub status: PaymentStatus,
+    pub amount: i64,
+    pub currency: Currency,
+    /// When we first recorded it; currency terms are read as of then.
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiscrepancyKind {
+    /// Provider has the object, `payments` doesn't.
+    MissingLocally,
+    StatusMismatch,
+    AmountMismatch,
+    CurrencyMismatch,
+    /// `payments` has the object, the provider says it doesn't exist.
+    /// Flagged by the worker, outside any run.
+    MissingAtProvider,
+    /// Taken in a currency the provider's terms didn't accept at the time.
+    CurrencyNotAccepted,
+}
+
+impl DiscrepancyKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::MissingLocally => "missing_locally",
+            Self::StatusMismatch => "status_mismatch",
+            Self::AmountMismatch => "amount_mismatch",
+            Self::CurrencyMismatch => "currency_mismatch",
+            Self::MissingAtProvider => "missing_at_provider",
+            Self::CurrencyNotAccepted => "currency_not_accepted",
+        }
+    }
+}
+
+impl fmt::Display for DiscrepancyKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl TryFrom<&str> for DiscrepancyKind {
+    type Error = DomainError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s {
+            "missing_locally" => Ok(Self::MissingLocally),
+            "status_mismatch" => Ok(Self::StatusMismatch),
+            "amount_mismatch" => Ok(Self::AmountMismatch),
+            "currency_mismatch" => Ok(Self::CurrencyMismatch),
+            "missing_at_provider" => Ok(Self::MissingAtProvider),
+            "currency_not_accepted" => Ok(Self::CurrencyNotAccepted),
+            other => Err(DomainError::Validation(format!(
+                "unknown discrepancy kind: {other}"
+            ))),
+        }
+    }
+}
+
+/// One difference between provider and local state, ready to persist.
+pub struct NewDiscrepancy {
+    pub id: Uuid,
+    pub run_id: Uuid,
+    pub payment_id: Option<Uuid>,
+    pub external_id: String,
+    pub kind: DiscrepancyKind,
+    pub details: serde_json::Value,
+}
+
+impl NewDiscrepancy {
+    pub fn audit_entry(&self, actor: &str) -> NewAuditEntry {
+        NewAuditEntry {
+            id: Uuid::now_v7(),
+            entity_type: "reconciliation".to_string(),
+            entity_id: Some(self.id),
+            external_id: Some(self.external_id.clone()),
+            // Synthetic, but unique per finding so the audit dedup index holds.
+            event_id: format!("recon:{}:{}:{}", self.run_id, self.external_id, self.kind),
+            source: None,
+            tenant_id: None,
+            action: "reconciliation_discrepancy".to_string(),
+            actor: actor.to_string(),
+            detail: serde_json::json!({
+                "kind": self.kind.as_str(),
+                "run_id": self.run_id,
+                "details": self.details,
+            }),
+        }
+    }
+}
+
+/// Pure diff of one provider object against local state.
+pub fn diff(
+    run_id: Uuid,
+    remote: &FetchedPayment,
+    local: Option<&LocalSnapshot>,
+) -> Vec<NewDiscrepancy> {
+    let external_id = remote.external_id.as_str().to_string();
+    let finding = |kind, payment_id, details| NewDiscrepancy {
+        id: Uuid::now_v7(),
+        run_id,
+        payment_id,
+        external_id: external_id.clone(),
+        kind,
+        details,
+    };
+
+    let Some(local) = local else {
+        return vec![finding(
+            DiscrepancyKind::MissingLocally,
+            None,
+            serde_json::json!({
+                "provider_status": remote.status.as_str(),
+                "provider_amount": remote.money.amount().cents(),
+                "provider_currency": remote.money.currency().as_str(),
+            }),
+        )];
+    };
+
+    let mut out = Vec::new();
+    if local.status != remote.status {
+        out.push(finding(
+            DiscrepancyKind::StatusMismatch,
+            Some(local.id),
+            serde_json::json!({
+                "local": local.status.as_str(),
+                "provider": remote.status.as_str(),
+            }),
+        ));
+    }
+    if local.amount != remote.money.amount().cents() {
+        out.push(finding(
+            DiscrepancyKind::AmountMismatch,
+            Some(local.id),
+            serde_json::json!({
+                "local": local.amount,
+                "provider": remote.money.amount().cents(),
+                "currency": local.currency.as_str(),
+            }),
+        ));
+    }
+    if &local.currency != remote.money.currency() {
+        out.push(finding(
+            DiscrepancyKind::CurrencyMismatch,
+            Some(local.id),
+            serde_json::json!({
+                "local": local.currency.as_str(),
+                "provider": remote.money.currency().as_str(),
+            }),
+        ));
+    }
+    out
+}
+
+/// A finding when `local` was taken while `terms`, the version of its
+/// provider's terms in force at `taken_at`, didn't accept its currency. No
+/// terms means nothing restricted it.
+pub fn unaccepted_currency(
+    run_id: Uuid,
+    local: &LocalSnapshot,
+    taken_at: DateTime<Utc>,
+    terms: Option<&CurrencyTerms>,
+) -> Option<NewDiscrepancy> {
+    let terms = terms.filter(|t| !t.accepted)?;
+    Some(NewDiscrepancy {
+        id: Uuid::now_v7(),
+        run_id,
+        payment_id: Some(local.id),
+        external_id: local.external_id.clone(),
+        kind: DiscrepancyKind::CurrencyNotAccepted,
+        details: serde_json::json!({
+            "currency": local.currency.as_str(),
+            "taken_at": taken_at,
+            "recorded_at": local.created_at,
+            "terms_id": terms.id,
+        }),
+    })
+}
+
+// ── Read models ──────────────────────────────────────────────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct ReconciliationRunView {
+    pub id: Uuid,
+    pub source: String,
+    pub window_start: chrono::DateTime<chrono::Utc>,
+    pub window_end: chrono::DateTime<chrono::Utc>,
+    pub status: String,
+    pub payments_checked: i32,
+    pub discrepancies: i32,
+    pub error: Option<String>,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    pub finished_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiscrepancyView {
+    pub id: Uuid,
+    pub external_id: Option<String>,
+    pub kind: Option<DiscrepancyKind>,
+    pub details: Option<serde_json::Value>,
+    pub resolved_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+// ── Summary ──────────────────────────────────────────────────────────────────
+
+/// Unmatched ids listed per kind in a summary; the rest are only counted.
+const SUMMARY_LISTED_IDS: usize = 20;
+
+/// Objects of one discrepancy kind, for the summary.
+#[derive(Debug, Clone, Serialize)]
+pub struct UnmatchedGroup {
+    pub kind: DiscrepancyKind,
+    pub count: usize,
+    /// First [`SUMMARY_LISTED_IDS`] external ids.
+    pub external_ids: Vec<String>,
+}
+
+/// What a finished run means for finance: how much matched, what didn't,
+/// and how far the totals are apart per currency.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReconciliationSummary {
+    pub run_id: Uuid,
+    pub source: String,
+    pub window_start: chrono::DateTime<chrono::Utc>,
+    pub window_end: chrono::DateTime<chrono::Utc>,
+    pub status: String,
+    pub error: Option<String>,
+    pub checked: i32,
+    pub matched: i32,
+    pub matched_pct: f64,
+    pub unmatched: Vec<UnmatchedGroup>,
+    /// Provider minus local amount, in minor units, per currency. Missing
+    /// rows count in full; currency mismatches can't be netted and are left out.
+    pub deltas: BTreeMap<String, i64>,
+    pub report_url: String,
+}
+
+impl ReconciliationSummary {
+    pub fn new(
+        run: &ReconciliationRunView,
+        discrepancies: &[DiscrepancyView],
+        report_url: String,
+    ) -> Self {
+        let mut by_kind: BTreeMap<DiscrepancyKind, Vec<String>> = BTreeMap::new();
+        let mut unmatched_ids = BTreeSet::new();
+        let mut deltas: BTreeMap<String, i64> = BTreeMap::new();
+
+        for d in discrepancies {
+            let (Some(kind), Some(external_id)) = (d.kind, d.external_id.as_ref()) else {
+                continue;
+            };
+            by_kind.entry(kind).or_default().push(external_id.clone());
+            unmatched_ids.insert(external_id.as_str());
+
+            let details = d.details.as_ref().unwrap_or(&serde_json::Value::Null);
+            let delta = match kind {
+                DiscrepancyKind::MissingLocally => details["provider_amount"]
+                    .as_i64()
+                    .zip(details["provider_currency"].as_str()),
+                DiscrepancyKind::AmountMismatch => {
+                    let diff = details["provider"].as_i64().zip(details["local"].as_i64());
+                    // Rows recorded before the currency was kept.
+                    let currency = details["currency"].as_str().unwrap_or("unknown");
+                    diff.map(|(provider, local)| (provider - local, currency))
+                }
+                DiscrepancyKind::StatusMismatch
+                | DiscrepancyKind::CurrencyMismatch
+                | DiscrepancyKind::MissingAtProvider
+                | DiscrepancyKind::CurrencyNotAccepted => None,
+            };
+            if let Some((amount, currency)) = delta {
+                *deltas.entry(currency.to_string()).or_default() += amount;
+            }
+        }
+
+        let matched = (run.payments_checked - unmatched_ids.len() as i32).max(0);
+        let matched_pct = if run.payments_checked > 0 {
+            (f64::from(matched) * 1000.0 / f64::from(run.payments_checked)).round() / 10.0
+        } else {
+            100.0
+        };
+        let unmatched = by_kind
+            .into_iter()
+            .map(|(kind, mut ids)| {
+                let count = ids.len();
+                ids.truncate(SUMMARY_LISTED_IDS);
+                UnmatchedGroup {
+                    kind,
+                    count,
+                    external_ids: ids,
+                }
+            })
+            .collect();
+
+        Self {
+            run_id: run.id,
+            source: run.source.clone(),
+            window_start: run.window_start,
+            window_end: run.window_end,
+            status: run.status.clone(),
+            error: run.error.clone(),
+            checked: run.payments_checked,
+            matched,
+            matched_pct,
+            unmatched,
+            deltas,
+            report_url,
+        }
+    }
+
+    pub fn subject(&self) -> String {
+        if self.status == "failed" {
+            return format!("Reconciliation {} failed", self.source);
+        }
+        let unmatched = self.checked - self.matched;
+        format!(
+            "Reconciliation {}: {}% matched, {unmatched} unmatched",
+            self.source, self.matched_pct
+        )
+    }
+
+    /// Plain-text body for inboxes and chat.
+    pub fn text(&self) -> String {
+        let fmt_ts = |ts: chrono::DateTime<chrono::Utc>| ts.format("%Y-%m-%d %H:%M").to_string();
+        let mut out = format!(
+            "Reconciliation against {} for {} to {} UTC: {}\n",
+            self.source,
+            fmt_ts(self.window_start),
+            fmt_ts(self.window_end),
+            self.status
+        );
+        if let Some(error) = &self.error {
+            out.push_str(&format!("Error: {error}\n"));
+        }
+        out.push_str(&format!(
+            "Checked {}, matched {} ({}%)\n",
+            self.checked, self.matched, self.matched_pct
+        ));
+        if !self.unmatched.is_empty() {
+            out.push_str("Unmatched:\n");
+            for group in &self.unmatched {
+                let more = group.count - group.external_ids.len();
+                let tail = if more > 0 {
+                    format!(" and {more} more")
+                } else {
+                    String::new()
+                };
+                out.push_str(&format!(
+                    "  {} ({}): {}{tail}\n",
+                    group.kind,
+                    group.count,
+                    group.external_ids.join(", ")
+                ));
+            }
+        }
+        if !self.deltas.is_empty() {
+            out.push_str("Deltas (provider - local, minor units):\n");
+            for (currency, delta) in &self.deltas {
+                out.push_str(&format!("  {} {delta:+}\n", currency.to_uppercase()));
+            }
+        }
+        out.push_str(&format!("Details: {}\n", self.report_url));
+        out
+    }
+
+    pub fn to_alert(&self) -> Alert {
+        Alert {
+            kind: "reconciliation_summary",
+            subject: self.subject(),
+            text: self.text(),
+            detail: serde_json::json!(self),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{
+        id::ExternalId,
+        money::{Money, MoneyAmount},
+        payment::PaymentDirection,
+    };
+
+    fn remote(status: PaymentStatus, cents: i64, currency: Currency) -> FetchedPayment {
+        FetchedPayment {
+            external_id: ExternalId::new("pi_diff").unwrap(),
+            direction: PaymentDirection::Inbound,
+            status,
+            money: Money::new(MoneyAmount::new(cents).unwrap(), currency),
+            metadata: serde_json::json!({}),
+            parent_external_id: None,
+            failure: None,
+            authorized_amount: None,
+            receipt: None,
+            customer: None,
+            created_at: None,
+        }
+    }
+
+    fn local(status: PaymentStatus, amount: i64) -> LocalSnapshot {
+        LocalSnapshot {
+            id: Uuid::now_v7(),
+            external_id: "pi_diff".into(),
+            status,
+            amount,
+            currency: Currency::USD,
+            created_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn matching_payment_has_no_discrepancies() {
+        let r = remote(PaymentStatus::Succeeded, 5000, Currency::USD);
+        let l = local(PaymentStatus::Succeeded, 5000);
+        assert!(diff(Uuid::now_v7(), &r, Some(&l)).is_empty());
+    }
+
+    #[test]
+    fn missing_local_row_is_reported() {
+        let r = remote(PaymentStatus::Succeeded, 5000, Currency::USD);
+        let found = diff(Uuid::now_v7(), &r, None);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].kind, DiscrepancyKind::MissingLocally);
+        assert_eq!(found[0].payment_id, None);
+    }
+
+    #[test]
+    fn each_mismatch_is_reported_separately() {
+        let r = remote(PaymentStatus::Failed, 4000, Currency::EUR);
+        let l = local(PaymentStatus::Succeeded, 5000);
+        let kinds: Vec<_> = diff(Uuid::now_v7(), &r, Some(&l))
+            .into_iter()
+            .map(|d| d.kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                DiscrepancyKind::StatusMismatch,
+                DiscrepancyKind::AmountMismatch,
+                DiscrepancyKind::CurrencyMismatch,
+            ]
+        );
+    }
+
+    #[test]
+    fn audit_event_id_is_unique_per_finding() {
+        let run = Uuid::now_v7();
+        let r = remote(PaymentStatus::Failed, 4000, Currency::USD);
+        let l = local(PaymentStatus::Succeeded, 5000);
+        let found = diff(run, &r, Some(&l));
+        let a = found[0].audit_entry("reconciler:stripe");
+        let b = found[1].audit_entry("reconciler:stripe");
+        assert_ne!(a.event_id, b.event_id);
+        assert_eq!(a.action, "reconciliation_discrepancy");
+    }
+
+    #[test]
+    fn summary_counts_matches_and_nets_deltas_per_currency() {
+        let run_id = Uuid::now_v7();
+        let now = chrono::Utc::now();
+        let run = ReconciliationRunView {
+            id: run_id,
+            source: "stripe".into(),
+            window_start: now - chrono::TimeDelta::hours(24),
+            window_end: now,
+            status: "completed".into(),
+            payments_checked: 10,
+            discrepancies: 3,
+            error: None,
+            started_at: now,
+            finished_at: Some(now),
+        };
+        let l = local(PaymentStatus::Succeeded, 5000);
+        let views: Vec<_> = [
+            diff(
+                run_id,
+                &remote(PaymentStatus::Failed, 4000, Currency::USD),
+                Some(&l),
+            ),
+            diff(
+                run_id,
+                &remote(PaymentStatus::Succeeded, 700, Currency::EUR),
+                None,
+            ),
+        ]
+        .into_iter()
+        .flatten()
+        .map(|d| DiscrepancyView {
+            id: d.id,
+            external_id: Some(d.external_id),
+            kind: Some(d.kind),
+            details: Some(d.details),
+            resolved_at: None,
+            created_at: now,
+        })
+        .collect();
+
+        let summary = ReconciliationSummary::new(&run, &views, "http://x/r".into());
+        // pi_diff shows up three times but is one unmatched object.
+        assert_eq!(summary.matched, 9);
+        assert_eq!(summary.matched_pct, 90.0);
+        let kinds: Vec<_> = summary.unmatched.iter().map(|g| g.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                DiscrepancyKind::MissingLocally,
+                DiscrepancyKind::StatusMismatch,
+                DiscrepancyKind::AmountMismatch,
+            ]
+        );
+        assert_eq!(summary.deltas["usd"], -1000);
+        assert_eq!(summary.deltas["eur"], 700);
+        let text = summary.text();
+        assert!(text.contains("matched 9 (90%)"));
+        assert!(text.contains("USD -1000"));
+        assert!(text.ends_with("Details: http://x/r\n"));
+    }
+}
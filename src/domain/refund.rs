@@ -0,0 +1,156 @@
+use {
+    super::{
+        audit::NewAuditEntry,
+        money::Currency,
+        payment::{NewPayment, PaymentDirection},
+    },
+    uuid::Uuid,
+};
+
+/// A refund's parent payment, as far as the refund checks it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParentPayment {
+    pub id: Uuid,
+    pub direction: PaymentDirection,
+    pub currency: Currency,
+}
+
+/// How a refund relates to the payment it names as its parent. Whether
+/// the amounts add up is `flag_over_refund`'s to say.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RefundLink {
+    /// An inbound payment in the refund's currency.
+    Linked(Uuid),
+    /// No payment by that id (yet: its fetch is queued).
+    Orphan,
+    /// A payment in another currency.
+    CurrencyMismatch {
+        parent_id: Uuid,
+        parent_currency: Currency,
+    },
+    /// An outbound payment, which has nothing to refund.
+    NotRefundable(Uuid),
+}
+
+impl RefundLink {
+    /// The parent `payment` refunds: set for an outbound payment naming
+    /// one, other than a dispute.
+    pub fn parent_of(payment: &NewPayment) -> Option<&str> {
+        if *payment.direction() != PaymentDirection::Outbound || payment.status().is_dispute() {
+            return None;
+        }
+        payment.parent_external_id()
+    }
+
+    pub fn new(refund: &NewPayment, parent: Option<&ParentPayment>) -> Self {
+        match parent {
+            None => Self::Orphan,
+            Some(p) if p.direction != PaymentDirection::Inbound => Self::NotRefundable(p.id),
+            Some(p) if p.currency != *refund.money().currency() => Self::CurrencyMismatch {
+                parent_id: p.id,
+                parent_currency: p.currency,
+            },
+            Some(p) => Self::Linked(p.id),
+        }
+    }
+
+    pub fn parent_id(&self) -> Option<Uuid> {
+        match self {
+            Self::Linked(id) | Self::NotRefundable(id) => Some(*id),
+            Self::CurrencyMismatch { parent_id, .. } => Some(*parent_id),
+            Self::Orphan => None,
+        }
+    }
+
+    /// Why the link is an anomaly; `None` when it holds.
+    pub fn anomaly(&self) -> Option<&'static str> {
+        match self {
+            Self::Linked(_) => None,
+            Self::Orphan => Some("orphan"),
+            Self::CurrencyMismatch { .. } => Some("currency_mismatch"),
+            Self::NotRefundable(_) => Some("parent_not_refundable"),
+        }
+    }
+
+    /// Point `audit`, an entry about the refund, at its parent.
+    pub fn mark(&self, audit: &mut NewAuditEntry) {
+        if let Some(parent_id) = self.parent_id() {
+            audit.detail["parent_id"] = parent_id.to_string().into();
+        }
+    }
+
+    /// The `refund_unlinked` anomaly recorded against `refund` when the
+    /// link doesn't hold.
+    pub fn audit_entry(&self, refund: &NewPayment, actor: &str) -> Option<NewAuditEntry> {
+        let reason = self.anomaly()?;
+        let mut audit = refund.audit_entry(actor, "refund_unlinked");
+        audit.event_id = format!("refund_link:{}", refund.last_event_id());
+        audit.detail = serde_json::json!({
+            "reason": reason,
+            "parent_external_id": refund.parent_external_id(),
+            "currency": refund.money().currency().as_str(),
+            "anomaly": true,
+        });
+        self.mark(&mut audit);
+        if let Self::CurrencyMismatch {
+            parent_currency, ..
+        } = self
+        {
+            audit.detail["parent_currency"] = parent_currency.as_str().into();
+        }
+        Some(audit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{domain::payment::PaymentStatus, testing::make_refund},
+    };
+
+    fn parent(direction: PaymentDirection, currency: Currency) -> ParentPayment {
+        ParentPayment {
+            id: Uuid::nil(),
+            direction,
+            currency,
+        }
+    }
+
+    #[test]
+    fn refunds_link_to_inbound_parents_in_their_currency() {
+        let refund = make_refund("re_1", "evt_1", PaymentStatus::Refunded, 100, "pi_1");
+        assert_eq!(RefundLink::parent_of(&refund), Some("pi_1"));
+
+        let linked = RefundLink::new(
+            &refund,
+            Some(&parent(PaymentDirection::Inbound, Currency::USD)),
+        );
+        assert_eq!(linked, RefundLink::Linked(Uuid::nil()));
+        assert!(linked.audit_entry(&refund, "test").is_none());
+
+        let orphan = RefundLink::new(&refund, None);
+        let audit = orphan.audit_entry(&refund, "test").unwrap();
+        assert_eq!(audit.action, "refund_unlinked");
+        assert_eq!(audit.entity_id, Some(refund.id()));
+        assert_eq!(audit.detail["reason"], "orphan");
+        assert!(audit.detail.get("parent_id").is_none());
+
+        let mismatch = RefundLink::new(
+            &refund,
+            Some(&parent(PaymentDirection::Inbound, Currency::EUR)),
+        );
+        let audit = mismatch.audit_entry(&refund, "test").unwrap();
+        assert_eq!(
+            (&audit.detail["reason"], &audit.detail["parent_currency"]),
+            (&"currency_mismatch".into(), &"eur".into())
+        );
+        assert_eq!(audit.detail["parent_id"], Uuid::nil().to_string());
+
+        let refund_of_refund = RefundLink::new(
+            &refund,
+            Some(&parent(PaymentDirection::Outbound, Currency::USD)),
+        );
+        assert_eq!(refund_of_refund.anomaly(), Some("parent_not_refundable"));
+    }
+}
@@ -0,0 +1,450 @@
+use {
+    super::{
+        ledger::LedgerLine,
+        payment::{NewPayment, PaymentStatus, PaymentView, ProcessResult},
+    },
+    chrono::{DateTime, Utc},
+    serde::{Deserialize, Serialize},
+};
+
+/// Which stored provider events a sandbox replay re-runs. At least one of
+/// `event_ids`, `object_ids` or `since` must be given.
+#[derive(Debug, Default, Deserialize)]
+pub struct ReplayRequest {
+    #[serde(default)]
+    pub event_ids: Vec<String>,
+    /// Every event for these payments.
+    #[serde(default)]
+    pub object_ids: Vec<String>,
+    /// Events received in `[since, until]`.
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub limit: Option<i64>,
+    /// Leave the scratch schema in place for inspection instead of dropping it.
+    #[serde(default)]
+    pub keep: bool,
+}
+
+impl ReplayRequest {
+    pub fn is_unbounded(&self) -> bool {
+        self.event_ids.is_empty() && self.object_ids.is_empty() && self.since.is_none()
+    }
+}
+
+/// A `provider_events` row picked for replay, with the source of the
+/// payment it belongs to (none for passthrough events).
+#[derive(Debug, Clone)]
+pub struct ReplayEvent {
+    pub event_id: String,
+    pub object_id: String,
+    pub event_type: String,
+    pub provider_ts: i64,
+    pub payload: serde_json::Value,
+    pub source: Option<String>,
+    pub tenant_id: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ReplayCounts {
+    pub created: u64,
+    pub updated: u64,
+    pub unchanged: u64,
+    pub stale_ignored: u64,
+    pub anomalies: u64,
+    pub duplicates: u64,
+    pub diverted: u64,
+}
+
+impl ReplayCounts {
+    pub fn record(&mut self, result: &ProcessResult) {
+        match result {
+            ProcessResult::Created(_) => self.created += 1,
+            ProcessResult::Updated(_) => self.updated += 1,
+            ProcessResult::Unchanged(_) => self.unchanged += 1,
+            ProcessResult::StaleIgnored(_) => self.stale_ignored += 1,
+            ProcessResult::Anomaly(_) => self.anomalies += 1,
+            ProcessResult::Duplicate => self.duplicates += 1,
+            ProcessResult::Diverted(_) => self.diverted += 1,
+            // Passthrough events aren't replayed.
+            ProcessResult::Logged => {}
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SkippedEvent {
+    pub event_id: String,
+    pub reason: String,
+}
+
+/// One field that ended up different in the sandbox. A side that has no
+/// row at all shows as `null` under `field: "payment"`.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct FieldDiff {
+    pub field: String,
+    pub production: serde_json::Value,
+    pub sandbox: serde_json::Value,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct PaymentDiff {
+    pub external_id: String,
+    pub fields: Vec<FieldDiff>,
+}
+
+/// Outcome of a replay: what the current pipeline did with the events, and
+/// where the payments it produced differ from production.
+#[derive(Debug, Serialize)]
+pub struct ReplayReport {
+    pub schema: String,
+    /// Whether `schema` still exists.
+    pub kept: bool,
+    pub selected: usize,
+    pub replayed: ReplayCounts,
+    pub skipped: Vec<SkippedEvent>,
+    pub diffs: Vec<PaymentDiff>,
+}
+
+/// Outcome of replaying one stored event against production.
+#[derive(Debug, Serialize)]
+pub struct EventReplay {
+    pub event_id: String,
+    /// What the pipeline did with it (`created`, `updated`, `unchanged`,
+    /// `stale_ignored`, `anomaly`).
+    pub result: &'static str,
+    /// The payment as it stands after the replay.
+    pub payment: Option<PaymentView>,
+}
+
+/// `POST /admin/simulate-event`: a stored event by `event_id`, as if the
+/// provider sent it again, or with `payload` one not received yet.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SimulateRequest {
+    pub event_id: String,
+    /// The event as the provider sends it. Needs `source`.
+    pub payload: Option<serde_json::Value>,
+    pub source: Option<String>,
+    /// Defaults to the payload's `type` (Stripe) or `event_type` (PayPal).
+    pub event_type: Option<String>,
+    /// Defaults to the payload's `created`, else now.
+    pub provider_ts: Option<i64>,
+    /// Connected account the event is for. Defaults to the payload's
+    /// `account` (Stripe Connect); none for the platform's.
+    pub tenant_id: Option<String>,
+}
+
+/// Prefix of the event id a simulated event's own audit entry is written
+/// under, so it never collides with the entry of the event as received.
+pub const SIMULATED_EVENT_PREFIX: &str = "simulate:";
+
+/// An audit entry a dry run wrote, read back before it rolled back.
+#[derive(Debug, Clone)]
+pub struct SimulatedEntry {
+    pub event_id: String,
+    pub action: String,
+    pub detail: serde_json::Value,
+}
+
+/// What the pipeline did with a simulated event before it was rolled back.
+#[derive(Debug)]
+pub enum Applied {
+    /// Refused before anything was written, e.g. a payment of another tenant.
+    Rejected(String),
+    Done {
+        result: ProcessResult,
+        entries: Vec<SimulatedEntry>,
+        ledger: Vec<LedgerLine>,
+    },
+}
+
+/// What the pipeline would do with one event, worked out by running it and
+/// rolling back.
+#[derive(Debug, Serialize)]
+pub struct Simulation {
+    pub event_id: String,
+    pub external_id: String,
+    /// What receiving the event now would do: `duplicate` if it was already
+    /// received, else `decision`.
+    pub result: &'static str,
+    /// What the pipeline decides past the dedup check, as a replay would:
+    /// `created`, `updated`, `unchanged` for a status the payment already
+    /// has, `stale_ignored` for an event older than one already seen or
+    /// behind a fresher feed, `anomaly`, `diverted` for an event dated in a
+    /// closed period, or `rejected` for a payment of another tenant.
+    pub decision: &'static str,
+    pub current_status: Option<PaymentStatus>,
+    pub incoming_status: PaymentStatus,
+    /// The audit entry it writes (`created`, `status_changed`,
+    /// `status_unchanged`, `event_stale_ignored`, `event_received`,
+    /// `event_diverted`); none when rejected.
+    pub audit_action: Option<String>,
+    /// Anomalies it records beside its own entry, e.g. `refund_unlinked`
+    /// against a refund or `over_refunded` against its parent.
+    pub anomalies: Vec<String>,
+    /// Ledger lines it posts; empty when no money moves.
+    pub ledger: Vec<LedgerLine>,
+    pub reason: Option<String>,
+}
+
+impl Simulation {
+    /// Report what `applied` shows the pipeline did, given whether the
+    /// event was already received and the payment's status before it.
+    pub fn new(
+        payment: &NewPayment,
+        duplicate: bool,
+        current_status: Option<PaymentStatus>,
+        applied: Applied,
+    ) -> Self {
+        let mut sim = Self {
+            event_id: payment.last_event_id().to_string(),
+            external_id: payment.external_id().to_string(),
+            result: "duplicate",
+            decision: "rejected",
+            current_status,
+            incoming_status: payment.status().clone(),
+            audit_action: None,
+            anomalies: Vec::new(),
+            ledger: Vec::new(),
+            reason: None,
+        };
+        match applied {
+            Applied::Rejected(reason) => sim.reason = Some(reason),
+            Applied::Done {
+                result,
+                entries,
+                ledger,
+            } => {
+                sim.decision = result.as_str();
+                sim.ledger = ledger;
+                for entry in entries {
+                    if entry.event_id.starts_with(SIMULATED_EVENT_PREFIX) {
+                        sim.reason = sim.reason_for(&entry);
+                        sim.audit_action = Some(entry.action);
+                    } else if entry.detail["anomaly"] == true {
+                        sim.anomalies.push(entry.action);
+                    }
+                }
+            }
+        }
+        if !duplicate {
+            sim.result = sim.decision;
+        }
+        sim
+    }
+
+    /// Why the event's own entry says the pipeline didn't apply it, if so.
+    fn reason_for(&self, entry: &SimulatedEntry) -> Option<String> {
+        let detail = &entry.detail;
+        match entry.action.as_str() {
+            "status_unchanged" => Some(format!("the payment is already {}", self.incoming_status)),
+            "event_stale_ignored" if detail["superseded"] == true => {
+                let ahead = &detail["conflict"];
+                Some(format!(
+                    "feed {} is ahead, at {} ({})",
+                    ahead["feed"].as_str().unwrap_or_default(),
+                    ahead["provider_ts"],
+                    ahead["event_id"].as_str().unwrap_or_default()
+                ))
+            }
+            "event_stale_ignored" => Some(format!(
+                "an event from {} was already seen",
+                detail["last_provider_ts"]
+            )),
+            "event_received" => Some(format!(
+                "the state machine refuses {} -> {}",
+                detail["current_status"].as_str().unwrap_or_default(),
+                self.incoming_status
+            )),
+            "event_diverted" => Some(format!(
+                "dated in the period closed through {}, held for adjustment",
+                detail["closed_through"].as_str().unwrap_or_default()
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// Row timestamps differ by construction, so they're left out.
+const IGNORED_FIELDS: &[&str] = &["created_at", "updated_at"];
+
+/// Field-by-field difference between the production and sandbox rows for
+/// `external_id`, or `None` if they match.
+pub fn diff_payments(
+    external_id: &str,
+    production: Option<&PaymentView>,
+    sandbox: Option<&PaymentView>,
+) -> Result<Option<PaymentDiff>, serde_json::Error> {
+    let fields = match (production, sandbox) {
+        (None, None) => Vec::new(),
+        (Some(_), None) | (None, Some(_)) => vec![FieldDiff {
+            field: "payment".into(),
+            production: serde_json::to_value(production)?,
+            sandbox: serde_json::to_value(sandbox)?,
+        }],
+        (Some(prod), Some(sand)) => {
+            let (prod, sand) = (serde_json::to_value(prod)?, serde_json::to_value(sand)?);
+            let (Some(prod), Some(sand)) = (prod.as_object(), sand.as_object()) else {
+                return Ok(None);
+            };
+            prod.iter()
+                .filter(|(field, _)| !IGNORED_FIELDS.contains(&field.as_str()))
+                .filter(|(field, value)| sand.get(*field) != Some(*value))
+                .map(|(field, value)| FieldDiff {
+                    field: field.clone(),
+                    production: value.clone(),
+                    sandbox: sand.get(field).cloned().unwrap_or_default(),
+                })
+                .collect()
+        }
+    };
+    Ok((!fields.is_empty()).then(|| PaymentDiff {
+        external_id: external_id.to_string(),
+        fields,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{
+        id::{EventId, ExternalId},
+        money::{Currency, Money, MoneyAmount},
+        payment::{NewPaymentParams, PaymentDirection},
+    };
+    use uuid::Uuid;
+
+    fn view(status: PaymentStatus) -> PaymentView {
+        PaymentView {
+            id: ExternalId::new("pi_1").unwrap(),
+            source: "stripe".into(),
+            tenant_id: None,
+            status,
+            amount: 1000,
+            currency: Currency::USD,
+            direction: PaymentDirection::Inbound,
+            parent_external_id: None,
+            failure: None,
+            authorized_amount: None,
+            receipt: None,
+            updated_at: Utc::now(),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn diff_lists_changed_fields_only() {
+        let prod = view(PaymentStatus::Succeeded);
+        assert_eq!(
+            diff_payments("pi_1", Some(&prod), Some(&prod)).unwrap(),
+            None
+        );
+
+        let sand = view(PaymentStatus::Pending);
+        let diff = diff_payments("pi_1", Some(&prod), Some(&sand))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            diff.fields,
+            vec![FieldDiff {
+                field: "status".into(),
+                production: "succeeded".into(),
+                sandbox: "pending".into(),
+            }]
+        );
+
+        let missing = diff_payments("pi_1", Some(&prod), None).unwrap().unwrap();
+        assert_eq!(missing.fields[0].field, "payment");
+        assert!(missing.fields[0].sandbox.is_null());
+    }
+
+    #[test]
+    fn simulation_reports_what_the_pipeline_wrote() {
+        let incoming = NewPayment::new(NewPaymentParams {
+            external_id: ExternalId::new("re_1").unwrap(),
+            source: "stripe".into(),
+            event_type: "charge.refunded".into(),
+            direction: PaymentDirection::Outbound,
+            money: Money::new(MoneyAmount::new(1000).unwrap(), Currency::USD),
+            status: PaymentStatus::Succeeded,
+            metadata: serde_json::json!({}),
+            raw_event: serde_json::json!({}),
+            last_event_id: EventId::new("evt_1").unwrap(),
+            parent_external_id: Some(ExternalId::new("pi_1").unwrap()),
+            provider_ts: 1000,
+            failure: None,
+            authorized_amount: None,
+            receipt: None,
+            tenant_id: None,
+            customer: None,
+        });
+        let entry = |event_id: &str, action: &str, detail| SimulatedEntry {
+            event_id: event_id.into(),
+            action: action.into(),
+            detail,
+        };
+
+        let diverted = Simulation::new(
+            &incoming,
+            false,
+            None,
+            Applied::Done {
+                result: ProcessResult::Diverted(Uuid::nil()),
+                entries: vec![
+                    entry(
+                        "simulate:1",
+                        "event_diverted",
+                        serde_json::json!({"closed_through": "2026-03-31T23:59:59Z"}),
+                    ),
+                    entry(
+                        "refund_link:evt_1",
+                        "refund_unlinked",
+                        serde_json::json!({"anomaly": true}),
+                    ),
+                ],
+                ledger: Vec::new(),
+            },
+        );
+        assert_eq!(
+            (diverted.result, diverted.audit_action.as_deref()),
+            ("diverted", Some("event_diverted"))
+        );
+        assert_eq!(diverted.anomalies, ["refund_unlinked"]);
+        assert!(diverted.reason.unwrap().contains("2026-03-31"));
+
+        let superseded = Simulation::new(
+            &incoming,
+            true,
+            Some(PaymentStatus::Pending),
+            Applied::Done {
+                result: ProcessResult::StaleIgnored(Uuid::nil()),
+                entries: vec![entry(
+                    "simulate:2",
+                    "event_stale_ignored",
+                    serde_json::json!({
+                        "superseded": true,
+                        "conflict": {"feed": "stripe@acct_1", "provider_ts": 2000, "event_id": "evt_2"},
+                    }),
+                )],
+                ledger: Vec::new(),
+            },
+        );
+        assert_eq!(
+            (superseded.result, superseded.decision),
+            ("duplicate", "stale_ignored")
+        );
+        assert_eq!(
+            superseded.reason.as_deref(),
+            Some("feed stripe@acct_1 is ahead, at 2000 (evt_2)")
+        );
+
+        let rejected = Simulation::new(
+            &incoming,
+            false,
+            Some(PaymentStatus::Pending),
+            Applied::Rejected("re_1 belongs to another tenant".into()),
+        );
+        assert_eq!((rejected.result, rejected.audit_action), ("rejected", None));
+        assert!(rejected.reason.unwrap().contains("another tenant"));
+    }
+}
@@ -0,0 +1,247 @@
+use {
+    super::{
+        currency_terms::{CurrencyTerms, TermsTimeline},
+        error::DomainError,
+        money::{Currency, Money, MoneyAmount},
+        payment::PaymentStatus,
+    },
+    chrono::{DateTime, NaiveDate, Utc},
+    serde::Serialize,
+    uuid::Uuid,
+};
+
+/// One `daily_summaries` row.
+#[derive(Debug, Serialize)]
+pub struct SummaryLine {
+    pub source: String,
+    pub direction: String,
+    pub currency: String,
+    pub status: String,
+    pub payment_count: i64,
+    pub total_amount: i64,
+}
+
+/// How one day's payments from a source, in one direction, ended up.
+/// Cancelled and expired payments are abandoned, not declined, so
+/// `failure_rate` is failures over payments that settled or failed only.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct OutcomeLine {
+    pub source: String,
+    pub direction: String,
+    pub settled: i64,
+    pub failed: i64,
+    pub cancelled: i64,
+    pub expired: i64,
+    pub pending: i64,
+    pub failure_rate: Option<f64>,
+}
+
+impl OutcomeLine {
+    /// One line per source and direction in `summaries`, in their order.
+    /// Dispute rows aren't payment outcomes and are left out.
+    pub fn from_summaries(summaries: &[SummaryLine]) -> Vec<Self> {
+        let mut lines: Vec<Self> = Vec::new();
+        for s in summaries {
+            let Ok(status) = PaymentStatus::try_from(s.status.as_str()) else {
+                continue;
+            };
+            let count: fn(&mut Self) -> &mut i64 = match status {
+                PaymentStatus::Succeeded | PaymentStatus::Refunded => |l| &mut l.settled,
+                PaymentStatus::Failed => |l| &mut l.failed,
+                PaymentStatus::Cancelled => |l| &mut l.cancelled,
+                PaymentStatus::Expired => |l| &mut l.expired,
+                PaymentStatus::Pending => |l| &mut l.pending,
+                PaymentStatus::Disputed
+                | PaymentStatus::DisputeWon
+                | PaymentStatus::DisputeLost => {
+                    continue;
+                }
+            };
+            let i = match lines
+                .iter()
+                .position(|l| l.source == s.source && l.direction == s.direction)
+            {
+                Some(i) => i,
+                None => {
+                    lines.push(Self {
+                        source: s.source.clone(),
+                        direction: s.direction.clone(),
+                        settled: 0,
+                        failed: 0,
+                        cancelled: 0,
+                        expired: 0,
+                        pending: 0,
+                        failure_rate: None,
+                    });
+                    lines.len() - 1
+                }
+            };
+            *count(&mut lines[i]) += s.payment_count;
+        }
+        for line in &mut lines {
+            let decided = line.settled + line.failed;
+            line.failure_rate = (decided > 0).then(|| line.failed as f64 / decided as f64);
+        }
+        lines
+    }
+}
+
+/// What one day's settled inbound payments from a source, in one currency,
+/// are expected to cost under the provider's terms.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct FeeLine {
+    pub source: String,
+    pub currency: String,
+    /// The `currency_terms` version applied.
+    pub terms_id: Uuid,
+    pub fee_bps: i32,
+    pub fixed_fee_minor: i64,
+    pub payment_count: i64,
+    pub total_amount: i64,
+    pub fee_amount: i64,
+}
+
+impl FeeLine {
+    /// One line per source and currency in `summaries` with terms in force
+    /// at `at`, in their order. Refunded payments were settled first and
+    /// count; the rest didn't cost a fee.
+    pub fn from_summaries(
+        summaries: &[SummaryLine],
+        terms: &TermsTimeline,
+        at: DateTime<Utc>,
+    ) -> Result<Vec<Self>, DomainError> {
+        let mut lines: Vec<(Self, &CurrencyTerms)> = Vec::new();
+        for s in summaries.iter().filter(|s| s.direction == "inbound") {
+            let settled = matches!(
+                PaymentStatus::try_from(s.status.as_str()),
+                Ok(PaymentStatus::Succeeded | PaymentStatus::Refunded)
+            );
+            let Some(version) = terms.at(&s.source, &s.currency, at).filter(|_| settled) else {
+                continue;
+            };
+            match lines.iter_mut().find(|(l, _)| l.terms_id == version.id) {
+                Some((line, _)) => {
+                    line.payment_count += s.payment_count;
+                    line.total_amount += s.total_amount;
+                }
+                None => lines.push((
+                    Self {
+                        source: s.source.clone(),
+                        currency: s.currency.clone(),
+                        terms_id: version.id,
+                        fee_bps: version.fee_bps,
+                        fixed_fee_minor: version.fixed_fee_minor,
+                        payment_count: s.payment_count,
+                        total_amount: s.total_amount,
+                        fee_amount: 0,
+                    },
+                    version,
+                )),
+            }
+        }
+        lines
+            .into_iter()
+            .map(|(mut line, version)| {
+                let total = Money::new(
+                    MoneyAmount::new(line.total_amount)?,
+                    Currency::try_from(line.currency.as_str())?,
+                );
+                line.fee_amount = version
+                    .expected_fee(total, line.payment_count)?
+                    .amount()
+                    .cents();
+                Ok(line)
+            })
+            .collect()
+    }
+}
+
+/// One `failure_reason_stats` row.
+#[derive(Debug, Serialize)]
+pub struct FailureReasonLine {
+    pub source: String,
+    pub status: String,
+    pub failure_code: String,
+    pub decline_code: String,
+    pub payment_count: i64,
+    pub total_amount: i64,
+}
+
+/// One month of `dispute_stats`. Rates are over decided disputes only
+/// (won + lost) and are `None` while nothing in the bucket has closed.
+#[derive(Debug, Serialize)]
+pub struct DisputeStatsLine {
+    pub month: DateTime<Utc>,
+    pub currency: String,
+    pub card_brand: String,
+    pub dispute_count: i64,
+    pub disputed_amount: i64,
+    pub fee_amount: i64,
+    pub won_count: i64,
+    pub lost_count: i64,
+    pub win_rate: Option<f64>,
+    pub loss_rate: Option<f64>,
+}
+
+/// Dispute financial impact over `[from, to)`, month by month.
+#[derive(Debug, Serialize)]
+pub struct DisputeReport {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub months: Vec<DisputeStatsLine>,
+}
+
+/// Daily report assembled from the day-bucket rollups.
+#[derive(Debug, Serialize)]
+pub struct DailyReport {
+    pub date: NaiveDate,
+    pub summaries: Vec<SummaryLine>,
+    /// `summaries` per source and direction: settled, failed, cancelled.
+    pub outcomes: Vec<OutcomeLine>,
+    /// Expected fees, under the terms in force as the day began.
+    pub fees: Vec<FeeLine>,
+    pub failure_reasons: Vec<FailureReasonLine>,
+}
+
+impl DailyReport {
+    /// UTC midnight starting `date` — the `bucket_start` of its day bucket.
+    pub fn bucket_start(date: NaiveDate) -> DateTime<Utc> {
+        date.and_hms_opt(0, 0, 0)
+            .expect("midnight is always valid")
+            .and_utc()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(source: &str, status: &str, payment_count: i64) -> SummaryLine {
+        SummaryLine {
+            source: source.into(),
+            direction: "inbound".into(),
+            currency: "usd".into(),
+            status: status.into(),
+            payment_count,
+            total_amount: payment_count * 1000,
+        }
+    }
+
+    #[test]
+    fn cancellations_stay_out_of_the_failure_rate() {
+        let lines = OutcomeLine::from_summaries(&[
+            summary("stripe", "succeeded", 6),
+            summary("stripe", "failed", 2),
+            summary("stripe", "cancelled", 5),
+            summary("stripe", "disputed", 1),
+            summary("paypal", "cancelled", 3),
+        ]);
+        assert_eq!(lines.len(), 2);
+        let stripe = &lines[0];
+        assert_eq!((stripe.settled, stripe.failed, stripe.cancelled), (6, 2, 5));
+        assert_eq!(stripe.failure_rate, Some(0.25));
+        // Nothing decided yet.
+        assert_eq!(lines[1].cancelled, 3);
+        assert_eq!(lines[1].failure_rate, None);
+    }
+}
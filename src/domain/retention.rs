@@ -0,0 +1,218 @@
+use {
+    super::{audit::NewAuditEntry, error::DomainError},
+    chrono::{DateTime, TimeDelta, Utc},
+    serde::{Deserialize, Serialize},
+    std::{fmt, str::FromStr},
+    uuid::Uuid,
+};
+
+/// Shortest retention a table may be given: provider events are what
+/// deduplicates redeliveries, and the audit log what ops read back first.
+pub const MIN_RETENTION_DAYS: u32 = 7;
+
+/// A table that is archived and pruned as it ages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetainedTable {
+    /// Aged by `received_at`.
+    ProviderEvents,
+    /// Aged by `created_at`.
+    AuditLog,
+}
+
+impl RetainedTable {
+    pub const ALL: [Self; 2] = [Self::ProviderEvents, Self::AuditLog];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::ProviderEvents => "provider_events",
+            Self::AuditLog => "audit_log",
+        }
+    }
+}
+
+impl fmt::Display for RetainedTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for RetainedTable {
+    type Err = DomainError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|t| t.as_str() == s)
+            .ok_or_else(|| DomainError::Validation(format!("unknown retained table: {s}")))
+    }
+}
+
+/// Rows of `table` older than `max_age_days` are archived, then deleted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct RetentionPolicy {
+    pub table: RetainedTable,
+    pub max_age_days: u32,
+}
+
+impl RetentionPolicy {
+    /// Rows written before this are due.
+    pub fn cutoff(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        now - TimeDelta::days(self.max_age_days.into())
+    }
+}
+
+/// A provider event as archived: the stored row with its payload decoded.
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchivedEvent {
+    pub event_id: String,
+    pub source: String,
+    pub tenant_id: Option<String>,
+    pub object_id: String,
+    pub event_type: String,
+    pub provider_ts: i64,
+    pub received_at: DateTime<Utc>,
+    pub payload: serde_json::Value,
+}
+
+/// Body of `POST /admin/retention/run`; `dry_run` defaults to the
+/// configured mode.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RetentionRunRequest {
+    pub dry_run: Option<bool>,
+}
+
+/// One archive file: the rows of one batch, deleted in the transaction that
+/// recorded it.
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchivedBatch {
+    pub rows: u64,
+    pub oldest: DateTime<Utc>,
+    pub newest: DateTime<Utc>,
+    /// A local path or `s3://<bucket>/<key>`.
+    pub location: String,
+    pub bytes: u64,
+}
+
+/// What one run did, or in a dry run would do, to one table.
+#[derive(Debug, Clone, Serialize)]
+pub struct TableRetention {
+    pub table: RetainedTable,
+    pub max_age_days: u32,
+    pub cutoff: DateTime<Utc>,
+    /// Rows archived and deleted; in a dry run, the rows that would be.
+    pub rows: u64,
+    /// Oldest row due, in a dry run.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub oldest: Option<DateTime<Utc>>,
+    pub batches: Vec<ArchivedBatch>,
+    /// Batches stop at the run's limit; the rest waits for the next run.
+    pub more: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RetentionReport {
+    pub run_id: Uuid,
+    pub dry_run: bool,
+    pub tables: Vec<TableRetention>,
+}
+
+impl RetentionReport {
+    pub fn rows(&self) -> u64 {
+        self.tables.iter().map(|t| t.rows).sum()
+    }
+}
+
+/// The audit entry for one archived batch, written in the transaction that
+/// deletes its rows.
+pub fn archived_audit_entry(
+    run_id: Uuid,
+    policy: &RetentionPolicy,
+    cutoff: DateTime<Utc>,
+    batch: &ArchivedBatch,
+    actor: &str,
+) -> NewAuditEntry {
+    let id = Uuid::now_v7();
+    NewAuditEntry {
+        id,
+        entity_type: "retention".to_string(),
+        entity_id: Some(run_id),
+        external_id: None,
+        event_id: format!("retention_archived:{id}"),
+        source: None,
+        tenant_id: None,
+        action: "retention_archived".to_string(),
+        actor: actor.to_string(),
+        detail: serde_json::json!({
+            "table": policy.table,
+            "max_age_days": policy.max_age_days,
+            "cutoff": cutoff,
+            "rows": batch.rows,
+            "oldest": batch.oldest,
+            "newest": batch.newest,
+            "location": batch.location,
+            "bytes": batch.bytes,
+        }),
+    }
+}
+
+/// The audit entry for a dry run that found rows due.
+pub fn dry_run_audit_entry(report: &RetentionReport, actor: &str) -> NewAuditEntry {
+    let id = Uuid::now_v7();
+    NewAuditEntry {
+        id,
+        entity_type: "retention".to_string(),
+        entity_id: Some(report.run_id),
+        external_id: None,
+        event_id: format!("retention_dry_run:{id}"),
+        source: None,
+        tenant_id: None,
+        action: "retention_dry_run".to_string(),
+        actor: actor.to_string(),
+        detail: serde_json::json!({
+            "tables": report.tables.iter().map(|t| serde_json::json!({
+                "table": t.table,
+                "max_age_days": t.max_age_days,
+                "cutoff": t.cutoff,
+                "rows": t.rows,
+                "oldest": t.oldest,
+            })).collect::<Vec<_>>(),
+        }),
+    }
+}
+
+/// Archive file name for a batch of `table` whose oldest row is `oldest`:
+/// sortable by table and age, unique per run and batch.
+pub fn archive_file_name(
+    table: RetainedTable,
+    oldest: DateTime<Utc>,
+    run_id: Uuid,
+    batch: usize,
+) -> String {
+    format!(
+        "{table}-{}-{}-{batch}.ndjson.zst",
+        oldest.format("%Y%m%dT%H%M%SZ"),
+        run_id.simple()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn archive_files_sort_by_table_and_age() {
+        let run = Uuid::nil();
+        let at = |s| DateTime::parse_from_rfc3339(s).unwrap().to_utc();
+        let older = archive_file_name(RetainedTable::AuditLog, at("2026-01-02T03:04:05Z"), run, 0);
+        assert_eq!(
+            older,
+            "audit_log-20260102T030405Z-00000000000000000000000000000000-0.ndjson.zst"
+        );
+        let newer = archive_file_name(RetainedTable::AuditLog, at("2026-02-01T00:00:00Z"), run, 1);
+        assert!(older < newer);
+        assert_eq!("provider_events".parse(), Ok(RetainedTable::ProviderEvents));
+        assert!("payments".parse::<RetainedTable>().is_err());
+    }
+}
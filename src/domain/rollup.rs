@@ -0,0 +1,323 @@
+use {
+    super::{error::DomainError, retention::RetainedTable},
+    chrono::{DateTime, Datelike, DurationRound, Months, TimeDelta, TimeZone, Utc},
+    serde::{Deserialize, Serialize},
+    std::fmt,
+};
+
+/// Granularity of a rollup bucket. Buckets are aligned to UTC boundaries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BucketSize {
+    Hour,
+    Day,
+    Month,
+}
+
+impl BucketSize {
+    /// Also the `date_trunc` field name, so it can be passed straight to SQL.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Hour => "hour",
+            Self::Day => "day",
+            Self::Month => "month",
+        }
+    }
+
+    /// Start of the bucket containing `ts`.
+    pub fn truncate(&self, ts: DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            Self::Hour => ts.duration_trunc(TimeDelta::hours(1)),
+            Self::Day => ts.duration_trunc(TimeDelta::days(1)),
+            Self::Month => {
+                return Utc
+                    .with_ymd_and_hms(ts.year(), ts.month(), 1, 0, 0, 0)
+                    .single()
+                    .expect("first of the month is unambiguous in UTC");
+            }
+        }
+        .expect("bucket duration fits any timestamp")
+    }
+
+    /// Start of the bucket after the one starting at `start`. Months vary in
+    /// length, so this is calendar arithmetic rather than a fixed duration.
+    pub fn next(&self, start: DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            Self::Hour => start + TimeDelta::hours(1),
+            Self::Day => start + TimeDelta::days(1),
+            Self::Month => start
+                .checked_add_months(Months::new(1))
+                .expect("month bucket within chrono range"),
+        }
+    }
+
+    /// Start of the first bucket at or after `ts`.
+    pub fn ceil(&self, ts: DateTime<Utc>) -> DateTime<Utc> {
+        let floor = self.truncate(ts);
+        if floor == ts { ts } else { self.next(floor) }
+    }
+}
+
+impl fmt::Display for BucketSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl TryFrom<&str> for BucketSize {
+    type Error = DomainError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s {
+            "hour" => Ok(Self::Hour),
+            "day" => Ok(Self::Day),
+            "month" => Ok(Self::Month),
+            other => Err(DomainError::Validation(format!(
+                "unknown bucket size: {other}"
+            ))),
+        }
+    }
+}
+
+/// Which stats table a rollup maintains.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RollupKind {
+    /// `provider_events` counted per event type.
+    EventTypeStats,
+    /// `payment_jobs` counted per job status, with total attempts.
+    DeliveryStats,
+    /// `payments` counted and summed per source/direction/currency/status.
+    DailySummaries,
+    /// Payments carrying failure details, per failure and decline code.
+    FailureReasonStats,
+    /// Disputes from `charge.dispute.*` events, per currency and card brand,
+    /// using each dispute's latest state.
+    DisputeStats,
+}
+
+impl RollupKind {
+    /// The table retention prunes that this rollup is built from, if any.
+    /// Dispute events are exempt from pruning, so the dispute rollup isn't.
+    pub fn source_table(&self) -> Option<RetainedTable> {
+        match self {
+            Self::EventTypeStats => Some(RetainedTable::ProviderEvents),
+            Self::DeliveryStats
+            | Self::DailySummaries
+            | Self::FailureReasonStats
+            | Self::DisputeStats => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::EventTypeStats => "event_type_stats",
+            Self::DeliveryStats => "delivery_stats",
+            Self::DailySummaries => "daily_summaries",
+            Self::FailureReasonStats => "failure_reason_stats",
+            Self::DisputeStats => "dispute_stats",
+        }
+    }
+}
+
+impl fmt::Display for RollupKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Half-open `[from, to)` range of whole buckets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct RollupWindow {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+}
+
+impl RollupWindow {
+    /// Widen an arbitrary range outward to bucket boundaries.
+    pub fn aligned(bucket: BucketSize, from: DateTime<Utc>, to: DateTime<Utc>) -> Self {
+        Self {
+            from: bucket.truncate(from),
+            to: bucket.ceil(to),
+        }
+    }
+
+    /// Starting after the bucket holding `pruned_through`, whose source rows
+    /// may be gone, so it isn't rebuilt from what's left of them.
+    pub fn after_pruned(self, bucket: BucketSize, pruned_through: DateTime<Utc>) -> Self {
+        let first_whole = bucket.next(bucket.truncate(pruned_through));
+        Self {
+            from: self.from.max(first_whole).min(self.to),
+            to: self.to,
+        }
+    }
+}
+
+/// One maintained rollup: a table at a bucket size, with how far back
+/// incremental runs re-read (`lookback`, for rows that change after insert)
+/// and how long buckets are kept (`retention`).
+#[derive(Debug, Clone, Copy)]
+pub struct RollupSpec {
+    pub kind: RollupKind,
+    pub bucket: BucketSize,
+    pub lookback: TimeDelta,
+    pub retention: TimeDelta,
+}
+
+impl RollupSpec {
+    pub fn defaults() -> Vec<Self> {
+        use {BucketSize::*, RollupKind::*};
+        let spec = |kind, bucket, lookback, retention_days| Self {
+            kind,
+            bucket,
+            lookback,
+            retention: TimeDelta::days(retention_days),
+        };
+        vec![
+            spec(EventTypeStats, Hour, TimeDelta::zero(), 14),
+            spec(EventTypeStats, Day, TimeDelta::zero(), 400),
+            spec(DeliveryStats, Hour, TimeDelta::hours(1), 14),
+            spec(DeliveryStats, Day, TimeDelta::hours(1), 400),
+            spec(DailySummaries, Day, TimeDelta::days(1), 730),
+            spec(FailureReasonStats, Day, TimeDelta::days(1), 730),
+            // Disputes are bucketed by when they were opened but keep changing
+            // until closed, which can take months — hence the long lookback.
+            spec(DisputeStats, Month, TimeDelta::days(180), 1825),
+        ]
+    }
+
+    /// Window for an incremental run: from the bucket holding
+    /// `watermark - lookback` (or the retention horizon on first run)
+    /// through the bucket holding `now`, inclusive.
+    pub fn incremental_window(
+        &self,
+        watermark: Option<DateTime<Utc>>,
+        now: DateTime<Utc>,
+    ) -> RollupWindow {
+        let horizon = self.retention_cutoff(now);
+        let from = watermark
+            .map(|w| (w - self.lookback).max(horizon))
+            .unwrap_or(horizon);
+        RollupWindow {
+            from: self.bucket.truncate(from),
+            to: self.bucket.next(self.bucket.truncate(now)),
+        }
+    }
+
+    /// Buckets starting before this are purged.
+    pub fn retention_cutoff(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        self.bucket.truncate(now - self.retention)
+    }
+}
+
+/// A dispute as one `charge.dispute.*` event describes it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DisputeSnapshot {
+    pub dispute_id: String,
+    pub opened_at: DateTime<Utc>,
+    pub currency: String,
+    pub card_brand: String,
+    pub amount: i64,
+    /// Net of the fee movements on its balance transactions.
+    pub fee: i64,
+    pub status: String,
+}
+
+impl DisputeSnapshot {
+    /// `None` if the event doesn't carry a dispute object.
+    pub fn from_event(payload: &serde_json::Value) -> Option<Self> {
+        let object = payload.pointer("/data/object")?;
+        let str_at = |path: &str| object.pointer(path).and_then(|v| v.as_str());
+        let fee = object
+            .get("balance_transactions")
+            .and_then(|t| t.as_array())
+            .map_or(0, |txns| {
+                txns.iter()
+                    .filter_map(|t| t.get("fee").and_then(|f| f.as_i64()))
+                    .sum()
+            });
+        Some(Self {
+            dispute_id: str_at("/id")?.to_string(),
+            opened_at: DateTime::from_timestamp(object.get("created")?.as_i64()?, 0)?,
+            currency: str_at("/currency")?.to_lowercase(),
+            card_brand: str_at("/payment_method_details/card/brand")
+                .unwrap_or("unknown")
+                .to_string(),
+            amount: object.get("amount")?.as_i64()?,
+            fee,
+            status: str_at("/status")?.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn ts(h: u32, m: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 3, 10, h, m, 0).unwrap()
+    }
+
+    #[test]
+    fn bucket_truncate_and_ceil() {
+        assert_eq!(BucketSize::Hour.truncate(ts(13, 45)), ts(13, 0));
+        assert_eq!(BucketSize::Hour.ceil(ts(13, 45)), ts(14, 0));
+        assert_eq!(BucketSize::Hour.ceil(ts(13, 0)), ts(13, 0));
+        assert_eq!(BucketSize::Day.truncate(ts(13, 45)), ts(0, 0));
+    }
+
+    #[test]
+    fn month_buckets_follow_the_calendar() {
+        let feb = Utc.with_ymd_and_hms(2026, 2, 1, 0, 0, 0).unwrap();
+        let mar = Utc.with_ymd_and_hms(2026, 3, 1, 0, 0, 0).unwrap();
+        assert_eq!(BucketSize::Month.truncate(ts(13, 45)), mar);
+        assert_eq!(BucketSize::Month.next(feb), mar);
+        assert_eq!(BucketSize::Month.ceil(feb + TimeDelta::days(27)), mar);
+        assert_eq!(BucketSize::Month.ceil(mar), mar);
+    }
+
+    #[test]
+    fn incremental_window_starts_at_watermark_minus_lookback() {
+        let spec = RollupSpec {
+            kind: RollupKind::DeliveryStats,
+            bucket: BucketSize::Hour,
+            lookback: TimeDelta::hours(1),
+            retention: TimeDelta::days(14),
+        };
+        let w = spec.incremental_window(Some(ts(10, 30)), ts(12, 5));
+        assert_eq!(w.from, ts(9, 0));
+        assert_eq!(w.to, ts(13, 0));
+    }
+
+    #[test]
+    fn first_run_starts_at_retention_horizon() {
+        let spec = RollupSpec {
+            kind: RollupKind::EventTypeStats,
+            bucket: BucketSize::Day,
+            lookback: TimeDelta::zero(),
+            retention: TimeDelta::days(2),
+        };
+        let now = ts(12, 0);
+        let w = spec.incremental_window(None, now);
+        assert_eq!(w.from, ts(0, 0) - TimeDelta::days(2));
+        assert_eq!(w.to, ts(0, 0) + TimeDelta::days(1));
+    }
+
+    #[test]
+    fn windows_start_after_the_pruned_bucket() {
+        let w = RollupWindow::aligned(BucketSize::Hour, ts(8, 0), ts(12, 0));
+        assert_eq!(w.after_pruned(BucketSize::Hour, ts(9, 30)).from, ts(10, 0));
+        assert_eq!(w.after_pruned(BucketSize::Hour, ts(6, 0)), w);
+        let gone = w.after_pruned(BucketSize::Hour, ts(13, 0));
+        assert_eq!((gone.from, gone.to), (ts(12, 0), ts(12, 0)));
+    }
+
+    #[test]
+    fn bucket_size_roundtrip() {
+        for b in [BucketSize::Hour, BucketSize::Day, BucketSize::Month] {
+            assert_eq!(BucketSize::try_from(b.as_str()).unwrap(), b);
+        }
+        assert!(BucketSize::try_from("week").is_err());
+    }
+}
@@ -0,0 +1,80 @@
+use {
+    super::{audit::NewAuditEntry, error::DomainError},
+    serde_json::Value,
+    uuid::Uuid,
+};
+
+/// Check a settings key against what the `settings` table accepts:
+/// 1 to 100 of lowercase letters, digits and `_.:-`.
+pub fn validate_key(key: &str) -> Result<(), DomainError> {
+    let valid = (1..=100).contains(&key.len())
+        && key.bytes().all(|b| {
+            b.is_ascii_lowercase() || b.is_ascii_digit() || matches!(b, b'_' | b'.' | b':' | b'-')
+        });
+    if !valid {
+        return Err(DomainError::Validation(format!(
+            "invalid settings key {key:?}: 1 to 100 of [a-z0-9_.:-]"
+        )));
+    }
+    Ok(())
+}
+
+/// A write to one settings key. `value` is `None` when the key was
+/// deleted; `previous` is `None` when it didn't exist.
+#[derive(Debug, Clone)]
+pub struct SettingChange {
+    pub key: String,
+    pub version: i64,
+    pub previous: Option<Value>,
+    pub value: Option<Value>,
+    /// Audit that the key changed, without its values.
+    pub secret: bool,
+}
+
+impl SettingChange {
+    /// `setting_changed`, or `setting_deleted` when the key went away.
+    pub fn audit_entry(&self, actor: &str) -> NewAuditEntry {
+        let id = Uuid::now_v7();
+        let action = if self.value.is_some() {
+            "setting_changed"
+        } else {
+            "setting_deleted"
+        };
+        let shown = |value: &Option<Value>| match value {
+            Some(_) if self.secret => Some(Value::from("[redacted]")),
+            other => other.clone(),
+        };
+        NewAuditEntry {
+            id,
+            entity_type: "setting".to_string(),
+            entity_id: None,
+            external_id: Some(self.key.clone()),
+            event_id: format!("{action}:{id}"),
+            source: None,
+            tenant_id: None,
+            action: action.to_string(),
+            actor: actor.to_string(),
+            detail: serde_json::json!({
+                "key": self.key,
+                "version": self.version,
+                "previous": shown(&self.previous),
+                "value": shown(&self.value),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_match_the_table_check() {
+        for key in ["stripe.endpoints", "flags:dual_secret", "a", "eod-cutoff_2"] {
+            assert!(validate_key(key).is_ok(), "{key}");
+        }
+        for key in ["", "Stripe", "a b", "é", &"k".repeat(101)] {
+            assert!(validate_key(key).is_err(), "{key}");
+        }
+    }
+}
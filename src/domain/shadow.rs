@@ -0,0 +1,209 @@
+use {
+    super::error::DomainError,
+    serde::{Deserialize, Serialize},
+};
+
+/// Where the move from `payments` to `payments_v2` stands. Set through the
+/// runtime config, one stage at a time: write both tables first, backfill
+/// and verify, then read from the new one. Going back is always allowed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShadowStage {
+    /// Only `payments` is written and read.
+    #[default]
+    Off,
+    /// Every write to `payments` is mirrored into `payments_v2`; reads stay
+    /// on `payments`.
+    DualWrite,
+    /// Still written to both, but payments are read from `payments_v2`.
+    ReadNew,
+}
+
+impl ShadowStage {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::DualWrite => "dual_write",
+            Self::ReadNew => "read_new",
+        }
+    }
+
+    /// Whether writes go to `payments_v2` too.
+    pub fn writes_new(self) -> bool {
+        self != Self::Off
+    }
+
+    pub fn reads_new(self) -> bool {
+        self == Self::ReadNew
+    }
+
+    /// A stage change is one step at a time: reading from `payments_v2`
+    /// before it was ever written would lose every payment, and a replica
+    /// still reading it a config sync after writes to it stopped would read
+    /// stale rows.
+    pub fn check_move(self, next: Self) -> Result<(), DomainError> {
+        let rank = |s: Self| match s {
+            Self::Off => 0u8,
+            Self::DualWrite => 1,
+            Self::ReadNew => 2,
+        };
+        if rank(next).abs_diff(rank(self)) <= 1 {
+            Ok(())
+        } else {
+            Err(DomainError::Validation(format!(
+                "payments_v2 can't move from {} to {}; go through dual_write",
+                self.as_str(),
+                next.as_str()
+            )))
+        }
+    }
+}
+
+impl TryFrom<&str> for ShadowStage {
+    type Error = DomainError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s {
+            "off" => Ok(Self::Off),
+            "dual_write" => Ok(Self::DualWrite),
+            "read_new" => Ok(Self::ReadNew),
+            other => Err(DomainError::Validation(format!(
+                "unknown payments_v2 stage: {other}"
+            ))),
+        }
+    }
+}
+
+/// One payment as the verifier saw it in both tables.
+#[derive(Debug, Clone)]
+pub struct ShadowRow {
+    pub external_id: String,
+    pub in_old: bool,
+    pub in_new: bool,
+    /// `payments` columns whose value in `payments_v2` differs, by their
+    /// old name.
+    pub differs: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShadowMismatchKind {
+    /// In `payments` only: not backfilled, or written with the stage off.
+    MissingInNew,
+    /// In `payments_v2` only.
+    MissingInOld,
+    /// In both, with different values.
+    Differs,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ShadowMismatch {
+    pub external_id: String,
+    pub kind: ShadowMismatchKind,
+    /// For `differs`, the columns that disagree.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub columns: Vec<String>,
+}
+
+impl ShadowRow {
+    /// How the two representations disagree, if they do.
+    pub fn mismatch(self) -> Option<ShadowMismatch> {
+        let kind = match (self.in_old, self.in_new) {
+            (true, false) => ShadowMismatchKind::MissingInNew,
+            (false, true) => ShadowMismatchKind::MissingInOld,
+            _ if !self.differs.is_empty() => ShadowMismatchKind::Differs,
+            _ => return None,
+        };
+        Some(ShadowMismatch {
+            external_id: self.external_id,
+            kind,
+            columns: self.differs,
+        })
+    }
+}
+
+/// Most mismatches a verification report lists; the counts cover them all.
+pub const MAX_REPORTED_MISMATCHES: usize = 100;
+
+/// Outcome of comparing `payments` with `payments_v2` row by row.
+#[derive(Debug, Default, Serialize)]
+pub struct ShadowReport {
+    pub checked: u64,
+    pub missing_in_new: u64,
+    pub missing_in_old: u64,
+    pub differing: u64,
+    /// The first mismatches in external id order.
+    pub mismatches: Vec<ShadowMismatch>,
+}
+
+impl ShadowReport {
+    pub fn record(&mut self, row: ShadowRow) {
+        self.checked += 1;
+        let Some(mismatch) = row.mismatch() else {
+            return;
+        };
+        match mismatch.kind {
+            ShadowMismatchKind::MissingInNew => self.missing_in_new += 1,
+            ShadowMismatchKind::MissingInOld => self.missing_in_old += 1,
+            ShadowMismatchKind::Differs => self.differing += 1,
+        }
+        if self.mismatches.len() < MAX_REPORTED_MISMATCHES {
+            self.mismatches.push(mismatch);
+        }
+    }
+
+    /// Both tables hold the same payments with the same values: safe to
+    /// read from `payments_v2`.
+    pub fn is_clean(&self) -> bool {
+        self.missing_in_new == 0 && self.missing_in_old == 0 && self.differing == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(in_old: bool, in_new: bool, differs: &[&str]) -> ShadowRow {
+        ShadowRow {
+            external_id: "pi_1".into(),
+            in_old,
+            in_new,
+            differs: differs.iter().map(|c| c.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn stages_move_one_step_at_a_time() {
+        use ShadowStage::*;
+        assert!(Off.check_move(DualWrite).is_ok());
+        assert!(DualWrite.check_move(ReadNew).is_ok());
+        assert!(Off.check_move(ReadNew).is_err());
+        assert!(ReadNew.check_move(Off).is_err());
+        assert!(ReadNew.check_move(DualWrite).is_ok());
+        assert!(ReadNew.check_move(ReadNew).is_ok());
+        for stage in [Off, DualWrite, ReadNew] {
+            assert_eq!(ShadowStage::try_from(stage.as_str()), Ok(stage));
+        }
+    }
+
+    #[test]
+    fn report_counts_each_kind_of_mismatch() {
+        let mut report = ShadowReport::default();
+        report.record(row(true, true, &[]));
+        assert!(report.is_clean());
+        report.record(row(true, false, &[]));
+        report.record(row(false, true, &[]));
+        report.record(row(true, true, &["amount", "status"]));
+        assert_eq!(
+            (
+                report.checked,
+                report.missing_in_new,
+                report.missing_in_old,
+                report.differing
+            ),
+            (4, 1, 1, 1)
+        );
+        assert!(!report.is_clean());
+        assert_eq!(report.mismatches[2].columns, ["amount", "status"]);
+    }
+}
@@ -0,0 +1,292 @@
+use {
+    super::{
+        audit::NewAuditEntry,
+        error::DomainError,
+        payment::{PaymentCursor, PaymentFilters},
+    },
+    chrono::{DateTime, Utc},
+    serde::{Deserialize, Serialize},
+    uuid::Uuid,
+};
+
+/// Longest tag or saved filter name.
+pub const MAX_NAME_LEN: usize = 64;
+
+/// Most tags one request may add or remove.
+pub const MAX_TAGS_PER_REQUEST: usize = 20;
+
+/// `GET /payments` parameters a saved filter may set. Paging (`limit`,
+/// `offset`, `cursor`) stays with whoever runs it.
+pub const FILTER_KEYS: &[&str] = &[
+    "source",
+    "tenant_id",
+    "status",
+    "amount",
+    "amount_min",
+    "amount_max",
+    "currency",
+    "direction",
+    "decline_code",
+    "parent_external_id",
+    "start_date",
+    "end_date",
+    "reference",
+    "tag",
+];
+
+/// A label on a payment, e.g. `chargeback-review` or `vip-customer`:
+/// lowercase letters, digits, `-`, `_` and `:`, starting with a letter or
+/// digit. Input is trimmed and lowercased first.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Tag(String);
+
+impl Tag {
+    pub fn new(tag: &str) -> Result<Self, DomainError> {
+        let tag = tag.trim().to_lowercase();
+        if is_slug(&tag, &['-', '_', ':']) {
+            Ok(Self(tag))
+        } else {
+            Err(DomainError::Validation(format!(
+                "invalid tag {tag:?}: 1 to {MAX_NAME_LEN} of a-z, 0-9, '-', '_' and ':', \
+                 starting with a letter or digit"
+            )))
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TryFrom<String> for Tag {
+    type Error = DomainError;
+
+    fn try_from(tag: String) -> Result<Self, Self::Error> {
+        Self::new(&tag)
+    }
+}
+
+impl From<Tag> for String {
+    fn from(tag: Tag) -> Self {
+        tag.0
+    }
+}
+
+/// Up to `MAX_NAME_LEN` chars of a-z, 0-9 and `extra`, starting with a
+/// letter or digit.
+fn is_slug(s: &str, extra: &[char]) -> bool {
+    s.len() <= MAX_NAME_LEN
+        && s.chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+        && s.chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || extra.contains(&c))
+}
+
+/// Body of `POST /payments/{id}/tags`. Adding a tag the payment has, or
+/// removing one it doesn't, is a no-op.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TagRequest {
+    #[serde(default)]
+    pub add: Vec<Tag>,
+    #[serde(default)]
+    pub remove: Vec<Tag>,
+}
+
+impl TagRequest {
+    pub fn validate(&self) -> Result<(), DomainError> {
+        if self.add.is_empty() && self.remove.is_empty() {
+            return Err(DomainError::Validation("give tags to add or remove".into()));
+        }
+        if self.add.len() > MAX_TAGS_PER_REQUEST || self.remove.len() > MAX_TAGS_PER_REQUEST {
+            return Err(DomainError::Validation(format!(
+                "at most {MAX_TAGS_PER_REQUEST} tags to add and {MAX_TAGS_PER_REQUEST} to remove"
+            )));
+        }
+        if let Some(both) = self.add.iter().find(|t| self.remove.contains(t)) {
+            return Err(DomainError::Validation(format!(
+                "tag {} is both added and removed",
+                both.as_str()
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// What a tag request changed on one payment.
+#[derive(Debug, Clone, Serialize)]
+pub struct TagChange {
+    pub external_id: String,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    /// All the payment's tags afterwards, sorted.
+    pub tags: Vec<String>,
+}
+
+impl TagChange {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+
+    pub fn audit_entry(
+        &self,
+        payment_id: Uuid,
+        tenant_id: Option<String>,
+        actor: &str,
+    ) -> NewAuditEntry {
+        let id = Uuid::now_v7();
+        NewAuditEntry {
+            id,
+            entity_type: "payment".to_string(),
+            entity_id: Some(payment_id),
+            external_id: Some(self.external_id.clone()),
+            event_id: format!("payment_tagged:{id}"),
+            source: None,
+            tenant_id,
+            action: "payment_tagged".to_string(),
+            actor: actor.to_string(),
+            detail: serde_json::json!({
+                "added": self.added,
+                "removed": self.removed,
+                "tags": self.tags,
+            }),
+        }
+    }
+}
+
+/// Body of `PUT /admin/saved-filters/{name}`: `filters` are `GET /payments`
+/// parameters (see `FILTER_KEYS`), typed as JSON.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SavedFilterRequest {
+    #[serde(default)]
+    pub description: String,
+    pub filters: serde_json::Value,
+}
+
+impl SavedFilterRequest {
+    /// Check `name` and the filters, which must be the ones `GET /payments`
+    /// takes and must parse as it would parse them.
+    pub fn validate(&self, name: &str) -> Result<(), DomainError> {
+        if !is_slug(name, &['-', '_']) {
+            return Err(DomainError::Validation(format!(
+                "invalid name {name:?}: 1 to {MAX_NAME_LEN} of a-z, 0-9, '-' and '_', \
+                 starting with a letter or digit"
+            )));
+        }
+        if self.description.len() > 500 {
+            return Err(DomainError::Validation(
+                "description must be at most 500 characters".into(),
+            ));
+        }
+        let Some(filters) = self.filters.as_object() else {
+            return Err(DomainError::Validation("filters must be an object".into()));
+        };
+        if let Some(key) = filters.keys().find(|k| !FILTER_KEYS.contains(&k.as_str())) {
+            return Err(DomainError::Validation(format!(
+                "filters can't set {key:?}; allowed: {}",
+                FILTER_KEYS.join(", ")
+            )));
+        }
+        let mut parsed = SavedFilter::parse(&self.filters)?;
+        parsed.normalize_reference()?;
+        parsed.normalize_tags()?;
+        Ok(())
+    }
+}
+
+/// A named payment listing for the admin dashboard.
+#[derive(Debug, Clone, Serialize)]
+pub struct SavedFilter {
+    pub name: String,
+    pub description: String,
+    pub filters: serde_json::Value,
+    pub updated_by: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Paging for `GET /admin/saved-filters/{name}/payments`, as `GET /payments`
+/// pages.
+#[derive(Debug, Default, Deserialize)]
+pub struct SavedFilterPage {
+    pub limit: Option<u64>,
+    pub cursor: Option<PaymentCursor>,
+}
+
+impl SavedFilter {
+    /// The stored filters as `GET /payments` takes them, paged by `page`.
+    pub fn payment_filters(&self, page: SavedFilterPage) -> Result<PaymentFilters, DomainError> {
+        Ok(PaymentFilters {
+            limit: page.limit,
+            cursor: page.cursor,
+            ..Self::parse(&self.filters)?
+        })
+    }
+
+    fn parse(filters: &serde_json::Value) -> Result<PaymentFilters, DomainError> {
+        serde_json::from_value(filters.clone())
+            .map_err(|e| DomainError::Validation(format!("invalid filters: {e}")))
+    }
+
+    /// `action` is `saved_filter_saved` or `saved_filter_deleted`.
+    pub fn audit_entry(&self, action: &str, actor: &str) -> NewAuditEntry {
+        let id = Uuid::now_v7();
+        NewAuditEntry {
+            id,
+            entity_type: "saved_filter".to_string(),
+            entity_id: None,
+            external_id: None,
+            event_id: format!("{action}:{id}"),
+            source: None,
+            tenant_id: None,
+            action: action.to_string(),
+            actor: actor.to_string(),
+            detail: serde_json::json!({
+                "name": self.name,
+                "description": self.description,
+                "filters": self.filters,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tags_are_normalized_slugs() {
+        assert_eq!(Tag::new(" VIP-Customer ").unwrap().as_str(), "vip-customer");
+        assert_eq!(
+            Tag::new("review:q3_2026").unwrap().as_str(),
+            "review:q3_2026"
+        );
+        for bad in ["", "-lead", "two words", "ünïcode", &"x".repeat(65)] {
+            assert!(Tag::new(bad).is_err(), "{bad:?}");
+        }
+    }
+
+    #[test]
+    fn saved_filters_take_only_listing_parameters() {
+        let req = |filters: serde_json::Value| SavedFilterRequest {
+            description: String::new(),
+            filters,
+        };
+        let ok = req(serde_json::json!({"tag": "chargeback-review", "status": "disputed"}));
+        ok.validate("chargeback-review").unwrap();
+        assert!(ok.validate("Chargeback Review").is_err());
+        for bad in [
+            serde_json::json!({"limit": 10}),
+            serde_json::json!({"cursor": "1:pi_1"}),
+            serde_json::json!({"status": "lost"}),
+            serde_json::json!({"tag": "not a tag"}),
+            serde_json::json!({"reference": "ab"}),
+            serde_json::json!(["tag"]),
+        ] {
+            assert!(req(bad.clone()).validate("x").is_err(), "{bad}");
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use {
+    chrono::{DateTime, Utc},
+    serde::Serialize,
+};
+
+/// One thing that happened to a payment: an event the provider sent, or
+/// an entry we wrote to its audit trail.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TimelineEntry {
+    /// A provider event about the payment, at the time we received it.
+    ProviderEvent {
+        at: DateTime<Utc>,
+        event_id: String,
+        event_type: String,
+        /// The provider's own timestamp, in seconds.
+        provider_ts: i64,
+        payload: serde_json::Value,
+    },
+    /// An audit entry: a status change, an anomaly, a manual action.
+    Audit {
+        at: DateTime<Utc>,
+        event_id: String,
+        action: String,
+        actor: String,
+        detail: serde_json::Value,
+    },
+}
+
+impl TimelineEntry {
+    pub fn at(&self) -> DateTime<Utc> {
+        match self {
+            Self::ProviderEvent { at, .. } | Self::Audit { at, .. } => *at,
+        }
+    }
+}
+
+/// Both sources in one list, oldest first. An event and the audit entry it
+/// caused can share a timestamp; the event comes first.
+pub fn merge(events: Vec<TimelineEntry>, audit: Vec<TimelineEntry>) -> Vec<TimelineEntry> {
+    let mut entries = events;
+    entries.extend(audit);
+    // Stable, so ties keep events ahead of audit entries.
+    entries.sort_by_key(TimelineEntry::at);
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+
+    fn event(at: DateTime<Utc>, id: &str) -> TimelineEntry {
+        TimelineEntry::ProviderEvent {
+            at,
+            event_id: id.into(),
+            event_type: "payment_intent.succeeded".into(),
+            provider_ts: at.timestamp(),
+            payload: serde_json::json!({}),
+        }
+    }
+
+    fn audit(at: DateTime<Utc>, id: &str) -> TimelineEntry {
+        TimelineEntry::Audit {
+            at,
+            event_id: id.into(),
+            action: "status_changed".into(),
+            actor: "worker".into(),
+            detail: serde_json::json!({}),
+        }
+    }
+
+    #[test]
+    fn entries_are_ordered_with_events_first_on_ties() {
+        let t = Utc::now();
+        let merged = merge(
+            vec![event(t, "evt_1"), event(t + TimeDelta::seconds(2), "evt_2")],
+            vec![
+                audit(t, "evt_1"),
+                audit(t + TimeDelta::seconds(1), "anomaly"),
+            ],
+        );
+        let order: Vec<_> = merged
+            .iter()
+            .map(|e| serde_json::to_value(e).unwrap())
+            .map(|v| {
+                format!(
+                    "{}:{}",
+                    v["type"].as_str().unwrap(),
+                    v["event_id"].as_str().unwrap()
+                )
+            })
+            .collect();
+        assert_eq!(
+            order,
+            [
+                "provider_event:evt_1",
+                "audit:evt_1",
+                "audit:anomaly",
+                "provider_event:evt_2"
+            ]
+        );
+    }
+}
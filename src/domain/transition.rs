@@ -0,0 +1,234 @@
+use {
+    super::{
+        manual::MANUAL_SOURCE,
+        payment::{PaymentDirection, PaymentStatus},
+    },
+    serde::Serialize,
+    std::sync::LazyLock,
+};
+
+/// Source name for bank transfers (`payments.source`).
+pub const BANK_TRANSFER_SOURCE: &str = "bank_transfer";
+
+/// The status transitions the pipeline enforces for one source's payments.
+/// Anything not allowed is logged as an anomaly instead of applied.
+///
+/// A new provider with its own lifecycle gets its own implementation,
+/// registered in `for_source`; the shared `PaymentStatus` table stays as is.
+pub trait TransitionPolicy: Send + Sync {
+    /// The `payments.source` this policy governs.
+    fn source(&self) -> &'static str;
+
+    fn allows(
+        &self,
+        direction: &PaymentDirection,
+        from: &PaymentStatus,
+        to: &PaymentStatus,
+    ) -> bool;
+
+    /// No transition leads out of `status`.
+    fn is_terminal(&self, direction: &PaymentDirection, status: &PaymentStatus) -> bool {
+        !PaymentStatus::ALL
+            .iter()
+            .any(|to| self.allows(direction, status, to))
+    }
+}
+
+/// Follows `PaymentStatus::can_transition_to` in both directions. Used for
+/// Stripe, manual payments and any source without a policy of its own.
+pub struct StandardPolicy {
+    source: &'static str,
+}
+
+impl StandardPolicy {
+    pub const fn new(source: &'static str) -> Self {
+        Self { source }
+    }
+}
+
+impl TransitionPolicy for StandardPolicy {
+    fn source(&self) -> &'static str {
+        self.source
+    }
+
+    fn allows(&self, _: &PaymentDirection, from: &PaymentStatus, to: &PaymentStatus) -> bool {
+        from.can_transition_to(to)
+    }
+}
+
+/// PayPal captures settle or fail, and refunds complete, fail or are
+/// cancelled. There are no disputes, and a refund status on a capture means
+/// nothing.
+pub struct PaypalPolicy;
+
+impl TransitionPolicy for PaypalPolicy {
+    fn source(&self) -> &'static str {
+        "paypal"
+    }
+
+    fn allows(
+        &self,
+        direction: &PaymentDirection,
+        from: &PaymentStatus,
+        to: &PaymentStatus,
+    ) -> bool {
+        use PaymentStatus::*;
+        match direction {
+            PaymentDirection::Inbound => matches!((from, to), (Pending, Succeeded | Failed)),
+            PaymentDirection::Outbound => {
+                matches!((from, to), (Pending, Refunded | Failed | Cancelled))
+            }
+        }
+    }
+}
+
+/// Bank transfers can bounce after they've settled: a returned transfer
+/// moves from Succeeded to Failed, in either direction.
+pub struct BankTransferPolicy;
+
+impl TransitionPolicy for BankTransferPolicy {
+    fn source(&self) -> &'static str {
+        BANK_TRANSFER_SOURCE
+    }
+
+    fn allows(&self, _: &PaymentDirection, from: &PaymentStatus, to: &PaymentStatus) -> bool {
+        use PaymentStatus::*;
+        matches!(
+            (from, to),
+            (Pending, Succeeded | Failed | Cancelled) | (Succeeded, Failed)
+        )
+    }
+}
+
+static DEFAULT: StandardPolicy = StandardPolicy::new("default");
+
+static POLICIES: LazyLock<Vec<Box<dyn TransitionPolicy>>> = LazyLock::new(|| {
+    vec![
+        Box::new(StandardPolicy::new("stripe")),
+        Box::new(PaypalPolicy),
+        Box::new(BankTransferPolicy),
+        Box::new(StandardPolicy::new(MANUAL_SOURCE)),
+    ]
+});
+
+/// The policy for payments from `source`, or the standard one if the source
+/// has none registered.
+pub fn for_source(source: &str) -> &'static dyn TransitionPolicy {
+    POLICIES
+        .iter()
+        .find(|p| p.source() == source)
+        .map_or(&DEFAULT as &dyn TransitionPolicy, |p| p.as_ref())
+}
+
+/// The enforced transitions as a graph, per direction.
+pub fn graph(policy: &dyn TransitionPolicy) -> StateMachineGraph {
+    StateMachineGraph {
+        source: policy.source(),
+        directions: PaymentDirection::ALL
+            .iter()
+            .map(|direction| {
+                let edges: Vec<_> = PaymentStatus::ALL
+                    .iter()
+                    .flat_map(|from| {
+                        PaymentStatus::ALL
+                            .iter()
+                            .filter(|to| policy.allows(direction, from, to))
+                            .map(|to| TransitionEdge {
+                                from: from.clone(),
+                                to: to.clone(),
+                            })
+                    })
+                    .collect();
+                DirectionGraph {
+                    direction: direction.clone(),
+                    nodes: PaymentStatus::ALL
+                        .iter()
+                        .map(|status| StatusNode {
+                            status: status.clone(),
+                            terminal: policy.is_terminal(direction, status),
+                        })
+                        .collect(),
+                    edges,
+                }
+            })
+            .collect(),
+    }
+}
+
+/// Body of `GET /meta/state-machine`.
+#[derive(Debug, Serialize)]
+pub struct StateMachineGraph {
+    pub source: &'static str,
+    pub directions: Vec<DirectionGraph>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DirectionGraph {
+    pub direction: PaymentDirection,
+    pub nodes: Vec<StatusNode>,
+    pub edges: Vec<TransitionEdge>,
+}
+
+/// A status. Payments may be created in any status; `terminal` ones have no
+/// way out.
+#[derive(Debug, Serialize)]
+pub struct StatusNode {
+    pub status: PaymentStatus,
+    pub terminal: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TransitionEdge {
+    pub from: PaymentStatus,
+    pub to: PaymentStatus,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn graph_matches_policy() {
+        let policy = for_source("stripe");
+        let graph = graph(policy);
+        assert_eq!(graph.directions.len(), PaymentDirection::ALL.len());
+        for d in &graph.directions {
+            assert_eq!(d.nodes.len(), PaymentStatus::ALL.len());
+            for e in &d.edges {
+                assert!(policy.allows(&d.direction, &e.from, &e.to));
+            }
+            for n in &d.nodes {
+                let open = matches!(n.status, PaymentStatus::Pending | PaymentStatus::Disputed);
+                assert_eq!(n.terminal, !open);
+            }
+        }
+    }
+
+    #[test]
+    fn graph_serializes_as_snake_case() {
+        let json = serde_json::to_value(graph(for_source("stripe"))).unwrap();
+        assert_eq!(json["source"], "stripe");
+        let inbound = &json["directions"][0];
+        assert_eq!(inbound["direction"], "inbound");
+        assert_eq!(
+            inbound["edges"][0],
+            serde_json::json!({"from": "pending", "to": "succeeded"})
+        );
+    }
+
+    #[test]
+    fn policies_are_picked_by_source() {
+        use {PaymentDirection::*, PaymentStatus::*};
+        assert_eq!(for_source("paypal").source(), "paypal");
+        assert_eq!(for_source("adyen").source(), "default");
+
+        assert!(for_source("stripe").allows(&Inbound, &Pending, &Refunded));
+        assert!(!for_source("paypal").allows(&Inbound, &Pending, &Refunded));
+        assert!(for_source("paypal").allows(&Outbound, &Pending, &Refunded));
+        assert!(!for_source("paypal").allows(&Outbound, &Disputed, &DisputeLost));
+
+        let bank = for_source(BANK_TRANSFER_SOURCE);
+        assert!(bank.allows(&Inbound, &Succeeded, &Failed));
+        assert!(!for_source("stripe").allows(&Inbound, &Succeeded, &Failed));
+    }
+}
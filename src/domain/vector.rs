@@ -0,0 +1,290 @@
+use {
+    super::{
+        error::DomainError,
+        id::{EventId, ExternalId},
+        manual::MANUAL_SOURCE,
+        money::{Currency, Money, MoneyAmount},
+        payment::{NewPayment, NewPaymentParams, PaymentDirection, PaymentStatus, ProcessResult},
+        transition::BANK_TRANSFER_SOURCE,
+    },
+    serde::{Deserialize, Serialize},
+};
+
+/// A test vector for the state machine and staleness logic: a sequence of
+/// normalized events for one payment, what the pipeline must answer to
+/// each, and the status the payment must end in. Vectors are plain JSON so
+/// another implementation can check parity against the same corpus
+/// (`tests/vectors/*.json`, each file an array of vectors).
+///
+/// A vector carries no ids, amounts, metadata or wall-clock times, so one
+/// exported from production gives away nothing but the sequence.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TestVector {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    /// `payments.source`; picks the transition policy.
+    pub source: String,
+    pub direction: PaymentDirection,
+    pub events: Vec<VectorEvent>,
+    pub final_status: PaymentStatus,
+}
+
+/// One delivery. Reusing an earlier `event` number redelivers that event.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VectorEvent {
+    pub event: u32,
+    pub status: PaymentStatus,
+    /// Seconds after the vector's first event.
+    pub provider_ts: i64,
+    pub expect: VectorOutcome,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VectorOutcome {
+    Created,
+    Updated,
+    #[serde(alias = "stale")]
+    Unchanged,
+    StaleIgnored,
+    Anomaly,
+    Duplicate,
+    /// Dated in a closed period and held for adjustment; a runner sees it
+    /// only with that period closed.
+    Diverted,
+}
+
+impl VectorOutcome {
+    pub fn matches(self, result: &ProcessResult) -> bool {
+        matches!(
+            (self, result),
+            (Self::Created, ProcessResult::Created(_))
+                | (Self::Updated, ProcessResult::Updated(_))
+                | (Self::Unchanged, ProcessResult::Unchanged(_))
+                | (Self::StaleIgnored, ProcessResult::StaleIgnored(_))
+                | (Self::Anomaly, ProcessResult::Anomaly(_))
+                | (Self::Duplicate, ProcessResult::Duplicate)
+                | (Self::Diverted, ProcessResult::Diverted(_))
+        )
+    }
+}
+
+impl TestVector {
+    /// Id prefix of the object this vector describes.
+    pub fn object_prefix(&self) -> &'static str {
+        match (self.source.as_str(), &self.direction) {
+            ("paypal", PaymentDirection::Inbound) => "pp_cap_",
+            ("paypal", PaymentDirection::Outbound) => "pp_ref_",
+            (MANUAL_SOURCE | BANK_TRANSFER_SOURCE, _) => "mp_",
+            (_, PaymentDirection::Inbound) => "pi_",
+            (_, PaymentDirection::Outbound) => {
+                if self.events.iter().any(|e| e.status.is_dispute()) {
+                    "dp_"
+                } else {
+                    "re_"
+                }
+            }
+        }
+    }
+
+    /// Pipeline input for each event, in order, against `object`. Event ids
+    /// are `evt_<tag>_<event>`, so `tag` keeps runs of one vector apart.
+    pub fn payments(&self, object: &ExternalId, tag: &str) -> Result<Vec<NewPayment>, DomainError> {
+        self.events
+            .iter()
+            .map(|e| {
+                Ok(NewPayment::new(NewPaymentParams {
+                    external_id: object.clone(),
+                    source: self.source.clone(),
+                    event_type: format!("vector.{}", e.status.as_str()),
+                    direction: self.direction.clone(),
+                    money: Money::new(MoneyAmount::new(1000)?, Currency::USD),
+                    status: e.status.clone(),
+                    metadata: serde_json::json!({}),
+                    raw_event: serde_json::json!(e),
+                    last_event_id: EventId::new(format!("evt_{tag}_{}", e.event))?,
+                    parent_external_id: None,
+                    provider_ts: e.provider_ts,
+                    failure: None,
+                    authorized_amount: None,
+                    receipt: None,
+                    tenant_id: None,
+                    customer: None,
+                }))
+            })
+            .collect()
+    }
+
+    /// A vector from one payment's recorded history, oldest first. Each
+    /// event's status and outcome come from the audit entry it left; one that
+    /// left none (recorded before same-status events were audited) changed
+    /// nothing, so it was unchanged at the status of the time.
+    /// `None` if the history doesn't start with the payment's creation.
+    pub fn from_history(
+        name: String,
+        source: String,
+        direction: PaymentDirection,
+        history: &[RecordedEvent],
+    ) -> Option<Self> {
+        let first_ts = history.first()?.provider_ts;
+        let mut status: Option<PaymentStatus> = None;
+        let mut events = Vec::with_capacity(history.len());
+        for (i, recorded) in history.iter().enumerate() {
+            let detail = &recorded.detail;
+            let recorded_status = |key: &str| {
+                detail[key]
+                    .as_str()
+                    .and_then(|s| PaymentStatus::try_from(s).ok())
+            };
+            let (event_status, expect) = match recorded.action.as_deref() {
+                Some("created") => (recorded_status("status")?, VectorOutcome::Created),
+                Some("status_changed") => (recorded_status("new_status")?, VectorOutcome::Updated),
+                Some("event_received") if detail["anomaly"] == true => {
+                    (recorded_status("incoming_status")?, VectorOutcome::Anomaly)
+                }
+                Some("event_stale_ignored") => (
+                    recorded_status("incoming_status")?,
+                    VectorOutcome::StaleIgnored,
+                ),
+                // Held back, so the payment stays where it was: at pending
+                // if the event created it.
+                Some("event_diverted") => {
+                    if status.is_none() {
+                        status = Some(recorded_status("current_status")?);
+                    }
+                    (recorded_status("incoming_status")?, VectorOutcome::Diverted)
+                }
+                _ => (status.clone()?, VectorOutcome::Unchanged),
+            };
+            if !matches!(
+                expect,
+                VectorOutcome::Anomaly | VectorOutcome::StaleIgnored | VectorOutcome::Diverted
+            ) {
+                status = Some(event_status.clone());
+            }
+            events.push(VectorEvent {
+                event: i as u32 + 1,
+                status: event_status,
+                provider_ts: recorded.provider_ts - first_ts,
+                expect,
+            });
+        }
+        Some(Self {
+            name,
+            description: String::new(),
+            source,
+            direction,
+            events,
+            final_status: status?,
+        })
+    }
+}
+
+/// A stored provider event with the audit entry its processing left, if any.
+#[derive(Debug)]
+pub struct RecordedEvent {
+    pub provider_ts: i64,
+    pub action: Option<String>,
+    pub detail: serde_json::Value,
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, serde_json::json};
+
+    fn recorded(ts: i64, action: Option<&str>, detail: serde_json::Value) -> RecordedEvent {
+        RecordedEvent {
+            provider_ts: ts,
+            action: action.map(String::from),
+            detail,
+        }
+    }
+
+    #[test]
+    fn history_becomes_a_vector() {
+        let history = [
+            recorded(1_700_000_000, Some("created"), json!({"status": "pending"})),
+            recorded(1_700_000_005, None, json!(null)),
+            recorded(
+                1_700_000_009,
+                Some("status_changed"),
+                json!({"old_status": "pending", "new_status": "failed"}),
+            ),
+            recorded(
+                1_700_000_012,
+                Some("event_received"),
+                json!({"current_status": "failed", "incoming_status": "succeeded", "anomaly": true}),
+            ),
+            recorded(
+                1_700_000_008,
+                Some("event_stale_ignored"),
+                json!({"current_status": "failed", "incoming_status": "failed"}),
+            ),
+        ];
+        let v = TestVector::from_history(
+            "anomaly_1".into(),
+            "stripe".into(),
+            PaymentDirection::Inbound,
+            &history,
+        )
+        .unwrap();
+        let outcomes: Vec<_> = v.events.iter().map(|e| (e.provider_ts, e.expect)).collect();
+        assert_eq!(
+            outcomes,
+            [
+                (0, VectorOutcome::Created),
+                (5, VectorOutcome::Unchanged),
+                (9, VectorOutcome::Updated),
+                (12, VectorOutcome::Anomaly),
+                (8, VectorOutcome::StaleIgnored),
+            ]
+        );
+        assert_eq!(v.events[1].status, PaymentStatus::Pending);
+        assert_eq!(v.final_status, PaymentStatus::Failed);
+        assert_eq!(v.object_prefix(), "pi_");
+
+        // Round-trips through the corpus format.
+        let json = serde_json::to_string(&v).unwrap();
+        assert_eq!(serde_json::from_str::<TestVector>(&json).unwrap(), v);
+
+        // A creation dated in a closed period leaves the payment at pending.
+        let late = [
+            recorded(
+                1_700_000_000,
+                Some("event_diverted"),
+                json!({"current_status": "pending", "incoming_status": "succeeded", "on_create": true}),
+            ),
+            recorded(
+                1_700_000_003,
+                Some("status_changed"),
+                json!({"old_status": "pending", "new_status": "failed"}),
+            ),
+        ];
+        let v = TestVector::from_history(
+            "diverted_1".into(),
+            "stripe".into(),
+            PaymentDirection::Inbound,
+            &late,
+        )
+        .unwrap();
+        assert_eq!(
+            (v.events[0].status.clone(), v.events[0].expect),
+            (PaymentStatus::Succeeded, VectorOutcome::Diverted)
+        );
+        assert_eq!(v.final_status, PaymentStatus::Failed);
+
+        // Without its creation the sequence can't be replayed.
+        assert!(
+            TestVector::from_history(
+                "x".into(),
+                "stripe".into(),
+                PaymentDirection::Inbound,
+                &history[1..]
+            )
+            .is_none()
+        );
+    }
+}
@@ -0,0 +1,233 @@
+use {
+    super::{audit::NewAuditEntry, error::DomainError},
+    chrono::{DateTime, TimeDelta, Utc},
+    std::{fmt, str::FromStr},
+    uuid::Uuid,
+};
+
+/// A verified webhook delivery, identified by its signature. A second
+/// delivery with the same signature is a replay, whatever its payload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedDelivery {
+    pub source: &'static str,
+    pub signature: String,
+    pub signed_at: DateTime<Utc>,
+}
+
+/// Why a correctly signed delivery was still turned away.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayRejection {
+    /// Signed longer ago than the configured max age.
+    Stale,
+    /// Signed further ahead of our clock than the max age: a forged
+    /// timestamp or a badly skewed clock.
+    Future,
+    /// Signature already seen.
+    Replayed,
+}
+
+impl ReplayRejection {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Stale => "stale",
+            Self::Future => "future",
+            Self::Replayed => "replayed",
+        }
+    }
+}
+
+impl SignedDelivery {
+    /// Age at `now`; negative if signed in the future.
+    pub fn age(&self, now: DateTime<Utc>) -> TimeDelta {
+        now - self.signed_at
+    }
+
+    /// Whether the signature timestamp is within `max_age` of `now`, on
+    /// either side.
+    pub fn check_age(&self, now: DateTime<Utc>, max_age: TimeDelta) -> Result<(), ReplayRejection> {
+        let age = self.age(now);
+        if age > max_age {
+            Err(ReplayRejection::Stale)
+        } else if age < -max_age {
+            Err(ReplayRejection::Future)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// `replay_rejected`, recorded for a correctly signed delivery that was
+    /// turned away.
+    pub fn rejection_audit_entry(
+        &self,
+        rejection: ReplayRejection,
+        now: DateTime<Utc>,
+    ) -> NewAuditEntry {
+        let id = Uuid::now_v7();
+        NewAuditEntry {
+            id,
+            entity_type: "webhook".to_string(),
+            entity_id: None,
+            external_id: None,
+            event_id: format!("replay_rejected:{id}"),
+            source: Some(self.source.to_string()),
+            tenant_id: None,
+            action: "replay_rejected".to_string(),
+            actor: format!("webhook:{}", self.source),
+            detail: serde_json::json!({
+                "rejection": rejection.as_str(),
+                "signature": self.signature,
+                "signed_at": self.signed_at,
+                "age_secs": self.age(now).num_seconds(),
+            }),
+        }
+    }
+}
+
+/// Whether `event_type` matches `pattern`: an exact type, or a prefix
+/// ending in `*` such as `payment_intent.*`.
+pub fn event_type_matches(pattern: &str, event_type: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => event_type.starts_with(prefix),
+        None => event_type == pattern,
+    }
+}
+
+/// An event type, or a non-empty prefix with a single trailing `*`.
+pub fn is_event_type_pattern(pattern: &str) -> bool {
+    let fixed = pattern.strip_suffix('*').unwrap_or(pattern);
+    !fixed.is_empty() && !fixed.contains('*')
+}
+
+/// How a provider webhook is handled, by event type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventStrategy {
+    /// The object is the payment: enqueued for the worker under its id.
+    Payment,
+    /// The object points at a payment (a Checkout Session or an Invoice
+    /// paid through a PaymentIntent); enqueued under that payment.
+    RelatedPayment,
+    /// A payout or transfer, recorded as it arrives.
+    Payout,
+    /// A capture of an authorized charge, recorded against its payment as
+    /// it arrives.
+    Capture,
+    /// Logged as received, not applied to any payment.
+    Passthrough,
+    /// Acknowledged and dropped, not even logged.
+    Ignore,
+}
+
+impl EventStrategy {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Payment => "payment",
+            Self::RelatedPayment => "related_payment",
+            Self::Payout => "payout",
+            Self::Capture => "capture",
+            Self::Passthrough => "passthrough",
+            Self::Ignore => "ignore",
+        }
+    }
+}
+
+impl fmt::Display for EventStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for EventStrategy {
+    type Err = DomainError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "payment" => Ok(Self::Payment),
+            "related_payment" => Ok(Self::RelatedPayment),
+            "payout" => Ok(Self::Payout),
+            "capture" => Ok(Self::Capture),
+            "passthrough" => Ok(Self::Passthrough),
+            "ignore" => Ok(Self::Ignore),
+            other => Err(DomainError::Validation(format!(
+                "expected payment, related_payment, payout, capture, passthrough or ignore, got: {other}"
+            ))),
+        }
+    }
+}
+
+/// One `pattern=strategy` routing rule, e.g. `invoice.*=ignore`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventRoute {
+    pub pattern: String,
+    pub strategy: EventStrategy,
+}
+
+impl EventRoute {
+    pub fn new(pattern: &str, strategy: EventStrategy) -> Self {
+        Self {
+            pattern: pattern.to_string(),
+            strategy,
+        }
+    }
+
+    pub fn matches(&self, event_type: &str) -> bool {
+        event_type_matches(&self.pattern, event_type)
+    }
+}
+
+impl FromStr for EventRoute {
+    type Err = DomainError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (pattern, strategy) = s.split_once('=').ok_or_else(|| {
+            DomainError::Validation(format!("expected pattern=strategy, got: {s}"))
+        })?;
+        let pattern = pattern.trim();
+        if !is_event_type_pattern(pattern) {
+            return Err(DomainError::Validation(format!(
+                "not an event type or a prefix ending in *: {pattern}"
+            )));
+        }
+        Ok(Self::new(pattern, strategy.trim().parse()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deliveries_outside_max_age_are_rejected() {
+        let d = SignedDelivery {
+            source: "stripe",
+            signature: "t=1700000000,v1=abc".into(),
+            signed_at: DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+        };
+        let max_age = TimeDelta::hours(1);
+        assert_eq!(d.check_age(d.signed_at + max_age, max_age), Ok(()));
+        assert_eq!(
+            d.check_age(d.signed_at + max_age + TimeDelta::seconds(1), max_age),
+            Err(ReplayRejection::Stale)
+        );
+        assert_eq!(d.check_age(d.signed_at - max_age, max_age), Ok(()));
+        assert_eq!(
+            d.check_age(d.signed_at - max_age - TimeDelta::seconds(1), max_age),
+            Err(ReplayRejection::Future)
+        );
+    }
+
+    #[test]
+    fn routes_parse_and_match_by_pattern() {
+        let route: EventRoute = " invoice.* = ignore ".parse().unwrap();
+        assert_eq!(route, EventRoute::new("invoice.*", EventStrategy::Ignore));
+        assert!(route.matches("invoice.paid"));
+        assert!(!route.matches("invoices.paid"));
+        assert!(
+            EventRoute::new("refund.updated", EventStrategy::Payment).matches("refund.updated")
+        );
+
+        assert!("invoice.*".parse::<EventRoute>().is_err());
+        assert!("invoice.*=drop".parse::<EventRoute>().is_err());
+        assert!("*=ignore".parse::<EventRoute>().is_err());
+        assert!("in*voice=ignore".parse::<EventRoute>().is_err());
+    }
+}
@@ -0,0 +1,291 @@
+use {
+    super::audit::NewAuditEntry,
+    chrono::{DateTime, Duration, Utc},
+    serde::{Deserialize, Serialize},
+    std::{collections::BTreeSet, fmt},
+    uuid::Uuid,
+};
+
+/// A Stripe webhook endpoint, as the API returns it. `secret` comes back
+/// only when the endpoint is created.
+#[derive(Clone, Deserialize)]
+pub struct WebhookEndpoint {
+    pub id: String,
+    pub url: String,
+    pub enabled_events: Vec<String>,
+    pub status: String,
+    #[serde(default)]
+    pub secret: Option<String>,
+}
+
+impl fmt::Debug for WebhookEndpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebhookEndpoint")
+            .field("id", &self.id)
+            .field("url", &self.url)
+            .field("enabled_events", &self.enabled_events)
+            .field("status", &self.status)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Where Stripe should send webhooks, and which.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EndpointTarget {
+    pub url: String,
+    pub enabled_events: Vec<String>,
+}
+
+impl EndpointTarget {
+    /// Whether `endpoint` already sends exactly these events here.
+    pub fn matches(&self, endpoint: &WebhookEndpoint) -> bool {
+        let events = |list: &[String]| list.iter().cloned().collect::<BTreeSet<_>>();
+        endpoint.url == self.url
+            && endpoint.status == "enabled"
+            && events(&endpoint.enabled_events) == events(&self.enabled_events)
+    }
+}
+
+/// One endpoint and the secret it signs with.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SecretVersion {
+    pub version: i64,
+    pub endpoint_id: String,
+    /// `None` for an endpoint found at Stripe rather than created here:
+    /// its secret is the configured `STRIPE_WEBHOOK_SECRET`.
+    pub secret: Option<String>,
+    pub created_at: DateTime<Utc>,
+    /// When the endpoint is deleted, once it was rotated out; `None` for
+    /// the current one.
+    pub retires_at: Option<DateTime<Utc>>,
+}
+
+impl fmt::Debug for SecretVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecretVersion")
+            .field("version", &self.version)
+            .field("endpoint_id", &self.endpoint_id)
+            .field("created_at", &self.created_at)
+            .field("retires_at", &self.retires_at)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The endpoints fin_sync manages and their signing secrets, oldest first.
+/// Rotating adds an endpoint rather than replacing the secret in place, so
+/// the old endpoint keeps delivering, signed as before, until senders of
+/// the new secret are sure to be verified.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebhookSecrets {
+    pub versions: Vec<SecretVersion>,
+}
+
+impl WebhookSecrets {
+    /// The version new deliveries are meant to be signed with.
+    pub fn current(&self) -> Option<&SecretVersion> {
+        self.versions.iter().rev().find(|v| v.retires_at.is_none())
+    }
+
+    /// Record `endpoint_id` as current. The one current until now retires
+    /// `overlap` from `now`. Returns the new version.
+    pub fn push(
+        &mut self,
+        endpoint_id: &str,
+        secret: Option<String>,
+        now: DateTime<Utc>,
+        overlap: Duration,
+    ) -> i64 {
+        for v in &mut self.versions {
+            v.retires_at.get_or_insert(now + overlap);
+        }
+        let version = self.versions.iter().map(|v| v.version).max().unwrap_or(0) + 1;
+        self.versions.push(SecretVersion {
+            version,
+            endpoint_id: endpoint_id.to_string(),
+            secret,
+            created_at: now,
+            retires_at: None,
+        });
+        version
+    }
+
+    /// The secrets deliveries may be signed with, newest first: every
+    /// stored version, `configured` standing in for an adopted endpoint's.
+    /// Before any setup, just `configured`, as version 0.
+    pub fn signing_secrets(&self, configured: &str) -> Vec<SigningSecret> {
+        if self.versions.is_empty() {
+            return vec![SigningSecret {
+                version: 0,
+                secret: configured.to_string(),
+                valid_until: None,
+            }];
+        }
+        self.versions
+            .iter()
+            .rev()
+            .map(|v| SigningSecret {
+                version: v.version,
+                secret: v.secret.clone().unwrap_or_else(|| configured.to_string()),
+                valid_until: v.retires_at,
+            })
+            .collect()
+    }
+
+    /// Remove and return the versions due to retire by `now`.
+    pub fn take_retired(&mut self, now: DateTime<Utc>) -> Vec<SecretVersion> {
+        let (retired, kept) = std::mem::take(&mut self.versions)
+            .into_iter()
+            .partition(|v| v.retires_at.is_some_and(|at| at <= now));
+        self.versions = kept;
+        retired
+    }
+}
+
+/// A secret a webhook delivery may be signed with, and until when.
+#[derive(Clone)]
+pub struct SigningSecret {
+    pub version: i64,
+    pub secret: String,
+    /// The end of the rotation overlap; `None` for the current secret.
+    pub valid_until: Option<DateTime<Utc>>,
+}
+
+impl SigningSecret {
+    pub fn valid_at(&self, now: DateTime<Utc>) -> bool {
+        self.valid_until.is_none_or(|until| now < until)
+    }
+}
+
+impl fmt::Debug for SigningSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SigningSecret")
+            .field("version", &self.version)
+            .field("valid_until", &self.valid_until)
+            .finish_non_exhaustive()
+    }
+}
+
+/// What `webhook-setup` did to the current endpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SetupAction {
+    Created,
+    /// An endpoint already at Stripe for the URL was taken over.
+    Adopted,
+    Updated,
+    Unchanged,
+}
+
+impl SetupAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Created => "created",
+            Self::Adopted => "adopted",
+            Self::Updated => "updated",
+            Self::Unchanged => "unchanged",
+        }
+    }
+}
+
+/// `POST /admin/stripe/webhook-endpoint` and `fin_sync webhook-setup`.
+#[derive(Debug, Clone, Serialize)]
+pub struct EndpointSetup {
+    pub action: SetupAction,
+    pub endpoint_id: String,
+    pub url: String,
+    pub enabled_events: Vec<String>,
+    pub secret_version: i64,
+    /// Endpoints rotated out earlier and deleted now.
+    pub retired: Vec<String>,
+}
+
+/// `POST /admin/stripe/webhook-endpoint/rotate` and
+/// `fin_sync webhook-setup rotate`. The new secret itself is stored, not
+/// shown.
+#[derive(Debug, Clone, Serialize)]
+pub struct SecretRotation {
+    pub endpoint_id: String,
+    pub secret_version: i64,
+    pub previous_endpoint_id: String,
+    pub previous_retires_at: DateTime<Utc>,
+    pub retired: Vec<String>,
+}
+
+/// An audit entry about the endpoint `endpoint_id`: `action` is
+/// `webhook_endpoint_created`, `_adopted`, `_updated`, `_retired` or
+/// `webhook_secret_rotated`. Secrets never go in `detail`.
+pub fn audit_entry(
+    action: &str,
+    endpoint_id: &str,
+    detail: serde_json::Value,
+    actor: &str,
+) -> NewAuditEntry {
+    let id = Uuid::now_v7();
+    NewAuditEntry {
+        id,
+        entity_type: "webhook_endpoint".to_string(),
+        entity_id: None,
+        external_id: Some(endpoint_id.to_string()),
+        event_id: format!("{action}:{id}"),
+        source: Some("stripe".to_string()),
+        tenant_id: None,
+        action: action.to_string(),
+        actor: actor.to_string(),
+        detail,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotated_versions_retire_after_the_overlap() {
+        let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let overlap = Duration::hours(24);
+        let mut secrets = WebhookSecrets::default();
+        assert_eq!(secrets.push("we_1", None, now, overlap), 1);
+        assert_eq!(secrets.current().unwrap().endpoint_id, "we_1");
+
+        let later = now + Duration::hours(1);
+        assert_eq!(
+            secrets.push("we_2", Some("whsec_2".into()), later, overlap),
+            2
+        );
+        assert_eq!(secrets.current().unwrap().endpoint_id, "we_2");
+        assert_eq!(secrets.versions[0].retires_at, Some(later + overlap));
+
+        assert!(secrets.take_retired(later + Duration::hours(23)).is_empty());
+        let retired = secrets.take_retired(later + overlap);
+        assert_eq!(retired.len(), 1);
+        assert_eq!(retired[0].endpoint_id, "we_1");
+        assert_eq!(secrets.versions.len(), 1);
+
+        let shown = format!("{:?}", secrets.versions[0]);
+        assert!(!shown.contains("whsec_2"));
+    }
+
+    #[test]
+    fn adopted_endpoints_sign_with_the_configured_secret() {
+        let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let mut secrets = WebhookSecrets::default();
+        let before_setup = secrets.signing_secrets("whsec_env");
+        assert_eq!(before_setup.len(), 1);
+        assert_eq!(
+            (before_setup[0].version, before_setup[0].secret.as_str()),
+            (0, "whsec_env")
+        );
+
+        secrets.push("we_1", None, now, Duration::zero());
+        secrets.push("we_2", Some("whsec_2".into()), now, Duration::hours(1));
+        let signing = secrets.signing_secrets("whsec_env");
+        let versions: Vec<_> = signing
+            .iter()
+            .map(|s| (s.version, s.secret.as_str()))
+            .collect();
+        assert_eq!(versions, [(2, "whsec_2"), (1, "whsec_env")]);
+        assert!(signing[1].valid_at(now + Duration::minutes(59)));
+        assert!(!signing[1].valid_at(now + Duration::hours(1)));
+        assert!(signing[0].valid_at(now + Duration::days(365)));
+    }
+}
@@ -0,0 +1,400 @@
+//! fin_sync as a library: `FinSync` wires what the bundled binary wires
+//! (pool, providers, notifier, runtime config) so another service can mount
+//! the routes in its own axum app, run the background tasks on its own
+//! runtime and call the pipeline directly.
+
+use {
+    crate::{
+        AppState,
+        adapters::{
+            paypal::client::PaypalProvider,
+            stripe::{client::StripeProvider, router::EventRouter},
+        },
+        domain::{
+            adjustment::{TransitionOutcome, TransitionRequest},
+            alert::AlertSink,
+            hook::{HookRegistry, TransitionHook},
+            id::ExternalId,
+            job::JobLane,
+            manual::{IdempotencyKey, ManualOutcome, ManualPaymentRequest},
+            payment::{NewPayment, PaymentTrigger, PaymentView, ProcessResult},
+            provider::{PaymentProvider, ProviderRegistry},
+            replay::Simulation,
+            rollup::RollupSpec,
+        },
+        error::PipelineError,
+        infra::{
+            alert::WebhookSink,
+            config::{Config, DatabaseConfig},
+            export_store::ExportStore,
+            postgres::audit_relay_repo,
+            redact::{self, Redactor},
+            settings::{Settings, run_settings_listener},
+        },
+        services::{
+            audit_relay::run_audit_relay,
+            budget::ApiBudget,
+            config::{self, run_config_sync},
+            event_gap::run_gap_detector,
+            export::run_exporter,
+            hooks::run_hook_dispatcher,
+            migrate,
+            notify::{AnomalyAlerts, Notifier},
+            payment::{adjust, lookup, manual, pipeline},
+            reconciliation::run_reconciler,
+            retention::run_retention,
+            rollup::run_rollups,
+            shadow::run_shadow_verifier,
+            worker::{WorkerIdentity, run_reaper, run_worker},
+        },
+        transport::http::router,
+    },
+    axum::Router,
+    sqlx::{PgPool, postgres::PgPoolOptions},
+    std::sync::Arc,
+    tokio::{sync::watch, task::JoinHandle},
+};
+
+/// A wired fin_sync instance. Cheap to clone; clones share everything.
+#[derive(Clone)]
+pub struct FinSync {
+    state: AppState,
+}
+
+/// Builds a `FinSync` from a `Config`; see `FinSync::builder`.
+pub struct FinSyncBuilder {
+    config: Config,
+    pool: Option<PgPool>,
+    hooks: HookRegistry,
+    sinks: Vec<Arc<dyn AlertSink>>,
+    providers: Vec<Arc<dyn PaymentProvider>>,
+}
+
+impl FinSyncBuilder {
+    /// Use the embedding service's pool instead of connecting with
+    /// `config.database`.
+    pub fn pool(mut self, pool: PgPool) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+
+    /// Run `hook` on every payment transition, through the hook outbox.
+    pub fn hook(mut self, hook: Arc<dyn TransitionHook>) -> Self {
+        self.hooks.register(hook);
+        self
+    }
+
+    /// Send alerts to `sink` as well as the configured destinations.
+    pub fn alert_sink(mut self, sink: Arc<dyn AlertSink>) -> Self {
+        self.sinks.push(sink);
+        self
+    }
+
+    /// Register `provider` under its `source()`, replacing Stripe or PayPal
+    /// if it has theirs. Its API calls count against `provider_budgets`.
+    pub fn provider(mut self, provider: Arc<dyn PaymentProvider>) -> Self {
+        self.providers.push(provider);
+        self
+    }
+
+    /// Connect (unless given a pool), migrate when `MIGRATE_ON_STARTUP`
+    /// is set, and load the runtime config. Also installs the log
+    /// redactor for `LOG_REDACT_PATHS`; the first one installed in a
+    /// process stays.
+    pub async fn build(self) -> Result<FinSync, PipelineError> {
+        let settings = self.config;
+        redact::init(Redactor::with_extra(&settings.redact_paths));
+
+        let pool = match self.pool {
+            Some(pool) => pool,
+            None => connect(&settings.database).await?,
+        };
+        if settings.migrate_on_startup {
+            let report = migrate::run(&pool).await?;
+            tracing::info!(
+                applied = report.applied.len(),
+                waited = report.waited,
+                "database schema up to date"
+            );
+        }
+        let runtime_config = config::load(&pool).await?;
+
+        let mut notifier = Notifier::new(&settings.public_base_url);
+        if let Some(url) = &settings.alert_webhook_url {
+            notifier = notifier.with_sink(Arc::new(WebhookSink::raw(url)));
+        }
+        for destination in &settings.alert_destinations {
+            notifier = notifier.with_sink(Arc::new(WebhookSink::new(destination.clone())));
+        }
+        for sink in self.sinks {
+            notifier = notifier.with_sink(sink);
+        }
+
+        // Every provider API call is counted against `provider_budgets`. The
+        // PayPal client in `AppState` only verifies webhooks, outside budgets.
+        let budget = ApiBudget::new(pool.clone(), runtime_config.clone(), notifier.clone());
+        let mut providers = ProviderRegistry::default();
+        providers.register(
+            budget.wrap(Arc::new(
+                StripeProvider::new(&settings.stripe.secret_key)
+                    .with_receipt_email(settings.receipt_email),
+            )),
+        );
+        let paypal = settings.paypal.as_ref().map(|p| {
+            Arc::new(PaypalProvider::new(
+                &p.api_base,
+                &p.client_id,
+                &p.client_secret,
+                &p.webhook_id,
+            ))
+        });
+        if let Some(paypal) = &paypal {
+            providers.register(budget.wrap(paypal.clone()));
+        }
+        for provider in self.providers {
+            providers.register(budget.wrap(provider));
+        }
+
+        let mut hooks = self.hooks;
+        if notifier.has_external_sinks() {
+            hooks.register(Arc::new(AnomalyAlerts::new(notifier.clone())));
+        }
+
+        let settings = Arc::new(settings);
+        Ok(FinSync {
+            state: AppState {
+                settings_store: Settings::new(pool.clone())
+                    .with_sealing_key(settings.settings_key.clone()),
+                pool,
+                providers,
+                hooks,
+                config: runtime_config,
+                notifier,
+                paypal,
+                stripe_routes: Arc::new(EventRouter::with_overrides(&settings.stripe.event_routes)),
+                export_store: ExportStore::new(&settings.exports),
+                settings,
+            },
+        })
+    }
+}
+
+/// The background tasks `spawn_workers` started.
+pub struct Workers {
+    workers: Vec<JoinHandle<()>>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl Workers {
+    /// After shutdown is signalled, wait for the job workers to drain
+    /// (bounded by `worker_drain_timeout_secs`) and hand back the jobs
+    /// they still hold; stopping sooner would leave those jobs to the
+    /// reaper. The other tasks stop at their next tick and aren't awaited.
+    pub async fn join(self) {
+        drop(self.tasks);
+        for worker in self.workers {
+            if let Err(e) = worker.await {
+                tracing::error!(error = %e, "job worker panicked");
+            }
+        }
+    }
+}
+
+impl FinSync {
+    pub fn builder(config: Config) -> FinSyncBuilder {
+        FinSyncBuilder {
+            config,
+            pool: None,
+            hooks: HookRegistry::default(),
+            sinks: Vec::new(),
+            providers: Vec::new(),
+        }
+    }
+
+    /// What the handlers share, for embedders that need more than the
+    /// methods here.
+    pub fn state(&self) -> &AppState {
+        &self.state
+    }
+
+    pub fn pool(&self) -> &PgPool {
+        &self.state.pool
+    }
+
+    /// Every fin_sync route, with its middleware, ready to `merge` or
+    /// `nest` into the embedding app. Serve it with
+    /// `into_make_service_with_connect_info::<SocketAddr>()`: rate limits
+    /// key on the peer address.
+    pub fn router(&self) -> Router {
+        router::build(self.state.clone())
+    }
+
+    /// Start the job workers (one per lane) and every background task the
+    /// config turns on. They stop once `shutdown` turns true.
+    pub async fn spawn_workers(
+        &self,
+        shutdown: watch::Receiver<bool>,
+    ) -> Result<Workers, PipelineError> {
+        let state = &self.state;
+        let settings = &state.settings;
+        let intervals = &settings.intervals;
+
+        // Refunds have a worker of their own; see `JobLane`.
+        let identity = WorkerIdentity::detect();
+        let workers = [JobLane::Standard, JobLane::Refund]
+            .into_iter()
+            .map(|lane| {
+                tokio::spawn(run_worker(
+                    state.pool.clone(),
+                    state.settings_store.clone(),
+                    state.providers.clone(),
+                    state.config.clone(),
+                    identity.clone(),
+                    lane,
+                    shutdown.clone(),
+                ))
+            })
+            .collect();
+
+        let mut tasks = vec![
+            tokio::spawn(run_reaper(
+                state.pool.clone(),
+                state.config.clone(),
+                shutdown.clone(),
+            )),
+            tokio::spawn(run_settings_listener(
+                state.settings_store.clone(),
+                shutdown.clone(),
+            )),
+            tokio::spawn(run_config_sync(
+                state.pool.clone(),
+                state.config.clone(),
+                intervals.config_sync,
+                shutdown.clone(),
+            )),
+            tokio::spawn(run_gap_detector(
+                state.pool.clone(),
+                state.config.clone(),
+                intervals.gap_scan,
+                shutdown.clone(),
+            )),
+            tokio::spawn(run_reconciler(
+                state.pool.clone(),
+                state.providers.clone(),
+                state.notifier.clone(),
+                intervals.reconcile,
+                shutdown.clone(),
+            )),
+        ];
+        if let Some(audit_db) = &settings.audit_database {
+            let audit_pool = connect(audit_db).await?;
+            audit_relay_repo::enable(&state.pool).await?;
+            tasks.push(tokio::spawn(run_audit_relay(
+                state.pool.clone(),
+                audit_pool,
+                shutdown.clone(),
+            )));
+        }
+        if !state.hooks.is_empty() {
+            tasks.push(tokio::spawn(run_hook_dispatcher(
+                state.pool.clone(),
+                state.hooks.clone(),
+                shutdown.clone(),
+            )));
+        }
+        if !settings.exports.scheduled.is_empty() {
+            tasks.push(tokio::spawn(run_exporter(
+                state.pool.clone(),
+                state.export_store.clone(),
+                settings.exports.scheduled.clone(),
+                intervals.export,
+                shutdown.clone(),
+            )));
+        }
+        if !settings.retention.policies.is_empty() {
+            tasks.push(tokio::spawn(run_retention(
+                state.pool.clone(),
+                state.export_store.clone(),
+                settings.retention.clone(),
+                intervals.retention,
+                shutdown.clone(),
+            )));
+        }
+        tasks.push(tokio::spawn(run_shadow_verifier(
+            state.pool.clone(),
+            state.config.clone(),
+            intervals.shadow_verify,
+            shutdown.clone(),
+        )));
+        tasks.push(tokio::spawn(run_rollups(
+            state.pool.clone(),
+            RollupSpec::defaults(),
+            intervals.rollup,
+            shutdown,
+        )));
+        Ok(Workers { workers, tasks })
+    }
+
+    /// Run `payment` through the pipeline, as a provider event would be.
+    pub async fn process_event(
+        &self,
+        payment: &NewPayment,
+        actor: &str,
+    ) -> Result<ProcessResult, PipelineError> {
+        pipeline::process_payment_event(&self.state.pool, payment, actor).await
+    }
+
+    /// What `process_event` would do, run and rolled back.
+    pub async fn dry_run(&self, payment: &NewPayment) -> Result<Simulation, PipelineError> {
+        pipeline::process_payment_event_dry_run(&self.state.pool, payment).await
+    }
+
+    /// Fetch the payment `trigger` names from its provider, as the worker
+    /// does for a webhook, and run it through the pipeline.
+    pub async fn fetch_and_process(
+        &self,
+        trigger: PaymentTrigger,
+        actor: &str,
+    ) -> Result<ProcessResult, PipelineError> {
+        let provider = self
+            .state
+            .providers
+            .get_scoped(&trigger.source, trigger.tenant_id.as_ref())?;
+        pipeline::fetch_and_process_payment(&self.state.pool, &*provider, trigger, actor).await
+    }
+
+    /// Record a payment reported by an internal system; see
+    /// `POST /payments`.
+    pub async fn submit_manual(
+        &self,
+        request: &ManualPaymentRequest,
+        key: &IdempotencyKey,
+        actor: &str,
+    ) -> Result<ManualOutcome, PipelineError> {
+        manual::submit_manual_payment(&self.state.pool, request, key, actor).await
+    }
+
+    /// Move a payment to a status confirmed out of band; see
+    /// `POST /admin/payments/{id}/transition`. `None` if it doesn't exist.
+    pub async fn transition(
+        &self,
+        id: ExternalId,
+        request: &TransitionRequest,
+        actor: &str,
+    ) -> Result<Option<TransitionOutcome>, PipelineError> {
+        adjust::transition_payment(&self.state.pool, id, request, actor).await
+    }
+
+    pub async fn payment(&self, id: ExternalId) -> Result<Option<PaymentView>, PipelineError> {
+        lookup::get_payment_by_id(&self.state.pool, id).await
+    }
+}
+
+async fn connect(db: &DatabaseConfig) -> Result<PgPool, PipelineError> {
+    let pool = PgPoolOptions::new()
+        .max_connections(db.max_connections)
+        .acquire_timeout(db.acquire_timeout)
+        .connect(&db.url)
+        .await?;
+    Ok(pool)
+}
@@ -0,0 +1,109 @@
+use {
+    crate::domain::{
+        error::{DomainError, PortError},
+        webhook::ReplayRejection,
+    },
+    chrono::{DateTime, Utc},
+    thiserror::Error,
+};
+
+/// Everything that can go wrong while processing: domain rule violations
+/// plus the infra and transport failures around them.
+#[derive(Debug, Error)]
+pub enum PipelineError {
+    #[error("validation: {0}")]
+    Validation(String),
+
+    #[error("database: {0}")]
+    Database(#[from] sqlx::Error),
+
+    #[error("serialization: {0}")]
+    Serialization(#[from] serde_json::Error),
+
+    #[error("webhook signature: {0}")]
+    WebhookSignature(String),
+
+    #[error("webhook replay: {0}")]
+    WebhookReplay(String),
+
+    #[error("provider: {0}")]
+    Provider(String),
+
+    /// The provider has no such object: deleted, or never created.
+    /// Asking again won't change that.
+    #[error("missing at provider: {0}")]
+    ProviderMissing(String),
+
+    /// The provider refused the request itself (a malformed id, a
+    /// permission it won't grant); sending it again gets the same answer.
+    #[error("rejected by provider: {0}")]
+    ProviderRejected(String),
+
+    /// A provider API budget is at its hard limit for the current window;
+    /// the call wasn't made. Fine again once the window ends, `until`.
+    #[error("provider budget exhausted until {until}: {reason}")]
+    BudgetExhausted {
+        reason: String,
+        until: DateTime<Utc>,
+    },
+
+    /// Writing or uploading a file: local disk or object storage.
+    #[error("storage: {0}")]
+    Storage(String),
+}
+
+impl From<DomainError> for PipelineError {
+    fn from(err: DomainError) -> Self {
+        match err {
+            DomainError::Validation(msg) => PipelineError::Validation(msg),
+        }
+    }
+}
+
+impl From<PortError> for PipelineError {
+    fn from(err: PortError) -> Self {
+        match err {
+            PortError::Unavailable(msg) => PipelineError::Provider(msg),
+            PortError::Missing(msg) => PipelineError::ProviderMissing(msg),
+            PortError::Rejected(msg) => PipelineError::ProviderRejected(msg),
+            PortError::Invalid(msg) => PipelineError::Validation(msg),
+            PortError::BudgetExhausted { reason, until } => {
+                PipelineError::BudgetExhausted { reason, until }
+            }
+        }
+    }
+}
+
+/// The adapter boundary: adapters do their work with `PipelineError` and
+/// report it through a port as the nearest `PortError`. Infra failures
+/// (the database, storage) are ones a retry may get past.
+impl From<PipelineError> for PortError {
+    fn from(err: PipelineError) -> Self {
+        match err {
+            PipelineError::Validation(msg) => PortError::Invalid(msg),
+            PipelineError::Serialization(e) => PortError::Invalid(e.to_string()),
+            PipelineError::Provider(msg) => PortError::Unavailable(msg),
+            PipelineError::ProviderMissing(msg) => PortError::Missing(msg),
+            PipelineError::ProviderRejected(msg) => PortError::Rejected(msg),
+            PipelineError::BudgetExhausted { reason, until } => {
+                PortError::BudgetExhausted { reason, until }
+            }
+            other @ (PipelineError::Database(_)
+            | PipelineError::WebhookSignature(_)
+            | PipelineError::WebhookReplay(_)
+            | PipelineError::Storage(_)) => PortError::Unavailable(other.to_string()),
+        }
+    }
+}
+
+impl From<serde_json::Error> for PortError {
+    fn from(err: serde_json::Error) -> Self {
+        PortError::Invalid(err.to_string())
+    }
+}
+
+impl From<ReplayRejection> for PipelineError {
+    fn from(r: ReplayRejection) -> Self {
+        PipelineError::WebhookReplay(r.as_str().to_string())
+    }
+}
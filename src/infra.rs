@@ -1 +1,11 @@
+pub mod alert;
+pub mod auth;
+pub mod config;
+pub mod export_store;
+#[cfg(feature = "parquet")]
+pub mod parquet;
 pub mod postgres;
+pub mod redact;
+pub mod seal;
+pub mod settings;
+pub mod telemetry;
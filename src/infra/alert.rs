@@ -0,0 +1,91 @@
+use {
+    crate::domain::{
+        alert::{Alert, AlertDestination, AlertFormat, AlertSink},
+        error::PortError,
+    },
+    std::{future::Future, pin::Pin, time::Duration},
+};
+
+/// Writes alerts to the log. Always configured, so nothing is lost when no
+/// webhook is set up.
+pub struct LogSink;
+
+impl AlertSink for LogSink {
+    fn name(&self) -> &'static str {
+        "log"
+    }
+
+    fn send<'a>(
+        &'a self,
+        alert: &'a Alert,
+    ) -> Pin<Box<dyn Future<Output = Result<(), PortError>> + Send + 'a>> {
+        Box::pin(async move {
+            tracing::info!(kind = alert.kind, subject = %alert.subject, "{}", alert.text);
+            Ok(())
+        })
+    }
+}
+
+/// POSTs each alert routed to a destination, in the destination's format:
+/// a chat incoming webhook, an incident tool's events API, or a mail
+/// gateway that forwards to an inbox.
+pub struct WebhookSink {
+    http: reqwest::Client,
+    destination: AlertDestination,
+}
+
+impl WebhookSink {
+    pub fn new(destination: AlertDestination) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            destination,
+        }
+    }
+
+    /// Every alert as it is (`ALERT_WEBHOOK_URL`).
+    pub fn raw(url: &str) -> Self {
+        Self::new(AlertDestination {
+            name: "webhook".into(),
+            url: url.to_string(),
+            kinds: Vec::new(),
+            format: AlertFormat::Raw,
+            vars: Default::default(),
+            headers: Default::default(),
+        })
+    }
+}
+
+impl AlertSink for WebhookSink {
+    fn name(&self) -> &str {
+        &self.destination.name
+    }
+
+    fn accepts(&self, kind: &str) -> bool {
+        self.destination.accepts(kind)
+    }
+
+    fn send<'a>(
+        &'a self,
+        alert: &'a Alert,
+    ) -> Pin<Box<dyn Future<Output = Result<(), PortError>> + Send + 'a>> {
+        Box::pin(async move {
+            let body = self.destination.render(alert)?;
+            let mut request = self
+                .http
+                .post(&self.destination.url)
+                .timeout(Duration::from_secs(10))
+                .json(&body);
+            for (name, value) in &self.destination.headers {
+                request = request.header(name, value);
+            }
+            request
+                .send()
+                .await
+                .and_then(reqwest::Response::error_for_status)
+                .map_err(|e| {
+                    PortError::Unavailable(format!("alert webhook {}: {e}", self.destination.name))
+                })?;
+            Ok(())
+        })
+    }
+}
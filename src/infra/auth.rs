@@ -0,0 +1,340 @@
+use {
+    crate::{
+        domain::{audit::NewAuditEntry, id::TenantId},
+        error::PipelineError,
+        infra::postgres::{api_key_repo, audit_repo::insert_audit_entry},
+    },
+    chrono::{DateTime, Duration, Utc},
+    serde::{Deserialize, Serialize},
+    sha2::{Digest, Sha256},
+    sqlx::PgPool,
+    std::{fmt, str::FromStr},
+    uuid::Uuid,
+};
+
+/// Every key starts with this, so a leaked one is easy to grep for.
+pub const KEY_PREFIX: &str = "fsk_";
+
+/// Characters of a key kept in the clear to tell keys apart in listings.
+const SHOWN_CHARS: usize = KEY_PREFIX.len() + 8;
+
+/// `last_used_at` is refreshed at most this often per key.
+const TOUCH_INTERVAL_SECS: i64 = 60;
+
+/// What a key may do. `Admin` includes the other two.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    /// Payment lookups, exports and reports.
+    Read,
+    /// Replaying stored provider events.
+    Replay,
+    /// Everything else under `/admin`, and writes outside it.
+    Admin,
+}
+
+impl Scope {
+    pub const ALL: [Self; 3] = [Self::Read, Self::Replay, Self::Admin];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Read => "read",
+            Self::Replay => "replay",
+            Self::Admin => "admin",
+        }
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Scope {
+    type Err = PipelineError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "read" => Ok(Self::Read),
+            "replay" => Ok(Self::Replay),
+            "admin" => Ok(Self::Admin),
+            other => Err(PipelineError::Validation(format!(
+                "unknown scope {other:?} (expected read, replay or admin)"
+            ))),
+        }
+    }
+}
+
+/// Who a key is for. Decides which fields read responses show; what the key
+/// may call is up to its scopes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// Payment state and history, without amounts or provider payloads.
+    Support,
+    /// Amounts too, still without provider payloads.
+    Finance,
+    /// Everything.
+    #[default]
+    Engineering,
+}
+
+impl Role {
+    pub const ALL: [Self; 3] = [Self::Support, Self::Finance, Self::Engineering];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Support => "support",
+            Self::Finance => "finance",
+            Self::Engineering => "engineering",
+        }
+    }
+
+    pub fn sees_amounts(self) -> bool {
+        matches!(self, Self::Finance | Self::Engineering)
+    }
+
+    pub fn sees_payloads(self) -> bool {
+        self == Self::Engineering
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Role {
+    type Err = PipelineError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "support" => Ok(Self::Support),
+            "finance" => Ok(Self::Finance),
+            "engineering" => Ok(Self::Engineering),
+            other => Err(PipelineError::Validation(format!(
+                "unknown role {other:?} (expected support, finance or engineering)"
+            ))),
+        }
+    }
+}
+
+/// Comma-separated scopes, as given to `create-api-key`; sorted, no repeats.
+pub fn parse_scopes(s: &str) -> Result<Vec<Scope>, PipelineError> {
+    let mut scopes = s
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(Scope::from_str)
+        .collect::<Result<Vec<_>, _>>()?;
+    scopes.sort();
+    scopes.dedup();
+    Ok(scopes)
+}
+
+/// One `api_keys` row. The key itself is never stored, only its hash.
+#[derive(Debug, Clone, Serialize)]
+pub struct ApiKey {
+    pub id: Uuid,
+    pub name: String,
+    /// The key's first characters.
+    pub prefix: String,
+    pub scopes: Vec<Scope>,
+    pub role: Role,
+    /// Set for a key handed to one connected account: it reads that
+    /// account's payments and events and nothing else.
+    pub tenant_id: Option<TenantId>,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl ApiKey {
+    pub fn allows(&self, scope: Scope) -> bool {
+        self.revoked_at.is_none() && self.scopes.iter().any(|&s| s == scope || s == Scope::Admin)
+    }
+
+    /// Audit actor for `who` acting as `role` with this key, e.g.
+    /// `admin:alice (key ops-console)`.
+    pub fn actor(&self, role: &str, who: &str) -> String {
+        format!("{role}:{who} (key {})", self.name)
+    }
+
+    fn audit_entry(&self, action: &str, actor: &str) -> NewAuditEntry {
+        NewAuditEntry {
+            id: Uuid::now_v7(),
+            entity_type: "api_key".to_string(),
+            entity_id: Some(self.id),
+            external_id: None,
+            event_id: format!("api_key_{action}:{}", self.id),
+            source: None,
+            tenant_id: None,
+            action: format!("api_key_{action}"),
+            actor: actor.to_string(),
+            detail: serde_json::json!({
+                "name": self.name,
+                "prefix": self.prefix,
+                "scopes": self.scopes,
+                "role": self.role,
+                "tenant_id": self.tenant_id,
+            }),
+        }
+    }
+}
+
+/// A key as handed out: `fsk_` and 64 hex characters from two v4 UUIDs.
+fn generate_key() -> String {
+    format!(
+        "{KEY_PREFIX}{}{}",
+        Uuid::new_v4().simple(),
+        Uuid::new_v4().simple()
+    )
+}
+
+/// What `api_keys.key_hash` holds for `key`. Keys are random and long, so a
+/// plain SHA-256 is enough; there is nothing to guess that a slow hash would
+/// protect.
+pub fn hash_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+/// Create a key called `name`, audited as `actor`; `tenant` limits it to one
+/// connected account's data, and such a key may only read. Returns the row
+/// and the key itself, which is shown this once and can't be recovered.
+pub async fn create_key(
+    pool: &PgPool,
+    name: &str,
+    scopes: &[Scope],
+    role: Role,
+    tenant: Option<&TenantId>,
+    actor: &str,
+) -> Result<(ApiKey, String), PipelineError> {
+    let name = name.trim();
+    if name.is_empty() || name.len() > 64 {
+        return Err(PipelineError::Validation(
+            "key name must be 1 to 64 characters".into(),
+        ));
+    }
+    if scopes.is_empty() {
+        return Err(PipelineError::Validation(
+            "a key needs at least one scope".into(),
+        ));
+    }
+    if tenant.is_some() && scopes != [Scope::Read] {
+        return Err(PipelineError::Validation(
+            "a tenant key may only have the read scope".into(),
+        ));
+    }
+
+    let secret = generate_key();
+    let mut tx = pool.begin().await?;
+    let key = api_key_repo::insert(
+        &mut tx,
+        Uuid::now_v7(),
+        name,
+        &secret[..SHOWN_CHARS],
+        &hash_key(&secret),
+        scopes,
+        role,
+        tenant.map(TenantId::as_str),
+        actor,
+    )
+    .await?
+    .ok_or_else(|| PipelineError::Validation(format!("a key named {name:?} already exists")))?;
+    insert_audit_entry(&mut tx, &key.audit_entry("created", actor)).await?;
+    tx.commit().await?;
+
+    tracing::info!(key = %key.name, scopes = ?key.scopes, role = %key.role, actor, "api key created");
+    Ok((key, secret))
+}
+
+/// Revoke key `id`, audited as `actor`. `None` if there is no such key;
+/// revoking a revoked key changes nothing.
+pub async fn revoke_key(
+    pool: &PgPool,
+    id: Uuid,
+    actor: &str,
+) -> Result<Option<ApiKey>, PipelineError> {
+    let mut tx = pool.begin().await?;
+    let Some(key) = api_key_repo::revoke(&mut tx, id).await? else {
+        tx.rollback().await?;
+        return api_key_repo::get(pool, id).await;
+    };
+    insert_audit_entry(&mut tx, &key.audit_entry("revoked", actor)).await?;
+    tx.commit().await?;
+
+    tracing::info!(key = %key.name, actor, "api key revoked");
+    Ok(Some(key))
+}
+
+/// The live key `token` belongs to, if any.
+pub async fn authenticate(pool: &PgPool, token: &str) -> Result<Option<ApiKey>, PipelineError> {
+    if !token.starts_with(KEY_PREFIX) {
+        return Ok(None);
+    }
+    let Some(mut key) = api_key_repo::find_by_hash(pool, &hash_key(token)).await? else {
+        return Ok(None);
+    };
+    if key.revoked_at.is_some() {
+        return Ok(None);
+    }
+
+    let now = Utc::now();
+    if key
+        .last_used_at
+        .is_none_or(|at| now - at >= Duration::seconds(TOUCH_INTERVAL_SECS))
+    {
+        api_key_repo::touch(pool, key.id, now).await?;
+        key.last_used_at = Some(now);
+    }
+    Ok(Some(key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_hash_and_scopes_nest() {
+        let key = generate_key();
+        assert!(key.starts_with(KEY_PREFIX));
+        assert_eq!(key.len(), KEY_PREFIX.len() + 64);
+        assert_ne!(key, generate_key());
+        assert_eq!(hash_key(&key), hash_key(&key));
+        assert_eq!(hash_key(&key).len(), 64);
+
+        assert_eq!(
+            parse_scopes("replay, read,replay").unwrap(),
+            [Scope::Read, Scope::Replay]
+        );
+        assert!(parse_scopes("read,write").is_err());
+        assert_eq!("finance".parse::<Role>().unwrap(), Role::Finance);
+        assert!("ops".parse::<Role>().is_err());
+
+        let mut api_key = ApiKey {
+            id: Uuid::now_v7(),
+            name: "ops-console".into(),
+            prefix: key[..SHOWN_CHARS].into(),
+            scopes: vec![Scope::Read],
+            role: Role::Support,
+            tenant_id: None,
+            created_by: "cli".into(),
+            created_at: Utc::now(),
+            last_used_at: None,
+            revoked_at: None,
+        };
+        assert!(api_key.allows(Scope::Read));
+        assert!(!api_key.allows(Scope::Replay));
+        api_key.scopes = vec![Scope::Admin];
+        assert!(api_key.allows(Scope::Replay));
+        api_key.revoked_at = Some(Utc::now());
+        assert!(!api_key.allows(Scope::Read));
+        assert_eq!(
+            api_key.actor("admin", "alice"),
+            "admin:alice (key ops-console)"
+        );
+    }
+}
@@ -0,0 +1,749 @@
+use {
+    crate::{
+        domain::{
+            alert::AlertDestination,
+            error::DomainError,
+            export::ExportKind,
+            payment::ReceiptEmailPolicy,
+            retention::{MIN_RETENTION_DAYS, RetainedTable, RetentionPolicy},
+            webhook::EventRoute,
+        },
+        infra::seal::SealingKey,
+    },
+    std::{
+        env,
+        fmt::{self, Display},
+        fs,
+        net::SocketAddr,
+        path::PathBuf,
+        str::FromStr,
+        time::Duration,
+    },
+    thiserror::Error,
+};
+
+#[derive(Debug, Error, PartialEq)]
+pub enum ConfigError {
+    #[error("{0} must be set")]
+    Missing(&'static str),
+
+    #[error("{var}: {reason}")]
+    Invalid { var: &'static str, reason: String },
+}
+
+/// Process configuration, read from the environment once at startup.
+/// Unlike `RuntimeConfig`, which lives in the database, changing any of
+/// this takes a restart.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub database: DatabaseConfig,
+    /// Where audit entries are shipped (`AUDIT_DATABASE_URL`), if anywhere.
+    pub audit_database: Option<DatabaseConfig>,
+    pub stripe: StripeConfig,
+    /// Set only when all three PayPal credentials are.
+    pub paypal: Option<PaypalConfig>,
+    pub http: HttpConfig,
+    pub intervals: IntervalConfig,
+    /// Where this service is reachable, for links in alerts.
+    pub public_base_url: String,
+    pub alert_webhook_url: Option<String>,
+    /// Webhooks with their own routing and payload format, declared in the
+    /// JSON file at `ALERT_DESTINATIONS_FILE`.
+    pub alert_destinations: Vec<AlertDestination>,
+    /// Extra JSON paths to mask in logged payloads (`LOG_REDACT_PATHS`).
+    pub redact_paths: Vec<String>,
+    /// How much of a customer's receipt email is stored
+    /// (`RECEIPT_EMAIL_STORAGE`: `full`, `masked` or `omit`).
+    pub receipt_email: ReceiptEmailPolicy,
+    /// Encrypts secret settings such as managed webhook secrets
+    /// (`SETTINGS_ENCRYPTION_KEY`, 64 hex characters). Without it they
+    /// can't be stored.
+    pub settings_key: Option<SealingKey>,
+    pub exports: ExportConfig,
+    pub retention: RetentionConfig,
+    pub migrate_on_startup: bool,
+    /// Mounts `/dev/*` (`DEV_ROUTES`), which takes events without a
+    /// signature. Local development only: refused with a live Stripe key.
+    pub dev_routes: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatabaseConfig {
+    pub url: String,
+    pub max_connections: u32,
+    pub acquire_timeout: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct StripeConfig {
+    pub webhook_secret: String,
+    pub secret_key: String,
+    /// Routing rules tried ahead of the built-in ones (`STRIPE_EVENT_ROUTES`,
+    /// comma-separated `pattern=strategy`).
+    pub event_routes: Vec<EventRoute>,
+    /// `STRIPE_API_BASE`; `https://api.stripe.com` unless testing.
+    pub api_base: String,
+    /// Where Stripe is told to send webhooks (`STRIPE_WEBHOOK_URL`);
+    /// `PUBLIC_BASE_URL` + `/webhook` by default.
+    pub webhook_url: String,
+    /// How long an endpoint keeps delivering after its secret is rotated
+    /// (`STRIPE_SECRET_OVERLAP_SECS`, default 24h).
+    pub secret_overlap: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct PaypalConfig {
+    pub client_id: String,
+    pub client_secret: String,
+    pub webhook_id: String,
+    pub api_base: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpConfig {
+    pub listen_addr: SocketAddr,
+    pub body_limit_bytes: usize,
+    /// Body limit for the webhook endpoints; Stripe's events stay under 64KB.
+    pub webhook_body_limit_bytes: usize,
+    /// Body limit for `/admin/*`, whose bulk requests run larger.
+    pub admin_body_limit_bytes: usize,
+    /// Body limit for `POST /ingest/batch`, which takes whole settlement files.
+    pub ingest_body_limit_bytes: usize,
+    pub request_timeout: Duration,
+    pub webhook_rate_limit: RateLimitConfig,
+    pub load_shed: LoadShedConfig,
+}
+
+/// When the read API gives way to ingestion: reads are refused while
+/// ingestion waits longer than `max_acquire_latency` for a connection. A zero latency (`LOAD_SHED_ACQUIRE_MS=0`) never
+/// sheds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LoadShedConfig {
+    /// `LOAD_SHED_ACQUIRE_MS`, default 250.
+    pub max_acquire_latency: Duration,
+    /// How long a wait ingestion finished still counts
+    /// (`LOAD_SHED_WINDOW_MS`, default 500); waits still going always do.
+    pub window: Duration,
+    /// `Retry-After` on a shed request (`LOAD_SHED_RETRY_AFTER_SECS`,
+    /// default 5).
+    pub retry_after_secs: u64,
+}
+
+/// Request rates the webhook endpoints accept, per sender IP and in total.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimitConfig {
+    pub per_ip: Rate,
+    pub global: Rate,
+}
+
+/// Steady requests per second, and how many may arrive at once.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rate {
+    pub per_sec: u32,
+    pub burst: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct ExportConfig {
+    /// Where exports are written (`EXPORT_DIR`); with a bucket, only while
+    /// they run.
+    pub dir: PathBuf,
+    /// Set when `EXPORT_S3_BUCKET` is.
+    pub s3: Option<S3Config>,
+    /// Kinds exported every day for the previous UTC day
+    /// (`SCHEDULED_EXPORTS`); none by default.
+    pub scheduled: Vec<ExportKind>,
+}
+
+/// Archival and pruning of the tables that grow without bound. Archives go
+/// where exports go: `EXPORT_DIR`, or the bucket.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetentionConfig {
+    /// One per table given a maximum age in days
+    /// (`RETENTION_PROVIDER_EVENTS_DAYS`, `RETENTION_AUDIT_LOG_DAYS`); unset
+    /// or 0 keeps the table whole.
+    pub policies: Vec<RetentionPolicy>,
+    /// Only report what is due (`RETENTION_DRY_RUN`).
+    pub dry_run: bool,
+    /// Rows per archive file (`RETENTION_BATCH_SIZE`).
+    pub batch_size: u32,
+    /// Most batches per table in one run (`RETENTION_MAX_BATCHES`); the rest
+    /// waits for the next.
+    pub max_batches: u32,
+}
+
+/// An S3-compatible bucket: AWS, or MinIO, R2 and the like via `endpoint`.
+#[derive(Clone)]
+pub struct S3Config {
+    pub endpoint: String,
+    pub region: String,
+    pub bucket: String,
+    /// Prepended to every key, e.g. `finance/`.
+    pub prefix: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+}
+
+impl fmt::Debug for S3Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("S3Config")
+            .field("endpoint", &self.endpoint)
+            .field("region", &self.region)
+            .field("bucket", &self.bucket)
+            .field("prefix", &self.prefix)
+            .field("access_key_id", &self.access_key_id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// How often the scheduled background tasks run. The worker and reaper are
+/// tuned through `RuntimeConfig` instead.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IntervalConfig {
+    pub reconcile: Duration,
+    pub gap_scan: Duration,
+    pub rollup: Duration,
+    pub config_sync: Duration,
+    /// How often scheduled exports check for a day not yet exported.
+    pub export: Duration,
+    /// How often `payments_v2` is compared with `payments` while writes
+    /// are mirrored into it.
+    pub shadow_verify: Duration,
+    /// How often tables with a retention policy are archived and pruned.
+    pub retention: Duration,
+}
+
+impl Config {
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Self::from_lookup(|name| env::var(name).ok())
+    }
+
+    /// Load from `lookup` (variable name to value) instead of the process
+    /// environment. Empty values count as unset.
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Result<Self, ConfigError> {
+        let vars = Vars(lookup);
+        let paypal = match (
+            vars.optional("PAYPAL_CLIENT_ID"),
+            vars.optional("PAYPAL_CLIENT_SECRET"),
+            vars.optional("PAYPAL_WEBHOOK_ID"),
+        ) {
+            (Some(client_id), Some(client_secret), Some(webhook_id)) => Some(PaypalConfig {
+                client_id,
+                client_secret,
+                webhook_id,
+                api_base: vars
+                    .optional("PAYPAL_API_BASE")
+                    .unwrap_or_else(|| "https://api-m.paypal.com".to_string()),
+            }),
+            (None, None, None) => None,
+            _ => {
+                return Err(ConfigError::Invalid {
+                    var: "PAYPAL_CLIENT_ID",
+                    reason: "PAYPAL_CLIENT_ID, PAYPAL_CLIENT_SECRET and PAYPAL_WEBHOOK_ID \
+                             must be set together"
+                        .into(),
+                });
+            }
+        };
+
+        let audit_database = match vars.optional("AUDIT_DATABASE_URL") {
+            Some(url) => Some(DatabaseConfig {
+                url,
+                max_connections: vars.ranged("AUDIT_DATABASE_MAX_CONNECTIONS", 2, 1..=100)?,
+                acquire_timeout: vars.secs("DATABASE_ACQUIRE_TIMEOUT_SECS", 3)?,
+            }),
+            None => None,
+        };
+
+        let public_base_url = vars
+            .optional("PUBLIC_BASE_URL")
+            .unwrap_or_else(|| "http://localhost:3000".to_string());
+
+        // `/dev/simulate` writes payments from unsigned, unauthenticated
+        // requests; never next to a live Stripe account.
+        let secret_key = vars.required("STRIPE_SECRET_KEY")?;
+        let dev_routes = vars.parsed("DEV_ROUTES", false)?;
+        if dev_routes && (secret_key.starts_with("sk_live_") || secret_key.starts_with("rk_live_"))
+        {
+            return Err(ConfigError::Invalid {
+                var: "DEV_ROUTES",
+                reason: "refused with a live STRIPE_SECRET_KEY".into(),
+            });
+        }
+
+        Ok(Self {
+            database: DatabaseConfig::load(&vars)?,
+            audit_database,
+            stripe: StripeConfig {
+                webhook_secret: vars.required("STRIPE_WEBHOOK_SECRET")?,
+                secret_key,
+                event_routes: vars
+                    .optional("STRIPE_EVENT_ROUTES")
+                    .unwrap_or_default()
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|r| !r.is_empty())
+                    .map(|r| {
+                        r.parse().map_err(|e: DomainError| ConfigError::Invalid {
+                            var: "STRIPE_EVENT_ROUTES",
+                            reason: e.to_string(),
+                        })
+                    })
+                    .collect::<Result<_, _>>()?,
+                api_base: vars
+                    .optional("STRIPE_API_BASE")
+                    .unwrap_or_else(|| "https://api.stripe.com".to_string()),
+                webhook_url: vars.optional("STRIPE_WEBHOOK_URL").unwrap_or_else(|| {
+                    format!("{}/webhook", public_base_url.trim_end_matches('/'))
+                }),
+                secret_overlap: Duration::from_secs(vars.ranged(
+                    "STRIPE_SECRET_OVERLAP_SECS",
+                    86_400,
+                    60..=7 * 86_400,
+                )?),
+            },
+            paypal,
+            http: HttpConfig {
+                listen_addr: vars.parsed("LISTEN_ADDR", SocketAddr::from(([0, 0, 0, 0], 3000)))?,
+                body_limit_bytes: vars.ranged(
+                    "HTTP_BODY_LIMIT_BYTES",
+                    64 * 1024,
+                    1024..=16 * 1024 * 1024,
+                )?,
+                webhook_body_limit_bytes: vars.ranged(
+                    "WEBHOOK_BODY_LIMIT_BYTES",
+                    64 * 1024,
+                    1024..=16 * 1024 * 1024,
+                )?,
+                admin_body_limit_bytes: vars.ranged(
+                    "ADMIN_BODY_LIMIT_BYTES",
+                    1024 * 1024,
+                    1024..=64 * 1024 * 1024,
+                )?,
+                ingest_body_limit_bytes: vars.ranged(
+                    "INGEST_BODY_LIMIT_BYTES",
+                    8 * 1024 * 1024,
+                    1024..=256 * 1024 * 1024,
+                )?,
+                request_timeout: vars.secs("HTTP_REQUEST_TIMEOUT_SECS", 30)?,
+                webhook_rate_limit: RateLimitConfig {
+                    per_ip: Rate {
+                        per_sec: vars.ranged("WEBHOOK_RATE_PER_IP", 50, 1..=100_000)?,
+                        burst: vars.ranged("WEBHOOK_BURST_PER_IP", 100, 1..=100_000)?,
+                    },
+                    global: Rate {
+                        per_sec: vars.ranged("WEBHOOK_RATE_GLOBAL", 200, 1..=100_000)?,
+                        burst: vars.ranged("WEBHOOK_BURST_GLOBAL", 400, 1..=100_000)?,
+                    },
+                },
+                load_shed: LoadShedConfig {
+                    max_acquire_latency: Duration::from_millis(vars.ranged(
+                        "LOAD_SHED_ACQUIRE_MS",
+                        250,
+                        0..=60_000,
+                    )?),
+                    window: Duration::from_millis(vars.ranged(
+                        "LOAD_SHED_WINDOW_MS",
+                        500,
+                        10..=60_000,
+                    )?),
+                    retry_after_secs: vars.ranged("LOAD_SHED_RETRY_AFTER_SECS", 5, 1..=300)?,
+                },
+            },
+            intervals: IntervalConfig {
+                reconcile: vars.secs("RECONCILE_INTERVAL_SECS", 3600)?,
+                gap_scan: vars.secs("GAP_SCAN_INTERVAL_SECS", 600)?,
+                rollup: vars.secs("ROLLUP_INTERVAL_SECS", 300)?,
+                config_sync: vars.secs("CONFIG_SYNC_INTERVAL_SECS", 30)?,
+                export: vars.secs("EXPORT_INTERVAL_SECS", 3600)?,
+                shadow_verify: vars.secs("SHADOW_VERIFY_INTERVAL_SECS", 3600)?,
+                retention: vars.secs("RETENTION_INTERVAL_SECS", 3600)?,
+            },
+            public_base_url,
+            alert_webhook_url: vars.optional("ALERT_WEBHOOK_URL"),
+            alert_destinations: match vars.optional("ALERT_DESTINATIONS_FILE") {
+                Some(path) => load_alert_destinations(&path)?,
+                None => Vec::new(),
+            },
+            redact_paths: vars
+                .optional("LOG_REDACT_PATHS")
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|p| !p.is_empty())
+                .map(String::from)
+                .collect(),
+            receipt_email: vars.parsed("RECEIPT_EMAIL_STORAGE", ReceiptEmailPolicy::default())?,
+            settings_key: vars
+                .optional("SETTINGS_ENCRYPTION_KEY")
+                .map(|hex| {
+                    SealingKey::from_hex(&hex).map_err(|reason| ConfigError::Invalid {
+                        var: "SETTINGS_ENCRYPTION_KEY",
+                        reason,
+                    })
+                })
+                .transpose()?,
+            exports: ExportConfig::load(&vars)?,
+            retention: RetentionConfig::load(&vars)?,
+            migrate_on_startup: vars.parsed("MIGRATE_ON_STARTUP", false)?,
+            dev_routes,
+        })
+    }
+}
+
+/// `{"destinations": [...]}`, each destination validated, names unique.
+fn load_alert_destinations(path: &str) -> Result<Vec<AlertDestination>, ConfigError> {
+    #[derive(serde::Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct File {
+        destinations: Vec<AlertDestination>,
+    }
+
+    let invalid = |reason: String| ConfigError::Invalid {
+        var: "ALERT_DESTINATIONS_FILE",
+        reason: format!("{path}: {reason}"),
+    };
+    let contents = fs::read_to_string(path).map_err(|e| invalid(e.to_string()))?;
+    let File { destinations } =
+        serde_json::from_str(&contents).map_err(|e| invalid(e.to_string()))?;
+    let mut names = std::collections::BTreeSet::new();
+    for d in &destinations {
+        d.validate().map_err(|e| invalid(e.to_string()))?;
+        if !names.insert(d.name.as_str()) {
+            return Err(invalid(format!("duplicate destination name: {}", d.name)));
+        }
+    }
+    Ok(destinations)
+}
+
+impl ExportConfig {
+    fn load(vars: &Vars<impl Fn(&str) -> Option<String>>) -> Result<Self, ConfigError> {
+        let s3 = match vars.optional("EXPORT_S3_BUCKET") {
+            Some(bucket) => {
+                let region = vars
+                    .optional("EXPORT_S3_REGION")
+                    .unwrap_or_else(|| "us-east-1".to_string());
+                Some(S3Config {
+                    endpoint: vars
+                        .optional("EXPORT_S3_ENDPOINT")
+                        .unwrap_or_else(|| format!("https://s3.{region}.amazonaws.com")),
+                    region,
+                    bucket,
+                    prefix: vars.optional("EXPORT_S3_PREFIX").unwrap_or_default(),
+                    access_key_id: vars.required("EXPORT_S3_ACCESS_KEY_ID")?,
+                    secret_access_key: vars.required("EXPORT_S3_SECRET_ACCESS_KEY")?,
+                })
+            }
+            None => None,
+        };
+        Ok(Self {
+            dir: vars
+                .optional("EXPORT_DIR")
+                .unwrap_or_else(|| "exports".to_string())
+                .into(),
+            s3,
+            scheduled: vars
+                .optional("SCHEDULED_EXPORTS")
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|k| !k.is_empty())
+                .map(|k| {
+                    k.parse().map_err(|e: DomainError| ConfigError::Invalid {
+                        var: "SCHEDULED_EXPORTS",
+                        reason: e.to_string(),
+                    })
+                })
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+impl RetentionConfig {
+    fn load(vars: &Vars<impl Fn(&str) -> Option<String>>) -> Result<Self, ConfigError> {
+        let mut policies = Vec::new();
+        for (table, var) in [
+            (
+                RetainedTable::ProviderEvents,
+                "RETENTION_PROVIDER_EVENTS_DAYS",
+            ),
+            (RetainedTable::AuditLog, "RETENTION_AUDIT_LOG_DAYS"),
+        ] {
+            let days = vars.ranged(var, 0, 0..=36_500)?;
+            if days == 0 {
+                continue;
+            }
+            if days < MIN_RETENTION_DAYS {
+                return Err(ConfigError::Invalid {
+                    var,
+                    reason: format!("keep at least {MIN_RETENTION_DAYS} days, or 0 for all"),
+                });
+            }
+            policies.push(RetentionPolicy {
+                table,
+                max_age_days: days,
+            });
+        }
+        Ok(Self {
+            policies,
+            dry_run: vars.parsed("RETENTION_DRY_RUN", false)?,
+            batch_size: vars.ranged("RETENTION_BATCH_SIZE", 5_000, 1..=100_000)?,
+            max_batches: vars.ranged("RETENTION_MAX_BATCHES", 20, 1..=10_000)?,
+        })
+    }
+}
+
+impl DatabaseConfig {
+    /// Just the main database, for commands that need nothing else.
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Self::load(&Vars(|name: &str| env::var(name).ok()))
+    }
+
+    fn load(vars: &Vars<impl Fn(&str) -> Option<String>>) -> Result<Self, ConfigError> {
+        Ok(Self {
+            url: vars.required("DATABASE_URL")?,
+            max_connections: vars.ranged("DATABASE_MAX_CONNECTIONS", 20, 1..=1000)?,
+            acquire_timeout: vars.secs("DATABASE_ACQUIRE_TIMEOUT_SECS", 3)?,
+        })
+    }
+}
+
+struct Vars<F>(F);
+
+impl<F: Fn(&str) -> Option<String>> Vars<F> {
+    fn optional(&self, name: &'static str) -> Option<String> {
+        (self.0)(name).filter(|v| !v.trim().is_empty())
+    }
+
+    fn required(&self, name: &'static str) -> Result<String, ConfigError> {
+        self.optional(name).ok_or(ConfigError::Missing(name))
+    }
+
+    fn parsed<T>(&self, name: &'static str, default: T) -> Result<T, ConfigError>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        match self.optional(name) {
+            None => Ok(default),
+            Some(v) => v.trim().parse().map_err(|e: T::Err| ConfigError::Invalid {
+                var: name,
+                reason: format!("{v:?}: {e}"),
+            }),
+        }
+    }
+
+    fn ranged<T>(
+        &self,
+        name: &'static str,
+        default: T,
+        range: std::ops::RangeInclusive<T>,
+    ) -> Result<T, ConfigError>
+    where
+        T: FromStr + PartialOrd + Display,
+        T::Err: Display,
+    {
+        let value = self.parsed(name, default)?;
+        if !range.contains(&value) {
+            return Err(ConfigError::Invalid {
+                var: name,
+                reason: format!("{value} is outside {}..={}", range.start(), range.end()),
+            });
+        }
+        Ok(value)
+    }
+
+    fn secs(&self, name: &'static str, default: u64) -> Result<Duration, ConfigError> {
+        self.ranged(name, default, 1..=86_400)
+            .map(Duration::from_secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, std::collections::HashMap};
+
+    fn load(vars: &[(&str, &str)]) -> Result<Config, ConfigError> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        Config::from_lookup(|name| vars.get(name).cloned())
+    }
+
+    const REQUIRED: &[(&str, &str)] = &[
+        ("DATABASE_URL", "postgres://localhost/fin_sync"),
+        ("STRIPE_WEBHOOK_SECRET", "whsec_x"),
+        ("STRIPE_SECRET_KEY", "sk_test_x"),
+    ];
+
+    #[test]
+    fn defaults_fill_in_everything_optional() {
+        let config = load(REQUIRED).unwrap();
+        assert_eq!(config.database.max_connections, 20);
+        assert_eq!(config.http.listen_addr.to_string(), "0.0.0.0:3000");
+        assert_eq!(config.http.body_limit_bytes, 64 * 1024);
+        assert_eq!(config.http.webhook_body_limit_bytes, 64 * 1024);
+        assert_eq!(config.http.webhook_rate_limit.global.per_sec, 200);
+        assert_eq!(
+            config.http.load_shed.max_acquire_latency,
+            Duration::from_millis(250)
+        );
+        assert_eq!(config.intervals.reconcile, Duration::from_secs(3600));
+        assert!(config.paypal.is_none() && config.audit_database.is_none());
+        assert!(!config.migrate_on_startup && !config.dev_routes);
+        assert!(config.settings_key.is_none());
+        assert!(config.redact_paths.is_empty());
+        assert_eq!(config.receipt_email, ReceiptEmailPolicy::Masked);
+        assert!(config.alert_destinations.is_empty());
+        assert!(config.stripe.event_routes.is_empty());
+        assert_eq!(config.stripe.webhook_url, "http://localhost:3000/webhook");
+        assert_eq!(config.stripe.secret_overlap, Duration::from_secs(86_400));
+        assert!(config.exports.s3.is_none() && config.exports.scheduled.is_empty());
+        assert!(config.retention.policies.is_empty() && !config.retention.dry_run);
+    }
+
+    #[test]
+    fn alert_destinations_are_read_from_their_file() {
+        let path = env::temp_dir().join(format!("alerts-{}.json", uuid::Uuid::new_v4()));
+        let with_file = |contents: &str| {
+            fs::write(&path, contents).unwrap();
+            let mut vars = REQUIRED.to_vec();
+            vars.push(("ALERT_DESTINATIONS_FILE", path.to_str().unwrap()));
+            load(&vars)
+        };
+
+        let config = with_file(
+            r#"{"destinations": [
+                {"name": "slack", "url": "https://hooks.slack.com/services/x", "format": "slack"},
+                {"name": "pd", "url": "https://events.pagerduty.com/v2/enqueue",
+                 "kinds": ["payment_anomaly"], "format": "pagerduty",
+                 "vars": {"routing_key": "abc"}}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(config.alert_destinations.len(), 2);
+        assert!(!config.alert_destinations[1].accepts("reconciliation_summary"));
+
+        let duplicate = r#"{"destinations": [
+            {"name": "a", "url": "https://x.example.com"},
+            {"name": "a", "url": "https://y.example.com"}
+        ]}"#;
+        for bad in [duplicate, r#"{"destinations": [{"name": "a"}]}"#, "[]"] {
+            assert!(matches!(
+                with_file(bad),
+                Err(ConfigError::Invalid {
+                    var: "ALERT_DESTINATIONS_FILE",
+                    ..
+                })
+            ));
+        }
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn missing_and_invalid_values_are_reported() {
+        assert_eq!(
+            load(&REQUIRED[1..]).unwrap_err(),
+            ConfigError::Missing("DATABASE_URL")
+        );
+
+        let with = |extra: (&'static str, &'static str)| {
+            let mut vars = REQUIRED.to_vec();
+            vars.push(extra);
+            load(&vars).unwrap_err()
+        };
+        assert!(matches!(
+            with(("LISTEN_ADDR", "localhost")),
+            ConfigError::Invalid {
+                var: "LISTEN_ADDR",
+                ..
+            }
+        ));
+        assert!(matches!(
+            with(("ROLLUP_INTERVAL_SECS", "0")),
+            ConfigError::Invalid {
+                var: "ROLLUP_INTERVAL_SECS",
+                ..
+            }
+        ));
+        assert!(matches!(
+            with(("WEBHOOK_BURST_PER_IP", "0")),
+            ConfigError::Invalid {
+                var: "WEBHOOK_BURST_PER_IP",
+                ..
+            }
+        ));
+        assert!(matches!(
+            load(&[
+                REQUIRED[0],
+                REQUIRED[1],
+                ("STRIPE_SECRET_KEY", "sk_live_x"),
+                ("DEV_ROUTES", "true"),
+            ])
+            .unwrap_err(),
+            ConfigError::Invalid {
+                var: "DEV_ROUTES",
+                ..
+            }
+        ));
+        assert!(matches!(
+            with(("SETTINGS_ENCRYPTION_KEY", "c0ffee")),
+            ConfigError::Invalid {
+                var: "SETTINGS_ENCRYPTION_KEY",
+                ..
+            }
+        ));
+        assert!(matches!(
+            with(("RECEIPT_EMAIL_STORAGE", "hashed")),
+            ConfigError::Invalid {
+                var: "RECEIPT_EMAIL_STORAGE",
+                ..
+            }
+        ));
+        assert!(matches!(
+            with(("STRIPE_EVENT_ROUTES", "invoice.*=ignore,charge.*")),
+            ConfigError::Invalid {
+                var: "STRIPE_EVENT_ROUTES",
+                ..
+            }
+        ));
+        assert!(matches!(
+            with(("SCHEDULED_EXPORTS", "payments,refunds")),
+            ConfigError::Invalid {
+                var: "SCHEDULED_EXPORTS",
+                ..
+            }
+        ));
+        assert!(matches!(
+            with(("RETENTION_AUDIT_LOG_DAYS", "3")),
+            ConfigError::Invalid {
+                var: "RETENTION_AUDIT_LOG_DAYS",
+                ..
+            }
+        ));
+        assert_eq!(
+            with(("EXPORT_S3_BUCKET", "finance")),
+            ConfigError::Missing("EXPORT_S3_ACCESS_KEY_ID")
+        );
+        assert!(matches!(
+            with(("PAYPAL_CLIENT_ID", "id")),
+            ConfigError::Invalid {
+                var: "PAYPAL_CLIENT_ID",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn the_bucket_secret_stays_out_of_debug_output() {
+        let mut vars = REQUIRED.to_vec();
+        vars.extend([
+            ("EXPORT_S3_BUCKET", "finance"),
+            ("EXPORT_S3_ACCESS_KEY_ID", "AKIAEXAMPLE"),
+            ("EXPORT_S3_SECRET_ACCESS_KEY", "s3cr3t-key"),
+        ]);
+        let shown = format!("{:?}", load(&vars).unwrap().exports.s3.unwrap());
+        assert!(shown.contains("AKIAEXAMPLE") && !shown.contains("s3cr3t-key"));
+    }
+}
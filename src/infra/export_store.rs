@@ -0,0 +1,249 @@
+use {
+    crate::{error::PipelineError, infra::config::ExportConfig, infra::config::S3Config},
+    chrono::{DateTime, Utc},
+    hmac::{Hmac, Mac},
+    sha2::{Digest, Sha256},
+    std::path::{Path, PathBuf},
+};
+
+/// Where finished exports go: a local directory, or an S3-compatible
+/// bucket. Exports are written to the directory either way; with a bucket
+/// each file is uploaded from there once complete, then removed.
+#[derive(Clone)]
+pub struct ExportStore {
+    dir: PathBuf,
+    s3: Option<S3Config>,
+    http: reqwest::Client,
+}
+
+impl ExportStore {
+    pub fn new(config: &ExportConfig) -> Self {
+        Self {
+            dir: config.dir.clone(),
+            s3: config.s3.clone(),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Where an export is written while it runs.
+    pub async fn staging_path(&self, file_name: &str) -> Result<PathBuf, PipelineError> {
+        tokio::fs::create_dir_all(&self.dir)
+            .await
+            .map_err(|e| storage(&self.dir, e))?;
+        Ok(self.dir.join(format!("{file_name}.part")))
+    }
+
+    /// Move a complete export from `staged` to its final place and return
+    /// that place: a local path, or `s3://<bucket>/<key>`.
+    pub async fn publish(&self, file_name: &str, staged: &Path) -> Result<String, PipelineError> {
+        let Some(s3) = &self.s3 else {
+            let path = self.dir.join(file_name);
+            tokio::fs::rename(staged, &path)
+                .await
+                .map_err(|e| storage(&path, e))?;
+            return Ok(path.display().to_string());
+        };
+
+        let key = format!("{}{file_name}", s3.prefix);
+        let file = tokio::fs::File::open(staged)
+            .await
+            .map_err(|e| storage(staged, e))?;
+        let len = file.metadata().await.map_err(|e| storage(staged, e))?.len();
+        let url = format!(
+            "{}/{}/{}",
+            s3.endpoint.trim_end_matches('/'),
+            s3.bucket,
+            uri_encode(&key)
+        );
+        let request = self
+            .http
+            .put(&url)
+            .header(reqwest::header::CONTENT_LENGTH, len)
+            .body(file)
+            .build()
+            .map_err(|e| PipelineError::Storage(format!("{url}: {e}")))?;
+        let request = sign_s3(request, s3, Utc::now())?;
+        let response = self
+            .http
+            .execute(request)
+            .await
+            .map_err(|e| PipelineError::Storage(format!("{url}: {e}")))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(PipelineError::Storage(format!("{url}: {status}: {body}")));
+        }
+        tokio::fs::remove_file(staged)
+            .await
+            .map_err(|e| storage(staged, e))?;
+        Ok(format!("s3://{}/{key}", s3.bucket))
+    }
+}
+
+fn storage(path: &Path, e: std::io::Error) -> PipelineError {
+    PipelineError::Storage(format!("{}: {e}", path.display()))
+}
+
+/// Sign `request` for S3 with AWS Signature Version 4. The body isn't
+/// hashed (`UNSIGNED-PAYLOAD`), so it can be streamed from disk.
+fn sign_s3(
+    mut request: reqwest::Request,
+    s3: &S3Config,
+    now: DateTime<Utc>,
+) -> Result<reqwest::Request, PipelineError> {
+    let url = request.url();
+    let host = match url.port() {
+        Some(port) => format!("{}:{port}", url.host_str().unwrap_or_default()),
+        None => url.host_str().unwrap_or_default().to_string(),
+    };
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let headers = [
+        ("host", host.as_str()),
+        ("x-amz-content-sha256", "UNSIGNED-PAYLOAD"),
+        ("x-amz-date", amz_date.as_str()),
+    ];
+    let authorization = authorization(
+        &SigningParams {
+            access_key_id: &s3.access_key_id,
+            secret_access_key: &s3.secret_access_key,
+            region: &s3.region,
+            service: "s3",
+        },
+        request.method().as_str(),
+        url.path(),
+        url.query().unwrap_or_default(),
+        &headers,
+        "UNSIGNED-PAYLOAD",
+        now,
+    );
+    for (name, value) in [
+        ("x-amz-content-sha256", "UNSIGNED-PAYLOAD"),
+        ("x-amz-date", amz_date.as_str()),
+        ("authorization", authorization.as_str()),
+    ] {
+        let value = value
+            .parse()
+            .map_err(|_| PipelineError::Storage(format!("invalid {name} header")))?;
+        request.headers_mut().insert(name, value);
+    }
+    Ok(request)
+}
+
+struct SigningParams<'a> {
+    access_key_id: &'a str,
+    secret_access_key: &'a str,
+    region: &'a str,
+    service: &'a str,
+}
+
+/// The `Authorization` header value for a request. `headers` are the
+/// signed ones: lowercase names, sorted, values trimmed.
+fn authorization(
+    params: &SigningParams<'_>,
+    method: &str,
+    path: &str,
+    query: &str,
+    headers: &[(&str, &str)],
+    payload_hash: &str,
+    now: DateTime<Utc>,
+) -> String {
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+    let scope = format!("{date}/{}/{}/aws4_request", params.region, params.service);
+
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{name}:{value}\n"))
+        .collect();
+    let signed_headers = headers
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(";");
+    let canonical_request =
+        format!("{method}\n{path}\n{query}\n{canonical_headers}\n{signed_headers}\n{payload_hash}");
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+        hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let key = [date.as_str(), params.region, params.service, "aws4_request"]
+        .iter()
+        .fold(
+            format!("AWS4{}", params.secret_access_key).into_bytes(),
+            |key, part| hmac(&key, part.as_bytes()),
+        );
+    format!(
+        "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={}",
+        params.access_key_id,
+        hex::encode(hmac(&key, string_to_sign.as_bytes()))
+    )
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    // HMAC takes keys of any length.
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Percent-encode an object key for the URL path, keeping `/`.
+fn uri_encode(key: &str) -> String {
+    key.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signature_matches_the_aws_example() {
+        // The IAM ListUsers example from the Signature Version 4 docs.
+        let params = SigningParams {
+            access_key_id: "AKIDEXAMPLE",
+            secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+            region: "us-east-1",
+            service: "iam",
+        };
+        let now = DateTime::parse_from_rfc3339("2015-08-30T12:36:00Z")
+            .unwrap()
+            .to_utc();
+        let authorization = authorization(
+            &params,
+            "GET",
+            "/",
+            "Action=ListUsers&Version=2010-05-08",
+            &[
+                (
+                    "content-type",
+                    "application/x-www-form-urlencoded; charset=utf-8",
+                ),
+                ("host", "iam.amazonaws.com"),
+                ("x-amz-date", "20150830T123600Z"),
+            ],
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+            now,
+        );
+        assert_eq!(
+            authorization,
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/iam/aws4_request, \
+             SignedHeaders=content-type;host;x-amz-date, \
+             Signature=5d672d79c15b13162d9279b0855cfba6789a8edb4c82c400e06b5924a6f2b5d7"
+        );
+    }
+
+    #[test]
+    fn keys_are_encoded_except_slashes() {
+        assert_eq!(
+            uri_encode("finance/payments 2026+x.csv"),
+            "finance/payments%202026%2Bx.csv"
+        );
+    }
+}
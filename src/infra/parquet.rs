@@ -0,0 +1,115 @@
+//! Parquet encoding of export rows. Rows are held until a row group is
+//! full, then encoded and handed back as bytes, so a Parquet export is
+//! written piece by piece like a CSV one, in memory bounded by a group.
+
+use {
+    crate::{domain::export::ExportRow, error::PipelineError},
+    parquet::{
+        basic::{Compression, ZstdLevel},
+        data_type::{ByteArray, ByteArrayType, Int64Type},
+        errors::ParquetError,
+        file::{properties::WriterProperties, writer::SerializedFileWriter},
+        schema::parser::parse_message_type,
+    },
+    std::sync::Arc,
+};
+
+/// Rows per row group.
+const ROW_GROUP_ROWS: usize = 8192;
+
+/// One Parquet file in the making. Columns are those of the row's CSV
+/// header: 64-bit integers for `ExportRow::PARQUET_INT64`, strings for the
+/// rest, and null where the CSV field is empty.
+pub struct ParquetEncoder {
+    writer: SerializedFileWriter<Vec<u8>>,
+    int64: &'static [usize],
+    /// Fields of the rows not yet encoded, by column.
+    pending: Vec<Vec<String>>,
+    rows: usize,
+}
+
+impl ParquetEncoder {
+    pub fn new<R: ExportRow>() -> Result<Self, PipelineError> {
+        let names: Vec<_> = R::CSV_HEADER.trim_end().split(',').collect();
+        let fields: String = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| match R::PARQUET_INT64.contains(&i) {
+                true => format!("optional int64 {name}; "),
+                false => format!("optional binary {name} (UTF8); "),
+            })
+            .collect();
+        let schema =
+            parse_message_type(&format!("message export {{ {fields}}}")).map_err(storage)?;
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::ZSTD(ZstdLevel::default()))
+            .build();
+        let writer = SerializedFileWriter::new(Vec::new(), Arc::new(schema), Arc::new(properties))
+            .map_err(storage)?;
+        Ok(Self {
+            writer,
+            int64: R::PARQUET_INT64,
+            pending: vec![Vec::with_capacity(ROW_GROUP_ROWS); names.len()],
+            rows: 0,
+        })
+    }
+
+    /// Adds `row`, appending to `buf` the bytes of the row group it fills,
+    /// if it does.
+    pub fn push<R: ExportRow>(&mut self, buf: &mut Vec<u8>, row: &R) -> Result<(), PipelineError> {
+        for (column, field) in self.pending.iter_mut().zip(row.csv_fields()) {
+            column.push(field);
+        }
+        self.rows += 1;
+        if self.rows == ROW_GROUP_ROWS {
+            self.write_group().map_err(storage)?;
+            buf.append(self.writer.inner_mut());
+        }
+        Ok(())
+    }
+
+    /// Appends the last row group and the footer to `buf`.
+    pub fn finish(mut self, buf: &mut Vec<u8>) -> Result<(), PipelineError> {
+        self.write_group().map_err(storage)?;
+        buf.append(&mut self.writer.into_inner().map_err(storage)?);
+        Ok(())
+    }
+
+    fn write_group(&mut self) -> Result<(), ParquetError> {
+        if self.rows == 0 {
+            return Ok(());
+        }
+        let mut group = self.writer.next_row_group()?;
+        let mut index = 0;
+        while let Some(mut column) = group.next_column()? {
+            let fields = std::mem::take(&mut self.pending[index]);
+            if self.int64.contains(&index) {
+                let values: Vec<Option<i64>> = fields.iter().map(|f| f.parse().ok()).collect();
+                let levels: Vec<i16> = values.iter().map(|v| i16::from(v.is_some())).collect();
+                let values: Vec<i64> = values.into_iter().flatten().collect();
+                column
+                    .typed::<Int64Type>()
+                    .write_batch(&values, Some(&levels), None)?;
+            } else {
+                let levels: Vec<i16> = fields.iter().map(|f| i16::from(!f.is_empty())).collect();
+                let values: Vec<ByteArray> = fields
+                    .into_iter()
+                    .filter(|f| !f.is_empty())
+                    .map(|f| ByteArray::from(f.into_bytes()))
+                    .collect();
+                column
+                    .typed::<ByteArrayType>()
+                    .write_batch(&values, Some(&levels), None)?;
+            }
+            column.close()?;
+            index += 1;
+        }
+        group.close()?;
+        self.rows = 0;
+        Ok(())
+    }
+}
+
+fn storage(e: ParquetError) -> PipelineError {
+    PipelineError::Storage(format!("parquet: {e}"))
+}
@@ -1,3 +1,34 @@
+pub mod api_key_repo;
+pub mod audit_relay_repo;
 pub mod audit_repo;
+pub mod backfill_repo;
+pub mod budget_repo;
+pub mod capture_repo;
+pub mod checkout_repo;
+pub mod config_repo;
+pub mod currency_terms_repo;
+pub mod event_gap_repo;
+pub mod export_repo;
+pub mod feed_repo;
+pub mod followup_repo;
+pub mod hook_repo;
 pub mod job_repo;
+pub mod ledger_repo;
+pub mod lock_repo;
+pub mod manual_repo;
+pub mod outbox_repo;
+pub mod payload_codec;
 pub mod payment_repo;
+pub mod payout_repo;
+pub mod period_repo;
+pub mod pressure;
+pub mod reconciliation_repo;
+pub mod replay_repo;
+pub mod report_repo;
+pub mod retention_repo;
+pub mod rollup_repo;
+pub mod settings_repo;
+pub mod shadow_repo;
+pub mod tag_repo;
+pub mod vector_repo;
+pub mod webhook_repo;
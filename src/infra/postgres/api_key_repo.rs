@@ -0,0 +1,163 @@
+use {
+    crate::{
+        domain::id::TenantId,
+        error::PipelineError,
+        infra::auth::{ApiKey, Role, Scope},
+    },
+    chrono::{DateTime, Utc},
+    sqlx::PgPool,
+    uuid::Uuid,
+};
+
+struct ApiKeyRow {
+    id: Uuid,
+    name: String,
+    prefix: String,
+    scopes: Vec<String>,
+    role: String,
+    tenant_id: Option<String>,
+    created_by: String,
+    created_at: DateTime<Utc>,
+    last_used_at: Option<DateTime<Utc>>,
+    revoked_at: Option<DateTime<Utc>>,
+}
+
+impl TryFrom<ApiKeyRow> for ApiKey {
+    type Error = PipelineError;
+
+    fn try_from(row: ApiKeyRow) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: row.id,
+            name: row.name,
+            prefix: row.prefix,
+            scopes: row
+                .scopes
+                .iter()
+                .map(|s| s.parse())
+                .collect::<Result<_, _>>()?,
+            role: row.role.parse()?,
+            tenant_id: row.tenant_id.map(TenantId::new).transpose()?,
+            created_by: row.created_by,
+            created_at: row.created_at,
+            last_used_at: row.last_used_at,
+            revoked_at: row.revoked_at,
+        })
+    }
+}
+
+/// Store a new key; `None` if one with the same name exists.
+#[allow(clippy::too_many_arguments)]
+pub async fn insert(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    id: Uuid,
+    name: &str,
+    prefix: &str,
+    key_hash: &str,
+    scopes: &[Scope],
+    role: Role,
+    tenant_id: Option<&str>,
+    created_by: &str,
+) -> Result<Option<ApiKey>, PipelineError> {
+    let scopes: Vec<String> = scopes.iter().map(|s| s.as_str().to_string()).collect();
+    let row = sqlx::query_as!(
+        ApiKeyRow,
+        r#"
+        INSERT INTO api_keys (id, name, prefix, key_hash, scopes, role, created_by, tenant_id)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        ON CONFLICT (name) DO NOTHING
+        RETURNING id, name, prefix, scopes, role, tenant_id, created_by, created_at, last_used_at,
+                  revoked_at
+        "#,
+        id,
+        name,
+        prefix,
+        key_hash,
+        &scopes,
+        role.as_str(),
+        created_by,
+        tenant_id,
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+    row.map(ApiKey::try_from).transpose()
+}
+
+pub async fn find_by_hash(pool: &PgPool, key_hash: &str) -> Result<Option<ApiKey>, PipelineError> {
+    let row = sqlx::query_as!(
+        ApiKeyRow,
+        r#"
+        SELECT id, name, prefix, scopes, role, tenant_id, created_by, created_at, last_used_at,
+               revoked_at
+        FROM api_keys
+        WHERE key_hash = $1
+        "#,
+        key_hash,
+    )
+    .fetch_optional(pool)
+    .await?;
+    row.map(ApiKey::try_from).transpose()
+}
+
+pub async fn get(pool: &PgPool, id: Uuid) -> Result<Option<ApiKey>, PipelineError> {
+    let row = sqlx::query_as!(
+        ApiKeyRow,
+        r#"
+        SELECT id, name, prefix, scopes, role, tenant_id, created_by, created_at, last_used_at,
+               revoked_at
+        FROM api_keys
+        WHERE id = $1
+        "#,
+        id,
+    )
+    .fetch_optional(pool)
+    .await?;
+    row.map(ApiKey::try_from).transpose()
+}
+
+/// All keys, revoked ones included, newest first.
+pub async fn list(pool: &PgPool) -> Result<Vec<ApiKey>, PipelineError> {
+    let rows = sqlx::query_as!(
+        ApiKeyRow,
+        r#"
+        SELECT id, name, prefix, scopes, role, tenant_id, created_by, created_at, last_used_at,
+               revoked_at
+        FROM api_keys
+        ORDER BY created_at DESC, id DESC
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+    rows.into_iter().map(ApiKey::try_from).collect()
+}
+
+/// Revoke a live key; `None` if it doesn't exist or already was.
+pub async fn revoke(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    id: Uuid,
+) -> Result<Option<ApiKey>, PipelineError> {
+    let row = sqlx::query_as!(
+        ApiKeyRow,
+        r#"
+        UPDATE api_keys
+        SET revoked_at = now()
+        WHERE id = $1 AND revoked_at IS NULL
+        RETURNING id, name, prefix, scopes, role, tenant_id, created_by, created_at, last_used_at,
+                  revoked_at
+        "#,
+        id,
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+    row.map(ApiKey::try_from).transpose()
+}
+
+pub async fn touch(pool: &PgPool, id: Uuid, at: DateTime<Utc>) -> Result<(), PipelineError> {
+    sqlx::query!(
+        "UPDATE api_keys SET last_used_at = $2 WHERE id = $1",
+        id,
+        at,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,113 @@
+use {
+    crate::{domain::audit::AuditRecord, error::PipelineError},
+    sqlx::PgPool,
+};
+
+/// Start queueing audit entries for the relay. Idempotent; entries written
+/// before this call are not queued.
+pub async fn enable(pool: &PgPool) -> Result<(), PipelineError> {
+    sqlx::query!("INSERT INTO audit_relay_state (id) VALUES (TRUE) ON CONFLICT (id) DO NOTHING")
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Lock up to `limit` queued entries, oldest first. Concurrent relays skip
+/// each other's rows.
+pub async fn claim_batch(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    limit: i64,
+) -> Result<Vec<AuditRecord>, PipelineError> {
+    let rows = sqlx::query_as!(
+        AuditRecord,
+        r#"
+        SELECT a.id, a.entity_type, a.entity_id, a.external_id, a.event_id, a.source,
+               a.tenant_id, a.action, a.actor, a.detail, a.created_at
+        FROM audit_outbox o
+        JOIN audit_log a ON a.id = o.audit_id
+        ORDER BY o.seq
+        LIMIT $1
+        FOR UPDATE OF o SKIP LOCKED
+        "#,
+        limit,
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+    Ok(rows)
+}
+
+/// Drop shipped entries from the outbox and bump the relay counters.
+pub async fn mark_shipped(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ids: &[uuid::Uuid],
+) -> Result<(), PipelineError> {
+    sqlx::query!("DELETE FROM audit_outbox WHERE audit_id = ANY($1)", ids)
+        .execute(&mut **tx)
+        .await?;
+    sqlx::query!(
+        r#"
+        UPDATE audit_relay_state
+        SET shipped_total = shipped_total + $1, last_shipped_at = now()
+        "#,
+        ids.len() as i64,
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Write entries to the audit database. Entries it already has are skipped,
+/// so re-shipping after a crash is harmless.
+pub async fn ship(target: &PgPool, records: &[AuditRecord]) -> Result<u64, PipelineError> {
+    let mut ids = Vec::with_capacity(records.len());
+    let mut entity_types = Vec::with_capacity(records.len());
+    let mut entity_ids = Vec::with_capacity(records.len());
+    let mut external_ids = Vec::with_capacity(records.len());
+    let mut event_ids = Vec::with_capacity(records.len());
+    let mut sources = Vec::with_capacity(records.len());
+    let mut tenant_ids = Vec::with_capacity(records.len());
+    let mut actions = Vec::with_capacity(records.len());
+    let mut actors = Vec::with_capacity(records.len());
+    let mut details = Vec::with_capacity(records.len());
+    let mut created = Vec::with_capacity(records.len());
+    for r in records {
+        ids.push(r.id);
+        entity_types.push(r.entity_type.clone());
+        entity_ids.push(r.entity_id);
+        external_ids.push(r.external_id.clone());
+        event_ids.push(r.event_id.clone());
+        sources.push(r.source.clone());
+        tenant_ids.push(r.tenant_id.clone());
+        actions.push(r.action.clone());
+        actors.push(r.actor.clone());
+        details.push(r.detail.clone());
+        created.push(r.created_at);
+    }
+
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO audit_log
+            (id, entity_type, entity_id, external_id, event_id, source, tenant_id, action,
+             actor, detail, created_at)
+        SELECT * FROM UNNEST(
+            $1::uuid[], $2::text[], $3::uuid[], $4::text[], $5::text[], $6::text[],
+            $7::text[], $8::text[], $9::text[], $10::jsonb[], $11::timestamptz[]
+        )
+        ON CONFLICT DO NOTHING
+        "#,
+        &ids,
+        &entity_types,
+        &entity_ids as &[Option<uuid::Uuid>],
+        &external_ids as &[Option<String>],
+        &event_ids,
+        &sources as &[Option<String>],
+        &tenant_ids as &[Option<String>],
+        &actions,
+        &actors,
+        &details,
+        &created,
+    )
+    .execute(target)
+    .await?;
+    Ok(result.rows_affected())
+}
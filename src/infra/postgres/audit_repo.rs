@@ -1,4 +1,27 @@
-use {crate::domain::audit::NewAuditEntry, crate::domain::error::PipelineError};
+use {
+    crate::domain::audit::{
+        AuditEntryView, AuditFilters, AuditLogFilters, AuditRecord, NewAuditEntry,
+    },
+    crate::domain::payment::AuditSummary,
+    crate::domain::replay::SimulatedEntry,
+    crate::domain::timeline::TimelineEntry,
+    crate::error::PipelineError,
+    chrono::{DateTime, Utc},
+    futures_util::{Stream, TryStreamExt},
+    sqlx::{PgPool, postgres::PgListener},
+    uuid::Uuid,
+};
+
+/// Channel notified (by the `audit_log_notify` trigger) with every new
+/// entry's id, external id, action and actor.
+pub const AUDIT_CHANNEL: &str = "audit_log";
+
+/// A dedicated connection listening on `AUDIT_CHANNEL`.
+pub async fn listen(pool: &PgPool) -> Result<PgListener, PipelineError> {
+    let mut listener = PgListener::connect_with(pool).await?;
+    listener.listen(AUDIT_CHANNEL).await?;
+    Ok(listener)
+}
 
 pub async fn insert_audit_entry(
     tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
@@ -6,15 +29,19 @@ pub async fn insert_audit_entry(
 ) -> Result<bool, PipelineError> {
     let result: sqlx::postgres::PgQueryResult = sqlx::query!(
         r#"
-        INSERT INTO audit_log (id, entity_type, entity_id, external_id, event_id, action, actor, detail)
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
-        ON CONFLICT (event_id) DO NOTHING
+        INSERT INTO audit_log
+            (id, entity_type, entity_id, external_id, event_id, source, tenant_id, action,
+             actor, detail)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+        ON CONFLICT (event_id, source, tenant_id) DO NOTHING
         "#,
         entry.id,
         &entry.entity_type,
         entry.entity_id,
         entry.external_id.as_deref(),
         &entry.event_id,
+        entry.source.as_deref(),
+        entry.tenant_id.as_deref(),
         &entry.action,
         &entry.actor,
         &entry.detail,
@@ -24,3 +51,249 @@ pub async fn insert_audit_entry(
 
     Ok(result.rows_affected() > 0)
 }
+
+/// The entries `tx` has written so far against `external_ids`, oldest
+/// first: what a dry run recorded before it rolls back.
+pub async fn written_in_transaction(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    external_ids: &[&str],
+) -> Result<Vec<SimulatedEntry>, PipelineError> {
+    let external_ids: Vec<String> = external_ids.iter().map(|id| id.to_string()).collect();
+    let rows = sqlx::query!(
+        r#"
+        SELECT event_id AS "event_id!", action, detail
+        FROM audit_log
+        WHERE external_id = ANY($1) AND xmin = pg_current_xact_id()::xid
+        ORDER BY id
+        "#,
+        &external_ids,
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| SimulatedEntry {
+            event_id: r.event_id,
+            action: r.action,
+            detail: r.detail,
+        })
+        .collect())
+}
+
+/// Entry count plus the most recent entry for one payment.
+pub async fn get_audit_summary(
+    pool: &PgPool,
+    external_id: &str,
+) -> Result<AuditSummary, PipelineError> {
+    let summary = sqlx::query_as!(
+        AuditSummary,
+        r#"
+        SELECT
+            (SELECT COUNT(*) FROM audit_log WHERE external_id = $1) AS "entries!",
+            last.action AS "last_action?",
+            last.actor AS "last_actor?",
+            last.event_id AS "last_event_id?",
+            last.created_at AS "last_at?"
+        FROM (SELECT 1) AS one
+        LEFT JOIN LATERAL (
+            SELECT action, actor, event_id, created_at
+            FROM audit_log
+            WHERE external_id = $1
+            ORDER BY created_at DESC, id DESC
+            LIMIT 1
+        ) AS last ON TRUE
+        "#,
+        external_id,
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(summary)
+}
+
+/// Audit entries for one payment, oldest first.
+pub async fn get_audit_entries(
+    pool: &PgPool,
+    external_id: &str,
+    filters: AuditFilters,
+) -> Result<Vec<AuditEntryView>, PipelineError> {
+    let limit = filters.limit.expect("limit must be set by service layer") as i64;
+    let rows = sqlx::query_as!(
+        AuditEntryView,
+        r#"
+        SELECT event_id, action, actor, detail, created_at
+        FROM audit_log
+        WHERE external_id = $1
+            AND ($2::text IS NULL OR action = $2)
+        ORDER BY created_at, id
+        LIMIT $3 OFFSET $4
+        "#,
+        external_id,
+        filters.action,
+        limit,
+        filters.offset,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// A payment's audit trail as timeline entries, oldest first.
+pub async fn get_audit_timeline(
+    pool: &PgPool,
+    external_id: &str,
+    limit: i64,
+) -> Result<Vec<TimelineEntry>, PipelineError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT event_id, action, actor, detail, created_at
+        FROM audit_log
+        WHERE external_id = $1
+        ORDER BY created_at, id
+        LIMIT $2
+        "#,
+        external_id,
+        limit,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|r| TimelineEntry::Audit {
+            at: r.created_at,
+            event_id: r.event_id,
+            action: r.action,
+            actor: r.actor,
+            detail: r.detail,
+        })
+        .collect())
+}
+
+pub async fn get_audit_record(
+    pool: &PgPool,
+    id: Uuid,
+) -> Result<Option<AuditRecord>, PipelineError> {
+    let record = sqlx::query_as!(
+        AuditRecord,
+        r#"
+        SELECT id, entity_type, entity_id, external_id, event_id, source, tenant_id, action,
+               actor, detail, created_at
+        FROM audit_log
+        WHERE id = $1
+        "#,
+        id,
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(record)
+}
+
+/// Entries matching `filters`, newest first, after `filters.cursor`.
+pub async fn list_audit_records(
+    pool: &PgPool,
+    filters: &AuditLogFilters,
+) -> Result<Vec<AuditRecord>, PipelineError> {
+    let limit = filters.limit.expect("limit must be set by service layer") as i64;
+    let (after_ts, after_id) = filters
+        .cursor
+        .as_ref()
+        .map(|c| (c.created_at, c.id))
+        .unzip();
+    let rows = sqlx::query_as!(
+        AuditRecord,
+        r#"
+        SELECT id, entity_type, entity_id, external_id, event_id, source, tenant_id, action,
+               actor, detail, created_at
+        FROM audit_log
+        WHERE ($1::text IS NULL OR external_id = $1)
+            AND ($2::text IS NULL OR source = $2)
+            AND ($3::text IS NULL OR action = $3)
+            AND ($4::text IS NULL OR starts_with(actor, $4))
+            AND (NOT $5 OR detail->>'anomaly' = 'true')
+            AND ($6::timestamptz IS NULL OR (created_at, id) < ($6, $7::uuid))
+        ORDER BY created_at DESC, id DESC
+        LIMIT $8
+        "#,
+        filters.external_id,
+        filters.source,
+        filters.action,
+        filters.actor,
+        filters.anomalies_only,
+        after_ts,
+        after_id,
+        limit,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// `list_audit_records` for each of `external_ids` at once, at most
+/// `filters.limit` entries each; `filters.external_id` is ignored. Grouped
+/// by external id, each newest first.
+pub async fn list_audit_records_of(
+    pool: &PgPool,
+    external_ids: &[String],
+    filters: &AuditLogFilters,
+) -> Result<Vec<AuditRecord>, PipelineError> {
+    let limit = filters.limit.expect("limit must be set by service layer") as i64;
+    let (after_ts, after_id) = filters
+        .cursor
+        .as_ref()
+        .map(|c| (c.created_at, c.id))
+        .unzip();
+    let rows = sqlx::query_as!(
+        AuditRecord,
+        r#"
+        SELECT a.id AS "id!", a.entity_type AS "entity_type!", a.entity_id, a.external_id,
+               a.event_id AS "event_id!", a.source, a.tenant_id, a.action AS "action!",
+               a.actor AS "actor!", a.detail AS "detail!", a.created_at AS "created_at!"
+        FROM unnest($1::text[]) AS k(external_id)
+        CROSS JOIN LATERAL (
+            SELECT l.* FROM audit_log l
+            WHERE l.external_id = k.external_id
+                AND ($2::text IS NULL OR l.source = $2)
+                AND ($3::text IS NULL OR l.action = $3)
+                AND ($4::text IS NULL OR starts_with(l.actor, $4))
+                AND (NOT $5 OR l.detail->>'anomaly' = 'true')
+                AND ($6::timestamptz IS NULL OR (l.created_at, l.id) < ($6, $7::uuid))
+            ORDER BY l.created_at DESC, l.id DESC
+            LIMIT $8
+        ) a
+        ORDER BY a.external_id, a.created_at DESC, a.id DESC
+        "#,
+        external_ids,
+        filters.source,
+        filters.action,
+        filters.actor,
+        filters.anomalies_only,
+        after_ts,
+        after_id,
+        limit,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Every entry written in `[since, until]`, oldest first, off a cursor.
+pub fn stream_audit_records<'a>(
+    pool: &'a PgPool,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> impl Stream<Item = Result<AuditRecord, PipelineError>> + Send + 'a {
+    sqlx::query_as!(
+        AuditRecord,
+        r#"
+        SELECT id, entity_type, entity_id, external_id, event_id, source, tenant_id, action,
+               actor, detail, created_at
+        FROM audit_log
+        WHERE created_at >= $1 AND created_at <= $2
+        ORDER BY created_at, id
+        "#,
+        since,
+        until,
+    )
+    .fetch(pool)
+    .map_err(PipelineError::from)
+}
@@ -0,0 +1,142 @@
+use {
+    crate::domain::backfill::{BackfillCounts, BackfillRunView},
+    crate::error::PipelineError,
+    chrono::{DateTime, Utc},
+    sqlx::PgPool,
+    uuid::Uuid,
+};
+
+pub async fn create_run(
+    pool: &PgPool,
+    source: &str,
+    window_start: DateTime<Utc>,
+    window_end: DateTime<Utc>,
+) -> Result<Uuid, PipelineError> {
+    let id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO backfill_runs (source, window_start, window_end)
+        VALUES ($1, $2, $3)
+        RETURNING id
+        "#,
+        source,
+        window_start,
+        window_end,
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(id)
+}
+
+/// Take over a failed run, or a running one that has not checkpointed for
+/// `stalled_after_secs` (its process died). Returns the run if claimed.
+pub async fn claim_for_resume(
+    pool: &PgPool,
+    id: Uuid,
+    stalled_after_secs: f64,
+) -> Result<Option<BackfillRunView>, PipelineError> {
+    let run = sqlx::query_as!(
+        BackfillRunView,
+        r#"
+        UPDATE backfill_runs
+        SET status = 'running', error = NULL, finished_at = NULL, updated_at = now()
+        WHERE id = $1
+            AND (status = 'failed'
+                 OR (status = 'running' AND updated_at < now() - make_interval(secs => $2)))
+        RETURNING id, source, window_start, window_end, status, cursor, pages, created,
+                  updated, unchanged, skipped, error, started_at, updated_at, finished_at
+        "#,
+        id,
+        stalled_after_secs,
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(run)
+}
+
+/// Record a finished page: the cursor of the next page plus this page's counts.
+pub async fn save_checkpoint(
+    pool: &PgPool,
+    id: Uuid,
+    cursor: Option<&str>,
+    counts: BackfillCounts,
+) -> Result<(), PipelineError> {
+    sqlx::query!(
+        r#"
+        UPDATE backfill_runs
+        SET cursor = $2, pages = pages + 1, created = created + $3, updated = updated + $4,
+            unchanged = unchanged + $5, skipped = skipped + $6, updated_at = now()
+        WHERE id = $1
+        "#,
+        id,
+        cursor,
+        counts.created,
+        counts.updated,
+        counts.unchanged,
+        counts.skipped,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn complete_run(pool: &PgPool, id: Uuid) -> Result<(), PipelineError> {
+    sqlx::query!(
+        r#"
+        UPDATE backfill_runs
+        SET status = 'completed', updated_at = now(), finished_at = now()
+        WHERE id = $1
+        "#,
+        id,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn fail_run(pool: &PgPool, id: Uuid, error: &str) -> Result<(), PipelineError> {
+    sqlx::query!(
+        r#"
+        UPDATE backfill_runs
+        SET status = 'failed', error = $2, updated_at = now(), finished_at = now()
+        WHERE id = $1
+        "#,
+        id,
+        error,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn get_run(pool: &PgPool, id: Uuid) -> Result<Option<BackfillRunView>, PipelineError> {
+    let run = sqlx::query_as!(
+        BackfillRunView,
+        r#"
+        SELECT id, source, window_start, window_end, status, cursor, pages, created,
+               updated, unchanged, skipped, error, started_at, updated_at, finished_at
+        FROM backfill_runs
+        WHERE id = $1
+        "#,
+        id,
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(run)
+}
+
+pub async fn list_runs(pool: &PgPool, limit: i64) -> Result<Vec<BackfillRunView>, PipelineError> {
+    let runs = sqlx::query_as!(
+        BackfillRunView,
+        r#"
+        SELECT id, source, window_start, window_end, status, cursor, pages, created,
+               updated, unchanged, skipped, error, started_at, updated_at, finished_at
+        FROM backfill_runs
+        ORDER BY started_at DESC
+        LIMIT $1
+        "#,
+        limit,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(runs)
+}
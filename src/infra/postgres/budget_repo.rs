@@ -0,0 +1,233 @@
+use {
+    crate::{
+        domain::budget::{ApiOperation, BudgetLevel, BudgetPeriod, BudgetUsage},
+        error::PipelineError,
+    },
+    chrono::{DateTime, Utc},
+    sqlx::PgPool,
+};
+
+/// Calls counted so far in the current window of one period.
+#[derive(Debug, Clone)]
+pub struct WindowCount {
+    pub period: BudgetPeriod,
+    pub period_start: DateTime<Utc>,
+    pub calls: i64,
+    /// Whether the soft or hard limit was already alerted on this window.
+    pub soft_alerted: bool,
+    pub hard_alerted: bool,
+}
+
+/// Outcome of counting one call.
+#[derive(Debug)]
+pub enum Charge {
+    /// Counted in every window; go ahead.
+    Counted(Vec<WindowCount>),
+    /// The window of this period is at its hard limit; nothing was counted
+    /// but the refusal.
+    Refused(WindowCount),
+}
+
+/// Rows each window's count is spread over, so concurrent calls don't all
+/// wait on one row lock. A hard limit is shared out between them.
+const SHARDS: u64 = 8;
+
+/// Count one `operation` call to `source` in the current window of each
+/// period, unless that would take a window past its hard limit (`None`
+/// for none), in which case no window is counted and the refusal is.
+///
+/// The call is counted on a shard picked at random; a shard at its share
+/// of the hard limit passes the call on to the next, and the call is
+/// refused only once every shard is full, so the limit holds exactly.
+pub async fn charge(
+    pool: &PgPool,
+    source: &str,
+    operation: ApiOperation,
+    hard_limits: &[(BudgetPeriod, Option<u64>)],
+) -> Result<Charge, PipelineError> {
+    let mut tx = pool.begin().await?;
+    let mut counted = Vec::with_capacity(hard_limits.len());
+    for &(period, hard_limit) in hard_limits {
+        let shards = hard_limit.map_or(SHARDS, |l| l.clamp(1, SHARDS));
+        let first = rand::random_range(0..shards);
+        let mut window_start = None;
+        for shard in (0..shards).map(|k| (first + k) % shards) {
+            let share = hard_limit.map_or(i64::MAX, |l| {
+                (l / shards + u64::from(shard < l % shards)) as i64
+            });
+            if share == 0 {
+                continue;
+            }
+            window_start = sqlx::query_scalar!(
+                r#"
+                INSERT INTO provider_api_usage
+                    (source, operation, period, period_start, shard, calls)
+                VALUES ($1, $2, $3, date_trunc($3, now(), 'UTC'), $4, 1)
+                ON CONFLICT (source, operation, period, period_start, shard) DO UPDATE
+                SET calls = provider_api_usage.calls + 1
+                WHERE provider_api_usage.calls < $5
+                RETURNING period_start
+                "#,
+                source,
+                operation.as_str(),
+                period.as_str(),
+                shard as i16,
+                share,
+            )
+            .fetch_optional(&mut *tx)
+            .await?;
+            if window_start.is_some() {
+                break;
+            }
+        }
+        let Some(period_start) = window_start else {
+            tx.rollback().await?;
+            return Ok(Charge::Refused(
+                refuse(pool, source, operation, period).await?,
+            ));
+        };
+        counted.push(window_count(&mut *tx, source, operation, period, period_start).await?);
+    }
+    tx.commit().await?;
+    Ok(Charge::Counted(counted))
+}
+
+async fn refuse(
+    pool: &PgPool,
+    source: &str,
+    operation: ApiOperation,
+    period: BudgetPeriod,
+) -> Result<WindowCount, PipelineError> {
+    let period_start = sqlx::query_scalar!(
+        r#"
+        INSERT INTO provider_api_usage
+            (source, operation, period, period_start, shard, refused)
+        VALUES ($1, $2, $3, date_trunc($3, now(), 'UTC'), 0, 1)
+        ON CONFLICT (source, operation, period, period_start, shard) DO UPDATE
+        SET refused = provider_api_usage.refused + 1
+        RETURNING period_start
+        "#,
+        source,
+        operation.as_str(),
+        period.as_str(),
+    )
+    .fetch_one(pool)
+    .await?;
+    window_count(pool, source, operation, period, period_start).await
+}
+
+/// The window's calls over all its shards, and whether it was alerted on.
+async fn window_count(
+    executor: impl sqlx::PgExecutor<'_>,
+    source: &str,
+    operation: ApiOperation,
+    period: BudgetPeriod,
+    period_start: DateTime<Utc>,
+) -> Result<WindowCount, PipelineError> {
+    let row = sqlx::query!(
+        r#"
+        SELECT COALESCE(sum(calls), 0)::bigint AS "calls!",
+               COALESCE(bool_or(soft_alerted_at IS NOT NULL), false) AS "soft_alerted!",
+               COALESCE(bool_or(hard_alerted_at IS NOT NULL), false) AS "hard_alerted!"
+        FROM provider_api_usage
+        WHERE source = $1 AND operation = $2 AND period = $3 AND period_start = $4
+        "#,
+        source,
+        operation.as_str(),
+        period.as_str(),
+        period_start,
+    )
+    .fetch_one(executor)
+    .await?;
+    Ok(WindowCount {
+        period,
+        period_start,
+        calls: row.calls,
+        soft_alerted: row.soft_alerted,
+        hard_alerted: row.hard_alerted,
+    })
+}
+
+/// Note that the `level` limit of this window was alerted on, on its
+/// shard 0. `false` if it already was, here or by another replica.
+pub async fn mark_alerted(
+    pool: &PgPool,
+    source: &str,
+    operation: ApiOperation,
+    window: &WindowCount,
+    level: BudgetLevel,
+) -> Result<bool, PipelineError> {
+    let (operation, period) = (operation.as_str(), window.period.as_str());
+    let marked = match level {
+        BudgetLevel::Soft => {
+            sqlx::query!(
+                r#"
+                INSERT INTO provider_api_usage
+                    (source, operation, period, period_start, shard, soft_alerted_at)
+                VALUES ($1, $2, $3, $4, 0, now())
+                ON CONFLICT (source, operation, period, period_start, shard) DO UPDATE
+                SET soft_alerted_at = now()
+                WHERE provider_api_usage.soft_alerted_at IS NULL
+                "#,
+                source,
+                operation,
+                period,
+                window.period_start,
+            )
+            .execute(pool)
+            .await?
+        }
+        BudgetLevel::Hard => {
+            sqlx::query!(
+                r#"
+                INSERT INTO provider_api_usage
+                    (source, operation, period, period_start, shard, hard_alerted_at)
+                VALUES ($1, $2, $3, $4, 0, now())
+                ON CONFLICT (source, operation, period, period_start, shard) DO UPDATE
+                SET hard_alerted_at = now()
+                WHERE provider_api_usage.hard_alerted_at IS NULL
+                "#,
+                source,
+                operation,
+                period,
+                window.period_start,
+            )
+            .execute(pool)
+            .await?
+        }
+    };
+    Ok(marked.rows_affected() == 1)
+}
+
+/// Counts of the current hour and day, by source, operation and period.
+/// Limits are left for the caller to fill in.
+pub async fn current_usage(pool: &PgPool) -> Result<Vec<BudgetUsage>, PipelineError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT source, operation, period, period_start,
+               sum(calls)::bigint AS "calls!", sum(refused)::bigint AS "refused!"
+        FROM provider_api_usage
+        WHERE period_start = date_trunc(period, now(), 'UTC')
+        GROUP BY source, operation, period, period_start
+        ORDER BY source, operation, period
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.into_iter()
+        .map(|r| {
+            Ok(BudgetUsage {
+                source: r.source,
+                operation: r.operation.parse()?,
+                period: r.period.parse()?,
+                period_start: r.period_start,
+                calls: r.calls,
+                refused: r.refused,
+                soft_limit: None,
+                hard_limit: None,
+                remaining: None,
+            })
+        })
+        .collect()
+}
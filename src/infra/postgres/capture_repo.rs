@@ -0,0 +1,81 @@
+use {
+    crate::{
+        domain::{
+            capture::{CaptureView, NewCapture},
+            money::Money,
+        },
+        error::PipelineError,
+    },
+    uuid::Uuid,
+};
+
+pub async fn insert_capture(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    id: Uuid,
+    capture: &NewCapture,
+    amount: &Money,
+) -> Result<(), PipelineError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO payment_captures
+            (id, payment_external_id, source, charge_id, amount, currency, captured_total,
+             authorized_amount, event_id, provider_ts)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+        "#,
+        id,
+        capture.payment_external_id.as_str(),
+        &capture.source,
+        &capture.charge_id,
+        amount.amount().cents(),
+        amount.currency().as_str(),
+        capture.captured_total.cents(),
+        capture.authorized.cents(),
+        capture.event_id.as_str(),
+        capture.provider_ts,
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// The charge's latest capture recorded so far and the running total it
+/// took the charge to. Read under the payment's lock.
+pub async fn latest_for_charge(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    charge_id: &str,
+) -> Result<Option<(Uuid, i64)>, PipelineError> {
+    let row = sqlx::query!(
+        r#"
+        SELECT id, captured_total
+        FROM payment_captures
+        WHERE charge_id = $1
+        ORDER BY captured_total DESC, provider_ts DESC, id DESC
+        LIMIT 1
+        "#,
+        charge_id,
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+    Ok(row.map(|r| (r.id, r.captured_total)))
+}
+
+/// The payment's captures in the order they were taken: by the running
+/// total the charge reported, which only grows.
+pub async fn list_captures(
+    executor: impl sqlx::PgExecutor<'_>,
+    payment_external_id: &str,
+) -> Result<Vec<CaptureView>, PipelineError> {
+    let rows = sqlx::query_as!(
+        CaptureView,
+        r#"
+        SELECT charge_id, amount, currency, authorized_amount, event_id, created_at
+        FROM payment_captures
+        WHERE payment_external_id = $1
+        ORDER BY captured_total, provider_ts, id
+        "#,
+        payment_external_id,
+    )
+    .fetch_all(executor)
+    .await?;
+    Ok(rows)
+}
@@ -0,0 +1,285 @@
+use {
+    crate::{
+        domain::{
+            checkout::{
+                AttemptSignature, CheckoutAttempt, FailedAttempt, NewCheckoutAttempt, PriorAttempt,
+                RETRY_WINDOW,
+            },
+            money::Currency,
+            payment::{PaymentFailure, PaymentStatus},
+        },
+        error::PipelineError,
+    },
+    sqlx::PgPool,
+    uuid::Uuid,
+};
+
+/// Serialize attempts sharing a reference or customer: see
+/// [`AttemptSignature::lock_key`].
+pub async fn lock(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    key: &str,
+) -> Result<(), PipelineError> {
+    sqlx::query!("SELECT pg_advisory_xact_lock(hashtextextended($1, 0))", key)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+/// The latest attempt at the checkout named `reference`, if any.
+pub async fn latest_by_reference(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    signature: &AttemptSignature,
+    reference: &str,
+) -> Result<Option<PriorAttempt>, PipelineError> {
+    let prior = sqlx::query_as!(
+        PriorAttempt,
+        r#"
+        SELECT checkout_key, attempt
+        FROM checkout_attempts
+        WHERE tenant_id IS NOT DISTINCT FROM $1 AND checkout_key = $2
+        ORDER BY attempt DESC
+        LIMIT 1
+        "#,
+        signature.tenant_id.as_ref().map(|t| t.as_str()),
+        reference,
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+    Ok(prior)
+}
+
+/// The latest attempt at the checkout a retry by `customer` of `amount`
+/// in `currency` continues: the customer's most recent failed attempt of
+/// that amount within [`RETRY_WINDOW`] before this one, in a checkout no
+/// attempt has yet succeeded in. A failed attempt is one failed, cancelled
+/// or expired, or still pending with the provider's error on it.
+pub async fn latest_retried(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    signature: &AttemptSignature,
+    customer: &str,
+    amount: i64,
+    currency: Currency,
+) -> Result<Option<PriorAttempt>, PipelineError> {
+    let prior = sqlx::query_as!(
+        PriorAttempt,
+        r#"
+        SELECT a.checkout_key,
+               (SELECT max(c.attempt) FROM checkout_attempts c
+                WHERE c.tenant_id IS NOT DISTINCT FROM a.tenant_id
+                  AND c.checkout_key = a.checkout_key) AS "attempt!"
+        FROM checkout_attempts a
+        JOIN payments p ON p.id = a.payment_id
+        WHERE a.tenant_id IS NOT DISTINCT FROM $1
+          AND a.source = $2 AND a.customer = $3
+          AND p.amount = $4 AND p.currency = $5
+          AND a.attempted_at BETWEEN $6 AND $7
+          AND (p.status IN ('failed', 'cancelled', 'expired')
+               OR (p.status = 'pending' AND p.failure_code IS NOT NULL))
+          AND NOT EXISTS (
+              SELECT 1 FROM checkout_attempts s
+              JOIN payments sp ON sp.id = s.payment_id
+              WHERE s.tenant_id IS NOT DISTINCT FROM a.tenant_id
+                AND s.checkout_key = a.checkout_key
+                AND sp.status = 'succeeded')
+        ORDER BY a.attempted_at DESC
+        LIMIT 1
+        "#,
+        signature.tenant_id.as_ref().map(|t| t.as_str()),
+        signature.source,
+        customer,
+        amount,
+        currency.as_str(),
+        signature.attempted_at - RETRY_WINDOW,
+        signature.attempted_at,
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+    Ok(prior)
+}
+
+/// Payment `payment_id`'s attempt, if `customer` made it without a
+/// reference and it is the latest in its checkout. The caller knows it
+/// failed.
+pub async fn failed_attempt(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    payment_id: Uuid,
+    customer: &str,
+) -> Result<Option<FailedAttempt>, PipelineError> {
+    let failed = sqlx::query_as!(
+        FailedAttempt,
+        r#"
+        SELECT a.checkout_key, a.attempt, a.attempted_at
+        FROM checkout_attempts a
+        WHERE a.payment_id = $1 AND a.customer = $2 AND a.reference IS NULL
+          AND NOT EXISTS (
+              SELECT 1 FROM checkout_attempts l
+              WHERE l.tenant_id IS NOT DISTINCT FROM a.tenant_id
+                AND l.checkout_key = a.checkout_key
+                AND l.attempt > a.attempt)
+        "#,
+        payment_id,
+        customer,
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+    Ok(failed)
+}
+
+/// The checkout begun by `customer`'s first attempt of `amount` in
+/// `currency` within [`RETRY_WINDOW`] after `failed`, when that attempt
+/// started a checkout of its own: a retry recorded before `failed` was
+/// known to have failed.
+pub async fn retried_after(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    signature: &AttemptSignature,
+    customer: &str,
+    amount: i64,
+    currency: Currency,
+    failed: &FailedAttempt,
+) -> Result<Option<String>, PipelineError> {
+    let key = sqlx::query_scalar!(
+        r#"
+        SELECT a.checkout_key
+        FROM checkout_attempts a
+        JOIN payments p ON p.id = a.payment_id
+        WHERE a.tenant_id IS NOT DISTINCT FROM $1
+          AND a.source = $2 AND a.customer = $3
+          AND p.amount = $4 AND p.currency = $5
+          AND a.attempted_at BETWEEN $6 AND $7
+          AND a.attempt = 1 AND a.linked_by IS NULL AND a.reference IS NULL
+          AND a.checkout_key <> $8
+        ORDER BY a.attempted_at
+        LIMIT 1
+        "#,
+        signature.tenant_id.as_ref().map(|t| t.as_str()),
+        signature.source,
+        customer,
+        amount,
+        currency.as_str(),
+        failed.attempted_at,
+        failed.attempted_at + RETRY_WINDOW,
+        failed.checkout_key,
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+    Ok(key)
+}
+
+/// Fold the checkout `key` into `failed`'s, its attempts numbered on from
+/// `failed` and its first linked by customer.
+pub async fn fold_into(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    signature: &AttemptSignature,
+    key: &str,
+    failed: &FailedAttempt,
+) -> Result<(), PipelineError> {
+    sqlx::query!(
+        r#"
+        UPDATE checkout_attempts
+        SET checkout_key = $3,
+            attempt = attempt + $4,
+            linked_by = COALESCE(linked_by, 'customer')
+        WHERE tenant_id IS NOT DISTINCT FROM $1 AND checkout_key = $2
+        "#,
+        signature.tenant_id.as_ref().map(|t| t.as_str()),
+        key,
+        failed.checkout_key,
+        failed.attempt,
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+pub async fn insert(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    attempt: &NewCheckoutAttempt,
+) -> Result<(), PipelineError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO checkout_attempts
+            (payment_id, tenant_id, checkout_key, attempt, source, customer,
+             reference, linked_by, attempted_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        "#,
+        attempt.payment_id,
+        attempt.tenant_id.as_ref().map(|t| t.as_str()),
+        attempt.checkout_key,
+        attempt.attempt,
+        attempt.source,
+        attempt.customer,
+        attempt.reference,
+        attempt.linked_by.map(|l| l.as_str()),
+        attempt.attempted_at,
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// The checkout `key`'s attempts in order, each with its payment as it is
+/// now.
+pub async fn list_attempts(
+    pool: &PgPool,
+    tenant_id: Option<&str>,
+    key: &str,
+) -> Result<Vec<CheckoutAttempt>, PipelineError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT a.attempt, p.external_id, a.source, p.status, p.amount, p.currency,
+               a.linked_by, a.attempted_at, p.failure_code, p.decline_code,
+               p.failure_message, p.network_advice_code
+        FROM checkout_attempts a
+        JOIN payments p ON p.id = a.payment_id
+        WHERE a.tenant_id IS NOT DISTINCT FROM $1 AND a.checkout_key = $2
+        ORDER BY a.attempt
+        "#,
+        tenant_id,
+        key,
+    )
+    .fetch_all(pool)
+    .await?;
+    rows.into_iter()
+        .map(|r| {
+            Ok(CheckoutAttempt {
+                attempt: r.attempt,
+                external_id: r.external_id,
+                source: r.source,
+                status: PaymentStatus::try_from(r.status.as_str())?,
+                amount: r.amount,
+                currency: r.currency,
+                linked_by: r.linked_by.as_deref().map(TryFrom::try_from).transpose()?,
+                attempted_at: r.attempted_at,
+                failure: PaymentFailure::from_parts(
+                    r.failure_code,
+                    r.decline_code,
+                    r.failure_message,
+                    r.network_advice_code,
+                ),
+            })
+        })
+        .collect()
+}
+
+/// The checkout `external_id` is an attempt at, so any attempt's id finds
+/// its checkout.
+pub async fn key_of(
+    pool: &PgPool,
+    tenant_id: Option<&str>,
+    external_id: &str,
+) -> Result<Option<String>, PipelineError> {
+    let key = sqlx::query_scalar!(
+        r#"
+        SELECT a.checkout_key
+        FROM checkout_attempts a
+        JOIN payments p ON p.id = a.payment_id
+        WHERE p.external_id = $1 AND a.tenant_id IS NOT DISTINCT FROM $2
+        "#,
+        external_id,
+        tenant_id,
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(key)
+}
@@ -0,0 +1,68 @@
+use {
+    crate::domain::config::{RuntimeConfig, VersionedConfig},
+    crate::error::PipelineError,
+    sqlx::PgPool,
+};
+
+pub async fn get_config(pool: &PgPool) -> Result<Option<VersionedConfig>, PipelineError> {
+    let row = sqlx::query!("SELECT version, config FROM runtime_config")
+        .fetch_optional(pool)
+        .await?;
+    row.map(|r| {
+        Ok(VersionedConfig {
+            version: r.version,
+            config: serde_json::from_value(r.config)?,
+        })
+    })
+    .transpose()
+}
+
+/// Current version only — cheap enough to poll.
+pub async fn get_version(pool: &PgPool) -> Result<Option<i64>, PipelineError> {
+    let version = sqlx::query_scalar!("SELECT version FROM runtime_config")
+        .fetch_optional(pool)
+        .await?;
+    Ok(version)
+}
+
+/// Lock the stored config for the life of `tx`. Serializes concurrent updates.
+pub async fn get_config_for_update(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<Option<VersionedConfig>, PipelineError> {
+    sqlx::query!("SELECT pg_advisory_xact_lock(hashtextextended('runtime_config', 0))")
+        .execute(&mut **tx)
+        .await?;
+    let row = sqlx::query!("SELECT version, config FROM runtime_config")
+        .fetch_optional(&mut **tx)
+        .await?;
+    row.map(|r| {
+        Ok(VersionedConfig {
+            version: r.version,
+            config: serde_json::from_value(r.config)?,
+        })
+    })
+    .transpose()
+}
+
+pub async fn save_config(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    version: i64,
+    config: &RuntimeConfig,
+    updated_by: &str,
+) -> Result<(), PipelineError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO runtime_config (id, version, config, updated_by)
+        VALUES (TRUE, $1, $2, $3)
+        ON CONFLICT (id) DO UPDATE
+        SET version = EXCLUDED.version, config = EXCLUDED.config,
+            updated_by = EXCLUDED.updated_by, updated_at = now()
+        "#,
+        version,
+        serde_json::to_value(config)?,
+        updated_by,
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,241 @@
+use {
+    crate::{
+        domain::currency_terms::{CurrencyTerms, ScheduleTerms},
+        error::PipelineError,
+    },
+    chrono::{DateTime, Utc},
+    sqlx::PgPool,
+    uuid::Uuid,
+};
+
+/// Serialize changes to one source and currency's versions, so two
+/// schedules can't both split the same one.
+pub async fn lock(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    source: &str,
+    currency: &str,
+) -> Result<(), PipelineError> {
+    let key = format!("currency_terms:{source}:{currency}");
+    sqlx::query!("SELECT pg_advisory_xact_lock(hashtextextended($1, 0))", key)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+pub async fn get(pool: &PgPool, id: Uuid) -> Result<Option<CurrencyTerms>, PipelineError> {
+    let terms = sqlx::query_as!(
+        CurrencyTerms,
+        r#"
+        SELECT id, source, currency, accepted, fee_bps, fixed_fee_minor,
+               valid_from, valid_to, reason, created_by, created_at
+        FROM currency_terms
+        WHERE id = $1
+        "#,
+        id,
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(terms)
+}
+
+/// The version starting exactly at `valid_from`, if any.
+pub async fn starting_at(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    source: &str,
+    currency: &str,
+    valid_from: DateTime<Utc>,
+) -> Result<Option<CurrencyTerms>, PipelineError> {
+    let terms = sqlx::query_as!(
+        CurrencyTerms,
+        r#"
+        SELECT id, source, currency, accepted, fee_bps, fixed_fee_minor,
+               valid_from, valid_to, reason, created_by, created_at
+        FROM currency_terms
+        WHERE source = $1 AND currency = $2 AND valid_from = $3
+        "#,
+        source,
+        currency,
+        valid_from,
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+    Ok(terms)
+}
+
+/// The version in force at `at`, if any.
+pub async fn in_force_at(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    source: &str,
+    currency: &str,
+    at: DateTime<Utc>,
+) -> Result<Option<CurrencyTerms>, PipelineError> {
+    let terms = sqlx::query_as!(
+        CurrencyTerms,
+        r#"
+        SELECT id, source, currency, accepted, fee_bps, fixed_fee_minor,
+               valid_from, valid_to, reason, created_by, created_at
+        FROM currency_terms
+        WHERE source = $1 AND currency = $2
+          AND valid_from <= $3 AND (valid_to IS NULL OR valid_to > $3)
+        "#,
+        source,
+        currency,
+        at,
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+    Ok(terms)
+}
+
+/// Where the first version after `at` starts, if one is scheduled.
+pub async fn next_start(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    source: &str,
+    currency: &str,
+    at: DateTime<Utc>,
+) -> Result<Option<DateTime<Utc>>, PipelineError> {
+    let next = sqlx::query_scalar!(
+        r#"
+        SELECT min(valid_from)
+        FROM currency_terms
+        WHERE source = $1 AND currency = $2 AND valid_from > $3
+        "#,
+        source,
+        currency,
+        at,
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+    Ok(next)
+}
+
+pub async fn exists(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    source: &str,
+    currency: &str,
+) -> Result<bool, PipelineError> {
+    let exists = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (SELECT 1 FROM currency_terms WHERE source = $1 AND currency = $2)
+            AS "exists!"
+        "#,
+        source,
+        currency,
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+    Ok(exists)
+}
+
+pub async fn insert(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    request: &ScheduleTerms,
+    valid_to: Option<DateTime<Utc>>,
+    created_by: &str,
+) -> Result<CurrencyTerms, PipelineError> {
+    let terms = sqlx::query_as!(
+        CurrencyTerms,
+        r#"
+        INSERT INTO currency_terms
+            (source, currency, accepted, fee_bps, fixed_fee_minor,
+             valid_from, valid_to, reason, created_by)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        RETURNING id, source, currency, accepted, fee_bps, fixed_fee_minor,
+                  valid_from, valid_to, reason, created_by, created_at
+        "#,
+        request.source,
+        request.currency,
+        request.accepted,
+        request.fee_bps as i32,
+        request.fixed_fee_minor,
+        request.valid_from,
+        valid_to,
+        request.reason,
+        created_by,
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+    Ok(terms)
+}
+
+/// End the version that ended at `old_end` at `new_end` instead: cut
+/// short for a version scheduled after it, or extended over a cancelled
+/// one.
+pub async fn move_end(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    source: &str,
+    currency: &str,
+    old_end: Option<DateTime<Utc>>,
+    new_end: Option<DateTime<Utc>>,
+) -> Result<(), PipelineError> {
+    sqlx::query!(
+        r#"
+        UPDATE currency_terms
+        SET valid_to = $4
+        WHERE source = $1 AND currency = $2 AND valid_to IS NOT DISTINCT FROM $3
+        "#,
+        source,
+        currency,
+        old_end,
+        new_end,
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+pub async fn delete(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    id: Uuid,
+) -> Result<(), PipelineError> {
+    sqlx::query!("DELETE FROM currency_terms WHERE id = $1", id)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+/// Every version, optionally of one source or currency, by source,
+/// currency and start.
+pub async fn list(
+    pool: &PgPool,
+    source: Option<&str>,
+    currency: Option<&str>,
+) -> Result<Vec<CurrencyTerms>, PipelineError> {
+    let terms = sqlx::query_as!(
+        CurrencyTerms,
+        r#"
+        SELECT id, source, currency, accepted, fee_bps, fixed_fee_minor,
+               valid_from, valid_to, reason, created_by, created_at
+        FROM currency_terms
+        WHERE ($1::text IS NULL OR source = $1)
+          AND ($2::text IS NULL OR currency = $2)
+        ORDER BY source, currency, valid_from
+        "#,
+        source,
+        currency,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(terms)
+}
+
+/// The versions in force at `at`, one per source and currency at most.
+pub async fn list_in_force(
+    pool: &PgPool,
+    at: DateTime<Utc>,
+) -> Result<Vec<CurrencyTerms>, PipelineError> {
+    let terms = sqlx::query_as!(
+        CurrencyTerms,
+        r#"
+        SELECT id, source, currency, accepted, fee_bps, fixed_fee_minor,
+               valid_from, valid_to, reason, created_by, created_at
+        FROM currency_terms
+        WHERE valid_from <= $1 AND (valid_to IS NULL OR valid_to > $1)
+        ORDER BY source, currency
+        "#,
+        at,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(terms)
+}
@@ -0,0 +1,156 @@
+use {
+    crate::domain::{
+        event_gap::{EventGapView, GapKind, ObservedLifecycle},
+        payment::{PaymentDirection, PaymentStatus},
+    },
+    crate::error::PipelineError,
+    chrono::{DateTime, Utc},
+    sqlx::PgPool,
+    uuid::Uuid,
+};
+
+/// Payments from `sources` created since `since`, each with the webhook
+/// events received for it. Payments known only from backfills or refetches
+/// have no lifecycle to judge and are left out.
+pub async fn load_lifecycles(
+    pool: &PgPool,
+    sources: &[&str],
+    since: DateTime<Utc>,
+) -> Result<Vec<ObservedLifecycle>, PipelineError> {
+    let sources: Vec<String> = sources.iter().map(|s| s.to_string()).collect();
+    let rows = sqlx::query!(
+        r#"
+        SELECT p.external_id, p.source, p.tenant_id, p.direction, p.status,
+               array_agg(pe.event_type ORDER BY pe.received_at) AS "event_types!",
+               max(pe.received_at) AS "last_event_at!"
+        FROM payments p
+        JOIN provider_events pe
+            ON pe.object_id = p.external_id
+            AND pe.event_type NOT LIKE 'backfill.%'
+            AND pe.event_type <> 'gap.refetch'
+        WHERE p.source = ANY($1) AND p.created_at >= $2
+        GROUP BY p.id
+        "#,
+        &sources,
+        since,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.into_iter()
+        .map(|r| {
+            Ok(ObservedLifecycle {
+                external_id: r.external_id,
+                source: r.source,
+                tenant_id: r.tenant_id,
+                direction: PaymentDirection::try_from(r.direction.as_str())?,
+                status: PaymentStatus::try_from(r.status.as_str())?,
+                event_types: r.event_types,
+                last_event_at: r.last_event_at,
+            })
+        })
+        .collect()
+}
+
+/// An open gap row after `open_gap`.
+pub struct OpenGap {
+    pub id: Uuid,
+    /// Newly inserted, or reopened after having been resolved.
+    pub opened: bool,
+    pub refetch_requested: bool,
+}
+
+/// Record `kind` as open for the payment, keeping the original detection
+/// time if it already was.
+pub async fn open_gap(
+    pool: &PgPool,
+    source: &str,
+    external_id: &str,
+    kind: GapKind,
+    status: &PaymentStatus,
+) -> Result<OpenGap, PipelineError> {
+    // `detected_at` only moves to now() on insert or reopen, so comparing it
+    // with the statement's now() tells the cases apart.
+    let row = sqlx::query!(
+        r#"
+        INSERT INTO event_gaps (source, external_id, kind, status)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (external_id, kind) DO UPDATE
+        SET status = EXCLUDED.status,
+            detected_at = CASE WHEN event_gaps.resolved_at IS NULL
+                               THEN event_gaps.detected_at ELSE now() END,
+            resolved_at = NULL
+        RETURNING id, refetch_requested, detected_at = now() AS "opened!"
+        "#,
+        source,
+        external_id,
+        kind.as_str(),
+        status.as_str(),
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(OpenGap {
+        id: row.id,
+        opened: row.opened,
+        refetch_requested: row.refetch_requested,
+    })
+}
+
+pub async fn mark_refetch_requested(pool: &PgPool, id: Uuid) -> Result<(), PipelineError> {
+    sqlx::query!(
+        "UPDATE event_gaps SET refetch_requested = true WHERE id = $1",
+        id
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Resolve open gaps of the `checked` payments that aren't in `still_open`
+/// (`<external_id>:<kind>` keys). Returns how many were resolved.
+pub async fn resolve_closed(
+    pool: &PgPool,
+    checked: &[String],
+    still_open: &[String],
+) -> Result<u64, PipelineError> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE event_gaps SET resolved_at = now()
+        WHERE resolved_at IS NULL
+            AND external_id = ANY($1)
+            AND NOT (external_id || ':' || kind = ANY($2))
+        "#,
+        checked,
+        still_open,
+    )
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Newest first. `open` restricts to unresolved (true) or resolved (false) gaps.
+pub async fn list_gaps(
+    pool: &PgPool,
+    open: Option<bool>,
+    kind: Option<&str>,
+    limit: i64,
+) -> Result<Vec<EventGapView>, PipelineError> {
+    let gaps = sqlx::query_as!(
+        EventGapView,
+        r#"
+        SELECT id, source, external_id, kind, status, refetch_requested,
+               detected_at, resolved_at
+        FROM event_gaps
+        WHERE ($1::bool IS NULL OR (resolved_at IS NULL) = $1)
+            AND ($2::text IS NULL OR kind = $2)
+        ORDER BY detected_at DESC, id DESC
+        LIMIT $3
+        "#,
+        open,
+        kind,
+        limit,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(gaps)
+}
@@ -0,0 +1,147 @@
+use {
+    crate::domain::export::{ExportView, NewExport},
+    crate::error::PipelineError,
+    sqlx::PgPool,
+    uuid::Uuid,
+};
+
+/// Record `export` as `running`, leased for `lease`. `None` if `trigger`
+/// is `schedule` and the same export is already running or done.
+pub async fn create_export(
+    pool: &PgPool,
+    export: &NewExport<'_>,
+    lease: std::time::Duration,
+) -> Result<Option<ExportView>, PipelineError> {
+    let export = sqlx::query_as!(
+        ExportView,
+        r#"
+        INSERT INTO exports
+            (kind, format, window_start, window_end, trigger, requested_by, lease_until)
+        VALUES ($1, $2, $3, $4, $5, $6, now() + make_interval(secs => $7))
+        ON CONFLICT (kind, format, window_start, window_end)
+            WHERE trigger = 'schedule' AND status <> 'failed'
+            DO NOTHING
+        RETURNING id, kind, format, window_start, window_end, trigger, requested_by, status,
+                  location, row_count, byte_count, error, started_at, finished_at
+        "#,
+        export.kind.as_str(),
+        export.format.extension(),
+        export.since,
+        export.until,
+        export.trigger,
+        export.requested_by,
+        lease.as_secs_f64(),
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(export)
+}
+
+/// Extend a running export's lease to `lease` from now. `false` if it is no
+/// longer running, e.g. because its lease ran out first.
+pub async fn renew_lease(
+    pool: &PgPool,
+    id: Uuid,
+    lease: std::time::Duration,
+) -> Result<bool, PipelineError> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE exports SET lease_until = now() + make_interval(secs => $2)
+        WHERE id = $1 AND status = 'running'
+        "#,
+        id,
+        lease.as_secs_f64(),
+    )
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() == 1)
+}
+
+/// Fail running exports whose lease has run out; their replica died or
+/// stalled. Returns how many.
+pub async fn fail_expired(pool: &PgPool) -> Result<u64, PipelineError> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE exports
+        SET status = 'failed', error = 'lease expired', finished_at = now()
+        WHERE status = 'running' AND lease_until < now()
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Only a running export completes; one failed for its lease stays failed.
+pub async fn complete_export(
+    pool: &PgPool,
+    id: Uuid,
+    location: &str,
+    row_count: i64,
+    byte_count: i64,
+) -> Result<(), PipelineError> {
+    sqlx::query!(
+        r#"
+        UPDATE exports
+        SET status = 'completed', location = $2, row_count = $3, byte_count = $4,
+            finished_at = now()
+        WHERE id = $1 AND status = 'running'
+        "#,
+        id,
+        location,
+        row_count,
+        byte_count,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn fail_export(pool: &PgPool, id: Uuid, error: &str) -> Result<(), PipelineError> {
+    sqlx::query!(
+        r#"
+        UPDATE exports
+        SET status = 'failed', error = $2, finished_at = now()
+        WHERE id = $1 AND status = 'running'
+        "#,
+        id,
+        error,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Newest first.
+pub async fn list_exports(pool: &PgPool, limit: i64) -> Result<Vec<ExportView>, PipelineError> {
+    let exports = sqlx::query_as!(
+        ExportView,
+        r#"
+        SELECT id, kind, format, window_start, window_end, trigger, requested_by, status,
+               location, row_count, byte_count, error, started_at, finished_at
+        FROM exports
+        ORDER BY started_at DESC, id DESC
+        LIMIT $1
+        "#,
+        limit,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(exports)
+}
+
+pub async fn get_export(pool: &PgPool, id: Uuid) -> Result<Option<ExportView>, PipelineError> {
+    let export = sqlx::query_as!(
+        ExportView,
+        r#"
+        SELECT id, kind, format, window_start, window_end, trigger, requested_by, status,
+               location, row_count, byte_count, error, started_at, finished_at
+        FROM exports
+        WHERE id = $1
+        "#,
+        id,
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(export)
+}
@@ -0,0 +1,80 @@
+use {
+    crate::{
+        domain::feed::{FeedPosition, MergePolicy},
+        error::PipelineError,
+    },
+    uuid::Uuid,
+};
+
+/// The `feed_merge_policy` from the stored runtime config, read in the
+/// pipeline's transaction as `shadow_repo::get_stage` reads its stage.
+pub async fn merge_policy(
+    executor: impl sqlx::PgExecutor<'_>,
+) -> Result<MergePolicy, PipelineError> {
+    let policy = sqlx::query_scalar!("SELECT config->>'feed_merge_policy' FROM runtime_config")
+        .fetch_optional(executor)
+        .await?
+        .flatten();
+    Ok(match policy {
+        Some(policy) => MergePolicy::try_from(policy.as_str())?,
+        None => MergePolicy::default(),
+    })
+}
+
+/// Where each feed of payment `payment_id` stands.
+pub async fn positions(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    payment_id: Uuid,
+) -> Result<Vec<FeedPosition>, PipelineError> {
+    let rows = sqlx::query!(
+        "SELECT feed, last_provider_ts, last_event_id FROM payment_feeds WHERE payment_id = $1",
+        payment_id,
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|r| FeedPosition {
+            feed: r.feed,
+            provider_ts: r.last_provider_ts,
+            event_id: r.last_event_id,
+        })
+        .collect())
+}
+
+/// Count an event received on `feed` of payment `payment_id`, moving the
+/// feed's position up to it unless the feed has seen a later one (by
+/// `provider_ts`, then event id). `conflict` counts it as behind another
+/// feed.
+pub async fn record(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    payment_id: Uuid,
+    feed: &str,
+    provider_ts: i64,
+    event_id: &str,
+    conflict: bool,
+) -> Result<(), PipelineError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO payment_feeds (payment_id, feed, last_provider_ts, last_event_id, conflicts)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (payment_id, feed) DO UPDATE SET
+            last_provider_ts = GREATEST(payment_feeds.last_provider_ts, EXCLUDED.last_provider_ts),
+            last_event_id = CASE
+                WHEN (EXCLUDED.last_provider_ts, EXCLUDED.last_event_id)
+                   > (payment_feeds.last_provider_ts, payment_feeds.last_event_id)
+                THEN EXCLUDED.last_event_id ELSE payment_feeds.last_event_id END,
+            events = payment_feeds.events + 1,
+            conflicts = payment_feeds.conflicts + EXCLUDED.conflicts,
+            updated_at = now()
+        "#,
+        payment_id,
+        feed,
+        provider_ts,
+        event_id,
+        i64::from(conflict),
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,145 @@
+use {crate::domain::followup::FollowUp, crate::error::PipelineError, uuid::Uuid};
+
+pub struct FollowUpRow {
+    pub id: Uuid,
+    pub kind: String,
+    pub payload: serde_json::Value,
+    pub attempts: i32,
+}
+
+/// Queue `followup` in the caller's transaction, so it exists exactly when
+/// the work that called for it commits. Returns `false` if the same
+/// follow-up was already queued.
+pub async fn enqueue(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    followup: &FollowUp,
+) -> Result<bool, PipelineError> {
+    let inserted: Option<bool> = sqlx::query_scalar!(
+        r#"
+        INSERT INTO followup_jobs (kind, dedup_key, payload)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (dedup_key) DO NOTHING
+        RETURNING true AS "inserted!"
+        "#,
+        followup.kind(),
+        followup.dedup_key(),
+        serde_json::to_value(followup)?,
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+    Ok(inserted.is_some())
+}
+
+/// Claim up to `limit` due follow-ups, skipping rows other workers hold.
+pub async fn claim(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    limit: i64,
+    claimed_by: &str,
+) -> Result<Vec<FollowUpRow>, PipelineError> {
+    let rows = sqlx::query_as!(
+        FollowUpRow,
+        r#"
+        UPDATE followup_jobs
+        SET status = 'processing', claimed_by = $2, updated_at = now()
+        WHERE id IN (
+            SELECT id FROM followup_jobs
+            WHERE status = 'pending' AND scheduled_at <= now()
+            ORDER BY scheduled_at
+            LIMIT $1
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING id, kind, payload, attempts
+        "#,
+        limit,
+        claimed_by,
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+    Ok(rows)
+}
+
+/// Mark a follow-up as done. `reason` is set when it was dropped without
+/// running, e.g. for a source with no provider.
+pub async fn complete(
+    pool: &sqlx::PgPool,
+    id: Uuid,
+    reason: Option<&str>,
+) -> Result<(), PipelineError> {
+    sqlx::query!(
+        r#"
+        UPDATE followup_jobs
+        SET status = 'completed', last_error = $2, updated_at = now()
+        WHERE id = $1
+        "#,
+        id,
+        reason,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Record a failure: due again in `retry_in`, or left `failed` once the
+/// retry policy gives up (`None`) or `max_attempts` are used up.
+pub async fn fail(
+    pool: &sqlx::PgPool,
+    id: Uuid,
+    error: &str,
+    retry_in: Option<std::time::Duration>,
+) -> Result<(), PipelineError> {
+    sqlx::query!(
+        r#"
+        UPDATE followup_jobs
+        SET attempts = attempts + 1,
+            last_error = $2,
+            status = CASE
+                WHEN $3::float8 IS NULL OR attempts + 1 >= max_attempts THEN 'failed'
+                ELSE 'pending'
+            END,
+            scheduled_at = CASE
+                WHEN $3 IS NULL OR attempts + 1 >= max_attempts THEN scheduled_at
+                ELSE now() + make_interval(secs => $3)
+            END,
+            updated_at = now()
+        WHERE id = $1
+        "#,
+        id,
+        error,
+        retry_in.map(|d| d.as_secs_f64()),
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Put follow-ups stuck in 'processing' longer than `stale_after_secs`
+/// back to 'pending'. Returns how many.
+pub async fn reap_stale(pool: &sqlx::PgPool, stale_after_secs: i64) -> Result<u64, PipelineError> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE followup_jobs
+        SET status = 'pending', updated_at = now()
+        WHERE status = 'processing' AND updated_at < now() - make_interval(secs => $1)
+        "#,
+        stale_after_secs as f64,
+    )
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Put the follow-ups `claimed_by` still holds back to 'pending'. Returns
+/// how many.
+pub async fn release(pool: &sqlx::PgPool, claimed_by: &str) -> Result<u64, PipelineError> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE followup_jobs
+        SET status = 'pending', updated_at = now()
+        WHERE status = 'processing' AND claimed_by = $1
+        "#,
+        claimed_by,
+    )
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
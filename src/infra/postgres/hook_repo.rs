@@ -0,0 +1,99 @@
+use {
+    crate::{domain::hook::HookDelivery, error::PipelineError},
+    sqlx::PgPool,
+};
+
+/// Start queueing transitions for `hooks`. Idempotent; transitions committed
+/// before a hook's first subscription are not queued for it.
+pub async fn subscribe(pool: &PgPool, hooks: &[String]) -> Result<(), PipelineError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO hook_subscriptions (hook)
+        SELECT * FROM UNNEST($1::text[])
+        ON CONFLICT (hook) DO NOTHING
+        "#,
+        hooks,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Lock up to `limit` due deliveries for `hooks`, oldest first. A delivery
+/// waits while an earlier one to the same hook for the same payment is still
+/// pending, so each hook sees a payment's transitions in order and a batch
+/// holds at most one per hook and payment. Concurrent dispatchers skip each
+/// other's rows.
+pub async fn claim_batch(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    hooks: &[String],
+    limit: i64,
+) -> Result<Vec<HookDelivery>, PipelineError> {
+    let rows = sqlx::query_as!(
+        HookDelivery,
+        r#"
+        SELECT o.seq, o.hook, o.attempts, a.external_id, a.event_id,
+               a.action, a.actor, a.detail, a.created_at
+        FROM hook_outbox o
+        JOIN audit_log a ON a.id = o.audit_id
+        WHERE o.hook = ANY($1)
+          AND o.failed_at IS NULL
+          AND o.next_attempt_at <= now()
+          AND NOT EXISTS (
+              SELECT 1
+              FROM hook_outbox p
+              JOIN audit_log pa ON pa.id = p.audit_id
+              WHERE p.hook = o.hook
+                AND p.seq < o.seq
+                AND p.failed_at IS NULL
+                AND pa.external_id = a.external_id
+          )
+        ORDER BY o.seq
+        LIMIT $2
+        FOR UPDATE OF o SKIP LOCKED
+        "#,
+        hooks,
+        limit,
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+    Ok(rows)
+}
+
+/// Drop deliveries their hook has accepted.
+pub async fn complete(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    seqs: &[i64],
+) -> Result<(), PipelineError> {
+    sqlx::query!("DELETE FROM hook_outbox WHERE seq = ANY($1)", seqs)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+/// Record a failed delivery, due again in `retry_in`. Once the retry policy
+/// gives up (`None`) the row is marked failed and kept, and the payment's
+/// later transitions go ahead without it.
+pub async fn fail(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    seq: i64,
+    error: &str,
+    retry_in: Option<std::time::Duration>,
+) -> Result<(), PipelineError> {
+    sqlx::query!(
+        r#"
+        UPDATE hook_outbox
+        SET attempts = attempts + 1,
+            last_error = $2,
+            failed_at = CASE WHEN $3::float8 IS NULL THEN now() END,
+            next_attempt_at = now() + make_interval(secs => COALESCE($3, 0))
+        WHERE seq = $1
+        "#,
+        seq,
+        error,
+        retry_in.map(|d| d.as_secs_f64()),
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
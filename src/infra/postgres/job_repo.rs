@@ -1,65 +1,166 @@
-use crate::domain::error::PipelineError;
+use {
+    crate::domain::{
+        job::{
+            AttemptOutcome, EventStatus, JobAttemptView, JobFilters, JobLane, JobPriority, JobView,
+            RequeueFilter,
+        },
+        payment::ProcessResult,
+    },
+    crate::error::PipelineError,
+    crate::infra::telemetry,
+    sqlx::postgres::PgListener,
+    uuid::Uuid,
+};
+
+/// Channel notified (by the `payment_jobs_pending` trigger) whenever a job
+/// becomes pending. The payload is the job's lane.
+pub const JOBS_CHANNEL: &str = "payment_jobs";
 
 pub struct JobRow {
     pub id: uuid::Uuid,
+    pub source: String,
+    pub tenant_id: Option<String>,
     pub event_id: String,
     pub object_id: String,
     pub event_type: String,
     pub provider_ts: i64,
     pub raw_event: serde_json::Value,
     pub attempts: i32,
+    /// The trace of the span that queued the job; see `telemetry::traceparent`.
+    pub traceparent: Option<String>,
+}
+
+/// A dedicated connection listening on `JOBS_CHANNEL`.
+pub async fn listen(pool: &sqlx::PgPool) -> Result<PgListener, PipelineError> {
+    let mut listener = PgListener::connect_with(pool).await?;
+    listener.listen(JOBS_CHANNEL).await?;
+    Ok(listener)
 }
 
-/// Enqueue a webhook event for async processing.
-/// Returns `true` if inserted, `false` if duplicate (already enqueued).
+/// Enqueue a webhook event for async processing, in the lane of its object
+/// and at `priority` within it. Returns the new job's id, or `None` if
+/// duplicate (`source` already enqueued `event_id` for `tenant_id`). The
+/// job carries the current trace, for the worker to continue.
+#[allow(clippy::too_many_arguments)]
+#[tracing::instrument(skip_all, fields(event_id = event_id, external_id = object_id))]
 pub async fn enqueue(
     pool: &sqlx::PgPool,
+    source: &str,
+    tenant_id: Option<&str>,
     event_id: &str,
     object_id: &str,
     event_type: &str,
     provider_ts: i64,
     raw_event: &serde_json::Value,
-) -> Result<bool, PipelineError> {
-    let inserted: Option<bool> = sqlx::query_scalar!(
+    priority: JobPriority,
+) -> Result<Option<Uuid>, PipelineError> {
+    let id = sqlx::query_scalar!(
         r#"
-        INSERT INTO payment_jobs (event_id, object_id, event_type, provider_ts, raw_event)
-        VALUES ($1, $2, $3, $4, $5)
-        ON CONFLICT (event_id) DO NOTHING
-        RETURNING true AS "inserted!"
+        INSERT INTO payment_jobs
+            (source, event_id, object_id, event_type, provider_ts, raw_event, lane, priority,
+             tenant_id, traceparent)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+        ON CONFLICT (event_id, source, tenant_id) DO NOTHING
+        RETURNING id
         "#,
+        source,
         event_id,
         object_id,
         event_type,
         provider_ts,
         raw_event,
+        JobLane::for_object(object_id).as_str(),
+        priority.value(),
+        tenant_id,
+        telemetry::traceparent(),
     )
     .fetch_optional(pool)
     .await?;
 
-    Ok(inserted.is_some())
+    Ok(id)
 }
 
-/// Claim up to `limit` pending jobs for processing.
-/// Uses SKIP LOCKED to avoid contention with other workers.
+/// Claim up to `limit` pending jobs of `lane` for processing, recording
+/// `claimed_by` and opening an attempt for each. Jobs are claimed by
+/// object: the objects with the most urgent due jobs are picked, and
+/// their due jobs taken whole, in that order, while they fit in `limit`.
+/// The batch is cut only between objects, except that the first object
+/// always goes, as its oldest `limit` events, so a later event is never
+/// claimed ahead of an earlier one of the same object. Jobs are returned
+/// oldest event first. An object with a job in another worker's hands,
+/// or being claimed right now, is left for later, so its jobs don't wait
+/// on each other's lock in two workers. Uses SKIP LOCKED to avoid
+/// contention with other workers.
 pub async fn claim(
     tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    lane: JobLane,
     limit: i64,
+    claimed_by: &str,
 ) -> Result<Vec<JobRow>, PipelineError> {
     let rows = sqlx::query_as!(
         JobRow,
         r#"
-        UPDATE payment_jobs
-        SET status = 'processing', updated_at = now()
-        WHERE id IN (
-            SELECT id FROM payment_jobs
-            WHERE status = 'pending' AND scheduled_at <= now()
-            ORDER BY scheduled_at
+        WITH candidates AS MATERIALIZED (
+            SELECT object_id, priority, scheduled_at FROM payment_jobs
+            WHERE status = 'pending' AND lane = $3 AND scheduled_at <= now()
+            ORDER BY priority DESC, scheduled_at
+            LIMIT $1::bigint * 4
+        ), objects AS MATERIALIZED (
+            SELECT c.object_id,
+                   row_number() OVER (ORDER BY max(c.priority) DESC, min(c.scheduled_at)) AS rank
+            FROM candidates c
+            WHERE NOT EXISTS (
+                SELECT 1 FROM payment_jobs p
+                WHERE p.object_id = c.object_id AND p.status = 'processing'
+            )
+            GROUP BY c.object_id
+            ORDER BY max(c.priority) DESC, min(c.scheduled_at)
             LIMIT $1
+        ), mine AS MATERIALIZED (
+            SELECT object_id, rank FROM objects
+            WHERE pg_try_advisory_xact_lock(hashtextextended('job_object:' || object_id, 0))
+        ), due AS MATERIALIZED (
+            SELECT j.id, j.object_id, j.provider_ts, j.event_id FROM payment_jobs j
+            WHERE j.status = 'pending' AND j.lane = $3 AND j.scheduled_at <= now()
+                AND j.object_id IN (SELECT object_id FROM mine)
             FOR UPDATE SKIP LOCKED
+        ), sized AS (
+            SELECT m.rank, count(*) AS jobs,
+                   sum(count(*)) OVER (ORDER BY m.rank) - count(*) AS before
+            FROM due d JOIN mine m USING (object_id)
+            GROUP BY m.rank
+        ), taken AS (
+            SELECT d.id FROM (
+                SELECT d.id, m.rank, row_number() OVER (
+                    PARTITION BY d.object_id ORDER BY d.provider_ts, d.event_id
+                ) AS nth
+                FROM due d JOIN mine m USING (object_id)
+            ) d
+            JOIN sized s USING (rank)
+            WHERE s.before + s.jobs <= $1 OR (s.before = 0 AND d.nth <= $1)
+        ), claimed AS (
+            UPDATE payment_jobs
+            SET status = 'processing', claimed_by = $2, updated_at = now()
+            WHERE id IN (SELECT id FROM taken)
+            RETURNING id, source, tenant_id, event_id, object_id, event_type, provider_ts, raw_event,
+                      attempts, traceparent
+        ), started AS (
+            INSERT INTO job_attempts (job_id, attempt, worker)
+            SELECT c.id, COALESCE(MAX(a.attempt), 0) + 1, $2
+            FROM claimed c
+            LEFT JOIN job_attempts a ON a.job_id = c.id
+            GROUP BY c.id
         )
-        RETURNING id, event_id, object_id, event_type, provider_ts, raw_event, attempts
+        SELECT id AS "id!", source AS "source!", tenant_id, event_id AS "event_id!",
+               object_id AS "object_id!", event_type AS "event_type!",
+               provider_ts AS "provider_ts!", raw_event AS "raw_event!", attempts AS "attempts!",
+               traceparent
+        FROM claimed
+        ORDER BY provider_ts, event_id
         "#,
         limit,
+        claimed_by,
+        lane.as_str(),
     )
     .fetch_all(&mut **tx)
     .await?;
@@ -67,56 +168,333 @@ pub async fn claim(
     Ok(rows)
 }
 
-/// Mark a job as completed.
-pub async fn complete(pool: &sqlx::PgPool, id: uuid::Uuid) -> Result<(), PipelineError> {
+/// Mark a job as completed with what processing it came to.
+pub async fn complete(
+    pool: &sqlx::PgPool,
+    id: uuid::Uuid,
+    result: &ProcessResult,
+) -> Result<(), PipelineError> {
+    finish(pool, id, AttemptOutcome::Succeeded, None, Some(result)).await
+}
+
+/// Mark a job as completed without having processed it; `reason` goes on
+/// the attempt.
+pub async fn discard(
+    pool: &sqlx::PgPool,
+    id: uuid::Uuid,
+    reason: &str,
+) -> Result<(), PipelineError> {
+    finish(pool, id, AttemptOutcome::Discarded, Some(reason), None).await
+}
+
+/// Mark a job as completed because the provider doesn't have its object,
+/// in the transaction that records that.
+pub async fn complete_missing(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    id: uuid::Uuid,
+    reason: &str,
+) -> Result<(), PipelineError> {
+    finish(
+        &mut **tx,
+        id,
+        AttemptOutcome::ProviderMissing,
+        Some(reason),
+        None,
+    )
+    .await
+}
+
+async fn finish(
+    executor: impl sqlx::PgExecutor<'_>,
+    id: uuid::Uuid,
+    outcome: AttemptOutcome,
+    error: Option<&str>,
+    result: Option<&ProcessResult>,
+) -> Result<(), PipelineError> {
     sqlx::query!(
-        "UPDATE payment_jobs SET status = 'completed', updated_at = now() WHERE id = $1",
+        r#"
+        WITH attempt AS (
+            UPDATE job_attempts SET finished_at = now(), outcome = $2, error = $3
+            WHERE job_id = $1 AND finished_at IS NULL
+        )
+        UPDATE payment_jobs
+        SET status = 'completed', result = $4, payment_id = $5, updated_at = now()
+        WHERE id = $1
+        "#,
         id,
+        outcome.as_str(),
+        error,
+        result.map(|r| r.as_str()),
+        result.and_then(|r| r.payment_id()),
     )
-    .execute(pool)
+    .execute(executor)
     .await?;
     Ok(())
 }
 
-/// Record a failure. Exponential backoff via scheduled_at.
-/// If max attempts reached, mark as 'failed' permanently.
-pub async fn fail(pool: &sqlx::PgPool, id: uuid::Uuid, error: &str) -> Result<(), PipelineError> {
+/// Record a failure: due again in `retry_in`, or 'failed' for good once the
+/// retry policy gives up (`None`) or the job's `max_attempts` are used up.
+pub async fn fail(
+    pool: &sqlx::PgPool,
+    id: uuid::Uuid,
+    error: &str,
+    retry_in: Option<std::time::Duration>,
+) -> Result<(), PipelineError> {
     sqlx::query!(
         r#"
+        WITH attempt AS (
+            UPDATE job_attempts SET finished_at = now(), outcome = 'failed', error = $2
+            WHERE job_id = $1 AND finished_at IS NULL
+        )
         UPDATE payment_jobs
         SET attempts = attempts + 1,
             last_error = $2,
             status = CASE
-                WHEN attempts + 1 >= max_attempts THEN 'failed'
+                WHEN $3::float8 IS NULL OR attempts + 1 >= max_attempts THEN 'failed'
                 ELSE 'pending'
             END,
             scheduled_at = CASE
-                WHEN attempts + 1 >= max_attempts THEN scheduled_at
-                ELSE now() + make_interval(secs => power(2, attempts + 1)::int)
+                WHEN $3 IS NULL OR attempts + 1 >= max_attempts THEN scheduled_at
+                ELSE now() + make_interval(secs => $3)
             END,
             updated_at = now()
         WHERE id = $1
         "#,
         id,
         error,
+        retry_in.map(|d| d.as_secs_f64()),
     )
     .execute(pool)
     .await?;
     Ok(())
 }
 
-/// Reset jobs stuck in 'processing' for >2 minutes back to 'pending'.
-/// Returns the number of reaped jobs.
-pub async fn reap_stale(pool: &sqlx::PgPool) -> Result<u64, PipelineError> {
-    let result = sqlx::query!(
+/// Put the job back to 'pending' until `until` without counting the
+/// attempt against it: the job didn't fail, it was refused a provider
+/// call (an exhausted API budget) and will be let through then.
+pub async fn defer(
+    pool: &sqlx::PgPool,
+    id: uuid::Uuid,
+    reason: &str,
+    until: chrono::DateTime<chrono::Utc>,
+) -> Result<(), PipelineError> {
+    sqlx::query!(
         r#"
+        WITH attempt AS (
+            UPDATE job_attempts SET finished_at = now(), outcome = 'deferred', error = $2
+            WHERE job_id = $1 AND finished_at IS NULL
+        )
         UPDATE payment_jobs
-        SET status = 'pending', updated_at = now()
-        WHERE status = 'processing' AND updated_at < now() - interval '2 minutes'
+        SET status = 'pending', last_error = $2, scheduled_at = $3, updated_at = now()
+        WHERE id = $1
         "#,
+        id,
+        reason,
+        until,
     )
     .execute(pool)
     .await?;
+    Ok(())
+}
+
+/// Reset jobs stuck in 'processing' longer than `stale_after_secs` back to
+/// 'pending', closing their attempts as abandoned. Returns the number of
+/// reaped jobs.
+pub async fn reap_stale(pool: &sqlx::PgPool, stale_after_secs: i64) -> Result<u64, PipelineError> {
+    let reaped = sqlx::query_scalar!(
+        r#"
+        WITH reaped AS (
+            UPDATE payment_jobs
+            SET status = 'pending', updated_at = now()
+            WHERE status = 'processing' AND updated_at < now() - make_interval(secs => $1)
+            RETURNING id
+        ), abandoned AS (
+            UPDATE job_attempts SET finished_at = now(), outcome = 'abandoned'
+            WHERE job_id IN (SELECT id FROM reaped) AND finished_at IS NULL
+        )
+        SELECT COUNT(*) AS "reaped!" FROM reaped
+        "#,
+        stale_after_secs as f64,
+    )
+    .fetch_one(pool)
+    .await?;
 
+    Ok(reaped as u64)
+}
+
+/// Put the jobs of `lane` that `claimed_by` still holds back to 'pending',
+/// closing their attempts as abandoned. A worker calls this on its way out
+/// so the jobs don't wait for the reaper. Returns the number released.
+pub async fn release(
+    pool: &sqlx::PgPool,
+    lane: JobLane,
+    claimed_by: &str,
+) -> Result<u64, PipelineError> {
+    let released = sqlx::query_scalar!(
+        r#"
+        WITH released AS (
+            UPDATE payment_jobs
+            SET status = 'pending', updated_at = now()
+            WHERE status = 'processing' AND lane = $1 AND claimed_by = $2
+            RETURNING id
+        ), abandoned AS (
+            UPDATE job_attempts
+            SET finished_at = now(), outcome = 'abandoned', error = 'released on shutdown'
+            WHERE job_id IN (SELECT id FROM released) AND finished_at IS NULL
+        )
+        SELECT COUNT(*) AS "released!" FROM released
+        "#,
+        lane.as_str(),
+        claimed_by,
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(released as u64)
+}
+
+/// Jobs newest-updated first, optionally by status and source.
+pub async fn list_jobs(
+    pool: &sqlx::PgPool,
+    filters: &JobFilters,
+    limit: i64,
+) -> Result<Vec<JobView>, PipelineError> {
+    let jobs = sqlx::query_as!(
+        JobView,
+        r#"
+        SELECT id, source, event_id, object_id, event_type, lane, priority, status, attempts,
+               max_attempts, last_error, claimed_by, scheduled_at, created_at, updated_at
+        FROM payment_jobs
+        WHERE ($1::text IS NULL OR status = $1)
+            AND ($2::text IS NULL OR source = $2)
+        ORDER BY updated_at DESC, id DESC
+        LIMIT $3
+        "#,
+        filters.status.map(|s| s.as_str()),
+        filters.source.as_deref(),
+        limit,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(jobs)
+}
+
+pub async fn get_job(pool: &sqlx::PgPool, id: Uuid) -> Result<Option<JobView>, PipelineError> {
+    let job = sqlx::query_as!(
+        JobView,
+        r#"
+        SELECT id, source, event_id, object_id, event_type, lane, priority, status, attempts,
+               max_attempts, last_error, claimed_by, scheduled_at, created_at, updated_at
+        FROM payment_jobs
+        WHERE id = $1
+        "#,
+        id,
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(job)
+}
+
+/// Where the jobs for `event_id` stand, one per source it came from (or
+/// just `source`'s), newest first.
+pub async fn event_status(
+    pool: &sqlx::PgPool,
+    event_id: &str,
+    source: Option<&str>,
+    tenant_id: Option<&str>,
+) -> Result<Vec<EventStatus>, PipelineError> {
+    let statuses = sqlx::query_as!(
+        EventStatus,
+        r#"
+        SELECT j.id AS token, j.event_id, j.source, j.tenant_id, j.status,
+               j.status IN ('completed', 'failed') AS "done!",
+               j.result, j.payment_id, a.outcome AS "outcome?", j.attempts, j.last_error,
+               j.updated_at
+        FROM payment_jobs j
+        LEFT JOIN LATERAL (
+            SELECT outcome FROM job_attempts
+            WHERE job_id = j.id
+            ORDER BY attempt DESC
+            LIMIT 1
+        ) a ON true
+        WHERE j.event_id = $1 AND ($2::text IS NULL OR j.source = $2)
+            AND ($3::text IS NULL OR j.tenant_id = $3)
+        ORDER BY j.created_at DESC
+        "#,
+        event_id,
+        source,
+        tenant_id,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(statuses)
+}
+
+/// Every attempt at a job, oldest first.
+pub async fn list_attempts(
+    pool: &sqlx::PgPool,
+    job_id: Uuid,
+) -> Result<Vec<JobAttemptView>, PipelineError> {
+    let attempts = sqlx::query_as!(
+        JobAttemptView,
+        r#"
+        SELECT attempt, worker, started_at, finished_at, outcome, error
+        FROM job_attempts
+        WHERE job_id = $1
+        ORDER BY attempt
+        "#,
+        job_id,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(attempts)
+}
+
+/// Put one failed job back in the queue with its attempts reset, due now.
+/// Returns the job as it was, or `None` if it isn't (or no longer) failed.
+pub async fn retry_failed(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    id: Uuid,
+) -> Result<Option<JobView>, PipelineError> {
+    let job = sqlx::query_as!(
+        JobView,
+        r#"
+        WITH before AS (
+            SELECT * FROM payment_jobs WHERE id = $1 AND status = 'failed' FOR UPDATE
+        )
+        UPDATE payment_jobs j
+        SET status = 'pending', attempts = 0, scheduled_at = now(), updated_at = now()
+        FROM before b
+        WHERE j.id = b.id
+        RETURNING b.id, b.source, b.event_id, b.object_id, b.event_type, b.lane, b.priority,
+                  b.status, b.attempts, b.max_attempts, b.last_error, b.claimed_by, b.scheduled_at,
+                  b.created_at, b.updated_at
+        "#,
+        id,
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+    Ok(job)
+}
+
+/// `retry_failed` for every failed job matching `filter`. Returns how many
+/// were requeued.
+pub async fn requeue_failed(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    filter: &RequeueFilter,
+) -> Result<u64, PipelineError> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE payment_jobs
+        SET status = 'pending', attempts = 0, scheduled_at = now(), updated_at = now()
+        WHERE status = 'failed'
+            AND ($1::text IS NULL OR source = $1)
+            AND ($2::timestamptz IS NULL OR updated_at >= $2)
+        "#,
+        filter.source.as_deref(),
+        filter.failed_since,
+    )
+    .execute(&mut **tx)
+    .await?;
     Ok(result.rows_affected())
 }
@@ -0,0 +1,157 @@
+use {
+    crate::{
+        domain::ledger::{
+            Balance, LedgerAccount, LedgerEntryView, LedgerLine, NewLedgerEntry, Side,
+        },
+        error::PipelineError,
+    },
+    sqlx::PgPool,
+    std::collections::HashMap,
+    uuid::Uuid,
+};
+
+/// What the payment's entries add up to, per account it touched.
+pub async fn balances(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    payment_id: Uuid,
+) -> Result<Vec<Balance>, PipelineError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT l.account,
+               SUM(CASE WHEN l.side = 'debit' THEN l.amount ELSE -l.amount END)::BIGINT
+                   AS "balance!"
+        FROM ledger_lines l
+        JOIN ledger_entries e ON e.id = l.entry_id
+        WHERE e.payment_id = $1
+        GROUP BY l.account
+        "#,
+        payment_id,
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    rows.into_iter()
+        .map(|r| Ok((LedgerAccount::try_from(r.account.as_str())?, r.balance)))
+        .collect()
+}
+
+pub async fn insert_entry(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    entry: &NewLedgerEntry,
+) -> Result<(), PipelineError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO ledger_entries
+            (id, payment_id, external_id, source, event_id, currency, from_status, to_status)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        "#,
+        entry.id,
+        entry.payment_id,
+        &entry.external_id,
+        &entry.source,
+        &entry.event_id,
+        &entry.currency,
+        entry.from_status.as_ref().map(|s| s.as_str()),
+        entry.to_status.as_str(),
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    let accounts: Vec<String> = entry.lines.iter().map(|l| l.account.to_string()).collect();
+    let sides: Vec<String> = entry.lines.iter().map(|l| l.side.as_str().into()).collect();
+    let amounts: Vec<i64> = entry.lines.iter().map(|l| l.amount).collect();
+    sqlx::query!(
+        r#"
+        INSERT INTO ledger_lines (entry_id, account, side, amount)
+        SELECT $1, * FROM UNNEST($2::text[], $3::text[], $4::bigint[])
+        "#,
+        entry.id,
+        &accounts,
+        &sides,
+        &amounts,
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// The lines of the entries `tx` has posted so far for `external_id`,
+/// oldest entry first: what a dry run posted before it rolls back.
+pub async fn lines_written_in_transaction(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    external_id: &str,
+) -> Result<Vec<LedgerLine>, PipelineError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT l.account, l.side, l.amount
+        FROM ledger_lines l
+        JOIN ledger_entries e ON e.id = l.entry_id
+        WHERE e.external_id = $1 AND e.xmin = pg_current_xact_id()::xid
+        ORDER BY e.id, l.account
+        "#,
+        external_id,
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    rows.into_iter()
+        .map(|l| {
+            Ok(LedgerLine {
+                account: LedgerAccount::try_from(l.account.as_str())?,
+                side: Side::try_from(l.side.as_str())?,
+                amount: l.amount,
+            })
+        })
+        .collect()
+}
+
+/// A payment's entries, oldest first, with their lines.
+pub async fn list_entries(
+    pool: &PgPool,
+    external_id: &str,
+) -> Result<Vec<LedgerEntryView>, PipelineError> {
+    let entries = sqlx::query!(
+        r#"
+        SELECT id, event_id, currency, from_status, to_status, created_at
+        FROM ledger_entries
+        WHERE external_id = $1
+        ORDER BY id
+        "#,
+        external_id,
+    )
+    .fetch_all(pool)
+    .await?;
+    let ids: Vec<Uuid> = entries.iter().map(|e| e.id).collect();
+    let lines = sqlx::query!(
+        r#"
+        SELECT entry_id, account, side, amount
+        FROM ledger_lines
+        WHERE entry_id = ANY($1)
+        ORDER BY entry_id, account
+        "#,
+        &ids,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut by_entry: HashMap<Uuid, Vec<LedgerLine>> = HashMap::new();
+    for l in lines {
+        by_entry.entry(l.entry_id).or_default().push(LedgerLine {
+            account: LedgerAccount::try_from(l.account.as_str())?,
+            side: Side::try_from(l.side.as_str())?,
+            amount: l.amount,
+        });
+    }
+    Ok(entries
+        .into_iter()
+        .map(|e| LedgerEntryView {
+            lines: by_entry.remove(&e.id).unwrap_or_default(),
+            id: e.id,
+            event_id: e.event_id,
+            currency: e.currency,
+            from_status: e.from_status,
+            to_status: e.to_status,
+            created_at: e.created_at,
+        })
+        .collect())
+}
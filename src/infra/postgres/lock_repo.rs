@@ -0,0 +1,213 @@
+use {
+    crate::{
+        domain::lock::{AdvisoryLock, Backend, LongTransaction, PAYMENT_TABLES},
+        error::PipelineError,
+    },
+    chrono::{DateTime, Utc},
+    sqlx::PgPool,
+};
+
+struct LockRow {
+    key: i64,
+    granted: bool,
+    pid: i32,
+    application_name: String,
+    client_addr: Option<String>,
+    state: Option<String>,
+    xact_start: Option<DateTime<Utc>>,
+    xact_age_secs: Option<i64>,
+    wait_event_type: Option<String>,
+    wait_event: Option<String>,
+    query: Option<String>,
+}
+
+struct TransactionRow {
+    tables: Vec<String>,
+    pid: i32,
+    application_name: String,
+    client_addr: Option<String>,
+    state: Option<String>,
+    xact_start: Option<DateTime<Utc>>,
+    xact_age_secs: Option<i64>,
+    wait_event_type: Option<String>,
+    wait_event: Option<String>,
+    query: Option<String>,
+}
+
+/// Advisory locks held or awaited in this database, oldest transaction
+/// first; with `lock_key`, only the one taken on
+/// `hashtextextended(lock_key, 0)`. Only single-bigint keys, the kind this
+/// service takes, are listed.
+pub async fn advisory_locks(
+    pool: &PgPool,
+    lock_key: Option<&str>,
+) -> Result<Vec<AdvisoryLock>, PipelineError> {
+    let rows = sqlx::query_as!(
+        LockRow,
+        r#"
+        SELECT (l.classid::BIGINT << 32) | l.objid::BIGINT AS "key!",
+               l.granted AS "granted!", a.pid AS "pid!",
+               a.application_name AS "application_name!", host(a.client_addr) AS client_addr,
+               a.state, a.xact_start,
+               EXTRACT(EPOCH FROM now() - a.xact_start)::BIGINT AS xact_age_secs,
+               a.wait_event_type, a.wait_event, a.query
+        FROM pg_locks l
+        JOIN pg_stat_activity a ON a.pid = l.pid
+        WHERE l.locktype = 'advisory' AND l.objsubid = 1
+          AND l.database = (SELECT oid FROM pg_database WHERE datname = current_database())
+          AND ($1::TEXT IS NULL
+               OR (l.classid::BIGINT << 32) | l.objid::BIGINT = hashtextextended($1, 0))
+        ORDER BY a.xact_start NULLS LAST, a.pid
+        "#,
+        lock_key,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| AdvisoryLock {
+            key: r.key,
+            granted: r.granted,
+            backend: Backend {
+                pid: r.pid,
+                application_name: r.application_name,
+                client_addr: r.client_addr,
+                state: r.state,
+                xact_start: r.xact_start,
+                xact_age_secs: r.xact_age_secs,
+                wait_event_type: r.wait_event_type,
+                wait_event: r.wait_event,
+                query: r.query,
+            },
+        })
+        .collect())
+}
+
+/// Transactions open at least `min_age_secs` holding or awaiting a lock on
+/// one of `PAYMENT_TABLES`, oldest first. The calling connection is left out.
+pub async fn long_transactions(
+    pool: &PgPool,
+    min_age_secs: i64,
+) -> Result<Vec<LongTransaction>, PipelineError> {
+    let tables: Vec<String> = PAYMENT_TABLES.iter().map(|t| t.to_string()).collect();
+    let rows = sqlx::query_as!(
+        TransactionRow,
+        r#"
+        SELECT array_agg(DISTINCT c.relname::TEXT ORDER BY c.relname::TEXT) AS "tables!",
+               a.pid AS "pid!", a.application_name AS "application_name!",
+               host(a.client_addr) AS client_addr, a.state, a.xact_start,
+               EXTRACT(EPOCH FROM now() - a.xact_start)::BIGINT AS xact_age_secs,
+               a.wait_event_type, a.wait_event, a.query
+        FROM pg_stat_activity a
+        JOIN pg_locks l ON l.pid = a.pid AND l.locktype = 'relation'
+        JOIN pg_class c ON c.oid = l.relation
+        WHERE a.datname = current_database() AND a.pid <> pg_backend_pid()
+          AND a.xact_start <= now() - $1::BIGINT * INTERVAL '1 second'
+          AND c.relnamespace = 'public'::regnamespace AND c.relname::TEXT = ANY($2)
+        GROUP BY a.pid, a.application_name, a.client_addr, a.state, a.xact_start,
+                 a.wait_event_type, a.wait_event, a.query
+        ORDER BY a.xact_start, a.pid
+        "#,
+        min_age_secs,
+        &tables,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| LongTransaction {
+            tables: r.tables,
+            backend: Backend {
+                pid: r.pid,
+                application_name: r.application_name,
+                client_addr: r.client_addr,
+                state: r.state,
+                xact_start: r.xact_start,
+                xact_age_secs: r.xact_age_secs,
+                wait_event_type: r.wait_event_type,
+                wait_event: r.wait_event,
+                query: r.query,
+            },
+        })
+        .collect())
+}
+
+/// The connection `pid`, if it is connected to this database.
+pub async fn backend(pool: &PgPool, pid: i32) -> Result<Option<Backend>, PipelineError> {
+    let backend = sqlx::query_as!(
+        Backend,
+        r#"
+        SELECT pid AS "pid!", application_name AS "application_name!",
+               host(client_addr) AS client_addr, state, xact_start,
+               EXTRACT(EPOCH FROM now() - xact_start)::BIGINT AS xact_age_secs,
+               wait_event_type, wait_event, query
+        FROM pg_stat_activity
+        WHERE pid = $1 AND datname = current_database()
+        "#,
+        pid,
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(backend)
+}
+
+/// Terminate the connection `pid`, only if it is another connection to
+/// this database, in a transaction open at least `min_age_secs`, holding
+/// or awaiting an advisory lock or a lock on one of `PAYMENT_TABLES`.
+/// Checked and signalled in one statement. Returns the backend as it was,
+/// or `None` if it didn't qualify. The database role needs
+/// `pg_signal_backend` to terminate other roles' connections.
+pub async fn terminate(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    pid: i32,
+    min_age_secs: i64,
+) -> Result<Option<Backend>, PipelineError> {
+    let tables: Vec<String> = PAYMENT_TABLES.iter().map(|t| t.to_string()).collect();
+    let row = sqlx::query!(
+        r#"
+        SELECT a.pid AS "pid!", a.application_name AS "application_name!",
+               host(a.client_addr) AS client_addr, a.state, a.xact_start,
+               EXTRACT(EPOCH FROM now() - a.xact_start)::BIGINT AS xact_age_secs,
+               a.wait_event_type, a.wait_event, a.query,
+               pg_terminate_backend(a.pid) AS "terminated!"
+        FROM pg_stat_activity a
+        WHERE a.pid = $1 AND a.datname = current_database() AND a.pid <> pg_backend_pid()
+          AND a.xact_start <= now() - $2::BIGINT * INTERVAL '1 second'
+          AND EXISTS (
+              SELECT 1
+              FROM pg_locks l
+              LEFT JOIN pg_class c
+                  ON c.oid = l.relation AND c.relnamespace = 'public'::regnamespace
+              WHERE l.pid = a.pid
+                AND (l.locktype = 'advisory' OR c.relname::TEXT = ANY($3))
+          )
+        "#,
+        pid,
+        min_age_secs,
+        &tables,
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+
+    Ok(row.filter(|r| r.terminated).map(|r| Backend {
+        pid: r.pid,
+        application_name: r.application_name,
+        client_addr: r.client_addr,
+        state: r.state,
+        xact_start: r.xact_start,
+        xact_age_secs: r.xact_age_secs,
+        wait_event_type: r.wait_event_type,
+        wait_event: r.wait_event,
+        query: r.query,
+    }))
+}
+
+/// The advisory lock key `pg_advisory_xact_lock(hashtextextended(lock_key, 0))` takes.
+pub async fn hash_key(pool: &PgPool, lock_key: &str) -> Result<i64, PipelineError> {
+    let key = sqlx::query_scalar!(r#"SELECT hashtextextended($1, 0) AS "key!""#, lock_key)
+        .fetch_one(pool)
+        .await?;
+    Ok(key)
+}
@@ -0,0 +1,44 @@
+use crate::{domain::manual::StoredManualRequest, error::PipelineError};
+
+/// Record what the request under `event_id` came to. A retry racing the
+/// first attempt leaves the first record in place.
+pub async fn record(
+    pool: &sqlx::PgPool,
+    event_id: &str,
+    request_hash: &str,
+    outcome: &str,
+    rejected_from: Option<&str>,
+) -> Result<(), PipelineError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO manual_requests (event_id, request_hash, outcome, rejected_from)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (event_id) DO NOTHING
+        "#,
+        event_id,
+        request_hash,
+        outcome,
+        rejected_from,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn find(
+    pool: &sqlx::PgPool,
+    event_id: &str,
+) -> Result<Option<StoredManualRequest>, PipelineError> {
+    let row = sqlx::query_as!(
+        StoredManualRequest,
+        r#"
+        SELECT request_hash, outcome, rejected_from
+        FROM manual_requests
+        WHERE event_id = $1
+        "#,
+        event_id,
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
@@ -0,0 +1,170 @@
+use {
+    crate::{
+        domain::{
+            audit::AuditRecord,
+            outbox::{ConsumerOffset, OutboxEvent},
+        },
+        error::PipelineError,
+    },
+    sqlx::{PgPool, postgres::PgListener},
+};
+
+/// Channel notified when an outbox event is committed, and again once
+/// events have been numbered.
+pub const OUTBOX_CHANNEL: &str = "outbox_events";
+
+/// A dedicated connection listening on `OUTBOX_CHANNEL`.
+pub async fn listen(pool: &PgPool) -> Result<PgListener, PipelineError> {
+    let mut listener = PgListener::connect_with(pool).await?;
+    listener.listen(OUTBOX_CHANNEL).await?;
+    Ok(listener)
+}
+
+/// Number up to `limit` committed events, in the order they were written,
+/// after the highest seq so far. Returns how many were numbered; 0 when
+/// there were none, or another session is numbering right now.
+pub async fn sequence_pending(pool: &PgPool, limit: i64) -> Result<u64, PipelineError> {
+    let mut tx = pool.begin().await?;
+    let locked = sqlx::query_scalar!(
+        r#"SELECT pg_try_advisory_xact_lock(hashtextextended('outbox_sequence', 0)) AS "locked!""#
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    if !locked {
+        return Ok(0);
+    }
+    let numbered = sqlx::query!(
+        r#"
+        WITH head AS (
+            SELECT COALESCE(max(seq), 0) AS seq FROM outbox_events
+        ),
+        pending AS (
+            SELECT id, row_number() OVER (ORDER BY id) AS n
+            FROM outbox_events
+            WHERE seq IS NULL
+            ORDER BY id
+            LIMIT $1
+        )
+        UPDATE outbox_events o
+        SET seq = head.seq + pending.n
+        FROM pending, head
+        WHERE o.id = pending.id
+        "#,
+        limit,
+    )
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    if numbered > 0 {
+        sqlx::query!("SELECT pg_notify('outbox_events', '')")
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+    Ok(numbered)
+}
+
+/// Up to `limit` numbered events after `after_seq`, in order.
+pub async fn list_events(
+    pool: &PgPool,
+    after_seq: i64,
+    limit: i64,
+) -> Result<Vec<OutboxEvent>, PipelineError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT o.seq AS "seq!", a.id, a.entity_type, a.entity_id, a.external_id, a.event_id,
+               a.source, a.tenant_id, a.action, a.actor, a.detail, a.created_at
+        FROM outbox_events o
+        JOIN audit_log a ON a.id = o.audit_id
+        WHERE o.seq > $1
+        ORDER BY o.seq
+        LIMIT $2
+        "#,
+        after_seq,
+        limit,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|r| OutboxEvent {
+            seq: r.seq,
+            entry: AuditRecord {
+                id: r.id,
+                entity_type: r.entity_type,
+                entity_id: r.entity_id,
+                external_id: r.external_id,
+                event_id: r.event_id,
+                source: r.source,
+                tenant_id: r.tenant_id,
+                action: r.action,
+                actor: r.actor,
+                detail: r.detail,
+                created_at: r.created_at,
+            },
+        })
+        .collect())
+}
+
+/// The highest seq handed out so far.
+pub async fn head_seq(pool: &PgPool) -> Result<i64, PipelineError> {
+    let head = sqlx::query_scalar!(r#"SELECT COALESCE(max(seq), 0) AS "seq!" FROM outbox_events"#)
+        .fetch_one(pool)
+        .await?;
+    Ok(head)
+}
+
+pub async fn get_offset(pool: &PgPool, consumer: &str) -> Result<Option<i64>, PipelineError> {
+    let offset = sqlx::query_scalar!(
+        "SELECT last_seq FROM consumer_offsets WHERE consumer = $1",
+        consumer
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(offset)
+}
+
+/// Record `last_seq` as the consumer's offset, creating the consumer on
+/// first use. Moving an offset back is allowed; it is how a consumer
+/// re-reads.
+pub async fn commit_offset(
+    pool: &PgPool,
+    consumer: &str,
+    last_seq: i64,
+) -> Result<ConsumerOffset, PipelineError> {
+    let offset = sqlx::query_as!(
+        ConsumerOffset,
+        r#"
+        INSERT INTO consumer_offsets (consumer, last_seq)
+        VALUES ($1, $2)
+        ON CONFLICT (consumer) DO UPDATE
+        SET last_seq = EXCLUDED.last_seq, updated_at = now()
+        RETURNING consumer, last_seq,
+                  GREATEST((SELECT COALESCE(max(seq), 0) FROM outbox_events) - last_seq, 0)
+                      AS "lag!",
+                  updated_at
+        "#,
+        consumer,
+        last_seq,
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(offset)
+}
+
+/// Every consumer's offset and how far it trails the head, by name.
+pub async fn list_offsets(pool: &PgPool) -> Result<Vec<ConsumerOffset>, PipelineError> {
+    let rows = sqlx::query_as!(
+        ConsumerOffset,
+        r#"
+        SELECT c.consumer, c.last_seq,
+               GREATEST(h.seq - c.last_seq, 0) AS "lag!", c.updated_at
+        FROM consumer_offsets c,
+             (SELECT COALESCE(max(seq), 0) AS seq FROM outbox_events) h
+        ORDER BY c.consumer
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
@@ -0,0 +1,70 @@
+use crate::error::PipelineError;
+
+/// Leading byte of a stored `provider_events.payload`: how the rest is encoded.
+const RAW: u8 = 0;
+const ZSTD: u8 = 1;
+
+const ZSTD_LEVEL: i32 = 3;
+
+/// Encode a payload for storage. Compressed unless that wouldn't make it
+/// smaller, as with tiny test payloads.
+pub fn encode(payload: &serde_json::Value) -> Result<Vec<u8>, PipelineError> {
+    let json = serde_json::to_vec(payload)?;
+    let compressed = zstd::bulk::compress(&json, ZSTD_LEVEL)
+        .map_err(|e| PipelineError::Database(sqlx::Error::Encode(e.into())))?;
+    let (format, body) = if compressed.len() < json.len() {
+        (ZSTD, compressed)
+    } else {
+        (RAW, json)
+    };
+    let mut stored = Vec::with_capacity(body.len() + 1);
+    stored.push(format);
+    stored.extend_from_slice(&body);
+    Ok(stored)
+}
+
+/// Decode a stored payload, whichever format it was written in.
+pub fn decode(stored: &[u8]) -> Result<serde_json::Value, PipelineError> {
+    match stored.split_first() {
+        Some((&RAW, json)) => Ok(serde_json::from_slice(json)?),
+        Some((&ZSTD, body)) => {
+            let json = zstd::stream::decode_all(body).map_err(|e| decode_error(e.into()))?;
+            Ok(serde_json::from_slice(&json)?)
+        }
+        Some((format, _)) => Err(decode_error(
+            format!("unknown payload format {format}").into(),
+        )),
+        None => Err(decode_error("empty payload".into())),
+    }
+}
+
+/// Whether `stored` was written uncompressed.
+pub fn is_raw(stored: &[u8]) -> bool {
+    stored.first() == Some(&RAW)
+}
+
+fn decode_error(e: sqlx::error::BoxDynError) -> PipelineError {
+    PipelineError::Database(sqlx::Error::Decode(e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrips_both_formats() {
+        let small = serde_json::json!({"id": "evt_1"});
+        let stored = encode(&small).unwrap();
+        assert!(is_raw(&stored));
+        assert_eq!(decode(&stored).unwrap(), small);
+
+        let large = serde_json::json!({"data": {"object": {"description": "x".repeat(4096)}}});
+        let stored = encode(&large).unwrap();
+        assert_eq!(stored[0], ZSTD);
+        assert!(stored.len() < 200);
+        assert_eq!(decode(&stored).unwrap(), large);
+
+        assert!(decode(&[]).is_err());
+        assert!(decode(&[7, b'{', b'}']).is_err());
+    }
+}
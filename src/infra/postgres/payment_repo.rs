@@ -1,52 +1,99 @@
 use {
     crate::domain::{
-        error::PipelineError,
-        id::ExternalId,
+        id::{ExternalId, TenantId},
         money::Currency,
         payment::{
-            ExistingPayment, NewPayment, PaymentDirection, PaymentFilters, PaymentStatus,
-            PaymentView,
+            ExistingPayment, NewPayment, NewProviderEvent, PaymentDirection, PaymentFailure,
+            PaymentFilters, PaymentReceipt, PaymentStatus, PaymentView,
         },
+        refund::ParentPayment,
+        rollup::DisputeSnapshot,
+        timeline::TimelineEntry,
     },
+    crate::error::PipelineError,
+    crate::infra::postgres::{payload_codec, rollup_repo, shadow_repo},
+    futures_util::{Stream, StreamExt},
     sqlx::PgPool,
     uuid::Uuid,
 };
 
-/// Record a Stripe event for dedup. Returns `true` if newly inserted, `false` if duplicate.
+/// Record a provider event for dedup, keyed by `(source, tenant_id,
+/// event_id)`. Returns `true` if newly inserted, `false` if duplicate. A new
+/// dispute event also records the dispute's state for the dispute rollup.
+#[tracing::instrument(
+    skip_all,
+    fields(event_id = event.event_id, external_id = event.object_id)
+)]
 pub async fn insert_provider_event(
     tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
-    event_id: &str,
-    object_id: &str,
-    event_type: &str,
-    provider_ts: i64,
-    payload: &serde_json::Value,
+    event: &NewProviderEvent<'_>,
 ) -> Result<bool, PipelineError> {
+    let payload = payload_codec::encode(event.payload)?;
     let inserted: Option<bool> = sqlx::query_scalar!(
         r#"
-        INSERT INTO provider_events (event_id, object_id, event_type, provider_ts, payload)
-        VALUES ($1, $2, $3, $4, $5)
-        ON CONFLICT (event_id) DO NOTHING
+        INSERT INTO provider_events
+            (source, tenant_id, event_id, object_id, event_type, provider_ts, payload)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        ON CONFLICT (event_id, source, tenant_id) DO NOTHING
         RETURNING true AS "inserted!"
         "#,
-        event_id,
-        object_id,
-        event_type,
-        provider_ts,
+        event.source,
+        event.tenant_id,
+        event.event_id,
+        event.object_id,
+        event.event_type,
+        event.provider_ts,
         payload,
     )
     .fetch_optional(&mut **tx)
     .await?;
 
+    if inserted.is_some()
+        && event.event_type.starts_with("charge.dispute.")
+        && let Some(dispute) = DisputeSnapshot::from_event(event.payload)
+    {
+        rollup_repo::record_dispute(tx, &dispute, event.provider_ts).await?;
+    }
     Ok(inserted.is_some())
 }
 
+/// Whether the event was already received: the dedup check of
+/// `insert_provider_event`, without the insert.
+pub async fn provider_event_exists(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    source: &str,
+    tenant_id: Option<&str>,
+    event_id: &str,
+) -> Result<bool, PipelineError> {
+    let exists = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS(
+            SELECT 1 FROM provider_events
+            WHERE event_id = $1 AND source = $2 AND tenant_id IS NOT DISTINCT FROM $3
+        ) AS "exists!"
+        "#,
+        event_id,
+        source,
+        tenant_id,
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+    Ok(exists)
+}
+
 /// Fetch the current state of a payment by external_id.
 pub async fn get_existing_payment(
     tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     external_id: &str,
 ) -> Result<Option<ExistingPayment>, PipelineError> {
+    if shadow_repo::get_stage(&mut **tx).await?.reads_new() {
+        return shadow_repo::get_existing_payment(tx, external_id).await;
+    }
     let row = sqlx::query!(
-        "SELECT id, status FROM payments WHERE external_id = $1",
+        r#"
+        SELECT id, status, tenant_id, last_provider_ts, last_event_id, last_request_id
+        FROM payments WHERE external_id = $1
+        "#,
         external_id,
     )
     .fetch_optional(&mut **tx)
@@ -56,11 +103,126 @@ pub async fn get_existing_payment(
         None => Ok(None),
         Some(r) => {
             let status = PaymentStatus::try_from(r.status.as_str())?;
-            Ok(Some(ExistingPayment { id: r.id, status }))
+            Ok(Some(ExistingPayment {
+                id: r.id,
+                status,
+                tenant_id: r.tenant_id,
+                last_provider_ts: r.last_provider_ts,
+                last_event_id: r.last_event_id,
+                last_request_id: r.last_request_id,
+            }))
         }
     }
 }
 
+/// A payment's stored metadata, if it exists.
+pub async fn get_payment_metadata(
+    pool: &PgPool,
+    external_id: &str,
+) -> Result<Option<serde_json::Value>, PipelineError> {
+    let metadata = sqlx::query_scalar!(
+        "SELECT metadata FROM payments WHERE external_id = $1",
+        external_id
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(metadata)
+}
+
+/// Stored payload of a provider event, if we've seen it.
+pub async fn get_provider_event_payload(
+    pool: &PgPool,
+    source: &str,
+    event_id: &str,
+) -> Result<Option<serde_json::Value>, PipelineError> {
+    let payload = sqlx::query_scalar!(
+        "SELECT payload FROM provider_events WHERE event_id = $1 AND source = $2",
+        event_id,
+        source,
+    )
+    .fetch_optional(pool)
+    .await?;
+    payload.as_deref().map(payload_codec::decode).transpose()
+}
+
+/// Provider events about a payment, oldest first: those for its object
+/// and any other event its audit trail points to.
+pub async fn get_payment_events(
+    pool: &PgPool,
+    external_id: &str,
+    limit: i64,
+) -> Result<Vec<TimelineEntry>, PipelineError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT event_id, event_type, provider_ts, payload, received_at
+        FROM provider_events
+        WHERE object_id = $1
+           OR (event_id, source) IN (
+               SELECT event_id, source FROM audit_log WHERE external_id = $1
+           )
+        ORDER BY received_at, provider_ts, event_id
+        LIMIT $2
+        "#,
+        external_id,
+        limit,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.into_iter()
+        .map(|r| {
+            Ok(TimelineEntry::ProviderEvent {
+                at: r.received_at,
+                payload: payload_codec::decode(&r.payload)?,
+                event_id: r.event_id,
+                event_type: r.event_type,
+                provider_ts: r.provider_ts,
+            })
+        })
+        .collect()
+}
+
+/// Rewrite up to `limit` payloads still stored uncompressed, in event id
+/// order after `after`. Returns how many were rewritten and the last event
+/// id looked at (`None` once there are no more).
+pub async fn compress_raw_payloads(
+    pool: &PgPool,
+    after: &str,
+    limit: i64,
+) -> Result<(u64, Option<String>), PipelineError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT source, event_id, payload FROM provider_events
+        WHERE event_id > $1 AND get_byte(payload, 0) = 0
+        ORDER BY event_id
+        LIMIT $2
+        "#,
+        after,
+        limit,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let last = rows.last().map(|r| r.event_id.clone());
+    let mut compressed = 0;
+    for r in rows {
+        let encoded = payload_codec::encode(&payload_codec::decode(&r.payload)?)?;
+        if payload_codec::is_raw(&encoded) {
+            continue;
+        }
+        sqlx::query!(
+            "UPDATE provider_events SET payload = $3 WHERE event_id = $1 AND source = $2",
+            r.event_id,
+            r.source,
+            encoded,
+        )
+        .execute(pool)
+        .await?;
+        compressed += 1;
+    }
+    Ok((compressed, last))
+}
+
 /// Look up a payment's UUID by external_id (for linking audit entries).
 pub async fn find_payment_id(
     tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
@@ -75,19 +237,95 @@ pub async fn find_payment_id(
     Ok(id)
 }
 
+/// What a refund checks of the parent payment `external_id`, if it exists.
+pub async fn get_parent_payment(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    external_id: &str,
+) -> Result<Option<ParentPayment>, PipelineError> {
+    let row = sqlx::query!(
+        "SELECT id, direction, currency FROM payments WHERE external_id = $1",
+        external_id
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+    row.map(|r| {
+        Ok(ParentPayment {
+            id: r.id,
+            direction: PaymentDirection::try_from(r.direction.as_str())?,
+            currency: Currency::try_from(r.currency.as_str())?,
+        })
+    })
+    .transpose()
+}
+
+pub async fn payment_exists(pool: &PgPool, external_id: &str) -> Result<bool, PipelineError> {
+    let exists = sqlx::query_scalar!(
+        r#"SELECT EXISTS(SELECT 1 FROM payments WHERE external_id = $1) AS "exists!""#,
+        external_id
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(exists)
+}
+
+/// The tenant of payment `external_id`: `Some(None)` for the platform's
+/// own, `None` if it doesn't exist.
+pub async fn find_tenant_id(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    external_id: &str,
+) -> Result<Option<Option<String>>, PipelineError> {
+    let tenant_id = sqlx::query_scalar!(
+        "SELECT tenant_id FROM payments WHERE external_id = $1",
+        external_id
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+    Ok(tenant_id)
+}
+
+/// Whether `external_id` exists and belongs to `tenant_id`.
+pub async fn payment_in_tenant(
+    pool: &PgPool,
+    external_id: &str,
+    tenant_id: &str,
+) -> Result<bool, PipelineError> {
+    let exists = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS(
+            SELECT 1 FROM payments WHERE external_id = $1 AND tenant_id = $2
+        ) AS "exists!"
+        "#,
+        external_id,
+        tenant_id,
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(exists)
+}
+
 /// Insert a brand-new payment row.
+#[tracing::instrument(
+    skip_all,
+    fields(event_id = payment.last_event_id(), external_id = payment.external_id())
+)]
 pub async fn insert_payment(
     tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     payment: &NewPayment,
 ) -> Result<(), PipelineError> {
     let pg_amount: i64 = payment.money().amount().cents();
+    let failure = payment.failure().cloned().unwrap_or_default();
+    let receipt = payment.receipt().cloned().unwrap_or_default();
     sqlx::query!(
         r#"
         INSERT INTO payments
             (id, external_id, source, event_type, direction,
              amount, currency, status, metadata, raw_event,
-             last_event_id, parent_external_id, last_provider_ts)
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+             last_event_id, parent_external_id, last_provider_ts,
+             failure_code, decline_code, failure_message, network_advice_code,
+             authorized_amount, statement_descriptor, receipt_email, receipt_url, tenant_id,
+             last_request_id)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18,
+                $19, $20, $21, $22, $23)
         "#,
         payment.id(),
         payment.external_id(),
@@ -102,9 +340,20 @@ pub async fn insert_payment(
         payment.last_event_id(),
         payment.parent_external_id(),
         payment.provider_ts(),
+        failure.code,
+        failure.decline_code,
+        failure.message,
+        failure.network_advice_code,
+        payment.authorized_amount().map(|a| a.cents()),
+        receipt.statement_descriptor,
+        receipt.receipt_email,
+        receipt.receipt_url,
+        payment.tenant_id().map(|t| t.as_str()),
+        payment.request_id(),
     )
     .execute(&mut **tx)
     .await?;
+    shadow_repo::mirror_payment(tx, payment.id()).await?;
     Ok(())
 }
 
@@ -112,16 +361,28 @@ pub async fn insert_payment(
 // NOTE: raw_event is intentionally NOT updated here.
 // It preserves the creation snapshot; latest event payload
 // is always available in provider_events by last_event_id.
+#[tracing::instrument(
+    skip_all,
+    fields(event_id = payment.last_event_id(), external_id = payment.external_id())
+)]
 pub async fn update_payment_status(
     tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     id: Uuid,
     payment: &NewPayment,
 ) -> Result<(), PipelineError> {
+    let failure = payment.failure().cloned().unwrap_or_default();
+    let receipt = payment.receipt().cloned().unwrap_or_default();
     sqlx::query!(
         r#"
         UPDATE payments
         SET status = $1, event_type = $2, metadata = $3,
-            last_event_id = $4, last_provider_ts = $5, updated_at = now()
+            last_event_id = $4, last_provider_ts = $5, last_request_id = $15,
+            failure_code = $7, decline_code = $8, failure_message = $9,
+            network_advice_code = $10,
+            authorized_amount = COALESCE($11, authorized_amount),
+            statement_descriptor = COALESCE($12, statement_descriptor),
+            receipt_email = COALESCE($13, receipt_email),
+            receipt_url = COALESCE($14, receipt_url), updated_at = now()
         WHERE id = $6
         "#,
         payment.status().as_str(),
@@ -130,27 +391,117 @@ pub async fn update_payment_status(
         payment.last_event_id(),
         payment.provider_ts(),
         id,
+        failure.code,
+        failure.decline_code,
+        failure.message,
+        failure.network_advice_code,
+        payment.authorized_amount().map(|a| a.cents()),
+        receipt.statement_descriptor,
+        receipt.receipt_email,
+        receipt.receipt_url,
+        payment.request_id(),
+    )
+    .execute(&mut **tx)
+    .await?;
+    shadow_repo::mirror_payment(tx, id).await?;
+    Ok(())
+}
+
+/// Record the latest failure details without a status change — a pending
+/// PaymentIntent collects a new `last_payment_error` on every declined attempt.
+pub async fn update_failure(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    id: Uuid,
+    failure: &PaymentFailure,
+) -> Result<(), PipelineError> {
+    sqlx::query!(
+        r#"
+        UPDATE payments
+        SET failure_code = $2, decline_code = $3, failure_message = $4,
+            network_advice_code = $5
+        WHERE id = $1
+        "#,
+        id,
+        failure.code,
+        failure.decline_code,
+        failure.message,
+        failure.network_advice_code,
+    )
+    .execute(&mut **tx)
+    .await?;
+    shadow_repo::mirror_payment(tx, id).await?;
+    Ok(())
+}
+
+/// Record a new authorized amount without a status change — an uncaptured
+/// PaymentIntent's capturable amount moves on incremental authorizations.
+pub async fn update_authorized_amount(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    id: Uuid,
+    cents: i64,
+) -> Result<(), PipelineError> {
+    sqlx::query!(
+        "UPDATE payments SET authorized_amount = $2 WHERE id = $1",
+        id,
+        cents,
+    )
+    .execute(&mut **tx)
+    .await?;
+    shadow_repo::mirror_payment(tx, id).await?;
+    Ok(())
+}
+
+/// Fill in receipt details without a status change — the charge behind a
+/// PaymentIntent often reports its descriptor and receipt after the intent
+/// settled. Fields the provider left out keep their stored value.
+pub async fn update_receipt(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    id: Uuid,
+    receipt: &PaymentReceipt,
+) -> Result<(), PipelineError> {
+    sqlx::query!(
+        r#"
+        UPDATE payments
+        SET statement_descriptor = COALESCE($2, statement_descriptor),
+            receipt_email = COALESCE($3, receipt_email),
+            receipt_url = COALESCE($4, receipt_url)
+        WHERE id = $1
+        "#,
+        id,
+        receipt.statement_descriptor,
+        receipt.receipt_email,
+        receipt.receipt_url,
     )
     .execute(&mut **tx)
     .await?;
+    shadow_repo::mirror_payment(tx, id).await?;
     Ok(())
 }
 
-/// Update event tracking + advance timestamp (same-status, anomaly).
+/// Update event tracking + advance timestamp (same-status, anomaly). The
+/// last event id (and its request) only moves with the newest timestamp, so
+/// an older event never shows as the last one.
 pub async fn touch_event_with_ts(
     tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     id: Uuid,
-    event_id: &str,
-    provider_ts: i64,
+    payment: &NewPayment,
 ) -> Result<(), PipelineError> {
     sqlx::query!(
-        "UPDATE payments SET last_event_id = $1, last_provider_ts = GREATEST(last_provider_ts, $2), updated_at = now() WHERE id = $3",
-        event_id,
-        provider_ts,
+        r#"
+        UPDATE payments
+        SET last_event_id = CASE WHEN $2 >= last_provider_ts THEN $1 ELSE last_event_id END,
+            last_request_id = CASE WHEN $2 >= last_provider_ts THEN $4 ELSE last_request_id END,
+            last_provider_ts = GREATEST(last_provider_ts, $2), updated_at = now()
+        WHERE id = $3
+        "#,
+        payment.last_event_id(),
+        payment.provider_ts(),
         id,
+        payment.request_id(),
     )
     .execute(&mut **tx)
     .await?;
+    shadow_repo::mirror_payment(tx, id).await?;
     Ok(())
 }
 
@@ -158,14 +509,27 @@ pub async fn get_payment_by_id(
     pool: &PgPool,
     id: ExternalId,
 ) -> Result<Option<PaymentView>, PipelineError> {
+    if shadow_repo::get_stage(pool).await?.reads_new() {
+        return shadow_repo::get_payment_by_id(pool, &id).await;
+    }
     let row = sqlx::query!(
         r#"SELECT 
             external_id, 
             source, 
+            tenant_id,
             status, 
             amount, 
             currency, 
             direction, 
+            parent_external_id,
+            failure_code,
+            decline_code,
+            failure_message,
+            network_advice_code,
+            authorized_amount,
+            statement_descriptor,
+            receipt_email,
+            receipt_url,
             updated_at, 
             created_at
            FROM payments
@@ -181,16 +545,173 @@ pub async fn get_payment_by_id(
         Some(r) => Ok(Some(PaymentView {
             id: ExternalId::new(r.external_id)?,
             source: r.source,
+            tenant_id: r.tenant_id.map(TenantId::new).transpose()?,
             status: PaymentStatus::try_from(r.status.as_str())?,
             amount: r.amount,
             currency: Currency::try_from(r.currency.as_str())?,
             direction: PaymentDirection::try_from(r.direction.as_str())?,
+            parent_external_id: r.parent_external_id.map(ExternalId::new).transpose()?,
+            failure: PaymentFailure::from_parts(
+                r.failure_code,
+                r.decline_code,
+                r.failure_message,
+                r.network_advice_code,
+            ),
+            authorized_amount: r.authorized_amount,
+            receipt: PaymentReceipt::from_parts(
+                r.statement_descriptor,
+                r.receipt_email,
+                r.receipt_url,
+            ),
             created_at: r.created_at,
             updated_at: r.updated_at,
         })),
     }
 }
 
+/// `get_payment_by_id` for each of `ids` at once; those that exist, in no
+/// particular order. Follows the `payments_v2` stage as lookups do.
+pub async fn get_payments_by_ids(
+    pool: &PgPool,
+    ids: &[String],
+) -> Result<Vec<PaymentView>, PipelineError> {
+    if shadow_repo::get_stage(pool).await?.reads_new() {
+        return shadow_repo::get_payments_by_ids(pool, ids).await;
+    }
+    let rows = sqlx::query!(
+        r#"SELECT
+            external_id,
+            source,
+            tenant_id,
+            status,
+            amount,
+            currency,
+            direction,
+            parent_external_id,
+            failure_code,
+            decline_code,
+            failure_message,
+            network_advice_code,
+            authorized_amount,
+            statement_descriptor,
+            receipt_email,
+            receipt_url,
+            updated_at,
+            created_at
+           FROM payments
+           WHERE external_id = ANY($1)
+        "#,
+        ids,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.into_iter()
+        .map(|r| {
+            Ok(PaymentView {
+                id: ExternalId::new(r.external_id)?,
+                source: r.source,
+                tenant_id: r.tenant_id.map(TenantId::new).transpose()?,
+                status: PaymentStatus::try_from(r.status.as_str())?,
+                amount: r.amount,
+                currency: Currency::try_from(r.currency.as_str())?,
+                direction: PaymentDirection::try_from(r.direction.as_str())?,
+                parent_external_id: r.parent_external_id.map(ExternalId::new).transpose()?,
+                failure: PaymentFailure::from_parts(
+                    r.failure_code,
+                    r.decline_code,
+                    r.failure_message,
+                    r.network_advice_code,
+                ),
+                authorized_amount: r.authorized_amount,
+                receipt: PaymentReceipt::from_parts(
+                    r.statement_descriptor,
+                    r.receipt_email,
+                    r.receipt_url,
+                ),
+                created_at: r.created_at,
+                updated_at: r.updated_at,
+            })
+        })
+        .collect()
+}
+
+/// Refunds and disputes against each of `parents`, at most `limit` each:
+/// grouped by parent, each newest first. A listing, so on `payments`.
+pub async fn get_children_of(
+    pool: &PgPool,
+    parents: &[String],
+    limit: i64,
+) -> Result<Vec<PaymentView>, PipelineError> {
+    let rows = sqlx::query!(
+        r#"
+            SELECT
+                c.external_id AS "external_id!",
+                c.source AS "source!",
+                c.tenant_id,
+                c.status AS "status!",
+                c.amount AS "amount!",
+                c.currency AS "currency!",
+                c.direction AS "direction!",
+                c.parent_external_id,
+                c.failure_code,
+                c.decline_code,
+                c.failure_message,
+                c.network_advice_code,
+                c.authorized_amount,
+                c.statement_descriptor,
+                c.receipt_email,
+                c.receipt_url,
+                c.updated_at AS "updated_at!",
+                c.created_at AS "created_at!"
+            FROM unnest($1::text[]) AS k(parent)
+            CROSS JOIN LATERAL (
+                SELECT * FROM payments p
+                WHERE p.parent_external_id = k.parent AND p.direction = 'outbound'
+                ORDER BY p.created_at DESC, p.external_id DESC
+                LIMIT $2
+            ) c
+            ORDER BY k.parent, c.created_at DESC, c.external_id DESC
+        "#,
+        parents,
+        limit,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.into_iter()
+        .map(|r| {
+            Ok(PaymentView {
+                id: ExternalId::new(r.external_id)?,
+                source: r.source,
+                tenant_id: r.tenant_id.map(TenantId::new).transpose()?,
+                status: PaymentStatus::try_from(r.status.as_str())?,
+                amount: r.amount,
+                currency: Currency::try_from(r.currency.as_str())?,
+                direction: PaymentDirection::try_from(r.direction.as_str())?,
+                parent_external_id: r.parent_external_id.map(ExternalId::new).transpose()?,
+                failure: PaymentFailure::from_parts(
+                    r.failure_code,
+                    r.decline_code,
+                    r.failure_message,
+                    r.network_advice_code,
+                ),
+                authorized_amount: r.authorized_amount,
+                receipt: PaymentReceipt::from_parts(
+                    r.statement_descriptor,
+                    r.receipt_email,
+                    r.receipt_url,
+                ),
+                created_at: r.created_at,
+                updated_at: r.updated_at,
+            })
+        })
+        .collect()
+}
+
+/// Payments matching `filters`. Lists and searches stay on `payments`
+/// whatever the `payments_v2` stage: its filter and search indexes aren't
+/// built on `payments_v2` until it replaces the old table.
 pub async fn get_list_payments(
     pool: &PgPool,
     filters: PaymentFilters,
@@ -199,15 +720,30 @@ pub async fn get_list_payments(
     let currency = filters.currency.map(|c| c.as_str().to_owned());
     let direction = filters.direction.map(|d| d.as_str().to_owned());
     let limit = filters.limit.expect("limit must be set by service layer") as i64;
+    let (after_ts, after_id) = filters
+        .cursor
+        .map(|c| (c.created_at, c.external_id))
+        .unzip();
+    let reference_pattern = filters.reference.as_deref().map(contains_pattern);
     let rows = sqlx::query!(
         r#"
             SELECT
                 external_id,
                 source,
+                tenant_id,
                 status,
                 amount,
                 currency,
                 direction,
+                parent_external_id,
+                failure_code,
+                decline_code,
+                failure_message,
+                network_advice_code,
+                authorized_amount,
+                statement_descriptor,
+                receipt_email,
+                receipt_url,
                 updated_at,
                 created_at
             FROM payments
@@ -219,7 +755,16 @@ pub async fn get_list_payments(
                 AND ($6::text IS NULL OR direction = $6)
                 AND ($7::timestamptz IS NULL OR created_at >= $7)
                 AND ($8::timestamptz IS NULL OR created_at <= $8)
-            ORDER BY created_at DESC
+                AND ($11::text IS NULL OR decline_code = $11)
+                AND ($12::text IS NULL OR parent_external_id = $12)
+                AND ($13::timestamptz IS NULL OR (created_at, external_id) < ($13, $14::text))
+                AND ($15::text IS NULL OR search_text LIKE $16 OR $15 <% search_text)
+                AND ($17::text IS NULL OR tenant_id = $17)
+                AND ($18::text IS NULL OR (
+                    SELECT COUNT(*) FROM payment_tags t
+                    WHERE t.payment_id = payments.id AND t.tag = ANY(string_to_array($18, ','))
+                ) = cardinality(string_to_array($18, ',')))
+            ORDER BY created_at DESC, external_id DESC
             LIMIT $9 OFFSET $10
         "#,
         filters.source,
@@ -232,6 +777,14 @@ pub async fn get_list_payments(
         filters.end_date,
         limit,
         filters.offset,
+        filters.decline_code,
+        filters.parent_external_id,
+        after_ts,
+        after_id,
+        filters.reference.as_deref(),
+        reference_pattern.as_deref(),
+        filters.tenant_id,
+        filters.tag,
     )
     .fetch_all(pool)
     .await?;
@@ -241,13 +794,169 @@ pub async fn get_list_payments(
             Ok(PaymentView {
                 id: ExternalId::new(r.external_id)?,
                 source: r.source,
+                tenant_id: r.tenant_id.map(TenantId::new).transpose()?,
                 status: PaymentStatus::try_from(r.status.as_str())?,
                 amount: r.amount,
                 currency: Currency::try_from(r.currency.as_str())?,
                 direction: PaymentDirection::try_from(r.direction.as_str())?,
+                parent_external_id: r.parent_external_id.map(ExternalId::new).transpose()?,
+                failure: PaymentFailure::from_parts(
+                    r.failure_code,
+                    r.decline_code,
+                    r.failure_message,
+                    r.network_advice_code,
+                ),
+                authorized_amount: r.authorized_amount,
+                receipt: PaymentReceipt::from_parts(
+                    r.statement_descriptor,
+                    r.receipt_email,
+                    r.receipt_url,
+                ),
                 created_at: r.created_at,
                 updated_at: r.updated_at,
             })
         })
         .collect()
 }
+
+/// `LIKE` pattern matching `term` anywhere, with its own wildcards escaped.
+/// `search_text` is lowercased, so `term` should be too.
+fn contains_pattern(term: &str) -> String {
+    let escaped = term
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{escaped}%")
+}
+
+/// Every payment matching `filters`, newest first, read off a server-side
+/// cursor row by row instead of collected. `limit`, `offset` and `cursor`
+/// are ignored. Dropping the stream mid-way releases the connection. Like
+/// `get_list_payments`, it reads `payments` at any stage.
+pub fn stream_payments<'a>(
+    pool: &'a PgPool,
+    filters: &'a PaymentFilters,
+) -> impl Stream<Item = Result<PaymentView, PipelineError>> + Send + 'a {
+    sqlx::query!(
+        r#"
+            SELECT
+                external_id,
+                source,
+                tenant_id,
+                status,
+                amount,
+                currency,
+                direction,
+                parent_external_id,
+                failure_code,
+                decline_code,
+                failure_message,
+                network_advice_code,
+                authorized_amount,
+                statement_descriptor,
+                receipt_email,
+                receipt_url,
+                updated_at,
+                created_at
+            FROM payments
+            WHERE ($1::text IS NULL OR source = $1)
+                AND ($2::text IS NULL OR status = $2)
+                AND ($3::bigint IS NULL OR amount >= $3)
+                AND ($4::bigint IS NULL OR amount <= $4)
+                AND ($5::text IS NULL OR currency = $5)
+                AND ($6::text IS NULL OR direction = $6)
+                AND ($7::timestamptz IS NULL OR created_at >= $7)
+                AND ($8::timestamptz IS NULL OR created_at <= $8)
+                AND ($9::text IS NULL OR decline_code = $9)
+                AND ($10::text IS NULL OR parent_external_id = $10)
+                AND ($11::text IS NULL OR search_text LIKE $12 OR $11 <% search_text)
+                AND ($13::text IS NULL OR tenant_id = $13)
+                AND ($14::text IS NULL OR (
+                    SELECT COUNT(*) FROM payment_tags t
+                    WHERE t.payment_id = payments.id AND t.tag = ANY(string_to_array($14, ','))
+                ) = cardinality(string_to_array($14, ',')))
+            ORDER BY created_at DESC, external_id DESC
+        "#,
+        filters.source.as_deref(),
+        filters.status.as_ref().map(|s| s.as_str()),
+        filters.amount_min,
+        filters.amount_max,
+        filters.currency.as_ref().map(|c| c.as_str()),
+        filters.direction.as_ref().map(|d| d.as_str()),
+        filters.start_date,
+        filters.end_date,
+        filters.decline_code.as_deref(),
+        filters.parent_external_id.as_deref(),
+        filters.reference.as_deref(),
+        filters.reference.as_deref().map(contains_pattern),
+        filters.tenant_id.as_deref(),
+        filters.tag.as_deref(),
+    )
+    .fetch(pool)
+    .map(|row| {
+        let r = row?;
+        Ok(PaymentView {
+            id: ExternalId::new(r.external_id)?,
+            source: r.source,
+            tenant_id: r.tenant_id.map(TenantId::new).transpose()?,
+            status: PaymentStatus::try_from(r.status.as_str())?,
+            amount: r.amount,
+            currency: Currency::try_from(r.currency.as_str())?,
+            direction: PaymentDirection::try_from(r.direction.as_str())?,
+            parent_external_id: r.parent_external_id.map(ExternalId::new).transpose()?,
+            failure: PaymentFailure::from_parts(
+                r.failure_code,
+                r.decline_code,
+                r.failure_message,
+                r.network_advice_code,
+            ),
+            authorized_amount: r.authorized_amount,
+            receipt: PaymentReceipt::from_parts(
+                r.statement_descriptor,
+                r.receipt_email,
+                r.receipt_url,
+            ),
+            created_at: r.created_at,
+            updated_at: r.updated_at,
+        })
+    })
+}
+
+/// Raw inputs for a refundable-balance computation: the parent payment plus
+/// the sums of its settled and in-flight refunds.
+pub struct RefundTotalsRow {
+    pub status: String,
+    pub direction: String,
+    pub amount: i64,
+    pub currency: String,
+    pub refunded: i64,
+    pub pending: i64,
+}
+
+/// Aggregate outbound rows linked to `external_id` via `parent_external_id`.
+pub async fn get_refund_totals(
+    conn: &mut sqlx::PgConnection,
+    external_id: &str,
+) -> Result<Option<RefundTotalsRow>, PipelineError> {
+    let row = sqlx::query_as!(
+        RefundTotalsRow,
+        r#"
+        SELECT
+            p.status,
+            p.direction,
+            p.amount,
+            p.currency,
+            COALESCE(SUM(r.amount) FILTER (WHERE r.status = 'refunded'), 0)::bigint AS "refunded!",
+            COALESCE(SUM(r.amount) FILTER (WHERE r.status = 'pending'), 0)::bigint AS "pending!"
+        FROM payments p
+        LEFT JOIN payments r
+            ON r.parent_external_id = p.external_id AND r.direction = 'outbound'
+        WHERE p.external_id = $1
+        GROUP BY p.id
+        "#,
+        external_id,
+    )
+    .fetch_optional(conn)
+    .await?;
+    Ok(row)
+}
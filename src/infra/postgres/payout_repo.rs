@@ -0,0 +1,154 @@
+use {
+    crate::{
+        domain::payout::{ExistingPayout, NewPayout, PayoutFilters, PayoutStatus, PayoutView},
+        error::PipelineError,
+    },
+    sqlx::PgPool,
+    uuid::Uuid,
+};
+
+/// Default and largest page size for `list_payouts`.
+const DEFAULT_LIMIT: i64 = 20;
+const MAX_LIMIT: i64 = 100;
+
+pub async fn get_existing_payout(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    external_id: &str,
+) -> Result<Option<ExistingPayout>, PipelineError> {
+    let row = sqlx::query!(
+        r#"
+        SELECT id, status, last_provider_ts, last_event_id, last_request_id
+        FROM payouts WHERE external_id = $1
+        "#,
+        external_id,
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+
+    row.map(|r| {
+        Ok(ExistingPayout {
+            id: r.id,
+            status: PayoutStatus::try_from(r.status.as_str())?,
+            last_provider_ts: r.last_provider_ts,
+            last_event_id: r.last_event_id,
+            last_request_id: r.last_request_id,
+        })
+    })
+    .transpose()
+}
+
+pub async fn insert_payout(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    id: Uuid,
+    payout: &NewPayout,
+) -> Result<(), PipelineError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO payouts
+            (id, external_id, source, kind, amount, currency, status, amount_reversed,
+             destination, arrival_date, failure_code, failure_message, metadata,
+             last_event_id, last_provider_ts, last_request_id)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)
+        "#,
+        id,
+        payout.external_id.as_str(),
+        &payout.source,
+        payout.kind.as_str(),
+        payout.money.amount().cents(),
+        payout.money.currency().as_str(),
+        payout.status.as_str(),
+        payout.amount_reversed.cents(),
+        payout.destination.as_deref(),
+        payout.arrival_date,
+        payout.failure_code.as_deref(),
+        payout.failure_message.as_deref(),
+        &payout.metadata,
+        payout.event_id.as_str(),
+        payout.provider_ts,
+        payout.request_id.as_deref(),
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Overwrite the payout with what `payout` says, status included. Events
+/// carry the whole object, so nothing is merged.
+pub async fn update_payout(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    id: Uuid,
+    payout: &NewPayout,
+) -> Result<(), PipelineError> {
+    sqlx::query!(
+        r#"
+        UPDATE payouts
+        SET status = $2, amount = $3, amount_reversed = $4, destination = $5,
+            arrival_date = $6, failure_code = $7, failure_message = $8, metadata = $9,
+            last_event_id = $10, last_provider_ts = $11, last_request_id = $12,
+            updated_at = now()
+        WHERE id = $1
+        "#,
+        id,
+        payout.status.as_str(),
+        payout.money.amount().cents(),
+        payout.amount_reversed.cents(),
+        payout.destination.as_deref(),
+        payout.arrival_date,
+        payout.failure_code.as_deref(),
+        payout.failure_message.as_deref(),
+        &payout.metadata,
+        payout.event_id.as_str(),
+        payout.provider_ts,
+        payout.request_id.as_deref(),
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+pub async fn get_payout(
+    pool: &PgPool,
+    external_id: &str,
+) -> Result<Option<PayoutView>, PipelineError> {
+    let view = sqlx::query_as!(
+        PayoutView,
+        r#"
+        SELECT external_id, source, kind, amount, currency, status, amount_reversed,
+               destination, arrival_date, failure_code, failure_message, metadata,
+               last_event_id, created_at, updated_at
+        FROM payouts
+        WHERE external_id = $1
+        "#,
+        external_id,
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(view)
+}
+
+/// Newest first.
+pub async fn list_payouts(
+    pool: &PgPool,
+    filters: &PayoutFilters,
+) -> Result<Vec<PayoutView>, PipelineError> {
+    let limit = filters.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let views = sqlx::query_as!(
+        PayoutView,
+        r#"
+        SELECT external_id, source, kind, amount, currency, status, amount_reversed,
+               destination, arrival_date, failure_code, failure_message, metadata,
+               last_event_id, created_at, updated_at
+        FROM payouts
+        WHERE ($1::text IS NULL OR kind = $1)
+          AND ($2::text IS NULL OR status = $2)
+        ORDER BY created_at DESC, external_id DESC
+        LIMIT $3
+        "#,
+        filters.kind.as_deref(),
+        filters.status.as_deref(),
+        limit,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(views)
+}
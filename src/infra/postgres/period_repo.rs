@@ -0,0 +1,227 @@
+use {
+    crate::{
+        domain::{
+            payment::{ExistingPayment, NewPayment},
+            period::{AdjustmentStatus, PeriodAdjustment, PeriodClose},
+        },
+        error::PipelineError,
+    },
+    chrono::{DateTime, NaiveDate, Utc},
+    sqlx::PgPool,
+    uuid::Uuid,
+};
+
+/// The period lock in force, read for an event about to be applied. Takes
+/// the periods lock shared, so a close can't commit between this read and
+/// the event's commit; `lock_periods` waits for such events to finish.
+pub async fn closed_through(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<Option<DateTime<Utc>>, PipelineError> {
+    sqlx::query!("SELECT pg_advisory_xact_lock_shared(hashtextextended('accounting_periods', 0))")
+        .execute(&mut **tx)
+        .await?;
+    let through = sqlx::query_scalar!(
+        "SELECT max(closed_through) FROM accounting_periods WHERE reopened_at IS NULL"
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+    Ok(through)
+}
+
+/// Take the periods lock for a close or reopen, and read the latest close
+/// still in force.
+pub async fn lock_periods(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<Option<PeriodClose>, PipelineError> {
+    sqlx::query!("SELECT pg_advisory_xact_lock(hashtextextended('accounting_periods', 0))")
+        .execute(&mut **tx)
+        .await?;
+    let latest = sqlx::query_as!(
+        PeriodClose,
+        r#"
+        SELECT id, business_date, closed_through, reason, closed_by, closed_at,
+               reopened_by, reopened_at, reopen_reason
+        FROM accounting_periods
+        WHERE reopened_at IS NULL
+        ORDER BY closed_through DESC, id DESC
+        LIMIT 1
+        "#
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+    Ok(latest)
+}
+
+pub async fn insert_close(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    business_date: NaiveDate,
+    closed_through: DateTime<Utc>,
+    reason: &str,
+    closed_by: &str,
+) -> Result<PeriodClose, PipelineError> {
+    let close = sqlx::query_as!(
+        PeriodClose,
+        r#"
+        INSERT INTO accounting_periods (business_date, closed_through, reason, closed_by)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id, business_date, closed_through, reason, closed_by, closed_at,
+                  reopened_by, reopened_at, reopen_reason
+        "#,
+        business_date,
+        closed_through,
+        reason,
+        closed_by,
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+    Ok(close)
+}
+
+pub async fn reopen(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    id: Uuid,
+    reason: &str,
+    reopened_by: &str,
+) -> Result<PeriodClose, PipelineError> {
+    let close = sqlx::query_as!(
+        PeriodClose,
+        r#"
+        UPDATE accounting_periods
+        SET reopened_by = $2, reopened_at = now(), reopen_reason = $3
+        WHERE id = $1
+        RETURNING id, business_date, closed_through, reason, closed_by, closed_at,
+                  reopened_by, reopened_at, reopen_reason
+        "#,
+        id,
+        reopened_by,
+        reason,
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+    Ok(close)
+}
+
+/// Every close, reopened ones included, newest first.
+pub async fn list_closes(pool: &PgPool, limit: i64) -> Result<Vec<PeriodClose>, PipelineError> {
+    let closes = sqlx::query_as!(
+        PeriodClose,
+        r#"
+        SELECT id, business_date, closed_through, reason, closed_by, closed_at,
+               reopened_by, reopened_at, reopen_reason
+        FROM accounting_periods
+        ORDER BY closed_at DESC, id DESC
+        LIMIT $1
+        "#,
+        limit,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(closes)
+}
+
+/// Hold `payment`'s move from `existing`'s status for review; `on_create`
+/// when `existing` was only just created, held at pending.
+pub async fn insert_adjustment(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    existing: &ExistingPayment,
+    payment: &NewPayment,
+    closed_through: DateTime<Utc>,
+    on_create: bool,
+) -> Result<Uuid, PipelineError> {
+    let id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO period_adjustments
+            (payment_id, external_id, source, tenant_id, event_id, event_type,
+             provider_ts, from_status, to_status, closed_through, on_create)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+        RETURNING id
+        "#,
+        existing.id,
+        payment.external_id(),
+        payment.source(),
+        existing.tenant_id,
+        payment.last_event_id(),
+        payment.event_type(),
+        payment.provider_ts(),
+        existing.status.as_str(),
+        payment.status().as_str(),
+        closed_through,
+        on_create,
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+    Ok(id)
+}
+
+/// Newest first, optionally in one status.
+pub async fn list_adjustments(
+    pool: &PgPool,
+    status: Option<AdjustmentStatus>,
+    limit: i64,
+) -> Result<Vec<PeriodAdjustment>, PipelineError> {
+    let adjustments = sqlx::query_as!(
+        PeriodAdjustment,
+        r#"
+        SELECT id, payment_id, external_id, source, tenant_id, event_id, event_type,
+               provider_ts, from_status, to_status, closed_through, on_create, status,
+               resolved_by, resolved_at, resolution_reason, created_at
+        FROM period_adjustments
+        WHERE ($1::text IS NULL OR status = $1)
+        ORDER BY created_at DESC, id DESC
+        LIMIT $2
+        "#,
+        status.map(|s| s.as_str()),
+        limit,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(adjustments)
+}
+
+pub async fn get_adjustment(
+    pool: &PgPool,
+    id: Uuid,
+) -> Result<Option<PeriodAdjustment>, PipelineError> {
+    let adjustment = sqlx::query_as!(
+        PeriodAdjustment,
+        r#"
+        SELECT id, payment_id, external_id, source, tenant_id, event_id, event_type,
+               provider_ts, from_status, to_status, closed_through, on_create, status,
+               resolved_by, resolved_at, resolution_reason, created_at
+        FROM period_adjustments
+        WHERE id = $1
+        "#,
+        id,
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(adjustment)
+}
+
+/// Settle a pending adjustment; `None` if it was already settled.
+pub async fn resolve_adjustment(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    id: Uuid,
+    status: AdjustmentStatus,
+    reason: &str,
+    resolved_by: &str,
+) -> Result<Option<PeriodAdjustment>, PipelineError> {
+    let adjustment = sqlx::query_as!(
+        PeriodAdjustment,
+        r#"
+        UPDATE period_adjustments
+        SET status = $2, resolution_reason = $3, resolved_by = $4, resolved_at = now()
+        WHERE id = $1 AND status = 'pending'
+        RETURNING id, payment_id, external_id, source, tenant_id, event_id, event_type,
+                  provider_ts, from_status, to_status, closed_through, on_create, status,
+                  resolved_by, resolved_at, resolution_reason, created_at
+        "#,
+        id,
+        status.as_str(),
+        reason,
+        resolved_by,
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+    Ok(adjustment)
+}
@@ -0,0 +1,125 @@
+use {
+    sqlx::{PgPool, Postgres, Transaction, pool::PoolConnection},
+    std::{
+        collections::{HashMap, VecDeque},
+        sync::{LazyLock, Mutex},
+        time::{Duration, Instant},
+    },
+};
+
+/// Completed waits kept for `acquire_latency`; older ones fall off.
+const KEPT: usize = 64;
+
+/// How long ingestion has been waiting for connections, in this process.
+static WAITS: LazyLock<Mutex<Waits>> = LazyLock::new(Mutex::default);
+
+#[derive(Debug, Default)]
+struct Waits {
+    next: u64,
+    /// Acquires still waiting, by when each began.
+    waiting: HashMap<u64, Instant>,
+    /// The latest completed waits: when each ended and how long it took.
+    done: VecDeque<(Instant, Duration)>,
+}
+
+impl Waits {
+    /// The longest wait of an acquire still waiting, or of one that ended
+    /// within `window` of `now`.
+    fn latency(&self, now: Instant, window: Duration) -> Duration {
+        let waiting = self
+            .waiting
+            .values()
+            .map(|began| now.saturating_duration_since(*began));
+        let done = self
+            .done
+            .iter()
+            .filter(|(ended, _)| now.saturating_duration_since(*ended) < window)
+            .map(|(_, waited)| *waited);
+        waiting.chain(done).max().unwrap_or_default()
+    }
+
+    fn finish(&mut self, id: u64, now: Instant) {
+        if let Some(began) = self.waiting.remove(&id) {
+            if self.done.len() == KEPT {
+                self.done.pop_front();
+            }
+            self.done
+                .push_back((now, now.saturating_duration_since(began)));
+        }
+    }
+}
+
+/// One acquire in flight. Dropped unfinished, as when its request is
+/// cancelled, it no longer counts as waiting.
+struct Waiting(u64);
+
+impl Waiting {
+    fn begin() -> Self {
+        let mut waits = lock();
+        let id = waits.next;
+        waits.next += 1;
+        waits.waiting.insert(id, Instant::now());
+        Self(id)
+    }
+
+    fn finish(self) {
+        lock().finish(self.0, Instant::now());
+    }
+}
+
+impl Drop for Waiting {
+    fn drop(&mut self) {
+        lock().waiting.remove(&self.0);
+    }
+}
+
+fn lock() -> std::sync::MutexGuard<'static, Waits> {
+    WAITS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// A connection for ingestion, its wait recorded for `acquire_latency`.
+pub async fn acquire(pool: &PgPool) -> Result<PoolConnection<Postgres>, sqlx::Error> {
+    let waiting = Waiting::begin();
+    let conn = pool.acquire().await?;
+    waiting.finish();
+    Ok(conn)
+}
+
+/// A transaction for ingestion, its connection's wait recorded as
+/// `acquire` does.
+pub async fn begin(pool: &PgPool) -> Result<Transaction<'static, Postgres>, sqlx::Error> {
+    let waiting = Waiting::begin();
+    let tx = pool.begin().await?;
+    waiting.finish();
+    Ok(tx)
+}
+
+/// How long ingestion is waiting for connections: the longest wait among
+/// acquires still waiting and those that ended within `window`. Zero when
+/// none did.
+pub fn acquire_latency(window: Duration) -> Duration {
+    lock().latency(Instant::now(), window)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn latency_is_the_longest_recent_or_pending_wait() {
+        let window = Duration::from_millis(500);
+        let start = Instant::now();
+        let mut waits = Waits::default();
+        assert_eq!(waits.latency(start, window), Duration::ZERO);
+
+        waits.waiting.insert(0, start);
+        waits.waiting.insert(1, start + Duration::from_millis(100));
+        let now = start + Duration::from_millis(300);
+        assert_eq!(waits.latency(now, window), Duration::from_millis(300));
+
+        waits.finish(0, now);
+        waits.finish(1, now);
+        assert_eq!(waits.latency(now, window), Duration::from_millis(300));
+        assert_eq!(waits.latency(now + window, window), Duration::ZERO);
+    }
+}
@@ -0,0 +1,258 @@
+use {
+    crate::domain::{
+        money::Currency,
+        payment::PaymentStatus,
+        reconciliation::{
+            DiscrepancyKind, DiscrepancyView, LocalSnapshot, NewDiscrepancy, ReconciliationRunView,
+        },
+    },
+    crate::error::PipelineError,
+    chrono::{DateTime, Utc},
+    sqlx::PgPool,
+    uuid::Uuid,
+};
+
+pub async fn create_run(
+    pool: &PgPool,
+    source: &str,
+    window_start: DateTime<Utc>,
+    window_end: DateTime<Utc>,
+) -> Result<Uuid, PipelineError> {
+    let id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO reconciliation_runs (source, window_start, window_end)
+        VALUES ($1, $2, $3)
+        RETURNING id
+        "#,
+        source,
+        window_start,
+        window_end,
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(id)
+}
+
+pub async fn complete_run(
+    pool: &PgPool,
+    id: Uuid,
+    payments_checked: i32,
+    discrepancies: i32,
+) -> Result<(), PipelineError> {
+    sqlx::query!(
+        r#"
+        UPDATE reconciliation_runs
+        SET status = 'completed', payments_checked = $2, discrepancies = $3, finished_at = now()
+        WHERE id = $1
+        "#,
+        id,
+        payments_checked,
+        discrepancies,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn fail_run(pool: &PgPool, id: Uuid, error: &str) -> Result<(), PipelineError> {
+    sqlx::query!(
+        r#"
+        UPDATE reconciliation_runs
+        SET status = 'failed', error = $2, finished_at = now()
+        WHERE id = $1
+        "#,
+        id,
+        error,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Local state for a batch of provider ids. Ids we don't have are simply absent.
+pub async fn get_local_snapshots(
+    pool: &PgPool,
+    external_ids: &[String],
+) -> Result<Vec<LocalSnapshot>, PipelineError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT id, external_id, status, amount, currency, created_at
+        FROM payments
+        WHERE external_id = ANY($1)
+        "#,
+        external_ids,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.into_iter()
+        .map(|r| {
+            Ok(LocalSnapshot {
+                id: r.id,
+                external_id: r.external_id,
+                status: PaymentStatus::try_from(r.status.as_str())?,
+                amount: r.amount,
+                currency: Currency::try_from(r.currency.as_str())?,
+                created_at: r.created_at,
+            })
+        })
+        .collect()
+}
+
+pub async fn insert_discrepancy(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    d: &NewDiscrepancy,
+) -> Result<(), PipelineError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO reconciliations
+            (id, payment_id, run_id, external_id, kind, status, discrepancy_details)
+        VALUES ($1, $2, $3, $4, $5, 'open', $6)
+        "#,
+        d.id,
+        d.payment_id,
+        d.run_id,
+        &d.external_id,
+        d.kind.as_str(),
+        &d.details,
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Flag a payment for review outside any run. A no-op while the same kind
+/// of finding is still open for it; returns whether a flag was added.
+pub async fn flag_for_review(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    payment_id: Uuid,
+    external_id: &str,
+    kind: DiscrepancyKind,
+    details: &serde_json::Value,
+) -> Result<bool, PipelineError> {
+    let flagged = sqlx::query!(
+        r#"
+        INSERT INTO reconciliations
+            (payment_id, external_id, kind, status, discrepancy_details)
+        SELECT $1, $2, $3, 'open', $4
+        WHERE NOT EXISTS (
+            SELECT 1 FROM reconciliations
+            WHERE external_id = $2 AND kind = $3 AND run_id IS NULL AND resolved_at IS NULL
+        )
+        "#,
+        payment_id,
+        external_id,
+        kind.as_str(),
+        details,
+    )
+    .execute(&mut **tx)
+    .await?
+    .rows_affected();
+    Ok(flagged > 0)
+}
+
+/// Open findings flagged outside a run, oldest first.
+pub async fn list_review(pool: &PgPool, limit: i64) -> Result<Vec<DiscrepancyView>, PipelineError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT id, external_id, kind, discrepancy_details, resolved_at, created_at
+        FROM reconciliations
+        WHERE run_id IS NULL AND resolved_at IS NULL
+        ORDER BY created_at, id
+        LIMIT $1
+        "#,
+        limit,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.into_iter()
+        .map(|r| {
+            Ok(DiscrepancyView {
+                id: r.id,
+                external_id: r.external_id,
+                kind: r
+                    .kind
+                    .as_deref()
+                    .map(DiscrepancyKind::try_from)
+                    .transpose()?,
+                details: r.discrepancy_details,
+                resolved_at: r.resolved_at,
+                created_at: r.created_at,
+            })
+        })
+        .collect()
+}
+
+pub async fn list_runs(
+    pool: &PgPool,
+    limit: i64,
+) -> Result<Vec<ReconciliationRunView>, PipelineError> {
+    let runs = sqlx::query_as!(
+        ReconciliationRunView,
+        r#"
+        SELECT id, source, window_start, window_end, status, payments_checked,
+               discrepancies, error, started_at, finished_at
+        FROM reconciliation_runs
+        ORDER BY started_at DESC
+        LIMIT $1
+        "#,
+        limit,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(runs)
+}
+
+pub async fn get_run(
+    pool: &PgPool,
+    id: Uuid,
+) -> Result<Option<ReconciliationRunView>, PipelineError> {
+    let run = sqlx::query_as!(
+        ReconciliationRunView,
+        r#"
+        SELECT id, source, window_start, window_end, status, payments_checked,
+               discrepancies, error, started_at, finished_at
+        FROM reconciliation_runs
+        WHERE id = $1
+        "#,
+        id,
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(run)
+}
+
+pub async fn list_discrepancies(
+    pool: &PgPool,
+    run_id: Uuid,
+) -> Result<Vec<DiscrepancyView>, PipelineError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT id, external_id, kind, discrepancy_details, resolved_at, created_at
+        FROM reconciliations
+        WHERE run_id = $1
+        ORDER BY created_at, id
+        "#,
+        run_id,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.into_iter()
+        .map(|r| {
+            Ok(DiscrepancyView {
+                id: r.id,
+                external_id: r.external_id,
+                kind: r
+                    .kind
+                    .as_deref()
+                    .map(DiscrepancyKind::try_from)
+                    .transpose()?,
+                details: r.discrepancy_details,
+                resolved_at: r.resolved_at,
+                created_at: r.created_at,
+            })
+        })
+        .collect()
+}
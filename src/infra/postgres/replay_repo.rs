@@ -0,0 +1,101 @@
+use {
+    crate::domain::replay::{ReplayEvent, ReplayRequest},
+    crate::error::PipelineError,
+    crate::infra::postgres::payload_codec,
+    sqlx::PgPool,
+};
+
+/// Provider events matching any of the request's selectors, oldest first,
+/// each with the source of the payment it belongs to.
+pub async fn select_events(
+    pool: &PgPool,
+    req: &ReplayRequest,
+    limit: i64,
+) -> Result<Vec<ReplayEvent>, PipelineError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT pe.event_id, pe.object_id, pe.event_type, pe.provider_ts, pe.payload,
+               p.source AS "source?", pe.tenant_id
+        FROM provider_events pe
+        LEFT JOIN payments p ON p.external_id = pe.object_id
+        WHERE pe.event_id = ANY($1)
+           OR pe.object_id = ANY($2)
+           OR ($3::timestamptz IS NOT NULL
+               AND pe.received_at >= $3
+               AND ($4::timestamptz IS NULL OR pe.received_at <= $4))
+        ORDER BY pe.provider_ts, pe.received_at, pe.event_id
+        LIMIT $5
+        "#,
+        &req.event_ids,
+        &req.object_ids,
+        req.since,
+        req.until,
+        limit,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.into_iter()
+        .map(|r| {
+            Ok(ReplayEvent {
+                payload: payload_codec::decode(&r.payload)?,
+                event_id: r.event_id,
+                object_id: r.object_id,
+                event_type: r.event_type,
+                provider_ts: r.provider_ts,
+                source: r.source,
+                tenant_id: r.tenant_id,
+            })
+        })
+        .collect()
+}
+
+/// One stored event. Its source comes from the payment it belongs to or,
+/// when that was never created, from the event itself.
+pub async fn get_event(
+    pool: &PgPool,
+    event_id: &str,
+) -> Result<Option<ReplayEvent>, PipelineError> {
+    let row = sqlx::query!(
+        r#"
+        SELECT pe.event_id, pe.object_id, pe.event_type, pe.provider_ts, pe.payload,
+               COALESCE(p.source, pe.source) AS "source?", pe.tenant_id
+        FROM provider_events pe
+        LEFT JOIN payments p ON p.external_id = pe.object_id
+        WHERE pe.event_id = $1
+        "#,
+        event_id,
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    row.map(|r| {
+        Ok(ReplayEvent {
+            payload: payload_codec::decode(&r.payload)?,
+            event_id: r.event_id,
+            object_id: r.object_id,
+            event_type: r.event_type,
+            provider_ts: r.provider_ts,
+            source: r.source,
+            tenant_id: r.tenant_id,
+        })
+    })
+    .transpose()
+}
+
+// Schema names can't be bound as parameters. Callers only pass names they
+// generated themselves (`replay_<uuid>`), never request input.
+
+pub async fn create_schema(pool: &PgPool, schema: &str) -> Result<(), PipelineError> {
+    sqlx::query(&format!("CREATE SCHEMA {schema}"))
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn drop_schema(pool: &PgPool, schema: &str) -> Result<(), PipelineError> {
+    sqlx::query(&format!("DROP SCHEMA IF EXISTS {schema} CASCADE"))
+        .execute(pool)
+        .await?;
+    Ok(())
+}
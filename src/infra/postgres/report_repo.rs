@@ -0,0 +1,68 @@
+use {
+    crate::domain::report::{DisputeStatsLine, FailureReasonLine, SummaryLine},
+    crate::error::PipelineError,
+    chrono::{DateTime, Utc},
+    sqlx::PgPool,
+};
+
+pub async fn get_daily_summaries(
+    pool: &PgPool,
+    bucket_start: DateTime<Utc>,
+) -> Result<Vec<SummaryLine>, PipelineError> {
+    let rows = sqlx::query_as!(
+        SummaryLine,
+        r#"
+        SELECT source, direction, currency, status, payment_count, total_amount
+        FROM daily_summaries
+        WHERE bucket_size = 'day' AND bucket_start = $1
+        ORDER BY source, direction, currency, status
+        "#,
+        bucket_start,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn get_dispute_stats(
+    pool: &PgPool,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<Vec<DisputeStatsLine>, PipelineError> {
+    let rows = sqlx::query_as!(
+        DisputeStatsLine,
+        r#"
+        SELECT bucket_start AS month, currency, card_brand, dispute_count, disputed_amount,
+               fee_amount, won_count, lost_count,
+               won_count::float8 / NULLIF(won_count + lost_count, 0) AS win_rate,
+               lost_count::float8 / NULLIF(won_count + lost_count, 0) AS loss_rate
+        FROM dispute_stats
+        WHERE bucket_size = 'month' AND bucket_start >= $1 AND bucket_start < $2
+        ORDER BY bucket_start, currency, card_brand
+        "#,
+        from,
+        to,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn get_failure_reasons(
+    pool: &PgPool,
+    bucket_start: DateTime<Utc>,
+) -> Result<Vec<FailureReasonLine>, PipelineError> {
+    let rows = sqlx::query_as!(
+        FailureReasonLine,
+        r#"
+        SELECT source, status, failure_code, decline_code, payment_count, total_amount
+        FROM failure_reason_stats
+        WHERE bucket_size = 'day' AND bucket_start = $1
+        ORDER BY payment_count DESC, failure_code, decline_code
+        "#,
+        bucket_start,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
@@ -0,0 +1,194 @@
+use {
+    crate::{
+        domain::{
+            audit::AuditRecord,
+            retention::{ArchivedEvent, RetainedTable},
+        },
+        error::PipelineError,
+        infra::postgres::payload_codec,
+    },
+    chrono::{DateTime, Utc},
+    sqlx::PgPool,
+};
+
+/// How many rows of `table` are older than `cutoff`, and the oldest one's
+/// time. Dispute events are due only once their dispute is recorded, and
+/// audit entries once no outbox waits on them; see `take_provider_events`
+/// and `take_audit_entries`.
+pub async fn due(
+    pool: &PgPool,
+    table: RetainedTable,
+    cutoff: DateTime<Utc>,
+) -> Result<(i64, Option<DateTime<Utc>>), PipelineError> {
+    let row = match table {
+        RetainedTable::ProviderEvents => sqlx::query!(
+            r#"
+                SELECT COUNT(*) AS "rows!", MIN(received_at) AS oldest
+                FROM provider_events e
+                WHERE received_at < $1
+                    AND (event_type NOT LIKE 'charge.dispute.%'
+                         OR EXISTS (SELECT 1 FROM disputes d WHERE d.dispute_id = e.object_id))
+                "#,
+            cutoff,
+        )
+        .fetch_one(pool)
+        .await
+        .map(|r| (r.rows, r.oldest))?,
+        RetainedTable::AuditLog => sqlx::query!(
+            r#"
+                SELECT COUNT(*) AS "rows!", MIN(created_at) AS oldest
+                FROM audit_log a
+                WHERE created_at < $1
+                    AND NOT EXISTS (SELECT 1 FROM audit_outbox x WHERE x.audit_id = a.id)
+                    AND NOT EXISTS (SELECT 1 FROM hook_outbox h WHERE h.audit_id = a.id)
+                    AND NOT EXISTS (SELECT 1 FROM outbox_events o, consumer_offsets c
+                                    WHERE o.audit_id = a.id
+                                        AND (o.seq IS NULL OR o.seq > c.last_seq))
+                "#,
+            cutoff,
+        )
+        .fetch_one(pool)
+        .await
+        .map(|r| (r.rows, r.oldest))?,
+    };
+    Ok(row)
+}
+
+/// Delete up to `limit` of the oldest provider events received before
+/// `cutoff` and return them, oldest first. Rows another run has locked are
+/// skipped. Nothing is gone until `tx` commits. A dispute event is kept
+/// until its dispute is recorded in `disputes`, which the dispute rollup
+/// reads instead.
+pub async fn take_provider_events(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    cutoff: DateTime<Utc>,
+    limit: i64,
+) -> Result<Vec<ArchivedEvent>, PipelineError> {
+    let rows = sqlx::query!(
+        r#"
+        DELETE FROM provider_events
+        WHERE ctid IN (
+            SELECT ctid FROM provider_events e
+            WHERE received_at < $1
+                AND (event_type NOT LIKE 'charge.dispute.%'
+                     OR EXISTS (SELECT 1 FROM disputes d WHERE d.dispute_id = e.object_id))
+            ORDER BY received_at
+            LIMIT $2
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING event_id, source, tenant_id, object_id, event_type, provider_ts,
+                  received_at, payload
+        "#,
+        cutoff,
+        limit,
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    let mut events = rows
+        .into_iter()
+        .map(|r| {
+            Ok(ArchivedEvent {
+                payload: payload_codec::decode(&r.payload)?,
+                event_id: r.event_id,
+                source: r.source,
+                tenant_id: r.tenant_id,
+                object_id: r.object_id,
+                event_type: r.event_type,
+                provider_ts: r.provider_ts,
+                received_at: r.received_at,
+            })
+        })
+        .collect::<Result<Vec<_>, PipelineError>>()?;
+    events.sort_by_key(|e| e.received_at);
+    Ok(events)
+}
+
+/// Delete up to `limit` of the oldest audit entries written before `cutoff`
+/// and return them, oldest first. Rows another run has locked are skipped.
+/// Nothing is gone until `tx` commits. An entry is kept while the audit
+/// relay has yet to ship it, a hook has yet to take it, or a feed consumer
+/// has yet to read past it; a pruned entry's feed row goes with it, except
+/// the one holding the highest seq, which numbering continues from.
+pub async fn take_audit_entries(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    cutoff: DateTime<Utc>,
+    limit: i64,
+) -> Result<Vec<AuditRecord>, PipelineError> {
+    let mut entries = sqlx::query_as!(
+        AuditRecord,
+        r#"
+        WITH taken AS (
+            DELETE FROM audit_log
+            WHERE id IN (
+                SELECT id FROM audit_log a
+                WHERE created_at < $1
+                    AND NOT EXISTS (SELECT 1 FROM audit_outbox x WHERE x.audit_id = a.id)
+                    AND NOT EXISTS (SELECT 1 FROM hook_outbox h WHERE h.audit_id = a.id)
+                    AND NOT EXISTS (SELECT 1 FROM outbox_events o, consumer_offsets c
+                                    WHERE o.audit_id = a.id
+                                        AND (o.seq IS NULL OR o.seq > c.last_seq))
+                ORDER BY created_at
+                LIMIT $2
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING id, entity_type, entity_id, external_id, event_id, source, tenant_id,
+                      action, actor, detail, created_at
+        ),
+        fed AS (
+            DELETE FROM outbox_events o
+            USING taken
+            WHERE o.audit_id = taken.id
+                AND (o.seq IS NULL OR o.seq < (SELECT max(seq) FROM outbox_events))
+        )
+        SELECT id AS "id!", entity_type AS "entity_type!", entity_id, external_id,
+               event_id AS "event_id!", source, tenant_id, action AS "action!", actor AS "actor!",
+               detail AS "detail!", created_at AS "created_at!"
+        FROM taken
+        "#,
+        cutoff,
+        limit,
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+    entries.sort_by_key(|e| (e.created_at, e.id));
+    Ok(entries)
+}
+
+/// Record that `table` has been pruned through `through`, unless it
+/// already was further.
+pub async fn advance_horizon(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    table: RetainedTable,
+    through: DateTime<Utc>,
+) -> Result<(), PipelineError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO retention_horizons (table_name, pruned_through)
+        VALUES ($1, $2)
+        ON CONFLICT (table_name) DO UPDATE
+        SET pruned_through = GREATEST(retention_horizons.pruned_through, EXCLUDED.pruned_through),
+            updated_at = now()
+        "#,
+        table.as_str(),
+        through,
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Rows of `table` up to this time may have been pruned; `None` if none
+/// ever were.
+pub async fn horizon(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    table: RetainedTable,
+) -> Result<Option<DateTime<Utc>>, PipelineError> {
+    let through = sqlx::query_scalar!(
+        "SELECT pruned_through FROM retention_horizons WHERE table_name = $1",
+        table.as_str(),
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+    Ok(through)
+}
@@ -0,0 +1,317 @@
+use {
+    crate::domain::rollup::{BucketSize, DisputeSnapshot, RollupKind, RollupWindow},
+    crate::error::PipelineError,
+    crate::infra::postgres::payload_codec,
+    chrono::{DateTime, Utc},
+};
+
+/// Serialize runs of the same rollup across replicas for the life of `tx`.
+pub async fn lock(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    kind: RollupKind,
+    bucket: BucketSize,
+) -> Result<(), PipelineError> {
+    let key = format!("rollup:{kind}:{bucket}");
+    sqlx::query!("SELECT pg_advisory_xact_lock(hashtextextended($1, 0))", key)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+pub async fn get_watermark(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    kind: RollupKind,
+    bucket: BucketSize,
+) -> Result<Option<DateTime<Utc>>, PipelineError> {
+    let watermark = sqlx::query_scalar!(
+        "SELECT watermark FROM rollup_watermarks WHERE rollup = $1 AND bucket_size = $2",
+        kind.as_str(),
+        bucket.as_str(),
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+    Ok(watermark)
+}
+
+pub async fn set_watermark(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    kind: RollupKind,
+    bucket: BucketSize,
+    watermark: DateTime<Utc>,
+) -> Result<(), PipelineError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO rollup_watermarks (rollup, bucket_size, watermark)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (rollup, bucket_size)
+        DO UPDATE SET watermark = EXCLUDED.watermark, updated_at = now()
+        "#,
+        kind.as_str(),
+        bucket.as_str(),
+        watermark,
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Replace every bucket in `window` with freshly aggregated rows.
+/// Returns the number of rows written.
+pub async fn recompute(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    kind: RollupKind,
+    bucket: BucketSize,
+    window: RollupWindow,
+) -> Result<u64, PipelineError> {
+    purge_range(tx, kind, bucket, Some(window.from), window.to).await?;
+
+    let b = bucket.as_str();
+    let result = match kind {
+        RollupKind::EventTypeStats => {
+            sqlx::query!(
+                r#"
+                INSERT INTO event_type_stats (bucket_size, bucket_start, event_type, event_count)
+                SELECT $1, date_trunc($1, received_at, 'UTC'), event_type, COUNT(*)
+                FROM provider_events
+                WHERE received_at >= $2 AND received_at < $3
+                GROUP BY 2, 3
+                "#,
+                b,
+                window.from,
+                window.to,
+            )
+            .execute(&mut **tx)
+            .await?
+        }
+        RollupKind::DeliveryStats => {
+            sqlx::query!(
+                r#"
+                INSERT INTO delivery_stats
+                    (bucket_size, bucket_start, status, claimed_by, job_count, total_attempts)
+                SELECT $1, date_trunc($1, created_at, 'UTC'), status, COALESCE(claimed_by, ''),
+                       COUNT(*), SUM(attempts)
+                FROM payment_jobs
+                WHERE created_at >= $2 AND created_at < $3
+                GROUP BY 2, 3, 4
+                "#,
+                b,
+                window.from,
+                window.to,
+            )
+            .execute(&mut **tx)
+            .await?
+        }
+        RollupKind::DailySummaries => {
+            sqlx::query!(
+                r#"
+                INSERT INTO daily_summaries
+                    (bucket_size, bucket_start, source, direction, currency, status,
+                     payment_count, total_amount)
+                SELECT $1, date_trunc($1, created_at, 'UTC'), source, direction, currency, status,
+                       COUNT(*), SUM(amount)
+                FROM payments
+                WHERE created_at >= $2 AND created_at < $3
+                GROUP BY 2, 3, 4, 5, 6
+                "#,
+                b,
+                window.from,
+                window.to,
+            )
+            .execute(&mut **tx)
+            .await?
+        }
+        RollupKind::FailureReasonStats => {
+            sqlx::query!(
+                r#"
+                INSERT INTO failure_reason_stats
+                    (bucket_size, bucket_start, source, status, failure_code, decline_code,
+                     payment_count, total_amount)
+                SELECT $1, date_trunc($1, created_at, 'UTC'), source, status,
+                       COALESCE(failure_code, 'unknown'), COALESCE(decline_code, 'unknown'),
+                       COUNT(*), SUM(amount)
+                FROM payments
+                WHERE created_at >= $2 AND created_at < $3
+                    AND (failure_code IS NOT NULL OR decline_code IS NOT NULL)
+                GROUP BY 2, 3, 4, 5, 6
+                "#,
+                b,
+                window.from,
+                window.to,
+            )
+            .execute(&mut **tx)
+            .await?
+        }
+        RollupKind::DisputeStats => {
+            record_unrecorded_disputes(tx).await?;
+            sqlx::query!(
+                r#"
+                INSERT INTO dispute_stats
+                    (bucket_size, bucket_start, currency, card_brand, dispute_count,
+                     disputed_amount, fee_amount, won_count, lost_count)
+                SELECT $1, date_trunc($1, opened_at, 'UTC'), currency, card_brand,
+                       COUNT(*), SUM(amount), SUM(fee),
+                       COUNT(*) FILTER (WHERE status = 'won'),
+                       COUNT(*) FILTER (WHERE status = 'lost')
+                FROM disputes
+                WHERE opened_at >= $2 AND opened_at < $3
+                GROUP BY 2, 3, 4
+                "#,
+                b,
+                window.from,
+                window.to,
+            )
+            .execute(&mut **tx)
+            .await?
+        }
+    };
+    Ok(result.rows_affected())
+}
+
+/// Record `dispute`'s state as of an event stamped `provider_ts`, unless
+/// a later event's is already recorded.
+pub async fn record_dispute(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    dispute: &DisputeSnapshot,
+    provider_ts: i64,
+) -> Result<(), PipelineError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO disputes
+            (dispute_id, opened_at, currency, card_brand, amount, fee, status, provider_ts)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        ON CONFLICT (dispute_id) DO UPDATE
+        SET opened_at = EXCLUDED.opened_at, currency = EXCLUDED.currency,
+            card_brand = EXCLUDED.card_brand, amount = EXCLUDED.amount,
+            fee = EXCLUDED.fee, status = EXCLUDED.status,
+            provider_ts = EXCLUDED.provider_ts, updated_at = now()
+        WHERE disputes.provider_ts <= EXCLUDED.provider_ts
+        "#,
+        dispute.dispute_id,
+        dispute.opened_at,
+        dispute.currency,
+        dispute.card_brand,
+        dispute.amount,
+        dispute.fee,
+        dispute.status,
+        provider_ts,
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Record the disputes whose events were stored before `disputes` was, by
+/// decoding those events. Once they are, only events that carry no dispute
+/// are left to decode.
+async fn record_unrecorded_disputes(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<(), PipelineError> {
+    let events = sqlx::query!(
+        r#"
+        SELECT e.provider_ts, e.payload FROM provider_events e
+        WHERE e.event_type LIKE 'charge.dispute.%'
+            AND NOT EXISTS (SELECT 1 FROM disputes d WHERE d.dispute_id = e.object_id)
+        ORDER BY e.provider_ts, e.received_at
+        "#
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    for event in events {
+        if let Some(dispute) = DisputeSnapshot::from_event(&payload_codec::decode(&event.payload)?)
+        {
+            record_dispute(tx, &dispute, event.provider_ts).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Delete buckets starting before `before` (and at or after `from`, if given).
+/// Returns the number of rows deleted.
+pub async fn purge_range(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    kind: RollupKind,
+    bucket: BucketSize,
+    from: Option<DateTime<Utc>>,
+    before: DateTime<Utc>,
+) -> Result<u64, PipelineError> {
+    let b = bucket.as_str();
+    let result = match kind {
+        RollupKind::EventTypeStats => {
+            sqlx::query!(
+                r#"
+                DELETE FROM event_type_stats
+                WHERE bucket_size = $1
+                    AND ($2::timestamptz IS NULL OR bucket_start >= $2)
+                    AND bucket_start < $3
+                "#,
+                b,
+                from,
+                before,
+            )
+            .execute(&mut **tx)
+            .await?
+        }
+        RollupKind::DeliveryStats => {
+            sqlx::query!(
+                r#"
+                DELETE FROM delivery_stats
+                WHERE bucket_size = $1
+                    AND ($2::timestamptz IS NULL OR bucket_start >= $2)
+                    AND bucket_start < $3
+                "#,
+                b,
+                from,
+                before,
+            )
+            .execute(&mut **tx)
+            .await?
+        }
+        RollupKind::DailySummaries => {
+            sqlx::query!(
+                r#"
+                DELETE FROM daily_summaries
+                WHERE bucket_size = $1
+                    AND ($2::timestamptz IS NULL OR bucket_start >= $2)
+                    AND bucket_start < $3
+                "#,
+                b,
+                from,
+                before,
+            )
+            .execute(&mut **tx)
+            .await?
+        }
+        RollupKind::FailureReasonStats => {
+            sqlx::query!(
+                r#"
+                DELETE FROM failure_reason_stats
+                WHERE bucket_size = $1
+                    AND ($2::timestamptz IS NULL OR bucket_start >= $2)
+                    AND bucket_start < $3
+                "#,
+                b,
+                from,
+                before,
+            )
+            .execute(&mut **tx)
+            .await?
+        }
+        RollupKind::DisputeStats => {
+            sqlx::query!(
+                r#"
+                DELETE FROM dispute_stats
+                WHERE bucket_size = $1
+                    AND ($2::timestamptz IS NULL OR bucket_start >= $2)
+                    AND bucket_start < $3
+                "#,
+                b,
+                from,
+                before,
+            )
+            .execute(&mut **tx)
+            .await?
+        }
+    };
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,87 @@
+use {crate::error::PipelineError, serde_json::Value, sqlx::postgres::PgListener};
+
+/// Channel notified (by the `settings_notify` trigger) whenever a setting
+/// is written or deleted. The payload is its key.
+pub const SETTINGS_CHANNEL: &str = "settings";
+
+/// A stored setting and how many times it has been written.
+pub struct SettingRow {
+    pub value: Value,
+    pub version: i64,
+}
+
+/// A dedicated connection listening on `SETTINGS_CHANNEL`.
+pub async fn listen(pool: &sqlx::PgPool) -> Result<PgListener, PipelineError> {
+    let mut listener = PgListener::connect_with(pool).await?;
+    listener.listen(SETTINGS_CHANNEL).await?;
+    Ok(listener)
+}
+
+pub async fn get(pool: &sqlx::PgPool, key: &str) -> Result<Option<SettingRow>, PipelineError> {
+    let row = sqlx::query_as!(
+        SettingRow,
+        "SELECT value, version FROM settings WHERE key = $1",
+        key
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+/// `key` as stored, locked for the life of `tx`. Locks the key even when
+/// it doesn't exist yet, so two first writes don't race.
+pub async fn get_for_update(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    key: &str,
+) -> Result<Option<SettingRow>, PipelineError> {
+    sqlx::query!(
+        "SELECT pg_advisory_xact_lock(hashtextextended('settings:' || $1, 0))",
+        key
+    )
+    .execute(&mut **tx)
+    .await?;
+    let row = sqlx::query_as!(
+        SettingRow,
+        "SELECT value, version FROM settings WHERE key = $1",
+        key
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+    Ok(row)
+}
+
+/// Write `value` under `key`, returning the new version.
+pub async fn put(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    key: &str,
+    value: &Value,
+    updated_by: &str,
+) -> Result<i64, PipelineError> {
+    let version = sqlx::query_scalar!(
+        r#"
+        INSERT INTO settings (key, value, updated_by)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (key) DO UPDATE
+        SET value = EXCLUDED.value, version = settings.version + 1,
+            updated_by = EXCLUDED.updated_by, updated_at = now()
+        RETURNING version
+        "#,
+        key,
+        value,
+        updated_by,
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+    Ok(version)
+}
+
+/// Delete `key`; `false` if it wasn't set.
+pub async fn delete(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    key: &str,
+) -> Result<bool, PipelineError> {
+    let result = sqlx::query!("DELETE FROM settings WHERE key = $1", key)
+        .execute(&mut **tx)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
@@ -0,0 +1,370 @@
+use {
+    crate::domain::{
+        id::{ExternalId, TenantId},
+        money::Currency,
+        payment::{
+            ExistingPayment, PaymentDirection, PaymentFailure, PaymentReceipt, PaymentStatus,
+            PaymentView,
+        },
+        shadow::{ShadowRow, ShadowStage},
+    },
+    crate::error::PipelineError,
+    sqlx::PgPool,
+    uuid::Uuid,
+};
+
+/// The `payments_v2` stage from the stored runtime config. Read from the
+/// database rather than the replica's cached config, so every replica
+/// switches writes and lookups at the same commit and none skips a
+/// mirrored write.
+pub async fn get_stage(executor: impl sqlx::PgExecutor<'_>) -> Result<ShadowStage, PipelineError> {
+    let stage = sqlx::query_scalar!("SELECT config->>'payments_v2' FROM runtime_config")
+        .fetch_optional(executor)
+        .await?
+        .flatten();
+    Ok(match stage {
+        Some(stage) => ShadowStage::try_from(stage.as_str())?,
+        None => ShadowStage::Off,
+    })
+}
+
+/// Write payment `id` as it now stands in `payments` into `payments_v2`,
+/// if the stage says to. Runs in the writer's transaction, right after its
+/// write, so both tables commit together.
+pub async fn mirror_payment(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    id: Uuid,
+) -> Result<(), PipelineError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO payments_v2
+            (external_id, payment_id, source, event_type, direction,
+             amount_minor, currency_code, status, metadata, raw_event,
+             last_event_id, parent_external_id, last_event_ts,
+             failure_code, decline_code, failure_message, network_advice_code,
+             authorized_amount_minor, statement_descriptor, receipt_email, receipt_url,
+             tenant_id, last_request_id, received_at, created_at, updated_at)
+        SELECT external_id, id, source, event_type, direction,
+               amount, currency, status, metadata, raw_event,
+               last_event_id, parent_external_id, last_provider_ts,
+               failure_code, decline_code, failure_message, network_advice_code,
+               authorized_amount, statement_descriptor, receipt_email, receipt_url,
+               tenant_id, last_request_id, received_at, created_at, updated_at
+        FROM payments
+        WHERE id = $1
+          AND COALESCE((SELECT config->>'payments_v2' FROM runtime_config), 'off') <> 'off'
+        ON CONFLICT (external_id) DO UPDATE
+        SET payment_id = EXCLUDED.payment_id, source = EXCLUDED.source,
+            event_type = EXCLUDED.event_type, direction = EXCLUDED.direction,
+            amount_minor = EXCLUDED.amount_minor, currency_code = EXCLUDED.currency_code,
+            status = EXCLUDED.status, metadata = EXCLUDED.metadata,
+            raw_event = EXCLUDED.raw_event, last_event_id = EXCLUDED.last_event_id,
+            parent_external_id = EXCLUDED.parent_external_id,
+            last_event_ts = EXCLUDED.last_event_ts, failure_code = EXCLUDED.failure_code,
+            decline_code = EXCLUDED.decline_code, failure_message = EXCLUDED.failure_message,
+            network_advice_code = EXCLUDED.network_advice_code,
+            authorized_amount_minor = EXCLUDED.authorized_amount_minor,
+            statement_descriptor = EXCLUDED.statement_descriptor,
+            receipt_email = EXCLUDED.receipt_email, receipt_url = EXCLUDED.receipt_url,
+            tenant_id = EXCLUDED.tenant_id, last_request_id = EXCLUDED.last_request_id,
+            received_at = EXCLUDED.received_at, created_at = EXCLUDED.created_at,
+            updated_at = EXCLUDED.updated_at
+        "#,
+        id,
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Copy up to `limit` payments, in external id order after `after`, that
+/// `payments_v2` doesn't have yet. Rows it already has are left alone: the
+/// mirrored writes keep those current, and a copy read before one of them
+/// committed would overwrite it with older values. Returns how many were
+/// copied and the last external id looked at (`None` once there are no more).
+pub async fn backfill(
+    pool: &PgPool,
+    after: &str,
+    limit: i64,
+) -> Result<(u64, Option<String>), PipelineError> {
+    let row = sqlx::query!(
+        r#"
+        WITH batch AS (
+            SELECT * FROM payments
+            WHERE external_id > $1
+            ORDER BY external_id
+            LIMIT $2
+        ),
+        copied AS (
+            INSERT INTO payments_v2
+                (external_id, payment_id, source, event_type, direction,
+                 amount_minor, currency_code, status, metadata, raw_event,
+                 last_event_id, parent_external_id, last_event_ts,
+                 failure_code, decline_code, failure_message, network_advice_code,
+                 authorized_amount_minor, statement_descriptor, receipt_email, receipt_url,
+                 tenant_id, last_request_id, received_at, created_at, updated_at)
+            SELECT external_id, id, source, event_type, direction,
+                   amount, currency, status, metadata, raw_event,
+                   last_event_id, parent_external_id, last_provider_ts,
+                   failure_code, decline_code, failure_message, network_advice_code,
+                   authorized_amount, statement_descriptor, receipt_email, receipt_url,
+                   tenant_id, last_request_id, received_at, created_at, updated_at
+            FROM batch
+            ON CONFLICT (external_id) DO NOTHING
+            RETURNING 1
+        )
+        SELECT
+            (SELECT count(*) FROM copied) AS "copied!",
+            (SELECT max(external_id) FROM batch) AS "last"
+        "#,
+        after,
+        limit,
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok((row.copied as u64, row.last))
+}
+
+/// Up to `limit` payments, in external id order after `after`, from either
+/// table, each compared column by column with its counterpart.
+pub async fn compare(
+    pool: &PgPool,
+    after: &str,
+    limit: i64,
+) -> Result<Vec<ShadowRow>, PipelineError> {
+    let rows = sqlx::query!(
+        r#"
+        WITH ids AS (
+            (SELECT external_id FROM payments WHERE external_id > $1
+             ORDER BY external_id LIMIT $2)
+            UNION
+            (SELECT external_id FROM payments_v2 WHERE external_id > $1
+             ORDER BY external_id LIMIT $2)
+            ORDER BY external_id
+            LIMIT $2
+        )
+        SELECT
+            ids.external_id AS "external_id!",
+            o.id IS NOT NULL AS "in_old!",
+            n.payment_id IS NOT NULL AS "in_new!",
+            array_remove(ARRAY[
+                CASE WHEN o.id IS DISTINCT FROM n.payment_id THEN 'id' END,
+                CASE WHEN o.source IS DISTINCT FROM n.source THEN 'source' END,
+                CASE WHEN o.event_type IS DISTINCT FROM n.event_type THEN 'event_type' END,
+                CASE WHEN o.direction IS DISTINCT FROM n.direction THEN 'direction' END,
+                CASE WHEN o.amount IS DISTINCT FROM n.amount_minor THEN 'amount' END,
+                CASE WHEN o.currency IS DISTINCT FROM n.currency_code THEN 'currency' END,
+                CASE WHEN o.status IS DISTINCT FROM n.status THEN 'status' END,
+                CASE WHEN o.metadata IS DISTINCT FROM n.metadata THEN 'metadata' END,
+                CASE WHEN o.raw_event IS DISTINCT FROM n.raw_event THEN 'raw_event' END,
+                CASE WHEN o.last_event_id IS DISTINCT FROM n.last_event_id
+                     THEN 'last_event_id' END,
+                CASE WHEN o.parent_external_id IS DISTINCT FROM n.parent_external_id
+                     THEN 'parent_external_id' END,
+                CASE WHEN o.last_provider_ts IS DISTINCT FROM n.last_event_ts
+                     THEN 'last_provider_ts' END,
+                CASE WHEN o.failure_code IS DISTINCT FROM n.failure_code
+                     THEN 'failure_code' END,
+                CASE WHEN o.decline_code IS DISTINCT FROM n.decline_code
+                     THEN 'decline_code' END,
+                CASE WHEN o.failure_message IS DISTINCT FROM n.failure_message
+                     THEN 'failure_message' END,
+                CASE WHEN o.network_advice_code IS DISTINCT FROM n.network_advice_code
+                     THEN 'network_advice_code' END,
+                CASE WHEN o.authorized_amount IS DISTINCT FROM n.authorized_amount_minor
+                     THEN 'authorized_amount' END,
+                CASE WHEN o.statement_descriptor IS DISTINCT FROM n.statement_descriptor
+                     THEN 'statement_descriptor' END,
+                CASE WHEN o.receipt_email IS DISTINCT FROM n.receipt_email
+                     THEN 'receipt_email' END,
+                CASE WHEN o.receipt_url IS DISTINCT FROM n.receipt_url THEN 'receipt_url' END,
+                CASE WHEN o.tenant_id IS DISTINCT FROM n.tenant_id THEN 'tenant_id' END,
+                CASE WHEN o.last_request_id IS DISTINCT FROM n.last_request_id
+                     THEN 'last_request_id' END,
+                CASE WHEN o.received_at IS DISTINCT FROM n.received_at THEN 'received_at' END,
+                CASE WHEN o.created_at IS DISTINCT FROM n.created_at THEN 'created_at' END,
+                CASE WHEN o.updated_at IS DISTINCT FROM n.updated_at THEN 'updated_at' END
+            ], NULL) AS "differs!"
+        FROM ids
+        LEFT JOIN payments o ON o.external_id = ids.external_id
+        LEFT JOIN payments_v2 n ON n.external_id = ids.external_id
+        ORDER BY ids.external_id
+        "#,
+        after,
+        limit,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| ShadowRow {
+            external_id: r.external_id,
+            in_old: r.in_old,
+            in_new: r.in_new,
+            // Only both-sided rows can differ meaningfully.
+            differs: if r.in_old && r.in_new {
+                r.differs
+            } else {
+                Vec::new()
+            },
+        })
+        .collect())
+}
+
+/// `payment_repo::get_existing_payment`, from `payments_v2`.
+pub async fn get_existing_payment(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    external_id: &str,
+) -> Result<Option<ExistingPayment>, PipelineError> {
+    let row = sqlx::query!(
+        r#"
+        SELECT payment_id, status, last_event_ts, last_event_id, tenant_id, last_request_id
+        FROM payments_v2
+        WHERE external_id = $1
+        "#,
+        external_id,
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+
+    match row {
+        None => Ok(None),
+        Some(r) => {
+            let status = PaymentStatus::try_from(r.status.as_str())?;
+            Ok(Some(ExistingPayment {
+                id: r.payment_id,
+                status,
+                tenant_id: r.tenant_id,
+                last_provider_ts: r.last_event_ts,
+                last_event_id: r.last_event_id,
+                last_request_id: r.last_request_id,
+            }))
+        }
+    }
+}
+
+/// `payment_repo::get_payments_by_ids`, from `payments_v2`.
+pub async fn get_payments_by_ids(
+    pool: &PgPool,
+    ids: &[String],
+) -> Result<Vec<PaymentView>, PipelineError> {
+    let rows = sqlx::query!(
+        r#"SELECT
+            external_id,
+            source,
+            status,
+            amount_minor,
+            currency_code,
+            direction,
+            parent_external_id,
+            failure_code,
+            decline_code,
+            failure_message,
+            network_advice_code,
+            authorized_amount_minor,
+            statement_descriptor,
+            receipt_email,
+            receipt_url,
+            updated_at,
+            created_at,
+            tenant_id
+           FROM payments_v2
+           WHERE external_id = ANY($1)
+        "#,
+        ids,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.into_iter()
+        .map(|r| {
+            Ok(PaymentView {
+                id: ExternalId::new(r.external_id)?,
+                source: r.source,
+                tenant_id: r.tenant_id.map(TenantId::new).transpose()?,
+                status: PaymentStatus::try_from(r.status.as_str())?,
+                amount: r.amount_minor,
+                currency: Currency::try_from(r.currency_code.as_str())?,
+                direction: PaymentDirection::try_from(r.direction.as_str())?,
+                parent_external_id: r.parent_external_id.map(ExternalId::new).transpose()?,
+                failure: PaymentFailure::from_parts(
+                    r.failure_code,
+                    r.decline_code,
+                    r.failure_message,
+                    r.network_advice_code,
+                ),
+                authorized_amount: r.authorized_amount_minor,
+                receipt: PaymentReceipt::from_parts(
+                    r.statement_descriptor,
+                    r.receipt_email,
+                    r.receipt_url,
+                ),
+                created_at: r.created_at,
+                updated_at: r.updated_at,
+            })
+        })
+        .collect()
+}
+
+/// `payment_repo::get_payment_by_id`, from `payments_v2`.
+pub async fn get_payment_by_id(
+    pool: &PgPool,
+    id: &ExternalId,
+) -> Result<Option<PaymentView>, PipelineError> {
+    let row = sqlx::query!(
+        r#"SELECT
+            external_id,
+            source,
+            status,
+            amount_minor,
+            currency_code,
+            direction,
+            parent_external_id,
+            failure_code,
+            decline_code,
+            failure_message,
+            network_advice_code,
+            authorized_amount_minor,
+            statement_descriptor,
+            receipt_email,
+            receipt_url,
+            updated_at,
+            created_at,
+            tenant_id
+           FROM payments_v2
+           WHERE external_id = $1
+        "#,
+        id.as_str()
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    match row {
+        None => Ok(None),
+        Some(r) => Ok(Some(PaymentView {
+            id: ExternalId::new(r.external_id)?,
+            source: r.source,
+            tenant_id: r.tenant_id.map(TenantId::new).transpose()?,
+            status: PaymentStatus::try_from(r.status.as_str())?,
+            amount: r.amount_minor,
+            currency: Currency::try_from(r.currency_code.as_str())?,
+            direction: PaymentDirection::try_from(r.direction.as_str())?,
+            parent_external_id: r.parent_external_id.map(ExternalId::new).transpose()?,
+            failure: PaymentFailure::from_parts(
+                r.failure_code,
+                r.decline_code,
+                r.failure_message,
+                r.network_advice_code,
+            ),
+            authorized_amount: r.authorized_amount_minor,
+            receipt: PaymentReceipt::from_parts(
+                r.statement_descriptor,
+                r.receipt_email,
+                r.receipt_url,
+            ),
+            created_at: r.created_at,
+            updated_at: r.updated_at,
+        })),
+    }
+}
@@ -0,0 +1,178 @@
+use {
+    crate::{domain::tag::SavedFilter, error::PipelineError},
+    sqlx::PgPool,
+    uuid::Uuid,
+};
+
+/// Tag payment `payment_id` with each of `tags` it doesn't carry yet;
+/// returns the ones added.
+pub async fn add_tags(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    payment_id: Uuid,
+    tags: &[String],
+    actor: &str,
+) -> Result<Vec<String>, PipelineError> {
+    let added = sqlx::query_scalar!(
+        r#"
+        INSERT INTO payment_tags (payment_id, tag, tagged_by)
+        SELECT $1, tag, $3 FROM UNNEST($2::text[]) AS tag
+        ON CONFLICT (payment_id, tag) DO NOTHING
+        RETURNING tag
+        "#,
+        payment_id,
+        tags,
+        actor,
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+    Ok(added)
+}
+
+/// Take `tags` off payment `payment_id`; returns the ones it carried.
+pub async fn remove_tags(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    payment_id: Uuid,
+    tags: &[String],
+) -> Result<Vec<String>, PipelineError> {
+    let removed = sqlx::query_scalar!(
+        "DELETE FROM payment_tags WHERE payment_id = $1 AND tag = ANY($2) RETURNING tag",
+        payment_id,
+        tags,
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+    Ok(removed)
+}
+
+/// Payment `payment_id`'s tags, sorted.
+pub async fn tags_in_tx(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    payment_id: Uuid,
+) -> Result<Vec<String>, PipelineError> {
+    let tags = sqlx::query_scalar!(
+        "SELECT tag FROM payment_tags WHERE payment_id = $1 ORDER BY tag",
+        payment_id,
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+    Ok(tags)
+}
+
+/// Payment `external_id`'s tags, sorted; empty if it doesn't exist.
+pub async fn get_payment_tags(
+    pool: &PgPool,
+    external_id: &str,
+) -> Result<Vec<String>, PipelineError> {
+    let tags = sqlx::query_scalar!(
+        r#"
+        SELECT t.tag FROM payment_tags t
+        JOIN payments p ON p.id = t.payment_id
+        WHERE p.external_id = $1
+        ORDER BY t.tag
+        "#,
+        external_id,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(tags)
+}
+
+/// `get_payment_tags` for each of `external_ids` at once, as
+/// `(external_id, tag)` pairs sorted by both.
+pub async fn get_tags_of(
+    pool: &PgPool,
+    external_ids: &[String],
+) -> Result<Vec<(String, String)>, PipelineError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT p.external_id, t.tag FROM payment_tags t
+        JOIN payments p ON p.id = t.payment_id
+        WHERE p.external_id = ANY($1)
+        ORDER BY p.external_id, t.tag
+        "#,
+        external_ids,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().map(|r| (r.external_id, r.tag)).collect())
+}
+
+pub async fn list_saved_filters(pool: &PgPool) -> Result<Vec<SavedFilter>, PipelineError> {
+    let filters = sqlx::query_as!(
+        SavedFilter,
+        r#"
+        SELECT name, description, filters, updated_by, created_at, updated_at
+        FROM saved_filters
+        ORDER BY name
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(filters)
+}
+
+pub async fn get_saved_filter(
+    pool: &PgPool,
+    name: &str,
+) -> Result<Option<SavedFilter>, PipelineError> {
+    let filter = sqlx::query_as!(
+        SavedFilter,
+        r#"
+        SELECT name, description, filters, updated_by, created_at, updated_at
+        FROM saved_filters
+        WHERE name = $1
+        "#,
+        name,
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(filter)
+}
+
+/// Create or replace saved filter `name`.
+pub async fn upsert_saved_filter(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    name: &str,
+    description: &str,
+    filters: &serde_json::Value,
+    actor: &str,
+) -> Result<SavedFilter, PipelineError> {
+    let filter = sqlx::query_as!(
+        SavedFilter,
+        r#"
+        INSERT INTO saved_filters (name, description, filters, updated_by)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (name) DO UPDATE
+        SET description = EXCLUDED.description,
+            filters = EXCLUDED.filters,
+            updated_by = EXCLUDED.updated_by,
+            updated_at = now()
+        RETURNING name, description, filters, updated_by, created_at, updated_at
+        "#,
+        name,
+        description,
+        filters,
+        actor,
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+    Ok(filter)
+}
+
+/// Delete saved filter `name`; returns it as it was, if it existed.
+pub async fn delete_saved_filter(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    name: &str,
+) -> Result<Option<SavedFilter>, PipelineError> {
+    let filter = sqlx::query_as!(
+        SavedFilter,
+        r#"
+        DELETE FROM saved_filters WHERE name = $1
+        RETURNING name, description, filters, updated_by, created_at, updated_at
+        "#,
+        name,
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+    Ok(filter)
+}
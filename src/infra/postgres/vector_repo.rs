@@ -0,0 +1,63 @@
+use {
+    crate::{domain::vector::RecordedEvent, error::PipelineError},
+    sqlx::PgPool,
+};
+
+/// A payment some event was refused for.
+pub struct AnomalousPayment {
+    pub external_id: String,
+    pub source: String,
+    pub direction: String,
+}
+
+/// Payments with at least one anomaly, most recently updated first.
+pub async fn anomalous_payments(
+    pool: &PgPool,
+    limit: i64,
+) -> Result<Vec<AnomalousPayment>, PipelineError> {
+    let rows = sqlx::query_as!(
+        AnomalousPayment,
+        r#"
+        SELECT p.external_id, p.source, p.direction
+        FROM payments p
+        WHERE EXISTS (
+            SELECT 1 FROM audit_log a
+            WHERE a.external_id = p.external_id
+              AND a.action = 'event_received'
+              AND a.detail->>'anomaly' = 'true'
+        )
+        ORDER BY p.updated_at DESC
+        LIMIT $1
+        "#,
+        limit,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Every payment event stored for `external_id`, in the order it was
+/// processed, with the audit entry it left (none for stale ones).
+/// Passthrough events linked to the payment are left out.
+pub async fn event_history(
+    pool: &PgPool,
+    external_id: &str,
+) -> Result<Vec<RecordedEvent>, PipelineError> {
+    let rows = sqlx::query_as!(
+        RecordedEvent,
+        r#"
+        SELECT e.provider_ts,
+               a.action AS "action?",
+               COALESCE(a.detail, 'null'::jsonb) AS "detail!"
+        FROM provider_events e
+        LEFT JOIN audit_log a ON a.event_id = e.event_id AND a.source = e.source
+        WHERE e.object_id = $1
+          AND (a.detail->>'passthrough') IS DISTINCT FROM 'true'
+        ORDER BY COALESCE(a.created_at, e.received_at), e.event_id
+        "#,
+        external_id,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
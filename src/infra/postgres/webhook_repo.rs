@@ -0,0 +1,81 @@
+use {
+    crate::{domain::webhook::SignedDelivery, error::PipelineError},
+    chrono::{DateTime, Utc},
+    sqlx::PgPool,
+};
+
+/// Record a delivery's signature. Returns `false` if it was already seen.
+pub async fn remember(
+    executor: impl sqlx::PgExecutor<'_>,
+    delivery: &SignedDelivery,
+) -> Result<bool, PipelineError> {
+    let inserted: Option<bool> = sqlx::query_scalar!(
+        r#"
+        INSERT INTO webhook_signatures (source, signature, signed_at)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (source, signature) DO NOTHING
+        RETURNING true AS "inserted!"
+        "#,
+        delivery.source,
+        delivery.signature,
+        delivery.signed_at,
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(inserted.is_some())
+}
+
+/// Count a rejection of a delivery's signature, seen before or not.
+/// Returns how many times it has been rejected, this time included.
+pub async fn record_rejection(
+    pool: &PgPool,
+    delivery: &SignedDelivery,
+) -> Result<i32, PipelineError> {
+    let rejections = sqlx::query_scalar!(
+        r#"
+        INSERT INTO webhook_signatures
+            (source, signature, signed_at, rejections, last_rejected_at)
+        VALUES ($1, $2, $3, 1, now())
+        ON CONFLICT (source, signature) DO UPDATE
+        SET rejections = webhook_signatures.rejections + 1, last_rejected_at = now()
+        RETURNING rejections
+        "#,
+        delivery.source,
+        delivery.signature,
+        delivery.signed_at,
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(rejections)
+}
+
+/// Drop a signature again, so the provider's retry of a delivery we failed
+/// to process isn't taken for a replay.
+pub async fn forget(pool: &PgPool, delivery: &SignedDelivery) -> Result<(), PipelineError> {
+    sqlx::query!(
+        "DELETE FROM webhook_signatures WHERE source = $1 AND signature = $2",
+        delivery.source,
+        delivery.signature,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Delete signatures signed before `before`; those deliveries are rejected
+/// as stale without a lookup. One rejected since `before` is kept, so a
+/// delivery replayed again and again stays counted rather than audited
+/// anew.
+pub async fn prune(pool: &PgPool, before: DateTime<Utc>) -> Result<u64, PipelineError> {
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM webhook_signatures
+        WHERE signed_at < $1 AND (last_rejected_at IS NULL OR last_rejected_at < $1)
+        "#,
+        before,
+    )
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,243 @@
+use {
+    serde_json::Value,
+    std::{fmt, sync::OnceLock},
+};
+
+const MASK: &str = "[REDACTED]";
+
+/// Paths redacted by default. Cover the places Stripe puts card data and
+/// customer emails in PaymentIntent, Refund and Charge payloads, and those
+/// PayPal puts the payer and the card or wallet paid with in order and
+/// capture resources.
+const DEFAULT_PATHS: &[&str] = &[
+    "data.object.receipt_email",
+    "data.object.customer_email",
+    "data.object.customer_details.email",
+    "data.object.billing_details",
+    "data.object.payment_method_details.card",
+    "data.object.last_payment_error.payment_method.billing_details",
+    "data.object.last_payment_error.payment_method.card",
+    "data.object.charges.data.*.billing_details",
+    "data.object.charges.data.*.payment_method_details.card",
+    "data.object.charges.data.*.receipt_email",
+    "resource.payer.email_address",
+    "resource.payer.name",
+    "resource.payer.phone",
+    "resource.payer.address",
+    "resource.payment_source.card",
+    "resource.payment_source.paypal",
+    "resource.purchase_units.*.shipping",
+];
+
+static GLOBAL: OnceLock<Redactor> = OnceLock::new();
+
+/// Masks configured JSON paths before a payload reaches log output.
+///
+/// Paths are dot-separated object keys; `*` matches any key or array index.
+/// A matched value is replaced wholesale, so redacting `card` hides every
+/// field underneath it.
+#[derive(Debug, Clone)]
+pub struct Redactor {
+    paths: Vec<Vec<String>>,
+}
+
+impl Redactor {
+    pub fn new<I, S>(paths: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let paths = paths
+            .into_iter()
+            .map(|p| {
+                p.as_ref()
+                    .split('.')
+                    .map(str::trim)
+                    .map(String::from)
+                    .collect::<Vec<_>>()
+            })
+            .filter(|segments| segments.iter().all(|s| !s.is_empty()))
+            .collect();
+        Self { paths }
+    }
+
+    /// Default paths plus `extra` (e.g. from `LOG_REDACT_PATHS`).
+    pub fn with_extra(extra: &[String]) -> Self {
+        Self::new(
+            DEFAULT_PATHS
+                .iter()
+                .copied()
+                .chain(extra.iter().map(String::as_str)),
+        )
+    }
+
+    /// Return a copy of `value` with every configured path masked.
+    pub fn redact(&self, value: &Value) -> Value {
+        let mut out = value.clone();
+        for path in &self.paths {
+            mask_path(&mut out, path);
+        }
+        out
+    }
+}
+
+impl Default for Redactor {
+    fn default() -> Self {
+        Self::new(DEFAULT_PATHS)
+    }
+}
+
+fn mask_path(value: &mut Value, path: &[String]) {
+    let Some((head, rest)) = path.split_first() else {
+        return;
+    };
+
+    let children: Vec<&mut Value> = match value {
+        Value::Object(map) if head == "*" => map.values_mut().collect(),
+        Value::Object(map) => map.get_mut(head).into_iter().collect(),
+        Value::Array(items) if head == "*" => items.iter_mut().collect(),
+        Value::Array(items) => head
+            .parse::<usize>()
+            .ok()
+            .and_then(|i| items.get_mut(i))
+            .into_iter()
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    for child in children {
+        if rest.is_empty() {
+            if !child.is_null() {
+                *child = Value::String(MASK.into());
+            }
+        } else {
+            mask_path(child, rest);
+        }
+    }
+}
+
+/// Install the process-wide redactor. Later calls are ignored.
+pub fn init(redactor: Redactor) {
+    let _ = GLOBAL.set(redactor);
+}
+
+fn global() -> &'static Redactor {
+    GLOBAL.get_or_init(Redactor::default)
+}
+
+/// Log-safe view of a JSON payload: `Display` and `Debug` both print the
+/// redacted form, so `%redacted(&payload)` and `?redacted(&payload)` are safe.
+pub struct Redacted<'a>(&'a Value);
+
+pub fn redacted(value: &Value) -> Redacted<'_> {
+    Redacted(value)
+}
+
+impl fmt::Display for Redacted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", global().redact(self.0))
+    }
+}
+
+impl fmt::Debug for Redacted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn charge_event() -> Value {
+        json!({
+            "id": "evt_1",
+            "type": "charge.succeeded",
+            "data": {"object": {
+                "id": "ch_1",
+                "amount": 2000,
+                "receipt_email": "jane@example.com",
+                "billing_details": {"email": "jane@example.com", "name": "Jane"},
+                "payment_method_details": {"card": {"last4": "4242", "exp_year": 2030}},
+            }}
+        })
+    }
+
+    #[test]
+    fn default_paths_mask_card_and_email() {
+        let out = Redactor::default().redact(&charge_event());
+        let obj = &out["data"]["object"];
+        assert_eq!(obj["receipt_email"], MASK);
+        assert_eq!(obj["billing_details"], MASK);
+        assert_eq!(obj["payment_method_details"]["card"], MASK);
+        // untouched fields survive
+        assert_eq!(obj["amount"], 2000);
+        assert_eq!(out["id"], "evt_1");
+    }
+
+    #[test]
+    fn default_paths_mask_paypal_payer_and_card() {
+        let event = json!({
+            "id": "WH-1",
+            "event_type": "CHECKOUT.ORDER.APPROVED",
+            "resource": {
+                "id": "5O190127TN364715T",
+                "payer": {
+                    "payer_id": "QYR5Z8XDVJNXQ",
+                    "email_address": "jane@example.com",
+                    "name": {"given_name": "Jane", "surname": "Doe"},
+                },
+                "payment_source": {"card": {"last_digits": "4242", "expiry": "2030-01"}},
+                "purchase_units": [{"shipping": {"address": {"postal_code": "95131"}}}],
+            },
+        });
+        let out = Redactor::default().redact(&event);
+        let resource = &out["resource"];
+        assert_eq!(resource["payer"]["email_address"], MASK);
+        assert_eq!(resource["payer"]["name"], MASK);
+        assert_eq!(resource["payment_source"]["card"], MASK);
+        assert_eq!(resource["purchase_units"][0]["shipping"], MASK);
+        assert_eq!(resource["payer"]["payer_id"], "QYR5Z8XDVJNXQ");
+        assert_eq!(resource["id"], "5O190127TN364715T");
+    }
+
+    #[test]
+    fn wildcard_matches_array_elements() {
+        let event = json!({"data": {"object": {"charges": {"data": [
+            {"receipt_email": "a@example.com", "amount": 1},
+            {"receipt_email": "b@example.com", "amount": 2},
+        ]}}}});
+        let out = Redactor::default().redact(&event);
+        let charges = out["data"]["object"]["charges"]["data"].as_array().unwrap();
+        assert!(charges.iter().all(|c| c["receipt_email"] == MASK));
+        assert_eq!(charges[1]["amount"], 2);
+    }
+
+    #[test]
+    fn missing_and_null_paths_are_left_alone() {
+        let event = json!({"data": {"object": {"receipt_email": null}}});
+        let out = Redactor::default().redact(&event);
+        assert_eq!(out, event);
+    }
+
+    #[test]
+    fn custom_paths_are_applied() {
+        let r = Redactor::new(["metadata.customer_ref", "items.0"]);
+        let out = r.redact(&json!({"metadata": {"customer_ref": "c-1"}, "items": ["x", "y"]}));
+        assert_eq!(out["metadata"]["customer_ref"], MASK);
+        assert_eq!(out["items"], json!([MASK, "y"]));
+    }
+
+    #[test]
+    fn display_wrapper_never_prints_sensitive_values() {
+        let rendered = format!(
+            "{} {:?}",
+            redacted(&charge_event()),
+            redacted(&charge_event())
+        );
+        assert!(!rendered.contains("jane@example.com"));
+        assert!(!rendered.contains("4242"));
+        assert!(rendered.contains(MASK));
+    }
+}
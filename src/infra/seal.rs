@@ -0,0 +1,107 @@
+use {
+    crate::error::PipelineError,
+    aes_gcm::{
+        Aes256Gcm, Nonce,
+        aead::{Aead, KeyInit, Payload},
+    },
+    serde_json::Value,
+    std::fmt,
+};
+
+const NONCE_BYTES: usize = 12;
+
+/// Encrypts the values of secret settings at rest (AES-256-GCM), so the
+/// `settings` table and its backups hold no usable secrets. Each value is
+/// bound to its key name, so a sealed value copied under another key
+/// doesn't open.
+#[derive(Clone)]
+pub struct SealingKey(Aes256Gcm);
+
+impl SealingKey {
+    /// From 64 hex characters (32 bytes), as `SETTINGS_ENCRYPTION_KEY` holds.
+    pub fn from_hex(hex: &str) -> Result<Self, String> {
+        let bytes = hex::decode(hex.trim()).map_err(|e| format!("not hex: {e}"))?;
+        Aes256Gcm::new_from_slice(&bytes)
+            .map(Self)
+            .map_err(|_| format!("expected 32 bytes, got {}", bytes.len()))
+    }
+
+    /// `value` as stored under `name`: `{"sealed": "<hex nonce and ciphertext>"}`.
+    pub fn seal(&self, name: &str, value: &Value) -> Result<Value, PipelineError> {
+        let nonce: [u8; NONCE_BYTES] = rand::random();
+        let plain = serde_json::to_vec(value)?;
+        let sealed = self
+            .0
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &plain,
+                    aad: name.as_bytes(),
+                },
+            )
+            .map_err(|_| PipelineError::Storage(format!("could not seal setting {name}")))?;
+        Ok(serde_json::json!({ "sealed": hex::encode([&nonce[..], &sealed].concat()) }))
+    }
+
+    /// The value `seal` stored under `name`.
+    pub fn open(&self, name: &str, stored: &str) -> Result<Value, PipelineError> {
+        let unreadable = || PipelineError::Storage(format!("setting {name} doesn't open"));
+        let bytes = hex::decode(stored).map_err(|_| unreadable())?;
+        if bytes.len() < NONCE_BYTES {
+            return Err(unreadable());
+        }
+        let (nonce, sealed) = bytes.split_at(NONCE_BYTES);
+        let plain = self
+            .0
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: sealed,
+                    aad: name.as_bytes(),
+                },
+            )
+            .map_err(|_| unreadable())?;
+        Ok(serde_json::from_slice(&plain)?)
+    }
+}
+
+/// The ciphertext of a value `SealingKey::seal` stored, or `None` for a
+/// plain one.
+pub fn sealed(stored: &Value) -> Option<&str> {
+    match stored {
+        Value::Object(fields) if fields.len() == 1 => fields.get("sealed")?.as_str(),
+        _ => None,
+    }
+}
+
+impl fmt::Debug for SealingKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SealingKey").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+
+    #[test]
+    fn sealed_values_open_only_under_their_own_name() {
+        let key = SealingKey::from_hex(KEY).unwrap();
+        let value = serde_json::json!({"versions": [{"secret": "whsec_abc"}]});
+        let stored = key.seal("stripe.webhook_secrets", &value).unwrap();
+        assert!(!stored.to_string().contains("whsec_abc"));
+        let ciphertext = sealed(&stored).unwrap();
+        assert_eq!(
+            key.open("stripe.webhook_secrets", ciphertext).unwrap(),
+            value
+        );
+        assert!(key.open("other.key", ciphertext).is_err());
+        assert!(sealed(&value).is_none());
+
+        let other = SealingKey::from_hex(&KEY.replace("1f", "ff")).unwrap();
+        assert!(other.open("stripe.webhook_secrets", ciphertext).is_err());
+        assert!(SealingKey::from_hex("abcd").is_err());
+    }
+}
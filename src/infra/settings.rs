@@ -0,0 +1,363 @@
+use {
+    crate::{
+        domain::setting::{SettingChange, validate_key},
+        error::PipelineError,
+        infra::{
+            postgres::{audit_repo::insert_audit_entry, settings_repo},
+            seal::{self, SealingKey},
+        },
+    },
+    serde::{Serialize, de::DeserializeOwned},
+    serde_json::Value,
+    sqlx::{PgPool, Postgres, Transaction},
+    std::{
+        collections::HashMap,
+        marker::PhantomData,
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    },
+    tokio::sync::{broadcast, watch},
+};
+
+/// How long a cached value is trusted without a change notification.
+/// Notifications normally drop it sooner; this bounds the damage when one
+/// is lost.
+pub const DEFAULT_TTL: Duration = Duration::from_secs(30);
+
+/// A settings key and the type stored under it. Declare one `const` per
+/// setting next to the code that reads it, so the key and its type can't
+/// drift apart between readers and writers.
+pub struct SettingKey<T> {
+    name: &'static str,
+    secret: bool,
+    _type: PhantomData<fn() -> T>,
+}
+
+impl<T> SettingKey<T> {
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            secret: false,
+            _type: PhantomData,
+        }
+    }
+
+    /// A key whose values are kept out of the audit log, its entries
+    /// saying that it changed, not to what, and stored encrypted.
+    pub const fn secret(name: &'static str) -> Self {
+        Self {
+            secret: true,
+            ..Self::new(name)
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+struct Cached {
+    /// `None` caches the key being unset.
+    value: Option<Value>,
+    fetched: Instant,
+}
+
+/// Typed access to the `settings` table, cached per key. Cloning is
+/// cheap; every clone shares the cache. Writes on this replica update it
+/// at once; other replicas drop their copy when `run_settings_listener`
+/// hears of the change, or when it's older than the TTL. Secret keys are
+/// sealed with the sealing key (`SETTINGS_ENCRYPTION_KEY`) and can't be
+/// written without one.
+#[derive(Clone)]
+pub struct Settings {
+    pool: PgPool,
+    ttl: Duration,
+    sealing: Option<SealingKey>,
+    cache: Arc<Mutex<HashMap<String, Cached>>>,
+    changes: broadcast::Sender<String>,
+}
+
+impl Settings {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            ttl: DEFAULT_TTL,
+            sealing: None,
+            cache: Arc::default(),
+            changes: broadcast::Sender::new(64),
+        }
+    }
+
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    pub fn with_sealing_key(mut self, key: Option<SealingKey>) -> Self {
+        self.sealing = key;
+        self
+    }
+
+    /// The value under `key`, or `None` if it isn't set. A stored value
+    /// that doesn't decode as `T` is an error, not a default: someone
+    /// wrote it on purpose.
+    pub async fn get<T: DeserializeOwned>(
+        &self,
+        key: &SettingKey<T>,
+    ) -> Result<Option<T>, PipelineError> {
+        let value = match self.cached(key.name) {
+            Some(value) => value,
+            None => {
+                let value = settings_repo::get(&self.pool, key.name)
+                    .await?
+                    .map(|row| self.open(key.name, row.value))
+                    .transpose()?;
+                self.store(key.name, value.clone());
+                value
+            }
+        };
+        Ok(value.map(serde_json::from_value).transpose()?)
+    }
+
+    /// Lock `key` for a read-modify-write that may take a while, such as
+    /// one calling a provider in between: no other writer, on any replica,
+    /// gets the key until the lock is set or dropped. Reads the stored
+    /// value, not the cache.
+    pub async fn lock<'k, T>(
+        &self,
+        key: &'k SettingKey<T>,
+    ) -> Result<SettingLock<'_, 'k, T>, PipelineError> {
+        validate_key(key.name)?;
+        let mut tx = self.pool.begin().await?;
+        let (version, previous) = match settings_repo::get_for_update(&mut tx, key.name).await? {
+            Some(row) => (row.version, Some(self.open(key.name, row.value)?)),
+            None => (0, None),
+        };
+        Ok(SettingLock {
+            settings: self,
+            key,
+            tx,
+            version,
+            previous,
+        })
+    }
+
+    /// [`Settings::get`], with `T::default()` for an unset key.
+    pub async fn get_or_default<T: DeserializeOwned + Default>(
+        &self,
+        key: &SettingKey<T>,
+    ) -> Result<T, PipelineError> {
+        Ok(self.get(key).await?.unwrap_or_default())
+    }
+
+    /// Store and audit `value` under `key`, returning its new version.
+    pub async fn set<T: Serialize>(
+        &self,
+        key: &SettingKey<T>,
+        value: &T,
+        actor: &str,
+    ) -> Result<i64, PipelineError> {
+        self.lock(key).await?.set(value, actor).await
+    }
+
+    /// Delete and audit `key`; `false` if it wasn't set.
+    pub async fn delete<T>(&self, key: &SettingKey<T>, actor: &str) -> Result<bool, PipelineError> {
+        self.lock(key).await?.delete(actor).await
+    }
+
+    /// Keys as they change, on this replica or (with
+    /// `run_settings_listener` running) any other. A lagging receiver
+    /// should read everything it cares about again.
+    pub fn subscribe(&self) -> broadcast::Receiver<String> {
+        self.changes.subscribe()
+    }
+
+    /// `stored` as written: opened if it was sealed. A secret written
+    /// before sealing was configured reads as it is, and is sealed on its
+    /// next write.
+    fn open(&self, key: &str, stored: Value) -> Result<Value, PipelineError> {
+        let Some(sealed) = seal::sealed(&stored) else {
+            return Ok(stored);
+        };
+        match &self.sealing {
+            Some(sealing) => sealing.open(key, sealed),
+            None => Err(PipelineError::Storage(format!(
+                "setting {key} is encrypted; SETTINGS_ENCRYPTION_KEY is not set"
+            ))),
+        }
+    }
+
+    fn cached(&self, key: &str) -> Option<Option<Value>> {
+        let cache = self.cache.lock().expect("settings cache lock poisoned");
+        cache
+            .get(key)
+            .filter(|c| c.fetched.elapsed() < self.ttl)
+            .map(|c| c.value.clone())
+    }
+
+    fn store(&self, key: &str, value: Option<Value>) {
+        let mut cache = self.cache.lock().expect("settings cache lock poisoned");
+        cache.insert(
+            key.to_string(),
+            Cached {
+                value,
+                fetched: Instant::now(),
+            },
+        );
+    }
+
+    /// Forget `key`, or everything when `None`.
+    fn invalidate(&self, key: Option<&str>) {
+        let mut cache = self.cache.lock().expect("settings cache lock poisoned");
+        match key {
+            Some(key) => {
+                cache.remove(key);
+            }
+            None => cache.clear(),
+        }
+    }
+}
+
+/// A settings key held by `Settings::lock`, with its value as stored.
+/// Dropping it releases the key unchanged.
+pub struct SettingLock<'s, 'k, T> {
+    settings: &'s Settings,
+    key: &'k SettingKey<T>,
+    tx: Transaction<'static, Postgres>,
+    /// The stored version; 0 while the key isn't set.
+    version: i64,
+    previous: Option<Value>,
+}
+
+impl<T> SettingLock<'_, '_, T> {
+    /// The locked value, or `None` if the key isn't set.
+    pub fn get(&self) -> Result<Option<T>, PipelineError>
+    where
+        T: DeserializeOwned,
+    {
+        Ok(self
+            .previous
+            .clone()
+            .map(serde_json::from_value)
+            .transpose()?)
+    }
+
+    /// The transaction holding the lock, for writes that should land with
+    /// the new value or not at all.
+    pub fn tx(&mut self) -> &mut Transaction<'static, Postgres> {
+        &mut self.tx
+    }
+
+    /// Store and audit `value`, release the key, and return its new version.
+    pub async fn set(mut self, value: &T, actor: &str) -> Result<i64, PipelineError>
+    where
+        T: Serialize,
+    {
+        let (settings, name) = (self.settings, self.key.name);
+        let value = serde_json::to_value(value)?;
+        let stored = match (&settings.sealing, self.key.secret) {
+            (Some(sealing), true) => sealing.seal(name, &value)?,
+            (None, true) => {
+                return Err(PipelineError::Validation(format!(
+                    "{name} is a secret; set SETTINGS_ENCRYPTION_KEY to store it"
+                )));
+            }
+            (_, false) => value.clone(),
+        };
+        let version = settings_repo::put(&mut self.tx, name, &stored, actor).await?;
+        let change = SettingChange {
+            key: name.to_string(),
+            version,
+            previous: self.previous,
+            value: Some(value.clone()),
+            secret: self.key.secret,
+        };
+        insert_audit_entry(&mut self.tx, &change.audit_entry(actor)).await?;
+        self.tx.commit().await?;
+
+        settings.store(name, Some(value));
+        let _ = settings.changes.send(name.to_string());
+        tracing::info!(key = name, version, actor, "setting changed");
+        Ok(version)
+    }
+
+    /// Delete and audit the key, and release it; `false` if it wasn't set.
+    pub async fn delete(mut self, actor: &str) -> Result<bool, PipelineError> {
+        let (settings, name) = (self.settings, self.key.name);
+        let Some(previous) = self.previous.take() else {
+            return Ok(false);
+        };
+        settings_repo::delete(&mut self.tx, name).await?;
+        let change = SettingChange {
+            key: name.to_string(),
+            version: self.version,
+            previous: Some(previous),
+            value: None,
+            secret: self.key.secret,
+        };
+        insert_audit_entry(&mut self.tx, &change.audit_entry(actor)).await?;
+        self.tx.commit().await?;
+
+        settings.store(name, None);
+        let _ = settings.changes.send(name.to_string());
+        tracing::info!(key = name, actor, "setting deleted");
+        Ok(true)
+    }
+
+    /// Release the key unchanged, keeping whatever was written through
+    /// `tx`.
+    pub async fn commit(self) -> Result<(), PipelineError> {
+        self.tx.commit().await?;
+        Ok(())
+    }
+}
+
+/// Drop cached settings as other replicas change them, and pass the keys
+/// on to `Settings::subscribe`. While the listener is down, or after it
+/// missed notifications, the whole cache is dropped instead.
+pub async fn run_settings_listener(settings: Settings, mut shutdown: watch::Receiver<bool>) {
+    tracing::info!("settings listener started");
+    let mut listener = None;
+
+    loop {
+        if listener.is_none() {
+            listener = match settings_repo::listen(&settings.pool).await {
+                Ok(l) => Some(l),
+                Err(e) => {
+                    tracing::warn!(error = %e, "settings notifications unavailable");
+                    None
+                }
+            };
+            // Anything may have changed while no one was listening.
+            settings.invalidate(None);
+        }
+
+        let notification = async {
+            match listener.as_mut() {
+                Some(l) => l.try_recv().await,
+                None => {
+                    tokio::time::sleep(settings.ttl).await;
+                    Ok(None)
+                }
+            }
+        };
+        tokio::select! {
+            _ = shutdown.changed() => {
+                tracing::info!("settings listener shutting down");
+                return;
+            }
+            received = notification => match received {
+                Ok(Some(n)) => {
+                    settings.invalidate(Some(n.payload()));
+                    let _ = settings.changes.send(n.payload().to_string());
+                }
+                // The connection dropped; notifications may have been lost.
+                Ok(None) => settings.invalidate(None),
+                Err(e) => {
+                    tracing::warn!(error = %e, "settings listener error");
+                    listener = None;
+                }
+            },
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use {
+    std::env,
+    tracing_subscriber::{filter::LevelFilter, layer::SubscriberExt, util::SubscriberInitExt},
+};
+
+// OTLP export is the `otel` feature; without it a stand-in with the same
+// functions exports nothing, so callers never check which build they're in.
+#[cfg(not(feature = "otel"))]
+mod disabled;
+#[cfg(feature = "otel")]
+mod otlp;
+
+#[cfg(not(feature = "otel"))]
+use disabled as backend;
+#[cfg(feature = "otel")]
+use otlp as backend;
+
+/// Where traces go, from the standard OpenTelemetry variables.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TelemetryConfig {
+    /// Base URL of an OTLP/HTTP collector (`OTEL_EXPORTER_OTLP_ENDPOINT`,
+    /// e.g. `http://localhost:4318`); spans go to `<endpoint>/v1/traces`.
+    /// Nothing is exported when unset.
+    pub otlp_endpoint: Option<String>,
+    /// `OTEL_SERVICE_NAME`, `fin_sync` by default.
+    pub service_name: String,
+}
+
+impl TelemetryConfig {
+    /// Read before anything else is, so it can't fail: an endpoint the
+    /// exporter can't use is reported by `init`.
+    pub fn from_env() -> Self {
+        let var = |name| env::var(name).ok().filter(|v| !v.trim().is_empty());
+        Self {
+            otlp_endpoint: var("OTEL_EXPORTER_OTLP_ENDPOINT"),
+            service_name: var("OTEL_SERVICE_NAME").unwrap_or_else(|| "fin_sync".to_string()),
+        }
+    }
+}
+
+/// Keeps trace export running; dropping it flushes the spans not sent yet.
+pub struct Telemetry {
+    _exporter: Option<backend::Exporter>,
+}
+
+/// Install the global subscriber: log lines at INFO and above, plus, with
+/// an OTLP endpoint, every span exported with its fields as attributes.
+/// Export is best effort: an exporter that can't be built, or a build
+/// without the `otel` feature, is logged and the service runs without it.
+pub fn init(config: &TelemetryConfig) -> Telemetry {
+    let started = config
+        .otlp_endpoint
+        .as_deref()
+        .map(|endpoint| backend::Exporter::start(endpoint, &config.service_name));
+    let (exporter, failure) = match started {
+        Some(Ok(exporter)) => (Some(exporter), None),
+        Some(Err(e)) => (None, Some(e)),
+        None => (None, None),
+    };
+
+    tracing_subscriber::registry()
+        .with(LevelFilter::INFO)
+        .with(tracing_subscriber::fmt::layer())
+        .with(exporter.as_ref().map(backend::Exporter::layer))
+        .init();
+
+    match (&config.otlp_endpoint, failure) {
+        (_, Some(e)) => {
+            tracing::error!(error = %e, "OTLP exporter unavailable, traces not exported")
+        }
+        (Some(endpoint), None) => tracing::info!(endpoint, "exporting traces over OTLP"),
+        (None, None) => {}
+    }
+    Telemetry {
+        _exporter: exporter,
+    }
+}
+
+/// The current span's trace as a W3C `traceparent`, for work carried on
+/// elsewhere, e.g. by the worker. `None` when spans aren't exported.
+pub fn traceparent() -> Option<String> {
+    backend::traceparent()
+}
+
+/// Make `span`, not yet entered, a child of the trace `traceparent` names.
+/// Without one, or without export, `span` stays where it is.
+pub fn continue_trace(span: &tracing::Span, traceparent: Option<&str>) {
+    if let Some(traceparent) = traceparent {
+        backend::continue_trace(span, traceparent);
+    }
+}
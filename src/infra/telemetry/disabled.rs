@@ -0,0 +1,23 @@
+//! Stand-in for `otlp` in builds without the `otel` feature: nothing is
+//! exported and no trace is carried to jobs.
+
+use tracing_subscriber::layer::Identity;
+
+/// Never built: there's no exporter to start.
+pub enum Exporter {}
+
+impl Exporter {
+    pub fn start(_endpoint: &str, _service_name: &str) -> Result<Self, String> {
+        Err("built without the `otel` feature".to_string())
+    }
+
+    pub fn layer(&self) -> Identity {
+        match *self {}
+    }
+}
+
+pub fn traceparent() -> Option<String> {
+    None
+}
+
+pub fn continue_trace(_span: &tracing::Span, _traceparent: &str) {}
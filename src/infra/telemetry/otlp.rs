@@ -0,0 +1,64 @@
+use {
+    opentelemetry::{propagation::TextMapPropagator, trace::TracerProvider as _},
+    opentelemetry_otlp::{SpanExporter, WithExportConfig},
+    opentelemetry_sdk::{
+        Resource,
+        propagation::TraceContextPropagator,
+        trace::{SdkTracer, SdkTracerProvider},
+    },
+    std::collections::HashMap,
+    tracing::Subscriber,
+    tracing_opentelemetry::{OpenTelemetryLayer, OpenTelemetrySpanExt},
+    tracing_subscriber::registry::LookupSpan,
+};
+
+/// Spans batched to an OTLP/HTTP collector.
+pub struct Exporter {
+    provider: SdkTracerProvider,
+}
+
+impl Exporter {
+    pub fn start(endpoint: &str, service_name: &str) -> Result<Self, String> {
+        let exporter = SpanExporter::builder()
+            .with_http()
+            .with_endpoint(format!("{}/v1/traces", endpoint.trim_end_matches('/')))
+            .build()
+            .map_err(|e| e.to_string())?;
+        let resource = Resource::builder()
+            .with_service_name(service_name.to_string())
+            .build();
+        let provider = SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_resource(resource)
+            .build();
+        Ok(Self { provider })
+    }
+
+    pub fn layer<S>(&self) -> OpenTelemetryLayer<S, SdkTracer>
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        tracing_opentelemetry::layer().with_tracer(self.provider.tracer("fin_sync"))
+    }
+}
+
+impl Drop for Exporter {
+    fn drop(&mut self) {
+        if let Err(e) = self.provider.shutdown() {
+            tracing::warn!(error = %e, "failed to flush traces");
+        }
+    }
+}
+
+pub fn traceparent() -> Option<String> {
+    let mut carrier = HashMap::new();
+    TraceContextPropagator::new().inject_context(&tracing::Span::current().context(), &mut carrier);
+    carrier.remove("traceparent")
+}
+
+pub fn continue_trace(span: &tracing::Span, traceparent: &str) {
+    let carrier = HashMap::from([("traceparent".to_string(), traceparent.to_string())]);
+    let parent = TraceContextPropagator::new().extract(&carrier);
+    // Errs only when spans aren't exported, where there's no trace to join.
+    let _ = span.set_parent(parent);
+}
@@ -1,16 +1,40 @@
 pub mod adapters;
 pub mod domain;
+pub mod embed;
+pub mod error;
 pub mod infra;
+pub mod retry;
 pub mod services;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 pub mod transport;
 
 use std::sync::Arc;
 
-use domain::provider::PaymentProvider;
+pub use embed::{FinSync, FinSyncBuilder};
+
+use adapters::{paypal::client::PaypalProvider, stripe::router::EventRouter};
+use domain::{hook::HookRegistry, provider::ProviderRegistry};
+use infra::{config::Config, export_store::ExportStore, settings::Settings};
+use services::{config::RuntimeConfigHandle, notify::Notifier};
 
 #[derive(Clone)]
 pub struct AppState {
     pub pool: sqlx::PgPool,
-    pub stripe_webhook_secret: Arc<str>,
-    pub provider: Arc<dyn PaymentProvider>,
+    /// Startup configuration from the environment.
+    pub settings: Arc<Config>,
+    pub providers: ProviderRegistry,
+    /// Transition hooks of an embedding application; none when run standalone.
+    pub hooks: HookRegistry,
+    pub config: RuntimeConfigHandle,
+    pub notifier: Notifier,
+    /// Set when PayPal credentials are configured; also in `providers`.
+    pub paypal: Option<Arc<PaypalProvider>>,
+    /// How each Stripe webhook event type is handled.
+    pub stripe_routes: Arc<EventRouter>,
+    /// Where `/admin/exports` and scheduled exports are written.
+    pub export_store: ExportStore,
+    /// The `settings` table, cached; kept fresh by `run_settings_listener`.
+    /// Holds the webhook signing secrets among others.
+    pub settings_store: Settings,
 }
@@ -1,59 +1,179 @@
 use {
     fin_sync::{
-        adapters::stripe::client::StripeProvider,
-        services::worker::{run_reaper, run_worker},
-        transport::http::router,
+        FinSync,
+        adapters::stripe::{client::StripeProvider, router::EventRouter},
+        domain::id::TenantId,
+        infra::auth::{self, Role},
+        infra::config::{Config, ConfigError, DatabaseConfig},
+        infra::settings::Settings,
+        infra::telemetry::{self, TelemetryConfig},
+        services::{migrate, webhook_setup},
+        transport::integrations,
     },
-    sqlx::postgres::PgPoolOptions,
-    std::{env, sync::Arc, time::Duration},
+    sqlx::{PgPool, postgres::PgPoolOptions},
+    std::{env, net::SocketAddr, process},
     tokio::signal,
 };
 
 #[tokio::main]
 async fn main() {
-    tracing_subscriber::fmt::init();
-
     dotenvy::dotenv().ok();
-    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-    let stripe_webhook_secret =
-        env::var("STRIPE_WEBHOOK_SECRET").expect("STRIPE_WEBHOOK_SECRET must be set");
-    let stripe_secret_key = env::var("STRIPE_SECRET_KEY").expect("STRIPE_SECRET_KEY must be set");
+    // Flushes unsent spans when `main` returns.
+    let _telemetry = telemetry::init(&TelemetryConfig::from_env());
 
-    let pool = PgPoolOptions::new()
-        .max_connections(20)
-        .acquire_timeout(Duration::from_secs(3))
-        .connect(&database_url)
+    // `fin_sync migrate` applies pending migrations and exits;
+    // `fin_sync compress-payloads` compresses payloads stored before
+    // compression; `fin_sync create-api-key <name> <scopes> [role]` creates
+    // a key and prints it. All three need only the database.
+    // `fin_sync webhook-setup [rotate]` registers the Stripe webhook
+    // endpoint, or rotates its secret, and needs the full config.
+    let args: Vec<String> = env::args().collect();
+    match args.get(1).map(String::as_str) {
+        None => serve(or_exit(Config::from_env())).await,
+        Some("migrate") => {
+            let pool = connect(&or_exit(DatabaseConfig::from_env())).await;
+            run_migrations(&pool).await;
+        }
+        Some("compress-payloads") => {
+            let pool = connect(&or_exit(DatabaseConfig::from_env())).await;
+            let compressed = migrate::compress_stored_payloads(&pool, 500)
+                .await
+                .expect("failed to compress payloads");
+            tracing::info!(compressed, "provider event payloads compressed");
+        }
+        Some("create-api-key") => {
+            let (Some(name), Some(scopes)) = (args.get(2), args.get(3)) else {
+                tracing::error!(
+                    "usage: fin_sync create-api-key <name> <read,replay,admin> [support|finance|engineering] [acct_...]"
+                );
+                process::exit(2);
+            };
+            let scopes = auth::parse_scopes(scopes).unwrap_or_else(|e| {
+                tracing::error!("{e}");
+                process::exit(2);
+            });
+            let role = args.get(4).map_or(Ok(Role::default()), |r| r.parse());
+            let role = role.unwrap_or_else(|e| {
+                tracing::error!("{e}");
+                process::exit(2);
+            });
+            let tenant = args.get(5).map(TenantId::new).transpose();
+            let tenant = tenant.unwrap_or_else(|e| {
+                tracing::error!("{e}");
+                process::exit(2);
+            });
+            let pool = connect(&or_exit(DatabaseConfig::from_env())).await;
+            let (key, secret) =
+                auth::create_key(&pool, name, &scopes, role, tenant.as_ref(), "cli")
+                    .await
+                    .expect("failed to create API key");
+            tracing::info!(id = %key.id, name = %key.name, "API key created; it is shown only once");
+            println!("{secret}");
+        }
+        Some("webhook-setup") => webhook_setup(or_exit(Config::from_env()), args.get(2)).await,
+        Some(other) => {
+            tracing::error!(
+                "unknown command {other:?} (expected migrate, compress-payloads, create-api-key or webhook-setup)"
+            );
+            process::exit(2);
+        }
+    }
+}
+
+fn or_exit<T>(config: Result<T, ConfigError>) -> T {
+    config.unwrap_or_else(|e| {
+        tracing::error!("invalid configuration: {e}");
+        process::exit(1);
+    })
+}
+
+async fn connect(db: &DatabaseConfig) -> PgPool {
+    PgPoolOptions::new()
+        .max_connections(db.max_connections)
+        .acquire_timeout(db.acquire_timeout)
+        .connect(&db.url)
         .await
-        .expect("failed to connect to database");
+        .expect("failed to connect to database")
+}
 
-    let provider = Arc::new(StripeProvider::new(&stripe_secret_key));
+async fn run_migrations(pool: &PgPool) {
+    let report = migrate::run(pool).await.expect("failed to run migrations");
+    tracing::info!(
+        applied = report.applied.len(),
+        waited = report.waited,
+        "database schema up to date"
+    );
+}
 
-    let state = fin_sync::AppState {
-        pool,
-        stripe_webhook_secret: stripe_webhook_secret.into(),
-        provider,
+async fn webhook_setup(settings: Config, command: Option<&String>) {
+    let pool = connect(&settings.database).await;
+    let store = Settings::new(pool).with_sealing_key(settings.settings_key.clone());
+    let stripe =
+        StripeProvider::new(&settings.stripe.secret_key).with_api_base(&settings.stripe.api_base);
+    let router = EventRouter::with_overrides(&settings.stripe.event_routes);
+    let target = webhook_setup::target(&settings.stripe.webhook_url, &router);
+    let report = match command.map(String::as_str) {
+        None => webhook_setup::sync_endpoint(&store, &stripe, &target, "cli")
+            .await
+            .map(|setup| serde_json::to_value(setup).unwrap_or_default()),
+        Some("rotate") => webhook_setup::rotate_secret(
+            &store,
+            &stripe,
+            &target,
+            settings.stripe.secret_overlap,
+            "cli",
+        )
+        .await
+        .map(|rotation| serde_json::to_value(rotation).unwrap_or_default()),
+        Some(other) => {
+            tracing::error!("usage: fin_sync webhook-setup [rotate] (got {other:?})");
+            process::exit(2);
+        }
     };
+    match report {
+        Ok(report) => println!("{report:#}"),
+        Err(e) => {
+            tracing::error!(error = %e, "webhook setup failed");
+            process::exit(1);
+        }
+    }
+}
 
-    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+async fn serve(settings: Config) {
+    if settings.dev_routes {
+        tracing::warn!("DEV_ROUTES is on: /dev/simulate takes unsigned events");
+    }
+    let listen_addr = settings.http.listen_addr;
+    let fin_sync = FinSync::builder(settings)
+        .build()
+        .await
+        .expect("failed to start fin_sync");
 
-    tokio::spawn(run_worker(
-        state.pool.clone(),
-        state.provider.clone(),
-        shutdown_rx.clone(),
-    ));
-    tokio::spawn(run_reaper(state.pool.clone(), shutdown_rx));
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+    let workers = fin_sync
+        .spawn_workers(shutdown_rx)
+        .await
+        .expect("failed to start background tasks");
 
-    let app = router::build(state);
+    let listener = tokio::net::TcpListener::bind(listen_addr).await.unwrap();
+    tracing::info!(
+        features = ?integrations::ENABLED,
+        "listening on {listen_addr}"
+    );
+    axum::serve(
+        listener,
+        fin_sync
+            .router()
+            .into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(async move {
+        shutdown_signal().await;
+        let _ = shutdown_tx.send(true);
+    })
+    .await
+    .unwrap();
 
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await.unwrap();
-    tracing::info!("listening on 0.0.0.0:3000");
-    axum::serve(listener, app)
-        .with_graceful_shutdown(async move {
-            shutdown_signal().await;
-            let _ = shutdown_tx.send(true);
-        })
-        .await
-        .unwrap();
+    workers.join().await;
 }
 
 async fn shutdown_signal() {
@@ -0,0 +1,250 @@
+//! Bounded retries, one way everywhere: a [`Policy`] says how many attempts
+//! a piece of work gets, how long to wait between them and which errors are
+//! worth another try. In-process retries (the Stripe client) go through
+//! [`run`]; queued work (jobs, follow-ups, hook deliveries) asks the policy
+//! for [`Policy::delay`] and schedules the next attempt in the database.
+
+use {
+    std::{fmt::Display, future::Future, time::Duration},
+    uuid::Uuid,
+};
+
+/// How the wait grows from one attempt to the next.
+#[derive(Debug, Clone, Copy)]
+pub enum Backoff {
+    Fixed(Duration),
+    /// This long after the first failure, doubled after each one after.
+    Exponential(Duration),
+}
+
+/// Randomness added to the backoff, so that work failing together doesn't
+/// come back together.
+#[derive(Debug, Clone, Copy)]
+pub enum Jitter {
+    None,
+    /// Anywhere between half the backoff and all of it.
+    Half,
+}
+
+/// What a failure calls for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Verdict {
+    /// Trying again won't help.
+    GiveUp,
+    /// Try again after the policy's backoff.
+    Retry,
+    /// Try again after this long, as the other side asked; given up on if
+    /// that's longer than the policy waits.
+    RetryAfter(Duration),
+}
+
+#[derive(Debug)]
+pub struct Policy<E> {
+    /// Attempts in all, the first one included.
+    pub max_attempts: u32,
+    pub backoff: Backoff,
+    /// Longest wait between two attempts.
+    pub max_delay: Duration,
+    pub jitter: Jitter,
+    pub retryable: fn(&E) -> Verdict,
+}
+
+impl<E> Policy<E> {
+    /// How long to wait before the next attempt, after `attempts` of them
+    /// ended with `error` last; `None` to give up.
+    pub fn delay(&self, attempts: u32, error: &E) -> Option<Duration> {
+        self.delay_with(attempts, error, unit_sample())
+    }
+
+    /// [`Policy::delay`], with `sample` (in `[0, 1)`) in place of a random
+    /// draw for the jitter.
+    pub fn delay_with(&self, attempts: u32, error: &E, sample: f64) -> Option<Duration> {
+        if attempts >= self.max_attempts {
+            return None;
+        }
+        let backoff = match (self.retryable)(error) {
+            Verdict::GiveUp => return None,
+            Verdict::RetryAfter(wait) => return (wait <= self.max_delay).then_some(wait),
+            Verdict::Retry => match self.backoff {
+                Backoff::Fixed(wait) => wait,
+                Backoff::Exponential(base) => {
+                    base.saturating_mul(2u32.saturating_pow(attempts.saturating_sub(1)))
+                }
+            },
+        };
+        let backoff = backoff.min(self.max_delay);
+        Some(match self.jitter {
+            Jitter::None => backoff,
+            Jitter::Half => backoff.mul_f64(0.5 + sample / 2.0),
+        })
+    }
+}
+
+/// Where retries wait; tests pass one that doesn't.
+pub trait Clock {
+    fn sleep(&self, wait: Duration) -> impl Future<Output = ()> + Send;
+}
+
+/// Waits on the tokio timer.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioClock;
+
+impl Clock for TokioClock {
+    fn sleep(&self, wait: Duration) -> impl Future<Output = ()> + Send {
+        tokio::time::sleep(wait)
+    }
+}
+
+/// Run `attempt` until it succeeds or `policy` gives up, waiting on `clock`
+/// in between; the last error is returned. `what` names the work in logs.
+pub async fn run<T, E, F, Fut>(
+    policy: &Policy<E>,
+    clock: &impl Clock,
+    what: &str,
+    mut attempt: F,
+) -> Result<T, E>
+where
+    E: Display,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        let error = match attempt().await {
+            Ok(value) => return Ok(value),
+            Err(e) => e,
+        };
+        let Some(wait) = policy.delay(attempts, &error) else {
+            return Err(error);
+        };
+        tracing::warn!(
+            what,
+            attempt = attempts,
+            delay_ms = wait.as_millis() as u64,
+            error = %error,
+            "attempt failed, retrying"
+        );
+        clock.sleep(wait).await;
+    }
+}
+
+/// A random number in `[0, 1)`, from the low 53 bits of a v4 UUID (all
+/// random, unlike the version and variant bits above them).
+fn unit_sample() -> f64 {
+    let bits = Uuid::new_v4().as_u128() & ((1 << 53) - 1);
+    bits as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std::sync::{
+            Mutex,
+            atomic::{AtomicU32, Ordering},
+        },
+    };
+
+    /// Records the waits instead of sleeping through them.
+    #[derive(Default)]
+    struct FakeClock {
+        slept: Mutex<Vec<Duration>>,
+    }
+
+    impl Clock for FakeClock {
+        fn sleep(&self, wait: Duration) -> impl Future<Output = ()> + Send {
+            self.slept.lock().unwrap().push(wait);
+            std::future::ready(())
+        }
+    }
+
+    #[derive(Debug)]
+    enum Failure {
+        Busy,
+        SlowDown(u64),
+        Broken,
+    }
+
+    impl Display for Failure {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{self:?}")
+        }
+    }
+
+    const POLICY: Policy<Failure> = Policy {
+        max_attempts: 4,
+        backoff: Backoff::Exponential(Duration::from_millis(100)),
+        max_delay: Duration::from_secs(1),
+        jitter: Jitter::None,
+        retryable: |e| match e {
+            Failure::Busy => Verdict::Retry,
+            Failure::SlowDown(secs) => Verdict::RetryAfter(Duration::from_secs(*secs)),
+            Failure::Broken => Verdict::GiveUp,
+        },
+    };
+
+    #[test]
+    fn delays_back_off_up_to_the_cap() {
+        let delays: Vec<_> = (1..=4).map(|n| POLICY.delay(n, &Failure::Busy)).collect();
+        let ms = |n| Some(Duration::from_millis(n));
+        assert_eq!(delays, [ms(100), ms(200), ms(400), None]);
+
+        let capped = Policy {
+            max_attempts: 10,
+            ..POLICY
+        };
+        assert_eq!(capped.delay(8, &Failure::Busy), ms(1000));
+        assert_eq!(capped.delay(1, &Failure::SlowDown(1)), ms(1000));
+        assert_eq!(capped.delay(1, &Failure::SlowDown(2)), None);
+        assert_eq!(capped.delay(1, &Failure::Broken), None);
+
+        let jittered = Policy {
+            jitter: Jitter::Half,
+            ..POLICY
+        };
+        assert_eq!(jittered.delay_with(2, &Failure::Busy, 0.0), ms(100));
+        assert_eq!(jittered.delay_with(2, &Failure::Busy, 0.5), ms(150));
+        for _ in 0..100 {
+            let wait = jittered.delay(2, &Failure::Busy).unwrap();
+            assert!((ms(100).unwrap()..=ms(200).unwrap()).contains(&wait));
+        }
+        // A wait asked for is kept to.
+        assert_eq!(jittered.delay_with(1, &Failure::SlowDown(1), 0.0), ms(1000));
+    }
+
+    #[tokio::test]
+    async fn run_retries_until_success_or_give_up() {
+        let clock = FakeClock::default();
+        let calls = AtomicU32::new(0);
+        let result = run(&POLICY, &clock, "test", || async {
+            match calls.fetch_add(1, Ordering::SeqCst) {
+                0 => Err(Failure::Busy),
+                1 => Err(Failure::SlowDown(1)),
+                2 => Err(Failure::Busy),
+                n => Ok(n),
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap(), 3);
+        let ms = Duration::from_millis;
+        assert_eq!(*clock.slept.lock().unwrap(), [ms(100), ms(1000), ms(400)]);
+
+        let clock = FakeClock::default();
+        let calls = AtomicU32::new(0);
+        let result: Result<(), _> = run(&POLICY, &clock, "test", || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err(Failure::Busy)
+        })
+        .await;
+        assert!(matches!(result, Err(Failure::Busy)));
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+        assert_eq!(clock.slept.lock().unwrap().len(), 3);
+
+        let clock = FakeClock::default();
+        let result: Result<(), _> =
+            run(&POLICY, &clock, "test", || async { Err(Failure::Broken) }).await;
+        assert!(matches!(result, Err(Failure::Broken)));
+        assert!(clock.slept.lock().unwrap().is_empty());
+    }
+}
@@ -1,2 +1,34 @@
+pub mod audit_relay;
+pub mod audit_tail;
+pub mod backfill;
+pub mod budget;
+pub mod capture;
+pub mod config;
+pub mod currency_terms;
+pub mod event_gap;
+pub mod export;
+pub mod fetch_cache;
+pub mod followup;
+pub mod hooks;
+pub mod ingest;
+pub mod jobs;
+pub mod ledger;
+pub mod locks;
+pub mod maintenance;
+pub mod migrate;
+pub mod notify;
+pub mod outbox;
 pub mod payment;
+pub mod payout;
+pub mod period;
+pub mod reconciliation;
+pub mod replay;
+pub mod report;
+pub mod retention;
+pub mod rollup;
+pub mod shadow;
+pub mod tags;
+pub mod vectors;
+pub mod webhook_guard;
+pub mod webhook_setup;
 pub mod worker;
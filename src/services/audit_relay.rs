@@ -0,0 +1,57 @@
+use {
+    crate::{error::PipelineError, infra::postgres::audit_relay_repo},
+    sqlx::PgPool,
+    std::time::Duration,
+    tokio::sync::watch,
+};
+
+const BATCH_SIZE: i64 = 500;
+const IDLE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Ship one batch from the local outbox to the audit database. The outbox
+/// rows stay locked until the target has accepted the batch, and are only
+/// deleted afterwards — a crash in between re-ships, never loses.
+/// Returns the number of entries shipped.
+pub async fn relay_once(local: &PgPool, target: &PgPool) -> Result<usize, PipelineError> {
+    let mut tx = local.begin().await?;
+    let batch = audit_relay_repo::claim_batch(&mut tx, BATCH_SIZE).await?;
+    if batch.is_empty() {
+        tx.commit().await?;
+        return Ok(0);
+    }
+
+    audit_relay_repo::ship(target, &batch).await?;
+    let ids: Vec<_> = batch.iter().map(|r| r.id).collect();
+    audit_relay_repo::mark_shipped(&mut tx, &ids).await?;
+    tx.commit().await?;
+    Ok(batch.len())
+}
+
+/// Drain the outbox continuously; back off only when it is empty or the
+/// target is unreachable.
+pub async fn run_audit_relay(local: PgPool, target: PgPool, mut shutdown: watch::Receiver<bool>) {
+    tracing::info!("audit relay started");
+
+    loop {
+        let wait = match relay_once(&local, &target).await {
+            Ok(n) if n as i64 == BATCH_SIZE => Duration::ZERO,
+            Ok(0) => IDLE_INTERVAL,
+            Ok(n) => {
+                tracing::debug!(count = n, "shipped audit entries");
+                IDLE_INTERVAL
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "audit relay error");
+                IDLE_INTERVAL
+            }
+        };
+
+        tokio::select! {
+            _ = shutdown.changed() => {
+                tracing::info!("audit relay shutting down");
+                return;
+            }
+            _ = tokio::time::sleep(wait) => {}
+        }
+    }
+}
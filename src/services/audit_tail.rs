@@ -0,0 +1,70 @@
+use {
+    crate::{
+        domain::audit::{AuditNotice, AuditRecord, AuditTailFilter},
+        error::PipelineError,
+        infra::postgres::audit_repo,
+    },
+    sqlx::{PgPool, postgres::PgListener},
+    tokio::sync::{Semaphore, mpsc},
+    tokio_stream::wrappers::ReceiverStream,
+};
+
+/// Each tail holds a database connection of its own for as long as it is
+/// open, so only this many run at once.
+const MAX_TAILS: usize = 4;
+static TAILS: Semaphore = Semaphore::const_new(MAX_TAILS);
+
+/// Entries waiting for a slow client before the tail stops reading more.
+const ENTRIES_IN_FLIGHT: usize = 64;
+
+/// Matching audit entries as they are committed. An `Err` is always the
+/// last item: the tail lost its listener.
+pub type AuditTail = ReceiverStream<Result<AuditRecord, PipelineError>>;
+
+/// Follow new audit entries matching `filter`, from now on. The listener
+/// runs on a background task that stops, releasing its connection, as soon
+/// as the returned stream is dropped. `None` if `MAX_TAILS` are already open.
+pub async fn tail_audit(
+    pool: PgPool,
+    filter: AuditTailFilter,
+) -> Result<Option<AuditTail>, PipelineError> {
+    let Ok(permit) = TAILS.try_acquire() else {
+        return Ok(None);
+    };
+    let listener = audit_repo::listen(&pool).await?;
+
+    let (tx, rx) = mpsc::channel(ENTRIES_IN_FLIGHT);
+    tokio::spawn(async move {
+        let _permit = permit;
+        if let Err(e) = pump(&pool, listener, &filter, &tx).await {
+            tracing::warn!(error = %e, "audit tail stopped");
+            let _ = tx.send(Err(e)).await;
+        }
+    });
+    Ok(Some(ReceiverStream::new(rx)))
+}
+
+/// Returns once the receiver goes away.
+async fn pump(
+    pool: &PgPool,
+    mut listener: PgListener,
+    filter: &AuditTailFilter,
+    tx: &mpsc::Sender<Result<AuditRecord, PipelineError>>,
+) -> Result<(), PipelineError> {
+    loop {
+        let notification = tokio::select! {
+            () = tx.closed() => return Ok(()),
+            notification = listener.recv() => notification?,
+        };
+        let notice: AuditNotice = serde_json::from_str(notification.payload())?;
+        if !filter.matches(&notice) {
+            continue;
+        }
+        let Some(record) = audit_repo::get_audit_record(pool, notice.id).await? else {
+            continue;
+        };
+        if tx.send(Ok(record)).await.is_err() {
+            return Ok(());
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use {
+    crate::{
+        domain::{
+            backfill::{BackfillCounts, BackfillRunView, backfill_payment},
+            provider::{ListCursor, PaymentPager, PaymentProvider},
+        },
+        error::PipelineError,
+        infra::postgres::backfill_repo,
+        services::payment::pipeline::process_payment_event,
+    },
+    chrono::{DateTime, Utc},
+    sqlx::PgPool,
+    uuid::Uuid,
+};
+
+/// A `running` run that hasn't checkpointed for this long is presumed dead
+/// and may be resumed.
+const STALLED_AFTER_SECS: f64 = 300.0;
+
+/// Record a new run of `source` for `[since, until]` and return its id.
+/// Nothing is imported until [`execute_run`].
+pub async fn start_run(
+    pool: &PgPool,
+    source: &str,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Result<Uuid, PipelineError> {
+    if since >= until {
+        return Err(PipelineError::Validation(format!(
+            "empty backfill window: {since} >= {until}"
+        )));
+    }
+    backfill_repo::create_run(pool, source, since, until).await
+}
+
+/// Claim a failed or stalled run so it can be executed again from its
+/// last checkpoint. `None` if the run doesn't exist.
+pub async fn resume_run(
+    pool: &PgPool,
+    run_id: Uuid,
+) -> Result<Option<BackfillRunView>, PipelineError> {
+    if let Some(run) = backfill_repo::claim_for_resume(pool, run_id, STALLED_AFTER_SECS).await? {
+        return Ok(Some(run));
+    }
+    match backfill_repo::get_run(pool, run_id).await? {
+        None => Ok(None),
+        Some(run) => Err(PipelineError::Validation(format!(
+            "backfill run {run_id} is {} and cannot be resumed",
+            run.status
+        ))),
+    }
+}
+
+/// Import the run's window from its stored cursor onward, checkpointing after
+/// every page. Marks the run failed on error; the checkpoint survives.
+/// `provider` must be the one for the run's source.
+pub async fn execute_run(
+    pool: &PgPool,
+    provider: &dyn PaymentProvider,
+    run_id: Uuid,
+) -> Result<(), PipelineError> {
+    let run = backfill_repo::get_run(pool, run_id)
+        .await?
+        .ok_or_else(|| PipelineError::Validation(format!("unknown backfill run: {run_id}")))?;
+    if run.source != provider.source() {
+        return Err(PipelineError::Validation(format!(
+            "backfill run {run_id} is for {}, not {}",
+            run.source,
+            provider.source()
+        )));
+    }
+
+    match import_window(pool, provider, &run).await {
+        Ok(()) => {
+            backfill_repo::complete_run(pool, run_id).await?;
+            tracing::info!(%run_id, "backfill run completed");
+            Ok(())
+        }
+        Err(e) => {
+            tracing::error!(%run_id, error = %e, "backfill run failed");
+            backfill_repo::fail_run(pool, run_id, &e.to_string()).await?;
+            Err(e)
+        }
+    }
+}
+
+async fn import_window(
+    pool: &PgPool,
+    provider: &dyn PaymentProvider,
+    run: &BackfillRunView,
+) -> Result<(), PipelineError> {
+    let mut pager = PaymentPager::resume(
+        provider,
+        run.window_start,
+        run.window_end,
+        run.cursor.clone().map(ListCursor::new),
+    );
+
+    let actor = format!("backfill:{}", run.source);
+
+    while let Some(payments) = pager.next_page().await? {
+        let observed_at = Utc::now().timestamp();
+        let mut counts = BackfillCounts::default();
+
+        for fetched in payments {
+            let external_id = fetched.external_id.clone();
+            let result = match backfill_payment(fetched, &run.source, run.id, observed_at) {
+                Ok(payment) => process_payment_event(pool, &payment, &actor).await,
+                Err(e) => Err(e.into()),
+            };
+            match result {
+                Ok(r) => counts.record(&r),
+                Err(PipelineError::Validation(msg)) => {
+                    tracing::warn!(run_id = %run.id, %external_id, "skipping backfilled object: {msg}");
+                    counts.skipped += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        let cursor = pager.cursor().map(|c| c.as_str());
+        backfill_repo::save_checkpoint(pool, run.id, cursor, counts).await?;
+    }
+    Ok(())
+}
+
+pub async fn list_runs(
+    pool: &PgPool,
+    limit: Option<i64>,
+) -> Result<Vec<BackfillRunView>, PipelineError> {
+    backfill_repo::list_runs(pool, limit.unwrap_or(20).clamp(1, 100)).await
+}
+
+pub async fn get_run(
+    pool: &PgPool,
+    run_id: Uuid,
+) -> Result<Option<BackfillRunView>, PipelineError> {
+    backfill_repo::get_run(pool, run_id).await
+}
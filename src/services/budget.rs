@@ -0,0 +1,262 @@
+use {
+    crate::{
+        domain::{
+            budget::{ApiOperation, BudgetAlert, BudgetLevel, BudgetPeriod, BudgetUsage},
+            config::RuntimeConfig,
+            error::PortError,
+            id::{ExternalId, TenantId},
+            provider::{FetchedPayment, ListCursor, PaymentPage, PaymentProvider},
+        },
+        error::PipelineError,
+        infra::postgres::budget_repo::{self, Charge, WindowCount},
+        services::{config::RuntimeConfigHandle, notify::Notifier},
+    },
+    chrono::{DateTime, Utc},
+    sqlx::PgPool,
+    std::{future::Future, pin::Pin, sync::Arc},
+};
+
+/// Counts provider API calls per source, operation and UTC hour and day,
+/// against `RuntimeConfig::provider_budgets`, so a runaway backfill or
+/// re-fetch loop is cut off instead of burning through the provider's rate
+/// limits. Past a soft limit a `provider_budget` alert goes out; at a hard
+/// limit calls fail with `BudgetExhausted` until the window ends, and
+/// jobs wait for its end without using up an attempt. Each limit is alerted on once per window. Counters live in
+/// the database, so the budget holds across replicas. Cheap to clone.
+#[derive(Clone)]
+pub struct ApiBudget {
+    pool: PgPool,
+    config: RuntimeConfigHandle,
+    notifier: Notifier,
+}
+
+impl ApiBudget {
+    pub fn new(pool: PgPool, config: RuntimeConfigHandle, notifier: Notifier) -> Self {
+        Self {
+            pool,
+            config,
+            notifier,
+        }
+    }
+
+    /// `provider` with every API call counted, and refused at a hard limit.
+    pub fn wrap(&self, provider: Arc<dyn PaymentProvider>) -> Arc<dyn PaymentProvider> {
+        Arc::new(BudgetedProvider {
+            inner: provider,
+            budget: self.clone(),
+        })
+    }
+
+    /// Count one `operation` call to `source`, or refuse it if a window is
+    /// at its hard limit.
+    pub async fn charge(
+        &self,
+        source: &'static str,
+        operation: ApiOperation,
+    ) -> Result<(), PipelineError> {
+        let config = &self.config.current().config;
+        let hard_limits = BudgetPeriod::ALL.map(|period| {
+            let budget = config.provider_budget(source, operation, period);
+            (period, budget.and_then(|b| b.hard_limit))
+        });
+        match budget_repo::charge(&self.pool, source, operation, &hard_limits).await? {
+            Charge::Counted(windows) => {
+                for window in windows.iter().filter(|w| !w.soft_alerted) {
+                    let budget = config.provider_budget(source, operation, window.period);
+                    if let Some(budget) = budget
+                        && let Some(soft) = budget.soft_limit
+                        && window.calls >= soft as i64
+                    {
+                        tracing::warn!(
+                            source,
+                            operation = operation.as_str(),
+                            period = window.period.as_str(),
+                            calls = window.calls,
+                            soft_limit = soft,
+                            "provider API budget past its soft limit"
+                        );
+                        self.alert(source, operation, window, BudgetLevel::Soft, config)
+                            .await;
+                    }
+                }
+                Ok(())
+            }
+            Charge::Refused(window) => {
+                let period = window.period.as_str();
+                tracing::warn!(
+                    source,
+                    operation = operation.as_str(),
+                    period,
+                    calls = window.calls,
+                    "provider API call refused: budget exhausted"
+                );
+                if !window.hard_alerted {
+                    self.alert(source, operation, &window, BudgetLevel::Hard, config)
+                        .await;
+                }
+                Err(PipelineError::BudgetExhausted {
+                    reason: format!(
+                        "{source} {} calls: {} this {period}, the hard limit",
+                        operation.as_str(),
+                        window.calls
+                    ),
+                    until: window.period.end(window.period_start),
+                })
+            }
+        }
+    }
+
+    /// Alert on `level` for `window`, unless this or another replica
+    /// already has. Best effort, like every alert.
+    async fn alert(
+        &self,
+        source: &str,
+        operation: ApiOperation,
+        window: &WindowCount,
+        level: BudgetLevel,
+        config: &RuntimeConfig,
+    ) {
+        let Some(budget) = config.provider_budget(source, operation, window.period) else {
+            return;
+        };
+        let limit = match level {
+            BudgetLevel::Soft => budget.soft_limit,
+            BudgetLevel::Hard => budget.hard_limit,
+        };
+        let Some(limit) = limit else { return };
+        match budget_repo::mark_alerted(&self.pool, source, operation, window, level).await {
+            Ok(true) => {}
+            Ok(false) => return,
+            Err(e) => {
+                tracing::error!(source, error = %e, "failed to record budget alert");
+                return;
+            }
+        }
+        let alert = BudgetAlert {
+            source: source.to_string(),
+            operation,
+            period: window.period,
+            period_start: window.period_start,
+            level,
+            calls: window.calls,
+            limit,
+            hard_limit: budget.hard_limit,
+            usage_url: self.notifier.link("/admin/provider-budgets"),
+        };
+        self.notifier.notify(&alert.to_alert()).await;
+    }
+}
+
+/// A provider whose API calls go through an `ApiBudget` first. Reading
+/// the object out of a stored event costs nothing and isn't counted.
+struct BudgetedProvider {
+    inner: Arc<dyn PaymentProvider>,
+    budget: ApiBudget,
+}
+
+impl PaymentProvider for BudgetedProvider {
+    fn source(&self) -> &'static str {
+        self.inner.source()
+    }
+
+    fn fetch_payment(
+        &self,
+        id: &ExternalId,
+    ) -> Pin<Box<dyn Future<Output = Result<FetchedPayment, PortError>> + Send + '_>> {
+        let id = id.clone();
+        Box::pin(async move {
+            self.budget
+                .charge(self.source(), ApiOperation::Fetch)
+                .await?;
+            self.inner.fetch_payment(&id).await
+        })
+    }
+
+    fn list_payments(
+        &self,
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+        cursor: Option<ListCursor>,
+    ) -> Pin<Box<dyn Future<Output = Result<PaymentPage, PortError>> + Send + '_>> {
+        Box::pin(async move {
+            self.budget
+                .charge(self.source(), ApiOperation::List)
+                .await?;
+            self.inner.list_payments(since, until, cursor).await
+        })
+    }
+
+    fn payment_from_event(
+        &self,
+        payload: &serde_json::Value,
+    ) -> Result<Option<FetchedPayment>, PortError> {
+        self.inner.payment_from_event(payload)
+    }
+
+    fn payment_from_payload(
+        &self,
+        payload: &serde_json::Value,
+    ) -> Result<Option<FetchedPayment>, PortError> {
+        self.inner.payment_from_payload(payload)
+    }
+
+    fn payment_for_event<'a>(
+        &'a self,
+        payload: &'a serde_json::Value,
+    ) -> Pin<Box<dyn Future<Output = Result<Option<ExternalId>, PortError>> + Send + 'a>> {
+        Box::pin(async move {
+            self.budget
+                .charge(self.source(), ApiOperation::Lookup)
+                .await?;
+            self.inner.payment_for_event(payload).await
+        })
+    }
+
+    fn for_tenant(&self, tenant: &TenantId) -> Result<Arc<dyn PaymentProvider>, PortError> {
+        Ok(self.budget.wrap(self.inner.for_tenant(tenant)?))
+    }
+}
+
+/// Calls in the current hour and day with the limits that apply, one
+/// entry per window counted or budgeted, by source, operation and period.
+pub async fn usage(
+    pool: &PgPool,
+    config: &RuntimeConfig,
+) -> Result<Vec<BudgetUsage>, PipelineError> {
+    let mut usage: Vec<BudgetUsage> = budget_repo::current_usage(pool)
+        .await?
+        .into_iter()
+        .map(|u| {
+            let budget = config.provider_budget(&u.source, u.operation, u.period);
+            u.with_budget(budget)
+        })
+        .collect();
+    let now = Utc::now();
+    for budget in &config.provider_budgets {
+        if !usage
+            .iter()
+            .any(|u| budget.applies_to(&u.source, u.operation, u.period))
+        {
+            let unused = BudgetUsage {
+                source: budget.source.clone(),
+                operation: budget.operation,
+                period: budget.period,
+                period_start: budget.period.start(now),
+                calls: 0,
+                refused: 0,
+                soft_limit: None,
+                hard_limit: None,
+                remaining: None,
+            };
+            usage.push(unused.with_budget(Some(budget)));
+        }
+    }
+    usage.sort_by(|a, b| {
+        (&a.source, a.operation.as_str(), a.period.as_str()).cmp(&(
+            &b.source,
+            b.operation.as_str(),
+            b.period.as_str(),
+        ))
+    });
+    Ok(usage)
+}
@@ -0,0 +1,109 @@
+use {
+    crate::{
+        domain::{
+            capture::{CaptureSummary, NewCapture},
+            payment::{NewProviderEvent, ProcessResult, lock_key},
+        },
+        error::PipelineError,
+        infra::postgres::{audit_repo::insert_audit_entry, capture_repo, payment_repo},
+    },
+    sqlx::PgPool,
+    uuid::Uuid,
+};
+
+/// Record a capture against its payment: dedup through `provider_events`
+/// under the payment's lock (the pipeline's), store the capture, then
+/// audit it on the payment with its totals so far. What the capture took
+/// is the rise in the charge's captured total over the highest one
+/// recorded; an event whose total is already covered, having arrived
+/// after a later capture, is audited and ignored as stale. Captures
+/// adding up to more than the authorization are recorded all the same,
+/// the provider having taken the money, and audited as an anomaly.
+pub async fn process_capture_event(
+    pool: &PgPool,
+    capture: &NewCapture,
+    actor: &str,
+) -> Result<ProcessResult, PipelineError> {
+    let external_id = capture.payment_external_id.as_str();
+    let mut tx = pool.begin().await?;
+    sqlx::query!("SET LOCAL lock_timeout = '5s'")
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query!(
+        "SELECT pg_advisory_xact_lock(hashtextextended($1, 0))",
+        lock_key(capture.tenant_id.as_ref(), external_id)
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let event = NewProviderEvent {
+        source: &capture.source,
+        tenant_id: capture.tenant_id.as_ref().map(|t| t.as_str()),
+        event_id: capture.event_id.as_str(),
+        object_id: external_id,
+        event_type: &capture.event_type,
+        provider_ts: capture.provider_ts,
+        payload: &capture.raw_event,
+    };
+    let is_new = payment_repo::insert_provider_event(&mut tx, &event).await?;
+    if !is_new {
+        tx.commit().await?;
+        return Ok(ProcessResult::Duplicate);
+    }
+
+    let latest = capture_repo::latest_for_charge(&mut tx, &capture.charge_id).await?;
+    let recorded = latest.map_or(0, |(_, total)| total);
+    // Captures can arrive before the payment; they are audited against it
+    // by external id until then.
+    let payment_id = payment_repo::find_payment_id(&mut tx, external_id).await?;
+    let Some(amount) = capture.amount_after(recorded) else {
+        let covering = latest.map_or_else(Uuid::now_v7, |(id, _)| id);
+        let captures = capture_repo::list_captures(&mut *tx, external_id).await?;
+        if let Some(summary) = CaptureSummary::new(captures)? {
+            let mut audit = capture.audit_entry(payment_id, actor, 0, &summary);
+            audit.action = "event_stale_ignored".to_string();
+            insert_audit_entry(&mut tx, &audit).await?;
+        }
+        tx.commit().await?;
+        tracing::info!(
+            external_id,
+            charge_id = %capture.charge_id,
+            captured_total = capture.captured_total.cents(),
+            recorded,
+            "capture already covered by a later one, ignored"
+        );
+        return Ok(ProcessResult::StaleIgnored(covering));
+    };
+
+    let id = Uuid::now_v7();
+    capture_repo::insert_capture(&mut tx, id, capture, &amount).await?;
+    let captures = capture_repo::list_captures(&mut *tx, external_id).await?;
+    let Some(summary) = CaptureSummary::new(captures)? else {
+        return Err(PipelineError::Validation(format!(
+            "capture {id} of {external_id} was not recorded"
+        )));
+    };
+    let audit = capture.audit_entry(payment_id, actor, amount.amount().cents(), &summary);
+    insert_audit_entry(&mut tx, &audit).await?;
+    tx.commit().await?;
+
+    if summary.over_captured {
+        tracing::warn!(
+            external_id,
+            authorized = summary.authorized,
+            captured = summary.captured,
+            "captures exceed the authorized amount, logged as anomaly"
+        );
+        return Ok(ProcessResult::Anomaly(id));
+    }
+    Ok(ProcessResult::Created(id))
+}
+
+/// The payment's captures and their totals; `None` when it has none.
+pub async fn get_capture_summary(
+    pool: &PgPool,
+    external_id: &str,
+) -> Result<Option<CaptureSummary>, PipelineError> {
+    let captures = capture_repo::list_captures(pool, external_id).await?;
+    Ok(CaptureSummary::new(captures)?)
+}
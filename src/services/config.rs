@@ -0,0 +1,137 @@
+use {
+    crate::{
+        domain::config::{RuntimeConfig, VersionedConfig},
+        error::PipelineError,
+        infra::postgres::{audit_repo::insert_audit_entry, config_repo},
+    },
+    sqlx::PgPool,
+    std::{sync::Arc, time::Duration},
+    tokio::sync::watch,
+};
+
+/// Shared, atomically swappable runtime config. Cloning is cheap; every
+/// clone sees the latest value.
+#[derive(Clone)]
+pub struct RuntimeConfigHandle {
+    tx: Arc<watch::Sender<Arc<VersionedConfig>>>,
+}
+
+impl RuntimeConfigHandle {
+    pub fn new(initial: VersionedConfig) -> Self {
+        Self {
+            tx: Arc::new(watch::Sender::new(Arc::new(initial))),
+        }
+    }
+
+    pub fn current(&self) -> Arc<VersionedConfig> {
+        self.tx.borrow().clone()
+    }
+
+    /// Receiver that is notified on every swap.
+    pub fn subscribe(&self) -> watch::Receiver<Arc<VersionedConfig>> {
+        self.tx.subscribe()
+    }
+
+    /// Swap in `next` unless it is older than what we already hold.
+    fn swap(&self, next: VersionedConfig) -> bool {
+        self.tx.send_if_modified(|cur| {
+            if next.version > cur.version {
+                *cur = Arc::new(next);
+                true
+            } else {
+                false
+            }
+        })
+    }
+}
+
+impl Default for RuntimeConfigHandle {
+    fn default() -> Self {
+        Self::new(VersionedConfig {
+            version: 0,
+            config: RuntimeConfig::default(),
+        })
+    }
+}
+
+/// Stored config, or defaults (version 0) if none was ever saved.
+pub async fn load(pool: &PgPool) -> Result<RuntimeConfigHandle, PipelineError> {
+    Ok(match config_repo::get_config(pool).await? {
+        Some(stored) => RuntimeConfigHandle::new(stored),
+        None => RuntimeConfigHandle::default(),
+    })
+}
+
+/// Validate, persist and audit `next`, then swap it in locally. Other
+/// replicas pick it up on their next sync. Mirrored writes and lookups
+/// follow the `payments_v2` stage as stored, so they switch everywhere on
+/// commit.
+pub async fn update_config(
+    pool: &PgPool,
+    handle: &RuntimeConfigHandle,
+    next: RuntimeConfig,
+    actor: &str,
+) -> Result<VersionedConfig, PipelineError> {
+    next.validate()?;
+
+    let mut tx = pool.begin().await?;
+    let before = config_repo::get_config_for_update(&mut tx)
+        .await?
+        .unwrap_or_else(|| VersionedConfig {
+            version: 0,
+            config: RuntimeConfig::default(),
+        });
+    before.config.payments_v2.check_move(next.payments_v2)?;
+    let version = before.version + 1;
+    config_repo::save_config(&mut tx, version, &next, actor).await?;
+    insert_audit_entry(&mut tx, &before.config.audit_entry(&next, version, actor)).await?;
+    tx.commit().await?;
+
+    let saved = VersionedConfig {
+        version,
+        config: next,
+    };
+    handle.swap(saved.clone());
+    tracing::info!(version, actor, "runtime config updated");
+    Ok(saved)
+}
+
+/// Pull the stored config if another replica changed it.
+pub async fn sync_once(pool: &PgPool, handle: &RuntimeConfigHandle) -> Result<bool, PipelineError> {
+    match config_repo::get_version(pool).await? {
+        Some(v) if v > handle.current().version => match config_repo::get_config(pool).await? {
+            Some(stored) => Ok(handle.swap(stored)),
+            None => Ok(false),
+        },
+        _ => Ok(false),
+    }
+}
+
+/// Every `interval`, reload the config if another replica changed it.
+pub async fn run_config_sync(
+    pool: PgPool,
+    handle: RuntimeConfigHandle,
+    interval: Duration,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    tracing::info!("config sync started");
+
+    loop {
+        tokio::select! {
+            _ = shutdown.changed() => {
+                tracing::info!("config sync shutting down");
+                return;
+            }
+            _ = tokio::time::sleep(interval) => {}
+        }
+
+        match sync_once(&pool, &handle).await {
+            Ok(true) => tracing::info!(
+                version = handle.current().version,
+                "runtime config reloaded"
+            ),
+            Ok(false) => {}
+            Err(e) => tracing::error!(error = %e, "config sync error"),
+        }
+    }
+}
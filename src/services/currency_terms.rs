@@ -0,0 +1,176 @@
+use {
+    crate::{
+        domain::currency_terms::{CurrencyTerms, ScheduleTerms, TermsTimeline},
+        error::PipelineError,
+        infra::postgres::{audit_repo::insert_audit_entry, currency_terms_repo},
+    },
+    chrono::{DateTime, Utc},
+    sqlx::PgPool,
+    uuid::Uuid,
+};
+
+/// What came of scheduling a version.
+#[derive(Debug)]
+pub enum Scheduled {
+    Created(CurrencyTerms),
+    /// The same terms were already scheduled from the same instant; a
+    /// retried request lands here.
+    Unchanged(CurrencyTerms),
+    /// Other terms are scheduled from the same instant; cancel them first.
+    Conflict(CurrencyTerms),
+}
+
+/// What came of cancelling a version.
+#[derive(Debug)]
+pub enum Cancellation {
+    /// Removed; the version before it, if any, runs on in its place.
+    Cancelled(CurrencyTerms),
+    /// Already in force, and past payments were read under it.
+    InForce(CurrencyTerms),
+}
+
+/// Schedule `request`'s terms from `request.valid_from` until the next
+/// version, cutting short the one in force then. Changes start after
+/// `now`, so past payments keep the terms they were made under; only a
+/// source and currency's first version may start earlier, there being
+/// nothing before it to reinterpret.
+pub async fn schedule(
+    pool: &PgPool,
+    request: &ScheduleTerms,
+    actor: &str,
+    now: DateTime<Utc>,
+) -> Result<Scheduled, PipelineError> {
+    let currency = request.validate()?;
+    let request = ScheduleTerms {
+        currency: currency.as_str().to_string(),
+        ..request.clone()
+    };
+    let (source, currency) = (request.source.as_str(), request.currency.as_str());
+
+    let mut tx = pool.begin().await?;
+    currency_terms_repo::lock(&mut tx, source, currency).await?;
+    if let Some(existing) =
+        currency_terms_repo::starting_at(&mut tx, source, currency, request.valid_from).await?
+    {
+        return Ok(if existing.matches(&request) {
+            Scheduled::Unchanged(existing)
+        } else {
+            Scheduled::Conflict(existing)
+        });
+    }
+    if request.valid_from <= now && currency_terms_repo::exists(&mut tx, source, currency).await? {
+        return Err(PipelineError::Validation(format!(
+            "{source} {currency} terms already exist; changes must start after {now}"
+        )));
+    }
+
+    let replaced =
+        currency_terms_repo::in_force_at(&mut tx, source, currency, request.valid_from).await?;
+    let next =
+        currency_terms_repo::next_start(&mut tx, source, currency, request.valid_from).await?;
+    if replaced.is_some() {
+        currency_terms_repo::move_end(&mut tx, source, currency, next, Some(request.valid_from))
+            .await?;
+    }
+    let terms = currency_terms_repo::insert(&mut tx, &request, next, actor).await?;
+    insert_audit_entry(
+        &mut tx,
+        &terms.audit_entry("currency_terms_scheduled", actor),
+    )
+    .await?;
+    tx.commit().await?;
+
+    tracing::info!(
+        source,
+        currency,
+        valid_from = %terms.valid_from,
+        accepted = terms.accepted,
+        fee_bps = terms.fee_bps,
+        actor,
+        "currency terms scheduled"
+    );
+    Ok(Scheduled::Created(terms))
+}
+
+/// Cancel a version not yet in force. `None` if there's no such version.
+pub async fn cancel(
+    pool: &PgPool,
+    id: Uuid,
+    actor: &str,
+    now: DateTime<Utc>,
+) -> Result<Option<Cancellation>, PipelineError> {
+    let Some(terms) = currency_terms_repo::get(pool, id).await? else {
+        return Ok(None);
+    };
+    let mut tx = pool.begin().await?;
+    currency_terms_repo::lock(&mut tx, &terms.source, &terms.currency).await?;
+    // Read again under the lock: a schedule may have moved its end.
+    let Some(terms) =
+        currency_terms_repo::starting_at(&mut tx, &terms.source, &terms.currency, terms.valid_from)
+            .await?
+    else {
+        return Ok(None);
+    };
+    if terms.valid_from <= now {
+        return Ok(Some(Cancellation::InForce(terms)));
+    }
+    currency_terms_repo::delete(&mut tx, terms.id).await?;
+    currency_terms_repo::move_end(
+        &mut tx,
+        &terms.source,
+        &terms.currency,
+        Some(terms.valid_from),
+        terms.valid_to,
+    )
+    .await?;
+    insert_audit_entry(
+        &mut tx,
+        &terms.audit_entry("currency_terms_cancelled", actor),
+    )
+    .await?;
+    tx.commit().await?;
+
+    tracing::info!(
+        source = %terms.source,
+        currency = %terms.currency,
+        valid_from = %terms.valid_from,
+        actor,
+        "scheduled currency terms cancelled"
+    );
+    Ok(Some(Cancellation::Cancelled(terms)))
+}
+
+/// Every version, or those in force at `at` when given.
+pub async fn list(
+    pool: &PgPool,
+    source: Option<&str>,
+    currency: Option<&str>,
+    at: Option<DateTime<Utc>>,
+) -> Result<Vec<CurrencyTerms>, PipelineError> {
+    let terms = match at {
+        Some(at) => currency_terms_repo::list_in_force(pool, at).await?,
+        None => return currency_terms_repo::list(pool, source, currency).await,
+    };
+    Ok(terms
+        .into_iter()
+        .filter(|t| {
+            source.is_none_or(|s| t.source == s) && currency.is_none_or(|c| t.currency == c)
+        })
+        .collect())
+}
+
+/// The terms in force at `at` for every source and currency that has
+/// them, as reports read them.
+pub async fn in_force_at(pool: &PgPool, at: DateTime<Utc>) -> Result<TermsTimeline, PipelineError> {
+    Ok(TermsTimeline::new(
+        currency_terms_repo::list_in_force(pool, at).await?,
+    ))
+}
+
+/// Every version of `source`'s terms, for looking up payments made at
+/// different times, as reconciliation does.
+pub async fn timeline(pool: &PgPool, source: &str) -> Result<TermsTimeline, PipelineError> {
+    Ok(TermsTimeline::new(
+        currency_terms_repo::list(pool, Some(source), None).await?,
+    ))
+}
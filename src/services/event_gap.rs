@@ -0,0 +1,138 @@
+use {
+    crate::{
+        domain::{
+            event_gap::{EventGapView, GapKind, GapScan, LIFECYCLES, ObservedLifecycle},
+            job::JobPriority,
+        },
+        error::PipelineError,
+        infra::postgres::{event_gap_repo, job_repo},
+        services::config::RuntimeConfigHandle,
+    },
+    chrono::{DateTime, TimeDelta, Utc},
+    sqlx::PgPool,
+    tokio::sync::watch,
+};
+
+/// Payments older than this aren't checked any more; their gaps stay as
+/// last recorded.
+const LOOKBACK: TimeDelta = TimeDelta::days(7);
+
+/// Check recent payments against their expected webhook lifecycle. New gaps
+/// are recorded and logged; a payment stuck open gets one refetch job, so
+/// the worker pulls its current state from the provider as if the missing
+/// webhook had been redelivered. Gaps that no longer show are resolved.
+pub async fn scan(
+    pool: &PgPool,
+    now: DateTime<Utc>,
+    timeout: TimeDelta,
+) -> Result<GapScan, PipelineError> {
+    let mut sources: Vec<&str> = LIFECYCLES.iter().map(|l| l.source).collect();
+    sources.dedup();
+    let observed = event_gap_repo::load_lifecycles(pool, &sources, now - LOOKBACK).await?;
+
+    let mut scan = GapScan {
+        checked: observed.len(),
+        ..Default::default()
+    };
+    let mut still_open = Vec::new();
+    for payment in &observed {
+        let Some(kind) = payment.gap(now, timeout) else {
+            continue;
+        };
+        still_open.push(format!("{}:{}", payment.external_id, kind.as_str()));
+
+        let gap = event_gap_repo::open_gap(
+            pool,
+            &payment.source,
+            &payment.external_id,
+            kind,
+            &payment.status,
+        )
+        .await?;
+        if gap.opened {
+            scan.opened += 1;
+            tracing::warn!(
+                external_id = %payment.external_id,
+                source = %payment.source,
+                kind = kind.as_str(),
+                status = %payment.status,
+                "webhook event gap detected"
+            );
+        }
+        if kind == GapKind::MissingTerminal && !gap.refetch_requested {
+            request_refetch(pool, payment, now).await?;
+            event_gap_repo::mark_refetch_requested(pool, gap.id).await?;
+            scan.refetches += 1;
+        }
+    }
+
+    let checked: Vec<String> = observed.into_iter().map(|p| p.external_id).collect();
+    scan.resolved = event_gap_repo::resolve_closed(pool, &checked, &still_open).await?;
+    Ok(scan)
+}
+
+/// Enqueue a job for the payment under a synthetic event id, once per payment.
+async fn request_refetch(
+    pool: &PgPool,
+    payment: &ObservedLifecycle,
+    now: DateTime<Utc>,
+) -> Result<(), PipelineError> {
+    job_repo::enqueue(
+        pool,
+        &payment.source,
+        payment.tenant_id.as_deref(),
+        &format!("evt_gap_{}", payment.external_id),
+        &payment.external_id,
+        "gap.refetch",
+        now.timestamp(),
+        &serde_json::json!({
+            "gap": GapKind::MissingTerminal.as_str(),
+            "external_id": payment.external_id,
+        }),
+        JobPriority::Normal,
+    )
+    .await?;
+    Ok(())
+}
+
+pub async fn list_gaps(
+    pool: &PgPool,
+    open: Option<bool>,
+    kind: Option<&str>,
+    limit: Option<i64>,
+) -> Result<Vec<EventGapView>, PipelineError> {
+    let limit = limit.unwrap_or(50).clamp(1, 500);
+    event_gap_repo::list_gaps(pool, open, kind, limit).await
+}
+
+/// Scans every `interval` with the configured timeout.
+pub async fn run_gap_detector(
+    pool: PgPool,
+    config: RuntimeConfigHandle,
+    interval: std::time::Duration,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    tracing::info!("event gap detector started");
+
+    loop {
+        tokio::select! {
+            _ = shutdown.changed() => {
+                tracing::info!("event gap detector shutting down");
+                return;
+            }
+            _ = tokio::time::sleep(interval) => {}
+        }
+
+        let timeout = config.current().config.event_gap_timeout();
+        match scan(&pool, Utc::now(), timeout).await {
+            Ok(scan) => tracing::debug!(
+                checked = scan.checked,
+                opened = scan.opened,
+                resolved = scan.resolved,
+                refetches = scan.refetches,
+                "event gap scan finished"
+            ),
+            Err(e) => tracing::error!(error = %e, "event gap scan error"),
+        }
+    }
+}
@@ -0,0 +1,403 @@
+use {
+    crate::{
+        domain::{
+            export::{
+                ExportFormat, ExportKind, ExportRequest, ExportRow, ExportView, NewExport,
+                day_window,
+            },
+            payment::{PaymentFilters, PaymentView},
+        },
+        error::PipelineError,
+        infra::{
+            export_store::ExportStore,
+            postgres::{audit_repo, export_repo, payment_repo},
+        },
+    },
+    chrono::{TimeDelta, Utc},
+    futures_util::{Stream, TryStreamExt},
+    sqlx::PgPool,
+    std::{path::Path, time::Duration},
+    tokio::{io::AsyncWriteExt, sync::mpsc, sync::watch},
+    tokio_stream::wrappers::ReceiverStream,
+    tracing::Instrument,
+    uuid::Uuid,
+};
+
+/// Rows are batched into chunks of about this size before being handed to
+/// the response body.
+const CHUNK_BYTES: usize = 64 * 1024;
+
+/// Encoded chunks waiting for the client. Once they're all unsent, the
+/// export stops reading rows until the client catches up, so memory stays
+/// bounded by roughly `CHUNK_BYTES * (CHUNKS_IN_FLIGHT + 1)` whatever the
+/// size of the result.
+const CHUNKS_IN_FLIGHT: usize = 4;
+
+/// How long a running export holds its row without renewing it. An export
+/// whose replica died is failed once its lease runs out, freeing the
+/// scheduled window for the next tick.
+const LEASE: Duration = Duration::from_secs(300);
+
+/// How often a running export renews its lease.
+const LEASE_RENEWAL: Duration = Duration::from_secs(60);
+
+/// Exported chunks, in order. An `Err` is always the last item: the export
+/// broke off and what came before is incomplete.
+pub type ExportStream = ReceiverStream<Result<Vec<u8>, PipelineError>>;
+
+/// Streams every payment matching `filters`, newest first, in `format`.
+/// The query runs on a background task that stops, releasing its
+/// connection, as soon as the returned stream is dropped.
+pub fn export_payments(
+    pool: PgPool,
+    mut filters: PaymentFilters,
+    format: ExportFormat,
+) -> Result<ExportStream, PipelineError> {
+    if filters.limit.is_some() || filters.offset.is_some() || filters.cursor.is_some() {
+        return Err(PipelineError::Validation(
+            "exports cover every matching payment; limit, offset and cursor don't apply".into(),
+        ));
+    }
+    if let Some(exact) = filters.amount {
+        filters.amount_min = Some(exact);
+        filters.amount_max = Some(exact);
+    }
+    if format == ExportFormat::Parquet {
+        return Err(PipelineError::Validation(
+            "parquet exports are written to storage through /admin/exports".into(),
+        ));
+    }
+    filters.normalize_reference()?;
+    filters.normalize_tags()?;
+
+    let (tx, rx) = mpsc::channel(CHUNKS_IN_FLIGHT);
+    let span = tracing::info_span!("export", format = format.extension());
+    tokio::spawn(
+        async move {
+            match pump(&pool, &filters, format, &tx).await {
+                Ok(rows) => tracing::info!(rows, "export finished"),
+                Err(e) => {
+                    tracing::error!(error = %e, "export failed");
+                    let _ = tx.send(Err(e)).await;
+                }
+            }
+        }
+        .instrument(span),
+    );
+    Ok(ReceiverStream::new(rx))
+}
+
+/// Returns the number of rows sent, or stops early without error if the
+/// receiver went away.
+async fn pump(
+    pool: &PgPool,
+    filters: &PaymentFilters,
+    format: ExportFormat,
+    tx: &mpsc::Sender<Result<Vec<u8>, PipelineError>>,
+) -> Result<u64, PipelineError> {
+    let mut rows = payment_repo::stream_payments(pool, filters);
+    let mut buf = Vec::with_capacity(CHUNK_BYTES);
+    buf.extend_from_slice(format.header::<PaymentView>());
+    let mut count = 0;
+
+    while let Some(payment) = rows.try_next().await? {
+        format.write_row(&mut buf, &payment)?;
+        count += 1;
+        if buf.len() >= CHUNK_BYTES {
+            let chunk = std::mem::replace(&mut buf, Vec::with_capacity(CHUNK_BYTES));
+            if tx.send(Ok(chunk)).await.is_err() {
+                tracing::info!(rows = count, "export abandoned by client");
+                return Ok(count);
+            }
+        }
+    }
+    if !buf.is_empty() {
+        let _ = tx.send(Ok(buf)).await;
+    }
+    Ok(count)
+}
+
+/// Record an export of `request` by `actor` and write it in the background.
+/// Returns it `running`; the window defaults to the previous UTC day.
+pub async fn start_export(
+    pool: &PgPool,
+    store: &ExportStore,
+    request: ExportRequest,
+    actor: &str,
+) -> Result<ExportView, PipelineError> {
+    let (since, until) = match (request.since, request.until) {
+        (None, None) => day_window(Utc::now().date_naive() - TimeDelta::days(1)),
+        (since, until) => {
+            let until = until.unwrap_or_else(Utc::now);
+            (since.unwrap_or(until - TimeDelta::days(1)), until)
+        }
+    };
+    if since >= until {
+        return Err(PipelineError::Validation(format!(
+            "empty export window: {since} >= {until}"
+        )));
+    }
+    Encoder::supports(request.format)?;
+    let new = NewExport {
+        kind: request.kind,
+        format: request.format,
+        since,
+        until,
+        trigger: "manual",
+        requested_by: actor,
+    };
+    let export = export_repo::create_export(pool, &new, LEASE)
+        .await?
+        .ok_or_else(|| PipelineError::Validation("export was not recorded".into()))?;
+
+    let (pool, store, running) = (pool.clone(), store.clone(), export.clone());
+    tokio::spawn(async move {
+        // Failures are recorded on the export row.
+        let _ = run_export(&pool, &store, &running).await;
+    });
+    Ok(export)
+}
+
+/// Write `export` to a staging file, publish it to `store`, and mark the row
+/// completed with its location, or failed. The export's lease is renewed
+/// while it is written.
+pub async fn run_export(
+    pool: &PgPool,
+    store: &ExportStore,
+    export: &ExportView,
+) -> Result<(), PipelineError> {
+    let file_name = export.file_name();
+    let result = async {
+        let staged = store.staging_path(&file_name).await?;
+        let mut lease = Lease::new(pool, export.id);
+        let written = write_export(pool, export, &staged, &mut lease).await;
+        if written.is_err() {
+            let _ = tokio::fs::remove_file(&staged).await;
+        }
+        let (rows, bytes) = written?;
+        let location = store.publish(&file_name, &staged).await?;
+        Ok::<_, PipelineError>((location, rows, bytes))
+    }
+    .await;
+
+    match result {
+        Ok((location, rows, bytes)) => {
+            export_repo::complete_export(pool, export.id, &location, rows, bytes).await?;
+            tracing::info!(export_id = %export.id, %location, rows, bytes, "export completed");
+            Ok(())
+        }
+        Err(e) => {
+            tracing::error!(export_id = %export.id, error = %e, "export failed");
+            export_repo::fail_export(pool, export.id, &e.to_string()).await?;
+            Err(e)
+        }
+    }
+}
+
+/// Returns the rows and bytes written.
+async fn write_export(
+    pool: &PgPool,
+    export: &ExportView,
+    path: &Path,
+    lease: &mut Lease<'_>,
+) -> Result<(i64, i64), PipelineError> {
+    let format = match export.format.as_str() {
+        "ndjson" => ExportFormat::Ndjson,
+        "parquet" => ExportFormat::Parquet,
+        _ => ExportFormat::Csv,
+    };
+    let (since, until) = (export.window_start, export.window_end);
+    match export.kind.parse::<ExportKind>()? {
+        ExportKind::Payments => {
+            let filters = PaymentFilters {
+                start_date: Some(since),
+                end_date: Some(until),
+                ..Default::default()
+            };
+            let rows = payment_repo::stream_payments(pool, &filters);
+            write_rows(rows, format, path, lease).await
+        }
+        ExportKind::Audit => {
+            let rows = audit_repo::stream_audit_records(pool, since, until);
+            write_rows(rows, format, path, lease).await
+        }
+    }
+}
+
+async fn write_rows<R: ExportRow>(
+    rows: impl Stream<Item = Result<R, PipelineError>>,
+    format: ExportFormat,
+    path: &Path,
+    lease: &mut Lease<'_>,
+) -> Result<(i64, i64), PipelineError> {
+    let storage = |e: std::io::Error| PipelineError::Storage(format!("{}: {e}", path.display()));
+    let mut file = tokio::fs::File::create(path).await.map_err(storage)?;
+    let mut buf = Vec::with_capacity(CHUNK_BYTES);
+    let mut encoder = Encoder::new::<R>(format, &mut buf)?;
+    let (mut count, mut bytes) = (0, 0);
+
+    futures_util::pin_mut!(rows);
+    while let Some(row) = rows.try_next().await? {
+        encoder.push(&mut buf, &row)?;
+        count += 1;
+        if buf.len() >= CHUNK_BYTES {
+            file.write_all(&buf).await.map_err(storage)?;
+            bytes += buf.len() as i64;
+            buf.clear();
+            lease.keep().await?;
+        }
+    }
+    encoder.finish(&mut buf)?;
+    file.write_all(&buf).await.map_err(storage)?;
+    bytes += buf.len() as i64;
+    file.sync_all().await.map_err(storage)?;
+    Ok((count, bytes))
+}
+
+pub async fn list_exports(
+    pool: &PgPool,
+    limit: Option<i64>,
+) -> Result<Vec<ExportView>, PipelineError> {
+    export_repo::list_exports(pool, limit.unwrap_or(50).clamp(1, 500)).await
+}
+
+pub async fn get_export(pool: &PgPool, id: Uuid) -> Result<Option<ExportView>, PipelineError> {
+    export_repo::get_export(pool, id).await
+}
+
+/// On start and then every `interval` (hourly by default), export the
+/// previous UTC day of each of `kinds` as CSV, unless that's already done
+/// or running, possibly on another replica. A failed day is tried again on
+/// the next tick, as is one whose replica died mid-export, once its lease
+/// has run out.
+pub async fn run_exporter(
+    pool: PgPool,
+    store: ExportStore,
+    kinds: Vec<ExportKind>,
+    interval: std::time::Duration,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    tracing::info!(?kinds, "exporter started");
+    let mut ticks = tokio::time::interval(interval);
+    ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            _ = shutdown.changed() => {
+                tracing::info!("exporter shutting down");
+                return;
+            }
+            _ = ticks.tick() => {}
+        }
+
+        match export_repo::fail_expired(&pool).await {
+            Ok(0) => {}
+            Ok(n) => tracing::warn!(exports = n, "failed exports whose lease ran out"),
+            Err(e) => tracing::error!(error = %e, "export lease check error"),
+        }
+        let (since, until) = day_window(Utc::now().date_naive() - TimeDelta::days(1));
+        for &kind in &kinds {
+            let new = NewExport {
+                kind,
+                format: ExportFormat::Csv,
+                since,
+                until,
+                trigger: "schedule",
+                requested_by: "scheduler",
+            };
+            let created = export_repo::create_export(&pool, &new, LEASE).await;
+            let result = match created {
+                Ok(Some(export)) => run_export(&pool, &store, &export).await,
+                Ok(None) => Ok(()),
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                tracing::error!(kind = kind.as_str(), error = %e, "scheduled export error");
+            }
+        }
+    }
+}
+
+/// Turns rows into the bytes of one export format.
+enum Encoder {
+    Text(ExportFormat),
+    #[cfg(feature = "parquet")]
+    Parquet(Box<crate::infra::parquet::ParquetEncoder>),
+}
+
+impl Encoder {
+    /// An encoder for `format`, its header written to `buf`.
+    fn new<R: ExportRow>(format: ExportFormat, buf: &mut Vec<u8>) -> Result<Self, PipelineError> {
+        Self::supports(format)?;
+        buf.extend_from_slice(format.header::<R>());
+        Ok(match format {
+            #[cfg(feature = "parquet")]
+            ExportFormat::Parquet => {
+                Self::Parquet(Box::new(crate::infra::parquet::ParquetEncoder::new::<R>()?))
+            }
+            format => Self::Text(format),
+        })
+    }
+
+    /// Whether this build writes `format`.
+    fn supports(format: ExportFormat) -> Result<(), PipelineError> {
+        if format == ExportFormat::Parquet && cfg!(not(feature = "parquet")) {
+            return Err(PipelineError::Validation(
+                "parquet exports need the `parquet` feature".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn push<R: ExportRow>(&mut self, buf: &mut Vec<u8>, row: &R) -> Result<(), PipelineError> {
+        match self {
+            Self::Text(format) => Ok(format.write_row(buf, row)?),
+            #[cfg(feature = "parquet")]
+            Self::Parquet(encoder) => encoder.push(buf, row),
+        }
+    }
+
+    /// Write whatever the format keeps back until the end: for Parquet, the
+    /// last row group and the footer.
+    #[cfg_attr(not(feature = "parquet"), allow(unused_variables, clippy::ptr_arg))]
+    fn finish(self, buf: &mut Vec<u8>) -> Result<(), PipelineError> {
+        match self {
+            Self::Text(_) => Ok(()),
+            #[cfg(feature = "parquet")]
+            Self::Parquet(encoder) => encoder.finish(buf),
+        }
+    }
+}
+
+/// A running export's hold on its row, renewed every `LEASE_RENEWAL`.
+struct Lease<'a> {
+    pool: &'a PgPool,
+    id: Uuid,
+    renewed: tokio::time::Instant,
+}
+
+impl<'a> Lease<'a> {
+    fn new(pool: &'a PgPool, id: Uuid) -> Self {
+        Self {
+            pool,
+            id,
+            renewed: tokio::time::Instant::now(),
+        }
+    }
+
+    /// Renew the lease if it is due. Fails if the export was given up on
+    /// in the meantime, so it stops rather than racing its retry.
+    async fn keep(&mut self) -> Result<(), PipelineError> {
+        if self.renewed.elapsed() < LEASE_RENEWAL {
+            return Ok(());
+        }
+        if !export_repo::renew_lease(self.pool, self.id, LEASE).await? {
+            return Err(PipelineError::Storage(format!(
+                "export {} lost its lease",
+                self.id
+            )));
+        }
+        self.renewed = tokio::time::Instant::now();
+        Ok(())
+    }
+}
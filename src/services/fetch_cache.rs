@@ -0,0 +1,125 @@
+use {
+    crate::domain::{id::ExternalId, provider::FetchedPayment},
+    chrono::{DateTime, TimeDelta, Utc},
+    std::collections::HashMap,
+};
+
+/// Objects cached at once; past this, expired entries are dropped, and if
+/// that frees nothing, everything is.
+const MAX_ENTRIES: usize = 10_000;
+
+/// Provider clocks drift from ours; an event stamped within this much of a
+/// fetch may describe a change the fetch didn't see.
+const CLOCK_SLACK_SECS: i64 = 1;
+
+#[derive(Debug)]
+struct Cached {
+    payment: FetchedPayment,
+    /// When the fetch started: the object was at least this fresh.
+    fetched_at: DateTime<Utc>,
+}
+
+/// A worker's recent provider fetches, by source and object, so a burst of
+/// events for one object spread over several batches costs one API call
+/// rather than one per batch. An entry only serves jobs whose events are
+/// older than its fetch: an event at least as new, as any status change
+/// brings, is fetched again and the entry replaced. Failed fetches are
+/// never cached.
+#[derive(Debug, Default)]
+pub struct FetchCache {
+    entries: HashMap<(String, ExternalId), Cached>,
+}
+
+impl FetchCache {
+    /// The cached object, if fetched within `ttl` of `now` and after
+    /// `newest_event_ts` (the provider timestamp, in seconds, of the newest
+    /// event about to be applied).
+    pub fn get(
+        &self,
+        source: &str,
+        id: &ExternalId,
+        newest_event_ts: i64,
+        ttl: TimeDelta,
+        now: DateTime<Utc>,
+    ) -> Option<FetchedPayment> {
+        let cached = self.entries.get(&(source.to_string(), id.clone()))?;
+        let fresh = now - cached.fetched_at < ttl;
+        let after_event = newest_event_ts + CLOCK_SLACK_SECS < cached.fetched_at.timestamp();
+        (fresh && after_event).then(|| cached.payment.clone())
+    }
+
+    /// Remember `payment`, fetched from `source` starting at `fetched_at`.
+    pub fn insert(
+        &mut self,
+        source: &str,
+        payment: &FetchedPayment,
+        fetched_at: DateTime<Utc>,
+        ttl: TimeDelta,
+    ) {
+        if self.entries.len() >= MAX_ENTRIES {
+            self.entries.retain(|_, c| fetched_at - c.fetched_at < ttl);
+            if self.entries.len() >= MAX_ENTRIES {
+                self.entries.clear();
+            }
+        }
+        self.entries.insert(
+            (source.to_string(), payment.external_id.clone()),
+            Cached {
+                payment: payment.clone(),
+                fetched_at,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::domain::{
+            money::{Currency, Money, MoneyAmount},
+            payment::{PaymentDirection, PaymentStatus},
+        },
+    };
+
+    fn payment(id: &str) -> FetchedPayment {
+        FetchedPayment {
+            external_id: ExternalId::new(id).unwrap(),
+            direction: PaymentDirection::Inbound,
+            status: PaymentStatus::Succeeded,
+            money: Money::new(MoneyAmount::new(500).unwrap(), Currency::USD),
+            metadata: serde_json::json!({}),
+            parent_external_id: None,
+            failure: None,
+            authorized_amount: None,
+            receipt: None,
+            customer: None,
+            created_at: None,
+        }
+    }
+
+    #[test]
+    fn entries_serve_older_events_until_they_expire() {
+        let mut cache = FetchCache::default();
+        let ttl = TimeDelta::seconds(10);
+        let fetched_at = DateTime::from_timestamp(1_000, 0).unwrap();
+        let id = ExternalId::new("pi_1").unwrap();
+        cache.insert("stripe", &payment("pi_1"), fetched_at, ttl);
+
+        let later = fetched_at + TimeDelta::seconds(5);
+        assert!(cache.get("stripe", &id, 990, ttl, later).is_some());
+        assert!(cache.get("paypal", &id, 990, ttl, later).is_none());
+        // An event as new as the fetch may be a change it missed.
+        assert!(cache.get("stripe", &id, 999, ttl, later).is_none());
+        assert!(cache.get("stripe", &id, 1_000, ttl, later).is_none());
+        // Expired.
+        let expired = fetched_at + TimeDelta::seconds(10);
+        assert!(cache.get("stripe", &id, 990, ttl, expired).is_none());
+        // Off.
+        assert!(
+            cache
+                .get("stripe", &id, 990, TimeDelta::zero(), later)
+                .is_none()
+        );
+    }
+}
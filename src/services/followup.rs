@@ -0,0 +1,126 @@
+use {
+    crate::{
+        domain::{
+            followup::FollowUp,
+            id::{EventId, ExternalId},
+            payment::PaymentTrigger,
+            provider::ProviderRegistry,
+        },
+        error::PipelineError,
+        infra::postgres::{
+            followup_repo::{self, FollowUpRow},
+            payment_repo,
+        },
+        retry::{Backoff, Jitter, Policy, Verdict},
+        services::payment::pipeline::fetch_and_process_payment,
+    },
+    chrono::Utc,
+    futures_util::{StreamExt, stream},
+    sqlx::PgPool,
+    std::time::Duration,
+};
+
+/// Failed follow-ups back off as jobs do: 2s, doubled after each failure,
+/// for 5 attempts in all. Errors that retrying can't fix never get here.
+const RETRY: Policy<PipelineError> = Policy {
+    max_attempts: 5,
+    backoff: Backoff::Exponential(Duration::from_secs(2)),
+    max_delay: Duration::from_secs(3600),
+    jitter: Jitter::None,
+    retryable: |_| Verdict::Retry,
+};
+
+/// Claim one batch of due follow-ups and run them, at most `concurrency`
+/// at a time. Returns how many were claimed.
+pub async fn run_due(
+    pool: &PgPool,
+    providers: &ProviderRegistry,
+    claimed_by: &str,
+    batch_size: i64,
+    concurrency: usize,
+) -> Result<usize, PipelineError> {
+    let mut tx = pool.begin().await?;
+    let rows = followup_repo::claim(&mut tx, batch_size, claimed_by).await?;
+    tx.commit().await?;
+    let claimed = rows.len();
+
+    let mut runs = stream::iter(rows)
+        .map(|row| run_one(pool, providers, row))
+        .buffer_unordered(concurrency);
+    while runs.next().await.is_some() {}
+    Ok(claimed)
+}
+
+async fn run_one(pool: &PgPool, providers: &ProviderRegistry, row: FollowUpRow) {
+    // A kind this build doesn't know was queued by a newer one, mid-deploy;
+    // it fails and is retried, by then most likely on a newer worker.
+    let outcome = match serde_json::from_value::<FollowUp>(row.payload) {
+        Ok(followup) => run(pool, providers, &followup).await,
+        Err(e) => Err(e.into()),
+    };
+    let recorded = match &outcome {
+        Ok(()) => {
+            tracing::info!(followup_id = %row.id, kind = %row.kind, "follow-up done");
+            followup_repo::complete(pool, row.id, None).await
+        }
+        Err(
+            PipelineError::Validation(msg)
+            | PipelineError::ProviderMissing(msg)
+            | PipelineError::ProviderRejected(msg),
+        ) => {
+            tracing::warn!(followup_id = %row.id, kind = %row.kind, error = %msg, "follow-up dropped (no retry)");
+            followup_repo::complete(pool, row.id, Some(msg)).await
+        }
+        Err(e) => {
+            tracing::error!(
+                followup_id = %row.id,
+                kind = %row.kind,
+                attempts = row.attempts + 1,
+                error = %e,
+                "follow-up failed, scheduling retry"
+            );
+            let retry_in = RETRY.delay(row.attempts as u32 + 1, e);
+            followup_repo::fail(pool, row.id, &e.to_string(), retry_in).await
+        }
+    };
+    // The follow-up stays `processing` until the reaper picks it up.
+    if let Err(e) = recorded {
+        tracing::error!(followup_id = %row.id, error = %e, "follow-up bookkeeping error");
+    }
+}
+
+async fn run(
+    pool: &PgPool,
+    providers: &ProviderRegistry,
+    followup: &FollowUp,
+) -> Result<(), PipelineError> {
+    match followup {
+        FollowUp::FetchParent {
+            source,
+            external_id,
+            tenant_id,
+        } => {
+            let external_id = ExternalId::new(external_id)?;
+            // Its own webhook may have got there first.
+            if payment_repo::get_payment_by_id(pool, external_id.clone())
+                .await?
+                .is_some()
+            {
+                return Ok(());
+            }
+            let provider = providers.get_scoped(source, tenant_id.as_ref())?;
+            let trigger = PaymentTrigger {
+                source: source.clone(),
+                tenant_id: tenant_id.clone(),
+                event_id: EventId::new(format!("evt_parent_{external_id}"))?,
+                event_type: "followup.fetch_parent".to_string(),
+                external_id,
+                raw_event: serde_json::json!(followup),
+                provider_ts: Utc::now().timestamp(),
+            };
+            let actor = format!("followup:{source}");
+            fetch_and_process_payment(pool, provider.as_ref(), trigger, &actor).await?;
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use {
+    crate::{
+        domain::{
+            hook::{HookDelivery, HookRegistry, Transition, TransitionNotice, deliver},
+            id::ExternalId,
+        },
+        error::PipelineError,
+        infra::postgres::{hook_repo, payment_repo},
+        retry::{Backoff, Jitter, Policy, Verdict},
+    },
+    sqlx::PgPool,
+    std::time::Duration,
+    tokio::sync::watch,
+};
+
+const BATCH_SIZE: i64 = 100;
+const IDLE_INTERVAL: Duration = Duration::from_secs(2);
+/// A failed delivery is due again 2s later, doubling up to an hour between
+/// attempts: about a day and a half of backoff before it is given up on.
+const RETRY: Policy<PipelineError> = Policy {
+    max_attempts: 40,
+    backoff: Backoff::Exponential(Duration::from_secs(2)),
+    max_delay: Duration::from_secs(3600),
+    jitter: Jitter::None,
+    retryable: |_| Verdict::Retry,
+};
+
+/// Deliver one batch of queued transitions to the registered hooks. The
+/// rows stay locked while their hooks run and are deleted only once a hook
+/// has accepted its delivery; a failure is rescheduled with backoff.
+/// Returns the number of deliveries attempted.
+pub async fn dispatch_once(pool: &PgPool, hooks: &HookRegistry) -> Result<usize, PipelineError> {
+    let mut tx = pool.begin().await?;
+    let batch = hook_repo::claim_batch(&mut tx, &hooks.names(), BATCH_SIZE).await?;
+
+    let mut done = Vec::with_capacity(batch.len());
+    for delivery in &batch {
+        match run_one(pool, hooks, delivery).await {
+            Ok(()) => done.push(delivery.seq),
+            Err(e) => {
+                tracing::warn!(
+                    hook = %delivery.hook,
+                    event_id = %delivery.event_id,
+                    attempts = delivery.attempts + 1,
+                    error = %e,
+                    "transition hook failed"
+                );
+                let retry_in = RETRY.delay(delivery.attempts as u32 + 1, &e);
+                hook_repo::fail(&mut tx, delivery.seq, &e.to_string(), retry_in).await?;
+            }
+        }
+    }
+    hook_repo::complete(&mut tx, &done).await?;
+    tx.commit().await?;
+    Ok(batch.len())
+}
+
+async fn run_one(
+    pool: &PgPool,
+    hooks: &HookRegistry,
+    delivery: &HookDelivery,
+) -> Result<(), PipelineError> {
+    let hook = hooks.get(&delivery.hook).ok_or_else(|| {
+        PipelineError::Validation(format!("hook not registered: {}", delivery.hook))
+    })?;
+    let transition = Transition::from_audit(&delivery.action, &delivery.detail)
+        .ok_or_else(|| PipelineError::Validation("audit entry records no transition".into()))?;
+    let external_id = ExternalId::new(delivery.external_id.clone().unwrap_or_default())?;
+    let payment = payment_repo::get_payment_by_id(pool, external_id)
+        .await?
+        .ok_or_else(|| PipelineError::Validation("payment not found".into()))?;
+
+    let notice = TransitionNotice {
+        event_id: delivery.event_id.clone(),
+        actor: delivery.actor.clone(),
+        occurred_at: delivery.created_at,
+        payment,
+    };
+    Ok(deliver(hook.as_ref(), transition, &notice).await?)
+}
+
+/// Subscribe the registered hooks and deliver their transitions continuously;
+/// back off only when nothing is due or the database is unreachable.
+pub async fn run_hook_dispatcher(
+    pool: PgPool,
+    hooks: HookRegistry,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    if let Err(e) = hook_repo::subscribe(&pool, &hooks.names()).await {
+        tracing::error!(error = %e, "failed to subscribe transition hooks");
+        return;
+    }
+    tracing::info!(hooks = ?hooks.names(), "hook dispatcher started");
+
+    loop {
+        let wait = match dispatch_once(&pool, &hooks).await {
+            // A batch holds one delivery per hook and payment at most, so
+            // keep going while there is any work.
+            Ok(0) => IDLE_INTERVAL,
+            Ok(_) => Duration::ZERO,
+            Err(e) => {
+                tracing::error!(error = %e, "hook dispatcher error");
+                IDLE_INTERVAL
+            }
+        };
+
+        tokio::select! {
+            _ = shutdown.changed() => {
+                tracing::info!("hook dispatcher shutting down");
+                return;
+            }
+            _ = tokio::time::sleep(wait) => {}
+        }
+    }
+}
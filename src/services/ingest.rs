@@ -0,0 +1,78 @@
+use {
+    crate::{
+        domain::ingest::{BatchFormat, BatchSummary, IngestCounts, RowResult, parse_batch},
+        error::PipelineError,
+        services::payment::pipeline::process_payment_event,
+    },
+    sqlx::PgPool,
+};
+
+/// Ingest a settlement export: every row goes through the payment pipeline
+/// on its own, like a webhook event would, and the summary says what became
+/// of each. A row that can't be read or applied is reported and skipped;
+/// only an unreadable batch as a whole is an error.
+pub async fn ingest_batch(
+    pool: &PgPool,
+    format: BatchFormat,
+    body: &str,
+    actor: &str,
+) -> Result<BatchSummary, PipelineError> {
+    let rows = parse_batch(format, body)?;
+    let mut counts = IngestCounts::default();
+    let mut results = Vec::with_capacity(rows.len());
+    for (row, event) in rows {
+        let (event_id, external_id) = match &event {
+            Ok(e) => (Some(e.event_id.clone()), Some(e.external_id.to_string())),
+            Err(_) => (None, None),
+        };
+        let applied = async {
+            let payment = event?.to_new_payment()?;
+            process_payment_event(pool, &payment, actor).await
+        }
+        .await;
+        let (result, error) = match applied {
+            Ok(result) => {
+                counts.record(&result);
+                (result.as_str(), None)
+            }
+            Err(e) => {
+                counts.errors += 1;
+                ("error", Some(row_error(row, e)))
+            }
+        };
+        results.push(RowResult {
+            row,
+            event_id,
+            external_id,
+            result,
+            error,
+        });
+    }
+
+    tracing::info!(
+        %actor,
+        rows = results.len(),
+        created = counts.created,
+        updated = counts.updated,
+        duplicates = counts.duplicates,
+        errors = counts.errors,
+        "batch ingested"
+    );
+    Ok(BatchSummary {
+        rows: results.len(),
+        counts,
+        results,
+    })
+}
+
+/// What the caller is told about a failed row: validation problems in
+/// full, anything else only as an internal error (it's logged here).
+fn row_error(row: usize, err: PipelineError) -> String {
+    match err {
+        PipelineError::Validation(msg) => msg,
+        other => {
+            tracing::error!(row, error = %other, "batch row failed");
+            "internal error".into()
+        }
+    }
+}
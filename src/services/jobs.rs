@@ -0,0 +1,96 @@
+use {
+    crate::{
+        domain::{
+            id::TenantId,
+            job::{EventStatus, JobDetail, JobFilters, JobView, RequeueFilter},
+        },
+        error::PipelineError,
+        infra::postgres::{audit_repo::insert_audit_entry, job_repo},
+    },
+    sqlx::PgPool,
+    uuid::Uuid,
+};
+
+/// Outcome of retrying one job.
+#[derive(Debug)]
+pub enum RetryOutcome {
+    /// Back in the queue; the job as it was before the retry.
+    Requeued(JobView),
+    /// Not in the dead-letter queue: still pending, in flight or done.
+    NotFailed(JobView),
+    NotFound,
+}
+
+/// Newest first; `?status=failed` is the dead-letter queue.
+pub async fn list_jobs(pool: &PgPool, filters: JobFilters) -> Result<Vec<JobView>, PipelineError> {
+    let limit = filters.limit.unwrap_or(50).clamp(1, 500);
+    job_repo::list_jobs(pool, &filters, limit).await
+}
+
+/// A job with its attempt history.
+pub async fn get_job_detail(pool: &PgPool, id: Uuid) -> Result<Option<JobDetail>, PipelineError> {
+    let Some(job) = job_repo::get_job(pool, id).await? else {
+        return Ok(None);
+    };
+    let history = job_repo::list_attempts(pool, id).await?;
+    Ok(Some(JobDetail { job, history }))
+}
+
+/// Where the queued job for `event_id` stands, or `None` if the event was
+/// never queued (unknown, or processed inline). Event ids are only unique
+/// per source and tenant: one received from several needs `source`, and
+/// `tenant` limits the search to one connected account's events.
+pub async fn get_event_status(
+    pool: &PgPool,
+    event_id: &str,
+    source: Option<&str>,
+    tenant: Option<&TenantId>,
+) -> Result<Option<EventStatus>, PipelineError> {
+    let tenant = tenant.map(TenantId::as_str);
+    let mut statuses = job_repo::event_status(pool, event_id, source, tenant).await?;
+    if statuses.len() > 1 {
+        return Err(PipelineError::Validation(format!(
+            "event {event_id} was received from more than one source or account; pass ?source="
+        )));
+    }
+    Ok(statuses.pop())
+}
+
+/// Reset a failed job's attempts and make it due now, audited as `actor`.
+pub async fn retry_job(
+    pool: &PgPool,
+    id: Uuid,
+    actor: &str,
+) -> Result<RetryOutcome, PipelineError> {
+    let mut tx = pool.begin().await?;
+    let Some(job) = job_repo::retry_failed(&mut tx, id).await? else {
+        tx.rollback().await?;
+        return Ok(match job_repo::get_job(pool, id).await? {
+            Some(job) => RetryOutcome::NotFailed(job),
+            None => RetryOutcome::NotFound,
+        });
+    };
+    insert_audit_entry(&mut tx, &job.retry_audit_entry(actor)).await?;
+    tx.commit().await?;
+
+    tracing::info!(job_id = %id, event_id = %job.event_id, actor, "failed job requeued");
+    Ok(RetryOutcome::Requeued(job))
+}
+
+/// Requeue every failed job matching `filter` in one go, e.g. after a
+/// provider outage. Returns how many were requeued.
+pub async fn requeue_failed(
+    pool: &PgPool,
+    filter: &RequeueFilter,
+    actor: &str,
+) -> Result<u64, PipelineError> {
+    let mut tx = pool.begin().await?;
+    let requeued = job_repo::requeue_failed(&mut tx, filter).await?;
+    if requeued > 0 {
+        insert_audit_entry(&mut tx, &filter.audit_entry(requeued, actor)).await?;
+    }
+    tx.commit().await?;
+
+    tracing::info!(requeued, actor, source = ?filter.source, "failed jobs requeued");
+    Ok(requeued)
+}
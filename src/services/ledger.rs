@@ -0,0 +1,41 @@
+use {
+    crate::{
+        domain::{
+            id::ExternalId,
+            ledger::{LedgerEntryView, NewLedgerEntry},
+            payment::{NewPayment, PaymentStatus},
+        },
+        error::PipelineError,
+        infra::postgres::{ledger_repo, payment_repo},
+    },
+    sqlx::PgPool,
+    uuid::Uuid,
+};
+
+/// Pipeline hook, run in the pipeline's transaction after a payment was
+/// created or changed status: post the entry taking its ledger balances to
+/// what the new status means, if any move. Runs under the payment's lock,
+/// so the balances read here can't change before the entry is written.
+pub(crate) async fn post(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    payment_id: Uuid,
+    payment: &NewPayment,
+    from_status: Option<&PaymentStatus>,
+) -> Result<(), PipelineError> {
+    let current = ledger_repo::balances(tx, payment_id).await?;
+    if let Some(entry) = NewLedgerEntry::for_change(payment_id, payment, from_status, &current) {
+        ledger_repo::insert_entry(tx, &entry).await?;
+    }
+    Ok(())
+}
+
+/// A payment's ledger entries, oldest first, or `None` if it doesn't exist.
+pub async fn get_payment_ledger(
+    pool: &PgPool,
+    id: ExternalId,
+) -> Result<Option<Vec<LedgerEntryView>>, PipelineError> {
+    if !payment_repo::payment_exists(pool, id.as_str()).await? {
+        return Ok(None);
+    }
+    Ok(Some(ledger_repo::list_entries(pool, id.as_str()).await?))
+}
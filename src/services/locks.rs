@@ -0,0 +1,68 @@
+use {
+    crate::{
+        domain::lock::{self, Backend, LockFilters, LockReport, TerminateRequest},
+        error::PipelineError,
+        infra::postgres::{audit_repo::insert_audit_entry, lock_repo},
+    },
+    sqlx::PgPool,
+};
+
+/// Outcome of terminating one backend.
+#[derive(Debug)]
+pub enum TerminateOutcome {
+    Terminated(Backend),
+    /// Connected, but not a transaction old enough holding payment locks.
+    Ineligible(Backend),
+    NotFound,
+}
+
+/// What may be holding payment processing up: advisory locks (all of them,
+/// or with `?external_id=` the one that payment's processing takes) and
+/// transactions open past `min_age_secs` with locks on payment tables.
+pub async fn report(pool: &PgPool, filters: &LockFilters) -> Result<LockReport, PipelineError> {
+    let min_age = lock::min_age_secs(filters.min_age_secs)?;
+    let lock_key = filters.lock_key();
+    let key = match &lock_key {
+        Some(k) => Some(lock_repo::hash_key(pool, k).await?),
+        None => None,
+    };
+    Ok(LockReport {
+        key,
+        advisory_locks: lock_repo::advisory_locks(pool, lock_key.as_deref()).await?,
+        long_transactions: lock_repo::long_transactions(pool, min_age as i64).await?,
+    })
+}
+
+/// Terminate backend `pid`, which rolls back its transaction and frees its
+/// locks. Refused unless it is in a transaction open at least the request's
+/// `min_age_secs` holding or awaiting an advisory lock or a payment table
+/// lock; never the connection asking. Audited.
+pub async fn terminate(
+    pool: &PgPool,
+    pid: i32,
+    request: &TerminateRequest,
+    actor: &str,
+) -> Result<TerminateOutcome, PipelineError> {
+    let min_age = request.min_age_secs()?;
+    let mut tx = pool.begin().await?;
+    let Some(backend) = lock_repo::terminate(&mut tx, pid, min_age as i64).await? else {
+        tx.rollback().await?;
+        return Ok(match lock_repo::backend(pool, pid).await? {
+            Some(backend) => TerminateOutcome::Ineligible(backend),
+            None => TerminateOutcome::NotFound,
+        });
+    };
+    let reason = request.reason.trim();
+    insert_audit_entry(&mut tx, &backend.termination_audit_entry(reason, actor)).await?;
+    tx.commit().await?;
+
+    tracing::warn!(
+        pid,
+        application_name = %backend.application_name,
+        xact_start = ?backend.xact_start,
+        actor,
+        reason,
+        "database backend terminated"
+    );
+    Ok(TerminateOutcome::Terminated(backend))
+}
@@ -0,0 +1,64 @@
+use {
+    crate::{
+        domain::maintenance::{MaintenanceRequest, MaintenanceStatus, MaintenanceWindow},
+        error::PipelineError,
+        infra::settings::{SettingKey, Settings},
+    },
+    chrono::{TimeDelta, Utc},
+};
+
+/// Maintenance mode, while it lasts. Unset, or run out, when ingestion
+/// isn't paused.
+pub const MAINTENANCE: SettingKey<MaintenanceWindow> = SettingKey::new("ingestion.maintenance");
+
+/// Whether ingestion is paused right now. Cached like any setting: a
+/// switch flipped on one replica holds on the others once they hear of it.
+pub async fn active(settings: &Settings) -> Result<Option<MaintenanceWindow>, PipelineError> {
+    let window = settings.get(&MAINTENANCE).await?;
+    Ok(window.filter(|w| w.is_live(Utc::now())))
+}
+
+pub async fn status(settings: &Settings) -> Result<MaintenanceStatus, PipelineError> {
+    Ok(active(settings).await?.into())
+}
+
+/// Pause ingestion: webhooks are refused with 503, for the provider to
+/// deliver again later, and workers stop claiming jobs. Ends on its own
+/// after the requested duration unless disabled first. Audited.
+pub async fn enable(
+    settings: &Settings,
+    request: &MaintenanceRequest,
+    actor: &str,
+) -> Result<MaintenanceWindow, PipelineError> {
+    let duration_secs = request.duration_secs()?;
+    let enabled_at = Utc::now();
+    let window = MaintenanceWindow {
+        reason: request.reason.trim().to_string(),
+        enabled_by: actor.to_string(),
+        enabled_at,
+        expires_at: enabled_at + TimeDelta::seconds(duration_secs as i64),
+    };
+    settings.set(&MAINTENANCE, &window, actor).await?;
+    tracing::warn!(
+        reason = %window.reason,
+        expires_at = %window.expires_at,
+        actor,
+        "maintenance mode enabled, ingestion paused"
+    );
+    Ok(window)
+}
+
+/// Resume ingestion. Returns the window ended, audited; `None`, and a
+/// no-op, if none was live.
+pub async fn disable(
+    settings: &Settings,
+    actor: &str,
+) -> Result<Option<MaintenanceWindow>, PipelineError> {
+    let lock = settings.lock(&MAINTENANCE).await?;
+    let Some(window) = lock.get()?.filter(|w| w.is_live(Utc::now())) else {
+        return Ok(None);
+    };
+    lock.delete(actor).await?;
+    tracing::info!(actor, "maintenance mode disabled, ingestion resumed");
+    Ok(Some(window))
+}
@@ -0,0 +1,125 @@
+use {
+    crate::{error::PipelineError, infra::postgres::payment_repo},
+    sqlx::{
+        Connection, PgConnection, PgPool,
+        migrate::{Migrate, MigrateError, Migrator},
+    },
+    std::collections::HashMap,
+};
+
+/// The schema migrations, embedded at build time.
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+/// Held for the whole run. Separate from the lock sqlx takes inside
+/// `Migrator::run`, so an instance can tell it had to wait.
+const LOCK_KEY: &str = "fin_sync_migrations";
+
+/// Outcome of one migration run.
+#[derive(Debug, Default)]
+pub struct MigrationReport {
+    /// Versions this instance applied; empty if another had already done so.
+    pub applied: Vec<i64>,
+    /// Another instance held the lock when this one arrived.
+    pub waited: bool,
+}
+
+/// Apply pending migrations once across every replica. Each caller takes a
+/// session advisory lock on a dedicated connection; whoever gets it first
+/// migrates, and the rest wait for it, then find nothing pending and only
+/// verify that every migration this binary knows is applied unchanged.
+pub async fn run(pool: &PgPool) -> Result<MigrationReport, PipelineError> {
+    // Detached, so the lock goes with the session when it closes — even if
+    // a migration fails half-way.
+    let mut conn = pool.acquire().await?.detach();
+    let locked = sqlx::query_scalar!(
+        r#"SELECT pg_try_advisory_lock(hashtextextended($1, 0)) AS "locked!""#,
+        LOCK_KEY
+    )
+    .fetch_one(&mut conn)
+    .await?;
+    if !locked {
+        tracing::info!("another instance is migrating, waiting for it to finish");
+        sqlx::query!("SELECT pg_advisory_lock(hashtextextended($1, 0))", LOCK_KEY)
+            .execute(&mut conn)
+            .await?;
+    }
+
+    let applied = apply_pending(&mut conn).await;
+    conn.close().await?;
+    Ok(MigrationReport {
+        applied: applied?,
+        waited: !locked,
+    })
+}
+
+/// Compress provider event payloads still stored as plain JSON (rows from
+/// before compression), `batch` at a time. Safe to rerun or to run while
+/// serving. Returns how many were rewritten.
+pub async fn compress_stored_payloads(pool: &PgPool, batch: i64) -> Result<u64, PipelineError> {
+    let mut total = 0;
+    let mut after = String::new();
+    loop {
+        let (compressed, last) = payment_repo::compress_raw_payloads(pool, &after, batch).await?;
+        total += compressed;
+        match last {
+            Some(last) => after = last,
+            None => return Ok(total),
+        }
+    }
+}
+
+async fn apply_pending(conn: &mut PgConnection) -> Result<Vec<i64>, PipelineError> {
+    conn.ensure_migrations_table()
+        .await
+        .map_err(sqlx::Error::from)?;
+    let done = applied_checksums(conn).await?;
+    let pending: Vec<i64> = MIGRATOR
+        .iter()
+        .filter(|m| m.migration_type.is_up_migration() && !done.contains_key(&m.version))
+        .map(|m| m.version)
+        .collect();
+    if !pending.is_empty() {
+        MIGRATOR.run(&mut *conn).await.map_err(sqlx::Error::from)?;
+    }
+    verify(conn).await?;
+    Ok(pending)
+}
+
+/// Every migration in this binary must be applied, with the same checksum.
+async fn verify(conn: &mut PgConnection) -> Result<(), PipelineError> {
+    if let Some(version) = conn.dirty_version().await.map_err(sqlx::Error::from)? {
+        return Err(sqlx::Error::from(MigrateError::Dirty(version)).into());
+    }
+    let done = applied_checksums(conn).await?;
+    for m in MIGRATOR
+        .iter()
+        .filter(|m| m.migration_type.is_up_migration())
+    {
+        match done.get(&m.version) {
+            None => {
+                return Err(PipelineError::Validation(format!(
+                    "migration {} is not applied",
+                    m.version
+                )));
+            }
+            Some(checksum) if *checksum != m.checksum.as_ref() => {
+                return Err(sqlx::Error::from(MigrateError::VersionMismatch(m.version)).into());
+            }
+            Some(_) => {}
+        }
+    }
+    Ok(())
+}
+
+async fn applied_checksums(
+    conn: &mut PgConnection,
+) -> Result<HashMap<i64, Vec<u8>>, PipelineError> {
+    let applied = conn
+        .list_applied_migrations()
+        .await
+        .map_err(sqlx::Error::from)?;
+    Ok(applied
+        .into_iter()
+        .map(|m| (m.version, m.checksum.into_owned()))
+        .collect())
+}
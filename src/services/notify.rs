@@ -0,0 +1,188 @@
+use {
+    crate::{
+        domain::{
+            alert::{Alert, AlertSink, PaymentAnomaly},
+            budget::{ApiOperation, BudgetAlert, BudgetLevel, BudgetPeriod},
+            hook::{HookFuture, TransitionHook, TransitionNotice},
+            payment::PaymentStatus,
+            reconciliation::ReconciliationSummary,
+        },
+        infra::alert::LogSink,
+    },
+    chrono::{TimeDelta, Utc},
+    serde::Serialize,
+    std::sync::Arc,
+    uuid::Uuid,
+};
+
+/// Fans alerts out to every configured sink. Cheap to clone.
+#[derive(Clone)]
+pub struct Notifier {
+    sinks: Vec<Arc<dyn AlertSink>>,
+    base_url: Arc<str>,
+}
+
+/// What happened to an alert at one sink.
+#[derive(Debug, Serialize)]
+pub struct SinkDelivery {
+    pub sink: String,
+    /// `delivered`, `failed`, or `not_routed` when the sink doesn't take
+    /// this kind of alert.
+    pub status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Notifier {
+    /// Logs only. `base_url` is where this service is reachable, for links
+    /// in alert bodies.
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            sinks: vec![Arc::new(LogSink)],
+            base_url: base_url.trim_end_matches('/').into(),
+        }
+    }
+
+    pub fn with_sink(mut self, sink: Arc<dyn AlertSink>) -> Self {
+        self.sinks.push(sink);
+        self
+    }
+
+    /// Whether anything besides the log is configured.
+    pub fn has_external_sinks(&self) -> bool {
+        self.sinks.len() > 1
+    }
+
+    /// Absolute URL for an API path such as `/admin/reconciliations/{id}`.
+    pub fn link(&self, path: &str) -> String {
+        format!("{}{path}", self.base_url)
+    }
+
+    /// Deliver to every sink. A failing sink is logged and doesn't stop the
+    /// others; alerts are best effort and never fail the caller.
+    pub async fn notify(&self, alert: &Alert) {
+        self.deliver(alert, None).await;
+    }
+
+    /// Deliver to the sinks that take `alert`'s kind (only the one named
+    /// `only`, if given) and report the outcome at each sink.
+    pub async fn deliver(&self, alert: &Alert, only: Option<&str>) -> Vec<SinkDelivery> {
+        let mut deliveries = Vec::new();
+        for sink in &self.sinks {
+            if only.is_some_and(|name| name != sink.name()) {
+                continue;
+            }
+            let (status, error) = if !sink.accepts(alert.kind) {
+                ("not_routed", None)
+            } else if let Err(e) = sink.send(alert).await {
+                tracing::error!(sink = sink.name(), kind = alert.kind, error = %e, "alert delivery failed");
+                ("failed", Some(e.to_string()))
+            } else {
+                ("delivered", None)
+            };
+            deliveries.push(SinkDelivery {
+                sink: sink.name().to_string(),
+                status,
+                error,
+            });
+        }
+        deliveries
+    }
+
+    /// A made-up alert of `kind`, shaped like a real one, for checking
+    /// routing and templates. `None` for unknown kinds.
+    pub fn sample_alert(&self, kind: &str) -> Option<Alert> {
+        let mut alert = match kind {
+            "payment_anomaly" => PaymentAnomaly {
+                external_id: "pi_test".into(),
+                source: "stripe".into(),
+                amount: 4200,
+                currency: "usd".into(),
+                event_id: "evt_test".into(),
+                actor: "test".into(),
+                current_status: PaymentStatus::Succeeded,
+                incoming_status: PaymentStatus::Pending,
+                audit_url: self.link("/payments/pi_test/audit"),
+            }
+            .to_alert(),
+            "provider_budget" => BudgetAlert {
+                source: "stripe".into(),
+                operation: ApiOperation::Fetch,
+                period: BudgetPeriod::Hour,
+                period_start: BudgetPeriod::Hour.start(Utc::now()),
+                level: BudgetLevel::Soft,
+                calls: 8000,
+                limit: 8000,
+                hard_limit: Some(10000),
+                usage_url: self.link("/admin/provider-budgets"),
+            }
+            .to_alert(),
+            "reconciliation_summary" => {
+                let run_id = Uuid::nil();
+                ReconciliationSummary {
+                    run_id,
+                    source: "stripe".into(),
+                    window_start: Utc::now() - TimeDelta::hours(1),
+                    window_end: Utc::now(),
+                    status: "completed".into(),
+                    error: None,
+                    checked: 100,
+                    matched: 100,
+                    matched_pct: 100.0,
+                    unmatched: Vec::new(),
+                    deltas: Default::default(),
+                    report_url: self.link(&format!("/admin/reconciliations/{run_id}")),
+                }
+                .to_alert()
+            }
+            _ => return None,
+        };
+        alert.subject = format!("[test] {}", alert.subject);
+        alert.detail["test"] = true.into();
+        Some(alert)
+    }
+}
+
+/// Sends a `payment_anomaly` alert for every transition the state machine
+/// refused, once it is committed.
+pub struct AnomalyAlerts {
+    notifier: Notifier,
+}
+
+impl AnomalyAlerts {
+    pub fn new(notifier: Notifier) -> Self {
+        Self { notifier }
+    }
+}
+
+impl TransitionHook for AnomalyAlerts {
+    fn name(&self) -> &'static str {
+        "anomaly_alerts"
+    }
+
+    fn on_anomaly<'a>(
+        &'a self,
+        notice: &'a TransitionNotice,
+        current: PaymentStatus,
+        incoming: PaymentStatus,
+    ) -> HookFuture<'a> {
+        Box::pin(async move {
+            let payment = &notice.payment;
+            let anomaly = PaymentAnomaly {
+                external_id: payment.id.as_str().to_string(),
+                source: payment.source.clone(),
+                amount: payment.amount,
+                currency: payment.currency.as_str().to_string(),
+                event_id: notice.event_id.clone(),
+                actor: notice.actor.clone(),
+                current_status: current,
+                incoming_status: incoming,
+                audit_url: self
+                    .notifier
+                    .link(&format!("/payments/{}/audit", payment.id.as_str())),
+            };
+            self.notifier.notify(&anomaly.to_alert()).await;
+            Ok(())
+        })
+    }
+}
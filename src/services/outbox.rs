@@ -0,0 +1,101 @@
+use {
+    crate::{
+        domain::outbox::{ConsumerOffset, OutboxPage, OutboxQuery, validate_consumer},
+        error::PipelineError,
+        infra::postgres::outbox_repo,
+    },
+    sqlx::PgPool,
+    std::time::Duration,
+    tokio::{sync::Semaphore, time::Instant},
+};
+
+/// Events numbered per pass; a backlog bigger than this is numbered over
+/// several reads.
+const SEQUENCE_BATCH: i64 = 1000;
+
+/// Each waiting long poll holds a database connection of its own, so only
+/// this many wait at once.
+const MAX_WAITERS: usize = 16;
+static WAITERS: Semaphore = Semaphore::const_new(MAX_WAITERS);
+
+/// Longest a poll may wait, whatever it asks for.
+pub const MAX_WAIT: Duration = Duration::from_secs(25);
+
+/// Events after the requested offset. When there are none yet and the
+/// query asks to `wait`, the answer is held until one is committed or the
+/// wait (capped at `max_wait`) runs out. `None` if `MAX_WAITERS` polls are
+/// already waiting.
+pub async fn poll_events(
+    pool: &PgPool,
+    query: OutboxQuery,
+    max_wait: Duration,
+) -> Result<Option<OutboxPage>, PipelineError> {
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+    let after_seq = match (query.after_seq, &query.consumer) {
+        (Some(seq), _) if seq < 0 => {
+            return Err(PipelineError::Validation(
+                "after_seq must not be negative".into(),
+            ));
+        }
+        (Some(seq), _) => seq,
+        (None, Some(consumer)) => {
+            validate_consumer(consumer)?;
+            outbox_repo::get_offset(pool, consumer).await?.unwrap_or(0)
+        }
+        (None, None) => 0,
+    };
+    let wait = Duration::from_secs(query.wait.unwrap_or(0)).min(max_wait);
+
+    let page = read(pool, after_seq, limit).await?;
+    if !page.events.is_empty() || wait.is_zero() {
+        return Ok(Some(page));
+    }
+
+    let Ok(_permit) = WAITERS.try_acquire() else {
+        return Ok(None);
+    };
+    let mut listener = outbox_repo::listen(pool).await?;
+    let deadline = Instant::now() + wait;
+    loop {
+        // Read again once listening: an event committed before the
+        // listener was up sent its notification to no one.
+        let page = read(pool, after_seq, limit).await?;
+        if !page.events.is_empty() {
+            return Ok(Some(page));
+        }
+        match tokio::time::timeout_at(deadline, listener.recv()).await {
+            Ok(notification) => {
+                notification?;
+            }
+            Err(_) => return Ok(Some(page)),
+        }
+    }
+}
+
+async fn read(pool: &PgPool, after_seq: i64, limit: i64) -> Result<OutboxPage, PipelineError> {
+    outbox_repo::sequence_pending(pool, SEQUENCE_BATCH).await?;
+    let events = outbox_repo::list_events(pool, after_seq, limit).await?;
+    let last_seq = events.last().map_or(after_seq, |e| e.seq);
+    Ok(OutboxPage { events, last_seq })
+}
+
+/// Record how far `consumer` has got. The offset can't be past the last
+/// numbered event.
+pub async fn commit_offset(
+    pool: &PgPool,
+    consumer: &str,
+    last_seq: i64,
+) -> Result<ConsumerOffset, PipelineError> {
+    validate_consumer(consumer)?;
+    let head = outbox_repo::head_seq(pool).await?;
+    if !(0..=head).contains(&last_seq) {
+        return Err(PipelineError::Validation(format!(
+            "last_seq must be between 0 and {head}"
+        )));
+    }
+    outbox_repo::commit_offset(pool, consumer, last_seq).await
+}
+
+pub async fn list_consumers(pool: &PgPool) -> Result<Vec<ConsumerOffset>, PipelineError> {
+    outbox_repo::list_offsets(pool).await
+}
@@ -1,2 +1,6 @@
+pub mod adjust;
+pub mod checkout;
 pub mod lookup;
+pub mod manual;
 pub mod pipeline;
+pub mod refund;
@@ -0,0 +1,68 @@
+use {
+    crate::{
+        domain::{
+            adjustment::{TransitionOutcome, TransitionRequest},
+            id::ExternalId,
+            payment::{PaymentView, ProcessResult},
+        },
+        error::PipelineError,
+        infra::postgres::payment_repo,
+        services::payment::pipeline::adjust_payment_event,
+    },
+    sqlx::PgPool,
+};
+
+/// Move a payment to a status an operator confirmed out of band. Runs
+/// through the pipeline as a synthetic `admin.transition` event, so the
+/// state machine and audit path apply as for provider events; `force`
+/// overrides a refusal. `None` if the payment doesn't exist.
+pub async fn transition_payment(
+    pool: &PgPool,
+    id: ExternalId,
+    request: &TransitionRequest,
+    actor: &str,
+) -> Result<Option<TransitionOutcome>, PipelineError> {
+    let Some(current) = payment_repo::get_payment_by_id(pool, id.clone()).await? else {
+        return Ok(None);
+    };
+    let metadata = payment_repo::get_payment_metadata(pool, id.as_str())
+        .await?
+        .unwrap_or_default();
+    let payment =
+        request.to_new_payment(&current, metadata, actor, chrono::Utc::now().timestamp())?;
+
+    let result =
+        adjust_payment_event(pool, &payment, actor, &request.reason, request.force).await?;
+    tracing::info!(
+        external_id = %id,
+        %actor,
+        status = %request.status,
+        force = request.force,
+        result = result.as_str(),
+        "manual transition"
+    );
+    let outcome = match result {
+        ProcessResult::Updated(_) => TransitionOutcome::Applied(load_view(pool, id).await?),
+        ProcessResult::Unchanged(_) | ProcessResult::StaleIgnored(_) => {
+            TransitionOutcome::Unchanged(load_view(pool, id).await?)
+        }
+        ProcessResult::Anomaly(_) => TransitionOutcome::Rejected {
+            current: load_view(pool, id).await?.status,
+        },
+        ProcessResult::Created(_)
+        | ProcessResult::Duplicate
+        | ProcessResult::Diverted(_)
+        | ProcessResult::Logged => {
+            return Err(PipelineError::Validation(
+                "manual transition was not applied".into(),
+            ));
+        }
+    };
+    Ok(Some(outcome))
+}
+
+async fn load_view(pool: &PgPool, id: ExternalId) -> Result<PaymentView, PipelineError> {
+    payment_repo::get_payment_by_id(pool, id.clone())
+        .await?
+        .ok_or_else(|| PipelineError::Validation(format!("payment not found: {id}")))
+}
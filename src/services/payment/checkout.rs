@@ -0,0 +1,109 @@
+use {
+    crate::{
+        domain::{
+            checkout::{AttemptSignature, Checkout, NewCheckoutAttempt, is_failed},
+            payment::NewPayment,
+        },
+        error::PipelineError,
+        infra::postgres::checkout_repo,
+    },
+    sqlx::PgPool,
+    uuid::Uuid,
+};
+
+/// Pipeline hook, run in the pipeline's transaction when an inbound
+/// payment is first recorded: place it in the checkout it's an attempt at.
+/// Takes the reference's or customer's lock, so two attempts arriving
+/// together get numbered one after the other.
+pub(crate) async fn record_attempt(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    payment: &NewPayment,
+) -> Result<(), PipelineError> {
+    let Some(signature) = AttemptSignature::of(payment) else {
+        return Ok(());
+    };
+    if let Some(key) = signature.lock_key() {
+        checkout_repo::lock(tx, &key).await?;
+    }
+    let prior = match (&signature.reference, &signature.customer) {
+        (Some(reference), _) => {
+            checkout_repo::latest_by_reference(tx, &signature, reference).await?
+        }
+        (None, Some(customer)) => {
+            let money = payment.money();
+            checkout_repo::latest_retried(
+                tx,
+                &signature,
+                customer,
+                money.amount().cents(),
+                *money.currency(),
+            )
+            .await?
+        }
+        (None, None) => None,
+    };
+    let attempt = NewCheckoutAttempt::new(payment, signature, prior);
+    checkout_repo::insert(tx, &attempt).await?;
+    relink_retries(tx, payment.id(), payment).await
+}
+
+/// Pipeline hook, run when payment `id` is recorded or updated as failed:
+/// events arrive out of order, so the customer's retry may already have
+/// been recorded, before this attempt had failed, as a checkout of its own.
+/// That checkout is folded into this attempt's.
+pub(crate) async fn relink_retries(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    id: Uuid,
+    payment: &NewPayment,
+) -> Result<(), PipelineError> {
+    if !is_failed(payment.status(), payment.failure()) {
+        return Ok(());
+    }
+    let Some(signature) = AttemptSignature::of(payment) else {
+        return Ok(());
+    };
+    let (None, Some(customer), Some(key)) = (
+        &signature.reference,
+        &signature.customer,
+        signature.lock_key(),
+    ) else {
+        return Ok(());
+    };
+    checkout_repo::lock(tx, &key).await?;
+    let Some(failed) = checkout_repo::failed_attempt(tx, id, customer).await? else {
+        return Ok(());
+    };
+    let money = payment.money();
+    let retry = checkout_repo::retried_after(
+        tx,
+        &signature,
+        customer,
+        money.amount().cents(),
+        *money.currency(),
+        &failed,
+    )
+    .await?;
+    if let Some(retry) = retry {
+        checkout_repo::fold_into(tx, &signature, &retry, &failed).await?;
+    }
+    Ok(())
+}
+
+/// The checkout named `key` — the merchant's reference, or the first
+/// attempt's id — or the one the payment `key` is an attempt at. `None`
+/// if neither is known to `tenant_id`.
+pub async fn get_checkout(
+    pool: &PgPool,
+    tenant_id: Option<&str>,
+    key: &str,
+) -> Result<Option<Checkout>, PipelineError> {
+    let attempts = checkout_repo::list_attempts(pool, tenant_id, key).await?;
+    if !attempts.is_empty() {
+        return Ok(Checkout::new(key.to_string(), attempts));
+    }
+    let Some(key) = checkout_repo::key_of(pool, tenant_id, key).await? else {
+        return Ok(None);
+    };
+    let attempts = checkout_repo::list_attempts(pool, tenant_id, &key).await?;
+    Ok(Checkout::new(key, attempts))
+}
@@ -1,12 +1,20 @@
-use sqlx::PgPool;
+use {sqlx::PgPool, std::collections::HashMap};
 
 use crate::{
     domain::{
-        error::PipelineError,
-        id::ExternalId,
-        payment::{PaymentFilters, PaymentView},
+        audit::{
+            AuditCursor, AuditEntryView, AuditFilters, AuditLogFilters, AuditPage, AuditRecord,
+        },
+        id::{ExternalId, TenantId},
+        payment::{
+            PaymentCursor, PaymentDetail, PaymentDirection, PaymentFilters, PaymentPageView,
+            PaymentView,
+        },
+        timeline::{self, TimelineEntry},
     },
-    infra::postgres::payment_repo,
+    error::PipelineError,
+    infra::postgres::{audit_repo, payment_repo, tag_repo},
+    services::{capture::get_capture_summary, payment::refund::get_refundable_balance},
 };
 
 pub async fn get_payment_by_id(
@@ -16,14 +24,189 @@ pub async fn get_payment_by_id(
     payment_repo::get_payment_by_id(pool, id).await
 }
 
+/// `get_payment_by_id` for many payments in one query; those that exist.
+pub async fn get_payments_by_ids(
+    pool: &PgPool,
+    ids: &[String],
+) -> Result<Vec<PaymentView>, PipelineError> {
+    payment_repo::get_payments_by_ids(pool, ids).await
+}
+
+/// The refunds and disputes against each of `parents`, newest first, at
+/// most `limit` (capped at 100) each, in one query. Parents without any
+/// are left out.
+pub async fn get_children_of(
+    pool: &PgPool,
+    parents: &[String],
+    limit: u64,
+) -> Result<HashMap<String, Vec<PaymentView>>, PipelineError> {
+    let children = payment_repo::get_children_of(pool, parents, limit.min(100) as i64).await?;
+    let mut by_parent: HashMap<String, Vec<PaymentView>> = HashMap::new();
+    for child in children {
+        if let Some(parent) = &child.parent_external_id {
+            by_parent
+                .entry(parent.as_str().to_string())
+                .or_default()
+                .push(child);
+        }
+    }
+    Ok(by_parent)
+}
+
+/// Whether a caller limited to `tenant` may see payment `id`. Payments of
+/// other tenants, and ones with none, are hidden from it as if missing.
+pub async fn payment_visible(
+    pool: &PgPool,
+    id: &ExternalId,
+    tenant: Option<&TenantId>,
+) -> Result<bool, PipelineError> {
+    match tenant {
+        None => Ok(true),
+        Some(tenant) => payment_repo::payment_in_tenant(pool, id.as_str(), tenant.as_str()).await,
+    }
+}
+
+/// The payment plus its tags, a summary of its audit trail and, for
+/// inbound payments, its refund and capture totals.
+pub async fn get_payment_detail(
+    pool: &PgPool,
+    id: ExternalId,
+) -> Result<Option<PaymentDetail>, PipelineError> {
+    let Some(payment) = payment_repo::get_payment_by_id(pool, id).await? else {
+        return Ok(None);
+    };
+    let audit = audit_repo::get_audit_summary(pool, payment.id.as_str()).await?;
+    let tags = tag_repo::get_payment_tags(pool, payment.id.as_str()).await?;
+    let (refunds, captures) = match payment.direction {
+        PaymentDirection::Inbound => (
+            get_refundable_balance(pool, payment.id.clone()).await?,
+            get_capture_summary(pool, payment.id.as_str()).await?,
+        ),
+        PaymentDirection::Outbound => (None, None),
+    };
+    Ok(Some(PaymentDetail {
+        payment,
+        audit,
+        refunds,
+        captures,
+        tags,
+    }))
+}
+
+/// The payment's audit trail, oldest first. `None` if the payment doesn't exist.
+pub async fn get_payment_audit(
+    pool: &PgPool,
+    id: ExternalId,
+    mut filters: AuditFilters,
+) -> Result<Option<Vec<AuditEntryView>>, PipelineError> {
+    if !payment_repo::payment_exists(pool, id.as_str()).await? {
+        return Ok(None);
+    }
+    filters.limit = Some(filters.limit.unwrap_or(50).min(200));
+    let entries = audit_repo::get_audit_entries(pool, id.as_str(), filters).await?;
+    Ok(Some(entries))
+}
+
+/// Most entries of each kind a timeline holds.
+const TIMELINE_LIMIT: i64 = 500;
+
+/// What the provider sent about the payment and what we did about it, in
+/// one list, oldest first. `None` if the payment doesn't exist.
+pub async fn get_payment_timeline(
+    pool: &PgPool,
+    id: ExternalId,
+) -> Result<Option<Vec<TimelineEntry>>, PipelineError> {
+    if !payment_repo::payment_exists(pool, id.as_str()).await? {
+        return Ok(None);
+    }
+    let events = payment_repo::get_payment_events(pool, id.as_str(), TIMELINE_LIMIT).await?;
+    let audit = audit_repo::get_audit_timeline(pool, id.as_str(), TIMELINE_LIMIT).await?;
+    Ok(Some(timeline::merge(events, audit)))
+}
+
+/// One page of payments, newest first. Pass `next_cursor` back as `cursor`
+/// for the following page; `offset` still works but can't be combined with it.
 pub async fn get_payment_list(
     pool: &PgPool,
     mut filters: PaymentFilters,
-) -> Result<Vec<PaymentView>, PipelineError> {
-    filters.limit = Some(filters.limit.unwrap_or(20).min(100));
+) -> Result<PaymentPageView, PipelineError> {
+    if filters.cursor.is_some() && filters.offset.is_some() {
+        return Err(PipelineError::Validation(
+            "cursor and offset cannot be combined".into(),
+        ));
+    }
+    let limit = filters.limit.unwrap_or(20).min(100);
+    // One extra row tells us whether another page exists.
+    filters.limit = Some(limit + 1);
     if let Some(exact) = filters.amount {
         filters.amount_min = Some(exact);
         filters.amount_max = Some(exact);
     }
-    payment_repo::get_list_payments(pool, filters).await
+    filters.normalize_reference()?;
+    filters.normalize_tags()?;
+
+    let mut payments = payment_repo::get_list_payments(pool, filters).await?;
+    let next_cursor = if payments.len() as u64 > limit {
+        payments.truncate(limit as usize);
+        payments.last().map(PaymentCursor::after)
+    } else {
+        None
+    };
+    Ok(PaymentPageView {
+        payments,
+        next_cursor,
+    })
+}
+
+/// One page of the whole audit log, newest first; payments' and everything
+/// else's. Pass `next_cursor` back as `cursor` for the following page.
+pub async fn get_audit_log(
+    pool: &PgPool,
+    mut filters: AuditLogFilters,
+) -> Result<AuditPage, PipelineError> {
+    let limit = filters.limit.unwrap_or(20).min(100);
+    // One extra row tells us whether another page exists.
+    filters.limit = Some(limit + 1);
+    let entries = audit_repo::list_audit_records(pool, &filters).await?;
+    Ok(audit_page(entries, limit))
+}
+
+/// `get_audit_log` for each of `external_ids`, in one query: a page of
+/// each one's entries under the rest of `filters`. Ids without any entry
+/// get an empty page.
+pub async fn get_audit_pages(
+    pool: &PgPool,
+    external_ids: &[String],
+    mut filters: AuditLogFilters,
+) -> Result<HashMap<String, AuditPage>, PipelineError> {
+    let limit = filters.limit.unwrap_or(20).min(100);
+    filters.limit = Some(limit + 1);
+    let records = audit_repo::list_audit_records_of(pool, external_ids, &filters).await?;
+    let mut by_id: HashMap<String, Vec<_>> = external_ids
+        .iter()
+        .map(|id| (id.clone(), Vec::new()))
+        .collect();
+    for record in records {
+        if let Some(entries) = record.external_id.as_ref().and_then(|id| by_id.get_mut(id)) {
+            entries.push(record);
+        }
+    }
+    Ok(by_id
+        .into_iter()
+        .map(|(id, entries)| (id, audit_page(entries, limit)))
+        .collect())
+}
+
+/// `entries`, read one past `limit`, as a page.
+fn audit_page(mut entries: Vec<AuditRecord>, limit: u64) -> AuditPage {
+    let next_cursor = if entries.len() as u64 > limit {
+        entries.truncate(limit as usize);
+        entries.last().map(AuditCursor::after)
+    } else {
+        None
+    };
+    AuditPage {
+        entries,
+        next_cursor,
+    }
 }
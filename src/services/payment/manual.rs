@@ -0,0 +1,103 @@
+use {
+    crate::{
+        domain::{
+            manual::{IdempotencyKey, ManualOutcome, ManualPaymentRequest},
+            payment::{NewPayment, PaymentStatus, PaymentView, ProcessResult},
+        },
+        error::PipelineError,
+        infra::postgres::{manual_repo, payment_repo},
+        services::payment::pipeline::process_payment_event,
+    },
+    sqlx::PgPool,
+};
+
+/// Record a payment reported by an internal system. Goes through the same
+/// dedup, state machine and audit path as provider events; the idempotency
+/// key stands in for the provider's event id. What the request came to is
+/// kept by key, so a retry of it is answered the same way.
+pub async fn submit_manual_payment(
+    pool: &PgPool,
+    request: &ManualPaymentRequest,
+    key: &IdempotencyKey,
+    actor: &str,
+) -> Result<ManualOutcome, PipelineError> {
+    let payment = request.to_new_payment(key, chrono::Utc::now().timestamp())?;
+    let request_hash = request.hash();
+
+    let outcome = match process_payment_event(pool, &payment, actor).await? {
+        ProcessResult::Created(_) => ManualOutcome::Created(load_view(pool, request).await?),
+        ProcessResult::Updated(_)
+        | ProcessResult::Unchanged(_)
+        | ProcessResult::StaleIgnored(_) => ManualOutcome::Applied(load_view(pool, request).await?),
+        ProcessResult::Duplicate => return replay(pool, request, &payment, &request_hash).await,
+        ProcessResult::Anomaly(_) | ProcessResult::Diverted(_) => ManualOutcome::Rejected {
+            current: load_view(pool, request).await?.status,
+        },
+        ProcessResult::Logged => {
+            return Err(PipelineError::Validation(
+                "manual payment was not applied".into(),
+            ));
+        }
+    };
+    if let Some(kind) = outcome.as_str() {
+        let rejected_from = match &outcome {
+            ManualOutcome::Rejected { current } => Some(current.as_str()),
+            _ => None,
+        };
+        manual_repo::record(
+            pool,
+            payment.last_event_id(),
+            &request_hash,
+            kind,
+            rejected_from,
+        )
+        .await?;
+    }
+    Ok(outcome)
+}
+
+/// Answer a request whose key was already used: with what it came to the
+/// first time if it is the same request, `KeyReused` if not. A request
+/// with no recorded outcome (it raced the first attempt's bookkeeping) is
+/// checked against the stored event and replayed as applied.
+async fn replay(
+    pool: &PgPool,
+    request: &ManualPaymentRequest,
+    payment: &NewPayment,
+    request_hash: &str,
+) -> Result<ManualOutcome, PipelineError> {
+    let original = match manual_repo::find(pool, payment.last_event_id()).await? {
+        Some(stored) if stored.request_hash != request_hash => return Ok(ManualOutcome::KeyReused),
+        Some(stored) => match (stored.outcome.as_str(), stored.rejected_from) {
+            ("created", _) => ManualOutcome::Created(load_view(pool, request).await?),
+            ("rejected", Some(current)) => ManualOutcome::Rejected {
+                current: PaymentStatus::try_from(current.as_str())?,
+            },
+            _ => ManualOutcome::Applied(load_view(pool, request).await?),
+        },
+        None => {
+            let stored = payment_repo::get_provider_event_payload(
+                pool,
+                payment.source(),
+                payment.last_event_id(),
+            )
+            .await?;
+            if stored.as_ref() != Some(payment.raw_event()) {
+                return Ok(ManualOutcome::KeyReused);
+            }
+            ManualOutcome::Applied(load_view(pool, request).await?)
+        }
+    };
+    Ok(ManualOutcome::Replayed(Box::new(original)))
+}
+
+async fn load_view(
+    pool: &PgPool,
+    request: &ManualPaymentRequest,
+) -> Result<PaymentView, PipelineError> {
+    payment_repo::get_payment_by_id(pool, request.external_id.clone())
+        .await?
+        .ok_or_else(|| {
+            PipelineError::Validation(format!("payment not found: {}", request.external_id))
+        })
+}
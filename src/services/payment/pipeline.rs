@@ -1,13 +1,28 @@
 use {
     crate::domain::audit::NewAuditEntry,
-    crate::domain::error::PipelineError,
+    crate::domain::feed::Merge,
+    crate::domain::followup::FollowUp,
+    crate::domain::id::TenantId,
     crate::domain::payment::{
-        NewPayment, NewPaymentParams, PassthroughEvent, PaymentAction, PaymentTrigger,
-        ProcessResult,
+        ExistingPayment, NewPayment, NewPaymentParams, NewProviderEvent, PassthroughEvent,
+        PaymentAction, PaymentStatus, PaymentTrigger, ProcessResult, lock_key,
     },
-    crate::domain::provider::PaymentProvider,
-    crate::infra::postgres::audit_repo::insert_audit_entry,
-    crate::infra::postgres::payment_repo,
+    crate::domain::period::in_closed_period,
+    crate::domain::provider::{FetchedPayment, PaymentProvider},
+    crate::domain::replay::{Applied, SIMULATED_EVENT_PREFIX, Simulation},
+    crate::error::PipelineError,
+    crate::infra::postgres::audit_repo::{self, insert_audit_entry},
+    crate::infra::postgres::{
+        feed_repo, followup_repo, ledger_repo, payment_repo, period_repo, pressure,
+    },
+    crate::services::{
+        ledger,
+        payment::{
+            checkout::{record_attempt, relink_retries},
+            refund::{flag_over_refund, flag_unlinked_refund, guard_initiated_refund, link_refund},
+        },
+    },
+    chrono::{DateTime, Utc},
     sqlx::PgPool,
     uuid::Uuid,
 };
@@ -19,61 +34,333 @@ pub async fn process_payment_event(
     payment: &NewPayment,
     actor: &str,
 ) -> Result<ProcessResult, PipelineError> {
-    let mut tx = pool.begin().await?;
-
-    sqlx::query!("SET LOCAL lock_timeout = '5s'")
-        .execute(&mut *tx)
-        .await?;
+    process(pool, payment, actor, Mode::Normal).await
+}
 
-    // Serialize all processing for this external_id.
-    sqlx::query!(
-        "SELECT pg_advisory_xact_lock(hashtextextended($1, 0))",
-        payment.external_id()
+/// What `process_payment_event` would do with `payment`: the event is run
+/// through the pipeline past the dedup check, under the payment's lock, and
+/// what it wrote is read back before the transaction is rolled back. A
+/// concurrent event can still change the answer.
+#[tracing::instrument(
+    name = "pipeline",
+    skip_all,
+    fields(
+        source = payment.source(),
+        event_id = payment.last_event_id(),
+        external_id = payment.external_id(),
+        simulated = true,
     )
-    .execute(&mut *tx)
-    .await?;
-
-    // Dedup: record the Stripe event. If already seen, bail early.
-    let is_new = payment_repo::insert_provider_event(
+)]
+pub async fn process_payment_event_dry_run(
+    pool: &PgPool,
+    payment: &NewPayment,
+) -> Result<Simulation, PipelineError> {
+    let mut tx = begin_locked(pool, payment).await?;
+    let duplicate = payment_repo::provider_event_exists(
         &mut tx,
+        payment.source(),
+        payment.tenant_id().map(|t| t.as_str()),
         payment.last_event_id(),
-        payment.external_id(),
-        payment.event_type(),
-        payment.provider_ts(),
-        payment.raw_event(),
     )
     .await?;
+    let current_status = payment_repo::get_existing_payment(&mut tx, payment.external_id())
+        .await?
+        .map(|e| e.status);
+    let applied = match apply(&mut tx, payment, SIMULATION_ACTOR, Mode::Simulate).await {
+        Ok(result) => {
+            // A refund's anomalies can be recorded against its parent.
+            let touched: Vec<&str> = std::iter::once(payment.external_id())
+                .chain(payment.parent_external_id())
+                .collect();
+            Applied::Done {
+                result,
+                entries: audit_repo::written_in_transaction(&mut tx, &touched).await?,
+                ledger: ledger_repo::lines_written_in_transaction(&mut tx, payment.external_id())
+                    .await?,
+            }
+        }
+        Err(PipelineError::Validation(reason)) => Applied::Rejected(reason),
+        Err(e) => return Err(e),
+    };
+    tx.rollback().await?;
+    Ok(Simulation::new(payment, duplicate, current_status, applied))
+}
+
+/// Who a simulated event's entries name; they never outlive the dry run.
+const SIMULATION_ACTOR: &str = "simulation";
+
+/// Apply an operator's status correction, recorded as a synthetic event.
+/// It goes through the state machine like any other event unless `force`
+/// overrides a refusal; its audit entries carry `reason`, and `override:
+/// true` when the state machine was overridden.
+pub async fn adjust_payment_event(
+    pool: &PgPool,
+    payment: &NewPayment,
+    actor: &str,
+    reason: &str,
+    force: bool,
+) -> Result<ProcessResult, PipelineError> {
+    process(pool, payment, actor, Mode::Adjust { reason, force }).await
+}
+
+/// How `apply` departs from the normal rules, if at all.
+#[derive(Clone, Copy)]
+enum Mode<'a> {
+    Normal,
+    /// A stored event run again; see `reprocess_payment_event`.
+    Replay,
+    /// An operator's correction; see `adjust_payment_event`.
+    Adjust {
+        reason: &'a str,
+        force: bool,
+    },
+    /// A dry run, rolled back; see `process_payment_event_dry_run`.
+    Simulate,
+}
+
+#[tracing::instrument(
+    name = "pipeline",
+    skip_all,
+    fields(
+        source = payment.source(),
+        event_id = payment.last_event_id(),
+        external_id = payment.external_id(),
+    )
+)]
+async fn process(
+    pool: &PgPool,
+    payment: &NewPayment,
+    actor: &str,
+    mode: Mode<'_>,
+) -> Result<ProcessResult, PipelineError> {
+    let mut tx = begin_locked(pool, payment).await?;
+
+    // Dedup: record the provider event. If already seen, bail early.
+    let is_new = payment_repo::insert_provider_event(&mut tx, &payment.provider_event()).await?;
 
     if !is_new {
         tx.commit().await?;
         return Ok(ProcessResult::Duplicate);
     }
 
-    let existing = payment_repo::get_existing_payment(&mut tx, payment.external_id()).await?;
+    let result = apply(&mut tx, payment, actor, mode).await?;
+    tx.commit().await?;
+    Ok(result)
+}
+
+/// Run an event already in `provider_events` through the pipeline again,
+/// past the dedup check. Every audit entry it writes is marked
+/// `replayed: true` and gets its own event id, so it sits beside the
+/// original's rather than colliding with it; a replay that changes nothing
+/// still leaves an `event_replayed` entry.
+#[tracing::instrument(
+    name = "pipeline",
+    skip_all,
+    fields(
+        source = payment.source(),
+        event_id = payment.last_event_id(),
+        external_id = payment.external_id(),
+        replay = true,
+    )
+)]
+pub async fn reprocess_payment_event(
+    pool: &PgPool,
+    payment: &NewPayment,
+    actor: &str,
+) -> Result<ProcessResult, PipelineError> {
+    let mut tx = begin_locked(pool, payment).await?;
+    let result = apply(&mut tx, payment, actor, Mode::Replay).await?;
+    tx.commit().await?;
+    Ok(result)
+}
+
+/// A transaction holding the payment's advisory lock, which serializes all
+/// processing for one external_id (within its tenant, see `lock_key`).
+async fn begin_locked<'c>(
+    pool: &PgPool,
+    payment: &NewPayment,
+) -> Result<sqlx::Transaction<'c, sqlx::Postgres>, PipelineError> {
+    let mut tx = pressure::begin(pool).await?;
+
+    sqlx::query!("SET LOCAL lock_timeout = '5s'")
+        .execute(&mut *tx)
+        .await?;
+
+    lock(&mut tx, &payment.lock_key()).await?;
+    Ok(tx)
+}
+
+async fn lock(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    key: &str,
+) -> Result<(), PipelineError> {
+    sqlx::query!("SELECT pg_advisory_xact_lock(hashtextextended($1, 0))", key)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+/// Apply an event past the dedup check, under the payment's lock. Nothing
+/// is committed: the caller commits, or a dry run rolls back.
+async fn apply(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    payment: &NewPayment,
+    actor: &str,
+    mode: Mode<'_>,
+) -> Result<ProcessResult, PipelineError> {
+    let mut existing = payment_repo::get_existing_payment(tx, payment.external_id()).await?;
+    if let Some(found) = &existing
+        && found.tenant_id.as_deref() != payment.tenant_id().map(|t| t.as_str())
+    {
+        if !found.admits(payment) {
+            return Err(PipelineError::Validation(format!(
+                "{} belongs to another tenant",
+                payment.external_id()
+            )));
+        }
+        // The platform feeding a connected account's payment: take the lock
+        // the account's own events take as well, and read the payment again
+        // under it.
+        let tenant = found.tenant_id.as_deref().map(TenantId::new).transpose()?;
+        lock(tx, &lock_key(tenant.as_ref(), payment.external_id())).await?;
+        existing = payment_repo::get_existing_payment(tx, payment.external_id()).await?;
+    }
 
     match existing {
         None => {
-            payment_repo::insert_payment(&mut tx, payment).await?;
-            let audit = payment.audit_entry(actor, "created");
-            insert_audit_entry(&mut tx, &audit).await?;
-            tx.commit().await?;
-            Ok(ProcessResult::Created(payment.id()))
+            guard_initiated_refund(tx, payment).await?;
+            // A payment first seen through an event dated in a closed period
+            // is recorded at pending, holding nothing, and its move to the
+            // event's status held like any later one.
+            let closed = match payment.status() {
+                PaymentStatus::Pending => None,
+                _ => closed_period(tx, payment, mode).await?,
+            };
+            let result = match closed {
+                Some(through) => {
+                    let held = payment.held();
+                    payment_repo::insert_payment(tx, &held).await?;
+                    let existing = ExistingPayment {
+                        id: held.id(),
+                        status: held.status().clone(),
+                        tenant_id: held.tenant_id().map(|t| t.to_string()),
+                        last_provider_ts: held.provider_ts(),
+                        last_event_id: held.last_event_id().to_string(),
+                        last_request_id: held.request_id().map(Into::into),
+                    };
+                    let audit = divert(tx, &existing, payment, through, true, actor).await?;
+                    insert_audit_entry(tx, &mark(audit, payment, mode)).await?;
+                    ProcessResult::Diverted(payment.id())
+                }
+                None => {
+                    payment_repo::insert_payment(tx, payment).await?;
+                    ledger::post(tx, payment.id(), payment, None).await?;
+                    let link = link_refund(tx, payment).await?;
+                    let mut audit = payment.audit_entry(actor, "created");
+                    if let Some(link) = &link {
+                        link.mark(&mut audit);
+                    }
+                    insert_audit_entry(tx, &mark(audit, payment, mode)).await?;
+                    if let Some(link) = &link {
+                        flag_unlinked_refund(tx, payment, link, actor).await?;
+                    }
+                    flag_over_refund(tx, payment, actor).await?;
+                    record_attempt(tx, payment).await?;
+                    ProcessResult::Created(payment.id())
+                }
+            };
+            queue_followups(tx, payment).await?;
+            if let Mode::Normal = mode {
+                feed_repo::record(
+                    tx,
+                    payment.id(),
+                    &payment.feed(),
+                    payment.provider_ts(),
+                    payment.last_event_id(),
+                    false,
+                )
+                .await?;
+            }
+            Ok(result)
         }
         Some(existing) => {
             let id = existing.id;
-            let action = existing.decide(payment);
+            // An operator's correction comes on no feed and isn't merged.
+            let merge = match mode {
+                Mode::Adjust { .. } => None,
+                _ => Some(merge_feeds(tx, id, payment, mode).await?),
+            };
+            // The payment's entries go under its tenant, whichever feed the
+            // event came on, and say when the event was behind another feed.
+            let finish = |mut audit: NewAuditEntry| {
+                audit.entity_id = Some(id);
+                audit.tenant_id = existing.tenant_id.clone();
+                if let Some(merge) = &merge {
+                    merge.mark(&mut audit);
+                }
+                mark(audit, payment, mode)
+            };
+
+            // An event behind a fresher feed is stale whatever its status.
+            let superseded = merge.as_ref().is_some_and(|m| m.supersedes());
+            let decision = if superseded {
+                PaymentAction::Stale
+            } else {
+                existing.decide(payment)
+            };
+            // A forced adjustment goes ahead where the state machine says no.
+            let (action, overridden) = match (decision, mode) {
+                (PaymentAction::LogAnomaly { current }, Mode::Adjust { force: true, .. }) => (
+                    PaymentAction::Advance {
+                        old_status: current,
+                    },
+                    true,
+                ),
+                (action, _) => (action, false),
+            };
 
             match action {
                 PaymentAction::SameStatus => {
-                    payment_repo::touch_event_with_ts(
-                        &mut tx,
-                        id,
-                        payment.last_event_id(),
-                        payment.provider_ts(),
-                    )
-                    .await?;
-                    tx.commit().await?;
-                    Ok(ProcessResult::Stale(id))
+                    if let Some(failure) = payment.failure() {
+                        payment_repo::update_failure(tx, id, failure).await?;
+                        relink_retries(tx, id, payment).await?;
+                    }
+                    if let Some(authorized) = payment.authorized_amount() {
+                        payment_repo::update_authorized_amount(tx, id, authorized.cents()).await?;
+                    }
+                    if let Some(receipt) = payment.receipt() {
+                        payment_repo::update_receipt(tx, id, receipt).await?;
+                    }
+                    let action = match mode {
+                        Mode::Replay => "event_replayed",
+                        _ => "status_unchanged",
+                    };
+                    insert_audit_entry(tx, &finish(payment.audit_entry(actor, action))).await?;
+                    payment_repo::touch_event_with_ts(tx, id, payment).await?;
+                    Ok(ProcessResult::Unchanged(id))
+                }
+                // Out of order: nothing of it is applied, the payment's last
+                // event included.
+                PaymentAction::Stale => {
+                    let mut audit = payment.audit_entry(actor, "event_stale_ignored");
+                    audit.detail = serde_json::json!({
+                        "event_type": payment.event_type(),
+                        "current_status": existing.status.as_str(),
+                        "incoming_status": payment.status().as_str(),
+                        "provider_ts": payment.provider_ts(),
+                        "last_provider_ts": existing.last_provider_ts,
+                        "last_event_id": existing.last_event_id,
+                    });
+                    if superseded {
+                        audit.detail["superseded"] = true.into();
+                    }
+                    insert_audit_entry(tx, &finish(audit)).await?;
+                    tracing::info!(
+                        external_id = %payment.external_id(),
+                        superseded,
+                        "stale event ignored"
+                    );
+                    Ok(ProcessResult::StaleIgnored(id))
                 }
                 PaymentAction::LogAnomaly { current } => {
                     let mut audit = payment.audit_entry(actor, "event_received");
@@ -83,17 +370,9 @@ pub async fn process_payment_event(
                         "incoming_status": payment.status().as_str(),
                         "anomaly": true,
                     });
-                    audit.entity_id = Some(id);
-                    insert_audit_entry(&mut tx, &audit).await?;
-
-                    payment_repo::touch_event_with_ts(
-                        &mut tx,
-                        id,
-                        payment.last_event_id(),
-                        payment.provider_ts(),
-                    )
-                    .await?;
-                    tx.commit().await?;
+                    insert_audit_entry(tx, &finish(audit)).await?;
+
+                    payment_repo::touch_event_with_ts(tx, id, payment).await?;
 
                     tracing::warn!(
                         external_id = %payment.external_id(),
@@ -104,7 +383,13 @@ pub async fn process_payment_event(
                     Ok(ProcessResult::Anomaly(id))
                 }
                 PaymentAction::Advance { old_status } => {
-                    payment_repo::update_payment_status(&mut tx, id, payment).await?;
+                    if let Some(through) = closed_period(tx, payment, mode).await? {
+                        let audit = divert(tx, &existing, payment, through, false, actor).await?;
+                        insert_audit_entry(tx, &finish(audit)).await?;
+                        return Ok(ProcessResult::Diverted(id));
+                    }
+                    payment_repo::update_payment_status(tx, id, payment).await?;
+                    ledger::post(tx, id, payment, Some(&old_status)).await?;
 
                     let mut audit = payment.audit_entry(actor, "status_changed");
                     audit.detail = serde_json::json!({
@@ -112,9 +397,15 @@ pub async fn process_payment_event(
                         "old_status": old_status.as_str(),
                         "new_status": payment.status().as_str(),
                     });
-                    audit.entity_id = Some(id);
-                    insert_audit_entry(&mut tx, &audit).await?;
-                    tx.commit().await?;
+                    if overridden {
+                        audit.detail["override"] = true.into();
+                    }
+                    if let Some(link) = link_refund(tx, payment).await? {
+                        link.mark(&mut audit);
+                    }
+                    insert_audit_entry(tx, &finish(audit)).await?;
+                    flag_over_refund(tx, payment, actor).await?;
+                    relink_retries(tx, id, payment).await?;
                     Ok(ProcessResult::Updated(id))
                 }
             }
@@ -122,6 +413,122 @@ pub async fn process_payment_event(
     }
 }
 
+/// The end of the closed period `payment` is dated in, if it is. A late
+/// event there can't move a payment the filed books already show; an
+/// operator's correction is made in the open period and always can.
+async fn closed_period(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    payment: &NewPayment,
+    mode: Mode<'_>,
+) -> Result<Option<DateTime<Utc>>, PipelineError> {
+    if let Mode::Adjust { .. } = mode {
+        return Ok(None);
+    }
+    let through = period_repo::closed_through(tx).await?;
+    Ok(through.filter(|t| in_closed_period(payment.provider_ts(), Some(*t))))
+}
+
+/// Hold `payment`'s move from `existing`'s status for review, since it's
+/// dated in the period closed `through`. Returns the entry to audit it with.
+async fn divert(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    existing: &ExistingPayment,
+    payment: &NewPayment,
+    through: DateTime<Utc>,
+    on_create: bool,
+    actor: &str,
+) -> Result<NewAuditEntry, PipelineError> {
+    let adjustment =
+        period_repo::insert_adjustment(tx, existing, payment, through, on_create).await?;
+    let mut audit = payment.audit_entry(actor, "event_diverted");
+    audit.detail = serde_json::json!({
+        "event_type": payment.event_type(),
+        "current_status": existing.status.as_str(),
+        "incoming_status": payment.status().as_str(),
+        "provider_ts": payment.provider_ts(),
+        "closed_through": through,
+        "adjustment_id": adjustment,
+    });
+    if on_create {
+        audit.detail["on_create"] = true.into();
+    }
+    tracing::warn!(
+        external_id = %payment.external_id(),
+        from = %existing.status,
+        to = %payment.status(),
+        %through,
+        %adjustment,
+        on_create,
+        "event dated in a closed period, held for adjustment"
+    );
+    Ok(audit)
+}
+
+/// Where `payment` falls among the feeds of payment `id`, under the policy
+/// in the runtime config. A new event moves its own feed's position; a
+/// replayed one was counted when it first arrived.
+async fn merge_feeds(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    id: Uuid,
+    payment: &NewPayment,
+    mode: Mode<'_>,
+) -> Result<Merge, PipelineError> {
+    let policy = feed_repo::merge_policy(&mut **tx).await?;
+    let positions = feed_repo::positions(tx, id).await?;
+    let merge = Merge::new(policy, payment, &positions);
+    if let Mode::Normal = mode {
+        feed_repo::record(
+            tx,
+            id,
+            &merge.feed,
+            payment.provider_ts(),
+            payment.last_event_id(),
+            merge.conflict.is_some(),
+        )
+        .await?;
+    }
+    Ok(merge)
+}
+
+/// Queue what a newly recorded payment leaves to do after commit: a refund
+/// or dispute that arrived before its parent has the parent fetched.
+async fn queue_followups(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    payment: &NewPayment,
+) -> Result<(), PipelineError> {
+    let Some(parent) = payment.parent_external_id() else {
+        return Ok(());
+    };
+    if payment_repo::find_payment_id(tx, parent).await?.is_none() {
+        let followup = FollowUp::FetchParent {
+            source: payment.source().to_string(),
+            external_id: parent.to_string(),
+            tenant_id: payment.tenant_id().cloned(),
+        };
+        followup_repo::enqueue(tx, &followup).await?;
+    }
+    Ok(())
+}
+
+/// Mark an audit entry with what `mode` did differently. A replayed
+/// event's entry gets an id of its own (the original event's is taken) and
+/// says which event it replays; an adjustment's carries the operator's
+/// reason. A simulated event's gets an id of its own too, so a dry run of
+/// an event already received can read it back.
+fn mark(mut audit: NewAuditEntry, payment: &NewPayment, mode: Mode<'_>) -> NewAuditEntry {
+    match mode {
+        Mode::Normal => {}
+        Mode::Simulate => audit.event_id = format!("{SIMULATED_EVENT_PREFIX}{}", audit.id),
+        Mode::Replay => {
+            audit.event_id = format!("replay:{}", audit.id);
+            audit.detail["replayed"] = true.into();
+            audit.detail["replayed_event_id"] = payment.last_event_id().into();
+        }
+        Mode::Adjust { reason, .. } => audit.detail["reason"] = reason.into(),
+    }
+    audit
+}
+
 /// Fetch current state from the provider API, then run the payment pipeline.
 pub async fn fetch_and_process_payment(
     pool: &PgPool,
@@ -130,9 +537,46 @@ pub async fn fetch_and_process_payment(
     actor: &str,
 ) -> Result<ProcessResult, PipelineError> {
     let fetched = provider.fetch_payment(&trigger.external_id).await?;
-    let payment = NewPayment::new(NewPaymentParams {
+    process_fetched_payment(pool, fetched, trigger, actor).await
+}
+
+/// Run the payment pipeline on provider state we already hold, e.g. a
+/// resource embedded in a verified webhook.
+pub async fn process_fetched_payment(
+    pool: &PgPool,
+    fetched: FetchedPayment,
+    trigger: PaymentTrigger,
+    actor: &str,
+) -> Result<ProcessResult, PipelineError> {
+    let payment = new_payment(fetched, trigger);
+    process_payment_event(pool, &payment, actor).await
+}
+
+/// `process_fetched_payment` for a stored event; see `reprocess_payment_event`.
+pub async fn reprocess_fetched_payment(
+    pool: &PgPool,
+    fetched: FetchedPayment,
+    trigger: PaymentTrigger,
+    actor: &str,
+) -> Result<ProcessResult, PipelineError> {
+    let payment = new_payment(fetched, trigger);
+    reprocess_payment_event(pool, &payment, actor).await
+}
+
+/// `process_fetched_payment`, dry run; see `process_payment_event_dry_run`.
+pub async fn simulate_fetched_payment(
+    pool: &PgPool,
+    fetched: FetchedPayment,
+    trigger: PaymentTrigger,
+) -> Result<Simulation, PipelineError> {
+    let payment = new_payment(fetched, trigger);
+    process_payment_event_dry_run(pool, &payment).await
+}
+
+fn new_payment(fetched: FetchedPayment, trigger: PaymentTrigger) -> NewPayment {
+    NewPayment::new(NewPaymentParams {
         external_id: fetched.external_id,
-        source: "stripe".into(),
+        source: trigger.source,
         event_type: trigger.event_type,
         direction: fetched.direction,
         money: fetched.money,
@@ -142,8 +586,12 @@ pub async fn fetch_and_process_payment(
         last_event_id: trigger.event_id,
         parent_external_id: fetched.parent_external_id,
         provider_ts: trigger.provider_ts,
-    });
-    process_payment_event(pool, &payment, actor).await
+        failure: fetched.failure,
+        authorized_amount: fetched.authorized_amount,
+        receipt: fetched.receipt,
+        tenant_id: trigger.tenant_id,
+        customer: fetched.customer,
+    })
 }
 
 /// Log an audit entry for events we don't upsert (charges, unknown).
@@ -158,15 +606,16 @@ pub async fn handle_passthrough(
         .as_ref()
         .map(|id| id.as_str())
         .unwrap_or("");
-    let is_new = payment_repo::insert_provider_event(
-        &mut tx,
-        event.event_id.as_str(),
+    let provider_event = NewProviderEvent {
+        source: &event.source,
+        tenant_id: event.tenant_id.as_ref().map(|t| t.as_str()),
+        event_id: event.event_id.as_str(),
         object_id,
-        &event.event_type,
-        event.provider_ts,
-        &event.raw_payload,
-    )
-    .await?;
+        event_type: &event.event_type,
+        provider_ts: event.provider_ts,
+        payload: &event.raw_payload,
+    };
+    let is_new = payment_repo::insert_provider_event(&mut tx, &provider_event).await?;
 
     if !is_new {
         tx.commit().await?;
@@ -184,6 +633,8 @@ pub async fn handle_passthrough(
         entity_id,
         external_id: event.external_id.as_ref().map(|id| id.as_str().to_string()),
         event_id: event.event_id.as_str().to_string(),
+        source: Some(event.source.clone()),
+        tenant_id: event.tenant_id.as_ref().map(|t| t.as_str().to_string()),
         action: "event_received".to_string(),
         actor: event.actor.clone(),
         detail: serde_json::json!({
@@ -0,0 +1,217 @@
+use {
+    crate::{
+        domain::{
+            audit::NewAuditEntry,
+            error::DomainError,
+            id::{ExternalId, TenantId},
+            manual::MANUAL_SOURCE,
+            money::{Currency, Money, MoneyAmount},
+            payment::{NewPayment, PaymentDirection, PaymentStatus, RefundableBalance, lock_key},
+            refund::RefundLink,
+        },
+        error::PipelineError,
+        infra::postgres::{audit_repo::insert_audit_entry, payment_repo},
+    },
+    sqlx::PgPool,
+    uuid::Uuid,
+};
+
+/// Current refund headroom for a payment, or `None` if it doesn't exist.
+pub async fn get_refundable_balance(
+    pool: &PgPool,
+    id: ExternalId,
+) -> Result<Option<RefundableBalance>, PipelineError> {
+    let mut conn = pool.acquire().await?;
+    load_balance(&mut conn, id).await
+}
+
+/// Server-side guard for refund initiation. Takes the payment's advisory lock
+/// (the same one the pipeline uses), so the balance can't move until `tx`
+/// ends — callers create the refund while still holding the transaction.
+pub async fn check_refund_amount(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    id: ExternalId,
+    amount: MoneyAmount,
+) -> Result<RefundableBalance, PipelineError> {
+    lock_parent(tx, None, id.as_str()).await?;
+    let balance = load_balance(tx, id.clone())
+        .await?
+        .ok_or_else(|| PipelineError::Validation(format!("payment not found: {id}")))?;
+    balance.ensure_covers(amount)?;
+    Ok(balance)
+}
+
+/// Pipeline hook, run in the pipeline's transaction before a payment is
+/// first inserted. A refund recorded through `POST /payments` is how our
+/// own tools initiate one, so it is refused unless its parent's refundable
+/// balance covers it; the parent's lock is held until the refund commits,
+/// so concurrent requests are checked one at a time. Provider refunds have
+/// already happened and are only flagged, by `flag_over_refund`.
+pub(crate) async fn guard_initiated_refund(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    refund: &NewPayment,
+) -> Result<(), PipelineError> {
+    let Some(parent) = refund.parent_external_id() else {
+        return Ok(());
+    };
+    // Only refunds still in flight or settled count against the balance.
+    if refund.source() != MANUAL_SOURCE
+        || *refund.direction() != PaymentDirection::Outbound
+        || !matches!(
+            refund.status(),
+            PaymentStatus::Pending | PaymentStatus::Refunded
+        )
+    {
+        return Ok(());
+    }
+    check_refund_amount(tx, ExternalId::new(parent)?, refund.money().amount()).await?;
+    Ok(())
+}
+
+/// Pipeline hook, run in the pipeline's transaction before a refund's
+/// audit entry is written. Takes the parent's lock, as `flag_over_refund`
+/// does, and checks the refund against the parent it names. `None` for
+/// anything but a refund naming a parent.
+pub(crate) async fn link_refund(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    refund: &NewPayment,
+) -> Result<Option<RefundLink>, PipelineError> {
+    let Some(parent) = RefundLink::parent_of(refund) else {
+        return Ok(None);
+    };
+    lock_parent(tx, refund.tenant_id(), parent).await?;
+    let parent = payment_repo::get_parent_payment(tx, parent).await?;
+    Ok(Some(RefundLink::new(refund, parent.as_ref())))
+}
+
+/// Record a new refund whose link to its parent doesn't hold as a
+/// `refund_unlinked` anomaly against the refund.
+pub(crate) async fn flag_unlinked_refund(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    refund: &NewPayment,
+    link: &RefundLink,
+    actor: &str,
+) -> Result<(), PipelineError> {
+    let Some(audit) = link.audit_entry(refund, actor) else {
+        return Ok(());
+    };
+    insert_audit_entry(tx, &audit).await?;
+    tracing::warn!(
+        external_id = %refund.external_id(),
+        parent = refund.parent_external_id(),
+        reason = link.anomaly(),
+        "refund doesn't link to its parent, logged as anomaly"
+    );
+    Ok(())
+}
+
+/// Pipeline hook, run in the pipeline's transaction after a refund row was
+/// created or changed status. Takes the parent's lock so concurrent refunds
+/// of one payment are totalled one at a time, and if the parent's refunds
+/// now add up to more than its amount, records an `over_refunded` anomaly
+/// against the parent. Returns the parent's balance when it did.
+pub(crate) async fn flag_over_refund(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    refund: &NewPayment,
+    actor: &str,
+) -> Result<Option<RefundableBalance>, PipelineError> {
+    let Some(parent) = refund.parent_external_id() else {
+        return Ok(None);
+    };
+    // Disputes hang off the parent too, but aren't refunds.
+    if *refund.direction() != PaymentDirection::Outbound || refund.status().is_dispute() {
+        return Ok(None);
+    }
+
+    lock_parent(tx, refund.tenant_id(), parent).await?;
+
+    // Refunds can arrive before their parent; nothing to total yet.
+    let Some(parent_id) = payment_repo::find_payment_id(tx, parent).await? else {
+        return Ok(None);
+    };
+    let balance = match load_balance(tx, ExternalId::new(parent)?).await {
+        Ok(Some(balance)) if balance.over_refunded => balance,
+        Ok(_) | Err(PipelineError::Validation(_)) => return Ok(None),
+        Err(e) => return Err(e),
+    };
+
+    insert_audit_entry(
+        tx,
+        &NewAuditEntry {
+            id: Uuid::now_v7(),
+            entity_type: "payment".to_string(),
+            entity_id: Some(parent_id),
+            external_id: Some(parent.to_string()),
+            event_id: format!("over_refund:{}", refund.last_event_id()),
+            source: Some(refund.source().to_string()),
+            tenant_id: refund.tenant_id().map(|t| t.to_string()),
+            action: "over_refunded".to_string(),
+            actor: actor.to_string(),
+            detail: serde_json::json!({
+                "refund_external_id": refund.external_id(),
+                "amount": balance.amount,
+                "refunded": balance.refunded,
+                "pending_refunds": balance.pending_refunds,
+                "anomaly": true,
+            }),
+        },
+    )
+    .await?;
+
+    tracing::warn!(
+        external_id = %parent,
+        refund = %refund.external_id(),
+        amount = balance.amount,
+        refunded = balance.refunded,
+        pending_refunds = balance.pending_refunds,
+        "refunds exceed payment amount, logged as anomaly"
+    );
+    Ok(Some(balance))
+}
+
+/// Take the lock the pipeline holds for `parent`, which is under the
+/// parent's own tenant: the platform can feed a connected account's refund
+/// and the other way round. A parent not seen yet is locked under `tenant`,
+/// the refund's, which its own events most likely come on.
+async fn lock_parent(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    tenant: Option<&TenantId>,
+    parent: &str,
+) -> Result<(), PipelineError> {
+    let tenant = match payment_repo::find_tenant_id(tx, parent).await? {
+        Some(found) => found.map(TenantId::new).transpose()?,
+        None => tenant.cloned(),
+    };
+    sqlx::query!(
+        "SELECT pg_advisory_xact_lock(hashtextextended($1, 0))",
+        lock_key(tenant.as_ref(), parent)
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+async fn load_balance(
+    conn: &mut sqlx::PgConnection,
+    id: ExternalId,
+) -> Result<Option<RefundableBalance>, PipelineError> {
+    let Some(row) = payment_repo::get_refund_totals(conn, id.as_str()).await? else {
+        return Ok(None);
+    };
+
+    if PaymentDirection::try_from(row.direction.as_str())? != PaymentDirection::Inbound {
+        return Err(PipelineError::Validation(format!(
+            "only inbound payments are refundable, got: {id}"
+        )));
+    }
+
+    let currency = Currency::try_from(row.currency.as_str())?;
+    let money = |cents| Ok::<_, DomainError>(Money::new(MoneyAmount::new(cents)?, currency));
+    Ok(Some(RefundableBalance::new(
+        id,
+        &PaymentStatus::try_from(row.status.as_str())?,
+        &money(row.amount)?,
+        &money(row.refunded)?,
+        &money(row.pending)?,
+    )?))
+}
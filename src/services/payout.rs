@@ -0,0 +1,113 @@
+use {
+    crate::{
+        domain::{
+            payment::{NewProviderEvent, ProcessResult},
+            payout::{
+                NewPayout, PayoutAction, PayoutFilters, PayoutKind, PayoutStatus, PayoutView,
+            },
+        },
+        error::PipelineError,
+        infra::postgres::{audit_repo::insert_audit_entry, payment_repo, payout_repo},
+    },
+    sqlx::PgPool,
+    uuid::Uuid,
+};
+
+/// Process a payout or transfer event: dedup through `provider_events`, lock
+/// the object, then insert or update with state machine validation and an
+/// audit entry, as payments do.
+pub async fn process_payout_event(
+    pool: &PgPool,
+    payout: &NewPayout,
+    actor: &str,
+) -> Result<ProcessResult, PipelineError> {
+    let external_id = payout.external_id.as_str();
+    let mut tx = pool.begin().await?;
+    sqlx::query!("SET LOCAL lock_timeout = '5s'")
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query!(
+        "SELECT pg_advisory_xact_lock(hashtextextended($1, 0))",
+        external_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let event = NewProviderEvent {
+        source: &payout.source,
+        tenant_id: None,
+        event_id: payout.event_id.as_str(),
+        object_id: external_id,
+        event_type: &payout.event_type,
+        provider_ts: payout.provider_ts,
+        payload: &payout.raw_event,
+    };
+    let is_new = payment_repo::insert_provider_event(&mut tx, &event).await?;
+    if !is_new {
+        tx.commit().await?;
+        return Ok(ProcessResult::Duplicate);
+    }
+
+    let Some(existing) = payout_repo::get_existing_payout(&mut tx, external_id).await? else {
+        let id = Uuid::now_v7();
+        payout_repo::insert_payout(&mut tx, id, payout).await?;
+        insert_audit_entry(&mut tx, &payout.audit_entry(id, actor, "created")).await?;
+        tx.commit().await?;
+        return Ok(ProcessResult::Created(id));
+    };
+
+    let id = existing.id;
+    let result = match existing.decide(payout) {
+        PayoutAction::Stale => ProcessResult::StaleIgnored(id),
+        PayoutAction::Refresh => {
+            payout_repo::update_payout(&mut tx, id, payout).await?;
+            ProcessResult::Unchanged(id)
+        }
+        PayoutAction::Advance { old_status } => {
+            payout_repo::update_payout(&mut tx, id, payout).await?;
+            let mut audit = payout.audit_entry(id, actor, "status_changed");
+            audit.detail["old_status"] = old_status.as_str().into();
+            audit.detail["new_status"] = payout.status.as_str().into();
+            insert_audit_entry(&mut tx, &audit).await?;
+            ProcessResult::Updated(id)
+        }
+        PayoutAction::LogAnomaly { current } => {
+            let mut audit = payout.audit_entry(id, actor, "event_received");
+            audit.detail["current_status"] = current.as_str().into();
+            audit.detail["incoming_status"] = payout.status.as_str().into();
+            audit.detail["anomaly"] = true.into();
+            insert_audit_entry(&mut tx, &audit).await?;
+            tracing::warn!(
+                external_id,
+                from = %current,
+                to = %payout.status,
+                "invalid payout status transition, logged as anomaly"
+            );
+            ProcessResult::Anomaly(id)
+        }
+    };
+    tx.commit().await?;
+    Ok(result)
+}
+
+pub async fn get_payout(
+    pool: &PgPool,
+    external_id: &str,
+) -> Result<Option<PayoutView>, PipelineError> {
+    payout_repo::get_payout(pool, external_id).await
+}
+
+/// Unknown `kind` or `status` filters are rejected rather than matching
+/// nothing.
+pub async fn list_payouts(
+    pool: &PgPool,
+    filters: &PayoutFilters,
+) -> Result<Vec<PayoutView>, PipelineError> {
+    if let Some(kind) = &filters.kind {
+        PayoutKind::try_from(kind.as_str())?;
+    }
+    if let Some(status) = &filters.status {
+        PayoutStatus::try_from(status.as_str())?;
+    }
+    payout_repo::list_payouts(pool, filters).await
+}
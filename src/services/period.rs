@@ -0,0 +1,229 @@
+use {
+    crate::{
+        domain::{
+            adjustment::{TransitionOutcome, TransitionRequest},
+            id::ExternalId,
+            payment::PaymentStatus,
+            period::{
+                AdjustmentStatus, CloseRequest, EodCutoff, PeriodAdjustment, PeriodClose,
+                PeriodStatus, validate_reason,
+            },
+        },
+        error::PipelineError,
+        infra::{
+            postgres::{audit_repo::insert_audit_entry, period_repo},
+            settings::{SettingKey, Settings},
+        },
+        services::payment::adjust,
+    },
+    chrono::Utc,
+    sqlx::PgPool,
+    uuid::Uuid,
+};
+
+/// When the business day ends; midnight UTC until set.
+pub const EOD_CUTOFF: SettingKey<EodCutoff> = SettingKey::new("accounting.eod_cutoff");
+
+/// What came of settling a diverted status change.
+#[derive(Debug)]
+pub enum Resolution {
+    /// Applied (the payment moved, or was found already there) or
+    /// dismissed, as asked.
+    Resolved(PeriodAdjustment),
+    /// The state machine refused from where the payment is now; the
+    /// adjustment stays pending.
+    Rejected { current: PaymentStatus },
+    /// Already applied or dismissed before.
+    AlreadySettled(PeriodAdjustment),
+}
+
+pub async fn status(pool: &PgPool, settings: &Settings) -> Result<PeriodStatus, PipelineError> {
+    let periods = period_repo::list_closes(pool, 100).await?;
+    Ok(PeriodStatus {
+        closed_through: periods
+            .iter()
+            .filter(|p| p.reopened_at.is_none())
+            .map(|p| p.closed_through)
+            .max(),
+        eod_cutoff: settings.get_or_default(&EOD_CUTOFF).await?,
+        periods,
+    })
+}
+
+/// Change the end-of-day cutoff. Closes already made keep the end they
+/// were given.
+pub async fn set_cutoff(
+    settings: &Settings,
+    cutoff: &EodCutoff,
+    actor: &str,
+) -> Result<(), PipelineError> {
+    cutoff.validate()?;
+    settings.set(&EOD_CUTOFF, cutoff, actor).await?;
+    Ok(())
+}
+
+/// Close the books through the end of `request.business_date` under the
+/// current cutoff. Periods close in order, and only once their day is
+/// over; from the commit on, status changes dated before the end are held
+/// as adjustments. Waits for events being applied to finish.
+pub async fn close(
+    pool: &PgPool,
+    settings: &Settings,
+    request: &CloseRequest,
+    actor: &str,
+) -> Result<PeriodClose, PipelineError> {
+    validate_reason(&request.reason)?;
+    let through = settings
+        .get_or_default(&EOD_CUTOFF)
+        .await?
+        .end_of(request.business_date);
+    if through > Utc::now() {
+        return Err(PipelineError::Validation(format!(
+            "{} doesn't end until {through}",
+            request.business_date
+        )));
+    }
+
+    let mut tx = pool.begin().await?;
+    if let Some(latest) = period_repo::lock_periods(&mut tx).await?
+        && latest.closed_through >= through
+    {
+        return Err(PipelineError::Validation(format!(
+            "already closed through {} ({})",
+            latest.closed_through, latest.business_date
+        )));
+    }
+    let close = period_repo::insert_close(
+        &mut tx,
+        request.business_date,
+        through,
+        &request.reason,
+        actor,
+    )
+    .await?;
+    insert_audit_entry(&mut tx, &close.audit_entry("period_closed", actor)).await?;
+    tx.commit().await?;
+
+    tracing::warn!(
+        business_date = %close.business_date,
+        closed_through = %close.closed_through,
+        actor,
+        "accounting period closed"
+    );
+    Ok(close)
+}
+
+/// Reopen the latest close; the one before it, if any, is the lock again.
+/// `None` if nothing is closed.
+pub async fn reopen(
+    pool: &PgPool,
+    reason: &str,
+    actor: &str,
+) -> Result<Option<PeriodClose>, PipelineError> {
+    validate_reason(reason)?;
+    let mut tx = pool.begin().await?;
+    let Some(latest) = period_repo::lock_periods(&mut tx).await? else {
+        return Ok(None);
+    };
+    let close = period_repo::reopen(&mut tx, latest.id, reason, actor).await?;
+    insert_audit_entry(&mut tx, &close.audit_entry("period_reopened", actor)).await?;
+    tx.commit().await?;
+
+    tracing::warn!(
+        business_date = %close.business_date,
+        actor,
+        "accounting period reopened"
+    );
+    Ok(Some(close))
+}
+
+pub async fn adjustments(
+    pool: &PgPool,
+    status: Option<&str>,
+    limit: Option<i64>,
+) -> Result<Vec<PeriodAdjustment>, PipelineError> {
+    let status = status.map(AdjustmentStatus::try_from).transpose()?;
+    let limit = limit.unwrap_or(50).clamp(1, 500);
+    period_repo::list_adjustments(pool, status, limit).await
+}
+
+/// Make a diverted status change in the open period, as an operator
+/// transition with `reason`. `None` if there's no such adjustment.
+pub async fn apply_adjustment(
+    pool: &PgPool,
+    id: Uuid,
+    reason: &str,
+    actor: &str,
+) -> Result<Option<Resolution>, PipelineError> {
+    validate_reason(reason)?;
+    let Some(adjustment) = period_repo::get_adjustment(pool, id).await? else {
+        return Ok(None);
+    };
+    if adjustment.status != AdjustmentStatus::Pending.as_str() {
+        return Ok(Some(Resolution::AlreadySettled(adjustment)));
+    }
+
+    let request = TransitionRequest {
+        status: PaymentStatus::try_from(adjustment.to_status.as_str())?,
+        reason: reason.to_string(),
+        force: adjustment.on_create,
+    };
+    let external_id = ExternalId::new(&adjustment.external_id)?;
+    let outcome = adjust::transition_payment(pool, external_id, &request, actor)
+        .await?
+        .ok_or_else(|| PipelineError::Validation("payment not found".into()))?;
+    if let TransitionOutcome::Rejected { current } = outcome {
+        return Ok(Some(Resolution::Rejected { current }));
+    }
+    Ok(Some(
+        match resolve(pool, id, AdjustmentStatus::Applied, reason, actor).await? {
+            Some(applied) => Resolution::Resolved(applied),
+            None => Resolution::AlreadySettled(adjustment),
+        },
+    ))
+}
+
+/// Leave a diverted status change unapplied for good. `None` if there's no
+/// such adjustment.
+pub async fn dismiss_adjustment(
+    pool: &PgPool,
+    id: Uuid,
+    reason: &str,
+    actor: &str,
+) -> Result<Option<Resolution>, PipelineError> {
+    validate_reason(reason)?;
+    let Some(adjustment) = period_repo::get_adjustment(pool, id).await? else {
+        return Ok(None);
+    };
+    Ok(Some(
+        match resolve(pool, id, AdjustmentStatus::Dismissed, reason, actor).await? {
+            Some(dismissed) => Resolution::Resolved(dismissed),
+            None => Resolution::AlreadySettled(adjustment),
+        },
+    ))
+}
+
+async fn resolve(
+    pool: &PgPool,
+    id: Uuid,
+    status: AdjustmentStatus,
+    reason: &str,
+    actor: &str,
+) -> Result<Option<PeriodAdjustment>, PipelineError> {
+    let mut tx = pool.begin().await?;
+    let Some(resolved) =
+        period_repo::resolve_adjustment(&mut tx, id, status, reason, actor).await?
+    else {
+        return Ok(None);
+    };
+    insert_audit_entry(&mut tx, &resolved.audit_entry(status, reason, actor)).await?;
+    tx.commit().await?;
+    tracing::info!(
+        adjustment = %id,
+        external_id = %resolved.external_id,
+        status = status.as_str(),
+        actor,
+        "period adjustment settled"
+    );
+    Ok(Some(resolved))
+}
@@ -0,0 +1,223 @@
+use {
+    crate::{
+        domain::{
+            provider::{PaymentPager, PaymentProvider, ProviderRegistry},
+            reconciliation::{
+                DiscrepancyView, ReconciliationRunView, ReconciliationSummary, diff,
+                unaccepted_currency,
+            },
+        },
+        error::PipelineError,
+        infra::postgres::{audit_repo::insert_audit_entry, reconciliation_repo},
+        services::{currency_terms, notify::Notifier},
+    },
+    chrono::{DateTime, TimeDelta, Utc},
+    serde::Serialize,
+    sqlx::PgPool,
+    std::collections::HashMap,
+    tokio::sync::watch,
+    uuid::Uuid,
+};
+
+#[derive(Debug, Serialize)]
+pub struct ReconciliationReport {
+    pub run: ReconciliationRunView,
+    pub discrepancies: Vec<DiscrepancyView>,
+}
+
+/// Record a new run of `source` for `[since, until]` and return its id. The
+/// run stays `running` until [`execute_run`] finishes it.
+pub async fn start_run(
+    pool: &PgPool,
+    source: &str,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Result<Uuid, PipelineError> {
+    if since >= until {
+        return Err(PipelineError::Validation(format!(
+            "empty reconciliation window: {since} >= {until}"
+        )));
+    }
+    reconciliation_repo::create_run(pool, source, since, until).await
+}
+
+/// Page through the provider listing, diff each object against `payments`,
+/// and persist discrepancies with audit entries. Marks the run failed on error.
+pub async fn execute_run(
+    pool: &PgPool,
+    provider: &dyn PaymentProvider,
+    run_id: Uuid,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Result<(), PipelineError> {
+    match compare_window(pool, provider, run_id, since, until).await {
+        Ok((checked, found)) => {
+            reconciliation_repo::complete_run(pool, run_id, checked, found).await?;
+            tracing::info!(%run_id, checked, discrepancies = found, "reconciliation run completed");
+            Ok(())
+        }
+        Err(e) => {
+            tracing::error!(%run_id, error = %e, "reconciliation run failed");
+            reconciliation_repo::fail_run(pool, run_id, &e.to_string()).await?;
+            Err(e)
+        }
+    }
+}
+
+async fn compare_window(
+    pool: &PgPool,
+    provider: &dyn PaymentProvider,
+    run_id: Uuid,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Result<(i32, i32), PipelineError> {
+    let actor = format!("reconciler:{}", provider.source());
+    let mut checked = 0i32;
+    let mut found = 0i32;
+    let mut pager = PaymentPager::new(provider, since, until);
+    let terms = currency_terms::timeline(pool, provider.source()).await?;
+
+    while let Some(payments) = pager.next_page().await? {
+        let ids: Vec<String> = payments
+            .iter()
+            .map(|p| p.external_id.as_str().to_string())
+            .collect();
+        let local: HashMap<String, _> = reconciliation_repo::get_local_snapshots(pool, &ids)
+            .await?
+            .into_iter()
+            .map(|s| (s.external_id.clone(), s))
+            .collect();
+
+        let mut tx = pool.begin().await?;
+        for remote in &payments {
+            let local = local.get(remote.external_id.as_str());
+            let mut findings = diff(run_id, remote, local);
+            findings.extend(local.and_then(|l| {
+                // Backfilled and late payments were recorded long after the
+                // provider took them; their terms are those of the latter.
+                let taken_at = remote.created_at.unwrap_or(l.created_at);
+                let in_force = terms.at(provider.source(), l.currency.as_str(), taken_at);
+                unaccepted_currency(run_id, l, taken_at, in_force)
+            }));
+            for d in &findings {
+                reconciliation_repo::insert_discrepancy(&mut tx, d).await?;
+                insert_audit_entry(&mut tx, &d.audit_entry(&actor)).await?;
+            }
+            found += findings.len() as i32;
+        }
+        tx.commit().await?;
+        checked += payments.len() as i32;
+    }
+    Ok((checked, found))
+}
+
+/// Run a reconciliation to completion and return its report.
+pub async fn reconcile(
+    pool: &PgPool,
+    provider: &dyn PaymentProvider,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Result<ReconciliationReport, PipelineError> {
+    let run_id = start_run(pool, provider.source(), since, until).await?;
+    execute_run(pool, provider, run_id, since, until).await?;
+    get_report(pool, run_id)
+        .await?
+        .ok_or_else(|| PipelineError::Validation(format!("run disappeared: {run_id}")))
+}
+
+pub async fn list_runs(
+    pool: &PgPool,
+    limit: Option<i64>,
+) -> Result<Vec<ReconciliationRunView>, PipelineError> {
+    reconciliation_repo::list_runs(pool, limit.unwrap_or(20).clamp(1, 100)).await
+}
+
+/// Open findings flagged outside a run, such as payments the provider no
+/// longer has, oldest first.
+pub async fn list_review(
+    pool: &PgPool,
+    limit: Option<i64>,
+) -> Result<Vec<DiscrepancyView>, PipelineError> {
+    reconciliation_repo::list_review(pool, limit.unwrap_or(100).clamp(1, 500)).await
+}
+
+pub async fn get_report(
+    pool: &PgPool,
+    run_id: Uuid,
+) -> Result<Option<ReconciliationReport>, PipelineError> {
+    let Some(run) = reconciliation_repo::get_run(pool, run_id).await? else {
+        return Ok(None);
+    };
+    let discrepancies = reconciliation_repo::list_discrepancies(pool, run_id).await?;
+    Ok(Some(ReconciliationReport { run, discrepancies }))
+}
+
+/// Build the run's summary and send it to every sink of `notifier`.
+/// `None` if the run doesn't exist.
+pub async fn deliver_summary(
+    pool: &PgPool,
+    notifier: &Notifier,
+    run_id: Uuid,
+) -> Result<Option<ReconciliationSummary>, PipelineError> {
+    let Some(report) = get_report(pool, run_id).await? else {
+        return Ok(None);
+    };
+    let url = notifier.link(&format!("/admin/reconciliations/{run_id}"));
+    let summary = ReconciliationSummary::new(&report.run, &report.discrepancies, url);
+    notifier.notify(&summary.to_alert()).await;
+    Ok(Some(summary))
+}
+
+/// [`execute_run`], then deliver the summary whether the run completed or
+/// failed.
+pub async fn execute_and_notify(
+    pool: &PgPool,
+    provider: &dyn PaymentProvider,
+    notifier: &Notifier,
+    run_id: Uuid,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Result<(), PipelineError> {
+    let result = execute_run(pool, provider, run_id, since, until).await;
+    if let Err(e) = deliver_summary(pool, notifier, run_id).await {
+        tracing::error!(%run_id, error = %e, "reconciliation summary not delivered");
+    }
+    result
+}
+
+/// Reconciliation of the last day every `interval` (hourly by default), for
+/// every configured provider, each followed by a summary to `notifier`. The newest 10 minutes are
+/// skipped so in-flight webhooks don't show up as missing rows.
+pub async fn run_reconciler(
+    pool: PgPool,
+    providers: ProviderRegistry,
+    notifier: Notifier,
+    interval: std::time::Duration,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    tracing::info!("reconciler started");
+
+    loop {
+        tokio::select! {
+            _ = shutdown.changed() => {
+                tracing::info!("reconciler shutting down");
+                return;
+            }
+            _ = tokio::time::sleep(interval) => {}
+        }
+
+        let until = Utc::now() - TimeDelta::minutes(10);
+        let since = until - TimeDelta::hours(25);
+        for provider in providers.all() {
+            let result = match start_run(&pool, provider.source(), since, until).await {
+                Ok(run_id) => {
+                    execute_and_notify(&pool, &**provider, &notifier, run_id, since, until).await
+                }
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                tracing::error!(source = provider.source(), error = %e, "scheduled reconciliation error");
+            }
+        }
+    }
+}
@@ -0,0 +1,264 @@
+use {
+    crate::{
+        domain::{
+            id::{EventId, ExternalId, TenantId},
+            payment::{PaymentTrigger, ProcessResult},
+            provider::{FetchedPayment, ProviderRegistry},
+            replay::{
+                EventReplay, PaymentDiff, ReplayCounts, ReplayEvent, ReplayReport, ReplayRequest,
+                SimulateRequest, Simulation, SkippedEvent, diff_payments,
+            },
+        },
+        error::PipelineError,
+        infra::postgres::{payment_repo, replay_repo},
+        services::{
+            migrate::MIGRATOR,
+            payment::pipeline::{
+                process_fetched_payment, reprocess_fetched_payment, simulate_fetched_payment,
+            },
+        },
+    },
+    chrono::Utc,
+    sqlx::{PgPool, postgres::PgPoolOptions},
+    std::collections::BTreeSet,
+    uuid::Uuid,
+};
+
+const ACTOR: &str = "replay";
+
+/// Most events one replay may select.
+const MAX_EVENTS: i64 = 500;
+
+/// Re-run stored provider events through the current pipeline code in a
+/// scratch schema, then diff the payments it produced against production.
+///
+/// The schema (`replay_<uuid>`) is created and migrated on demand, and
+/// dropped afterwards unless `keep` is set. Events are replayed from the
+/// object embedded in their stored payload, so no provider API is called;
+/// passthrough events and payloads without an object are reported as
+/// skipped. Production tables are only read.
+pub async fn replay(
+    pool: &PgPool,
+    providers: &ProviderRegistry,
+    req: ReplayRequest,
+) -> Result<ReplayReport, PipelineError> {
+    if req.is_unbounded() {
+        return Err(PipelineError::Validation(
+            "select events by event_ids, object_ids or since".into(),
+        ));
+    }
+    let limit = req.limit.unwrap_or(100).clamp(1, MAX_EVENTS);
+    let events = replay_repo::select_events(pool, &req, limit).await?;
+
+    let schema = format!("replay_{}", Uuid::now_v7().simple());
+    replay_repo::create_schema(pool, &schema).await?;
+    let outcome = run_in_schema(pool, providers, &schema, &events).await;
+
+    // A failed replay returns no report naming the schema, so don't keep it.
+    let kept = req.keep && outcome.is_ok();
+    if !kept && let Err(e) = replay_repo::drop_schema(pool, &schema).await {
+        tracing::error!(%schema, error = %e, "failed to drop replay schema");
+    }
+    let (replayed, skipped, diffs) = outcome?;
+
+    tracing::info!(
+        %schema,
+        selected = events.len(),
+        skipped = skipped.len(),
+        diffs = diffs.len(),
+        "sandbox replay finished"
+    );
+    Ok(ReplayReport {
+        schema,
+        kept,
+        selected: events.len(),
+        replayed,
+        skipped,
+        diffs,
+    })
+}
+
+/// Re-run one stored provider event against production, e.g. after a
+/// mapping fix. Like the sandbox replay, the payment is rebuilt from the
+/// object embedded in the stored payload; unlike it, the result is applied
+/// for real, past the dedup check, with every audit entry marked
+/// `replayed`. `None` if no such event was stored.
+pub async fn replay_stored_event(
+    pool: &PgPool,
+    providers: &ProviderRegistry,
+    event_id: &str,
+    actor: &str,
+) -> Result<Option<EventReplay>, PipelineError> {
+    let Some(event) = replay_repo::get_event(pool, event_id).await? else {
+        return Ok(None);
+    };
+    let (fetched, trigger) = rebuild(providers, &event)?;
+    let external_id = fetched.external_id.clone();
+    let result = reprocess_fetched_payment(pool, fetched, trigger, actor).await?;
+
+    tracing::info!(
+        event_id,
+        %external_id,
+        %actor,
+        result = result.as_str(),
+        "stored event replayed"
+    );
+    Ok(Some(EventReplay {
+        event_id: event.event_id,
+        result: result.as_str(),
+        payment: payment_repo::get_payment_by_id(pool, external_id).await?,
+    }))
+}
+
+/// What the pipeline would do with an event, without doing it: a stored
+/// one as if the provider sent it again, or one not received yet from the
+/// request's `payload`. The payment is built from the object in the
+/// payload, as for replays, so no provider API is called. `None` for a
+/// stored event that doesn't exist.
+pub async fn simulate_event(
+    pool: &PgPool,
+    providers: &ProviderRegistry,
+    req: SimulateRequest,
+) -> Result<Option<Simulation>, PipelineError> {
+    let event = match req.payload {
+        None => match replay_repo::get_event(pool, &req.event_id).await? {
+            Some(event) => event,
+            None => return Ok(None),
+        },
+        Some(payload) => {
+            let source = req.source.ok_or_else(|| {
+                PipelineError::Validation("source is required with a payload".into())
+            })?;
+            let event_type = req
+                .event_type
+                .or_else(|| payload["type"].as_str().map(Into::into))
+                .or_else(|| payload["event_type"].as_str().map(Into::into))
+                .ok_or_else(|| {
+                    PipelineError::Validation("event_type is required with this payload".into())
+                })?;
+            // Stripe Connect events name their account.
+            let tenant_id = req
+                .tenant_id
+                .or_else(|| payload["account"].as_str().map(Into::into));
+            ReplayEvent {
+                event_id: req.event_id,
+                object_id: String::new(),
+                event_type,
+                provider_ts: req
+                    .provider_ts
+                    .or_else(|| payload["created"].as_i64())
+                    .unwrap_or_else(|| Utc::now().timestamp()),
+                payload,
+                source: Some(source),
+                tenant_id,
+            }
+        }
+    };
+    let (fetched, trigger) = rebuild(providers, &event)?;
+    let simulation = simulate_fetched_payment(pool, fetched, trigger).await?;
+
+    tracing::info!(
+        event_id = simulation.event_id,
+        external_id = simulation.external_id,
+        result = simulation.result,
+        "event simulated"
+    );
+    Ok(Some(simulation))
+}
+
+type Outcome = (ReplayCounts, Vec<SkippedEvent>, Vec<PaymentDiff>);
+
+async fn run_in_schema(
+    pool: &PgPool,
+    providers: &ProviderRegistry,
+    schema: &str,
+    events: &[ReplayEvent],
+) -> Result<Outcome, PipelineError> {
+    // Same database and credentials; only the search path differs, so the
+    // pipeline's queries land in the scratch schema unchanged. `public`
+    // stays on the path for functions installed there (e.g. `uuidv7()` on
+    // older Postgres); every table resolves to the sandbox once migrated.
+    let options = (*pool.connect_options())
+        .clone()
+        .options([("search_path", format!("{schema},public"))]);
+    let sandbox = PgPoolOptions::new()
+        .max_connections(2)
+        .connect_with(options)
+        .await?;
+    let outcome = replay_into(pool, &sandbox, providers, events).await;
+    sandbox.close().await;
+    outcome
+}
+
+async fn replay_into(
+    pool: &PgPool,
+    sandbox: &PgPool,
+    providers: &ProviderRegistry,
+    events: &[ReplayEvent],
+) -> Result<Outcome, PipelineError> {
+    MIGRATOR.run(sandbox).await.map_err(sqlx::Error::from)?;
+
+    let mut replayed = ReplayCounts::default();
+    let mut skipped = Vec::new();
+    let mut object_ids = BTreeSet::new();
+    for event in events {
+        match replay_event(sandbox, providers, event).await {
+            Ok(result) => {
+                replayed.record(&result);
+                object_ids.insert(event.object_id.as_str());
+            }
+            Err(e) => skipped.push(SkippedEvent {
+                event_id: event.event_id.clone(),
+                reason: e.to_string(),
+            }),
+        }
+    }
+
+    let mut diffs = Vec::new();
+    for object_id in object_ids {
+        let Ok(id) = ExternalId::new(object_id) else {
+            continue;
+        };
+        let production = payment_repo::get_payment_by_id(pool, id.clone()).await?;
+        let replayed = payment_repo::get_payment_by_id(sandbox, id).await?;
+        if let Some(diff) = diff_payments(object_id, production.as_ref(), replayed.as_ref())? {
+            diffs.push(diff);
+        }
+    }
+    Ok((replayed, skipped, diffs))
+}
+
+async fn replay_event(
+    sandbox: &PgPool,
+    providers: &ProviderRegistry,
+    event: &ReplayEvent,
+) -> Result<ProcessResult, PipelineError> {
+    let (fetched, trigger) = rebuild(providers, event)?;
+    process_fetched_payment(sandbox, fetched, trigger, ACTOR).await
+}
+
+/// The payment and trigger a stored event would have produced, from the
+/// object embedded in its payload.
+fn rebuild(
+    providers: &ProviderRegistry,
+    event: &ReplayEvent,
+) -> Result<(FetchedPayment, PaymentTrigger), PipelineError> {
+    let source = event
+        .source
+        .as_deref()
+        .ok_or_else(|| PipelineError::Validation("no payment for this event".into()))?;
+    let fetched = providers
+        .get(source)?
+        .payment_from_event(&event.payload)?
+        .ok_or_else(|| PipelineError::Validation("event carries no payment object".into()))?;
+    let trigger = PaymentTrigger {
+        source: source.to_string(),
+        tenant_id: event.tenant_id.clone().map(TenantId::new).transpose()?,
+        event_id: EventId::new(&event.event_id)?,
+        event_type: event.event_type.clone(),
+        external_id: fetched.external_id.clone(),
+        raw_event: event.payload.clone(),
+        provider_ts: event.provider_ts,
+    };
+    Ok((fetched, trigger))
+}
@@ -0,0 +1,66 @@
+use {
+    crate::{
+        domain::{
+            report::{DailyReport, DisputeReport, FeeLine, OutcomeLine},
+            rollup::BucketSize,
+        },
+        error::PipelineError,
+        infra::postgres::report_repo,
+        services::currency_terms,
+    },
+    chrono::{DateTime, Months, NaiveDate, Utc},
+    sqlx::PgPool,
+};
+
+/// Months covered by the dispute report when no range is given.
+const DEFAULT_DISPUTE_MONTHS: u32 = 12;
+
+/// Read the day's rollups. Figures are as fresh as the last rollup run
+/// (see `services::rollup`); recompute the window first after data fixes.
+/// Fees are worked out under the currency terms in force at the day's
+/// start.
+pub async fn get_daily_report(
+    pool: &PgPool,
+    date: NaiveDate,
+) -> Result<DailyReport, PipelineError> {
+    let bucket_start = DailyReport::bucket_start(date);
+    let summaries = report_repo::get_daily_summaries(pool, bucket_start).await?;
+    let failure_reasons = report_repo::get_failure_reasons(pool, bucket_start).await?;
+    let terms = currency_terms::in_force_at(pool, bucket_start).await?;
+    Ok(DailyReport {
+        date,
+        outcomes: OutcomeLine::from_summaries(&summaries),
+        fees: FeeLine::from_summaries(&summaries, &terms, bucket_start)?,
+        summaries,
+        failure_reasons,
+    })
+}
+
+/// Monthly dispute figures for the months touching `[from, to)`. Defaults to
+/// the last twelve months including the current one.
+pub async fn get_dispute_report(
+    pool: &PgPool,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    now: DateTime<Utc>,
+) -> Result<DisputeReport, PipelineError> {
+    let month = BucketSize::Month;
+    let to = match to {
+        Some(d) => month.ceil(DailyReport::bucket_start(d)),
+        None => month.next(month.truncate(now)),
+    };
+    let from = match from {
+        Some(d) => month.truncate(DailyReport::bucket_start(d)),
+        None => to
+            .checked_sub_months(Months::new(DEFAULT_DISPUTE_MONTHS))
+            .expect("report range within chrono range"),
+    };
+    if from >= to {
+        return Err(PipelineError::Validation(format!(
+            "empty dispute report range: {from} >= {to}"
+        )));
+    }
+
+    let months = report_repo::get_dispute_stats(pool, from, to).await?;
+    Ok(DisputeReport { from, to, months })
+}
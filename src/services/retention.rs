@@ -0,0 +1,235 @@
+use {
+    crate::{
+        domain::retention::{
+            self, ArchivedBatch, RetainedTable, RetentionPolicy, RetentionReport, TableRetention,
+        },
+        error::PipelineError,
+        infra::{
+            config::RetentionConfig,
+            export_store::ExportStore,
+            postgres::{audit_repo::insert_audit_entry, retention_repo},
+        },
+    },
+    chrono::{DateTime, Utc},
+    serde::Serialize,
+    sqlx::PgPool,
+    std::io::Write,
+    tokio::sync::watch,
+    uuid::Uuid,
+};
+
+const ZSTD_LEVEL: i32 = 3;
+
+/// Archive and delete what each of `config`'s policies has made due, or
+/// with `dry_run` only count it.
+///
+/// Each batch of up to `batch_size` rows is deleted in a transaction that
+/// commits only once the batch is stored, as zstd-compressed NDJSON, where
+/// exports go, and records it in an audit entry; a failure leaves the rows
+/// in place. A dry run that finds rows due is audited too.
+pub async fn run(
+    pool: &PgPool,
+    store: &ExportStore,
+    config: &RetentionConfig,
+    dry_run: bool,
+    actor: &str,
+) -> Result<RetentionReport, PipelineError> {
+    let run_id = Uuid::now_v7();
+    let now = Utc::now();
+    let mut tables = Vec::with_capacity(config.policies.len());
+    for policy in &config.policies {
+        let table = if dry_run {
+            count_due(pool, policy, now).await?
+        } else {
+            archive(pool, store, config, policy, now, run_id, actor).await?
+        };
+        tables.push(table);
+    }
+    let report = RetentionReport {
+        run_id,
+        dry_run,
+        tables,
+    };
+
+    if dry_run && report.rows() > 0 {
+        let mut tx = pool.begin().await?;
+        insert_audit_entry(&mut tx, &retention::dry_run_audit_entry(&report, actor)).await?;
+        tx.commit().await?;
+    }
+    Ok(report)
+}
+
+async fn count_due(
+    pool: &PgPool,
+    policy: &RetentionPolicy,
+    now: DateTime<Utc>,
+) -> Result<TableRetention, PipelineError> {
+    let cutoff = policy.cutoff(now);
+    let (rows, oldest) = retention_repo::due(pool, policy.table, cutoff).await?;
+    Ok(TableRetention {
+        table: policy.table,
+        max_age_days: policy.max_age_days,
+        cutoff,
+        rows: rows as u64,
+        oldest,
+        batches: Vec::new(),
+        more: false,
+    })
+}
+
+async fn archive(
+    pool: &PgPool,
+    store: &ExportStore,
+    config: &RetentionConfig,
+    policy: &RetentionPolicy,
+    now: DateTime<Utc>,
+    run_id: Uuid,
+    actor: &str,
+) -> Result<TableRetention, PipelineError> {
+    let cutoff = policy.cutoff(now);
+    let limit = i64::from(config.batch_size);
+    let mut report = TableRetention {
+        table: policy.table,
+        max_age_days: policy.max_age_days,
+        cutoff,
+        rows: 0,
+        oldest: None,
+        batches: Vec::new(),
+        more: true,
+    };
+
+    for batch in 0..config.max_batches as usize {
+        let mut tx = pool.begin().await?;
+        let taken = match policy.table {
+            RetainedTable::ProviderEvents => {
+                let rows = retention_repo::take_provider_events(&mut tx, cutoff, limit).await?;
+                encode(&rows, |e| e.received_at)?
+            }
+            RetainedTable::AuditLog => {
+                let rows = retention_repo::take_audit_entries(&mut tx, cutoff, limit).await?;
+                encode(&rows, |e| e.created_at)?
+            }
+        };
+        let Some(Encoded {
+            rows,
+            oldest,
+            newest,
+            body,
+        }) = taken
+        else {
+            report.more = false;
+            break;
+        };
+
+        let file_name = retention::archive_file_name(policy.table, oldest, run_id, batch);
+        let location = store_archive(store, &file_name, &body).await?;
+        let archived = ArchivedBatch {
+            rows,
+            oldest,
+            newest,
+            location,
+            bytes: body.len() as u64,
+        };
+        let entry = retention::archived_audit_entry(run_id, policy, cutoff, &archived, actor);
+        insert_audit_entry(&mut tx, &entry).await?;
+        retention_repo::advance_horizon(&mut tx, policy.table, newest).await?;
+        tx.commit().await?;
+
+        tracing::info!(
+            table = %policy.table,
+            rows,
+            location = %archived.location,
+            "retention batch archived"
+        );
+        report.rows += rows;
+        report.batches.push(archived);
+        if rows < limit as u64 {
+            report.more = false;
+            break;
+        }
+    }
+    Ok(report)
+}
+
+/// A batch as its archive file holds it.
+struct Encoded {
+    rows: u64,
+    oldest: DateTime<Utc>,
+    newest: DateTime<Utc>,
+    body: Vec<u8>,
+}
+
+/// `rows`, oldest first, as zstd-compressed NDJSON; `None` if there are none.
+fn encode<R: Serialize>(
+    rows: &[R],
+    at: impl Fn(&R) -> DateTime<Utc>,
+) -> Result<Option<Encoded>, PipelineError> {
+    let (Some(first), Some(last)) = (rows.first(), rows.last()) else {
+        return Ok(None);
+    };
+    let compress = |e: std::io::Error| PipelineError::Storage(format!("compressing archive: {e}"));
+    let mut encoder =
+        zstd::stream::write::Encoder::new(Vec::new(), ZSTD_LEVEL).map_err(compress)?;
+    for row in rows {
+        serde_json::to_writer(&mut encoder, row)?;
+        encoder.write_all(b"\n").map_err(compress)?;
+    }
+    Ok(Some(Encoded {
+        rows: rows.len() as u64,
+        oldest: at(first),
+        newest: at(last),
+        body: encoder.finish().map_err(compress)?,
+    }))
+}
+
+async fn store_archive(
+    store: &ExportStore,
+    file_name: &str,
+    body: &[u8],
+) -> Result<String, PipelineError> {
+    let staged = store.staging_path(file_name).await?;
+    let published = async {
+        tokio::fs::write(&staged, body)
+            .await
+            .map_err(|e| PipelineError::Storage(format!("{}: {e}", staged.display())))?;
+        store.publish(file_name, &staged).await
+    }
+    .await;
+    if published.is_err() {
+        let _ = tokio::fs::remove_file(&staged).await;
+    }
+    published
+}
+
+/// Every `interval` (hourly by default), archive and prune what
+/// `config`'s policies have made due. Replicas running at once split the
+/// rows between them.
+pub async fn run_retention(
+    pool: PgPool,
+    store: ExportStore,
+    config: RetentionConfig,
+    interval: std::time::Duration,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    tracing::info!(policies = ?config.policies, dry_run = config.dry_run, "retention started");
+
+    loop {
+        tokio::select! {
+            _ = shutdown.changed() => {
+                tracing::info!("retention shutting down");
+                return;
+            }
+            _ = tokio::time::sleep(interval) => {}
+        }
+
+        match run(&pool, &store, &config, config.dry_run, "scheduler").await {
+            Ok(report) if report.rows() > 0 => tracing::info!(
+                rows = report.rows(),
+                dry_run = report.dry_run,
+                "retention run finished"
+            ),
+            Ok(_) => {}
+            Err(e) => tracing::error!(error = %e, "retention run error"),
+        }
+    }
+}
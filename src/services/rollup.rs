@@ -0,0 +1,135 @@
+use {
+    crate::{
+        domain::rollup::{BucketSize, RollupKind, RollupSpec, RollupWindow},
+        error::PipelineError,
+        infra::postgres::{retention_repo, rollup_repo},
+    },
+    chrono::{DateTime, Utc},
+    serde::Serialize,
+    sqlx::PgPool,
+    tokio::sync::watch,
+};
+
+#[derive(Debug, Serialize)]
+pub struct RollupRun {
+    pub rollup: RollupKind,
+    pub bucket: BucketSize,
+    pub window: RollupWindow,
+    pub rows_written: u64,
+    pub rows_purged: u64,
+}
+
+/// Incremental run: recompute from the stored watermark up to `now`,
+/// purge buckets past retention, then advance the watermark.
+pub async fn run_incremental(
+    pool: &PgPool,
+    spec: &RollupSpec,
+    now: DateTime<Utc>,
+) -> Result<RollupRun, PipelineError> {
+    let mut tx = pool.begin().await?;
+    rollup_repo::lock(&mut tx, spec.kind, spec.bucket).await?;
+
+    let watermark = rollup_repo::get_watermark(&mut tx, spec.kind, spec.bucket).await?;
+    let mut window = spec.incremental_window(watermark, now);
+    if let Some(table) = spec.kind.source_table()
+        && let Some(through) = retention_repo::horizon(&mut tx, table).await?
+    {
+        window = window.after_pruned(spec.bucket, through);
+    }
+    let rows_written = rollup_repo::recompute(&mut tx, spec.kind, spec.bucket, window).await?;
+    let rows_purged = rollup_repo::purge_range(
+        &mut tx,
+        spec.kind,
+        spec.bucket,
+        None,
+        spec.retention_cutoff(now),
+    )
+    .await?;
+    rollup_repo::set_watermark(&mut tx, spec.kind, spec.bucket, now).await?;
+    tx.commit().await?;
+
+    Ok(RollupRun {
+        rollup: spec.kind,
+        bucket: spec.bucket,
+        window,
+        rows_written,
+        rows_purged,
+    })
+}
+
+/// Rebuild an explicit window, e.g. after a data fix. The window is widened
+/// to whole buckets; the watermark is left alone. Refused for a window
+/// reaching into source rows retention has pruned.
+pub async fn recompute_window(
+    pool: &PgPool,
+    kind: RollupKind,
+    bucket: BucketSize,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<RollupRun, PipelineError> {
+    if from >= to {
+        return Err(PipelineError::Validation(format!(
+            "empty recompute window: {from} >= {to}"
+        )));
+    }
+
+    let window = RollupWindow::aligned(bucket, from, to);
+    let mut tx = pool.begin().await?;
+    rollup_repo::lock(&mut tx, kind, bucket).await?;
+    if let Some(table) = kind.source_table()
+        && let Some(through) = retention_repo::horizon(&mut tx, table).await?
+        && window.after_pruned(bucket, through) != window
+    {
+        return Err(PipelineError::Validation(format!(
+            "{table} is pruned through {through}; {kind} can't be recomputed before then"
+        )));
+    }
+    let rows_written = rollup_repo::recompute(&mut tx, kind, bucket, window).await?;
+    tx.commit().await?;
+
+    Ok(RollupRun {
+        rollup: kind,
+        bucket,
+        window,
+        rows_written,
+        rows_purged: 0,
+    })
+}
+
+/// Run every configured rollup incrementally, every `interval`.
+pub async fn run_rollups(
+    pool: PgPool,
+    specs: Vec<RollupSpec>,
+    interval: std::time::Duration,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    tracing::info!("rollup scheduler started");
+
+    loop {
+        tokio::select! {
+            _ = shutdown.changed() => {
+                tracing::info!("rollup scheduler shutting down");
+                return;
+            }
+            _ = tokio::time::sleep(interval) => {}
+        }
+
+        for spec in &specs {
+            match run_incremental(&pool, spec, Utc::now()).await {
+                Ok(run) => tracing::debug!(
+                    rollup = %run.rollup,
+                    bucket = %run.bucket,
+                    written = run.rows_written,
+                    purged = run.rows_purged,
+                    "rollup refreshed"
+                ),
+                Err(e) => tracing::error!(
+                    rollup = %spec.kind,
+                    bucket = %spec.bucket,
+                    error = %e,
+                    "rollup error"
+                ),
+            }
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use {
+    crate::{
+        domain::shadow::ShadowReport, error::PipelineError, infra::postgres::shadow_repo,
+        services::config::RuntimeConfigHandle,
+    },
+    sqlx::PgPool,
+    std::time::Duration,
+    tokio::sync::watch,
+};
+
+/// Payments copied or compared per query.
+const BATCH: i64 = 500;
+
+/// Copy every payment `payments_v2` doesn't have yet. Only once writes are
+/// mirrored: a copy taken with the stage off would go stale with the next
+/// event. Safe to rerun or to run while serving. Returns how many were copied.
+pub async fn backfill(pool: &PgPool) -> Result<u64, PipelineError> {
+    if !shadow_repo::get_stage(pool).await?.writes_new() {
+        return Err(PipelineError::Validation(
+            "payments_v2 is off; set it to dual_write before backfilling".into(),
+        ));
+    }
+    let mut total = 0;
+    let mut after = String::new();
+    loop {
+        let (copied, last) = shadow_repo::backfill(pool, &after, BATCH).await?;
+        total += copied;
+        match last {
+            Some(last) => after = last,
+            None => {
+                tracing::info!(copied = total, "payments_v2 backfill finished");
+                return Ok(total);
+            }
+        }
+    }
+}
+
+/// Compare `payments` with `payments_v2` row by row. A clean report means
+/// reads can move to `payments_v2`.
+pub async fn verify(pool: &PgPool) -> Result<ShadowReport, PipelineError> {
+    let mut report = ShadowReport::default();
+    let mut after = String::new();
+    loop {
+        let rows = shadow_repo::compare(pool, &after, BATCH).await?;
+        let Some(last) = rows.last() else {
+            return Ok(report);
+        };
+        after = last.external_id.clone();
+        for row in rows {
+            report.record(row);
+        }
+    }
+}
+
+/// Every `interval`, verify `payments_v2` while writes are mirrored into it.
+pub async fn run_shadow_verifier(
+    pool: PgPool,
+    config: RuntimeConfigHandle,
+    interval: Duration,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    tracing::info!("payments_v2 verifier started");
+
+    loop {
+        tokio::select! {
+            _ = shutdown.changed() => {
+                tracing::info!("payments_v2 verifier shutting down");
+                return;
+            }
+            _ = tokio::time::sleep(interval) => {}
+        }
+
+        if !config.current().config.payments_v2.writes_new() {
+            continue;
+        }
+        match verify(&pool).await {
+            Ok(report) if report.is_clean() => {
+                tracing::info!(checked = report.checked, "payments_v2 matches payments")
+            }
+            Ok(report) => tracing::warn!(
+                checked = report.checked,
+                missing_in_new = report.missing_in_new,
+                missing_in_old = report.missing_in_old,
+                differing = report.differing,
+                "payments_v2 differs from payments"
+            ),
+            Err(e) => tracing::error!(error = %e, "payments_v2 verification error"),
+        }
+    }
+}
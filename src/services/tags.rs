@@ -0,0 +1,150 @@
+use {
+    crate::{
+        domain::{
+            id::ExternalId,
+            payment::PaymentPageView,
+            tag::{SavedFilter, SavedFilterPage, SavedFilterRequest, TagChange, TagRequest},
+        },
+        error::PipelineError,
+        infra::postgres::{audit_repo::insert_audit_entry, payment_repo, tag_repo},
+        services::payment::lookup::get_payment_list,
+    },
+    sqlx::PgPool,
+    std::collections::HashMap,
+};
+
+/// Add and remove tags on payment `id` in one go. Audited when anything
+/// changed. `None` if the payment doesn't exist.
+pub async fn tag_payment(
+    pool: &PgPool,
+    id: &ExternalId,
+    request: &TagRequest,
+    actor: &str,
+) -> Result<Option<TagChange>, PipelineError> {
+    request.validate()?;
+    let add: Vec<String> = request.add.iter().map(|t| t.as_str().to_owned()).collect();
+    let remove: Vec<String> = request
+        .remove
+        .iter()
+        .map(|t| t.as_str().to_owned())
+        .collect();
+
+    let mut tx = pool.begin().await?;
+    let Some(payment_id) = payment_repo::find_payment_id(&mut tx, id.as_str()).await? else {
+        return Ok(None);
+    };
+    let mut added = tag_repo::add_tags(&mut tx, payment_id, &add, actor).await?;
+    let mut removed = tag_repo::remove_tags(&mut tx, payment_id, &remove).await?;
+    added.sort();
+    removed.sort();
+    let change = TagChange {
+        external_id: id.as_str().to_owned(),
+        added,
+        removed,
+        tags: tag_repo::tags_in_tx(&mut tx, payment_id).await?,
+    };
+    if !change.is_empty() {
+        let tenant_id = payment_repo::find_tenant_id(&mut tx, id.as_str())
+            .await?
+            .flatten();
+        insert_audit_entry(&mut tx, &change.audit_entry(payment_id, tenant_id, actor)).await?;
+    }
+    tx.commit().await?;
+
+    tracing::info!(
+        external_id = %id,
+        actor,
+        added = ?change.added,
+        removed = ?change.removed,
+        "payment tags changed"
+    );
+    Ok(Some(change))
+}
+
+/// Payment `id`'s tags, sorted. `None` if the payment doesn't exist.
+pub async fn get_payment_tags(
+    pool: &PgPool,
+    id: &ExternalId,
+) -> Result<Option<Vec<String>>, PipelineError> {
+    if !payment_repo::payment_exists(pool, id.as_str()).await? {
+        return Ok(None);
+    }
+    Ok(Some(tag_repo::get_payment_tags(pool, id.as_str()).await?))
+}
+
+/// The tags of each of `external_ids`, sorted, in one query. Payments
+/// without any, or that don't exist, are left out.
+pub async fn get_tags_of(
+    pool: &PgPool,
+    external_ids: &[String],
+) -> Result<HashMap<String, Vec<String>>, PipelineError> {
+    let mut tags: HashMap<String, Vec<String>> = HashMap::new();
+    for (external_id, tag) in tag_repo::get_tags_of(pool, external_ids).await? {
+        tags.entry(external_id).or_default().push(tag);
+    }
+    Ok(tags)
+}
+
+pub async fn list_saved_filters(pool: &PgPool) -> Result<Vec<SavedFilter>, PipelineError> {
+    tag_repo::list_saved_filters(pool).await
+}
+
+pub async fn get_saved_filter(
+    pool: &PgPool,
+    name: &str,
+) -> Result<Option<SavedFilter>, PipelineError> {
+    tag_repo::get_saved_filter(pool, name).await
+}
+
+/// Create or replace saved filter `name`. Audited.
+pub async fn save_filter(
+    pool: &PgPool,
+    name: &str,
+    request: &SavedFilterRequest,
+    actor: &str,
+) -> Result<SavedFilter, PipelineError> {
+    request.validate(name)?;
+    let mut tx = pool.begin().await?;
+    let filter = tag_repo::upsert_saved_filter(
+        &mut tx,
+        name,
+        request.description.trim(),
+        &request.filters,
+        actor,
+    )
+    .await?;
+    insert_audit_entry(&mut tx, &filter.audit_entry("saved_filter_saved", actor)).await?;
+    tx.commit().await?;
+    tracing::info!(name, actor, "saved filter saved");
+    Ok(filter)
+}
+
+/// Delete saved filter `name`. Audited. `None` if it doesn't exist.
+pub async fn delete_filter(
+    pool: &PgPool,
+    name: &str,
+    actor: &str,
+) -> Result<Option<SavedFilter>, PipelineError> {
+    let mut tx = pool.begin().await?;
+    let Some(filter) = tag_repo::delete_saved_filter(&mut tx, name).await? else {
+        return Ok(None);
+    };
+    insert_audit_entry(&mut tx, &filter.audit_entry("saved_filter_deleted", actor)).await?;
+    tx.commit().await?;
+    tracing::info!(name, actor, "saved filter deleted");
+    Ok(Some(filter))
+}
+
+/// One page of the payments saved filter `name` selects, as `GET /payments`
+/// would list them. `None` if there is no such filter.
+pub async fn run_saved_filter(
+    pool: &PgPool,
+    name: &str,
+    page: SavedFilterPage,
+) -> Result<Option<PaymentPageView>, PipelineError> {
+    let Some(filter) = tag_repo::get_saved_filter(pool, name).await? else {
+        return Ok(None);
+    };
+    let filters = filter.payment_filters(page)?;
+    Ok(Some(get_payment_list(pool, filters).await?))
+}
@@ -0,0 +1,34 @@
+use {
+    crate::{
+        domain::{payment::PaymentDirection, vector::TestVector},
+        error::PipelineError,
+        infra::postgres::vector_repo,
+    },
+    sqlx::PgPool,
+};
+
+pub const DEFAULT_LIMIT: i64 = 20;
+pub const MAX_LIMIT: i64 = 200;
+
+/// Test vectors from the histories of payments that hit an anomaly, newest
+/// first, in the corpus format (`tests/vectors/`). Named by position only;
+/// nothing identifying leaves the database. Payments whose history can't be
+/// replayed (the creating event is missing) are skipped.
+pub async fn export_anomaly_vectors(
+    pool: &PgPool,
+    limit: Option<i64>,
+) -> Result<Vec<TestVector>, PipelineError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let payments = vector_repo::anomalous_payments(pool, limit).await?;
+
+    let mut vectors = Vec::with_capacity(payments.len());
+    for payment in payments {
+        let direction = PaymentDirection::try_from(payment.direction.as_str())?;
+        let history = vector_repo::event_history(pool, &payment.external_id).await?;
+        let name = format!("production_anomaly_{}", vectors.len() + 1);
+        if let Some(vector) = TestVector::from_history(name, payment.source, direction, &history) {
+            vectors.push(vector);
+        }
+    }
+    Ok(vectors)
+}
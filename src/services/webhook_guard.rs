@@ -0,0 +1,92 @@
+use {
+    crate::{
+        domain::webhook::{ReplayRejection, SignedDelivery},
+        error::PipelineError,
+        infra::postgres::{audit_repo::insert_audit_entry, pressure, webhook_repo},
+        services::config::RuntimeConfigHandle,
+    },
+    chrono::Utc,
+    sqlx::PgPool,
+};
+
+/// Replay protection on top of the provider's own signature check. Call
+/// after the signature is verified: deliveries whose signature timestamp is
+/// more than `webhook_max_age_secs` from now, or whose signature was already
+/// accepted, are rejected before anything is enqueued.
+///
+/// Rejections are logged under the `security` target so they can be
+/// monitored apart from ordinary signature failures, and counted on the
+/// signature's row. Only the first rejection of a signature is audited, as
+/// `replay_rejected`: replaying one captured delivery over and over adds
+/// to its count, not to the audit log.
+pub async fn admit(
+    pool: &PgPool,
+    config: &RuntimeConfigHandle,
+    delivery: &SignedDelivery,
+) -> Result<(), PipelineError> {
+    let now = Utc::now();
+    let max_age = config.current().config.webhook_max_age();
+
+    // The first connection a delivery takes: its wait tells the load
+    // shedder how ingestion is faring.
+    let verdict = match delivery.check_age(now, max_age) {
+        Ok(())
+            if webhook_repo::remember(&mut *pressure::acquire(pool).await?, delivery).await? =>
+        {
+            Ok(())
+        }
+        Ok(()) => Err(ReplayRejection::Replayed),
+        Err(r) => Err(r),
+    };
+
+    let Err(rejection) = verdict else {
+        return Ok(());
+    };
+    // The delivery is refused either way; a failed count or audit write
+    // must not turn that into a 500 the provider would retry.
+    let rejections = match record_rejection(pool, delivery, rejection).await {
+        Ok(n) => Some(n),
+        Err(e) => {
+            tracing::error!(source = delivery.source, error = %e, "failed to record replay rejection");
+            None
+        }
+    };
+    tracing::warn!(
+        target: "security",
+        source = delivery.source,
+        rejection = rejection.as_str(),
+        signed_at = %delivery.signed_at,
+        age_secs = delivery.age(now).num_seconds(),
+        rejections,
+        "webhook delivery rejected as replay"
+    );
+    Err(rejection.into())
+}
+
+/// Count the rejection, auditing it if it's the signature's first.
+async fn record_rejection(
+    pool: &PgPool,
+    delivery: &SignedDelivery,
+    rejection: ReplayRejection,
+) -> Result<i32, PipelineError> {
+    let rejections = webhook_repo::record_rejection(pool, delivery).await?;
+    if rejections > 1 {
+        return Ok(rejections);
+    }
+    let mut tx = pool.begin().await?;
+    insert_audit_entry(
+        &mut tx,
+        &delivery.rejection_audit_entry(rejection, Utc::now()),
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(rejections)
+}
+
+/// Undo [`admit`] for a delivery that failed downstream, so the provider
+/// can redeliver it.
+pub async fn release(pool: &PgPool, delivery: &SignedDelivery) {
+    if let Err(e) = webhook_repo::forget(pool, delivery).await {
+        tracing::error!(source = delivery.source, error = %e, "failed to release webhook signature");
+    }
+}
@@ -0,0 +1,238 @@
+use {
+    crate::{
+        adapters::stripe::{client::StripeProvider, router::EventRouter},
+        domain::{
+            audit::NewAuditEntry,
+            webhook_endpoint::{
+                self, EndpointSetup, EndpointTarget, SecretRotation, SetupAction, SigningSecret,
+                WebhookSecrets,
+            },
+        },
+        error::PipelineError,
+        infra::{
+            postgres::audit_repo::insert_audit_entry,
+            settings::{SettingKey, Settings},
+        },
+    },
+    chrono::Utc,
+    std::time::Duration,
+};
+
+/// The managed endpoints and their secrets.
+pub const WEBHOOK_SECRETS: SettingKey<WebhookSecrets> =
+    SettingKey::secret("stripe.webhook_secrets");
+
+/// The secrets Stripe deliveries may be signed with right now, newest
+/// first; `configured` is `STRIPE_WEBHOOK_SECRET`. Read through the
+/// settings cache, so a rotation on any replica is picked up as soon as
+/// its notification arrives.
+pub async fn signing_secrets(
+    settings: &Settings,
+    configured: &str,
+) -> Result<Vec<SigningSecret>, PipelineError> {
+    let secrets = settings.get_or_default(&WEBHOOK_SECRETS).await?;
+    let now = Utc::now();
+    Ok(secrets
+        .signing_secrets(configured)
+        .into_iter()
+        .filter(|s| s.valid_at(now))
+        .collect())
+}
+
+/// `url`, sent every event `router` does something with.
+pub fn target(url: &str, router: &EventRouter) -> EndpointTarget {
+    EndpointTarget {
+        url: url.to_string(),
+        enabled_events: router
+            .enabled_events()
+            .into_iter()
+            .map(str::to_string)
+            .collect(),
+    }
+}
+
+/// Make Stripe send `target`'s events to its URL: update the current
+/// endpoint if it differs, or, the first time, take over an endpoint
+/// already there for the URL (whose secret is `STRIPE_WEBHOOK_SECRET`) or
+/// create one. Endpoints rotated out and past their overlap are deleted.
+/// Audited; safe to run on every deploy. Holds the secrets locked from
+/// read to write, so a concurrent setup or rotation, on any replica, waits
+/// rather than overwriting the version this one adds.
+pub async fn sync_endpoint(
+    settings: &Settings,
+    stripe: &StripeProvider,
+    target: &EndpointTarget,
+    actor: &str,
+) -> Result<EndpointSetup, PipelineError> {
+    let mut lock = settings.lock(&WEBHOOK_SECRETS).await?;
+    let mut secrets = lock.get()?.unwrap_or_default();
+    let mut audit = Vec::new();
+    let retired = retire_expired(&mut secrets, stripe, actor, &mut audit).await?;
+    let mut changed = !retired.is_empty();
+
+    let (action, endpoint) = match secrets.current() {
+        Some(current) => {
+            let endpoint = stripe.webhook_endpoint(&current.endpoint_id).await?;
+            if target.matches(&endpoint) {
+                (SetupAction::Unchanged, endpoint)
+            } else {
+                let endpoint = stripe.update_webhook_endpoint(&endpoint.id, target).await?;
+                (SetupAction::Updated, endpoint)
+            }
+        }
+        None => {
+            let existing = stripe
+                .webhook_endpoints()
+                .await?
+                .into_iter()
+                .find(|e| e.url == target.url);
+            let (action, endpoint) = match existing {
+                Some(e) if target.matches(&e) => (SetupAction::Adopted, e),
+                Some(e) => (
+                    SetupAction::Adopted,
+                    stripe.update_webhook_endpoint(&e.id, target).await?,
+                ),
+                None => (
+                    SetupAction::Created,
+                    stripe.create_webhook_endpoint(target).await?,
+                ),
+            };
+            secrets.push(
+                &endpoint.id,
+                endpoint.secret.clone(),
+                Utc::now(),
+                chrono::Duration::zero(),
+            );
+            changed = true;
+            (action, endpoint)
+        }
+    };
+    let secret_version = secrets.current().map_or(0, |v| v.version);
+    if action != SetupAction::Unchanged {
+        audit.push(webhook_endpoint::audit_entry(
+            &format!("webhook_endpoint_{}", action.as_str()),
+            &endpoint.id,
+            serde_json::json!({
+                "url": endpoint.url,
+                "enabled_events": endpoint.enabled_events,
+                "secret_version": secret_version,
+            }),
+            actor,
+        ));
+    }
+    record(lock.tx(), &audit).await?;
+    if changed {
+        lock.set(&secrets, actor).await?;
+    } else {
+        lock.commit().await?;
+    }
+    tracing::info!(
+        action = action.as_str(),
+        endpoint_id = %endpoint.id,
+        events = endpoint.enabled_events.len(),
+        "stripe webhook endpoint set up"
+    );
+
+    Ok(EndpointSetup {
+        action,
+        endpoint_id: endpoint.id,
+        url: endpoint.url,
+        enabled_events: endpoint.enabled_events,
+        secret_version,
+        retired,
+    })
+}
+
+/// Move to a new signing secret without losing deliveries. Stripe only
+/// rolls a secret from its dashboard, so a new endpoint is created for the
+/// same URL and events, and its secret stored as the next version. The old
+/// endpoint keeps delivering for `overlap` (each event then comes twice,
+/// once per endpoint, and the second is a duplicate) and is deleted by the
+/// first setup or rotation after that. Locked like `sync_endpoint`.
+pub async fn rotate_secret(
+    settings: &Settings,
+    stripe: &StripeProvider,
+    target: &EndpointTarget,
+    overlap: Duration,
+    actor: &str,
+) -> Result<SecretRotation, PipelineError> {
+    let mut lock = settings.lock(&WEBHOOK_SECRETS).await?;
+    let mut secrets = lock.get()?.unwrap_or_default();
+    let mut audit = Vec::new();
+    let retired = retire_expired(&mut secrets, stripe, actor, &mut audit).await?;
+    let Some(previous) = secrets.current().map(|v| v.endpoint_id.clone()) else {
+        return Err(PipelineError::Validation(
+            "no webhook endpoint set up yet; run the setup first".into(),
+        ));
+    };
+
+    let endpoint = stripe.create_webhook_endpoint(target).await?;
+    let now = Utc::now();
+    let overlap = chrono::Duration::from_std(overlap)
+        .map_err(|e| PipelineError::Validation(format!("overlap: {e}")))?;
+    let secret_version = secrets.push(&endpoint.id, endpoint.secret.clone(), now, overlap);
+    let previous_retires_at = now + overlap;
+    audit.push(webhook_endpoint::audit_entry(
+        "webhook_secret_rotated",
+        &endpoint.id,
+        serde_json::json!({
+            "url": endpoint.url,
+            "secret_version": secret_version,
+            "previous_endpoint_id": previous,
+            "previous_retires_at": previous_retires_at,
+        }),
+        actor,
+    ));
+    record(lock.tx(), &audit).await?;
+    lock.set(&secrets, actor).await?;
+    tracing::warn!(
+        endpoint_id = %endpoint.id,
+        secret_version,
+        previous_endpoint_id = %previous,
+        %previous_retires_at,
+        "stripe webhook secret rotated"
+    );
+
+    Ok(SecretRotation {
+        endpoint_id: endpoint.id,
+        secret_version,
+        previous_endpoint_id: previous,
+        previous_retires_at,
+        retired,
+    })
+}
+
+/// Delete the endpoints past their overlap, returning their ids. One
+/// already gone at Stripe counts as deleted.
+async fn retire_expired(
+    secrets: &mut WebhookSecrets,
+    stripe: &StripeProvider,
+    actor: &str,
+    audit: &mut Vec<NewAuditEntry>,
+) -> Result<Vec<String>, PipelineError> {
+    let mut retired = Vec::new();
+    for version in secrets.take_retired(Utc::now()) {
+        match stripe.delete_webhook_endpoint(&version.endpoint_id).await {
+            Ok(()) | Err(PipelineError::ProviderMissing(_)) => {}
+            Err(e) => return Err(e),
+        }
+        audit.push(webhook_endpoint::audit_entry(
+            "webhook_endpoint_retired",
+            &version.endpoint_id,
+            serde_json::json!({"secret_version": version.version}),
+            actor,
+        ));
+        retired.push(version.endpoint_id);
+    }
+    Ok(retired)
+}
+
+async fn record(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    entries: &[NewAuditEntry],
+) -> Result<(), PipelineError> {
+    for entry in entries {
+        insert_audit_entry(tx, entry).await?;
+    }
+    Ok(())
+}
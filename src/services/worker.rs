@@ -1,106 +1,697 @@
 use {
-    crate::domain::error::PipelineError,
-    crate::domain::id::{EventId, ExternalId},
-    crate::domain::payment::PaymentTrigger,
-    crate::domain::provider::PaymentProvider,
-    crate::infra::postgres::job_repo,
-    crate::services::payment::pipeline::fetch_and_process_payment,
-    sqlx::PgPool,
-    std::sync::Arc,
+    crate::domain::config::RuntimeConfig,
+    crate::domain::id::{EventId, ExternalId, TenantId},
+    crate::domain::job::{JobLane, MissingObject},
+    crate::domain::payment::{PaymentTrigger, ProcessResult},
+    crate::domain::provider::{FetchedPayment, ProviderRegistry},
+    crate::domain::reconciliation::DiscrepancyKind,
+    crate::error::PipelineError,
+    crate::infra::postgres::{
+        audit_repo, followup_repo,
+        job_repo::{self, JobRow},
+        payment_repo, pressure, reconciliation_repo, webhook_repo,
+    },
+    crate::infra::{redact::redacted, settings::Settings, telemetry},
+    crate::retry::{Backoff, Clock, Jitter, Policy, TokioClock, Verdict},
+    crate::services::config::RuntimeConfigHandle,
+    crate::services::payment::pipeline::process_fetched_payment,
+    crate::services::{fetch_cache::FetchCache, followup, maintenance},
+    chrono::Utc,
+    futures_util::{StreamExt, stream},
+    sqlx::{PgPool, postgres::PgListener},
+    std::{collections::HashMap, fmt, time::Duration},
     tokio::sync::watch,
+    tracing::Instrument,
+    uuid::Uuid,
 };
 
-/// Poll for pending jobs and process them via the existing payment pipeline.
+/// Failed jobs are due again 2s after the first failure, doubling after
+/// each one, for 5 attempts in all; errors the provider will give again
+/// (a request it rejects) fail the job at once. A job refused by an
+/// exhausted API budget isn't a failure: it is deferred to the end of the
+/// budget's window instead.
+const RETRY: Policy<PipelineError> = Policy {
+    max_attempts: 5,
+    backoff: Backoff::Exponential(Duration::from_secs(2)),
+    max_delay: Duration::from_secs(3600),
+    jitter: Jitter::None,
+    retryable: |e| match e {
+        PipelineError::ProviderRejected(_) => Verdict::GiveUp,
+        _ => Verdict::Retry,
+    },
+};
+
+/// Which worker process claimed a job: `<hostname>/<instance id>`. The
+/// instance id is fresh on every start, so restarts on the same host are
+/// told apart.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkerIdentity {
+    pub hostname: String,
+    pub instance_id: String,
+}
+
+impl WorkerIdentity {
+    /// Hostname from `$HOSTNAME` (the pod name on Kubernetes) or
+    /// `/etc/hostname`, plus a random instance id.
+    pub fn detect() -> Self {
+        let hostname = std::env::var("HOSTNAME")
+            .ok()
+            .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+            .map(|h| h.trim().to_string())
+            .filter(|h| !h.is_empty())
+            .unwrap_or_else(|| "unknown".to_string());
+        let uuid = Uuid::now_v7().simple().to_string();
+        // The tail of a v7 uuid is random; the head is the timestamp.
+        let instance_id = uuid[uuid.len() - 8..].to_string();
+        Self {
+            hostname,
+            instance_id,
+        }
+    }
+}
+
+impl fmt::Display for WorkerIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.hostname, self.instance_id)
+    }
+}
+
+/// Process pending jobs of `lane` via the existing payment pipeline,
+/// fetching from the provider matching each job's source. The worker keeps
+/// polling while batches come back full, then sleeps until a notification
+/// for its lane arrives (see `job_repo::listen`) or its pause runs out,
+/// which still picks up retries coming due and covers a lost listener. The
+/// pause starts at the lane's poll interval (see `RuntimeConfig::lane_worker`)
+/// and doubles after every wakeup that claims nothing, up to
+/// `worker_idle_poll_max_ms`, so a quiet queue costs few queries. Batch
+/// size, concurrency and pauses are re-read from `config` on every wakeup.
+/// Fetched objects are kept for `provider_cache_ttl_secs` for later
+/// batches, see `FetchCache`. The standard lane's worker also runs due
+/// follow-ups (see `services::followup`) once its jobs are drained.
+/// Claimed jobs are stamped with `identity`, which also tags every log line.
+///
+/// While maintenance mode is on (see `services::maintenance`, read through
+/// `settings`) the worker claims nothing; it looks again on every wakeup.
+///
+/// On shutdown the worker stops claiming and lets the batch in hand finish,
+/// for up to `worker_drain_timeout_secs`. Whatever it still holds after
+/// that is put back to `pending` before it returns, for another replica to
+/// pick up straight away.
 pub async fn run_worker(
     pool: PgPool,
-    provider: Arc<dyn PaymentProvider>,
+    settings: Settings,
+    providers: ProviderRegistry,
+    config: RuntimeConfigHandle,
+    identity: WorkerIdentity,
+    lane: JobLane,
+    shutdown: watch::Receiver<bool>,
+) {
+    run_worker_with_clock(
+        pool, settings, providers, config, identity, lane, shutdown, TokioClock,
+    )
+    .await
+}
+
+/// [`run_worker`], pausing between polls on `clock`.
+#[tracing::instrument(
+    name = "worker",
+    skip_all,
+    fields(worker = %identity, lane = lane.as_str())
+)]
+#[allow(clippy::too_many_arguments)]
+pub async fn run_worker_with_clock(
+    pool: PgPool,
+    settings: Settings,
+    providers: ProviderRegistry,
+    config: RuntimeConfigHandle,
+    identity: WorkerIdentity,
+    lane: JobLane,
     mut shutdown: watch::Receiver<bool>,
+    clock: impl Clock + Send + Sync,
 ) {
     tracing::info!("job worker started");
+    let claimed_by = identity.to_string();
+    let mut listener = None;
+    let mut paused = false;
+    let mut cache = FetchCache::default();
+    let mut backoff = IdleBackoff::default();
 
     loop {
-        tokio::select! {
-            _ = shutdown.changed() => {
-                tracing::info!("job worker shutting down");
-                return;
+        if listener.is_none() {
+            listener = match job_repo::listen(&pool).await {
+                Ok(l) => Some(l),
+                Err(e) => {
+                    tracing::warn!(error = %e, "job notifications unavailable, polling only");
+                    None
+                }
+            };
+        }
+
+        let cfg = config.current();
+        let (concurrency, poll_interval) = cfg.config.lane_worker(lane);
+        let in_maintenance = match maintenance::active(&settings).await {
+            Ok(window) => window.is_some(),
+            Err(e) => {
+                tracing::error!(error = %e, "maintenance check failed");
+                paused
+            }
+        };
+        if in_maintenance != paused {
+            paused = in_maintenance;
+            if paused {
+                tracing::warn!("maintenance mode, worker paused");
+            } else {
+                tracing::info!("maintenance over, worker resumed");
             }
-            _ = tokio::time::sleep(std::time::Duration::from_secs(1)) => {}
         }
+        let work = async {
+            let mut claimed_total = 0;
+            if paused {
+                return claimed_total;
+            }
+            // Keep claiming until a batch comes back empty, so a burst
+            // drains without waiting for further wakeups. Batches are cut
+            // between objects, so a short one doesn't mean none are left.
+            while !*shutdown.borrow() {
+                match poll_once(
+                    &pool,
+                    &providers,
+                    &mut cache,
+                    &cfg.config,
+                    &claimed_by,
+                    lane,
+                    concurrency,
+                )
+                .await
+                {
+                    Ok(claimed) => {
+                        claimed_total += claimed;
+                        if claimed == 0 {
+                            break;
+                        }
+                    }
+                    Err(e) => {
+                        tracing::error!(error = %e, "worker poll error");
+                        break;
+                    }
+                }
+            }
+            while lane == JobLane::Standard && !*shutdown.borrow() {
+                match followup::run_due(
+                    &pool,
+                    &providers,
+                    &claimed_by,
+                    cfg.config.worker_batch_size,
+                    concurrency,
+                )
+                .await
+                {
+                    Ok(claimed) => {
+                        claimed_total += claimed;
+                        if claimed as i64 != cfg.config.worker_batch_size {
+                            break;
+                        }
+                    }
+                    Err(e) => {
+                        tracing::error!(error = %e, "follow-up poll error");
+                        break;
+                    }
+                }
+            }
+            claimed_total
+        };
+        let claimed = tokio::select! {
+            claimed = work => claimed,
+            _ = drain_deadline(shutdown.clone(), cfg.config.worker_drain_timeout()) => {
+                tracing::warn!("in-flight jobs not finished in time, giving them up");
+                break;
+            }
+        };
 
-        if let Err(e) = poll_once(&pool, &*provider).await {
-            tracing::error!(error = %e, "worker poll error");
+        // While paused, keep to the poll interval to notice the end of
+        // maintenance promptly.
+        let busy = paused || claimed > 0;
+        let pause = backoff.next(busy, poll_interval, cfg.config.worker_idle_poll_max());
+        let woken = tokio::select! {
+            _ = shutdown.changed() => break,
+            _ = clock.sleep(pause) => Ok(()),
+            notified = next_notification(&mut listener, lane) => notified,
+        };
+        if let Err(e) = woken {
+            tracing::warn!(error = %e, "job listener lost, reconnecting");
+            listener = None;
         }
     }
+
+    release_claimed(&pool, lane, &claimed_by).await;
+    tracing::info!("job worker shut down");
 }
 
-async fn poll_once(pool: &PgPool, provider: &dyn PaymentProvider) -> Result<(), PipelineError> {
-    let mut tx = pool.begin().await?;
-    let jobs = job_repo::claim(&mut tx, 10).await?;
+/// The worker's pause between polls: `base` after a wakeup that was busy
+/// and after the first idle one, doubling with each further idle wakeup,
+/// capped at `max` (or `base`, if that's longer).
+#[derive(Debug, Default)]
+struct IdleBackoff {
+    idle: u32,
+}
+
+impl IdleBackoff {
+    fn next(&mut self, busy: bool, base: Duration, max: Duration) -> Duration {
+        self.idle = if busy { 0 } else { self.idle.saturating_add(1) };
+        let doublings = self.idle.saturating_sub(1).min(31);
+        base.saturating_mul(1 << doublings).min(max.max(base))
+    }
+}
+
+/// Resolves `drain` after shutdown is signalled, or the sender dropped.
+async fn drain_deadline(mut shutdown: watch::Receiver<bool>, drain: Duration) {
+    let _ = shutdown.wait_for(|stop| *stop).await;
+    tokio::time::sleep(drain).await;
+}
+
+/// Put back the jobs (and, on the standard lane, follow-ups) this worker
+/// still holds, rather than leave them to the reaper.
+async fn release_claimed(pool: &PgPool, lane: JobLane, claimed_by: &str) {
+    match job_repo::release(pool, lane, claimed_by).await {
+        Ok(0) => {}
+        Ok(n) => tracing::info!(count = n, "released claimed jobs"),
+        Err(e) => tracing::error!(error = %e, "failed to release claimed jobs"),
+    }
+    if lane != JobLane::Standard {
+        return;
+    }
+    match followup_repo::release(pool, claimed_by).await {
+        Ok(0) => {}
+        Ok(n) => tracing::info!(count = n, "released claimed follow-ups"),
+        Err(e) => tracing::error!(error = %e, "failed to release claimed follow-ups"),
+    }
+}
+
+/// Resolves on the next job notification for `lane`; never, without a
+/// listener.
+async fn next_notification(
+    listener: &mut Option<PgListener>,
+    lane: JobLane,
+) -> Result<(), sqlx::Error> {
+    let Some(l) = listener else {
+        return std::future::pending().await;
+    };
+    loop {
+        if l.recv().await?.payload() == lane.as_str() {
+            return Ok(());
+        }
+    }
+}
+
+/// Claim a batch, fetch every object it names from its provider, at most
+/// `concurrency` fetches at a time, then apply the jobs. Jobs for the same
+/// object, which `claim` hands to one worker at a time, share one fetch and
+/// apply one after another, oldest event first; up to `concurrency` objects
+/// are applied at a time, so one slow object doesn't hold up the rest.
+/// Objects in `cache` fetched after the newest of their jobs' events aren't
+/// fetched again, and neither are objects whose every job is of a type in
+/// `payload_event_types` and carries the object complete: those jobs apply
+/// the object as their event sent it.
+async fn poll_once(
+    pool: &PgPool,
+    providers: &ProviderRegistry,
+    cache: &mut FetchCache,
+    config: &RuntimeConfig,
+    claimed_by: &str,
+    lane: JobLane,
+    concurrency: usize,
+) -> Result<usize, PipelineError> {
+    let mut tx = pressure::begin(pool).await?;
+    let jobs = job_repo::claim(&mut tx, lane, config.worker_batch_size, claimed_by).await?;
     tx.commit().await?;
+    let claimed = jobs.len();
 
+    let mut groups: HashMap<ObjectKey, Vec<PreparedJob>> = HashMap::new();
     for job in jobs {
-        let event_id = match EventId::new(&job.event_id) {
-            Ok(id) => id,
-            Err(e) => {
-                tracing::warn!(event_id = %job.event_id, error = %e, "invalid event_id, completing as garbage");
-                job_repo::complete(pool, job.id).await?;
-                continue;
+        match prepare_job(pool, providers, &job).await {
+            Ok(Some(trigger)) => {
+                let embedded = config
+                    .uses_payload(&trigger.event_type)
+                    .then(|| payload_payment(providers, &trigger))
+                    .flatten();
+                groups
+                    .entry((
+                        job.source.clone(),
+                        trigger.tenant_id.clone(),
+                        trigger.external_id.clone(),
+                    ))
+                    .or_default()
+                    .push((job, trigger, embedded))
             }
-        };
+            Ok(None) => {}
+            Err(e) => tracing::error!(job_id = %job.id, error = %e, "job bookkeeping error"),
+        }
+    }
 
-        let external_id = match ExternalId::new(&job.object_id) {
-            Ok(id) => id,
-            Err(e) => {
-                tracing::warn!(object_id = %job.object_id, error = %e, "invalid external_id, completing as garbage");
-                job_repo::complete(pool, job.id).await?;
-                continue;
+    let ttl = config.provider_cache_ttl();
+    let now = Utc::now();
+    let mut fetched: HashMap<ObjectKey, Result<FetchedPayment, PipelineError>> = HashMap::new();
+    let mut by_account: HashMap<(&str, Option<&TenantId>), Vec<ExternalId>> = HashMap::new();
+    for (key @ (source, tenant, id), jobs) in &groups {
+        if jobs.iter().all(|(_, _, embedded)| embedded.is_some()) {
+            continue;
+        }
+        let newest_event_ts = jobs.iter().map(|(_, t, _)| t.provider_ts).max();
+        match newest_event_ts.and_then(|ts| cache.get(source, id, ts, ttl, now)) {
+            Some(payment) => {
+                tracing::debug!(object_id = %id, "provider fetch served from cache");
+                fetched.insert(key.clone(), Ok(payment));
             }
-        };
+            None => by_account
+                .entry((source, tenant.as_ref()))
+                .or_default()
+                .push(id.clone()),
+        }
+    }
+    for ((source, tenant), ids) in by_account {
+        // Every job left names a registered provider that can act for its
+        // tenant; `prepare_job` checked.
+        let provider = providers.get_scoped(source, tenant)?;
+        let fetched_at = Utc::now();
+        let batch = provider.fetch_payments_batch(&ids, concurrency).await;
+        for (id, result) in batch {
+            if let Ok(payment) = &result {
+                cache.insert(source, payment, fetched_at, ttl);
+            }
+            fetched.insert(
+                (source.to_string(), tenant.cloned(), id),
+                result.map_err(PipelineError::from),
+            );
+        }
+    }
 
-        let trigger = PaymentTrigger {
-            event_id,
-            event_type: job.event_type,
-            external_id,
-            raw_event: job.raw_event,
-            provider_ts: job.provider_ts,
-        };
+    let mut results = stream::iter(groups)
+        .map(|(key, jobs)| {
+            let payment = fetched.remove(&key);
+            apply_jobs(pool, key, jobs, payment)
+        })
+        .buffer_unordered(concurrency);
+    while results.next().await.is_some() {}
 
-        match fetch_and_process_payment(pool, provider, trigger, "worker:stripe").await {
-            Ok(result) => {
-                tracing::info!(job_id = %job.id, ?result, "job processed");
-                job_repo::complete(pool, job.id).await?;
-            }
-            Err(PipelineError::Validation(msg)) => {
-                tracing::warn!(job_id = %job.id, error = %msg, "validation error, completing (no retry)");
-                job_repo::complete(pool, job.id).await?;
-            }
-            Err(e) => {
-                tracing::error!(job_id = %job.id, error = %e, "job failed, scheduling retry");
-                job_repo::fail(pool, job.id, &e.to_string()).await?;
+    Ok(claimed)
+}
+
+/// Jobs are grouped, and objects fetched, by source, tenant and object id.
+/// Object ids are unique across a provider's accounts, so the fetch cache
+/// keys on source and id alone.
+type ObjectKey = (String, Option<TenantId>, ExternalId);
+
+/// A claimed job ready to run, with the payment its event carries when
+/// that's applied instead of a fetch.
+type PreparedJob = (JobRow, PaymentTrigger, Option<FetchedPayment>);
+
+/// The payment in the event's own object, if the provider finds it complete
+/// and about the job's object; `None` has the job fetch it as usual.
+fn payload_payment(
+    providers: &ProviderRegistry,
+    trigger: &PaymentTrigger,
+) -> Option<FetchedPayment> {
+    let provider = providers
+        .get_scoped(&trigger.source, trigger.tenant_id.as_ref())
+        .ok()?;
+    match provider.payment_from_payload(&trigger.raw_event) {
+        Ok(Some(payment)) if payment.external_id == trigger.external_id => Some(payment),
+        Ok(_) => None,
+        Err(e) => {
+            tracing::debug!(
+                event_id = %trigger.event_id,
+                error = %e,
+                "webhook object not applicable, fetching instead"
+            );
+            None
+        }
+    }
+}
+
+/// The job's trigger, or `None` if the job can't run and was failed or
+/// discarded instead.
+async fn prepare_job(
+    pool: &PgPool,
+    providers: &ProviderRegistry,
+    job: &JobRow,
+) -> Result<Option<PaymentTrigger>, PipelineError> {
+    let tenant_id = match job.tenant_id.clone().map(TenantId::new).transpose() {
+        Ok(tenant) => tenant,
+        Err(e) => {
+            tracing::warn!(
+                tenant_id = ?job.tenant_id,
+                error = %e,
+                "invalid tenant_id, completing as garbage"
+            );
+            job_repo::discard(pool, job.id, &e.to_string()).await?;
+            return Ok(None);
+        }
+    };
+
+    if let Err(e) = providers.get_scoped(&job.source, tenant_id.as_ref()) {
+        // Retried with backoff, in case the provider is being configured.
+        tracing::error!(job_id = %job.id, source = %job.source, error = %e, "no provider for job");
+        fail_job(pool, job, &PipelineError::from(e)).await?;
+        return Ok(None);
+    }
+
+    let event_id = match EventId::new(&job.event_id) {
+        Ok(id) => id,
+        Err(e) => {
+            tracing::warn!(
+                event_id = %job.event_id,
+                error = %e,
+                "invalid event_id, completing as garbage"
+            );
+            job_repo::discard(pool, job.id, &e.to_string()).await?;
+            return Ok(None);
+        }
+    };
+
+    let external_id = match ExternalId::new(&job.object_id) {
+        Ok(id) => id,
+        Err(e) => {
+            tracing::warn!(
+                object_id = %job.object_id,
+                error = %e,
+                "invalid external_id, completing as garbage"
+            );
+            job_repo::discard(pool, job.id, &e.to_string()).await?;
+            return Ok(None);
+        }
+    };
+
+    Ok(Some(PaymentTrigger {
+        source: job.source.clone(),
+        tenant_id,
+        event_id,
+        event_type: job.event_type.clone(),
+        external_id,
+        raw_event: job.raw_event.clone(),
+        provider_ts: job.provider_ts,
+    }))
+}
+
+/// Run one object's jobs, in order, each against the object its event
+/// carried if it's applied as sent, else against the fetched state. Each
+/// job runs in a `job` span continuing the trace it was queued under.
+async fn apply_jobs(
+    pool: &PgPool,
+    (source, _, id): ObjectKey,
+    jobs: Vec<PreparedJob>,
+    fetched: Option<Result<FetchedPayment, PipelineError>>,
+) {
+    let actor = format!("worker:{source}");
+    let fetched =
+        fetched.unwrap_or_else(|| Err(PipelineError::Provider(format!("{id} was not fetched"))));
+    for (job, trigger, embedded) in jobs {
+        let span = tracing::info_span!(
+            "job",
+            job_id = %job.id,
+            event_id = %job.event_id,
+            external_id = %job.object_id,
+            source = %job.source,
+        );
+        telemetry::continue_trace(&span, job.traceparent.as_deref());
+        async {
+            let processed;
+            let payment = match (embedded, &fetched) {
+                (Some(payment), _) => Ok(payment),
+                (None, Ok(payment)) => Ok(payment.clone()),
+                (None, Err(e)) => Err(e),
+            };
+            let outcome = match payment {
+                Ok(payment) => {
+                    processed = process_fetched_payment(pool, payment, trigger, &actor).await;
+                    processed.as_ref()
+                }
+                Err(e) => Err(e),
+            };
+            // The job stays `processing` until the reaper picks it up.
+            if let Err(e) = finish_job(pool, &job, outcome).await {
+                tracing::error!(job_id = %job.id, error = %e, "job bookkeeping error");
             }
         }
+        .instrument(span)
+        .await;
     }
+}
 
+async fn finish_job(
+    pool: &PgPool,
+    job: &JobRow,
+    outcome: Result<&ProcessResult, &PipelineError>,
+) -> Result<(), PipelineError> {
+    match outcome {
+        Ok(result) => {
+            tracing::info!(job_id = %job.id, ?result, "job processed");
+            job_repo::complete(pool, job.id, result).await?;
+        }
+        Err(PipelineError::Validation(msg)) => {
+            tracing::warn!(
+                job_id = %job.id,
+                error = %msg,
+                payload = %redacted(&job.raw_event),
+                "validation error, completing (no retry)"
+            );
+            job_repo::discard(pool, job.id, msg).await?;
+        }
+        Err(PipelineError::ProviderMissing(msg)) => {
+            tracing::warn!(
+                job_id = %job.id,
+                object_id = %job.object_id,
+                error = %msg,
+                "object missing at provider, completing (no retry)"
+            );
+            complete_missing(pool, job, msg).await?;
+        }
+        Err(PipelineError::BudgetExhausted { reason, until }) => {
+            tracing::warn!(
+                job_id = %job.id,
+                object_id = %job.object_id,
+                %until,
+                "provider budget exhausted, job deferred: {reason}"
+            );
+            job_repo::defer(pool, job.id, reason, *until).await?;
+        }
+        Err(e) => fail_job(pool, job, e).await?,
+    }
+
+    Ok(())
+}
+
+/// Record a failed attempt, scheduling the next one as `RETRY` says.
+async fn fail_job(pool: &PgPool, job: &JobRow, e: &PipelineError) -> Result<(), PipelineError> {
+    let retry_in = RETRY.delay(job.attempts as u32 + 1, e);
+    tracing::error!(
+        job_id = %job.id,
+        object_id = %job.object_id,
+        error = %e,
+        payload = %redacted(&job.raw_event),
+        retry_in_secs = retry_in.map(|d| d.as_secs()),
+        "job failed"
+    );
+    job_repo::fail(pool, job.id, &e.to_string(), retry_in).await
+}
+
+/// Record that the provider doesn't have the job's object, flag the payment
+/// (if we have one) for review, and complete the job, all at once.
+async fn complete_missing(pool: &PgPool, job: &JobRow, error: &str) -> Result<(), PipelineError> {
+    let missing = MissingObject {
+        job_id: job.id,
+        source: job.source.clone(),
+        event_id: job.event_id.clone(),
+        object_id: job.object_id.clone(),
+        event_type: job.event_type.clone(),
+        error: error.to_string(),
+    };
+    let mut tx = pool.begin().await?;
+    let payment_id = payment_repo::find_payment_id(&mut tx, &job.object_id).await?;
+    let actor = format!("worker:{}", job.source);
+    audit_repo::insert_audit_entry(&mut tx, &missing.audit_entry(payment_id, &actor)).await?;
+    if let Some(payment_id) = payment_id {
+        reconciliation_repo::flag_for_review(
+            &mut tx,
+            payment_id,
+            &job.object_id,
+            DiscrepancyKind::MissingAtProvider,
+            &serde_json::json!({"event_id": job.event_id, "error": error}),
+        )
+        .await?;
+    }
+    job_repo::complete_missing(&mut tx, job.id, error).await?;
+    tx.commit().await?;
     Ok(())
 }
 
-/// Periodically reset jobs stuck in 'processing' back to 'pending'.
-pub async fn run_reaper(pool: PgPool, mut shutdown: watch::Receiver<bool>) {
+/// Periodically reset jobs and follow-ups stuck in 'processing' back to
+/// 'pending', and drop webhook signatures past the replay window.
+pub async fn run_reaper(
+    pool: PgPool,
+    config: RuntimeConfigHandle,
+    mut shutdown: watch::Receiver<bool>,
+) {
     tracing::info!("stale job reaper started");
 
     loop {
+        let cfg = config.current();
         tokio::select! {
             _ = shutdown.changed() => {
                 tracing::info!("stale job reaper shutting down");
                 return;
             }
-            _ = tokio::time::sleep(std::time::Duration::from_secs(60)) => {}
+            _ = tokio::time::sleep(cfg.config.reaper_interval()) => {}
         }
 
-        match job_repo::reap_stale(&pool).await {
+        match job_repo::reap_stale(&pool, cfg.config.stale_job_timeout_secs).await {
             Ok(0) => {}
             Ok(n) => tracing::info!(count = n, "reaped stale jobs"),
             Err(e) => tracing::error!(error = %e, "reaper error"),
         }
+        match followup_repo::reap_stale(&pool, cfg.config.stale_job_timeout_secs).await {
+            Ok(0) => {}
+            Ok(n) => tracing::info!(count = n, "reaped stale follow-ups"),
+            Err(e) => tracing::error!(error = %e, "follow-up reaper error"),
+        }
+
+        let cutoff = chrono::Utc::now() - cfg.config.webhook_max_age();
+        match webhook_repo::prune(&pool, cutoff).await {
+            Ok(0) => {}
+            Ok(n) => tracing::debug!(count = n, "pruned webhook signatures"),
+            Err(e) => tracing::error!(error = %e, "webhook signature prune error"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identity_is_host_and_instance() {
+        let a = WorkerIdentity::detect();
+        let b = WorkerIdentity::detect();
+        assert_eq!(a.hostname, b.hostname);
+        assert_eq!(a.instance_id.len(), 8);
+        assert_ne!(a.instance_id, b.instance_id);
+        assert_eq!(a.to_string(), format!("{}/{}", a.hostname, a.instance_id));
+    }
+
+    #[test]
+    fn idle_pauses_double_up_to_the_max_and_reset_when_busy() {
+        let base = Duration::from_millis(100);
+        let max = Duration::from_millis(500);
+        let mut backoff = IdleBackoff::default();
+        let pauses: Vec<u64> = [false, false, false, false, false, true, false, false]
+            .into_iter()
+            .map(|busy| backoff.next(busy, base, max).as_millis() as u64)
+            .collect();
+        assert_eq!(pauses, [100, 200, 400, 500, 500, 100, 100, 200]);
+
+        // A max below the poll interval leaves the poll interval.
+        let mut backoff = IdleBackoff::default();
+        backoff.next(false, base, Duration::from_millis(50));
+        let pause = backoff.next(false, base, Duration::from_millis(50));
+        assert_eq!(pause, base);
     }
 }
@@ -0,0 +1,263 @@
+//! Fixtures for integration tests and local development: payments ready for
+//! the pipeline or as a provider returns them, and canned Stripe events shaped as `/webhook` receives
+//! them, to sign and deliver or to post to `/dev/simulate`.
+
+use {
+    crate::domain::{
+        id::{EventId, ExternalId},
+        money::{Currency, Money, MoneyAmount},
+        payment::{NewPayment, NewPaymentParams, PaymentDirection, PaymentStatus},
+        provider::FetchedPayment,
+    },
+    serde_json::{Value, json},
+};
+
+/// An inbound (PaymentIntent) payment of 50.00 USD.
+pub fn make_payment(
+    external_id: &str,
+    event_id: &str,
+    status: PaymentStatus,
+    provider_ts: i64,
+) -> NewPayment {
+    NewPayment::new(NewPaymentParams {
+        external_id: ExternalId::new(external_id).unwrap(),
+        source: "stripe".to_string(),
+        event_type: format!("payment_intent.{}", status.as_str()),
+        direction: PaymentDirection::Inbound,
+        money: Money::new(MoneyAmount::new(5000).unwrap(), Currency::USD),
+        status,
+        metadata: json!({}),
+        raw_event: json!({"id": event_id}),
+        last_event_id: EventId::new(event_id).unwrap(),
+        parent_external_id: None,
+        provider_ts,
+        failure: None,
+        authorized_amount: None,
+        receipt: None,
+        tenant_id: None,
+        customer: None,
+    })
+}
+
+/// An outbound (Refund) payment of the whole 50.00 USD.
+pub fn make_refund(
+    external_id: &str,
+    event_id: &str,
+    status: PaymentStatus,
+    provider_ts: i64,
+    parent_external_id: &str,
+) -> NewPayment {
+    make_partial_refund(
+        external_id,
+        event_id,
+        status,
+        provider_ts,
+        parent_external_id,
+        5000,
+    )
+}
+
+/// An outbound (Refund) payment of `cents` USD.
+pub fn make_partial_refund(
+    external_id: &str,
+    event_id: &str,
+    status: PaymentStatus,
+    provider_ts: i64,
+    parent_external_id: &str,
+    cents: i64,
+) -> NewPayment {
+    NewPayment::new(NewPaymentParams {
+        external_id: ExternalId::new(external_id).unwrap(),
+        source: "stripe".to_string(),
+        event_type: format!("charge.refund.{}", status.as_str()),
+        direction: PaymentDirection::Outbound,
+        money: Money::new(MoneyAmount::new(cents).unwrap(), Currency::USD),
+        status,
+        metadata: json!({}),
+        raw_event: json!({"id": event_id}),
+        last_event_id: EventId::new(event_id).unwrap(),
+        parent_external_id: Some(ExternalId::new(parent_external_id).unwrap()),
+        provider_ts,
+        failure: None,
+        authorized_amount: None,
+        receipt: None,
+        tenant_id: None,
+        customer: None,
+    })
+}
+
+/// An inbound payment of `cents` USD as a provider returns it.
+pub fn fetched_payment(external_id: &str, status: PaymentStatus, cents: i64) -> FetchedPayment {
+    FetchedPayment {
+        external_id: ExternalId::new(external_id).unwrap(),
+        direction: PaymentDirection::Inbound,
+        status,
+        money: Money::new(MoneyAmount::new(cents).unwrap(), Currency::USD),
+        metadata: json!({}),
+        parent_external_id: None,
+        failure: None,
+        authorized_amount: None,
+        receipt: None,
+        customer: None,
+        created_at: None,
+    }
+}
+
+/// A Stripe event around a PaymentIntent or Refund, with everything the
+/// adapter reads filled in. Amounts default to 15.00 USD, the event id to
+/// `evt_<object id>` and the timestamps to 2023-11-14.
+#[derive(Debug, Clone)]
+pub struct StripeEventFixture {
+    event_id: String,
+    event_type: String,
+    created: i64,
+    account: Option<String>,
+    object: Value,
+}
+
+impl StripeEventFixture {
+    /// `payment_intent.<...>` for a PaymentIntent in Stripe's `status`
+    /// (`succeeded`, `requires_payment_method`, `canceled`, ...).
+    pub fn payment_intent(id: &str, status: &str) -> Self {
+        let event_type = match status {
+            "succeeded" => "payment_intent.succeeded",
+            "requires_payment_method" => "payment_intent.payment_failed",
+            "canceled" => "payment_intent.canceled",
+            "requires_capture" => "payment_intent.amount_capturable_updated",
+            "processing" => "payment_intent.processing",
+            "requires_action" => "payment_intent.requires_action",
+            _ => "payment_intent.created",
+        };
+        let capturable = if status == "requires_capture" {
+            1500
+        } else {
+            0
+        };
+        let received = if status == "succeeded" { 1500 } else { 0 };
+        Self::new(
+            id,
+            event_type,
+            json!({
+                "id": id,
+                "object": "payment_intent",
+                "amount": 1500,
+                "amount_capturable": capturable,
+                "amount_received": received,
+                "capture_method": "automatic",
+                "confirmation_method": "automatic",
+                "created": 1_700_000_000,
+                "currency": "usd",
+                "livemode": false,
+                "metadata": {},
+                "payment_method_types": ["card"],
+                "status": status,
+                "latest_charge": null,
+            }),
+        )
+    }
+
+    /// `refund.updated` (`refund.failed` when failed) for a refund of
+    /// `payment_intent` in Stripe's `status`.
+    pub fn refund(id: &str, payment_intent: &str, status: &str) -> Self {
+        let event_type = if status == "failed" {
+            "refund.failed"
+        } else {
+            "refund.updated"
+        };
+        Self::new(
+            id,
+            event_type,
+            json!({
+                "id": id,
+                "object": "refund",
+                "amount": 1500,
+                "created": 1_700_000_000,
+                "currency": "usd",
+                "metadata": {},
+                "payment_intent": payment_intent,
+                "status": status,
+            }),
+        )
+    }
+
+    fn new(object_id: &str, event_type: &str, object: Value) -> Self {
+        Self {
+            event_id: format!("evt_{object_id}"),
+            event_type: event_type.to_string(),
+            created: 1_700_000_000,
+            account: None,
+            object,
+        }
+    }
+
+    pub fn with_event_id(mut self, event_id: &str) -> Self {
+        self.event_id = event_id.to_string();
+        self
+    }
+
+    pub fn with_event_type(mut self, event_type: &str) -> Self {
+        self.event_type = event_type.to_string();
+        self
+    }
+
+    /// The event's `created`, in unix seconds: the order events apply in.
+    pub fn with_created(mut self, created: i64) -> Self {
+        self.created = created;
+        self
+    }
+
+    /// In Stripe's units for `currency` (lowercase ISO code, as Stripe
+    /// sends it).
+    pub fn with_amount(mut self, amount: i64, currency: &str) -> Self {
+        self.object["amount"] = amount.into();
+        self.object["currency"] = currency.into();
+        for field in ["amount_capturable", "amount_received"] {
+            if self.object[field].as_i64().is_some_and(|a| a > 0) {
+                self.object[field] = amount.into();
+            }
+        }
+        self
+    }
+
+    pub fn with_metadata(mut self, metadata: Value) -> Self {
+        self.object["metadata"] = metadata;
+        self
+    }
+
+    /// Sent for the connected account `account`.
+    pub fn with_account(mut self, account: &str) -> Self {
+        self.account = Some(account.to_string());
+        self
+    }
+
+    /// Set `field` of the object itself, for whatever the setters above
+    /// don't cover.
+    pub fn with_object_field(mut self, field: &str, value: Value) -> Self {
+        self.object[field] = value;
+        self
+    }
+
+    pub fn event_id(&self) -> &str {
+        &self.event_id
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut event = json!({
+            "id": self.event_id,
+            "object": "event",
+            "type": self.event_type,
+            "created": self.created,
+            "livemode": false,
+            "data": {"object": self.object},
+        });
+        if let Some(account) = &self.account {
+            event["account"] = account.as_str().into();
+        }
+        event
+    }
+
+    /// The request body, as Stripe sends it.
+    pub fn body(&self) -> String {
+        self.to_json().to_string()
+    }
+}
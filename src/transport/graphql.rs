@@ -0,0 +1,40 @@
+pub mod handler;
+pub mod loader;
+pub mod query;
+
+use {
+    crate::AppState,
+    async_graphql::{EmptyMutation, EmptySubscription, Schema},
+    axum::{Router, routing::get},
+    query::Query,
+    std::sync::LazyLock,
+};
+
+/// Read-only: dashboards query, the REST API writes.
+pub type ReadSchema = Schema<Query, EmptyMutation, EmptySubscription>;
+
+/// Deepest selection accepted: a payment's refunds' audit entries is 4.
+const MAX_DEPTH: usize = 8;
+/// Cost ceiling per query: one per field selected, times the rows a list
+/// may return (`first`, or its default, for pages; 100 for refunds), so
+/// roughly the values one response can hold. Nested lookups are batched
+/// per level (see `loader`), so it doesn't bound queries but response size.
+const MAX_COMPLEXITY: usize = 5_000;
+
+static SCHEMA: LazyLock<ReadSchema> = LazyLock::new(|| {
+    Schema::build(Query, EmptyMutation, EmptySubscription)
+        .limit_depth(MAX_DEPTH)
+        .limit_complexity(MAX_COMPLEXITY)
+        .finish()
+});
+
+/// The schema, built once. The pool and the caller's role come with each
+/// request.
+pub fn schema() -> &'static ReadSchema {
+    &SCHEMA
+}
+
+/// `GET` and `POST /graphql`, mounted with the admin routes.
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/graphql", get(handler::sdl).post(handler::query))
+}
@@ -0,0 +1,32 @@
+use {
+    crate::{
+        AppState,
+        transport::{
+            graphql::{loader::Loaders, schema},
+            http::auth::{AdminScope, Authorized},
+        },
+    },
+    async_graphql::{Request, Response, dataloader::DataLoader},
+    axum::{Json, extract::State},
+};
+
+/// `POST /graphql` — run a read query (`{"query": ..., "variables": ...}`)
+/// over payments and audit data. Always 200; errors come back in the
+/// body's `errors`, each with an `extensions.code`.
+pub async fn query(
+    State(state): State<AppState>,
+    auth: Authorized<AdminScope>,
+    Json(request): Json<Request>,
+) -> Json<Response> {
+    let loaders = DataLoader::new(Loaders::new(state.pool.clone()), tokio::spawn);
+    let request = request
+        .data(state.pool.clone())
+        .data(auth.key.role)
+        .data(loaders);
+    Json(schema().execute(request).await)
+}
+
+/// `GET /graphql` — the schema, in SDL, for codegen and editors.
+pub async fn sdl(_auth: Authorized<AdminScope>) -> String {
+    schema().sdl()
+}
@@ -0,0 +1,156 @@
+use {
+    super::query::{AuditConnection, AuditEntry, Payment, api_error},
+    crate::{
+        domain::audit::{AuditCursor, AuditLogFilters},
+        services::{
+            payment::lookup::{get_audit_pages, get_children_of, get_payments_by_ids},
+            tags::get_tags_of,
+        },
+    },
+    async_graphql::{Error, dataloader::Loader},
+    sqlx::PgPool,
+    std::collections::HashMap,
+};
+
+/// Batches the lookups nested fields make, one query per kind of lookup per
+/// level of the query instead of one per parent object. Made per request.
+pub struct Loaders {
+    pool: PgPool,
+}
+
+impl Loaders {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+/// A payment by external id (`Payment.parent`).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PaymentById(pub String);
+
+/// A payment's refunds and disputes, at most `limit` (`Payment.refunds`).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ChildrenOf {
+    pub parent: String,
+    pub limit: u64,
+}
+
+/// A payment's tags (`Payment.tags`).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TagsOf(pub String);
+
+/// A page of a payment's audit trail (`Payment.audit`).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AuditOf {
+    pub external_id: String,
+    pub action: Option<String>,
+    pub limit: Option<u64>,
+    pub cursor: Option<AuditCursor>,
+}
+
+impl Loader<PaymentById> for Loaders {
+    type Value = Payment;
+    type Error = Error;
+
+    async fn load(&self, keys: &[PaymentById]) -> Result<HashMap<PaymentById, Payment>, Error> {
+        let ids: Vec<String> = keys.iter().map(|k| k.0.clone()).collect();
+        let payments = get_payments_by_ids(&self.pool, &ids)
+            .await
+            .map_err(api_error)?;
+        Ok(payments
+            .into_iter()
+            .map(|p| (PaymentById(p.id.as_str().to_string()), Payment(p)))
+            .collect())
+    }
+}
+
+impl Loader<ChildrenOf> for Loaders {
+    type Value = Vec<Payment>;
+    type Error = Error;
+
+    async fn load(&self, keys: &[ChildrenOf]) -> Result<HashMap<ChildrenOf, Vec<Payment>>, Error> {
+        let mut loaded = HashMap::new();
+        for (limit, parents) in group(keys, |k| k.limit, |k| k.parent.clone()) {
+            let mut children = get_children_of(&self.pool, &parents, limit)
+                .await
+                .map_err(api_error)?;
+            for parent in parents {
+                let payments = children.remove(&parent).unwrap_or_default();
+                loaded.insert(
+                    ChildrenOf { parent, limit },
+                    payments.into_iter().map(Payment).collect(),
+                );
+            }
+        }
+        Ok(loaded)
+    }
+}
+
+impl Loader<TagsOf> for Loaders {
+    type Value = Vec<String>;
+    type Error = Error;
+
+    async fn load(&self, keys: &[TagsOf]) -> Result<HashMap<TagsOf, Vec<String>>, Error> {
+        let ids: Vec<String> = keys.iter().map(|k| k.0.clone()).collect();
+        let mut tags = get_tags_of(&self.pool, &ids).await.map_err(api_error)?;
+        Ok(ids
+            .into_iter()
+            .map(|id| {
+                let found = tags.remove(&id).unwrap_or_default();
+                (TagsOf(id), found)
+            })
+            .collect())
+    }
+}
+
+impl Loader<AuditOf> for Loaders {
+    type Value = AuditConnection;
+    type Error = Error;
+
+    async fn load(&self, keys: &[AuditOf]) -> Result<HashMap<AuditOf, AuditConnection>, Error> {
+        let mut loaded = HashMap::new();
+        let pages = group(
+            keys,
+            |k| (k.action.clone(), k.limit, k.cursor.clone()),
+            |k| k.external_id.clone(),
+        );
+        for ((action, limit, cursor), ids) in pages {
+            let filters = AuditLogFilters {
+                action: action.clone(),
+                limit,
+                cursor: cursor.clone(),
+                ..Default::default()
+            };
+            let pages = get_audit_pages(&self.pool, &ids, filters)
+                .await
+                .map_err(api_error)?;
+            for (external_id, page) in pages {
+                let key = AuditOf {
+                    external_id,
+                    action: action.clone(),
+                    limit,
+                    cursor: cursor.clone(),
+                };
+                let connection = AuditConnection {
+                    nodes: page.entries.into_iter().map(AuditEntry).collect(),
+                    next_cursor: page.next_cursor.map(|c| c.to_string()),
+                };
+                loaded.insert(key, connection);
+            }
+        }
+        Ok(loaded)
+    }
+}
+
+/// `keys`' ids, grouped by the arguments they're loaded with.
+fn group<K, A: Eq + std::hash::Hash>(
+    keys: &[K],
+    args: impl Fn(&K) -> A,
+    id: impl Fn(&K) -> String,
+) -> HashMap<A, Vec<String>> {
+    let mut groups: HashMap<A, Vec<String>> = HashMap::new();
+    for key in keys {
+        groups.entry(args(key)).or_default().push(id(key));
+    }
+    groups
+}
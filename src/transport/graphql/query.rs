@@ -0,0 +1,438 @@
+use {
+    crate::{
+        domain::{
+            audit::{AuditCursor, AuditLogFilters, AuditPage, AuditRecord},
+            error::DomainError,
+            id::ExternalId,
+            money::Currency,
+            payment::{
+                PaymentCursor, PaymentDirection, PaymentFailure, PaymentFilters, PaymentReceipt,
+                PaymentStatus, PaymentView,
+            },
+        },
+        error::PipelineError,
+        infra::auth::Role,
+        services::payment::lookup::{get_audit_log, get_payment_by_id, get_payment_list},
+        transport::{
+            graphql::loader::{AuditOf, ChildrenOf, Loaders, PaymentById, TagsOf},
+            http::{errors::ApiError, projection::project},
+        },
+    },
+    async_graphql::{
+        Context, Error, ErrorExtensions, Json, Object, Result, SimpleObject, dataloader::DataLoader,
+    },
+    chrono::{DateTime, Utc},
+    sqlx::PgPool,
+};
+
+/// Most children `Payment.refunds` lists.
+const MAX_CHILDREN: u64 = 100;
+
+/// Page size when `first` isn't given, and the most it may ask for; as
+/// the services have them.
+const DEFAULT_PAGE: usize = 20;
+const MAX_PAGE: usize = 100;
+
+/// Field and argument names are snake_case, as in the REST API.
+pub struct Query;
+
+#[Object(rename_fields = "snake_case", rename_args = "snake_case")]
+impl Query {
+    /// One payment by external id; null if there is none.
+    async fn payment(&self, ctx: &Context<'_>, id: String) -> Result<Option<Payment>> {
+        let id = ExternalId::new(id).map_err(invalid)?;
+        let payment = get_payment_by_id(pool(ctx)?, id).await.map_err(api_error)?;
+        Ok(payment.map(Payment))
+    }
+
+    /// Payments newest first, filtered as `GET /payments` is. Pass
+    /// `next_cursor` back as `after` for the following page.
+    #[allow(clippy::too_many_arguments)]
+    #[graphql(complexity = "page_cost(first, child_complexity)")]
+    async fn payments(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+        source: Option<String>,
+        tenant_id: Option<String>,
+        status: Option<String>,
+        currency: Option<String>,
+        direction: Option<String>,
+        parent_external_id: Option<String>,
+        decline_code: Option<String>,
+        reference: Option<String>,
+        tag: Option<String>,
+        start_date: Option<DateTime<Utc>>,
+        end_date: Option<DateTime<Utc>>,
+    ) -> Result<PaymentConnection> {
+        let filters = PaymentFilters {
+            source,
+            tenant_id,
+            status: status
+                .as_deref()
+                .map(PaymentStatus::try_from)
+                .transpose()
+                .map_err(invalid)?,
+            currency: currency
+                .as_deref()
+                .map(Currency::try_from)
+                .transpose()
+                .map_err(invalid)?,
+            direction: direction
+                .as_deref()
+                .map(PaymentDirection::try_from)
+                .transpose()
+                .map_err(invalid)?,
+            parent_external_id,
+            decline_code,
+            reference,
+            tag,
+            start_date,
+            end_date,
+            limit: page_size(first)?,
+            cursor: after
+                .map(PaymentCursor::try_from)
+                .transpose()
+                .map_err(invalid)?,
+            ..Default::default()
+        };
+        let page = get_payment_list(pool(ctx)?, filters)
+            .await
+            .map_err(api_error)?;
+        Ok(PaymentConnection {
+            nodes: page.payments.into_iter().map(Payment).collect(),
+            next_cursor: page.next_cursor.map(|c| c.to_string()),
+        })
+    }
+
+    /// The whole audit log newest first. `actor` matches as a prefix.
+    #[allow(clippy::too_many_arguments)]
+    #[graphql(complexity = "page_cost(first, child_complexity)")]
+    async fn audit_entries(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+        external_id: Option<String>,
+        source: Option<String>,
+        action: Option<String>,
+        actor: Option<String>,
+    ) -> Result<AuditConnection> {
+        let filters = AuditLogFilters {
+            external_id,
+            source,
+            action,
+            actor,
+            ..Default::default()
+        };
+        audit_page(ctx, filters, first, after).await
+    }
+
+    /// Events the state machine refused, newest first.
+    #[graphql(complexity = "page_cost(first, child_complexity)")]
+    async fn anomalies(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+        external_id: Option<String>,
+        source: Option<String>,
+    ) -> Result<AuditConnection> {
+        let filters = AuditLogFilters {
+            external_id,
+            source,
+            anomalies_only: true,
+            ..Default::default()
+        };
+        audit_page(ctx, filters, first, after).await
+    }
+}
+
+/// One page of payments. `next_cursor` is null on the last page.
+#[derive(SimpleObject)]
+#[graphql(rename_fields = "snake_case")]
+pub struct PaymentConnection {
+    nodes: Vec<Payment>,
+    next_cursor: Option<String>,
+}
+
+/// One page of audit entries. `next_cursor` is null on the last page.
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "snake_case")]
+pub struct AuditConnection {
+    pub(super) nodes: Vec<AuditEntry>,
+    pub(super) next_cursor: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct Payment(pub(super) PaymentView);
+
+#[Object(rename_fields = "snake_case", rename_args = "snake_case")]
+impl Payment {
+    async fn id(&self) -> &str {
+        self.0.id.as_str()
+    }
+
+    async fn source(&self) -> &str {
+        &self.0.source
+    }
+
+    /// Connected account; null for the platform's own payments.
+    async fn tenant_id(&self) -> Option<&str> {
+        self.0.tenant_id.as_ref().map(|t| t.as_str())
+    }
+
+    async fn status(&self) -> &str {
+        self.0.status.as_str()
+    }
+
+    /// In minor units. Null for roles that don't see amounts.
+    async fn amount(&self, ctx: &Context<'_>) -> Option<i64> {
+        sees_amounts(ctx).then_some(self.0.amount)
+    }
+
+    async fn currency(&self) -> &str {
+        self.0.currency.as_str()
+    }
+
+    async fn direction(&self) -> &str {
+        self.0.direction.as_str()
+    }
+
+    async fn parent_external_id(&self) -> Option<&str> {
+        self.0.parent_external_id.as_ref().map(|p| p.as_str())
+    }
+
+    async fn failure(&self) -> Option<Failure> {
+        self.0.failure.clone().map(Failure::from)
+    }
+
+    /// Last amount held by an authorization awaiting capture. Null for
+    /// roles that don't see amounts.
+    async fn authorized_amount(&self, ctx: &Context<'_>) -> Option<i64> {
+        self.0.authorized_amount.filter(|_| sees_amounts(ctx))
+    }
+
+    async fn receipt(&self) -> Option<Receipt> {
+        self.0.receipt.clone().map(Receipt::from)
+    }
+
+    async fn created_at(&self) -> DateTime<Utc> {
+        self.0.created_at
+    }
+
+    async fn updated_at(&self) -> DateTime<Utc> {
+        self.0.updated_at
+    }
+
+    /// Labels ops put on the payment, sorted.
+    async fn tags(&self, ctx: &Context<'_>) -> Result<Vec<String>> {
+        let key = TagsOf(self.0.id.as_str().to_string());
+        Ok(loader(ctx)?.load_one(key).await?.unwrap_or_default())
+    }
+
+    /// Refunds and disputes against this payment, newest first.
+    #[graphql(complexity = "MAX_CHILDREN as usize * child_complexity")]
+    async fn refunds(&self, ctx: &Context<'_>) -> Result<Vec<Payment>> {
+        let key = ChildrenOf {
+            parent: self.0.id.as_str().to_string(),
+            limit: MAX_CHILDREN,
+        };
+        Ok(loader(ctx)?.load_one(key).await?.unwrap_or_default())
+    }
+
+    /// The payment a refund or dispute is against.
+    async fn parent(&self, ctx: &Context<'_>) -> Result<Option<Payment>> {
+        let Some(parent) = &self.0.parent_external_id else {
+            return Ok(None);
+        };
+        let key = PaymentById(parent.as_str().to_string());
+        loader(ctx)?.load_one(key).await
+    }
+
+    /// This payment's audit trail, newest first.
+    #[graphql(complexity = "page_cost(first, child_complexity)")]
+    async fn audit(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+        action: Option<String>,
+    ) -> Result<AuditConnection> {
+        let key = AuditOf {
+            external_id: self.0.id.as_str().to_string(),
+            action,
+            limit: page_size(first)?,
+            cursor: after
+                .map(AuditCursor::try_from)
+                .transpose()
+                .map_err(invalid)?,
+        };
+        let page = loader(ctx)?.load_one(key).await?;
+        Ok(page.unwrap_or(AuditConnection {
+            nodes: Vec::new(),
+            next_cursor: None,
+        }))
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(rename_fields = "snake_case")]
+pub struct Failure {
+    code: Option<String>,
+    decline_code: Option<String>,
+    message: Option<String>,
+    network_advice_code: Option<String>,
+}
+
+impl From<PaymentFailure> for Failure {
+    fn from(f: PaymentFailure) -> Self {
+        Self {
+            code: f.code,
+            decline_code: f.decline_code,
+            message: f.message,
+            network_advice_code: f.network_advice_code,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(rename_fields = "snake_case")]
+pub struct Receipt {
+    statement_descriptor: Option<String>,
+    receipt_email: Option<String>,
+    receipt_url: Option<String>,
+}
+
+impl From<PaymentReceipt> for Receipt {
+    fn from(r: PaymentReceipt) -> Self {
+        Self {
+            statement_descriptor: r.statement_descriptor,
+            receipt_email: r.receipt_email,
+            receipt_url: r.receipt_url,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct AuditEntry(pub(super) AuditRecord);
+
+#[Object(rename_fields = "snake_case", rename_args = "snake_case")]
+impl AuditEntry {
+    async fn id(&self) -> String {
+        self.0.id.to_string()
+    }
+
+    async fn entity_type(&self) -> &str {
+        &self.0.entity_type
+    }
+
+    async fn external_id(&self) -> Option<&str> {
+        self.0.external_id.as_deref()
+    }
+
+    async fn event_id(&self) -> &str {
+        &self.0.event_id
+    }
+
+    async fn source(&self) -> Option<&str> {
+        self.0.source.as_deref()
+    }
+
+    async fn tenant_id(&self) -> Option<&str> {
+        self.0.tenant_id.as_deref()
+    }
+
+    async fn action(&self) -> &str {
+        &self.0.action
+    }
+
+    async fn actor(&self) -> &str {
+        &self.0.actor
+    }
+
+    /// Whether the state machine refused the event.
+    async fn anomaly(&self) -> bool {
+        self.0.detail["anomaly"] == true
+    }
+
+    /// Without amounts and payloads the caller's role doesn't see.
+    async fn detail(&self, ctx: &Context<'_>) -> Result<Json<serde_json::Value>> {
+        let detail = project(role(ctx)?, &self.0.detail).map_err(api_error)?;
+        Ok(Json(detail))
+    }
+
+    async fn created_at(&self) -> DateTime<Utc> {
+        self.0.created_at
+    }
+}
+
+async fn audit_page(
+    ctx: &Context<'_>,
+    mut filters: AuditLogFilters,
+    first: Option<i32>,
+    after: Option<String>,
+) -> Result<AuditConnection> {
+    filters.limit = page_size(first)?;
+    filters.cursor = after
+        .map(AuditCursor::try_from)
+        .transpose()
+        .map_err(invalid)?;
+    let AuditPage {
+        entries,
+        next_cursor,
+    } = get_audit_log(pool(ctx)?, filters)
+        .await
+        .map_err(api_error)?;
+    Ok(AuditConnection {
+        nodes: entries.into_iter().map(AuditEntry).collect(),
+        next_cursor: next_cursor.map(|c| c.to_string()),
+    })
+}
+
+/// `first`, which the service caps at 100.
+fn page_size(first: Option<i32>) -> Result<Option<u64>> {
+    first
+        .map(|n| {
+            u64::try_from(n)
+                .map_err(|_| invalid(DomainError::Validation("first must not be negative".into())))
+        })
+        .transpose()
+}
+
+/// What a listing costs: its fields' cost once per row it may return.
+fn page_cost(first: Option<i32>, child_complexity: usize) -> usize {
+    let rows = first.map_or(DEFAULT_PAGE, |n| {
+        usize::try_from(n).unwrap_or(0).min(MAX_PAGE)
+    });
+    rows * child_complexity
+}
+
+fn pool<'a>(ctx: &Context<'a>) -> Result<&'a PgPool> {
+    ctx.data::<PgPool>()
+}
+
+fn loader<'a>(ctx: &Context<'a>) -> Result<&'a DataLoader<Loaders>> {
+    ctx.data::<DataLoader<Loaders>>()
+}
+
+fn role(ctx: &Context<'_>) -> Result<Role> {
+    ctx.data::<Role>().copied()
+}
+
+fn sees_amounts(ctx: &Context<'_>) -> bool {
+    role(ctx).is_ok_and(Role::sees_amounts)
+}
+
+/// A bad argument, with the reason: it's the caller's own input.
+fn invalid(err: DomainError) -> Error {
+    Error::new(err.to_string()).extend_with(|_, e| e.set("code", "validation_error"))
+}
+
+/// Anything else goes out as the REST API would word it, so database and
+/// provider errors aren't leaked.
+pub(super) fn api_error(err: PipelineError) -> Error {
+    let err = ApiError::from(err);
+    Error::new(err.message()).extend_with(|_, e| e.set("code", err.code()))
+}
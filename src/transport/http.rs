@@ -1,3 +1,19 @@
+pub mod admin;
+pub mod auth;
+pub mod checkout;
 pub mod errors;
+pub mod event;
+pub mod headers;
+pub mod ingest;
+pub mod jsonapi;
+pub mod load_shed;
+pub mod maintenance;
+pub mod meta;
+pub mod outbox;
 pub mod payment;
+pub mod payout;
+pub mod projection;
+pub mod rate_limit;
+pub mod report;
 pub mod router;
+pub mod webhook_security;
@@ -0,0 +1,22 @@
+pub mod alert_handler;
+pub mod api_key_handler;
+pub mod audit_handler;
+pub mod backfill_handler;
+pub mod budget_handler;
+pub mod config_handler;
+pub mod currency_terms_handler;
+pub mod event_gap_handler;
+pub mod export_handler;
+pub mod job_handler;
+pub mod lock_handler;
+pub mod maintenance_handler;
+pub mod payment_handler;
+pub mod period_handler;
+pub mod reconciliation_handler;
+pub mod replay_handler;
+pub mod retention_handler;
+pub mod rollup_handler;
+pub mod saved_filter_handler;
+pub mod shadow_handler;
+pub mod vector_handler;
+pub mod webhook_endpoint_handler;
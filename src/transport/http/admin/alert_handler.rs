@@ -0,0 +1,48 @@
+use axum::{Json, extract::State};
+use serde::Deserialize;
+
+use crate::{
+    AppState,
+    domain::alert::ALERT_KINDS,
+    error::PipelineError,
+    transport::http::{
+        auth::{AdminScope, Authorized},
+        errors::ApiError,
+    },
+};
+
+#[derive(Deserialize)]
+pub struct TestRequest {
+    /// Defaults to `payment_anomaly`.
+    pub kind: Option<String>,
+    /// Fire at this sink only.
+    pub destination: Option<String>,
+}
+
+/// `POST /admin/alerts/test` — send a sample alert of `kind` through the
+/// configured routing and report what each sink did with it, so a new
+/// destination can be checked without waiting for a real alert.
+pub async fn test(
+    State(state): State<AppState>,
+    auth: Authorized<AdminScope>,
+    Json(req): Json<TestRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let kind = req.kind.as_deref().unwrap_or("payment_anomaly");
+    let alert = state.notifier.sample_alert(kind).ok_or_else(|| {
+        PipelineError::Validation(format!(
+            "unknown alert kind {kind:?}, expected one of {}",
+            ALERT_KINDS.join(", ")
+        ))
+    })?;
+    let deliveries = state
+        .notifier
+        .deliver(&alert, req.destination.as_deref())
+        .await;
+    if deliveries.is_empty() {
+        return Err(ApiError::not_found("no such alert destination"));
+    }
+    tracing::info!(key = %auth.key.name, kind, "test alert fired");
+    Ok(Json(
+        serde_json::json!({"alert": alert, "deliveries": deliveries}),
+    ))
+}
@@ -0,0 +1,82 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    domain::id::TenantId,
+    infra::{
+        auth::{self, ApiKey, Role, Scope},
+        postgres::api_key_repo,
+    },
+    transport::http::{
+        auth::{AdminScope, Authorized},
+        errors::ApiError,
+        headers::{ACTOR_HEADER, required_header},
+    },
+};
+
+#[derive(Deserialize)]
+pub struct CreateRequest {
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    /// Defaults to `engineering`.
+    #[serde(default)]
+    pub role: Role,
+    /// Limits the key to one connected account; such keys may only read.
+    #[serde(default)]
+    pub tenant_id: Option<TenantId>,
+}
+
+/// `GET /admin/api-keys` — every key, revoked ones included, newest first.
+/// Keys themselves are never returned, only their prefixes.
+pub async fn keys(
+    State(state): State<AppState>,
+    _auth: Authorized<AdminScope>,
+) -> Result<Json<Vec<ApiKey>>, ApiError> {
+    Ok(Json(api_key_repo::list(&state.pool).await?))
+}
+
+/// `POST /admin/api-keys` — create a key with the body's `name`, `scopes`,
+/// `role` and optional `tenant_id`. The response is the only time the key is shown. Requires
+/// `X-Actor`.
+pub async fn create(
+    State(state): State<AppState>,
+    auth: Authorized<AdminScope>,
+    headers: HeaderMap,
+    Json(req): Json<CreateRequest>,
+) -> Result<(StatusCode, Json<serde_json::Value>), ApiError> {
+    let actor = required_header(&headers, ACTOR_HEADER)?;
+    let (key, secret) = auth::create_key(
+        &state.pool,
+        &req.name,
+        &req.scopes,
+        req.role,
+        req.tenant_id.as_ref(),
+        &auth.key.actor("admin", actor),
+    )
+    .await?;
+    Ok((
+        StatusCode::CREATED,
+        Json(serde_json::json!({"key": secret, "api_key": key})),
+    ))
+}
+
+/// `POST /admin/api-keys/{id}/revoke` — the key stops working at once.
+/// Idempotent. Requires `X-Actor`.
+pub async fn revoke(
+    State(state): State<AppState>,
+    auth: Authorized<AdminScope>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiKey>, ApiError> {
+    let actor = required_header(&headers, ACTOR_HEADER)?;
+    let key = auth::revoke_key(&state.pool, id, &auth.key.actor("admin", actor))
+        .await?
+        .ok_or_else(|| ApiError::not_found("API key not found"))?;
+    Ok(Json(key))
+}
@@ -0,0 +1,43 @@
+use std::convert::Infallible;
+
+use axum::{
+    extract::{Query, State},
+    response::sse::{Event, KeepAlive, Sse},
+};
+use futures_util::{Stream, StreamExt};
+
+use crate::{
+    AppState,
+    domain::audit::AuditTailFilter,
+    services::audit_tail::tail_audit,
+    transport::http::{
+        auth::{AdminScope, Authorized},
+        errors::ApiError,
+    },
+};
+
+/// `GET /admin/audit/tail?external_id=&action=&actor=` — server-sent events,
+/// one `audit` event per matching entry as it is committed (`actor` matches
+/// as a prefix). An `error` event ends the stream if the tail breaks off.
+/// 429 while too many tails are open.
+pub async fn tail(
+    State(state): State<AppState>,
+    auth: Authorized<AdminScope>,
+    Query(filter): Query<AuditTailFilter>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+    tracing::info!(key = %auth.key.name, ?filter, "audit tail opened");
+    let entries = tail_audit(state.pool.clone(), filter)
+        .await?
+        .ok_or_else(|| ApiError::too_many_requests("too many audit tails open"))?;
+    let events = entries.map(|entry| {
+        Ok(match entry {
+            Ok(record) => Event::default()
+                .event("audit")
+                .id(record.id.to_string())
+                .json_data(&record)
+                .unwrap_or_else(|e| Event::default().event("error").data(e.to_string())),
+            Err(e) => Event::default().event("error").data(e.to_string()),
+        })
+    });
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
@@ -0,0 +1,96 @@
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    domain::{backfill::BackfillRunView, provider::PaymentProvider},
+    services::backfill::{execute_run, get_run, list_runs, resume_run, start_run},
+    transport::http::{
+        auth::{AdminScope, Authorized},
+        errors::ApiError,
+    },
+};
+
+#[derive(Debug, Deserialize)]
+pub struct StartRequest {
+    /// Provider to import from; defaults to `stripe`.
+    pub source: Option<String>,
+    pub since: DateTime<Utc>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RunListQuery {
+    pub limit: Option<i64>,
+}
+
+/// Start importing `[since, until]` (default until now) in the background.
+pub async fn start(
+    State(state): State<AppState>,
+    _auth: Authorized<AdminScope>,
+    Json(req): Json<StartRequest>,
+) -> Result<(StatusCode, Json<BackfillRunView>), ApiError> {
+    let until = req.until.unwrap_or_else(Utc::now);
+    let source = req.source.as_deref().unwrap_or("stripe");
+    let provider = state.providers.get(source)?.clone();
+    let run_id = start_run(&state.pool, source, req.since, until).await?;
+    spawn_run(&state, provider, run_id);
+
+    let run = get_run(&state.pool, run_id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("backfill run not found"))?;
+    Ok((StatusCode::ACCEPTED, Json(run)))
+}
+
+/// Continue a failed or stalled run from its last checkpoint.
+pub async fn resume(
+    State(state): State<AppState>,
+    _auth: Authorized<AdminScope>,
+    Path(id): Path<Uuid>,
+) -> Result<(StatusCode, Json<BackfillRunView>), ApiError> {
+    let not_found = || ApiError::not_found("backfill run not found");
+    // Resolve the provider before claiming, so an unconfigured source
+    // doesn't leave the run marked running.
+    let source = get_run(&state.pool, id)
+        .await?
+        .ok_or_else(not_found)?
+        .source;
+    let provider = state.providers.get(&source)?.clone();
+    let run = resume_run(&state.pool, id).await?.ok_or_else(not_found)?;
+    spawn_run(&state, provider, id);
+    Ok((StatusCode::ACCEPTED, Json(run)))
+}
+
+pub async fn runs(
+    State(state): State<AppState>,
+    _auth: Authorized<AdminScope>,
+    Query(q): Query<RunListQuery>,
+) -> Result<Json<Vec<BackfillRunView>>, ApiError> {
+    Ok(Json(list_runs(&state.pool, q.limit).await?))
+}
+
+pub async fn run_by_id(
+    State(state): State<AppState>,
+    _auth: Authorized<AdminScope>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<BackfillRunView>, ApiError> {
+    let run = get_run(&state.pool, id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("backfill run not found"))?;
+    Ok(Json(run))
+}
+
+fn spawn_run(state: &AppState, provider: Arc<dyn PaymentProvider>, run_id: Uuid) {
+    let pool = state.pool.clone();
+    tokio::spawn(async move {
+        // Failures are recorded on the run row; nothing else to do here.
+        let _ = execute_run(&pool, &*provider, run_id).await;
+    });
+}
@@ -0,0 +1,21 @@
+use axum::{Json, extract::State};
+
+use crate::{
+    AppState,
+    domain::budget::BudgetUsage,
+    services::budget,
+    transport::http::{
+        auth::{AdminScope, Authorized},
+        errors::ApiError,
+    },
+};
+
+/// `GET /admin/provider-budgets` — provider API calls this hour and today,
+/// by source and operation, with the configured limits and what is left.
+pub async fn usage(
+    State(state): State<AppState>,
+    _auth: Authorized<AdminScope>,
+) -> Result<Json<Vec<BudgetUsage>>, ApiError> {
+    let config = state.config.current();
+    Ok(Json(budget::usage(&state.pool, &config.config).await?))
+}
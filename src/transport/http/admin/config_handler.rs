@@ -0,0 +1,37 @@
+use axum::{Json, extract::State, http::HeaderMap};
+
+use crate::{
+    AppState,
+    domain::config::{RuntimeConfig, VersionedConfig},
+    services::config::update_config,
+    transport::http::{
+        auth::{AdminScope, Authorized},
+        errors::ApiError,
+        headers::{ACTOR_HEADER, required_header},
+    },
+};
+
+pub async fn get_config(
+    State(state): State<AppState>,
+    _auth: Authorized<AdminScope>,
+) -> Json<VersionedConfig> {
+    Json((*state.config.current()).clone())
+}
+
+/// Replace the whole runtime config. Requires `X-Actor`.
+pub async fn put_config(
+    State(state): State<AppState>,
+    auth: Authorized<AdminScope>,
+    headers: HeaderMap,
+    Json(next): Json<RuntimeConfig>,
+) -> Result<Json<VersionedConfig>, ApiError> {
+    let actor = required_header(&headers, ACTOR_HEADER)?;
+    let saved = update_config(
+        &state.pool,
+        &state.config,
+        next,
+        &auth.key.actor("admin", actor),
+    )
+    .await?;
+    Ok(Json(saved))
+}
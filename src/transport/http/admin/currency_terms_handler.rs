@@ -0,0 +1,95 @@
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    domain::currency_terms::{CurrencyTerms, ScheduleTerms},
+    services::currency_terms::{self, Cancellation, Scheduled},
+    transport::http::{
+        auth::{AdminScope, Authorized},
+        errors::ApiError,
+        headers::{ACTOR_HEADER, required_header},
+    },
+};
+
+#[derive(Debug, Deserialize)]
+pub struct TermsQuery {
+    pub source: Option<String>,
+    pub currency: Option<String>,
+    /// Only the versions in force at this instant.
+    pub at: Option<DateTime<Utc>>,
+}
+
+/// `GET /admin/currency-terms` — every version by source, currency and
+/// start, or with `at` the ones in force then.
+pub async fn terms(
+    State(state): State<AppState>,
+    _auth: Authorized<AdminScope>,
+    Query(q): Query<TermsQuery>,
+) -> Result<Json<Vec<CurrencyTerms>>, ApiError> {
+    let terms = currency_terms::list(
+        &state.pool,
+        q.source.as_deref(),
+        q.currency.as_deref(),
+        q.at,
+    )
+    .await?;
+    Ok(Json(terms))
+}
+
+/// `POST /admin/currency-terms` — schedule terms from `valid_from`. 201 on
+/// creation, 200 if the same terms were already scheduled from then, 409
+/// if other terms were, 422 for a change starting now or earlier.
+/// Requires `X-Actor`.
+pub async fn schedule(
+    State(state): State<AppState>,
+    auth: Authorized<AdminScope>,
+    headers: HeaderMap,
+    Json(request): Json<ScheduleTerms>,
+) -> Result<(StatusCode, Json<CurrencyTerms>), ApiError> {
+    let actor = required_header(&headers, ACTOR_HEADER)?;
+    match currency_terms::schedule(
+        &state.pool,
+        &request,
+        &auth.key.actor("admin", actor),
+        Utc::now(),
+    )
+    .await?
+    {
+        Scheduled::Created(terms) => Ok((StatusCode::CREATED, Json(terms))),
+        Scheduled::Unchanged(terms) => Ok((StatusCode::OK, Json(terms))),
+        Scheduled::Conflict(terms) => Err(ApiError::conflict(format!(
+            "other terms are scheduled from {} ({}); cancel them first",
+            terms.valid_from, terms.id
+        ))),
+    }
+}
+
+/// `DELETE /admin/currency-terms/{id}` — cancel a version not yet in
+/// force; the one before it runs on. 409 once in force. Requires
+/// `X-Actor`.
+pub async fn cancel(
+    State(state): State<AppState>,
+    auth: Authorized<AdminScope>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> Result<Json<CurrencyTerms>, ApiError> {
+    let actor = required_header(&headers, ACTOR_HEADER)?;
+    let cancellation =
+        currency_terms::cancel(&state.pool, id, &auth.key.actor("admin", actor), Utc::now())
+            .await?
+            .ok_or_else(|| ApiError::not_found("currency terms not found"))?;
+    match cancellation {
+        Cancellation::Cancelled(terms) => Ok(Json(terms)),
+        Cancellation::InForce(terms) => Err(ApiError::conflict(format!(
+            "in force since {}; schedule a change instead",
+            terms.valid_from
+        ))),
+    }
+}
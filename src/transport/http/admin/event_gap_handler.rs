@@ -0,0 +1,50 @@
+use axum::{
+    Json,
+    extract::{Query, State},
+};
+use chrono::Utc;
+use serde::Deserialize;
+
+use crate::{
+    AppState,
+    domain::event_gap::{EventGapView, GapScan},
+    services::event_gap,
+    transport::http::{
+        auth::{AdminScope, Authorized},
+        errors::ApiError,
+    },
+};
+
+#[derive(Debug, Deserialize)]
+pub struct GapListQuery {
+    /// `true` for unresolved gaps only, `false` for resolved ones.
+    pub open: Option<bool>,
+    pub kind: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// `GET /admin/event-gaps` — newest first.
+pub async fn gaps(
+    State(state): State<AppState>,
+    _auth: Authorized<AdminScope>,
+    Query(q): Query<GapListQuery>,
+) -> Result<Json<Vec<EventGapView>>, ApiError> {
+    let gaps = event_gap::list_gaps(&state.pool, q.open, q.kind.as_deref(), q.limit).await?;
+    Ok(Json(gaps))
+}
+
+/// `POST /admin/event-gaps/scan` — run a detection pass now.
+pub async fn scan(
+    State(state): State<AppState>,
+    _auth: Authorized<AdminScope>,
+) -> Result<Json<GapScan>, ApiError> {
+    let timeout = state.config.current().config.event_gap_timeout();
+    let scan = event_gap::scan(&state.pool, Utc::now(), timeout).await?;
+    tracing::info!(
+        opened = scan.opened,
+        resolved = scan.resolved,
+        refetches = scan.refetches,
+        "event gap scan run on demand"
+    );
+    Ok(Json(scan))
+}
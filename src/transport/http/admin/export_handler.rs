@@ -0,0 +1,62 @@
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    domain::export::{ExportRequest, ExportView},
+    services::export,
+    transport::http::{
+        auth::{AdminScope, Authorized},
+        errors::ApiError,
+        headers::{ACTOR_HEADER, required_header},
+    },
+};
+
+#[derive(Debug, Deserialize)]
+pub struct ExportListQuery {
+    pub limit: Option<i64>,
+}
+
+/// `POST /admin/exports` — write an export in the background and return it
+/// immediately (status `running`). Requires `X-Actor`.
+pub async fn start(
+    State(state): State<AppState>,
+    auth: Authorized<AdminScope>,
+    headers: HeaderMap,
+    Json(request): Json<ExportRequest>,
+) -> Result<(StatusCode, Json<ExportView>), ApiError> {
+    let actor = required_header(&headers, ACTOR_HEADER)?;
+    let export = export::start_export(
+        &state.pool,
+        &state.export_store,
+        request,
+        &auth.key.actor("admin", actor),
+    )
+    .await?;
+    Ok((StatusCode::ACCEPTED, Json(export)))
+}
+
+/// `GET /admin/exports?limit=` — newest first.
+pub async fn exports(
+    State(state): State<AppState>,
+    _auth: Authorized<AdminScope>,
+    Query(q): Query<ExportListQuery>,
+) -> Result<Json<Vec<ExportView>>, ApiError> {
+    Ok(Json(export::list_exports(&state.pool, q.limit).await?))
+}
+
+pub async fn export_by_id(
+    State(state): State<AppState>,
+    _auth: Authorized<AdminScope>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ExportView>, ApiError> {
+    let export = export::get_export(&state.pool, id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("export not found"))?;
+    Ok(Json(export))
+}
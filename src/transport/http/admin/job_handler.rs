@@ -0,0 +1,76 @@
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::HeaderMap,
+};
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    domain::job::{JobDetail, JobFilters, JobView, RequeueFilter},
+    services::jobs::{self, RetryOutcome},
+    transport::http::{
+        auth::{AdminScope, Authorized},
+        errors::ApiError,
+        headers::{ACTOR_HEADER, required_header},
+    },
+};
+
+/// `GET /admin/jobs?status=failed&source=&limit=` — newest first.
+pub async fn jobs(
+    State(state): State<AppState>,
+    _auth: Authorized<AdminScope>,
+    Query(filters): Query<JobFilters>,
+) -> Result<Json<Vec<JobView>>, ApiError> {
+    Ok(Json(jobs::list_jobs(&state.pool, filters).await?))
+}
+
+/// `GET /admin/jobs/{id}` — the job and every attempt at it: worker,
+/// start/end, outcome and error.
+pub async fn job(
+    State(state): State<AppState>,
+    _auth: Authorized<AdminScope>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<JobDetail>, ApiError> {
+    let detail = jobs::get_job_detail(&state.pool, id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("job not found"))?;
+    Ok(Json(detail))
+}
+
+/// `POST /admin/jobs/{id}/retry` — requeue one failed job with fresh
+/// attempts. 409 if the job isn't failed. Requires `X-Actor`.
+pub async fn retry(
+    State(state): State<AppState>,
+    auth: Authorized<AdminScope>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let actor = required_header(&headers, ACTOR_HEADER)?;
+    match jobs::retry_job(&state.pool, id, &auth.key.actor("admin", actor)).await? {
+        RetryOutcome::Requeued(job) => Ok(Json(serde_json::json!({
+            "status": "requeued",
+            "job_id": job.id,
+            "previous_attempts": job.attempts,
+        }))),
+        RetryOutcome::NotFailed(job) => Err(ApiError::conflict(format!(
+            "job is {}, only failed jobs can be retried",
+            job.status
+        ))),
+        RetryOutcome::NotFound => Err(ApiError::not_found("job not found")),
+    }
+}
+
+/// `POST /admin/jobs/requeue` — requeue every failed job matching the body
+/// (`source`, `failed_since`; both optional). Requires `X-Actor`.
+pub async fn requeue(
+    State(state): State<AppState>,
+    auth: Authorized<AdminScope>,
+    headers: HeaderMap,
+    Json(filter): Json<RequeueFilter>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let actor = required_header(&headers, ACTOR_HEADER)?;
+    let requeued =
+        jobs::requeue_failed(&state.pool, &filter, &auth.key.actor("admin", actor)).await?;
+    Ok(Json(serde_json::json!({"requeued": requeued})))
+}
@@ -0,0 +1,53 @@
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::HeaderMap,
+};
+
+use crate::{
+    AppState,
+    domain::lock::{DEFAULT_MIN_AGE_SECS, LockFilters, LockReport, TerminateRequest},
+    services::locks::{self, TerminateOutcome},
+    transport::http::{
+        auth::{AdminScope, Authorized},
+        errors::ApiError,
+        headers::{ACTOR_HEADER, required_header},
+    },
+};
+
+/// `GET /admin/locks?external_id=&tenant_id=&min_age_secs=` — advisory
+/// locks held or awaited, and transactions open at least `min_age_secs`
+/// (default 30) with locks on payment tables.
+pub async fn locks(
+    State(state): State<AppState>,
+    _auth: Authorized<AdminScope>,
+    Query(filters): Query<LockFilters>,
+) -> Result<Json<LockReport>, ApiError> {
+    Ok(Json(locks::report(&state.pool, &filters).await?))
+}
+
+/// `POST /admin/locks/{pid}/terminate` — end a stuck backend, rolling back
+/// its transaction. 409 unless it is in a transaction open at least
+/// `min_age_secs` (default 30) holding payment locks. Requires `X-Actor`.
+pub async fn terminate(
+    State(state): State<AppState>,
+    auth: Authorized<AdminScope>,
+    headers: HeaderMap,
+    Path(pid): Path<i32>,
+    Json(request): Json<TerminateRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let actor = required_header(&headers, ACTOR_HEADER)?;
+    let actor = auth.key.actor("admin", actor);
+    match locks::terminate(&state.pool, pid, &request, &actor).await? {
+        TerminateOutcome::Terminated(backend) => Ok(Json(serde_json::json!({
+            "status": "terminated",
+            "backend": backend,
+        }))),
+        TerminateOutcome::Ineligible(backend) => Err(ApiError::conflict(format!(
+            "backend {} is not in a transaction of at least {}s holding payment locks",
+            backend.pid,
+            request.min_age_secs.unwrap_or(DEFAULT_MIN_AGE_SECS)
+        ))),
+        TerminateOutcome::NotFound => Err(ApiError::not_found("backend not found")),
+    }
+}
@@ -0,0 +1,49 @@
+use axum::{Json, extract::State, http::HeaderMap};
+
+use crate::{
+    AppState,
+    domain::maintenance::{MaintenanceRequest, MaintenanceStatus},
+    services::maintenance,
+    transport::http::{
+        auth::{AdminScope, Authorized},
+        errors::ApiError,
+        headers::{ACTOR_HEADER, required_header},
+    },
+};
+
+/// `GET /admin/maintenance`
+pub async fn status(
+    State(state): State<AppState>,
+    _auth: Authorized<AdminScope>,
+) -> Result<Json<MaintenanceStatus>, ApiError> {
+    Ok(Json(maintenance::status(&state.settings_store).await?))
+}
+
+/// `PUT /admin/maintenance` — pause ingestion for `duration_secs` (default
+/// 1h, at most 24h). Requires `X-Actor`.
+pub async fn enable(
+    State(state): State<AppState>,
+    auth: Authorized<AdminScope>,
+    headers: HeaderMap,
+    Json(request): Json<MaintenanceRequest>,
+) -> Result<Json<MaintenanceStatus>, ApiError> {
+    let actor = required_header(&headers, ACTOR_HEADER)?;
+    let window = maintenance::enable(
+        &state.settings_store,
+        &request,
+        &auth.key.actor("admin", actor),
+    )
+    .await?;
+    Ok(Json(Some(window).into()))
+}
+
+/// `DELETE /admin/maintenance` — resume ingestion now. Requires `X-Actor`.
+pub async fn disable(
+    State(state): State<AppState>,
+    auth: Authorized<AdminScope>,
+    headers: HeaderMap,
+) -> Result<Json<MaintenanceStatus>, ApiError> {
+    let actor = required_header(&headers, ACTOR_HEADER)?;
+    maintenance::disable(&state.settings_store, &auth.key.actor("admin", actor)).await?;
+    Ok(Json(None.into()))
+}
@@ -0,0 +1,45 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::HeaderMap,
+};
+
+use crate::{
+    AppState,
+    domain::{
+        adjustment::{TransitionOutcome, TransitionRequest},
+        id::ExternalId,
+        payment::PaymentView,
+    },
+    services::payment::adjust,
+    transport::http::{
+        auth::{AdminScope, Authorized},
+        errors::ApiError,
+        headers::{ACTOR_HEADER, required_header},
+    },
+};
+
+/// `POST /admin/payments/{external_id}/transition` — move a payment to the
+/// body's `status`, with a `reason`, attributed to
+/// `admin:<X-Actor> (key <name>)`. 409 if the state machine refuses and
+/// `force` isn't set; 404 if the payment doesn't exist.
+pub async fn transition(
+    State(state): State<AppState>,
+    auth: Authorized<AdminScope>,
+    headers: HeaderMap,
+    Path(id): Path<ExternalId>,
+    Json(request): Json<TransitionRequest>,
+) -> Result<Json<PaymentView>, ApiError> {
+    let actor = required_header(&headers, ACTOR_HEADER)?;
+    let outcome =
+        adjust::transition_payment(&state.pool, id, &request, &auth.key.actor("admin", actor))
+            .await?
+            .ok_or_else(|| ApiError::not_found("payment not found"))?;
+    match outcome {
+        TransitionOutcome::Applied(view) | TransitionOutcome::Unchanged(view) => Ok(Json(view)),
+        TransitionOutcome::Rejected { current } => Err(ApiError::conflict(format!(
+            "payment is {current}; cannot move to {} without force",
+            request.status
+        ))),
+    }
+}
@@ -0,0 +1,171 @@
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::HeaderMap,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    domain::period::{
+        CloseRequest, EodCutoff, PeriodAdjustment, PeriodClose, PeriodStatus, ReasonRequest,
+    },
+    services::period::{self, Resolution},
+    transport::http::{
+        auth::{AdminScope, Authorized},
+        errors::ApiError,
+        headers::{ACTOR_HEADER, required_header},
+    },
+};
+
+/// `GET /admin/periods` — the lock in force, the cutoff, and every close
+/// newest first.
+pub async fn periods(
+    State(state): State<AppState>,
+    _auth: Authorized<AdminScope>,
+) -> Result<Json<PeriodStatus>, ApiError> {
+    Ok(Json(
+        period::status(&state.pool, &state.settings_store).await?,
+    ))
+}
+
+/// `POST /admin/periods/close` — close the books through the end of
+/// `business_date`. 422 if that day isn't over or is already closed.
+/// Requires `X-Actor`.
+pub async fn close(
+    State(state): State<AppState>,
+    auth: Authorized<AdminScope>,
+    headers: HeaderMap,
+    Json(request): Json<CloseRequest>,
+) -> Result<Json<PeriodClose>, ApiError> {
+    let actor = required_header(&headers, ACTOR_HEADER)?;
+    let close = period::close(
+        &state.pool,
+        &state.settings_store,
+        &request,
+        &auth.key.actor("admin", actor),
+    )
+    .await?;
+    Ok(Json(close))
+}
+
+/// `POST /admin/periods/reopen` — reopen the latest close; 404 if none.
+/// Requires `X-Actor`.
+pub async fn reopen(
+    State(state): State<AppState>,
+    auth: Authorized<AdminScope>,
+    headers: HeaderMap,
+    Json(request): Json<ReasonRequest>,
+) -> Result<Json<PeriodClose>, ApiError> {
+    let actor = required_header(&headers, ACTOR_HEADER)?;
+    let close = period::reopen(
+        &state.pool,
+        &request.reason,
+        &auth.key.actor("admin", actor),
+    )
+    .await?
+    .ok_or_else(|| ApiError::not_found("no closed period"))?;
+    Ok(Json(close))
+}
+
+/// `GET /admin/periods/eod-cutoff`
+pub async fn cutoff(
+    State(state): State<AppState>,
+    _auth: Authorized<AdminScope>,
+) -> Result<Json<EodCutoff>, ApiError> {
+    let cutoff = state
+        .settings_store
+        .get_or_default(&period::EOD_CUTOFF)
+        .await?;
+    Ok(Json(cutoff))
+}
+
+/// `PUT /admin/periods/eod-cutoff` — used by closes from now on. Requires
+/// `X-Actor`.
+pub async fn set_cutoff(
+    State(state): State<AppState>,
+    auth: Authorized<AdminScope>,
+    headers: HeaderMap,
+    Json(cutoff): Json<EodCutoff>,
+) -> Result<Json<EodCutoff>, ApiError> {
+    let actor = required_header(&headers, ACTOR_HEADER)?;
+    period::set_cutoff(
+        &state.settings_store,
+        &cutoff,
+        &auth.key.actor("admin", actor),
+    )
+    .await?;
+    Ok(Json(cutoff))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AdjustmentListQuery {
+    /// `pending`, `applied` or `dismissed`.
+    pub status: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// `GET /admin/period-adjustments` — newest first.
+pub async fn adjustments(
+    State(state): State<AppState>,
+    _auth: Authorized<AdminScope>,
+    Query(q): Query<AdjustmentListQuery>,
+) -> Result<Json<Vec<PeriodAdjustment>>, ApiError> {
+    let adjustments = period::adjustments(&state.pool, q.status.as_deref(), q.limit).await?;
+    Ok(Json(adjustments))
+}
+
+/// `POST /admin/period-adjustments/{id}/apply` — make the held status
+/// change now, through the state machine. 409 if it refuses or the
+/// adjustment is already settled. Requires `X-Actor`.
+pub async fn apply(
+    State(state): State<AppState>,
+    auth: Authorized<AdminScope>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+    Json(request): Json<ReasonRequest>,
+) -> Result<Json<PeriodAdjustment>, ApiError> {
+    let actor = required_header(&headers, ACTOR_HEADER)?;
+    let resolution = period::apply_adjustment(
+        &state.pool,
+        id,
+        &request.reason,
+        &auth.key.actor("admin", actor),
+    )
+    .await?;
+    resolved(resolution)
+}
+
+/// `POST /admin/period-adjustments/{id}/dismiss` — leave the held status
+/// change unapplied. 409 if already settled. Requires `X-Actor`.
+pub async fn dismiss(
+    State(state): State<AppState>,
+    auth: Authorized<AdminScope>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+    Json(request): Json<ReasonRequest>,
+) -> Result<Json<PeriodAdjustment>, ApiError> {
+    let actor = required_header(&headers, ACTOR_HEADER)?;
+    let resolution = period::dismiss_adjustment(
+        &state.pool,
+        id,
+        &request.reason,
+        &auth.key.actor("admin", actor),
+    )
+    .await?;
+    resolved(resolution)
+}
+
+fn resolved(resolution: Option<Resolution>) -> Result<Json<PeriodAdjustment>, ApiError> {
+    match resolution.ok_or_else(|| ApiError::not_found("adjustment not found"))? {
+        Resolution::Resolved(adjustment) => Ok(Json(adjustment)),
+        Resolution::AlreadySettled(adjustment) => Err(ApiError::conflict(format!(
+            "adjustment already {}",
+            adjustment.status
+        ))),
+        Resolution::Rejected { current } => Err(ApiError::conflict(format!(
+            "payment is {current}; the held status change no longer applies"
+        ))),
+    }
+}
@@ -0,0 +1,88 @@
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    domain::reconciliation::{DiscrepancyView, ReconciliationRunView},
+    services::reconciliation::{
+        ReconciliationReport, execute_and_notify, get_report, list_review, list_runs, start_run,
+    },
+    transport::http::{
+        auth::{AdminScope, Authorized},
+        errors::ApiError,
+    },
+};
+
+#[derive(Debug, Deserialize)]
+pub struct TriggerRequest {
+    /// Provider to reconcile against; defaults to `stripe`.
+    pub source: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RunListQuery {
+    pub limit: Option<i64>,
+}
+
+/// Start a run in the background and return it immediately (status `running`).
+/// Defaults to the last 24 hours.
+pub async fn trigger(
+    State(state): State<AppState>,
+    _auth: Authorized<AdminScope>,
+    Json(req): Json<TriggerRequest>,
+) -> Result<(StatusCode, Json<ReconciliationRunView>), ApiError> {
+    let until = req.until.unwrap_or_else(Utc::now);
+    let since = req.since.unwrap_or(until - TimeDelta::hours(24));
+    let source = req.source.as_deref().unwrap_or("stripe");
+    let provider = state.providers.get(source)?.clone();
+    let run_id = start_run(&state.pool, source, since, until).await?;
+
+    let pool = state.pool.clone();
+    let notifier = state.notifier.clone();
+    tokio::spawn(async move {
+        // Failures are recorded on the run row and in the summary.
+        let _ = execute_and_notify(&pool, &*provider, &notifier, run_id, since, until).await;
+    });
+
+    let report = get_report(&state.pool, run_id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("reconciliation run not found"))?;
+    Ok((StatusCode::ACCEPTED, Json(report.run)))
+}
+
+pub async fn runs(
+    State(state): State<AppState>,
+    _auth: Authorized<AdminScope>,
+    Query(q): Query<RunListQuery>,
+) -> Result<Json<Vec<ReconciliationRunView>>, ApiError> {
+    Ok(Json(list_runs(&state.pool, q.limit).await?))
+}
+
+/// `GET /admin/reconciliations/review` — open findings raised outside a
+/// run, oldest first, at most `limit` (default 100, max 500).
+pub async fn review(
+    State(state): State<AppState>,
+    _auth: Authorized<AdminScope>,
+    Query(q): Query<RunListQuery>,
+) -> Result<Json<Vec<DiscrepancyView>>, ApiError> {
+    Ok(Json(list_review(&state.pool, q.limit).await?))
+}
+
+pub async fn run_by_id(
+    State(state): State<AppState>,
+    _auth: Authorized<AdminScope>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ReconciliationReport>, ApiError> {
+    let report = get_report(&state.pool, id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("reconciliation run not found"))?;
+    Ok(Json(report))
+}
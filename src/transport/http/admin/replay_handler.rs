@@ -0,0 +1,63 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::HeaderMap,
+};
+
+use crate::{
+    AppState,
+    domain::replay::{EventReplay, ReplayReport, ReplayRequest, SimulateRequest},
+    services::replay,
+    transport::http::{
+        auth::{AdminScope, Authorized, ReplayScope},
+        errors::ApiError,
+        headers::{ACTOR_HEADER, required_header},
+        projection::project,
+    },
+};
+
+/// `POST /admin/replays` — replay the selected provider events into a
+/// scratch schema and return the diff against production.
+pub async fn replay(
+    State(state): State<AppState>,
+    _auth: Authorized<ReplayScope>,
+    Json(req): Json<ReplayRequest>,
+) -> Result<Json<ReplayReport>, ApiError> {
+    let report = replay::replay(&state.pool, &state.providers, req).await?;
+    Ok(Json(report))
+}
+
+/// `POST /admin/events/{event_id}/replay` — re-run one stored provider event
+/// against production, past dedup. Audit entries are marked `replayed`.
+/// Requires `X-Actor`; 404 if the event was never stored.
+pub async fn replay_event(
+    State(state): State<AppState>,
+    auth: Authorized<ReplayScope>,
+    headers: HeaderMap,
+    Path(event_id): Path<String>,
+) -> Result<Json<EventReplay>, ApiError> {
+    let actor = required_header(&headers, ACTOR_HEADER)?;
+    let replayed = replay::replay_stored_event(
+        &state.pool,
+        &state.providers,
+        &event_id,
+        &auth.key.actor("admin", actor),
+    )
+    .await?
+    .ok_or_else(|| ApiError::not_found("event not found"))?;
+    Ok(Json(replayed))
+}
+
+/// `POST /admin/simulate-event` — what the pipeline would do with an event
+/// (a stored one resent, or a `payload` not received yet), without writing
+/// anything. 404 for a stored event that doesn't exist.
+pub async fn simulate(
+    State(state): State<AppState>,
+    auth: Authorized<AdminScope>,
+    Json(req): Json<SimulateRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let simulation = replay::simulate_event(&state.pool, &state.providers, req)
+        .await?
+        .ok_or_else(|| ApiError::not_found("event not found"))?;
+    Ok(Json(project(auth.key.role, &simulation)?))
+}
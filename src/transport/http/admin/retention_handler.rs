@@ -0,0 +1,36 @@
+use axum::{Json, extract::State, http::HeaderMap};
+
+use crate::{
+    AppState,
+    domain::retention::{RetentionReport, RetentionRunRequest},
+    services::retention,
+    transport::http::{
+        auth::{AdminScope, Authorized},
+        errors::ApiError,
+        headers::{ACTOR_HEADER, required_header},
+    },
+};
+
+/// `POST /admin/retention/run` — archive and prune what the retention
+/// policies have made due now, or with `{"dry_run": true}` only count it
+/// (`{}` follows `RETENTION_DRY_RUN`). Requires `X-Actor`; each archived
+/// batch is audited.
+pub async fn run(
+    State(state): State<AppState>,
+    auth: Authorized<AdminScope>,
+    headers: HeaderMap,
+    Json(request): Json<RetentionRunRequest>,
+) -> Result<Json<RetentionReport>, ApiError> {
+    let actor = required_header(&headers, ACTOR_HEADER)?;
+    let config = &state.settings.retention;
+    let dry_run = request.dry_run.unwrap_or(config.dry_run);
+    let report = retention::run(
+        &state.pool,
+        &state.export_store,
+        config,
+        dry_run,
+        &auth.key.actor("admin", actor),
+    )
+    .await?;
+    Ok(Json(report))
+}
@@ -0,0 +1,35 @@
+use axum::{Json, extract::State};
+use serde::Deserialize;
+
+use crate::{
+    AppState,
+    domain::rollup::{BucketSize, RollupKind},
+    services::rollup::{RollupRun, recompute_window},
+    transport::http::{
+        auth::{AdminScope, Authorized},
+        errors::ApiError,
+    },
+};
+
+#[derive(Debug, Deserialize)]
+pub struct RecomputeRequest {
+    pub rollup: RollupKind,
+    pub bucket: BucketSize,
+    pub from: chrono::DateTime<chrono::Utc>,
+    pub to: chrono::DateTime<chrono::Utc>,
+}
+
+pub async fn recompute(
+    State(state): State<AppState>,
+    _auth: Authorized<AdminScope>,
+    Json(req): Json<RecomputeRequest>,
+) -> Result<Json<RollupRun>, ApiError> {
+    let run = recompute_window(&state.pool, req.rollup, req.bucket, req.from, req.to).await?;
+    tracing::info!(
+        rollup = %run.rollup,
+        bucket = %run.bucket,
+        rows = run.rows_written,
+        "rollup window recomputed"
+    );
+    Ok(Json(run))
+}
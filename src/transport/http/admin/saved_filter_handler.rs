@@ -0,0 +1,94 @@
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::{HeaderMap, HeaderValue},
+    response::{IntoResponse, Response},
+};
+
+use crate::{
+    AppState,
+    domain::tag::{SavedFilter, SavedFilterPage, SavedFilterRequest},
+    services::tags,
+    transport::http::{
+        auth::{AdminScope, Authorized},
+        errors::ApiError,
+        headers::{ACTOR_HEADER, required_header},
+        payment::lookup_handler::NEXT_CURSOR_HEADER,
+        projection::project,
+    },
+};
+
+/// `GET /admin/saved-filters` — every saved filter, by name.
+pub async fn filters(
+    State(state): State<AppState>,
+    _auth: Authorized<AdminScope>,
+) -> Result<Json<Vec<SavedFilter>>, ApiError> {
+    Ok(Json(tags::list_saved_filters(&state.pool).await?))
+}
+
+/// `GET /admin/saved-filters/{name}`
+pub async fn filter_by_name(
+    State(state): State<AppState>,
+    _auth: Authorized<AdminScope>,
+    Path(name): Path<String>,
+) -> Result<Json<SavedFilter>, ApiError> {
+    let filter = tags::get_saved_filter(&state.pool, &name)
+        .await?
+        .ok_or_else(|| ApiError::not_found("saved filter not found"))?;
+    Ok(Json(filter))
+}
+
+/// `PUT /admin/saved-filters/{name}` — create or replace it with the body's
+/// `description` and `filters`, which are `GET /payments` parameters minus
+/// paging. Requires `X-Actor`.
+pub async fn save(
+    State(state): State<AppState>,
+    auth: Authorized<AdminScope>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+    Json(request): Json<SavedFilterRequest>,
+) -> Result<Json<SavedFilter>, ApiError> {
+    let actor = required_header(&headers, ACTOR_HEADER)?;
+    let filter = tags::save_filter(
+        &state.pool,
+        &name,
+        &request,
+        &auth.key.actor("admin", actor),
+    )
+    .await?;
+    Ok(Json(filter))
+}
+
+/// `DELETE /admin/saved-filters/{name}` — requires `X-Actor`.
+pub async fn delete(
+    State(state): State<AppState>,
+    auth: Authorized<AdminScope>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+) -> Result<Json<SavedFilter>, ApiError> {
+    let actor = required_header(&headers, ACTOR_HEADER)?;
+    let filter = tags::delete_filter(&state.pool, &name, &auth.key.actor("admin", actor))
+        .await?
+        .ok_or_else(|| ApiError::not_found("saved filter not found"))?;
+    Ok(Json(filter))
+}
+
+/// `GET /admin/saved-filters/{name}/payments?limit=&cursor=` — the payments
+/// the filter selects, paged as `GET /payments` pages them.
+pub async fn payments(
+    State(state): State<AppState>,
+    auth: Authorized<AdminScope>,
+    Path(name): Path<String>,
+    Query(page): Query<SavedFilterPage>,
+) -> Result<Response, ApiError> {
+    let page = tags::run_saved_filter(&state.pool, &name, page)
+        .await?
+        .ok_or_else(|| ApiError::not_found("saved filter not found"))?;
+    let mut headers = HeaderMap::new();
+    if let Some(cursor) = page.next_cursor
+        && let Ok(value) = HeaderValue::from_str(&cursor.to_string())
+    {
+        headers.insert(NEXT_CURSOR_HEADER, value);
+    }
+    Ok((headers, Json(project(auth.key.role, &page.payments)?)).into_response())
+}
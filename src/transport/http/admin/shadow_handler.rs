@@ -0,0 +1,35 @@
+use axum::{Json, extract::State};
+use serde::Serialize;
+
+use crate::{
+    AppState,
+    domain::shadow::ShadowReport,
+    services::shadow,
+    transport::http::{
+        auth::{AdminScope, Authorized},
+        errors::ApiError,
+    },
+};
+
+#[derive(Debug, Serialize)]
+pub struct BackfillResponse {
+    pub copied: u64,
+}
+
+/// `POST /admin/payments-v2/backfill` — copy payments `payments_v2` doesn't
+/// have yet. 422 while the stage is `off`.
+pub async fn backfill(
+    State(state): State<AppState>,
+    _auth: Authorized<AdminScope>,
+) -> Result<Json<BackfillResponse>, ApiError> {
+    let copied = shadow::backfill(&state.pool).await?;
+    Ok(Json(BackfillResponse { copied }))
+}
+
+/// `POST /admin/payments-v2/verify` — compare both tables row by row.
+pub async fn verify(
+    State(state): State<AppState>,
+    _auth: Authorized<AdminScope>,
+) -> Result<Json<ShadowReport>, ApiError> {
+    Ok(Json(shadow::verify(&state.pool).await?))
+}
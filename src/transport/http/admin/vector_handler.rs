@@ -0,0 +1,32 @@
+use axum::{
+    Json,
+    extract::{Query, State},
+};
+use serde::Deserialize;
+
+use crate::{
+    AppState,
+    domain::vector::TestVector,
+    services::vectors,
+    transport::http::{
+        auth::{AdminScope, Authorized},
+        errors::ApiError,
+    },
+};
+
+#[derive(Debug, Deserialize)]
+pub struct VectorQuery {
+    pub limit: Option<i64>,
+}
+
+/// `GET /admin/test-vectors` — state machine test vectors exported from
+/// payments that hit an anomaly. The body can be saved as is under
+/// `tests/vectors/`.
+pub async fn export(
+    State(state): State<AppState>,
+    _auth: Authorized<AdminScope>,
+    Query(q): Query<VectorQuery>,
+) -> Result<Json<Vec<TestVector>>, ApiError> {
+    let vectors = vectors::export_anomaly_vectors(&state.pool, q.limit).await?;
+    Ok(Json(vectors))
+}
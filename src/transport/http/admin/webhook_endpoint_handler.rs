@@ -0,0 +1,59 @@
+use axum::{Json, extract::State, http::HeaderMap};
+
+use crate::{
+    AppState,
+    adapters::stripe::client::StripeProvider,
+    domain::webhook_endpoint::{EndpointSetup, SecretRotation},
+    services::webhook_setup,
+    transport::http::{
+        auth::{AdminScope, Authorized},
+        errors::ApiError,
+        headers::{ACTOR_HEADER, required_header},
+    },
+};
+
+fn stripe(state: &AppState) -> StripeProvider {
+    StripeProvider::new(&state.settings.stripe.secret_key)
+        .with_api_base(&state.settings.stripe.api_base)
+}
+
+/// `POST /admin/stripe/webhook-endpoint` — create or update the Stripe
+/// webhook endpoint for `STRIPE_WEBHOOK_URL` and the routed events.
+/// Requires `X-Actor`.
+pub async fn setup(
+    State(state): State<AppState>,
+    auth: Authorized<AdminScope>,
+    headers: HeaderMap,
+) -> Result<Json<EndpointSetup>, ApiError> {
+    let actor = required_header(&headers, ACTOR_HEADER)?;
+    let target = webhook_setup::target(&state.settings.stripe.webhook_url, &state.stripe_routes);
+    let setup = webhook_setup::sync_endpoint(
+        &state.settings_store,
+        &stripe(&state),
+        &target,
+        &auth.key.actor("admin", actor),
+    )
+    .await?;
+    Ok(Json(setup))
+}
+
+/// `POST /admin/stripe/webhook-endpoint/rotate` — move to a new signing
+/// secret, the old endpoint delivering for `STRIPE_SECRET_OVERLAP_SECS`
+/// more. Requires `X-Actor`.
+pub async fn rotate(
+    State(state): State<AppState>,
+    auth: Authorized<AdminScope>,
+    headers: HeaderMap,
+) -> Result<Json<SecretRotation>, ApiError> {
+    let actor = required_header(&headers, ACTOR_HEADER)?;
+    let target = webhook_setup::target(&state.settings.stripe.webhook_url, &state.stripe_routes);
+    let rotation = webhook_setup::rotate_secret(
+        &state.settings_store,
+        &stripe(&state),
+        &target,
+        state.settings.stripe.secret_overlap,
+        &auth.key.actor("admin", actor),
+    )
+    .await?;
+    Ok(Json(rotation))
+}
@@ -0,0 +1,104 @@
+use {
+    crate::{
+        AppState,
+        domain::id::ExternalId,
+        infra::auth::{self, ApiKey, Scope},
+        services::payment::lookup::payment_visible,
+        transport::http::errors::ApiError,
+    },
+    axum::{
+        extract::FromRequestParts,
+        http::{header::AUTHORIZATION, request::Parts},
+    },
+    sqlx::PgPool,
+    std::marker::PhantomData,
+};
+
+/// The scope a handler needs, as a type so it can sit in the extractor.
+pub trait RequiredScope: Send + Sync {
+    const SCOPE: Scope;
+    /// Whether tenant keys may call the handler. Only handlers that scope
+    /// what they return to `key.tenant_id` say yes.
+    const TENANT_KEYS: bool = false;
+}
+
+pub struct ReadScope;
+/// `ReadScope` for handlers that scope their results by tenant.
+pub struct TenantReadScope;
+pub struct ReplayScope;
+pub struct AdminScope;
+
+impl RequiredScope for ReadScope {
+    const SCOPE: Scope = Scope::Read;
+}
+
+impl RequiredScope for TenantReadScope {
+    const SCOPE: Scope = Scope::Read;
+    const TENANT_KEYS: bool = true;
+}
+
+impl RequiredScope for ReplayScope {
+    const SCOPE: Scope = Scope::Replay;
+}
+
+impl RequiredScope for AdminScope {
+    const SCOPE: Scope = Scope::Admin;
+}
+
+/// Extractor for a request carrying `Authorization: Bearer <key>` with a
+/// live key that has scope `S`. 401 without a valid key, 403 with one that
+/// lacks the scope or is a tenant key where `S` takes none.
+pub struct Authorized<S: RequiredScope> {
+    pub key: ApiKey,
+    scope: PhantomData<S>,
+}
+
+impl<S: RequiredScope> Authorized<S> {
+    /// 404 for a payment this key's tenant can't see, as for a missing one.
+    pub async fn check_payment(&self, pool: &PgPool, id: &ExternalId) -> Result<(), ApiError> {
+        if payment_visible(pool, id, self.key.tenant_id.as_ref()).await? {
+            Ok(())
+        } else {
+            Err(ApiError::not_found("payment not found"))
+        }
+    }
+}
+
+impl<S: RequiredScope> FromRequestParts<AppState> for Authorized<S> {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, ApiError> {
+        let token = parts
+            .headers
+            .get(AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .map(str::trim)
+            .ok_or_else(|| ApiError::unauthorized("missing bearer token"))?;
+        let key = auth::authenticate(&state.pool, token)
+            .await?
+            .ok_or_else(|| ApiError::unauthorized("invalid or revoked API key"))?;
+
+        if !key.allows(S::SCOPE) {
+            tracing::warn!(
+                key = %key.name,
+                scope = %S::SCOPE,
+                path = %parts.uri.path(),
+                "API key lacks scope"
+            );
+            return Err(ApiError::forbidden(format!(
+                "this key lacks the {} scope",
+                S::SCOPE
+            )));
+        }
+        if key.tenant_id.is_some() && !S::TENANT_KEYS {
+            return Err(ApiError::forbidden(
+                "this endpoint is not available to tenant keys",
+            ));
+        }
+        Ok(Self {
+            key,
+            scope: PhantomData,
+        })
+    }
+}
@@ -0,0 +1 @@
+pub mod attempts_handler;
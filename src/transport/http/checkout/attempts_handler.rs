@@ -0,0 +1,35 @@
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+};
+
+use crate::{
+    AppState,
+    domain::checkout::CheckoutQuery,
+    services::payment::checkout::get_checkout,
+    transport::http::{
+        auth::{Authorized, TenantReadScope},
+        errors::ApiError,
+        projection::project,
+    },
+};
+
+/// `GET /checkouts/{key}/attempts` — every attempt at a checkout, in
+/// order, with each payment's status and failure, and the attempt it
+/// converted on. `key` is the merchant's reference, the first attempt's
+/// id, or any attempt's id. The platform's own checkouts unless
+/// `tenant_id` is given; a tenant key sees only its own.
+pub async fn checkout_attempts(
+    State(state): State<AppState>,
+    auth: Authorized<TenantReadScope>,
+    Path(key): Path<String>,
+    Query(mut query): Query<CheckoutQuery>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    if let Some(tenant) = &auth.key.tenant_id {
+        query.tenant_id = Some(tenant.to_string());
+    }
+    let checkout = get_checkout(&state.pool, query.tenant_id.as_deref(), &key)
+        .await?
+        .ok_or_else(|| ApiError::not_found("checkout not found"))?;
+    Ok(Json(project(auth.key.role, &checkout)?))
+}
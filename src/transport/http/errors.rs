@@ -1,4 +1,7 @@
-use crate::domain::error::PipelineError;
+use crate::{
+    domain::error::{DomainError, PortError},
+    error::PipelineError,
+};
 use axum::{
     Json,
     http::StatusCode,
@@ -21,6 +24,64 @@ impl ApiError {
             message: message.into(),
         }
     }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self {
+            status: StatusCode::CONFLICT,
+            code: "conflict",
+            message: message.into(),
+        }
+    }
+
+    pub fn unauthorized(message: impl Into<String>) -> Self {
+        Self {
+            status: StatusCode::UNAUTHORIZED,
+            code: "unauthorized",
+            message: message.into(),
+        }
+    }
+
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        Self {
+            status: StatusCode::FORBIDDEN,
+            code: "forbidden",
+            message: message.into(),
+        }
+    }
+
+    pub fn too_many_requests(message: impl Into<String>) -> Self {
+        Self {
+            status: StatusCode::TOO_MANY_REQUESTS,
+            code: "rate_limited",
+            message: message.into(),
+        }
+    }
+
+    /// 429 for a read shed while the database is under pressure.
+    pub fn overloaded(message: impl Into<String>) -> Self {
+        Self {
+            status: StatusCode::TOO_MANY_REQUESTS,
+            code: "overloaded",
+            message: message.into(),
+        }
+    }
+
+    pub fn service_unavailable(message: impl Into<String>) -> Self {
+        Self {
+            status: StatusCode::SERVICE_UNAVAILABLE,
+            code: "maintenance",
+            message: message.into(),
+        }
+    }
+
+    /// `error_code` in the response body.
+    pub fn code(&self) -> &'static str {
+        self.code
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
 }
 
 /// PipelineError → ApiError so `?` works in handlers.
@@ -40,6 +101,12 @@ impl From<PipelineError> for ApiError {
                 code: "webhook_error",
                 message: "invalid webhook signature".into(),
             },
+            PipelineError::WebhookReplay(_) => Self {
+                status: StatusCode::BAD_REQUEST,
+                code: "webhook_replay",
+                message: "webhook delivery is outside the replay window or was already received"
+                    .into(),
+            },
             PipelineError::Database(err) => {
                 tracing::error!("database error: {err}");
                 Self {
@@ -64,10 +131,54 @@ impl From<PipelineError> for ApiError {
                     message: "internal error".into(),
                 }
             }
+            PipelineError::ProviderRejected(err) => {
+                tracing::error!("rejected by provider: {err}");
+                Self {
+                    status: StatusCode::INTERNAL_SERVER_ERROR,
+                    code: "provider_error",
+                    message: "internal error".into(),
+                }
+            }
+            PipelineError::ProviderMissing(err) => {
+                tracing::warn!("missing at provider: {err}");
+                Self {
+                    status: StatusCode::NOT_FOUND,
+                    code: "provider_missing",
+                    message: "object not found at the provider".into(),
+                }
+            }
+            PipelineError::BudgetExhausted { reason, until } => {
+                tracing::warn!("provider budget exhausted until {until}: {reason}");
+                Self {
+                    status: StatusCode::SERVICE_UNAVAILABLE,
+                    code: "budget_exhausted",
+                    message: "provider API budget exhausted, try again later".into(),
+                }
+            }
+            PipelineError::Storage(err) => {
+                tracing::error!("storage error: {err}");
+                Self {
+                    status: StatusCode::INTERNAL_SERVER_ERROR,
+                    code: "internal_error",
+                    message: "internal error".into(),
+                }
+            }
         }
     }
 }
 
+impl From<DomainError> for ApiError {
+    fn from(err: DomainError) -> Self {
+        PipelineError::from(err).into()
+    }
+}
+
+impl From<PortError> for ApiError {
+    fn from(err: PortError) -> Self {
+        PipelineError::from(err).into()
+    }
+}
+
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
         let body = serde_json::json!({
@@ -0,0 +1,37 @@
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+};
+use serde::Deserialize;
+
+use crate::{
+    AppState,
+    services::jobs::get_event_status,
+    transport::http::{
+        auth::{Authorized, TenantReadScope},
+        errors::ApiError,
+        projection::project,
+    },
+};
+
+#[derive(Debug, Deserialize)]
+pub struct EventStatusQuery {
+    /// Needed only for an event id more than one provider sent.
+    pub source: Option<String>,
+}
+
+/// `GET /events/{event_id}/status` — where a queued event stands: the job's
+/// state and, once it succeeded, the pipeline's result. Poll until `done`.
+/// Tenant keys see only their own account's events.
+pub async fn event_status(
+    State(state): State<AppState>,
+    auth: Authorized<TenantReadScope>,
+    Path(event_id): Path<String>,
+    Query(q): Query<EventStatusQuery>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let tenant = auth.key.tenant_id.as_ref();
+    let status = get_event_status(&state.pool, &event_id, q.source.as_deref(), tenant)
+        .await?
+        .ok_or_else(|| ApiError::not_found("no queued event with that id"))?;
+    Ok(Json(project(auth.key.role, &status)?))
+}
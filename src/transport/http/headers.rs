@@ -0,0 +1,19 @@
+use axum::http::HeaderMap;
+
+use crate::error::PipelineError;
+
+/// Header naming who made a change; recorded in the audit log.
+pub const ACTOR_HEADER: &str = "X-Actor";
+
+/// Caller-chosen key that makes a write safe to retry.
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+/// Trimmed value of a header the request can't do without.
+pub fn required_header<'a>(headers: &'a HeaderMap, name: &str) -> Result<&'a str, PipelineError> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .ok_or_else(|| PipelineError::Validation(format!("missing {name} header")))
+}
@@ -0,0 +1,35 @@
+use axum::{
+    Json,
+    extract::State,
+    http::{HeaderMap, header},
+};
+
+use crate::{
+    AppState,
+    domain::ingest::{BatchFormat, BatchSummary},
+    services::ingest,
+    transport::http::{
+        auth::{AdminScope, Authorized},
+        errors::ApiError,
+        headers::{ACTOR_HEADER, required_header},
+    },
+};
+
+/// `POST /ingest/batch` — a settlement export as `text/csv` or
+/// `application/x-ndjson`, one normalized payment event per row. Rows are
+/// applied independently; the response reports each one. Requires `X-Actor`.
+pub async fn ingest_batch(
+    State(state): State<AppState>,
+    auth: Authorized<AdminScope>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<Json<BatchSummary>, ApiError> {
+    let actor = required_header(&headers, ACTOR_HEADER)?;
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok());
+    let format = BatchFormat::from_content_type(content_type)?;
+    let summary =
+        ingest::ingest_batch(&state.pool, format, &body, &auth.key.actor("ingest", actor)).await?;
+    Ok(Json(summary))
+}
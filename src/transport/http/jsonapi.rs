@@ -0,0 +1,281 @@
+use {
+    crate::{
+        AppState,
+        domain::{
+            audit::AuditEntryView,
+            payment::{PaymentDetail, PaymentDirection, PaymentView},
+        },
+    },
+    axum::{
+        Json,
+        extract::FromRequestParts,
+        http::{header, request::Parts},
+        response::{IntoResponse, Response},
+    },
+    serde::Serialize,
+    serde_json::{Map, Value, json},
+    std::convert::Infallible,
+};
+
+/// Media type of a JSON:API document.
+pub const MEDIA_TYPE: &str = "application/vnd.api+json";
+
+/// Body shape of a read endpoint. An `Accept` header naming
+/// `application/vnd.api+json` gets a JSON:API document and one naming
+/// `application/json` gets plain JSON; anything else follows the
+/// `jsonapi_by_default` config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadFormat {
+    Plain,
+    JsonApi,
+}
+
+impl ReadFormat {
+    pub fn negotiate(accept: Option<&str>, jsonapi_by_default: bool) -> Self {
+        match accept {
+            Some(accept) if accept.contains(MEDIA_TYPE) => Self::JsonApi,
+            Some(accept) if accept.contains("application/json") => Self::Plain,
+            _ if jsonapi_by_default => Self::JsonApi,
+            _ => Self::Plain,
+        }
+    }
+}
+
+impl FromRequestParts<AppState> for ReadFormat {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Infallible> {
+        let accept = parts
+            .headers
+            .get(header::ACCEPT)
+            .and_then(|v| v.to_str().ok());
+        Ok(Self::negotiate(
+            accept,
+            state.config.current().config.jsonapi_by_default,
+        ))
+    }
+}
+
+/// A top-level document; `data` is one resource or an array of them.
+#[derive(Debug, Serialize)]
+pub struct Document<T> {
+    pub data: T,
+    pub links: Links,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Links {
+    #[serde(rename = "self")]
+    pub self_link: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub related: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next: Option<String>,
+}
+
+impl Links {
+    fn to(self_link: String) -> Self {
+        Self {
+            self_link,
+            related: None,
+            next: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Resource {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub id: String,
+    pub attributes: Map<String, Value>,
+    pub relationships: Map<String, Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub links: Option<Links>,
+}
+
+/// Serves the document as `application/vnd.api+json`.
+pub struct JsonApi<T>(pub T);
+
+impl<T: Serialize> IntoResponse for JsonApi<T> {
+    fn into_response(self) -> Response {
+        ([(header::CONTENT_TYPE, MEDIA_TYPE)], Json(self.0)).into_response()
+    }
+}
+
+/// `value`'s fields, minus the ones the resource carries elsewhere.
+fn attributes(
+    value: &impl Serialize,
+    skip: &[&str],
+) -> Result<Map<String, Value>, serde_json::Error> {
+    let mut attributes = match serde_json::to_value(value)? {
+        Value::Object(map) => map,
+        _ => Map::new(),
+    };
+    for key in skip {
+        attributes.remove(*key);
+    }
+    Ok(attributes)
+}
+
+fn payment_path(id: &str) -> String {
+    format!("/payments/{id}")
+}
+
+/// A `payments` resource. Outbound payments point at their `parent`; inbound
+/// ones link to their `refunds`; every payment links to its `audit` trail.
+pub fn payment_resource(p: &PaymentView) -> Result<Resource, serde_json::Error> {
+    let id = p.id.as_str();
+    let mut relationships = Map::new();
+    if let Some(parent) = &p.parent_external_id {
+        relationships.insert(
+            "parent".into(),
+            json!({"data": {"type": "payments", "id": parent.as_str()}}),
+        );
+    }
+    if p.direction == PaymentDirection::Inbound {
+        relationships.insert(
+            "refunds".into(),
+            json!({"links": {"related": format!("/payments?parent_external_id={id}")}}),
+        );
+    }
+    relationships.insert(
+        "audit".into(),
+        json!({"links": {"related": format!("{}/audit", payment_path(id))}}),
+    );
+
+    Ok(Resource {
+        kind: "payments",
+        id: id.to_string(),
+        attributes: attributes(p, &["id", "parent_external_id"])?,
+        relationships,
+        links: Some(Links::to(payment_path(id))),
+    })
+}
+
+/// `GET /payments/{id}`. Tags are an attribute. The audit summary and refund
+/// totals become the `meta` of the `audit` and `refunds` relationships;
+/// captures, which have no endpoint of their own, a `captures` relationship
+/// with only `meta`.
+pub fn payment_document(detail: &PaymentDetail) -> Result<Document<Resource>, serde_json::Error> {
+    let mut resource = payment_resource(&detail.payment)?;
+    resource
+        .attributes
+        .insert("tags".into(), serde_json::to_value(&detail.tags)?);
+    if let Some(audit) = resource.relationships.get_mut("audit") {
+        audit["meta"] = serde_json::to_value(&detail.audit)?;
+    }
+    if let (Some(refunds), Some(balance)) =
+        (resource.relationships.get_mut("refunds"), &detail.refunds)
+    {
+        refunds["meta"] = serde_json::to_value(balance)?;
+    }
+    if let Some(captures) = &detail.captures {
+        resource.relationships.insert(
+            "captures".into(),
+            json!({"meta": serde_json::to_value(captures)?}),
+        );
+    }
+    Ok(Document {
+        links: Links::to(payment_path(&resource.id)),
+        data: resource,
+    })
+}
+
+/// `GET /payments`. `query` is the request's query string; the `next` link
+/// repeats it with the page's cursor in place of any `cursor` or `offset`.
+pub fn payment_list_document(
+    payments: &[PaymentView],
+    query: Option<&str>,
+    next_cursor: Option<String>,
+) -> Result<Document<Vec<Resource>>, serde_json::Error> {
+    let data = payments
+        .iter()
+        .map(payment_resource)
+        .collect::<Result<_, _>>()?;
+    let self_link = match query {
+        Some(q) if !q.is_empty() => format!("/payments?{q}"),
+        _ => "/payments".to_string(),
+    };
+    let next = next_cursor.map(|cursor| {
+        let mut params: Vec<&str> = query
+            .unwrap_or("")
+            .split('&')
+            .filter(|p| !p.is_empty() && !p.starts_with("cursor=") && !p.starts_with("offset="))
+            .collect();
+        let cursor = format!("cursor={cursor}");
+        params.push(&cursor);
+        format!("/payments?{}", params.join("&"))
+    });
+    Ok(Document {
+        data,
+        links: Links {
+            next,
+            ..Links::to(self_link)
+        },
+    })
+}
+
+/// `GET /payments/{id}/audit`: `audit_entries` resources keyed by event id,
+/// each pointing back at the payment.
+pub fn audit_document(
+    payment_id: &str,
+    entries: &[AuditEntryView],
+) -> Result<Document<Vec<Resource>>, serde_json::Error> {
+    let data = entries
+        .iter()
+        .map(|e| {
+            let mut relationships = Map::new();
+            relationships.insert(
+                "payment".into(),
+                json!({"data": {"type": "payments", "id": payment_id}}),
+            );
+            Ok(Resource {
+                kind: "audit_entries",
+                id: e.event_id.clone(),
+                attributes: attributes(e, &["event_id"])?,
+                relationships,
+                links: None,
+            })
+        })
+        .collect::<Result<_, serde_json::Error>>()?;
+    Ok(Document {
+        data,
+        links: Links {
+            related: Some(payment_path(payment_id)),
+            ..Links::to(format!("{}/audit", payment_path(payment_id)))
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accept_header_picks_the_format() {
+        use ReadFormat::*;
+        assert_eq!(ReadFormat::negotiate(Some(MEDIA_TYPE), false), JsonApi);
+        assert_eq!(ReadFormat::negotiate(Some("application/json"), true), Plain);
+        assert_eq!(ReadFormat::negotiate(Some("*/*"), true), JsonApi);
+        assert_eq!(ReadFormat::negotiate(None, false), Plain);
+    }
+
+    #[test]
+    fn next_link_replaces_the_cursor() {
+        let doc = payment_list_document(
+            &[],
+            Some("status=succeeded&cursor=1:pi_a&limit=2"),
+            Some("2:pi_b".into()),
+        )
+        .unwrap();
+        assert_eq!(
+            doc.links.self_link,
+            "/payments?status=succeeded&cursor=1:pi_a&limit=2"
+        );
+        assert_eq!(
+            doc.links.next.as_deref(),
+            Some("/payments?status=succeeded&limit=2&cursor=2:pi_b")
+        );
+    }
+}
@@ -0,0 +1,86 @@
+use {
+    crate::{
+        infra::{config::LoadShedConfig, postgres::pressure},
+        transport::http::errors::ApiError,
+    },
+    axum::{
+        extract::{Request, State},
+        http::{Method, header::RETRY_AFTER},
+        middleware::Next,
+        response::{IntoResponse, Response},
+    },
+    std::sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+/// Sheds reads while ingestion waits too long for database connections.
+/// The waits are those of ingestion's own acquires (see
+/// `infra::postgres::pressure`), so measuring takes no connection from it.
+#[derive(Debug, Clone)]
+pub struct LoadShedder {
+    config: LoadShedConfig,
+    /// Whether the last read checked was shed, to log the changes.
+    shedding: Arc<AtomicBool>,
+}
+
+impl LoadShedder {
+    pub fn new(config: LoadShedConfig) -> Self {
+        Self {
+            config,
+            shedding: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    fn shedding(&self) -> bool {
+        let limit = self.config.max_acquire_latency;
+        let latency = pressure::acquire_latency(self.config.window);
+        let shedding = latency >= limit;
+        if self.shedding.swap(shedding, Ordering::Relaxed) != shedding {
+            if shedding {
+                tracing::warn!(
+                    acquire_ms = latency.as_millis() as u64,
+                    max_acquire_ms = limit.as_millis() as u64,
+                    "database under pressure, shedding reads"
+                );
+            } else {
+                tracing::info!("database pressure eased, admitting reads");
+            }
+        }
+        shedding
+    }
+
+    fn refuse(&self, request: &Request) -> Response {
+        tracing::debug!(path = %request.uri().path(), "read shed under load");
+        (
+            [(RETRY_AFTER, self.config.retry_after_secs.to_string())],
+            ApiError::overloaded("database under load, retry later"),
+        )
+            .into_response()
+    }
+}
+
+/// Middleware for `axum::middleware::from_fn_with_state` on the read
+/// routes: 429 with `Retry-After` for a `GET` while the pool is under
+/// pressure, so reporting queries can't starve ingestion. Other methods on
+/// the same routes go through.
+pub async fn shed(State(shedder): State<LoadShedder>, request: Request, next: Next) -> Response {
+    if !matches!(*request.method(), Method::GET | Method::HEAD) || !shedder.shedding() {
+        return next.run(request).await;
+    }
+    shedder.refuse(&request)
+}
+
+/// `shed` for routes that only read, whatever the method: `POST /graphql`
+/// and starting an export are reads too.
+pub async fn shed_any(
+    State(shedder): State<LoadShedder>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !shedder.shedding() {
+        return next.run(request).await;
+    }
+    shedder.refuse(&request)
+}
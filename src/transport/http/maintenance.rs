@@ -0,0 +1,52 @@
+use {
+    crate::{AppState, services::maintenance, transport::http::errors::ApiError},
+    axum::{
+        extract::{Request, State},
+        http::header::RETRY_AFTER,
+        middleware::Next,
+        response::{IntoResponse, Response},
+    },
+    chrono::Utc,
+};
+
+/// Middleware for the webhook routes: 503 with `Retry-After` while
+/// maintenance mode is on, before the body is read, so providers keep the
+/// deliveries and send them again later. If the switch can't be read the
+/// delivery goes through.
+pub async fn hold_webhooks(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    match maintenance::active(&state.settings_store).await {
+        Ok(Some(window)) => {
+            tracing::info!(
+                path = %request.uri().path(),
+                "webhook refused, maintenance mode"
+            );
+            (
+                [(RETRY_AFTER, window.retry_after_secs(Utc::now()).to_string())],
+                ApiError::service_unavailable("ingestion paused for maintenance"),
+            )
+                .into_response()
+        }
+        Ok(None) => next.run(request).await,
+        Err(e) => {
+            tracing::error!(error = %e, "maintenance check failed, accepting webhook");
+            next.run(request).await
+        }
+    }
+}
+
+/// `GET /` — `ok`, or when ingestion is paused, `maintenance until <time>`.
+/// Always 200: the process is alive either way.
+pub async fn health(State(state): State<AppState>) -> String {
+    match maintenance::active(&state.settings_store).await {
+        Ok(Some(window)) => format!("maintenance until {}", window.expires_at.to_rfc3339()),
+        Ok(None) => "ok".to_string(),
+        Err(e) => {
+            tracing::error!(error = %e, "maintenance check failed");
+            "ok".to_string()
+        }
+    }
+}
@@ -0,0 +1,18 @@
+use axum::{Json, extract::Query};
+use serde::Deserialize;
+
+use crate::domain::transition::{self, StateMachineGraph};
+
+#[derive(Debug, Deserialize)]
+pub struct StateMachineQuery {
+    pub source: Option<String>,
+}
+
+/// `GET /meta/state-machine?source=paypal` — the transitions enforced for a
+/// source's payments, as a graph. Without `source`, the standard policy that
+/// sources without their own follow.
+pub async fn state_machine(Query(q): Query<StateMachineQuery>) -> Json<StateMachineGraph> {
+    Json(transition::graph(transition::for_source(
+        q.source.as_deref().unwrap_or_default(),
+    )))
+}
@@ -0,0 +1,58 @@
+use std::time::Duration;
+
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+};
+
+use crate::{
+    AppState,
+    domain::outbox::{ConsumerOffset, OffsetCommit, OutboxQuery},
+    services::outbox::{self, MAX_WAIT},
+    transport::http::{
+        auth::{Authorized, ReadScope},
+        errors::ApiError,
+        projection::project,
+    },
+};
+
+/// `GET /outbox/events?after_seq=&consumer=&limit=&wait=` — audit entries
+/// in outbox order. A long poll never outlasts the request timeout; 429
+/// while too many are waiting.
+pub async fn events(
+    State(state): State<AppState>,
+    auth: Authorized<ReadScope>,
+    Query(query): Query<OutboxQuery>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let max_wait = MAX_WAIT.min(
+        state
+            .settings
+            .http
+            .request_timeout
+            .saturating_sub(Duration::from_secs(1)),
+    );
+    let page = outbox::poll_events(&state.pool, query, max_wait)
+        .await?
+        .ok_or_else(|| ApiError::too_many_requests("too many outbox polls waiting"))?;
+    Ok(Json(project(auth.key.role, &page)?))
+}
+
+/// `GET /outbox/consumers` — committed offsets and lag, by consumer name.
+pub async fn consumers(
+    State(state): State<AppState>,
+    _auth: Authorized<ReadScope>,
+) -> Result<Json<Vec<ConsumerOffset>>, ApiError> {
+    Ok(Json(outbox::list_consumers(&state.pool).await?))
+}
+
+/// `PUT /outbox/consumers/{consumer}/offset` — the offset is the consumer's
+/// own bookkeeping, so a read key may move it.
+pub async fn commit_offset(
+    State(state): State<AppState>,
+    _auth: Authorized<ReadScope>,
+    Path(consumer): Path<String>,
+    Json(body): Json<OffsetCommit>,
+) -> Result<Json<ConsumerOffset>, ApiError> {
+    let offset = outbox::commit_offset(&state.pool, &consumer, body.last_seq).await?;
+    Ok(Json(offset))
+}
@@ -1 +1,6 @@
+pub mod export_handler;
+pub mod ledger_handler;
 pub mod lookup_handler;
+pub mod manual_handler;
+pub mod refund_handler;
+pub mod tag_handler;
@@ -0,0 +1,50 @@
+use axum::{
+    body::Body,
+    extract::{Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+};
+
+use crate::{
+    AppState,
+    domain::{export::ExportParams, payment::PaymentFilters},
+    services::export::export_payments,
+    transport::http::{
+        auth::{Authorized, TenantReadScope},
+        errors::ApiError,
+    },
+};
+
+/// `GET /payments/export?format=csv|ndjson` with the `GET /payments` filters.
+/// The body is sent chunked as rows are read; if the export breaks off
+/// part-way the connection is dropped instead of ending the body cleanly, so
+/// a truncated file can't pass for a complete one. Rows carry amounts, so
+/// keys whose role doesn't see them get 403. Tenant keys export only their
+/// own account's payments.
+pub async fn payment_export(
+    State(state): State<AppState>,
+    auth: Authorized<TenantReadScope>,
+    Query(params): Query<ExportParams>,
+    Query(mut filters): Query<PaymentFilters>,
+) -> Result<Response, ApiError> {
+    if !auth.key.role.sees_amounts() {
+        return Err(ApiError::forbidden(format!(
+            "exports carry amounts, which the {} role doesn't see",
+            auth.key.role
+        )));
+    }
+    if let Some(tenant) = &auth.key.tenant_id {
+        filters.tenant_id = Some(tenant.to_string());
+    }
+    let format = params.format;
+    let chunks = export_payments(state.pool.clone(), filters, format)?;
+    let disposition = format!("attachment; filename=\"payments.{}\"", format.extension());
+    Ok((
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        Body::from_stream(chunks),
+    )
+        .into_response())
+}
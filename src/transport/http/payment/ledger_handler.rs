@@ -0,0 +1,29 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+
+use crate::{
+    AppState,
+    domain::id::ExternalId,
+    services::ledger::get_payment_ledger,
+    transport::http::{
+        auth::{Authorized, TenantReadScope},
+        errors::ApiError,
+        projection::project,
+    },
+};
+
+/// Ledger entries posted for one payment, oldest first.
+pub async fn payment_ledger(
+    State(state): State<AppState>,
+    auth: Authorized<TenantReadScope>,
+    Path(id): Path<ExternalId>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    auth.check_payment(&state.pool, &id).await?;
+    let entries = get_payment_ledger(&state.pool, id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("payment not found"))?;
+
+    Ok(Json(project(auth.key.role, &entries)?))
+}
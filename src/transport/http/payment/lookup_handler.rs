@@ -1,33 +1,118 @@
 use axum::{
     Json,
-    extract::{Path, Query, State},
+    extract::{Path, Query, RawQuery, State},
+    http::{HeaderMap, HeaderValue},
+    response::{IntoResponse, Response},
 };
 
 use crate::{
     AppState,
-    domain::{
-        id::ExternalId,
-        payment::{PaymentFilters, PaymentView},
+    domain::{audit::AuditFilters, id::ExternalId, payment::PaymentFilters},
+    error::PipelineError,
+    services::payment::lookup::{
+        get_payment_audit, get_payment_detail, get_payment_list, get_payment_timeline,
+    },
+    transport::http::{
+        auth::{Authorized, TenantReadScope},
+        errors::ApiError,
+        jsonapi::{self, JsonApi, ReadFormat},
+        projection::project,
     },
-    services::payment::lookup::{get_payment_by_id, get_payment_list},
-    transport::http::errors::ApiError,
 };
 
+/// Response header carrying the cursor for the next page of `GET /payments`.
+pub(crate) const NEXT_CURSOR_HEADER: &str = "X-Next-Cursor";
+
 pub async fn payment_by_id(
     State(state): State<AppState>,
+    auth: Authorized<TenantReadScope>,
+    format: ReadFormat,
     Path(id): Path<ExternalId>,
-) -> Result<Json<PaymentView>, ApiError> {
-    let payment = get_payment_by_id(&state.pool, id)
+) -> Result<Response, ApiError> {
+    auth.check_payment(&state.pool, &id).await?;
+    let payment = get_payment_detail(&state.pool, id)
         .await?
         .ok_or_else(|| ApiError::not_found("payment not found"))?;
 
-    Ok(Json(payment))
+    let role = auth.key.role;
+    Ok(match format {
+        ReadFormat::Plain => Json(project(role, &payment)?).into_response(),
+        ReadFormat::JsonApi => {
+            let doc = jsonapi::payment_document(&payment).map_err(PipelineError::from)?;
+            JsonApi(project(role, &doc)?).into_response()
+        }
+    })
+}
+
+/// Audit trail for one payment, oldest first, optionally filtered by `action`
+/// and paged with `limit`/`offset`.
+pub async fn payment_audit(
+    State(state): State<AppState>,
+    auth: Authorized<TenantReadScope>,
+    format: ReadFormat,
+    Path(id): Path<ExternalId>,
+    Query(filters): Query<AuditFilters>,
+) -> Result<Response, ApiError> {
+    auth.check_payment(&state.pool, &id).await?;
+    let payment_id = id.as_str().to_string();
+    let entries = get_payment_audit(&state.pool, id, filters)
+        .await?
+        .ok_or_else(|| ApiError::not_found("payment not found"))?;
+
+    let role = auth.key.role;
+    Ok(match format {
+        ReadFormat::Plain => Json(project(role, &entries)?).into_response(),
+        ReadFormat::JsonApi => {
+            let doc =
+                jsonapi::audit_document(&payment_id, &entries).map_err(PipelineError::from)?;
+            JsonApi(project(role, &doc)?).into_response()
+        }
+    })
+}
+
+/// Provider events and audit entries for one payment, oldest first, each
+/// tagged with its `type`.
+pub async fn payment_timeline(
+    State(state): State<AppState>,
+    auth: Authorized<TenantReadScope>,
+    Path(id): Path<ExternalId>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    auth.check_payment(&state.pool, &id).await?;
+    let entries = get_payment_timeline(&state.pool, id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("payment not found"))?;
+    Ok(Json(project(auth.key.role, &entries)?))
 }
 
+/// The body stays a plain array; the next-page cursor, if any, is returned
+/// in `X-Next-Cursor` (and as the `next` link of a JSON:API document).
+/// Tenant keys list only their own account's payments.
 pub async fn payment_list(
     State(state): State<AppState>,
-    Query(filters): Query<PaymentFilters>,
-) -> Result<Json<Vec<PaymentView>>, ApiError> {
-    let payments = get_payment_list(&state.pool, filters).await?;
-    Ok(Json(payments))
+    auth: Authorized<TenantReadScope>,
+    format: ReadFormat,
+    RawQuery(query): RawQuery,
+    Query(mut filters): Query<PaymentFilters>,
+) -> Result<Response, ApiError> {
+    if let Some(tenant) = &auth.key.tenant_id {
+        filters.tenant_id = Some(tenant.to_string());
+    }
+    let page = get_payment_list(&state.pool, filters).await?;
+    let next_cursor = page.next_cursor.map(|c| c.to_string());
+    let mut headers = HeaderMap::new();
+    if let Some(cursor) = &next_cursor {
+        // Cursors are digits, ':' and an external id — always a valid header value.
+        if let Ok(value) = HeaderValue::from_str(cursor) {
+            headers.insert(NEXT_CURSOR_HEADER, value);
+        }
+    }
+    let role = auth.key.role;
+    Ok(match format {
+        ReadFormat::Plain => (headers, Json(project(role, &page.payments)?)).into_response(),
+        ReadFormat::JsonApi => {
+            let doc = jsonapi::payment_list_document(&page.payments, query.as_deref(), next_cursor)
+                .map_err(PipelineError::from)?;
+            (headers, JsonApi(project(role, &doc)?)).into_response()
+        }
+    })
 }
@@ -0,0 +1,61 @@
+use axum::{
+    Json,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+};
+
+use crate::{
+    AppState,
+    domain::{
+        manual::{IdempotencyKey, ManualOutcome, ManualPaymentRequest},
+        payment::PaymentView,
+    },
+    services::payment::manual::submit_manual_payment,
+    transport::http::{
+        auth::{AdminScope, Authorized},
+        errors::ApiError,
+        headers::{ACTOR_HEADER, IDEMPOTENCY_KEY_HEADER, required_header},
+    },
+};
+
+/// Record a manual payment. Requires `Idempotency-Key` and `X-Actor`.
+/// 201 on creation, 200 on a status change, 409 if the key was used for a
+/// different request or the transition isn't allowed, 422 if a refund it
+/// initiates exceeds the parent's refundable balance. A retry of a request
+/// gets the status the request first got.
+pub async fn create_payment(
+    State(state): State<AppState>,
+    auth: Authorized<AdminScope>,
+    headers: HeaderMap,
+    Json(request): Json<ManualPaymentRequest>,
+) -> Result<(StatusCode, Json<PaymentView>), ApiError> {
+    let key = IdempotencyKey::new(required_header(&headers, IDEMPOTENCY_KEY_HEADER)?)?;
+    let actor = required_header(&headers, ACTOR_HEADER)?;
+
+    let outcome = submit_manual_payment(
+        &state.pool,
+        &request,
+        &key,
+        &auth.key.actor("manual", actor),
+    )
+    .await?;
+    respond(outcome, &request)
+}
+
+fn respond(
+    outcome: ManualOutcome,
+    request: &ManualPaymentRequest,
+) -> Result<(StatusCode, Json<PaymentView>), ApiError> {
+    match outcome {
+        ManualOutcome::Created(view) => Ok((StatusCode::CREATED, Json(view))),
+        ManualOutcome::Applied(view) => Ok((StatusCode::OK, Json(view))),
+        ManualOutcome::Replayed(original) => respond(*original, request),
+        ManualOutcome::KeyReused => Err(ApiError::conflict(
+            "idempotency key was already used for a different request",
+        )),
+        ManualOutcome::Rejected { current } => Err(ApiError::conflict(format!(
+            "payment is {current}; cannot move to {}",
+            request.status
+        ))),
+    }
+}
@@ -0,0 +1,28 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+
+use crate::{
+    AppState,
+    domain::id::ExternalId,
+    services::payment::refund::get_refundable_balance,
+    transport::http::{
+        auth::{Authorized, TenantReadScope},
+        errors::ApiError,
+        projection::project,
+    },
+};
+
+pub async fn refundable_balance(
+    State(state): State<AppState>,
+    auth: Authorized<TenantReadScope>,
+    Path(id): Path<ExternalId>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    auth.check_payment(&state.pool, &id).await?;
+    let balance = get_refundable_balance(&state.pool, id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("payment not found"))?;
+
+    Ok(Json(project(auth.key.role, &balance)?))
+}
@@ -0,0 +1,49 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::HeaderMap,
+};
+
+use crate::{
+    AppState,
+    domain::{
+        id::ExternalId,
+        tag::{TagChange, TagRequest},
+    },
+    services::tags,
+    transport::http::{
+        auth::{AdminScope, Authorized, TenantReadScope},
+        errors::ApiError,
+        headers::{ACTOR_HEADER, required_header},
+    },
+};
+
+/// `GET /payments/{id}/tags` — the payment's tags, sorted.
+pub async fn payment_tags(
+    State(state): State<AppState>,
+    auth: Authorized<TenantReadScope>,
+    Path(id): Path<ExternalId>,
+) -> Result<Json<Vec<String>>, ApiError> {
+    auth.check_payment(&state.pool, &id).await?;
+    let tags = tags::get_payment_tags(&state.pool, &id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("payment not found"))?;
+    Ok(Json(tags))
+}
+
+/// `POST /payments/{id}/tags` — `{"add": [...], "remove": [...]}`; returns
+/// what changed and the tags afterwards. Tags are trimmed and lowercased.
+/// Requires `X-Actor`; audited as `payment_tagged` when anything changed.
+pub async fn tag_payment(
+    State(state): State<AppState>,
+    auth: Authorized<AdminScope>,
+    headers: HeaderMap,
+    Path(id): Path<ExternalId>,
+    Json(request): Json<TagRequest>,
+) -> Result<Json<TagChange>, ApiError> {
+    let actor = required_header(&headers, ACTOR_HEADER)?;
+    let change = tags::tag_payment(&state.pool, &id, &request, &auth.key.actor("admin", actor))
+        .await?
+        .ok_or_else(|| ApiError::not_found("payment not found"))?;
+    Ok(Json(change))
+}
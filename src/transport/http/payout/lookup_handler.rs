@@ -0,0 +1,38 @@
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+};
+
+use crate::{
+    AppState,
+    domain::payout::PayoutFilters,
+    services::payout::{get_payout, list_payouts},
+    transport::http::{
+        auth::{Authorized, ReadScope},
+        errors::ApiError,
+        projection::project,
+    },
+};
+
+/// `GET /payouts/{id}` — one payout or transfer by its provider id.
+pub async fn payout_by_id(
+    State(state): State<AppState>,
+    auth: Authorized<ReadScope>,
+    Path(id): Path<String>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let payout = get_payout(&state.pool, &id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("payout not found"))?;
+    Ok(Json(project(auth.key.role, &payout)?))
+}
+
+/// `GET /payouts` — newest first, optionally filtered by `kind` and
+/// `status`, at most `limit` (default 20, max 100).
+pub async fn payout_list(
+    State(state): State<AppState>,
+    auth: Authorized<ReadScope>,
+    Query(filters): Query<PayoutFilters>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let payouts = list_payouts(&state.pool, &filters).await?;
+    Ok(Json(project(auth.key.role, &payouts)?))
+}
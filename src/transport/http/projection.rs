@@ -0,0 +1,82 @@
+use {
+    crate::{error::PipelineError, infra::auth::Role},
+    serde::Serialize,
+    serde_json::{Map, Value},
+};
+
+/// Provider payloads, as received. Engineering only.
+const PAYLOAD_FIELDS: &[&str] = &["payload", "raw_event", "raw_payload"];
+
+/// Money, in minor units. Finance and engineering only.
+const AMOUNT_FIELDS: &[&str] = &[
+    "amount",
+    "amount_reversed",
+    "authorized",
+    "authorized_amount",
+    "capturable",
+    "captured",
+    "captured_total",
+    "disputed_amount",
+    "fee_amount",
+    "pending_refunds",
+    "refundable",
+    "refunded",
+    "total_amount",
+];
+
+/// `body` as `role` may see it. Fields are dropped by name at any depth,
+/// so amounts inside audit details and JSON:API attributes go too, and a
+/// new view using these names is covered without changes here.
+pub fn project(role: Role, body: &impl Serialize) -> Result<Value, PipelineError> {
+    let mut value = serde_json::to_value(body)?;
+    if role != Role::Engineering {
+        strip(&mut value, role);
+    }
+    Ok(value)
+}
+
+fn strip(value: &mut Value, role: Role) {
+    match value {
+        Value::Object(map) => strip_fields(map, role),
+        Value::Array(items) => items.iter_mut().for_each(|v| strip(v, role)),
+        _ => {}
+    }
+}
+
+fn strip_fields(map: &mut Map<String, Value>, role: Role) {
+    map.retain(|field, _| {
+        let field = field.as_str();
+        (role.sees_payloads() || !PAYLOAD_FIELDS.contains(&field))
+            && (role.sees_amounts() || !AMOUNT_FIELDS.contains(&field))
+    });
+    map.values_mut().for_each(|v| strip(v, role));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fields_are_dropped_by_role_at_any_depth() {
+        let body = serde_json::json!({
+            "id": "pi_1",
+            "amount": 5000,
+            "audit": [{"action": "created", "detail": {"amount": 5000, "status": "pending"}}],
+            "payload": {"data": {}},
+        });
+
+        let support = project(Role::Support, &body).unwrap();
+        assert_eq!(
+            support,
+            serde_json::json!({
+                "id": "pi_1",
+                "audit": [{"action": "created", "detail": {"status": "pending"}}],
+            })
+        );
+        let finance = project(Role::Finance, &body).unwrap();
+        assert_eq!(finance["amount"], 5000);
+        assert_eq!(finance["audit"][0]["detail"]["amount"], 5000);
+        assert!(finance.get("payload").is_none());
+        assert_eq!(project(Role::Engineering, &body).unwrap(), body);
+    }
+}
@@ -0,0 +1,184 @@
+use {
+    crate::{
+        infra::config::{Rate, RateLimitConfig},
+        transport::http::errors::ApiError,
+    },
+    axum::{
+        extract::{ConnectInfo, Request, State},
+        http::header::RETRY_AFTER,
+        middleware::Next,
+        response::{IntoResponse, Response},
+    },
+    std::{
+        collections::HashMap,
+        net::{IpAddr, SocketAddr},
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    },
+};
+
+/// Senders tracked at once; past this, senders whose bucket has refilled
+/// are forgotten.
+const MAX_TRACKED_IPS: usize = 10_000;
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn full(rate: Rate, now: Instant) -> Self {
+        Self {
+            tokens: f64::from(rate.burst),
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, rate: Rate, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * f64::from(rate.per_sec)).min(f64::from(rate.burst));
+        self.updated = now;
+    }
+
+    /// Time until a token is available; zero if one is.
+    fn wait(&self, rate: Rate) -> Duration {
+        if self.tokens >= 1.0 {
+            return Duration::ZERO;
+        }
+        Duration::from_secs_f64((1.0 - self.tokens) / f64::from(rate.per_sec))
+    }
+}
+
+#[derive(Debug)]
+struct Buckets {
+    global: Bucket,
+    per_ip: HashMap<IpAddr, Bucket>,
+}
+
+/// Token buckets for one group of routes: one per sender IP and one shared
+/// by all senders. A request spends a token from both or from neither.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Arc<Mutex<Buckets>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            buckets: Arc::new(Mutex::new(Buckets {
+                global: Bucket::full(config.global, Instant::now()),
+                per_ip: HashMap::new(),
+            })),
+        }
+    }
+
+    /// Spend a token for a request from `ip`, or say how long until it could.
+    fn check(&self, ip: Option<IpAddr>, now: Instant) -> Result<(), Duration> {
+        let RateLimitConfig { per_ip, global } = self.config;
+        let mut buckets = self.buckets.lock().expect("rate limiter lock poisoned");
+        let Buckets {
+            global: global_bucket,
+            per_ip: by_ip,
+        } = &mut *buckets;
+
+        if let Some(ip) = ip
+            && by_ip.len() >= MAX_TRACKED_IPS
+            && !by_ip.contains_key(&ip)
+        {
+            by_ip.retain(|_, b| {
+                b.refill(per_ip, now);
+                b.tokens < f64::from(per_ip.burst)
+            });
+        }
+        let mut ip_bucket =
+            ip.map(|ip| by_ip.entry(ip).or_insert_with(|| Bucket::full(per_ip, now)));
+
+        global_bucket.refill(global, now);
+        let mut wait = global_bucket.wait(global);
+        if let Some(b) = ip_bucket.as_deref_mut() {
+            b.refill(per_ip, now);
+            wait = wait.max(b.wait(per_ip));
+        }
+        if !wait.is_zero() {
+            return Err(wait);
+        }
+
+        global_bucket.tokens -= 1.0;
+        if let Some(b) = ip_bucket {
+            b.tokens -= 1.0;
+        }
+        Ok(())
+    }
+}
+
+/// Middleware for `axum::middleware::from_fn_with_state`: 429 with
+/// `Retry-After` once a sender, or all senders together, exceed their rate.
+/// The sender is the peer address; without `ConnectInfo` only the global
+/// limit applies.
+pub async fn limit(State(limiter): State<RateLimiter>, request: Request, next: Next) -> Response {
+    let ip = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+
+    match limiter.check(ip, Instant::now()) {
+        Ok(()) => next.run(request).await,
+        Err(wait) => {
+            tracing::warn!(
+                ip = ?ip,
+                path = %request.uri().path(),
+                "rate limit exceeded"
+            );
+            let retry_after = wait.as_secs_f64().ceil().max(1.0) as u64;
+            (
+                [(RETRY_AFTER, retry_after.to_string())],
+                ApiError::too_many_requests("rate limit exceeded"),
+            )
+                .into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(per_ip: (u32, u32), global: (u32, u32)) -> RateLimiter {
+        RateLimiter::new(RateLimitConfig {
+            per_ip: Rate {
+                per_sec: per_ip.0,
+                burst: per_ip.1,
+            },
+            global: Rate {
+                per_sec: global.0,
+                burst: global.1,
+            },
+        })
+    }
+
+    #[test]
+    fn senders_are_limited_apart_and_together() {
+        let limiter = limiter((1, 2), (10, 3));
+        let now = Instant::now();
+        let a = Some(IpAddr::from([10, 0, 0, 1]));
+        let b = Some(IpAddr::from([10, 0, 0, 2]));
+
+        // A's burst of 2 is spent; B still gets through.
+        assert!(limiter.check(a, now).is_ok());
+        assert!(limiter.check(a, now).is_ok());
+        assert_eq!(limiter.check(a, now), Err(Duration::from_secs(1)));
+        assert!(limiter.check(b, now).is_ok());
+
+        // Now the global burst of 3 is spent too, and refused requests
+        // didn't spend anything.
+        assert!(limiter.check(b, now).is_err());
+        let later = now + Duration::from_millis(100);
+        assert!(limiter.check(b, later).is_ok());
+
+        // A refills at 1/s.
+        assert!(limiter.check(a, now + Duration::from_secs(1)).is_ok());
+    }
+}
@@ -0,0 +1,2 @@
+pub mod daily_handler;
+pub mod dispute_handler;
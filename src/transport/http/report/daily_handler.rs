@@ -0,0 +1,32 @@
+use axum::{
+    Json,
+    extract::{Query, State},
+};
+use chrono::{NaiveDate, Utc};
+use serde::Deserialize;
+
+use crate::{
+    AppState,
+    services::report::get_daily_report,
+    transport::http::{
+        auth::{Authorized, ReadScope},
+        errors::ApiError,
+        projection::project,
+    },
+};
+
+#[derive(Debug, Deserialize)]
+pub struct DailyReportQuery {
+    pub date: Option<NaiveDate>,
+}
+
+/// `GET /reports/daily?date=YYYY-MM-DD` — defaults to today (UTC).
+pub async fn daily_report(
+    State(state): State<AppState>,
+    auth: Authorized<ReadScope>,
+    Query(q): Query<DailyReportQuery>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let date = q.date.unwrap_or_else(|| Utc::now().date_naive());
+    let report = get_daily_report(&state.pool, date).await?;
+    Ok(Json(project(auth.key.role, &report)?))
+}
@@ -0,0 +1,33 @@
+use axum::{
+    Json,
+    extract::{Query, State},
+};
+use chrono::{NaiveDate, Utc};
+use serde::Deserialize;
+
+use crate::{
+    AppState,
+    services::report::get_dispute_report,
+    transport::http::{
+        auth::{Authorized, ReadScope},
+        errors::ApiError,
+        projection::project,
+    },
+};
+
+#[derive(Debug, Deserialize)]
+pub struct DisputeReportQuery {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+/// `GET /reports/disputes?from=YYYY-MM-DD&to=YYYY-MM-DD` — whole months,
+/// defaults to the last twelve.
+pub async fn dispute_report(
+    State(state): State<AppState>,
+    auth: Authorized<ReadScope>,
+    Query(q): Query<DisputeReportQuery>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let report = get_dispute_report(&state.pool, q.from, q.to, Utc::now()).await?;
+    Ok(Json(project(auth.key.role, &report)?))
+}
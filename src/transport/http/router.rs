@@ -1,27 +1,254 @@
 use axum::{
     Router,
     extract::DefaultBodyLimit,
-    routing::{get, post},
+    middleware,
+    routing::{delete, get, post, put},
 };
-use std::time::Duration;
 use tower_http::timeout::TimeoutLayer;
 
 use crate::{
     AppState,
-    adapters::stripe::webhook::wh_handler,
-    transport::http::payment::lookup_handler::{payment_by_id, payment_list},
+    adapters::{
+        paypal::webhook::paypal_wh_handler,
+        stripe::webhook::{dev_simulate_handler, wh_handler},
+    },
+    transport::http::{
+        admin::{
+            alert_handler, api_key_handler, audit_handler, backfill_handler, budget_handler,
+            config_handler, currency_terms_handler, event_gap_handler, export_handler, job_handler,
+            lock_handler, maintenance_handler, payment_handler, period_handler,
+            reconciliation_handler, replay_handler, retention_handler, rollup_handler,
+            saved_filter_handler, shadow_handler, vector_handler, webhook_endpoint_handler,
+        },
+        checkout::attempts_handler::checkout_attempts,
+        event::status_handler::event_status,
+        ingest::batch_handler::ingest_batch,
+        load_shed::{self, LoadShedder},
+        maintenance::{self, health},
+        meta::state_machine_handler::state_machine,
+        outbox::events_handler,
+        payment::{
+            export_handler::payment_export,
+            ledger_handler::payment_ledger,
+            lookup_handler::{payment_audit, payment_by_id, payment_list, payment_timeline},
+            manual_handler::create_payment,
+            refund_handler::refundable_balance,
+            tag_handler::{payment_tags, tag_payment},
+        },
+        payout::lookup_handler::{payout_by_id, payout_list},
+        rate_limit::{self, RateLimiter},
+        report::{daily_handler::daily_report, dispute_handler::dispute_report},
+    },
+    transport::integrations,
 };
 
 pub fn build(state: AppState) -> Router {
-    Router::new()
-        .route("/", get(|| async { "ok" }))
+    let http = state.settings.http.clone();
+    // Providers can be noisy; a runaway sender gets 429s before its
+    // deliveries reach the pipeline. Under maintenance every delivery gets
+    // a 503.
+    let webhooks = Router::new()
         .route("/webhook", post(wh_handler))
+        .route("/webhooks/paypal", post(paypal_wh_handler));
+    // Unsigned events, for local development; absent unless `DEV_ROUTES`.
+    let webhooks = if state.settings.dev_routes {
+        webhooks.route("/dev/simulate", post(dev_simulate_handler))
+    } else {
+        webhooks
+    };
+    let webhooks = webhooks
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            maintenance::hold_webhooks,
+        ))
+        .layer(middleware::from_fn_with_state(
+            RateLimiter::new(http.webhook_rate_limit),
+            rate_limit::limit,
+        ))
+        .layer(DefaultBodyLimit::max(http.webhook_body_limit_bytes));
+    let admin = Router::new()
+        .route("/admin/alerts/test", post(alert_handler::test))
+        .route("/admin/audit/tail", get(audit_handler::tail))
+        .route(
+            "/admin/api-keys",
+            get(api_key_handler::keys).post(api_key_handler::create),
+        )
+        .route("/admin/api-keys/{id}/revoke", post(api_key_handler::revoke))
+        .route(
+            "/admin/config",
+            get(config_handler::get_config).put(config_handler::put_config),
+        )
+        .route(
+            "/admin/backfills",
+            get(backfill_handler::runs).post(backfill_handler::start),
+        )
+        .route("/admin/backfills/{id}", get(backfill_handler::run_by_id))
+        .route(
+            "/admin/backfills/{id}/resume",
+            post(backfill_handler::resume),
+        )
+        .route("/admin/retention/run", post(retention_handler::run))
+        .route("/admin/rollups/recompute", post(rollup_handler::recompute))
+        .route("/admin/jobs", get(job_handler::jobs))
+        .route("/admin/jobs/requeue", post(job_handler::requeue))
+        .route("/admin/jobs/{id}", get(job_handler::job))
+        .route("/admin/jobs/{id}/retry", post(job_handler::retry))
+        .route("/admin/locks", get(lock_handler::locks))
+        .route(
+            "/admin/locks/{pid}/terminate",
+            post(lock_handler::terminate),
+        )
+        .route(
+            "/admin/maintenance",
+            get(maintenance_handler::status)
+                .put(maintenance_handler::enable)
+                .delete(maintenance_handler::disable),
+        )
+        .route("/admin/event-gaps", get(event_gap_handler::gaps))
+        .route("/admin/event-gaps/scan", post(event_gap_handler::scan))
+        .route("/admin/exports/{id}", get(export_handler::export_by_id))
+        .route(
+            "/admin/payments/{id}/transition",
+            post(payment_handler::transition),
+        )
+        .route("/admin/periods", get(period_handler::periods))
+        .route("/admin/periods/close", post(period_handler::close))
+        .route("/admin/periods/reopen", post(period_handler::reopen))
+        .route(
+            "/admin/periods/eod-cutoff",
+            get(period_handler::cutoff).put(period_handler::set_cutoff),
+        )
+        .route(
+            "/admin/period-adjustments",
+            get(period_handler::adjustments),
+        )
+        .route(
+            "/admin/period-adjustments/{id}/apply",
+            post(period_handler::apply),
+        )
+        .route(
+            "/admin/period-adjustments/{id}/dismiss",
+            post(period_handler::dismiss),
+        )
+        .route(
+            "/admin/payments-v2/backfill",
+            post(shadow_handler::backfill),
+        )
+        .route("/admin/payments-v2/verify", post(shadow_handler::verify))
+        .route("/admin/provider-budgets", get(budget_handler::usage))
+        .route(
+            "/admin/currency-terms",
+            get(currency_terms_handler::terms).post(currency_terms_handler::schedule),
+        )
+        .route(
+            "/admin/currency-terms/{id}",
+            delete(currency_terms_handler::cancel),
+        )
+        .route("/admin/saved-filters", get(saved_filter_handler::filters))
+        .route(
+            "/admin/saved-filters/{name}",
+            get(saved_filter_handler::filter_by_name)
+                .put(saved_filter_handler::save)
+                .delete(saved_filter_handler::delete),
+        )
+        .route("/admin/replays", post(replay_handler::replay))
+        .route("/admin/simulate-event", post(replay_handler::simulate))
+        .route(
+            "/admin/events/{event_id}/replay",
+            post(replay_handler::replay_event),
+        )
+        .route("/admin/test-vectors", get(vector_handler::export))
+        .route(
+            "/admin/stripe/webhook-endpoint",
+            post(webhook_endpoint_handler::setup),
+        )
+        .route(
+            "/admin/stripe/webhook-endpoint/rotate",
+            post(webhook_endpoint_handler::rotate),
+        )
+        .route(
+            "/admin/reconciliations",
+            get(reconciliation_handler::runs).post(reconciliation_handler::trigger),
+        )
+        .route(
+            "/admin/reconciliations/review",
+            get(reconciliation_handler::review),
+        )
+        .route(
+            "/admin/reconciliations/{id}",
+            get(reconciliation_handler::run_by_id),
+        );
+    // Admin routes that only read, heavily, give way like the reads below
+    // whatever their method.
+    let heavy = Router::new()
+        .route(
+            "/admin/exports",
+            get(export_handler::exports).post(export_handler::start),
+        )
+        .route(
+            "/admin/saved-filters/{name}/payments",
+            get(saved_filter_handler::payments),
+        )
+        .merge(integrations::admin_routes());
+    let shedder = LoadShedder::new(http.load_shed);
+    let shedding = !http.load_shed.max_acquire_latency.is_zero();
+    let heavy = if shedding {
+        heavy.route_layer(middleware::from_fn_with_state(
+            shedder.clone(),
+            load_shed::shed_any,
+        ))
+    } else {
+        heavy
+    };
+    let admin = admin
+        .merge(heavy)
+        .layer(DefaultBodyLimit::max(http.admin_body_limit_bytes));
+
+    // Reads give way to ingestion: under database pressure they get 429s,
+    // while webhooks, writes and the rest of `/admin/*` go through.
+    let reads = Router::new()
+        .route("/checkouts/{key}/attempts", get(checkout_attempts))
+        .route("/events/{event_id}/status", get(event_status))
+        .route("/payments/export", get(payment_export))
         .route("/payments/{id}", get(payment_by_id))
-        .route("/payments", get(payment_list))
-        .layer(DefaultBodyLimit::max(64 * 1024))
+        .route("/payments/{id}/audit", get(payment_audit))
+        .route("/payments/{id}/ledger", get(payment_ledger))
+        .route("/payments/{id}/refundable", get(refundable_balance))
+        .route("/payments/{id}/tags", get(payment_tags).post(tag_payment))
+        .route("/payments/{id}/timeline", get(payment_timeline))
+        .route("/payments", get(payment_list).post(create_payment))
+        .route("/payouts/{id}", get(payout_by_id))
+        .route("/payouts", get(payout_list))
+        .route("/outbox/events", get(events_handler::events))
+        .route("/outbox/consumers", get(events_handler::consumers))
+        .route("/reports/daily", get(daily_report))
+        .route("/reports/disputes", get(dispute_report));
+    let reads = if shedding {
+        reads.route_layer(middleware::from_fn_with_state(shedder, load_shed::shed))
+    } else {
+        reads
+    };
+
+    // Everything but `/`, the webhooks and the state machine graph needs an
+    // API key; each handler names the scope it takes.
+    Router::new()
+        .route("/", get(health))
+        .route(
+            "/ingest/batch",
+            post(ingest_batch).layer(DefaultBodyLimit::max(http.ingest_body_limit_bytes)),
+        )
+        .route("/meta/state-machine", get(state_machine))
+        .route(
+            "/outbox/consumers/{consumer}/offset",
+            put(events_handler::commit_offset),
+        )
+        .merge(reads)
+        .merge(webhooks)
+        .merge(admin)
+        .layer(DefaultBodyLimit::max(http.body_limit_bytes))
         .layer(TimeoutLayer::with_status_code(
             axum::http::StatusCode::REQUEST_TIMEOUT,
-            Duration::from_secs(30),
+            http.request_timeout,
         ))
         .with_state(state)
 }
@@ -0,0 +1,166 @@
+use {
+    axum::{
+        Router,
+        body::{Body, to_bytes},
+        http::{HeaderMap, Method, Request, StatusCode, header::CONTENT_TYPE},
+    },
+    chrono::{DateTime, TimeDelta, Utc},
+    tower::ServiceExt,
+};
+
+/// Signs deliveries for one webhook endpoint the way its provider does, so
+/// the negative checks can build requests that are right in every respect
+/// but the one under test.
+pub trait WebhookSigner: Send + Sync {
+    /// Where the endpoint is routed, e.g. `/webhook`.
+    fn path(&self) -> &str;
+
+    /// An event the endpoint accepts once signed. Each call should return
+    /// a new event id.
+    fn sample_event(&self) -> serde_json::Value;
+
+    /// Headers signing `body` as sent at `signed_at`.
+    fn sign(&self, body: &str, signed_at: DateTime<Utc>) -> HeaderMap;
+}
+
+/// A delivery every webhook endpoint must refuse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NegativeCase {
+    /// No signature headers at all.
+    MissingSignature,
+    /// Signed, then the body changed: the signature no longer matches.
+    InvalidSignature,
+    /// Correctly signed a day ago: a captured delivery sent again.
+    ExpiredTimestamp,
+    /// Correctly signed, but over the endpoint's body limit.
+    Oversized,
+    /// Correctly signed, but not a JSON document.
+    MalformedPayload,
+}
+
+impl NegativeCase {
+    pub const ALL: [Self; 5] = [
+        Self::MissingSignature,
+        Self::InvalidSignature,
+        Self::ExpiredTimestamp,
+        Self::Oversized,
+        Self::MalformedPayload,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::MissingSignature => "missing_signature",
+            Self::InvalidSignature => "invalid_signature",
+            Self::ExpiredTimestamp => "expired_timestamp",
+            Self::Oversized => "oversized",
+            Self::MalformedPayload => "malformed_payload",
+        }
+    }
+
+    /// The request for this case. `body_limit` is the endpoint's body limit
+    /// in bytes (`WEBHOOK_BODY_LIMIT_BYTES` for the built-in ones).
+    pub fn request(self, signer: &dyn WebhookSigner, body_limit: usize) -> Request<Body> {
+        let now = Utc::now();
+        let event = signer.sample_event();
+        let body = event.to_string();
+        let (headers, body) = match self {
+            Self::MissingSignature => (HeaderMap::new(), body),
+            Self::InvalidSignature => {
+                let mut tampered = event;
+                tampered["tampered"] = true.into();
+                (signer.sign(&body, now), tampered.to_string())
+            }
+            Self::ExpiredTimestamp => (signer.sign(&body, now - TimeDelta::days(1)), body),
+            Self::Oversized => {
+                let mut padded = event;
+                padded["padding"] = "x".repeat(body_limit).into();
+                let body = padded.to_string();
+                (signer.sign(&body, now), body)
+            }
+            Self::MalformedPayload => {
+                // Cut off mid-document, as a broken proxy might.
+                let body = String::from_utf8_lossy(&body.as_bytes()[..body.len() / 2]).into_owned();
+                (signer.sign(&body, now), body)
+            }
+        };
+        delivery(signer.path(), headers, body)
+    }
+
+    /// Whether `status` is the refusal this case calls for: 413 for an
+    /// oversized body, any other 4xx for the rest. A 5xx isn't one; the
+    /// provider would retry it, and it would page someone.
+    pub fn refused(self, status: StatusCode) -> bool {
+        match self {
+            Self::Oversized => status == StatusCode::PAYLOAD_TOO_LARGE,
+            _ => status.is_client_error(),
+        }
+    }
+}
+
+/// A check an endpoint failed, with the response it gave.
+#[derive(Debug)]
+pub struct SecurityFinding {
+    /// A [`NegativeCase`] name, or `genuine_delivery`.
+    pub check: &'static str,
+    pub status: StatusCode,
+    pub body: String,
+}
+
+/// Send every [`NegativeCase`] to `router`, then a genuine delivery, which
+/// must still be accepted: a refused delivery mustn't use up anything a
+/// real one needs. Returns the checks that failed, none for an endpoint
+/// fit to enable.
+pub async fn check_webhook_security(
+    router: Router,
+    signer: &dyn WebhookSigner,
+    body_limit: usize,
+) -> Vec<SecurityFinding> {
+    let mut findings = Vec::new();
+    for case in NegativeCase::ALL {
+        let (status, body) = send(&router, case.request(signer, body_limit)).await;
+        if !case.refused(status) {
+            findings.push(SecurityFinding {
+                check: case.as_str(),
+                status,
+                body,
+            });
+        }
+    }
+
+    let body = signer.sample_event().to_string();
+    let genuine = delivery(signer.path(), signer.sign(&body, Utc::now()), body);
+    let (status, body) = send(&router, genuine).await;
+    if !status.is_success() {
+        findings.push(SecurityFinding {
+            check: "genuine_delivery",
+            status,
+            body,
+        });
+    }
+    findings
+}
+
+fn delivery(path: &str, headers: HeaderMap, body: String) -> Request<Body> {
+    let mut request = Request::builder()
+        .method(Method::POST)
+        .uri(path)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(body))
+        .expect("a valid request");
+    request.headers_mut().extend(headers);
+    request
+}
+
+async fn send(router: &Router, request: Request<Body>) -> (StatusCode, String) {
+    let response = router
+        .clone()
+        .oneshot(request)
+        .await
+        .expect("routers are infallible");
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX)
+        .await
+        .map(|b| String::from_utf8_lossy(&b).into_owned())
+        .unwrap_or_default();
+    (status, body)
+}
@@ -0,0 +1,24 @@
+//! The optional integrations, each compiled in by the cargo feature of the
+//! same name. The router mounts what's here without knowing which are
+//! built, so feature checks stay in this module and the integrations'
+//! own.
+
+use {crate::AppState, axum::Router};
+
+/// Optional features in this build, for the startup log.
+pub const ENABLED: &[&str] = &[
+    #[cfg(feature = "graphql")]
+    "graphql",
+    #[cfg(feature = "otel")]
+    "otel",
+    #[cfg(feature = "parquet")]
+    "parquet",
+];
+
+/// Routes that go under `/admin`'s auth and body limit.
+pub fn admin_routes() -> Router<AppState> {
+    let routes = Router::new();
+    #[cfg(feature = "graphql")]
+    let routes = routes.merge(super::graphql::routes());
+    routes
+}
@@ -0,0 +1,121 @@
+mod common;
+
+use axum::{Json, Router, extract::State, routing::post};
+use common::*;
+use fin_sync::domain::alert::AlertDestination;
+use fin_sync::domain::hook::HookRegistry;
+use fin_sync::domain::payment::PaymentStatus;
+use fin_sync::infra::alert::WebhookSink;
+use fin_sync::infra::postgres::hook_repo;
+use fin_sync::services::hooks::dispatch_once;
+use fin_sync::services::notify::{AnomalyAlerts, Notifier};
+use fin_sync::services::payment::pipeline::process_payment_event;
+use std::sync::{Arc, Mutex};
+
+type Received = Arc<Mutex<Vec<(String, serde_json::Value)>>>;
+
+/// A local endpoint per destination path that keeps every body posted to it.
+async fn receiver() -> (String, Received) {
+    let received = Received::default();
+    let record = |path: &'static str| {
+        post(
+            move |State(received): State<Received>, Json(body): Json<serde_json::Value>| async move {
+                received.lock().unwrap().push((path.to_string(), body));
+            },
+        )
+    };
+    let app = Router::new()
+        .route("/slack", record("slack"))
+        .route("/pagerduty", record("pagerduty"))
+        .with_state(received.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    (url, received)
+}
+
+fn destination(value: serde_json::Value) -> AlertDestination {
+    let destination: AlertDestination = serde_json::from_value(value).unwrap();
+    destination.validate().unwrap();
+    destination
+}
+
+// ── 85. anomalies_alert_each_destination_in_its_format ─────────────────────
+
+#[tokio::test]
+async fn anomalies_alert_each_destination_in_its_format() {
+    let pool = setup_pool("fin_sync_test_alert").await;
+    let (url, received) = receiver().await;
+
+    let slack = destination(serde_json::json!({
+        "name": "slack", "url": format!("{url}/slack"), "format": "slack",
+    }));
+    let pagerduty = destination(serde_json::json!({
+        "name": "pagerduty", "url": format!("{url}/pagerduty"),
+        "kinds": ["payment_anomaly"], "format": "pagerduty",
+        "vars": {"routing_key": "R0UT1NG", "severity": "error"},
+    }));
+    let notifier = Notifier::new("https://fin-sync.example.com")
+        .with_sink(Arc::new(WebhookSink::new(slack)))
+        .with_sink(Arc::new(WebhookSink::new(pagerduty)));
+    let mut hooks = HookRegistry::default();
+    hooks.register(Arc::new(AnomalyAlerts::new(notifier.clone())));
+    hook_repo::subscribe(&pool, &hooks.names()).await.unwrap();
+
+    for (event, status, ts) in [
+        ("evt_alert_1", PaymentStatus::Succeeded, 1000),
+        ("evt_alert_2", PaymentStatus::Pending, 1001),
+    ] {
+        let p = make_payment("pi_alert", event, status, ts);
+        process_payment_event(&pool, &p, "test").await.unwrap();
+    }
+    while dispatch_once(&pool, &hooks).await.unwrap() > 0 {}
+
+    // Only the refused transition alerts, once per destination.
+    let bodies = received.lock().unwrap().clone();
+    assert_eq!(bodies.len(), 2, "{bodies:?}");
+    let (_, slack) = bodies.iter().find(|(path, _)| path == "slack").unwrap();
+    let text = slack["text"].as_str().unwrap();
+    assert!(text.starts_with("*Anomaly on pi_alert: succeeded -> pending refused*\n"));
+    assert!(text.contains("https://fin-sync.example.com/payments/pi_alert/audit"));
+    let (_, pd) = bodies.iter().find(|(path, _)| path == "pagerduty").unwrap();
+    assert_eq!(pd["routing_key"], "R0UT1NG");
+    assert_eq!(pd["payload"]["severity"], "error");
+    assert_eq!(pd["payload"]["custom_details"]["event_id"], "evt_alert_2");
+
+    // A test fire goes through the same routing: PagerDuty only takes anomalies.
+    received.lock().unwrap().clear();
+    let alert = notifier.sample_alert("reconciliation_summary").unwrap();
+    let outcome: Vec<_> = notifier
+        .deliver(&alert, None)
+        .await
+        .into_iter()
+        .map(|d| (d.sink, d.status))
+        .collect();
+    assert_eq!(
+        outcome,
+        [
+            ("log".to_string(), "delivered"),
+            ("slack".to_string(), "delivered"),
+            ("pagerduty".to_string(), "not_routed"),
+        ]
+    );
+    let bodies = received.lock().unwrap().clone();
+    assert_eq!(bodies.len(), 1);
+    assert!(
+        bodies[0].1["text"]
+            .as_str()
+            .unwrap()
+            .starts_with("*[test] ")
+    );
+    assert!(notifier.sample_alert("payment_anomalies").is_none());
+
+    // A destination that is down is reported, not hidden.
+    let down = destination(serde_json::json!({"name": "down", "url": format!("{url}/gone")}));
+    let notifier = notifier.with_sink(Arc::new(WebhookSink::new(down)));
+    let alert = notifier.sample_alert("payment_anomaly").unwrap();
+    let outcome = notifier.deliver(&alert, Some("down")).await;
+    assert_eq!(outcome.len(), 1);
+    assert_eq!(outcome[0].status, "failed");
+    assert!(outcome[0].error.as_deref().unwrap().contains("404"));
+}
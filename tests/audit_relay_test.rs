@@ -0,0 +1,73 @@
+mod common;
+
+use common::*;
+use fin_sync::domain::payment::PaymentStatus;
+use fin_sync::infra::postgres::audit_relay_repo;
+use fin_sync::services::audit_relay::relay_once;
+use fin_sync::services::payment::pipeline::process_payment_event;
+
+async fn outbox_len(pool: &sqlx::PgPool) -> i64 {
+    sqlx::query_scalar("SELECT COUNT(*) FROM audit_outbox")
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+// ── 51. audit_relay_ships_entries_at_least_once ────────────────────────────
+
+#[tokio::test]
+async fn audit_relay_ships_entries_at_least_once() {
+    let pool = setup_pool("fin_sync_test_audit_relay").await;
+    let target = setup_audit_pool("fin_sync_test_audit_target").await;
+
+    // Nothing is queued until a target is registered.
+    let early = make_payment(
+        "pi_relay_early",
+        "evt_relay_early",
+        PaymentStatus::Pending,
+        1000,
+    );
+    process_payment_event(&pool, &early, "test").await.unwrap();
+    assert_eq!(outbox_len(&pool).await, 0);
+
+    audit_relay_repo::enable(&pool).await.unwrap();
+    let p1 = make_payment("pi_relay", "evt_relay_1", PaymentStatus::Pending, 1000);
+    process_payment_event(&pool, &p1, "test").await.unwrap();
+    let p2 = make_payment("pi_relay", "evt_relay_2", PaymentStatus::Succeeded, 2000);
+    process_payment_event(&pool, &p2, "worker:stripe")
+        .await
+        .unwrap();
+    assert_eq!(outbox_len(&pool).await, 2);
+
+    // Simulate a crash after shipping but before the outbox was cleared:
+    // the target already holds one of the rows.
+    let mut tx = pool.begin().await.unwrap();
+    let first = audit_relay_repo::claim_batch(&mut tx, 1).await.unwrap();
+    tx.rollback().await.unwrap();
+    assert_eq!(audit_relay_repo::ship(&target, &first).await.unwrap(), 1);
+
+    assert_eq!(relay_once(&pool, &target).await.unwrap(), 2);
+    assert_eq!(outbox_len(&pool).await, 0);
+    assert_eq!(relay_once(&pool, &target).await.unwrap(), 0);
+
+    let shipped: Vec<(String, String)> = sqlx::query_as(
+        "SELECT event_id, actor FROM audit_log WHERE external_id = 'pi_relay' ORDER BY created_at",
+    )
+    .fetch_all(&target)
+    .await
+    .unwrap();
+    assert_eq!(
+        shipped,
+        vec![
+            ("evt_relay_1".to_string(), "test".to_string()),
+            ("evt_relay_2".to_string(), "worker:stripe".to_string()),
+        ]
+    );
+    assert_eq!(count_audit_entries(&target, "pi_relay_early").await, 0);
+
+    let total: i64 = sqlx::query_scalar("SELECT shipped_total FROM audit_relay_state")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(total, 2);
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use common::*;
+use fin_sync::domain::audit::AuditTailFilter;
+use fin_sync::domain::payment::PaymentStatus;
+use fin_sync::services::audit_tail::{AuditTail, tail_audit};
+use fin_sync::services::payment::pipeline::process_payment_event;
+use std::time::Duration;
+use tokio_stream::StreamExt;
+
+// ── 86. audit_tail_follows_matching_entries ────────────────────────────────
+
+#[tokio::test]
+async fn audit_tail_follows_matching_entries() {
+    let pool = setup_pool("fin_sync_test_audit_tail").await;
+    let filter = AuditTailFilter {
+        external_id: Some("pi_tail".into()),
+        ..Default::default()
+    };
+    let mut tail = tail_audit(pool.clone(), filter).await.unwrap().unwrap();
+    let status_only = AuditTailFilter {
+        action: Some("status_changed".into()),
+        actor: Some("tes".into()),
+        ..Default::default()
+    };
+    let mut changes = tail_audit(pool.clone(), status_only)
+        .await
+        .unwrap()
+        .unwrap();
+
+    for (id, event, status, ts) in [
+        ("pi_other", "evt_tail_0", PaymentStatus::Pending, 1000),
+        ("pi_tail", "evt_tail_1", PaymentStatus::Pending, 1000),
+        ("pi_tail", "evt_tail_2", PaymentStatus::Succeeded, 1001),
+    ] {
+        let p = make_payment(id, event, status, ts);
+        process_payment_event(&pool, &p, "test").await.unwrap();
+    }
+
+    let next = async |tail: &mut AuditTail| {
+        tokio::time::timeout(Duration::from_secs(5), tail.next())
+            .await
+            .expect("no audit entry within 5s")
+            .unwrap()
+            .unwrap()
+    };
+    let first = next(&mut tail).await;
+    assert_eq!(
+        (first.event_id.as_str(), first.action.as_str()),
+        ("evt_tail_1", "created")
+    );
+    let second = next(&mut tail).await;
+    assert_eq!(second.action, "status_changed");
+    assert_eq!(second.detail["new_status"], "succeeded");
+
+    let change = next(&mut changes).await;
+    assert_eq!(change.external_id.as_deref(), Some("pi_tail"));
+    assert_eq!(change.event_id, "evt_tail_2");
+
+    // Closing a tail frees its slot and its connection.
+    drop(tail);
+    drop(changes);
+    let mut open = Vec::new();
+    for _ in 0..4 {
+        let tail = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                if let Some(tail) = tail_audit(pool.clone(), AuditTailFilter::default())
+                    .await
+                    .unwrap()
+                {
+                    break tail;
+                }
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .expect("tail slots not released");
+        open.push(tail);
+    }
+    assert!(
+        tail_audit(pool.clone(), AuditTailFilter::default())
+            .await
+            .unwrap()
+            .is_none()
+    );
+}
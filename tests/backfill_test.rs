@@ -0,0 +1,90 @@
+mod common;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use common::*;
+use fin_sync::domain::id::ExternalId;
+use fin_sync::domain::payment::{PaymentDirection, PaymentStatus};
+use fin_sync::domain::provider::FetchedPayment;
+use fin_sync::services::backfill::{execute_run, get_run, resume_run, start_run};
+
+fn history(prefix: &str) -> Vec<FetchedPayment> {
+    let pi = |n: u32, status| fetched_payment(&format!("pi_{prefix}_{n}"), status, 1000);
+    vec![
+        pi(1, PaymentStatus::Succeeded),
+        pi(2, PaymentStatus::Failed),
+        pi(3, PaymentStatus::Succeeded),
+        FetchedPayment {
+            direction: PaymentDirection::Outbound,
+            parent_external_id: Some(ExternalId::new(format!("pi_{prefix}_1")).unwrap()),
+            ..fetched_payment(&format!("re_{prefix}_1"), PaymentStatus::Succeeded, 400)
+        },
+    ]
+}
+
+fn window() -> (DateTime<Utc>, DateTime<Utc>) {
+    let until = Utc::now();
+    (until - TimeDelta::days(365), until)
+}
+
+// ── 47. backfill_imports_history_and_is_idempotent ─────────────────────────
+
+#[tokio::test]
+async fn backfill_imports_history_and_is_idempotent() {
+    let pool = setup_pool("fin_sync_test_backfill").await;
+    let provider = StubProvider::listing(history("bf_imp"));
+    let (since, until) = window();
+
+    let run_id = start_run(&pool, "stripe", since, until).await.unwrap();
+    execute_run(&pool, &provider, run_id).await.unwrap();
+
+    let run = get_run(&pool, run_id).await.unwrap().unwrap();
+    assert_eq!(run.status, "completed");
+    assert_eq!((run.pages, run.created, run.unchanged), (2, 4, 0));
+    assert!(run.cursor.is_none());
+
+    let refund = get_payment(&pool, "re_bf_imp_1").await.unwrap();
+    assert_eq!(refund.parent_external_id.as_deref(), Some("pi_bf_imp_1"));
+    assert_eq!(refund.last_event_id, "evt_backfill_re_bf_imp_1_succeeded");
+    let audits = get_audit_entries(&pool, "pi_bf_imp_2").await;
+    assert_eq!(audits.len(), 1);
+    assert_eq!(audits[0].action, "created");
+
+    // A second pass over the same history changes nothing.
+    let rerun = start_run(&pool, "stripe", since, until).await.unwrap();
+    execute_run(&pool, &provider, rerun).await.unwrap();
+    let rerun = get_run(&pool, rerun).await.unwrap().unwrap();
+    assert_eq!((rerun.created, rerun.unchanged), (0, 4));
+    assert_eq!(count_audit_entries(&pool, "pi_bf_imp_2").await, 1);
+}
+
+// ── 48. backfill_resumes_from_checkpoint ───────────────────────────────────
+
+#[tokio::test]
+async fn backfill_resumes_from_checkpoint() {
+    let pool = setup_pool("fin_sync_test_backfill").await;
+    let (since, until) = window();
+    let run_id = start_run(&pool, "stripe", since, until).await.unwrap();
+
+    let flaky = StubProvider {
+        fail_from: Some(2),
+        ..StubProvider::listing(history("bf_res"))
+    };
+    assert!(execute_run(&pool, &flaky, run_id).await.is_err());
+    let run = get_run(&pool, run_id).await.unwrap().unwrap();
+    assert_eq!(run.status, "failed");
+    assert_eq!((run.pages, run.created), (1, 2));
+    assert_eq!(run.cursor.as_deref(), Some("2"));
+
+    let claimed = resume_run(&pool, run_id).await.unwrap().unwrap();
+    assert_eq!(claimed.status, "running");
+    let healthy = StubProvider::listing(history("bf_res"));
+    execute_run(&pool, &healthy, run_id).await.unwrap();
+
+    let run = get_run(&pool, run_id).await.unwrap().unwrap();
+    assert_eq!(run.status, "completed");
+    // Only the second page was fetched again.
+    assert_eq!((run.pages, run.created, run.unchanged), (2, 4, 0));
+
+    // Completed runs can't be resumed.
+    assert!(resume_run(&pool, run_id).await.is_err());
+}
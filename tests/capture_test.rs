@@ -0,0 +1,122 @@
+mod common;
+
+use common::*;
+use fin_sync::adapters::stripe::client::convert_capture_event;
+use fin_sync::domain::capture::NewCapture;
+use fin_sync::domain::id::{EventId, ExternalId};
+use fin_sync::domain::payment::{PaymentStatus, ProcessResult};
+use fin_sync::services::capture::process_capture_event;
+use fin_sync::services::payment::lookup::get_payment_detail;
+use fin_sync::services::payment::pipeline::process_payment_event;
+
+/// `charge.captured` for the charge of `pi_multi`, authorized for 5000,
+/// after a capture that took the captured total to `total`: the charge as
+/// Stripe sends it, without `previous_attributes`.
+fn captured(event_id: &str, total: i64) -> NewCapture {
+    let event = serde_json::json!({
+        "id": event_id, "type": "charge.captured", "created": 1_700_000_000 + total,
+        "data": {
+            "object": {
+                "id": "ch_multi", "object": "charge", "amount": 5000,
+                "amount_captured": total, "currency": "usd",
+                "payment_intent": "pi_multi", "captured": true,
+            },
+        },
+    });
+    convert_capture_event(
+        EventId::new(event_id).unwrap(),
+        "charge.captured",
+        1_700_000_000 + total,
+        &event,
+    )
+    .unwrap()
+}
+
+// ── 95. multicapture_accumulates_against_the_authorization ─────────────────
+
+#[tokio::test]
+async fn multicapture_accumulates_against_the_authorization() {
+    let pool = setup_pool("fin_sync_test_capture").await;
+
+    // The first capture arrives before the payment is recorded.
+    let r = process_capture_event(&pool, &captured("evt_cap_1", 2000), "test")
+        .await
+        .unwrap();
+    assert!(matches!(r, ProcessResult::Created(_)));
+    let pi = make_payment("pi_multi", "evt_pi_1", PaymentStatus::Pending, 1000);
+    process_payment_event(&pool, &pi, "test").await.unwrap();
+
+    let r = process_capture_event(&pool, &captured("evt_cap_2", 3500), "test")
+        .await
+        .unwrap();
+    assert!(matches!(r, ProcessResult::Created(_)));
+    // Redelivery of the same event.
+    let r = process_capture_event(&pool, &captured("evt_cap_2", 3500), "test")
+        .await
+        .unwrap();
+    assert!(matches!(r, ProcessResult::Duplicate));
+
+    let detail = get_payment_detail(&pool, ExternalId::new("pi_multi").unwrap())
+        .await
+        .unwrap()
+        .unwrap();
+    let captures = detail.captures.unwrap();
+    assert_eq!(
+        (captures.authorized, captures.captured, captures.capturable),
+        (5000, 3500, 1500)
+    );
+    assert!(!captures.over_captured);
+    let amounts: Vec<_> = captures.captures.iter().map(|c| c.amount).collect();
+    assert_eq!(amounts, [2000, 1500]);
+
+    // A capture delivered after a later one is already covered by it.
+    let r = process_capture_event(&pool, &captured("evt_cap_late", 3000), "test")
+        .await
+        .unwrap();
+    assert!(matches!(r, ProcessResult::StaleIgnored(_)));
+    let detail = get_payment_detail(&pool, ExternalId::new("pi_multi").unwrap())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(detail.captures.unwrap().captured, 3500);
+
+    // More than the authorization held: recorded, and flagged.
+    let r = process_capture_event(&pool, &captured("evt_cap_3", 6000), "test")
+        .await
+        .unwrap();
+    assert!(matches!(r, ProcessResult::Anomaly(_)));
+    let detail = get_payment_detail(&pool, ExternalId::new("pi_multi").unwrap())
+        .await
+        .unwrap()
+        .unwrap();
+    let captures = detail.captures.unwrap();
+    assert_eq!((captures.captured, captures.capturable), (6000, 0));
+    assert!(captures.over_captured);
+
+    // Every capture is on the payment's audit trail with its running total.
+    let audit: Vec<_> = get_audit_entries(&pool, "pi_multi")
+        .await
+        .into_iter()
+        .filter(|e| e.action == "captured")
+        .map(|e| {
+            (
+                e.detail["amount"].as_i64().unwrap(),
+                e.detail["captured"].as_i64().unwrap(),
+                e.detail["anomaly"].as_bool().unwrap_or(false),
+            )
+        })
+        .collect();
+    assert_eq!(
+        audit,
+        [(2000, 2000, false), (1500, 3500, false), (2500, 6000, true)]
+    );
+
+    // Payments captured in one go through the pipeline have no summary.
+    let plain = make_payment("pi_plain", "evt_plain_1", PaymentStatus::Succeeded, 1000);
+    process_payment_event(&pool, &plain, "test").await.unwrap();
+    let detail = get_payment_detail(&pool, ExternalId::new("pi_plain").unwrap())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(detail.captures.is_none());
+}
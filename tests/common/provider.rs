@@ -0,0 +1,84 @@
+use chrono::{DateTime, Utc};
+use fin_sync::domain::error::PortError;
+use fin_sync::domain::id::ExternalId;
+use fin_sync::domain::payment::PaymentStatus;
+use fin_sync::domain::provider::{FetchedPayment, ListCursor, PaymentPage, PaymentProvider};
+use fin_sync::testing::fetched_payment;
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Answers every fetch with a succeeded payment of `cents` for the requested
+/// id, and lists `payments` two per page. Pages at or past `fail_from`
+/// error out, to simulate the provider going away mid-run. Counts every
+/// call that reaches it.
+pub struct StubProvider {
+    pub source: &'static str,
+    pub cents: i64,
+    pub payments: Vec<FetchedPayment>,
+    pub fail_from: Option<usize>,
+    pub calls: AtomicUsize,
+}
+
+impl Default for StubProvider {
+    fn default() -> Self {
+        Self {
+            source: "stripe",
+            cents: 900,
+            payments: Vec::new(),
+            fail_from: None,
+            calls: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl StubProvider {
+    /// Lists `payments`, two per page.
+    pub fn listing(payments: Vec<FetchedPayment>) -> Self {
+        Self {
+            payments,
+            ..Default::default()
+        }
+    }
+
+    pub fn calls(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+}
+
+impl PaymentProvider for StubProvider {
+    fn source(&self) -> &'static str {
+        self.source
+    }
+
+    fn fetch_payment(
+        &self,
+        id: &ExternalId,
+    ) -> Pin<Box<dyn Future<Output = Result<FetchedPayment, PortError>> + Send + '_>> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        let fetched = fetched_payment(id.as_str(), PaymentStatus::Succeeded, self.cents);
+        Box::pin(async move { Ok(fetched) })
+    }
+
+    fn list_payments(
+        &self,
+        _since: DateTime<Utc>,
+        _until: DateTime<Utc>,
+        cursor: Option<ListCursor>,
+    ) -> Pin<Box<dyn Future<Output = Result<PaymentPage, PortError>> + Send + '_>> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        Box::pin(async move {
+            let start: usize = cursor.map(|c| c.as_str().parse().unwrap()).unwrap_or(0);
+            if self.fail_from.is_some_and(|f| start >= f) {
+                return Err(PortError::Unavailable("Stripe API: 503".into()));
+            }
+            let end = (start + 2).min(self.payments.len());
+            Ok(PaymentPage {
+                payments: self.payments[start..end].to_vec(),
+                next_cursor: (end < self.payments.len()).then(|| ListCursor::new(end.to_string())),
+            })
+        })
+    }
+}
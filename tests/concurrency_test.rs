@@ -38,7 +38,7 @@ async fn concurrent_duplicate_events() {
 
 // ── 27. concurrent_updates_same_external_id ────────────────────────────────
 // First create a pending payment, then fire 5 concurrent "succeeded" events
-// with different event_ids. Advisory lock serializes: 1 Updated, 4 Stale.
+// with different event_ids. Advisory lock serializes: 1 Updated, 4 not applied.
 
 #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
 async fn concurrent_updates_same_external_id() {
@@ -58,19 +58,23 @@ async fn concurrent_updates_same_external_id() {
     }
 
     let mut updated = 0;
-    let mut stale = 0;
+    let mut unchanged = 0;
     let mut anomaly = 0;
     for h in handles {
         match h.await.unwrap() {
             ProcessResult::Updated(_) => updated += 1,
-            ProcessResult::Stale(_) => stale += 1,
+            ProcessResult::Unchanged(_) | ProcessResult::StaleIgnored(_) => unchanged += 1,
             ProcessResult::Anomaly(_) => anomaly += 1,
             other => panic!("unexpected result: {other:?}"),
         }
     }
 
     assert_eq!(updated, 1, "exactly 1 Updated");
-    assert_eq!(stale + anomaly, 4, "4 Stale or Anomaly (all non-Updated)");
+    assert_eq!(
+        unchanged + anomaly,
+        4,
+        "4 Unchanged, StaleIgnored or Anomaly (all non-Updated)"
+    );
 
     let row = get_payment(&pool, "pi_cser").await.unwrap();
     assert_eq!(row.status, "succeeded");
@@ -89,6 +93,8 @@ async fn concurrent_passthrough_dedup() {
         handles.push(tokio::spawn(async move {
             let event = PassthroughEvent {
                 external_id: Some(ExternalId::new("pi_cpt").unwrap()),
+                source: "stripe".into(),
+                tenant_id: None,
                 event_id: EventId::new("evt_cpt_same").unwrap(),
                 event_type: "charge.created".into(),
                 provider_ts: 1000,
@@ -132,17 +138,18 @@ async fn advisory_lock_prevents_double_insert() {
     }
 
     let mut created = 0;
-    let mut stale = 0;
+    let mut unchanged = 0;
     for h in handles {
         match h.await.unwrap() {
             ProcessResult::Created(_) => created += 1,
-            ProcessResult::Stale(_) => stale += 1,
+            // Unchanged if the newer event came second, stale if the older.
+            ProcessResult::Unchanged(_) | ProcessResult::StaleIgnored(_) => unchanged += 1,
             other => panic!("unexpected result: {other:?}"),
         }
     }
 
     assert_eq!(created, 1, "exactly 1 Created");
-    assert_eq!(stale, 1, "exactly 1 Stale (same status)");
+    assert_eq!(unchanged, 1, "exactly 1 not applied (same status)");
     assert_eq!(
         count_payments(&pool, "pi_adv_lock").await,
         1,
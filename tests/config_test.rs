@@ -0,0 +1,73 @@
+mod common;
+
+use common::*;
+use fin_sync::domain::config::RuntimeConfig;
+use fin_sync::services::config::{RuntimeConfigHandle, load, sync_once, update_config};
+
+// ── 45. update_config_persists_audits_and_syncs ────────────────────────────
+
+#[tokio::test]
+async fn update_config_persists_audits_and_syncs() {
+    let pool = setup_pool("fin_sync_test_config").await;
+    let handle = load(&pool).await.unwrap();
+    let replica = RuntimeConfigHandle::default();
+    let before = handle.current().version;
+
+    let next = RuntimeConfig {
+        worker_batch_size: 25,
+        ..Default::default()
+    };
+    let saved = update_config(&pool, &handle, next.clone(), "admin:ops")
+        .await
+        .unwrap();
+    assert_eq!(saved.version, before + 1);
+    assert_eq!(handle.current().config, next);
+
+    let (actor, detail): (String, serde_json::Value) = sqlx::query_as(
+        "SELECT actor, detail FROM audit_log WHERE event_id = $1 AND action = 'config_changed'",
+    )
+    .bind(format!("config:{}", saved.version))
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(actor, "admin:ops");
+    assert_eq!(
+        detail["changes"]["worker_batch_size"],
+        serde_json::json!({"from": 10, "to": 25})
+    );
+
+    // Another replica picks the change up on its next sync, exactly once.
+    assert!(sync_once(&pool, &replica).await.unwrap());
+    assert_eq!(replica.current().config.worker_batch_size, 25);
+    assert!(!sync_once(&pool, &replica).await.unwrap());
+
+    // A fresh process starts from the stored config.
+    assert_eq!(load(&pool).await.unwrap().current().version, saved.version);
+}
+
+// ── 46. update_config_rejects_invalid_values ───────────────────────────────
+
+#[tokio::test]
+async fn update_config_rejects_invalid_values() {
+    let pool = setup_pool("fin_sync_test_config").await;
+    let handle = RuntimeConfigHandle::default();
+    let bad = RuntimeConfig {
+        stale_job_timeout_secs: 1,
+        ..Default::default()
+    };
+    assert!(
+        update_config(&pool, &handle, bad, "admin:ops")
+            .await
+            .is_err()
+    );
+    let bad = RuntimeConfig {
+        accepted_event_types: vec!["payment_*.succeeded".into()],
+        ..Default::default()
+    };
+    assert!(
+        update_config(&pool, &handle, bad, "admin:ops")
+            .await
+            .is_err()
+    );
+    assert_eq!(handle.current().version, 0);
+}
@@ -0,0 +1,229 @@
+mod common;
+
+use chrono::{DateTime, Duration, NaiveDate, SubsecRound, Utc};
+use common::*;
+use fin_sync::domain::currency_terms::ScheduleTerms;
+use fin_sync::domain::id::{EventId, ExternalId};
+use fin_sync::domain::money::{Currency, Money, MoneyAmount};
+use fin_sync::domain::payment::{NewPayment, NewPaymentParams, PaymentDirection, PaymentStatus};
+use fin_sync::domain::provider::FetchedPayment;
+use fin_sync::domain::reconciliation::DiscrepancyKind;
+use fin_sync::domain::report::DailyReport;
+use fin_sync::error::PipelineError;
+use fin_sync::services::currency_terms::{self, Cancellation, Scheduled};
+use fin_sync::services::payment::pipeline::process_payment_event;
+use fin_sync::services::reconciliation::reconcile;
+use fin_sync::services::report::get_daily_report;
+use sqlx::PgPool;
+
+fn terms(currency: &str, accepted: bool, fee_bps: u32, valid_from: DateTime<Utc>) -> ScheduleTerms {
+    ScheduleTerms {
+        source: "stripe".into(),
+        currency: currency.into(),
+        accepted,
+        fee_bps,
+        fixed_fee_minor: 30,
+        valid_from,
+        reason: "pricing agreement".into(),
+    }
+}
+
+async fn summary(pool: &PgPool, day: DateTime<Utc>, status: &str, count: i64, total: i64) {
+    sqlx::query(
+        "INSERT INTO daily_summaries \
+         (bucket_size, bucket_start, source, direction, currency, status, payment_count, total_amount) \
+         VALUES ('day', $1, 'stripe', 'inbound', 'usd', $2, $3, $4)",
+    )
+    .bind(day)
+    .bind(status)
+    .bind(count)
+    .bind(total)
+    .execute(pool)
+    .await
+    .unwrap();
+}
+
+async fn fee_bps_at(pool: &PgPool, at: DateTime<Utc>) -> Option<i32> {
+    currency_terms::list(pool, Some("stripe"), Some("usd"), Some(at))
+        .await
+        .unwrap()
+        .first()
+        .map(|t| t.fee_bps)
+}
+
+// ── 127. currency_terms_are_scheduled_ahead_and_read_as_of_each_payment ───
+
+#[tokio::test]
+async fn currency_terms_are_scheduled_ahead_and_read_as_of_each_payment() {
+    let pool = setup_pool("fin_sync_test_currency_terms").await;
+    // Whole seconds, as Postgres hands them back.
+    let now = Utc::now().trunc_subsecs(0);
+    let today = DailyReport::bucket_start(now.date_naive());
+    let start = today - Duration::days(30);
+
+    // The first version may start in the past; retrying it changes nothing.
+    let first = terms("USD", true, 290, start);
+    let Scheduled::Created(original) = currency_terms::schedule(&pool, &first, "ops", now)
+        .await
+        .unwrap()
+    else {
+        panic!("first version not created");
+    };
+    assert_eq!(original.currency, "usd");
+    assert!(matches!(
+        currency_terms::schedule(&pool, &first, "ops", now).await.unwrap(),
+        Scheduled::Unchanged(t) if t.id == original.id
+    ));
+    assert!(matches!(
+        currency_terms::schedule(&pool, &terms("usd", true, 300, start), "ops", now)
+            .await
+            .unwrap(),
+        Scheduled::Conflict(t) if t.id == original.id
+    ));
+
+    // Later changes can't rewrite the past.
+    let backdated = terms("usd", true, 250, today - Duration::days(1));
+    assert!(matches!(
+        currency_terms::schedule(&pool, &backdated, "ops", now).await,
+        Err(PipelineError::Validation(_))
+    ));
+
+    // Versions start at midnight, so no day is split between two.
+    let tomorrow = today + Duration::days(1);
+    let mid_day = terms("usd", true, 250, tomorrow + Duration::hours(9));
+    assert!(matches!(
+        currency_terms::schedule(&pool, &mid_day, "ops", now).await,
+        Err(PipelineError::Validation(_))
+    ));
+
+    // Nor can any two versions overlap, whoever writes them.
+    let overlap = sqlx::query(
+        "INSERT INTO currency_terms \
+         (source, currency, accepted, fee_bps, fixed_fee_minor, valid_from, reason, created_by) \
+         VALUES ('stripe', 'usd', true, 100, 0, $1, 'by hand', 'ops')",
+    )
+    .bind(tomorrow + Duration::days(1))
+    .execute(&pool)
+    .await
+    .unwrap_err();
+    assert_eq!(
+        overlap.as_database_error().and_then(|e| e.constraint()),
+        Some("currency_terms_no_overlap")
+    );
+
+    // A change from tomorrow cuts the current version short there.
+    let Scheduled::Created(change) =
+        currency_terms::schedule(&pool, &terms("usd", true, 250, tomorrow), "ops", now)
+            .await
+            .unwrap()
+    else {
+        panic!("change not created");
+    };
+    assert_eq!(fee_bps_at(&pool, now).await, Some(290));
+    assert_eq!(fee_bps_at(&pool, tomorrow).await, Some(250));
+    assert_eq!(fee_bps_at(&pool, start - Duration::seconds(1)).await, None);
+    let versions = currency_terms::list(&pool, Some("stripe"), None, None)
+        .await
+        .unwrap();
+    assert_eq!(versions.len(), 2);
+    assert_eq!(versions[0].valid_to, Some(tomorrow));
+
+    // The version in force stays; the scheduled one can be withdrawn, and
+    // the one before it runs on.
+    assert!(matches!(
+        currency_terms::cancel(&pool, original.id, "ops", now)
+            .await
+            .unwrap(),
+        Some(Cancellation::InForce(_))
+    ));
+    assert!(matches!(
+        currency_terms::cancel(&pool, change.id, "ops", now)
+            .await
+            .unwrap(),
+        Some(Cancellation::Cancelled(_))
+    ));
+    assert_eq!(fee_bps_at(&pool, tomorrow).await, Some(290));
+    assert!(
+        currency_terms::cancel(&pool, change.id, "ops", now)
+            .await
+            .unwrap()
+            .is_none()
+    );
+    let actions: Vec<String> = sqlx::query_scalar(
+        "SELECT action FROM audit_log WHERE entity_type = 'currency_terms' ORDER BY id",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        actions,
+        [
+            "currency_terms_scheduled",
+            "currency_terms_scheduled",
+            "currency_terms_cancelled"
+        ]
+    );
+
+    // Yesterday's report prices settled payments under the terms then:
+    // 2.9% of 100.00 plus 0.30 on each of 4; failures cost nothing.
+    let yesterday: NaiveDate = (now - Duration::days(1)).date_naive();
+    let day = DailyReport::bucket_start(yesterday);
+    summary(&pool, day, "succeeded", 4, 10_000).await;
+    summary(&pool, day, "failed", 2, 3_000).await;
+    let report = get_daily_report(&pool, yesterday).await.unwrap();
+    assert_eq!(report.fees.len(), 1);
+    let fees = &report.fees[0];
+    assert_eq!(fees.terms_id, original.id);
+    assert_eq!(
+        (fees.payment_count, fees.total_amount, fees.fee_amount),
+        (4, 10_000, 290 + 120)
+    );
+
+    // Reconciliation flags payments taken while their currency wasn't, by
+    // when the provider took them: this one was recorded only once euros
+    // were accepted, as a late backfill would be.
+    let refused = terms("eur", false, 0, start);
+    currency_terms::schedule(&pool, &refused, "ops", now)
+        .await
+        .unwrap();
+    let accepted = terms("eur", true, 250, tomorrow);
+    currency_terms::schedule(&pool, &accepted, "ops", now)
+        .await
+        .unwrap();
+    let payment = NewPayment::new(NewPaymentParams {
+        external_id: ExternalId::new("pi_terms_eur").unwrap(),
+        source: "stripe".to_string(),
+        event_type: "payment_intent.succeeded".to_string(),
+        direction: PaymentDirection::Inbound,
+        money: Money::new(MoneyAmount::new(5000).unwrap(), Currency::EUR),
+        status: PaymentStatus::Succeeded,
+        metadata: serde_json::json!({}),
+        raw_event: serde_json::json!({"id": "evt_terms_eur"}),
+        last_event_id: EventId::new("evt_terms_eur").unwrap(),
+        parent_external_id: None,
+        provider_ts: 1000,
+        failure: None,
+        authorized_amount: None,
+        receipt: None,
+        tenant_id: None,
+        customer: None,
+    });
+    process_payment_event(&pool, &payment, "test")
+        .await
+        .unwrap();
+    sqlx::query("UPDATE payments SET created_at = $1 WHERE external_id = 'pi_terms_eur'")
+        .bind(tomorrow + Duration::hours(1))
+        .execute(&pool)
+        .await
+        .unwrap();
+    let provider = StubProvider::listing(vec![FetchedPayment {
+        money: Money::new(MoneyAmount::new(5000).unwrap(), Currency::EUR),
+        created_at: Some(now - Duration::hours(1)),
+        ..fetched_payment("pi_terms_eur", PaymentStatus::Succeeded, 5000)
+    }]);
+    let report = reconcile(&pool, &provider, now - Duration::days(1), Utc::now())
+        .await
+        .unwrap();
+    let kinds: Vec<_> = report.discrepancies.iter().map(|d| d.kind).collect();
+    assert_eq!(kinds, [Some(DiscrepancyKind::CurrencyNotAccepted)]);
+}
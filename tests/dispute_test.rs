@@ -0,0 +1,87 @@
+mod common;
+
+use common::*;
+use fin_sync::domain::id::ExternalId;
+use fin_sync::domain::payment::{PaymentStatus, ProcessResult};
+use fin_sync::services::payment::pipeline::process_payment_event;
+use fin_sync::services::payment::refund::get_refundable_balance;
+
+// ── 60. dispute_lifecycle_is_tracked_under_its_parent ──────────────────────
+
+#[tokio::test]
+async fn dispute_lifecycle_is_tracked_under_its_parent() {
+    let pool = setup_pool("fin_sync_test_dispute").await;
+    let pi = make_payment("pi_dp_1", "evt_dp_1", PaymentStatus::Succeeded, 1000);
+    process_payment_event(&pool, &pi, "test").await.unwrap();
+    let refund = make_partial_refund(
+        "re_dp_1",
+        "evt_dp_2",
+        PaymentStatus::Refunded,
+        1100,
+        "pi_dp_1",
+        3000,
+    );
+    process_payment_event(&pool, &refund, "test").await.unwrap();
+
+    let opened = make_partial_refund(
+        "dp_dp_1",
+        "evt_dp_3",
+        PaymentStatus::Disputed,
+        1200,
+        "pi_dp_1",
+        5000,
+    );
+    let result = process_payment_event(&pool, &opened, "worker:stripe")
+        .await
+        .unwrap();
+    assert!(matches!(result, ProcessResult::Created(_)));
+
+    // Disputes aren't refunds: they neither use up the balance nor get
+    // flagged as over-refunding the parent.
+    let balance = get_refundable_balance(&pool, ExternalId::new("pi_dp_1").unwrap())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!((balance.refunded, balance.refundable), (3000, 2000));
+    assert!(!balance.over_refunded);
+    assert!(
+        get_audit_entries(&pool, "pi_dp_1")
+            .await
+            .iter()
+            .all(|e| e.action != "over_refunded")
+    );
+
+    let lost = make_partial_refund(
+        "dp_dp_1",
+        "evt_dp_4",
+        PaymentStatus::DisputeLost,
+        1300,
+        "pi_dp_1",
+        5000,
+    );
+    let result = process_payment_event(&pool, &lost, "worker:stripe")
+        .await
+        .unwrap();
+    assert!(matches!(result, ProcessResult::Updated(_)));
+    let row = get_payment(&pool, "dp_dp_1").await.unwrap();
+    assert_eq!(row.status, "dispute_lost");
+    assert_eq!(row.parent_external_id.as_deref(), Some("pi_dp_1"));
+
+    // A closed dispute doesn't reopen.
+    let won = make_partial_refund(
+        "dp_dp_1",
+        "evt_dp_5",
+        PaymentStatus::DisputeWon,
+        1400,
+        "pi_dp_1",
+        5000,
+    );
+    let result = process_payment_event(&pool, &won, "worker:stripe")
+        .await
+        .unwrap();
+    assert!(matches!(result, ProcessResult::Anomaly(_)));
+    assert_eq!(
+        get_payment(&pool, "dp_dp_1").await.unwrap().status,
+        "dispute_lost"
+    );
+}
@@ -0,0 +1,115 @@
+mod common;
+
+use chrono::{TimeDelta, Utc};
+use common::*;
+use fin_sync::domain::id::{EventId, ExternalId};
+use fin_sync::domain::money::{Currency, Money, MoneyAmount};
+use fin_sync::domain::payment::{NewPayment, NewPaymentParams, PaymentDirection, PaymentStatus};
+use fin_sync::services::event_gap::{list_gaps, scan};
+use fin_sync::services::payment::pipeline::process_payment_event;
+use sqlx::PgPool;
+
+async fn deliver(pool: &PgPool, id: &str, evt: &str, event_type: &str, status: PaymentStatus) {
+    let payment = NewPayment::new(NewPaymentParams {
+        external_id: ExternalId::new(id).unwrap(),
+        source: "stripe".into(),
+        event_type: event_type.into(),
+        direction: PaymentDirection::Inbound,
+        money: Money::new(MoneyAmount::new(5000).unwrap(), Currency::USD),
+        status,
+        metadata: serde_json::json!({}),
+        raw_event: serde_json::json!({"id": evt}),
+        last_event_id: EventId::new(evt).unwrap(),
+        parent_external_id: None,
+        provider_ts: Utc::now().timestamp(),
+        failure: None,
+        authorized_amount: None,
+        receipt: None,
+        tenant_id: None,
+        customer: None,
+    });
+    process_payment_event(pool, &payment, "worker:stripe")
+        .await
+        .unwrap();
+}
+
+// ── 65. event_gaps_are_flagged_refetched_and_resolved ──────────────────────
+
+#[tokio::test]
+async fn event_gaps_are_flagged_refetched_and_resolved() {
+    let pool = setup_pool("fin_sync_test_event_gap").await;
+    let timeout = TimeDelta::hours(6);
+    let later = Utc::now() + timeout + TimeDelta::hours(1);
+    use PaymentStatus::*;
+
+    deliver(
+        &pool,
+        "pi_gap_ok",
+        "evt_gap_t1",
+        "payment_intent.created",
+        Pending,
+    )
+    .await;
+    deliver(
+        &pool,
+        "pi_gap_ok",
+        "evt_gap_t2",
+        "payment_intent.succeeded",
+        Succeeded,
+    )
+    .await;
+    deliver(
+        &pool,
+        "pi_gap_unopened",
+        "evt_gap_t3",
+        "payment_intent.succeeded",
+        Succeeded,
+    )
+    .await;
+    deliver(
+        &pool,
+        "pi_gap_stuck",
+        "evt_gap_t4",
+        "payment_intent.created",
+        Pending,
+    )
+    .await;
+
+    // Right away only the missing opening shows; the stuck one is still young.
+    let first = scan(&pool, Utc::now(), timeout).await.unwrap();
+    assert_eq!((first.checked, first.opened, first.refetches), (3, 1, 0));
+
+    let second = scan(&pool, later, timeout).await.unwrap();
+    assert_eq!((second.opened, second.refetches), (1, 1));
+    let job_object: String = sqlx::query_scalar(
+        "SELECT object_id FROM payment_jobs WHERE event_id = 'evt_gap_pi_gap_stuck'",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(job_object, "pi_gap_stuck");
+
+    // Already flagged and refetched: nothing new.
+    let third = scan(&pool, later, timeout).await.unwrap();
+    assert_eq!((third.opened, third.refetches, third.resolved), (0, 0, 0));
+
+    // The terminal event turns up after all.
+    deliver(
+        &pool,
+        "pi_gap_stuck",
+        "evt_gap_t5",
+        "payment_intent.succeeded",
+        Succeeded,
+    )
+    .await;
+    let fourth = scan(&pool, later, timeout).await.unwrap();
+    assert_eq!(fourth.resolved, 1);
+
+    let open = list_gaps(&pool, Some(true), None, None).await.unwrap();
+    assert_eq!(open.len(), 1);
+    assert_eq!(open[0].external_id, "pi_gap_unopened");
+    assert_eq!(open[0].kind, "missing_opening");
+    let resolved = list_gaps(&pool, Some(false), None, None).await.unwrap();
+    assert_eq!(resolved.len(), 1);
+    assert!(resolved[0].refetch_requested);
+}
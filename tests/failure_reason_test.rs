@@ -0,0 +1,175 @@
+mod common;
+
+use chrono::{TimeDelta, Utc};
+use common::*;
+use fin_sync::domain::id::{EventId, ExternalId};
+use fin_sync::domain::money::{Currency, Money, MoneyAmount};
+use fin_sync::domain::payment::{
+    NewPayment, NewPaymentParams, PaymentDirection, PaymentFailure, PaymentFilters, PaymentStatus,
+};
+use fin_sync::domain::rollup::{BucketSize, RollupKind, RollupSpec};
+use fin_sync::services::payment::lookup::{get_payment_by_id, get_payment_list};
+use fin_sync::services::payment::pipeline::process_payment_event;
+use fin_sync::services::report::get_daily_report;
+use fin_sync::services::rollup::run_incremental;
+
+fn card_declined(decline_code: &str) -> PaymentFailure {
+    PaymentFailure {
+        code: Some("card_declined".into()),
+        decline_code: Some(decline_code.into()),
+        message: Some("Your card was declined.".into()),
+        network_advice_code: None,
+    }
+}
+
+fn failed_payment(
+    external_id: &str,
+    event_id: &str,
+    provider_ts: i64,
+    failure: PaymentFailure,
+) -> NewPayment {
+    NewPayment::new(NewPaymentParams {
+        external_id: ExternalId::new(external_id).unwrap(),
+        source: "stripe".to_string(),
+        event_type: "payment_intent.payment_failed".to_string(),
+        direction: PaymentDirection::Inbound,
+        money: Money::new(MoneyAmount::new(5000).unwrap(), Currency::USD),
+        status: PaymentStatus::Failed,
+        metadata: serde_json::json!({}),
+        raw_event: serde_json::json!({"id": event_id}),
+        last_event_id: EventId::new(event_id).unwrap(),
+        parent_external_id: None,
+        provider_ts,
+        failure: Some(failure),
+        authorized_amount: None,
+        receipt: None,
+        tenant_id: None,
+        customer: None,
+    })
+}
+
+fn filters_by_decline_code(code: &str) -> PaymentFilters {
+    PaymentFilters {
+        decline_code: Some(code.into()),
+        ..Default::default()
+    }
+}
+
+// ── 41. failure_details_stored_and_exposed ─────────────────────────────────
+
+#[tokio::test]
+async fn failure_details_stored_and_exposed() {
+    let pool = setup_pool("fin_sync_test_failure").await;
+    let p = failed_payment(
+        "pi_fail_store",
+        "evt_fail_store",
+        1000,
+        card_declined("insufficient_funds"),
+    );
+    process_payment_event(&pool, &p, "test").await.unwrap();
+
+    let view = get_payment_by_id(&pool, ExternalId::new("pi_fail_store").unwrap())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(view.failure, Some(card_declined("insufficient_funds")));
+
+    let listed = get_payment_list(&pool, filters_by_decline_code("insufficient_funds"))
+        .await
+        .unwrap();
+    assert!(
+        listed
+            .payments
+            .iter()
+            .any(|v| v.id.as_str() == "pi_fail_store")
+    );
+    let other = get_payment_list(&pool, filters_by_decline_code("lost_card"))
+        .await
+        .unwrap();
+    assert!(
+        other
+            .payments
+            .iter()
+            .all(|v| v.id.as_str() != "pi_fail_store")
+    );
+}
+
+// ── 42. pending_to_failed_records_decline_reason ───────────────────────────
+
+#[tokio::test]
+async fn pending_to_failed_records_decline_reason() {
+    let pool = setup_pool("fin_sync_test_failure").await;
+    let p1 = make_payment("pi_fail_trans", "evt_fail_t1", PaymentStatus::Pending, 1000);
+    process_payment_event(&pool, &p1, "test").await.unwrap();
+
+    let p2 = failed_payment(
+        "pi_fail_trans",
+        "evt_fail_t2",
+        2000,
+        card_declined("do_not_honor"),
+    );
+    process_payment_event(&pool, &p2, "test").await.unwrap();
+
+    let view = get_payment_by_id(&pool, ExternalId::new("pi_fail_trans").unwrap())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(view.status, PaymentStatus::Failed);
+    assert_eq!(
+        view.failure.and_then(|f| f.decline_code).as_deref(),
+        Some("do_not_honor")
+    );
+}
+
+// ── 43. daily_report_breaks_down_failure_reasons ───────────────────────────
+
+#[tokio::test]
+async fn daily_report_breaks_down_failure_reasons() {
+    let pool = setup_pool("fin_sync_test_failure").await;
+    for i in 0..2 {
+        let p = failed_payment(
+            &format!("pi_fail_report_{i}"),
+            &format!("evt_fail_report_{i}"),
+            1000,
+            card_declined("expired_card"),
+        );
+        process_payment_event(&pool, &p, "test").await.unwrap();
+    }
+    // An abandoned checkout isn't a failure.
+    let p = make_payment(
+        "pi_fail_report_abandoned",
+        "evt_fail_report_abandoned",
+        PaymentStatus::Cancelled,
+        1000,
+    );
+    process_payment_event(&pool, &p, "test").await.unwrap();
+
+    let now = Utc::now();
+    for kind in [RollupKind::DailySummaries, RollupKind::FailureReasonStats] {
+        let spec = RollupSpec {
+            kind,
+            bucket: BucketSize::Day,
+            lookback: TimeDelta::days(1),
+            retention: TimeDelta::days(30),
+        };
+        run_incremental(&pool, &spec, now).await.unwrap();
+    }
+
+    let report = get_daily_report(&pool, now.date_naive()).await.unwrap();
+    assert!(report.summaries.iter().any(|s| s.status == "failed"));
+    let stripe = report
+        .outcomes
+        .iter()
+        .find(|o| o.source == "stripe" && o.direction == "inbound")
+        .expect("stripe inbound outcomes");
+    assert_eq!((stripe.failed, stripe.cancelled), (2, 1));
+    assert_eq!(stripe.failure_rate, Some(1.0));
+    let line = report
+        .failure_reasons
+        .iter()
+        .find(|l| l.decline_code == "expired_card")
+        .expect("expired_card bucket");
+    assert_eq!(line.failure_code, "card_declined");
+    assert_eq!(line.payment_count, 2);
+    assert_eq!(line.total_amount, 10000);
+}
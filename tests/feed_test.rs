@@ -0,0 +1,155 @@
+mod common;
+
+use common::*;
+use fin_sync::domain::config::RuntimeConfig;
+use fin_sync::domain::feed::MergePolicy;
+use fin_sync::domain::id::{EventId, ExternalId, TenantId};
+use fin_sync::domain::money::{Currency, Money, MoneyAmount};
+use fin_sync::domain::payment::{
+    NewPayment, NewPaymentParams, PaymentDirection, PaymentStatus, ProcessResult,
+};
+use fin_sync::domain::transition::BANK_TRANSFER_SOURCE;
+use fin_sync::error::PipelineError;
+use fin_sync::services::config::{RuntimeConfigHandle, update_config};
+use fin_sync::services::payment::pipeline::process_payment_event;
+use serde_json::Value;
+use sqlx::PgPool;
+
+/// A bank transfer event from connected account `tenant`, or the platform.
+/// Transfers may go from succeeded to failed, so a late `failed` would
+/// roll one back.
+fn transfer(
+    external_id: &str,
+    tenant: Option<&str>,
+    event_id: &str,
+    status: PaymentStatus,
+    provider_ts: i64,
+) -> NewPayment {
+    NewPayment::new(NewPaymentParams {
+        external_id: ExternalId::new(external_id).unwrap(),
+        source: BANK_TRANSFER_SOURCE.to_string(),
+        event_type: format!("transfer.{}", status.as_str()),
+        direction: PaymentDirection::Inbound,
+        money: Money::new(MoneyAmount::new(5000).unwrap(), Currency::EUR),
+        status,
+        metadata: serde_json::json!({}),
+        raw_event: serde_json::json!({"id": event_id}),
+        last_event_id: EventId::new(event_id).unwrap(),
+        parent_external_id: None,
+        provider_ts,
+        failure: None,
+        authorized_amount: None,
+        receipt: None,
+        tenant_id: tenant.map(|t| TenantId::new(t).unwrap()),
+        customer: None,
+    })
+}
+
+/// The detail and tenant of the audit entry `event_id` wrote.
+async fn audit(pool: &PgPool, event_id: &str) -> (String, Value, Option<String>) {
+    sqlx::query_as("SELECT action, detail, tenant_id FROM audit_log WHERE event_id = $1")
+        .bind(event_id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+// ── 117. lagging_feeds_cant_roll_back_fresher_ones ─────────────────────────
+
+#[tokio::test]
+async fn lagging_feeds_cant_roll_back_fresher_ones() {
+    let pool = setup_pool("fin_sync_test_feed").await;
+    let apply = |payment: NewPayment| {
+        let pool = pool.clone();
+        async move { process_payment_event(&pool, &payment, "test").await }
+    };
+    use PaymentStatus::*;
+
+    // The connected account's feed settles the transfer at 300.
+    let acct = Some("acct_feed");
+    let created = apply(transfer("pi_feed_1", acct, "evt_f1", Pending, 100)).await;
+    assert!(matches!(created, Ok(ProcessResult::Created(_))));
+    let settled = apply(transfer("pi_feed_1", acct, "evt_f2", Succeeded, 300)).await;
+    assert!(matches!(settled, Ok(ProcessResult::Updated(_))));
+
+    // The platform's feed lags: its failure from 200 is recorded, not applied.
+    let late = apply(transfer("pi_feed_1", None, "evt_f3", Failed, 200)).await;
+    assert!(matches!(late, Ok(ProcessResult::StaleIgnored(_))));
+    let payment = get_payment(&pool, "pi_feed_1").await.unwrap();
+    assert_eq!(
+        (payment.status.as_str(), payment.last_event_id.as_str()),
+        ("succeeded", "evt_f2")
+    );
+    let (action, detail, tenant) = audit(&pool, "evt_f3").await;
+    assert_eq!(action, "event_stale_ignored");
+    assert_eq!(tenant.as_deref(), Some("acct_feed"));
+    assert_eq!(detail["superseded"], true);
+    assert_eq!(detail["feed"], "bank_transfer");
+    assert_eq!(detail["merge_policy"], "freshest");
+    assert_eq!(
+        detail["conflict"],
+        serde_json::json!({
+            "feed": "bank_transfer@acct_feed",
+            "provider_ts": 300,
+            "event_id": "evt_f2",
+            "behind_secs": 100,
+        })
+    );
+
+    // Each feed's position and counts.
+    let feeds: Vec<(String, i64, String, i64, i64)> = sqlx::query_as(
+        "SELECT feed, last_provider_ts, last_event_id, events, conflicts \
+         FROM payment_feeds ORDER BY feed",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        feeds,
+        [
+            ("bank_transfer".into(), 200, "evt_f3".into(), 1, 1),
+            ("bank_transfer@acct_feed".into(), 300, "evt_f2".into(), 2, 0),
+        ]
+    );
+
+    // A fresher failure from the platform is a returned transfer, applied.
+    let returned = apply(transfer("pi_feed_1", None, "evt_f4", Failed, 400)).await;
+    assert!(matches!(returned, Ok(ProcessResult::Updated(_))));
+    let (action, detail, _) = audit(&pool, "evt_f4").await;
+    assert_eq!(action, "status_changed");
+    assert!(detail.get("conflict").is_none());
+
+    // Another connected account is no feed of this payment.
+    let intruder = apply(transfer(
+        "pi_feed_1",
+        Some("acct_other"),
+        "evt_f5",
+        Pending,
+        500,
+    ))
+    .await;
+    assert!(matches!(intruder, Err(PipelineError::Validation(_))));
+
+    // Left to the state machine, a lagging event goes through; the conflict
+    // is still recorded.
+    let lenient = RuntimeConfig {
+        feed_merge_policy: MergePolicy::StateMachine,
+        ..Default::default()
+    };
+    update_config(&pool, &RuntimeConfigHandle::default(), lenient, "admin:ops")
+        .await
+        .unwrap();
+    apply(transfer("pi_feed_2", acct, "evt_f6", Succeeded, 300))
+        .await
+        .unwrap();
+    let late = apply(transfer("pi_feed_2", None, "evt_f7", Failed, 200)).await;
+    assert!(matches!(late, Ok(ProcessResult::Updated(_))));
+    assert_eq!(
+        get_payment(&pool, "pi_feed_2").await.unwrap().status,
+        "failed"
+    );
+    let (action, detail, _) = audit(&pool, "evt_f7").await;
+    assert_eq!(action, "status_changed");
+    assert_eq!(detail["merge_policy"], "state_machine");
+    assert_eq!(detail["conflict"]["event_id"], "evt_f6");
+}
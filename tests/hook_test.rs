@@ -0,0 +1,147 @@
+mod common;
+
+use common::*;
+use fin_sync::domain::error::PortError;
+use fin_sync::domain::hook::{HookFuture, HookRegistry, TransitionHook, TransitionNotice};
+use fin_sync::domain::payment::PaymentStatus;
+use fin_sync::infra::postgres::hook_repo;
+use fin_sync::services::hooks::dispatch_once;
+use fin_sync::services::payment::pipeline::process_payment_event;
+use std::sync::{
+    Arc, Mutex,
+    atomic::{AtomicBool, Ordering},
+};
+
+/// Records what it was told; fails its first status change if `flaky`.
+struct RecordingHook {
+    name: &'static str,
+    flaky: AtomicBool,
+    seen: Mutex<Vec<String>>,
+}
+
+impl RecordingHook {
+    fn new(name: &'static str, flaky: bool) -> Arc<Self> {
+        Arc::new(Self {
+            name,
+            flaky: AtomicBool::new(flaky),
+            seen: Mutex::default(),
+        })
+    }
+
+    fn record(&self, line: String) -> HookFuture<'_> {
+        self.seen.lock().unwrap().push(line);
+        Box::pin(async { Ok(()) })
+    }
+
+    fn seen(&self) -> Vec<String> {
+        self.seen.lock().unwrap().clone()
+    }
+}
+
+impl TransitionHook for RecordingHook {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn on_created<'a>(&'a self, notice: &'a TransitionNotice) -> HookFuture<'a> {
+        self.record(format!("created {} {}", notice.payment.id, notice.event_id))
+    }
+
+    fn on_status_changed<'a>(
+        &'a self,
+        notice: &'a TransitionNotice,
+        from: PaymentStatus,
+        to: PaymentStatus,
+    ) -> HookFuture<'a> {
+        if self.flaky.swap(false, Ordering::SeqCst) {
+            return Box::pin(async { Err(PortError::Unavailable("hook endpoint down".into())) });
+        }
+        self.record(format!("changed {} {from}->{to}", notice.payment.id))
+    }
+
+    fn on_anomaly<'a>(
+        &'a self,
+        notice: &'a TransitionNotice,
+        current: PaymentStatus,
+        incoming: PaymentStatus,
+    ) -> HookFuture<'a> {
+        self.record(format!(
+            "anomaly {} {current}<-{incoming}",
+            notice.payment.id
+        ))
+    }
+}
+
+async fn drain(pool: &sqlx::PgPool, hooks: &HookRegistry) -> usize {
+    let mut total = 0;
+    loop {
+        match dispatch_once(pool, hooks).await.unwrap() {
+            0 => return total,
+            n => total += n,
+        }
+    }
+}
+
+// ── 79. transition_hooks_run_post_commit_and_retry ─────────────────────────
+
+#[tokio::test]
+async fn transition_hooks_run_post_commit_and_retry() {
+    let pool = setup_pool("fin_sync_test_hooks").await;
+    let steady = RecordingHook::new("steady", false);
+    let flaky = RecordingHook::new("flaky", true);
+    let mut hooks = HookRegistry::default();
+    hooks.register(steady.clone());
+    hooks.register(flaky.clone());
+
+    // Nothing is queued for a hook before it subscribes.
+    let before = make_payment("pi_hook_0", "evt_hook_0", PaymentStatus::Pending, 1000);
+    process_payment_event(&pool, &before, "test").await.unwrap();
+    hook_repo::subscribe(&pool, &hooks.names()).await.unwrap();
+
+    for (event, status, ts) in [
+        ("evt_hook_1", PaymentStatus::Pending, 1000),
+        ("evt_hook_2", PaymentStatus::Succeeded, 1001),
+        ("evt_hook_3", PaymentStatus::Pending, 1002),
+    ] {
+        let p = make_payment("pi_hook_1", event, status, ts);
+        process_payment_event(&pool, &p, "test").await.unwrap();
+    }
+
+    // One delivery per hook and payment per batch, in order.
+    assert_eq!(dispatch_once(&pool, &hooks).await.unwrap(), 2);
+    assert_eq!(drain(&pool, &hooks).await, 3);
+    assert_eq!(
+        steady.seen(),
+        [
+            "created pi_hook_1 evt_hook_1",
+            "changed pi_hook_1 pending->succeeded",
+            "anomaly pi_hook_1 succeeded<-pending",
+        ]
+    );
+    // The anomaly waits behind the failed status change.
+    assert_eq!(flaky.seen(), ["created pi_hook_1 evt_hook_1"]);
+
+    let (attempts, error): (i32, Option<String>) = sqlx::query_as(
+        "SELECT attempts, last_error FROM hook_outbox WHERE hook = 'flaky' ORDER BY seq LIMIT 1",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(attempts, 1);
+    assert!(error.unwrap().contains("hook endpoint down"));
+
+    // Not due yet; once it is, both go through in order.
+    assert_eq!(dispatch_once(&pool, &hooks).await.unwrap(), 0);
+    sqlx::query("UPDATE hook_outbox SET next_attempt_at = now()")
+        .execute(&pool)
+        .await
+        .unwrap();
+    assert_eq!(drain(&pool, &hooks).await, 2);
+    assert_eq!(flaky.seen(), steady.seen());
+
+    let left: i64 = sqlx::query_scalar("SELECT count(*) FROM hook_outbox")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(left, 0);
+}
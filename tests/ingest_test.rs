@@ -0,0 +1,66 @@
+mod common;
+
+use common::*;
+use fin_sync::domain::ingest::BatchFormat;
+use fin_sync::services::ingest::ingest_batch;
+
+const SETTLEMENT_CSV: &str = "\
+event_id,external_id,source,direction,amount,currency,status,occurred_at,parent_external_id,metadata
+stl-100,pi_ing_1,stripe,inbound,4999,usd,pending,2026-03-02T09:00:00Z,,\"{\"\"order_id\"\": \"\"ORD-77\"\"}\"
+stl-101,pi_ing_1,stripe,inbound,4999,usd,succeeded,2026-03-02T09:05:00Z,,
+stl-102,pi_ing_2,stripe,inbound,1500,xyz,succeeded,2026-03-02T09:06:00Z,,
+stl-103,re_ing_1,stripe,outbound,1000,usd,succeeded,2026-03-02T10:00:00Z,pi_ing_1,
+";
+
+// ── 78. batch_ingest_reports_each_row_and_is_idempotent ────────────────────
+
+#[tokio::test]
+async fn batch_ingest_reports_each_row_and_is_idempotent() {
+    let pool = setup_pool("fin_sync_test_ingest").await;
+
+    let first = ingest_batch(&pool, BatchFormat::Csv, SETTLEMENT_CSV, "ingest:finance")
+        .await
+        .unwrap();
+    assert_eq!(first.rows, 4);
+    let results: Vec<_> = first.results.iter().map(|r| (r.row, r.result)).collect();
+    assert_eq!(
+        results,
+        [(1, "created"), (2, "updated"), (3, "error"), (4, "created")]
+    );
+    // The bad row doesn't hold back the ones after it.
+    assert!(first.results[2].error.is_some());
+    assert_eq!((first.counts.created, first.counts.errors), (2, 1));
+
+    let payment = get_payment(&pool, "pi_ing_1").await.unwrap();
+    assert_eq!(payment.status, "succeeded");
+    assert_eq!(payment.last_event_id, "evt_ingest_stl-101");
+    let refund = get_payment(&pool, "re_ing_1").await.unwrap();
+    assert_eq!(refund.parent_external_id.as_deref(), Some("pi_ing_1"));
+    let actor: String =
+        sqlx::query_scalar("SELECT actor FROM audit_log WHERE external_id = 'pi_ing_1' LIMIT 1")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(actor, "ingest:finance");
+
+    // Sending the same file again changes nothing.
+    let again = ingest_batch(&pool, BatchFormat::Csv, SETTLEMENT_CSV, "ingest:finance")
+        .await
+        .unwrap();
+    assert_eq!((again.counts.duplicates, again.counts.errors), (3, 1));
+    assert_eq!(count_audit_entries(&pool, "pi_ing_1").await, 2);
+
+    let ndjson = r#"{"event_id":"stl-200","external_id":"pi_ing_3","source":"stripe","direction":"inbound","amount":700,"currency":"eur","status":"succeeded","occurred_at":"2026-03-03T08:00:00Z"}
+not json"#;
+    let lines = ingest_batch(&pool, BatchFormat::Ndjson, ndjson, "ingest:finance")
+        .await
+        .unwrap();
+    let results: Vec<_> = lines.results.iter().map(|r| r.result).collect();
+    assert_eq!(results, ["created", "error"]);
+
+    assert!(
+        ingest_batch(&pool, BatchFormat::Csv, "", "ingest:finance")
+            .await
+            .is_err()
+    );
+}
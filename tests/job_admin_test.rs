@@ -0,0 +1,109 @@
+mod common;
+
+use common::*;
+use fin_sync::domain::job::{JobFilters, JobPriority, JobStatus, RequeueFilter};
+use fin_sync::infra::postgres::job_repo;
+use fin_sync::services::jobs::{RetryOutcome, list_jobs, requeue_failed, retry_job};
+use sqlx::PgPool;
+use std::time::Duration;
+
+/// Enqueue a job and fail it until it's dead-lettered.
+async fn dead_letter(pool: &PgPool, source: &str, event_id: &str) -> uuid::Uuid {
+    job_repo::enqueue(
+        pool,
+        source,
+        None,
+        event_id,
+        "pi_dlq",
+        "payment_intent.succeeded",
+        1000,
+        &serde_json::json!({"id": event_id}),
+        JobPriority::Normal,
+    )
+    .await
+    .unwrap();
+    let id: uuid::Uuid = sqlx::query_scalar("SELECT id FROM payment_jobs WHERE event_id = $1")
+        .bind(event_id)
+        .fetch_one(pool)
+        .await
+        .unwrap();
+    for _ in 0..5 {
+        job_repo::fail(pool, id, "Stripe API: 503", Some(Duration::from_secs(2)))
+            .await
+            .unwrap();
+    }
+    id
+}
+
+// ── 66. failed_jobs_are_listed_and_requeued ────────────────────────────────
+
+#[tokio::test]
+async fn failed_jobs_are_listed_and_requeued() {
+    let pool = setup_pool("fin_sync_test_job_admin").await;
+    let first = dead_letter(&pool, "stripe", "evt_dlq_1").await;
+    dead_letter(&pool, "stripe", "evt_dlq_2").await;
+    dead_letter(&pool, "paypal", "evt_dlq_3").await;
+
+    let failed = list_jobs(
+        &pool,
+        JobFilters {
+            status: Some(JobStatus::Failed),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(failed.len(), 3);
+    assert!(failed.iter().all(|j| j.attempts == 5));
+    assert_eq!(failed[0].last_error.as_deref(), Some("Stripe API: 503"));
+
+    let RetryOutcome::Requeued(before) = retry_job(&pool, first, "admin:ops").await.unwrap() else {
+        panic!("failed job should be requeued");
+    };
+    assert_eq!(before.attempts, 5);
+    let after = job_repo::get_job(&pool, first).await.unwrap().unwrap();
+    assert_eq!((after.status.as_str(), after.attempts), ("pending", 0));
+    // Not failed any more, so a second retry is refused.
+    assert!(matches!(
+        retry_job(&pool, first, "admin:ops").await.unwrap(),
+        RetryOutcome::NotFailed(_)
+    ));
+    assert!(matches!(
+        retry_job(&pool, uuid::Uuid::now_v7(), "admin:ops")
+            .await
+            .unwrap(),
+        RetryOutcome::NotFound
+    ));
+
+    let stripe_only = RequeueFilter {
+        source: Some("stripe".into()),
+        failed_since: None,
+    };
+    assert_eq!(
+        requeue_failed(&pool, &stripe_only, "admin:ops")
+            .await
+            .unwrap(),
+        1
+    );
+    let still_failed: Vec<_> = list_jobs(
+        &pool,
+        JobFilters {
+            status: Some(JobStatus::Failed),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap()
+    .into_iter()
+    .map(|j| j.event_id)
+    .collect();
+    assert_eq!(still_failed, ["evt_dlq_3"]);
+
+    let audited: Vec<String> = sqlx::query_scalar(
+        "SELECT action FROM audit_log WHERE entity_type = 'payment_job' ORDER BY created_at",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(audited, ["job_retried", "jobs_requeued"]);
+}
@@ -0,0 +1,97 @@
+mod common;
+
+use common::*;
+use fin_sync::domain::id::ExternalId;
+use fin_sync::domain::ledger::{LedgerAccount, LedgerEntryView, Side};
+use fin_sync::domain::payment::PaymentStatus;
+use fin_sync::services::ledger::get_payment_ledger;
+use fin_sync::services::payment::pipeline::{adjust_payment_event, process_payment_event};
+
+async fn ledger(pool: &sqlx::PgPool, id: &str) -> Vec<LedgerEntryView> {
+    get_payment_ledger(pool, ExternalId::new(id).unwrap())
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+/// Each line as `(account, signed amount)`, debits positive.
+fn postings(entry: &LedgerEntryView) -> Vec<(LedgerAccount, i64)> {
+    entry
+        .lines
+        .iter()
+        .map(|l| match l.side {
+            Side::Debit => (l.account, l.amount),
+            Side::Credit => (l.account, -l.amount),
+        })
+        .collect()
+}
+
+// ── 104. payment_changes_post_balanced_ledger_entries ──────────────────────
+
+#[tokio::test]
+async fn payment_changes_post_balanced_ledger_entries() {
+    let pool = setup_pool("fin_sync_test_ledger").await;
+    use LedgerAccount::*;
+
+    // Nothing posts until money settles.
+    let pending = make_payment("pi_ledger", "evt_led_1", PaymentStatus::Pending, 100);
+    process_payment_event(&pool, &pending, "test")
+        .await
+        .unwrap();
+    assert!(ledger(&pool, "pi_ledger").await.is_empty());
+
+    let settled = make_payment("pi_ledger", "evt_led_2", PaymentStatus::Succeeded, 200);
+    process_payment_event(&pool, &settled, "test")
+        .await
+        .unwrap();
+    let entries = ledger(&pool, "pi_ledger").await;
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].event_id, "evt_led_2");
+    assert_eq!(entries[0].from_status.as_deref(), Some("pending"));
+    assert_eq!(postings(&entries[0]), [(Cash, 5000), (Revenue, -5000)]);
+
+    // A duplicate or a same-status event posts nothing more.
+    process_payment_event(&pool, &settled, "test")
+        .await
+        .unwrap();
+    let again = make_payment("pi_ledger", "evt_led_3", PaymentStatus::Succeeded, 300);
+    process_payment_event(&pool, &again, "test").await.unwrap();
+    assert_eq!(ledger(&pool, "pi_ledger").await.len(), 1);
+
+    // A refund draws on cash.
+    let refund = make_partial_refund(
+        "re_ledger",
+        "evt_led_4",
+        PaymentStatus::Refunded,
+        400,
+        "pi_ledger",
+        1500,
+    );
+    process_payment_event(&pool, &refund, "test").await.unwrap();
+    let entries = ledger(&pool, "re_ledger").await;
+    assert_eq!(entries[0].from_status, None);
+    assert_eq!(postings(&entries[0]), [(Cash, -1500), (Refunds, 1500)]);
+
+    // A forced correction reverses what the payment had posted.
+    let bounced = make_payment("pi_ledger", "evt_led_5", PaymentStatus::Failed, 500);
+    adjust_payment_event(&pool, &bounced, "admin:ops", "returned", true)
+        .await
+        .unwrap();
+    let entries = ledger(&pool, "pi_ledger").await;
+    assert_eq!(entries.len(), 2);
+    assert_eq!(postings(&entries[1]), [(Cash, -5000), (Revenue, 5000)]);
+
+    // Every entry balances.
+    let (debits, credits): (i64, i64) = sqlx::query_as(
+        "SELECT COALESCE(SUM(amount) FILTER (WHERE side = 'debit'), 0)::BIGINT,
+                COALESCE(SUM(amount) FILTER (WHERE side = 'credit'), 0)::BIGINT
+         FROM ledger_lines",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(debits, credits);
+
+    let unknown = ExternalId::new("pi_ledger_unknown").unwrap();
+    assert!(get_payment_ledger(&pool, unknown).await.unwrap().is_none());
+}
@@ -0,0 +1,399 @@
+mod common;
+
+use common::*;
+use fin_sync::domain::audit::AuditFilters;
+use fin_sync::domain::id::{EventId, ExternalId};
+use fin_sync::domain::money::{Currency, Money, MoneyAmount};
+use fin_sync::domain::payment::{
+    NewPayment, NewPaymentParams, PaymentCursor, PaymentDirection, PaymentFilters, PaymentStatus,
+};
+use fin_sync::services::payment::lookup::{
+    get_payment_audit, get_payment_detail, get_payment_list, get_payment_timeline,
+};
+use fin_sync::services::payment::pipeline::process_payment_event;
+use fin_sync::transport::http::jsonapi;
+
+// ── 49. keyset_pagination_walks_every_page_once ────────────────────────────
+
+#[tokio::test]
+async fn keyset_pagination_walks_every_page_once() {
+    let pool = setup_pool("fin_sync_test_lookup").await;
+    let parent = make_payment(
+        "pi_ks_parent",
+        "evt_ks_parent",
+        PaymentStatus::Succeeded,
+        1000,
+    );
+    process_payment_event(&pool, &parent, "test").await.unwrap();
+    for i in 0..5 {
+        let r = make_partial_refund(
+            &format!("re_ks_{i}"),
+            &format!("evt_ks_{i}"),
+            PaymentStatus::Succeeded,
+            1000,
+            "pi_ks_parent",
+            100,
+        );
+        process_payment_event(&pool, &r, "test").await.unwrap();
+    }
+
+    let mut seen = Vec::new();
+    let mut sizes = Vec::new();
+    let mut cursor: Option<PaymentCursor> = None;
+    loop {
+        let page = get_payment_list(
+            &pool,
+            PaymentFilters {
+                parent_external_id: Some("pi_ks_parent".into()),
+                limit: Some(2),
+                cursor: cursor.take(),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        sizes.push(page.payments.len());
+        seen.extend(page.payments.iter().map(|p| p.id.as_str().to_string()));
+        match page.next_cursor {
+            Some(next) => cursor = Some(next),
+            None => break,
+        }
+    }
+    assert_eq!(sizes, vec![2, 2, 1]);
+    let mut expected: Vec<String> = (0..5).map(|i| format!("re_ks_{i}")).collect();
+    seen.sort();
+    expected.sort();
+    assert_eq!(seen, expected);
+
+    let both = get_payment_list(
+        &pool,
+        PaymentFilters {
+            offset: Some(2),
+            cursor: Some(PaymentCursor::try_from("1:pi_x".to_string()).unwrap()),
+            ..Default::default()
+        },
+    )
+    .await;
+    assert!(both.is_err());
+}
+
+// ── 50. payment_detail_includes_audit_summary ──────────────────────────────
+
+#[tokio::test]
+async fn payment_detail_includes_audit_summary() {
+    let pool = setup_pool("fin_sync_test_lookup").await;
+    let p1 = make_payment("pi_detail", "evt_detail_1", PaymentStatus::Pending, 1000);
+    process_payment_event(&pool, &p1, "test").await.unwrap();
+    let p2 = make_payment("pi_detail", "evt_detail_2", PaymentStatus::Succeeded, 2000);
+    process_payment_event(&pool, &p2, "worker:stripe")
+        .await
+        .unwrap();
+
+    let detail = get_payment_detail(&pool, ExternalId::new("pi_detail").unwrap())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(detail.payment.status, PaymentStatus::Succeeded);
+    assert_eq!(detail.audit.entries, 2);
+    assert_eq!(detail.audit.last_action.as_deref(), Some("status_changed"));
+    assert_eq!(detail.audit.last_actor.as_deref(), Some("worker:stripe"));
+    assert_eq!(detail.audit.last_event_id.as_deref(), Some("evt_detail_2"));
+
+    let missing = get_payment_detail(&pool, ExternalId::new("pi_detail_none").unwrap())
+        .await
+        .unwrap();
+    assert!(missing.is_none());
+}
+
+// ── 52. payment_audit_trail_filters_and_pages ──────────────────────────────
+
+#[tokio::test]
+async fn payment_audit_trail_filters_and_pages() {
+    let pool = setup_pool("fin_sync_test_lookup").await;
+    let events = [
+        ("evt_trail_1", PaymentStatus::Pending, 1000),
+        ("evt_trail_2", PaymentStatus::Succeeded, 2000),
+        ("evt_trail_3", PaymentStatus::Pending, 3000),
+    ];
+    for (evt, status, ts) in events {
+        let p = make_payment("pi_trail", evt, status, ts);
+        process_payment_event(&pool, &p, "test").await.unwrap();
+    }
+    let id = || ExternalId::new("pi_trail").unwrap();
+
+    let all = get_payment_audit(&pool, id(), AuditFilters::default())
+        .await
+        .unwrap()
+        .unwrap();
+    let actions: Vec<_> = all.iter().map(|e| e.action.as_str()).collect();
+    assert_eq!(actions, ["created", "status_changed", "event_received"]);
+    assert_eq!(all[2].event_id, "evt_trail_3");
+
+    let received = get_payment_audit(
+        &pool,
+        id(),
+        AuditFilters {
+            action: Some("event_received".into()),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0].event_id, "evt_trail_3");
+
+    let second = get_payment_audit(
+        &pool,
+        id(),
+        AuditFilters {
+            limit: Some(1),
+            offset: Some(1),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(second.len(), 1);
+    assert_eq!(second[0].event_id, "evt_trail_2");
+
+    let missing = get_payment_audit(
+        &pool,
+        ExternalId::new("pi_trail_none").unwrap(),
+        AuditFilters::default(),
+    )
+    .await
+    .unwrap();
+    assert!(missing.is_none());
+}
+
+// ── 67. jsonapi_documents_link_payment_refunds_and_audit ───────────────────
+
+#[tokio::test]
+async fn jsonapi_documents_link_payment_refunds_and_audit() {
+    let pool = setup_pool("fin_sync_test_lookup").await;
+    let pi = make_payment("pi_ja", "evt_ja_1", PaymentStatus::Succeeded, 1000);
+    process_payment_event(&pool, &pi, "test").await.unwrap();
+    let refund = make_partial_refund(
+        "re_ja",
+        "evt_ja_2",
+        PaymentStatus::Refunded,
+        1100,
+        "pi_ja",
+        2500,
+    );
+    process_payment_event(&pool, &refund, "test").await.unwrap();
+
+    let detail = get_payment_detail(&pool, ExternalId::new("pi_ja").unwrap())
+        .await
+        .unwrap()
+        .unwrap();
+    let doc = serde_json::to_value(jsonapi::payment_document(&detail).unwrap()).unwrap();
+    let data = &doc["data"];
+    assert_eq!(data["type"], "payments");
+    assert_eq!(data["id"], "pi_ja");
+    assert_eq!(data["attributes"]["status"], "succeeded");
+    assert!(data["attributes"].get("id").is_none());
+    let rel = &data["relationships"];
+    assert_eq!(
+        rel["refunds"]["links"]["related"],
+        "/payments?parent_external_id=pi_ja"
+    );
+    assert_eq!(rel["refunds"]["meta"]["refunded"], 2500);
+    assert_eq!(rel["audit"]["links"]["related"], "/payments/pi_ja/audit");
+    assert_eq!(rel["audit"]["meta"]["entries"], 1);
+    assert_eq!(doc["links"]["self"], "/payments/pi_ja");
+
+    let page = get_payment_list(
+        &pool,
+        PaymentFilters {
+            parent_external_id: Some("pi_ja".into()),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    let query = "parent_external_id=pi_ja";
+    let doc = serde_json::to_value(
+        jsonapi::payment_list_document(&page.payments, Some(query), None).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(doc["data"][0]["id"], "re_ja");
+    assert_eq!(
+        doc["data"][0]["relationships"]["parent"]["data"],
+        serde_json::json!({"type": "payments", "id": "pi_ja"})
+    );
+    assert!(doc["data"][0]["relationships"].get("refunds").is_none());
+    assert!(doc["links"].get("next").is_none());
+
+    let entries = get_payment_audit(
+        &pool,
+        ExternalId::new("pi_ja").unwrap(),
+        AuditFilters::default(),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    let doc = serde_json::to_value(jsonapi::audit_document("pi_ja", &entries).unwrap()).unwrap();
+    assert_eq!(doc["data"][0]["type"], "audit_entries");
+    assert_eq!(doc["data"][0]["id"], "evt_ja_1");
+    assert_eq!(
+        doc["data"][0]["relationships"]["payment"]["data"]["id"],
+        "pi_ja"
+    );
+    assert_eq!(doc["links"]["related"], "/payments/pi_ja");
+}
+
+// ── 77. payment_search_by_amount_window_and_reference ──────────────────────
+
+#[tokio::test]
+async fn payment_search_by_amount_window_and_reference() {
+    let pool = setup_pool("fin_sync_test_lookup").await;
+    let seed = [
+        (
+            "pi_srch_a",
+            4999,
+            serde_json::json!({"order_id": "ORD-1234"}),
+        ),
+        (
+            "pi_srch_b",
+            4999,
+            serde_json::json!({"order_id": "ORD-5678"}),
+        ),
+        (
+            "pi_srch_c",
+            12_000,
+            serde_json::json!({"statement_descriptor": "ACME ORDER 1234"}),
+        ),
+    ];
+    for (id, amount, metadata) in seed {
+        let p = NewPayment::new(NewPaymentParams {
+            external_id: ExternalId::new(id).unwrap(),
+            source: "stripe".into(),
+            event_type: "payment_intent.succeeded".into(),
+            direction: PaymentDirection::Inbound,
+            money: Money::new(MoneyAmount::new(amount).unwrap(), Currency::USD),
+            status: PaymentStatus::Succeeded,
+            metadata,
+            raw_event: serde_json::json!({}),
+            last_event_id: EventId::new(format!("evt_{id}")).unwrap(),
+            parent_external_id: None,
+            provider_ts: 1000,
+            failure: None,
+            authorized_amount: None,
+            receipt: None,
+            tenant_id: None,
+            customer: None,
+        });
+        process_payment_event(&pool, &p, "test").await.unwrap();
+    }
+    let since = chrono::Utc::now() - chrono::Duration::hours(1);
+    let search = |filters: PaymentFilters| {
+        let pool = pool.clone();
+        async move {
+            let page = get_payment_list(
+                &pool,
+                PaymentFilters {
+                    start_date: Some(since),
+                    ..filters
+                },
+            )
+            .await?;
+            let mut ids: Vec<String> = page
+                .payments
+                .iter()
+                .map(|p| p.id.as_str().to_string())
+                .filter(|id| id.starts_with("pi_srch_"))
+                .collect();
+            ids.sort();
+            Ok::<_, fin_sync::error::PipelineError>(ids)
+        }
+    };
+
+    // "The ~$49.99 payment referencing order 1234."
+    let near = search(PaymentFilters {
+        amount_min: Some(4900),
+        amount_max: Some(5100),
+        reference: Some(" 1234 ".into()),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    assert_eq!(near, ["pi_srch_a"]);
+
+    let any_amount = search(PaymentFilters {
+        reference: Some("1234".into()),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    assert_eq!(any_amount, ["pi_srch_a", "pi_srch_c"]);
+
+    // A typo still finds the statement descriptor.
+    let fuzzy = search(PaymentFilters {
+        reference: Some("Acme Ordr 1234".into()),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    assert_eq!(fuzzy, ["pi_srch_c"]);
+
+    let too_short = search(PaymentFilters {
+        reference: Some("12".into()),
+        ..Default::default()
+    })
+    .await;
+    assert!(too_short.is_err());
+}
+
+// ── 91. payment_timeline_interleaves_events_and_audit ──────────────────────
+
+#[tokio::test]
+async fn payment_timeline_interleaves_events_and_audit() {
+    let pool = setup_pool("fin_sync_test_lookup").await;
+    let events = [
+        ("evt_tl_1", PaymentStatus::Pending, 1000),
+        ("evt_tl_2", PaymentStatus::Succeeded, 2000),
+        ("evt_tl_3", PaymentStatus::Pending, 3000),
+    ];
+    for (evt, status, ts) in events {
+        let p = make_payment("pi_timeline", evt, status, ts);
+        process_payment_event(&pool, &p, "test").await.unwrap();
+    }
+
+    let timeline = get_payment_timeline(&pool, ExternalId::new("pi_timeline").unwrap())
+        .await
+        .unwrap()
+        .unwrap();
+    let entries: Vec<_> = timeline
+        .iter()
+        .map(|e| serde_json::to_value(e).unwrap())
+        .collect();
+    let tags: Vec<_> = entries
+        .iter()
+        .map(|e| {
+            let what = e.get("event_type").or_else(|| e.get("action")).unwrap();
+            format!("{}:{}", e["type"].as_str().unwrap(), what.as_str().unwrap())
+        })
+        .collect();
+    assert_eq!(
+        tags,
+        [
+            "provider_event:payment_intent.pending",
+            "audit:created",
+            "provider_event:payment_intent.succeeded",
+            "audit:status_changed",
+            "provider_event:payment_intent.pending",
+            "audit:event_received",
+        ]
+    );
+    assert_eq!(entries[0]["payload"]["id"], "evt_tl_1");
+    assert_eq!(entries[3]["actor"], "test");
+    assert!(timeline.windows(2).all(|w| w[0].at() <= w[1].at()));
+
+    let missing = get_payment_timeline(&pool, ExternalId::new("pi_timeline_none").unwrap())
+        .await
+        .unwrap();
+    assert!(missing.is_none());
+}
@@ -0,0 +1,245 @@
+mod common;
+
+use common::*;
+use fin_sync::domain::adjustment::{TransitionOutcome, TransitionRequest};
+use fin_sync::domain::id::{EventId, ExternalId};
+use fin_sync::domain::manual::{IdempotencyKey, ManualOutcome, ManualPaymentRequest};
+use fin_sync::domain::money::{Currency, Money, MoneyAmount};
+use fin_sync::domain::payment::{NewPayment, NewPaymentParams, PaymentDirection, PaymentStatus};
+use fin_sync::services::payment::adjust::transition_payment;
+use fin_sync::services::payment::manual::submit_manual_payment;
+use fin_sync::services::payment::pipeline::process_payment_event;
+
+fn transfer(external_id: &str, status: PaymentStatus) -> ManualPaymentRequest {
+    ManualPaymentRequest {
+        external_id: ExternalId::new(external_id).unwrap(),
+        direction: PaymentDirection::Inbound,
+        amount: 12_000,
+        currency: Currency::EUR,
+        status,
+        parent_external_id: None,
+        metadata: serde_json::json!({"reference": "INV-1001"}),
+    }
+}
+
+fn key(k: &str) -> IdempotencyKey {
+    IdempotencyKey::new(k).unwrap()
+}
+
+// ── 53. manual_payment_is_idempotent_per_key ───────────────────────────────
+
+#[tokio::test]
+async fn manual_payment_is_idempotent_per_key() {
+    let pool = setup_pool("fin_sync_test_manual").await;
+    let req = transfer("mp_bank_1", PaymentStatus::Succeeded);
+
+    let created = submit_manual_payment(&pool, &req, &key("bank-1"), "manual:erp")
+        .await
+        .unwrap();
+    let ManualOutcome::Created(view) = created else {
+        panic!("expected Created, got {created:?}");
+    };
+    assert_eq!(view.source, "manual");
+    assert_eq!(view.amount, 12_000);
+
+    // Retrying the same request is a no-op that returns the payment, as
+    // the first attempt did.
+    let replay = submit_manual_payment(&pool, &req, &key("bank-1"), "manual:erp")
+        .await
+        .unwrap();
+    let ManualOutcome::Replayed(original) = replay else {
+        panic!("expected Replayed, got {replay:?}");
+    };
+    assert!(matches!(*original, ManualOutcome::Created(ref v) if v.id.as_str() == "mp_bank_1"));
+    assert_eq!(count_payments(&pool, "mp_bank_1").await, 1);
+    assert_eq!(count_audit_entries(&pool, "mp_bank_1").await, 1);
+
+    // The same key with a different body is refused.
+    let mut changed = req.clone();
+    changed.amount = 13_000;
+    let reused = submit_manual_payment(&pool, &changed, &key("bank-1"), "manual:erp")
+        .await
+        .unwrap();
+    assert!(matches!(reused, ManualOutcome::KeyReused));
+
+    // Provider id namespaces are off limits.
+    let stripe_like = transfer("pi_bank_1", PaymentStatus::Succeeded);
+    assert!(
+        submit_manual_payment(&pool, &stripe_like, &key("bank-2"), "manual:erp")
+            .await
+            .is_err()
+    );
+}
+
+// ── 54. manual_payment_follows_state_machine ───────────────────────────────
+
+#[tokio::test]
+async fn manual_payment_follows_state_machine() {
+    let pool = setup_pool("fin_sync_test_manual").await;
+    let pending = transfer("mp_cash_1", PaymentStatus::Pending);
+    submit_manual_payment(&pool, &pending, &key("cash-1a"), "manual:till")
+        .await
+        .unwrap();
+
+    let settled = transfer("mp_cash_1", PaymentStatus::Succeeded);
+    let applied = submit_manual_payment(&pool, &settled, &key("cash-1b"), "manual:till")
+        .await
+        .unwrap();
+    assert!(
+        matches!(applied, ManualOutcome::Applied(ref v) if v.status == PaymentStatus::Succeeded)
+    );
+
+    let back = transfer("mp_cash_1", PaymentStatus::Pending);
+    let rejected = submit_manual_payment(&pool, &back, &key("cash-1c"), "manual:till")
+        .await
+        .unwrap();
+    assert!(matches!(
+        rejected,
+        ManualOutcome::Rejected {
+            current: PaymentStatus::Succeeded
+        }
+    ));
+    // Its retry is refused again, not answered as a success.
+    let retried = submit_manual_payment(&pool, &back, &key("cash-1c"), "manual:till")
+        .await
+        .unwrap();
+    let ManualOutcome::Replayed(original) = retried else {
+        panic!("expected Replayed, got {retried:?}");
+    };
+    assert!(matches!(
+        *original,
+        ManualOutcome::Rejected {
+            current: PaymentStatus::Succeeded
+        }
+    ));
+
+    let audits = get_audit_entries(&pool, "mp_cash_1").await;
+    let actions: Vec<_> = audits.iter().map(|a| a.action.as_str()).collect();
+    assert_eq!(actions, ["created", "status_changed", "event_received"]);
+    assert_eq!(audits[1].event_id.as_deref(), Some("evt_manual_cash-1b"));
+}
+
+// ── 76. admin_transition_goes_through_state_machine ────────────────────────
+
+#[tokio::test]
+async fn admin_transition_goes_through_state_machine() {
+    let pool = setup_pool("fin_sync_test_manual").await;
+    let id = "pi_adjust_1";
+    let pending = NewPayment::new(NewPaymentParams {
+        external_id: ExternalId::new(id).unwrap(),
+        source: "stripe".into(),
+        event_type: "payment_intent.created".into(),
+        direction: PaymentDirection::Inbound,
+        money: Money::new(MoneyAmount::new(5000).unwrap(), Currency::USD),
+        status: PaymentStatus::Pending,
+        metadata: serde_json::json!({"order_id": "ord_9"}),
+        raw_event: serde_json::json!({"id": "evt_adjust_1"}),
+        last_event_id: EventId::new("evt_adjust_1").unwrap(),
+        parent_external_id: None,
+        provider_ts: 1000,
+        failure: None,
+        authorized_amount: None,
+        receipt: None,
+        tenant_id: None,
+        customer: None,
+    });
+    process_payment_event(&pool, &pending, "webhook:stripe")
+        .await
+        .unwrap();
+    let transition = |status, force| TransitionRequest {
+        status,
+        reason: "Stripe support confirmed the charge failed".into(),
+        force,
+    };
+    let pid = || ExternalId::new(id).unwrap();
+
+    let failed = transition_payment(
+        &pool,
+        pid(),
+        &transition(PaymentStatus::Failed, false),
+        "admin:ana",
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    let TransitionOutcome::Applied(view) = failed else {
+        panic!("expected Applied, got {failed:?}");
+    };
+    assert_eq!(view.status, PaymentStatus::Failed);
+    assert_eq!(view.amount, 5000);
+    let metadata: serde_json::Value =
+        sqlx::query_scalar("SELECT metadata FROM payments WHERE external_id = $1")
+            .bind(id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(metadata["order_id"], "ord_9");
+
+    // Failed -> Succeeded isn't allowed; refused and logged as an anomaly.
+    let refused = transition_payment(
+        &pool,
+        pid(),
+        &transition(PaymentStatus::Succeeded, false),
+        "admin:ana",
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert!(matches!(
+        refused,
+        TransitionOutcome::Rejected {
+            current: PaymentStatus::Failed
+        }
+    ));
+
+    let forced = transition_payment(
+        &pool,
+        pid(),
+        &transition(PaymentStatus::Succeeded, true),
+        "admin:ana",
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert!(
+        matches!(forced, TransitionOutcome::Applied(ref v) if v.status == PaymentStatus::Succeeded)
+    );
+
+    let entries: Vec<(String, String, serde_json::Value)> = sqlx::query_as(
+        "SELECT action, actor, detail FROM audit_log WHERE external_id = $1 ORDER BY created_at, id",
+    )
+    .bind(id)
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    let admin: Vec<_> = entries
+        .iter()
+        .filter(|(_, actor, _)| actor == "admin:ana")
+        .collect();
+    assert_eq!(admin.len(), 3);
+    assert!(
+        admin
+            .iter()
+            .all(|(_, _, d)| d["reason"] == "Stripe support confirmed the charge failed")
+    );
+    assert_eq!(admin[0].0, "status_changed");
+    assert_eq!(
+        (admin[1].0.as_str(), &admin[1].2["anomaly"]),
+        ("event_received", &true.into())
+    );
+    assert_eq!(
+        (admin[2].0.as_str(), &admin[2].2["override"]),
+        ("status_changed", &true.into())
+    );
+    assert!(admin[0].2.get("override").is_none());
+
+    let missing = transition_payment(
+        &pool,
+        ExternalId::new("pi_adjust_missing").unwrap(),
+        &transition(PaymentStatus::Failed, false),
+        "admin:ana",
+    )
+    .await
+    .unwrap();
+    assert!(missing.is_none());
+}
@@ -0,0 +1,31 @@
+mod common;
+
+use common::*;
+use fin_sync::services::migrate::{self, MIGRATOR};
+
+// ── 72. concurrent_migrations_apply_once ───────────────────────────────────
+
+#[tokio::test]
+async fn concurrent_migrations_apply_once() {
+    let pool = empty_pool("fin_sync_test_migrate").await;
+    let total = MIGRATOR.iter().count();
+
+    let (a, b) = tokio::join!(migrate::run(&pool), migrate::run(&pool));
+    let (a, b) = (a.unwrap(), b.unwrap());
+    let mut applied = [a.applied.len(), b.applied.len()];
+    applied.sort();
+    assert_eq!(applied, [0, total], "exactly one instance migrates");
+
+    let again = migrate::run(&pool).await.unwrap();
+    assert!(again.applied.is_empty());
+    assert!(!again.waited);
+
+    // A migration edited after it was applied fails verification.
+    sqlx::query("UPDATE _sqlx_migrations SET checksum = '\\x00' WHERE version = $1")
+        .bind(MIGRATOR.iter().next().unwrap().version)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let err = migrate::run(&pool).await.unwrap_err().to_string();
+    assert!(err.contains("modified"), "got: {err}");
+}
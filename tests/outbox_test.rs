@@ -0,0 +1,167 @@
+mod common;
+
+use common::*;
+use fin_sync::domain::audit::NewAuditEntry;
+use fin_sync::domain::outbox::OutboxQuery;
+use fin_sync::domain::payment::PaymentStatus;
+use fin_sync::error::PipelineError;
+use fin_sync::infra::postgres::audit_repo;
+use fin_sync::services::outbox::{commit_offset, list_consumers, poll_events};
+use fin_sync::services::payment::pipeline::process_payment_event;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+fn after(seq: i64) -> OutboxQuery {
+    OutboxQuery {
+        after_seq: Some(seq),
+        ..Default::default()
+    }
+}
+
+fn note(event_id: &str) -> NewAuditEntry {
+    NewAuditEntry {
+        id: Uuid::now_v7(),
+        entity_type: "note".into(),
+        entity_id: None,
+        external_id: None,
+        event_id: event_id.into(),
+        source: None,
+        tenant_id: None,
+        action: "noted".into(),
+        actor: "test".into(),
+        detail: serde_json::json!({}),
+    }
+}
+
+// ── 92. outbox_numbers_committed_events_for_consumers ──────────────────────
+
+#[tokio::test]
+async fn outbox_numbers_committed_events_for_consumers() {
+    let pool = setup_pool("fin_sync_test_outbox").await;
+    for (evt, status, ts) in [
+        ("evt_ob_1", PaymentStatus::Pending, 1000),
+        ("evt_ob_2", PaymentStatus::Succeeded, 2000),
+    ] {
+        let p = make_payment("pi_outbox", evt, status, ts);
+        process_payment_event(&pool, &p, "test").await.unwrap();
+    }
+
+    let page = poll_events(&pool, after(0), Duration::ZERO)
+        .await
+        .unwrap()
+        .unwrap();
+    let seen: Vec<_> = page
+        .events
+        .iter()
+        .map(|e| (e.seq, e.entry.action.as_str()))
+        .collect();
+    assert_eq!(seen, [(1, "created"), (2, "status_changed")]);
+    assert_eq!(page.last_seq, 2);
+
+    // An entry written first but committed last is numbered after the one
+    // that overtook it, so a consumer past that one still gets it.
+    let mut slow = pool.begin().await.unwrap();
+    audit_repo::insert_audit_entry(&mut slow, &note("evt_ob_slow"))
+        .await
+        .unwrap();
+    let mut fast = pool.begin().await.unwrap();
+    audit_repo::insert_audit_entry(&mut fast, &note("evt_ob_fast"))
+        .await
+        .unwrap();
+    fast.commit().await.unwrap();
+    let page = poll_events(&pool, after(2), Duration::ZERO)
+        .await
+        .unwrap()
+        .unwrap();
+    let seen: Vec<_> = page
+        .events
+        .iter()
+        .map(|e| e.entry.event_id.as_str())
+        .collect();
+    assert_eq!(seen, ["evt_ob_fast"]);
+    assert_eq!(page.last_seq, 3);
+    slow.commit().await.unwrap();
+    let page = poll_events(&pool, after(3), Duration::ZERO)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(page.events.len(), 1);
+    assert_eq!(page.events[0].seq, 4);
+    assert_eq!(page.events[0].entry.event_id, "evt_ob_slow");
+
+    // A named consumer reads on from its committed offset.
+    let offset = commit_offset(&pool, "erp", 2).await.unwrap();
+    assert_eq!((offset.last_seq, offset.lag), (2, 2));
+    let page = poll_events(
+        &pool,
+        OutboxQuery {
+            consumer: Some("erp".into()),
+            limit: Some(1),
+            ..Default::default()
+        },
+        Duration::ZERO,
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(page.events[0].seq, 3);
+    commit_offset(&pool, "erp", page.last_seq).await.unwrap();
+    let consumers = list_consumers(&pool).await.unwrap();
+    assert_eq!(consumers.len(), 1);
+    assert_eq!((consumers[0].last_seq, consumers[0].lag), (3, 1));
+    assert!(matches!(
+        commit_offset(&pool, "erp", 5).await,
+        Err(PipelineError::Validation(_))
+    ));
+    assert!(matches!(
+        commit_offset(&pool, "erp sync", 1).await,
+        Err(PipelineError::Validation(_))
+    ));
+
+    // A long poll at the head answers as soon as something is committed.
+    let writer = {
+        let pool = pool.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            let p = make_payment("pi_outbox_2", "evt_ob_3", PaymentStatus::Pending, 3000);
+            process_payment_event(&pool, &p, "test").await.unwrap();
+        })
+    };
+    let started = Instant::now();
+    let page = poll_events(
+        &pool,
+        OutboxQuery {
+            after_seq: Some(4),
+            wait: Some(10),
+            ..Default::default()
+        },
+        Duration::from_secs(10),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    writer.await.unwrap();
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert_eq!(page.events.len(), 1);
+    assert_eq!(page.events[0].seq, 5);
+    assert_eq!(
+        page.events[0].entry.external_id.as_deref(),
+        Some("pi_outbox_2")
+    );
+
+    // With nothing to wait for, the wait runs out and the offset stands.
+    let page = poll_events(
+        &pool,
+        OutboxQuery {
+            after_seq: Some(5),
+            wait: Some(10),
+            ..Default::default()
+        },
+        Duration::from_millis(200),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert!(page.events.is_empty());
+    assert_eq!(page.last_seq, 5);
+}
@@ -2,7 +2,11 @@ mod common;
 
 use common::*;
 use fin_sync::domain::id::{EventId, ExternalId};
+use fin_sync::domain::job::JobPriority;
 use fin_sync::domain::payment::{PassthroughEvent, PaymentStatus};
+use fin_sync::infra::postgres::job_repo;
+use fin_sync::infra::postgres::payment_repo::get_provider_event_payload;
+use fin_sync::services::migrate::compress_stored_payloads;
 use fin_sync::services::payment::pipeline::{handle_passthrough, process_payment_event};
 
 // ── 21. passthrough_logs_event ─────────────────────────────────────────────
@@ -13,6 +17,8 @@ async fn passthrough_logs_event() {
 
     let event = PassthroughEvent {
         external_id: Some(ExternalId::new("pi_pt_1").unwrap()),
+        source: "stripe".into(),
+        tenant_id: None,
         event_id: EventId::new("evt_pt_1").unwrap(),
         event_type: "charge.created".into(),
         provider_ts: 1000,
@@ -38,6 +44,8 @@ async fn passthrough_duplicate_returns_false() {
 
     let event = PassthroughEvent {
         external_id: Some(ExternalId::new("pi_ptd").unwrap()),
+        source: "stripe".into(),
+        tenant_id: None,
         event_id: EventId::new("evt_ptd_1").unwrap(),
         event_type: "charge.created".into(),
         provider_ts: 1000,
@@ -71,6 +79,8 @@ async fn passthrough_links_existing_payment() {
     // Now log a passthrough event referencing the same external_id
     let event = PassthroughEvent {
         external_id: Some(ExternalId::new("pi_ptlink").unwrap()),
+        source: "stripe".into(),
+        tenant_id: None,
         event_id: EventId::new("evt_ptlink_pt").unwrap(),
         event_type: "charge.succeeded".into(),
         provider_ts: 2000,
@@ -100,6 +110,8 @@ async fn passthrough_no_existing_payment() {
 
     let event = PassthroughEvent {
         external_id: Some(ExternalId::new("pi_nonexistent").unwrap()),
+        source: "stripe".into(),
+        tenant_id: None,
         event_id: EventId::new("evt_ptnone").unwrap(),
         event_type: "charge.created".into(),
         provider_ts: 1000,
@@ -127,6 +139,8 @@ async fn passthrough_with_none_external_id() {
 
     let event = PassthroughEvent {
         external_id: None,
+        source: "stripe".into(),
+        tenant_id: None,
         event_id: EventId::new("evt_ptnull").unwrap(),
         event_type: "unknown.event".into(),
         provider_ts: 1000,
@@ -148,3 +162,117 @@ async fn passthrough_with_none_external_id() {
     assert!(ext_id.is_none());
     assert!(entity_id.is_none());
 }
+
+// ── 73. payloads_compressed_at_rest ────────────────────────────────────────
+
+#[tokio::test]
+async fn payloads_compressed_at_rest() {
+    let pool = setup_pool("fin_sync_test_passthrough").await;
+    let stored = async |event_id: &str| -> Vec<u8> {
+        sqlx::query_scalar("SELECT payload FROM provider_events WHERE event_id = $1")
+            .bind(event_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+    };
+    let payload = serde_json::json!({
+        "type": "charge.updated",
+        "data": {"object": {"id": "ch_big", "description": "line item ".repeat(500)}},
+    });
+    let json_len = serde_json::to_vec(&payload).unwrap().len();
+
+    let event = PassthroughEvent {
+        external_id: None,
+        source: "stripe".into(),
+        tenant_id: None,
+        event_id: EventId::new("evt_pt_big").unwrap(),
+        event_type: "charge.updated".into(),
+        provider_ts: 1000,
+        raw_payload: payload.clone(),
+        actor: "test".into(),
+    };
+    handle_passthrough(&pool, &event).await.unwrap();
+    let bytes = stored("evt_pt_big").await;
+    assert_eq!(bytes[0], 1, "zstd format marker");
+    assert!(bytes.len() * 5 < json_len);
+    assert_eq!(
+        get_provider_event_payload(&pool, "stripe", "evt_pt_big")
+            .await
+            .unwrap(),
+        Some(payload.clone())
+    );
+
+    // A row written before compression reads back as-is, then gets compressed.
+    sqlx::query(
+        "INSERT INTO provider_events (source, event_id, object_id, event_type, provider_ts, payload)
+         VALUES ('stripe', 'evt_pt_legacy', '', 'charge.updated', 1000,
+                 '\\x00'::bytea || convert_to($1::jsonb::text, 'UTF8'))",
+    )
+    .bind(&payload)
+    .execute(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        get_provider_event_payload(&pool, "stripe", "evt_pt_legacy")
+            .await
+            .unwrap(),
+        Some(payload.clone())
+    );
+    assert!(compress_stored_payloads(&pool, 2).await.unwrap() >= 1);
+    assert_eq!(stored("evt_pt_legacy").await[0], 1);
+    assert_eq!(
+        get_provider_event_payload(&pool, "stripe", "evt_pt_legacy")
+            .await
+            .unwrap(),
+        Some(payload)
+    );
+}
+
+// ── 100. event_ids_dedup_per_source ────────────────────────────────────────
+
+#[tokio::test]
+async fn event_ids_dedup_per_source() {
+    let pool = setup_pool("fin_sync_test_passthrough").await;
+    let event = |source: &str| PassthroughEvent {
+        external_id: Some(ExternalId::new("pi_pt_src").unwrap()),
+        source: source.into(),
+        tenant_id: None,
+        event_id: EventId::new("evt_pt_shared").unwrap(),
+        event_type: "charge.created".into(),
+        provider_ts: 1000,
+        raw_payload: serde_json::json!({"source": source}),
+        actor: "test".into(),
+    };
+
+    // The same id from another provider is a different event.
+    assert!(handle_passthrough(&pool, &event("stripe")).await.unwrap());
+    assert!(handle_passthrough(&pool, &event("paypal")).await.unwrap());
+    assert!(!handle_passthrough(&pool, &event("paypal")).await.unwrap());
+    assert_eq!(get_audit_entries(&pool, "pi_pt_src").await.len(), 2);
+    assert_eq!(
+        get_provider_event_payload(&pool, "paypal", "evt_pt_shared")
+            .await
+            .unwrap(),
+        Some(serde_json::json!({"source": "paypal"}))
+    );
+
+    let enqueue = async |source: &str| {
+        job_repo::enqueue(
+            &pool,
+            source,
+            None,
+            "evt_pt_shared",
+            "pi_pt_src",
+            "payment_intent.succeeded",
+            1000,
+            &serde_json::json!({}),
+            JobPriority::Normal,
+        )
+        .await
+        .unwrap()
+        .is_some()
+    };
+    assert!(enqueue("stripe").await);
+    assert!(enqueue("paypal").await);
+    assert!(!enqueue("stripe").await);
+}
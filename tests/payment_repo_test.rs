@@ -1,7 +1,12 @@
 mod common;
 
 use common::*;
-use fin_sync::domain::payment::{PaymentStatus, ProcessResult};
+use fin_sync::domain::id::{EventId, ExternalId};
+use fin_sync::domain::money::{Currency, Money, MoneyAmount};
+use fin_sync::domain::payment::{
+    NewPayment, NewPaymentParams, PaymentDirection, PaymentStatus, ProcessResult,
+};
+use fin_sync::services::payment::lookup::get_payment_by_id;
 use fin_sync::services::payment::pipeline::process_payment_event;
 
 // ── 1. create_new_payment ──────────────────────────────────────────────────
@@ -117,21 +122,21 @@ async fn duplicate_event_returns_duplicate() {
     assert!(matches!(result, ProcessResult::Duplicate));
 }
 
-// ── 8. same_status_returns_stale ───────────────────────────────────────────
+// ── 8. same_status_returns_unchanged ───────────────────────────────────────
 
 #[tokio::test]
-async fn same_status_returns_stale() {
+async fn same_status_returns_unchanged() {
     let pool = setup_pool("fin_sync_test_payment").await;
     let p1 = make_payment("pi_same", "evt_same1", PaymentStatus::Pending, 1000);
     process_payment_event(&pool, &p1, "test").await.unwrap();
 
     let p2 = make_payment("pi_same", "evt_same2", PaymentStatus::Pending, 2000);
     let result = process_payment_event(&pool, &p2, "test").await.unwrap();
-    assert!(matches!(result, ProcessResult::Stale(_)));
+    assert!(matches!(result, ProcessResult::Unchanged(_)));
 
-    // No additional audit entry for same-status stale
+    // Audited as unchanged, so it's told apart from an out-of-order event
     let count = count_audit_entries(&pool, "pi_same").await;
-    assert_eq!(count, 1); // only the "created" entry
+    assert_eq!(count, 2); // "created", then "status_unchanged"
 }
 
 // ── 9. older_timestamp_valid_transition_still_advances ─────────────────────
@@ -339,7 +344,7 @@ async fn check_constraint_rejects_invalid_status() {
             (id, external_id, source, event_type, direction, amount, currency,
              status, metadata, raw_event, last_event_id, last_provider_ts)
         VALUES (gen_random_uuid(), 'pi_bad_status', 'stripe', 'test', 'inbound',
-                1000, 'usd', 'cancelled', '{}', '{}', 'evt_x', 1000)
+                1000, 'usd', 'voided', '{}', '{}', 'evt_x', 1000)
         "#,
     )
     .execute(&pool)
@@ -377,3 +382,100 @@ async fn check_constraint_rejects_negative_amount() {
         "expected check constraint violation, got: {err}"
     );
 }
+
+// ── 64. transitions_follow_the_source_policy ───────────────────────────────
+
+#[tokio::test]
+async fn transitions_follow_the_source_policy() {
+    let pool = setup_pool("fin_sync_test_payment").await;
+    let capture = |status: PaymentStatus, evt: &str, ts| {
+        NewPayment::new(NewPaymentParams {
+            external_id: ExternalId::new("pp_cap_policy").unwrap(),
+            source: "paypal".into(),
+            event_type: "PAYMENT.CAPTURE.PENDING".into(),
+            direction: PaymentDirection::Inbound,
+            money: Money::new(MoneyAmount::new(5000).unwrap(), Currency::USD),
+            status,
+            metadata: serde_json::json!({}),
+            raw_event: serde_json::json!({"id": evt}),
+            last_event_id: EventId::new(evt).unwrap(),
+            parent_external_id: None,
+            provider_ts: ts,
+            failure: None,
+            authorized_amount: None,
+            receipt: None,
+            tenant_id: None,
+            customer: None,
+        })
+    };
+    let pending = capture(PaymentStatus::Pending, "evt_pp_pol1", 1000);
+    process_payment_event(&pool, &pending, "test")
+        .await
+        .unwrap();
+
+    // Stripe allows Pending -> Refunded on an inbound row (see test 5); for
+    // PayPal the refund is its own row, so this is an anomaly.
+    let refunded = capture(PaymentStatus::Refunded, "evt_pp_pol2", 2000);
+    let result = process_payment_event(&pool, &refunded, "test")
+        .await
+        .unwrap();
+    assert!(matches!(result, ProcessResult::Anomaly(_)));
+    assert_eq!(
+        get_payment(&pool, "pp_cap_policy").await.unwrap().status,
+        "pending"
+    );
+}
+
+// ── 71. authorization_amount_tracked_until_expiry ──────────────────────────
+
+#[tokio::test]
+async fn authorization_amount_tracked_until_expiry() {
+    let pool = setup_pool("fin_sync_test_payment").await;
+    let intent = |status: PaymentStatus, evt: &str, ts, authorized: Option<i64>| {
+        NewPayment::new(NewPaymentParams {
+            external_id: ExternalId::new("pi_auth_exp").unwrap(),
+            source: "stripe".into(),
+            event_type: "payment_intent.amount_capturable_updated".into(),
+            direction: PaymentDirection::Inbound,
+            money: Money::new(MoneyAmount::new(5000).unwrap(), Currency::USD),
+            status,
+            metadata: serde_json::json!({}),
+            raw_event: serde_json::json!({"id": evt}),
+            last_event_id: EventId::new(evt).unwrap(),
+            parent_external_id: None,
+            provider_ts: ts,
+            failure: None,
+            authorized_amount: authorized.map(|c| MoneyAmount::new(c).unwrap()),
+            receipt: None,
+            tenant_id: None,
+            customer: None,
+        })
+    };
+    let authorized_amount = async || {
+        get_payment_by_id(&pool, ExternalId::new("pi_auth_exp").unwrap())
+            .await
+            .unwrap()
+            .unwrap()
+            .authorized_amount
+    };
+
+    let held = intent(PaymentStatus::Pending, "evt_auth1", 1000, Some(5000));
+    process_payment_event(&pool, &held, "test").await.unwrap();
+    assert_eq!(authorized_amount().await, Some(5000));
+
+    // An incremental authorization moves the held amount without a status change.
+    let raised = intent(PaymentStatus::Pending, "evt_auth2", 2000, Some(7000));
+    process_payment_event(&pool, &raised, "test").await.unwrap();
+    assert_eq!(authorized_amount().await, Some(7000));
+
+    let expired = intent(PaymentStatus::Expired, "evt_auth3", 3000, None);
+    let result = process_payment_event(&pool, &expired, "test")
+        .await
+        .unwrap();
+    assert!(matches!(result, ProcessResult::Updated(_)));
+    assert_eq!(
+        get_payment(&pool, "pi_auth_exp").await.unwrap().status,
+        "expired"
+    );
+    assert_eq!(authorized_amount().await, Some(7000));
+}
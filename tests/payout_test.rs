@@ -0,0 +1,162 @@
+mod common;
+
+use common::*;
+use fin_sync::domain::id::{EventId, PayoutId};
+use fin_sync::domain::money::{Currency, Money, MoneyAmount};
+use fin_sync::domain::payment::ProcessResult;
+use fin_sync::domain::payout::{NewPayout, PayoutFilters, PayoutKind, PayoutStatus};
+use fin_sync::services::payout::{get_payout, list_payouts, process_payout_event};
+
+fn payout(event_id: &str, status: PayoutStatus, provider_ts: i64) -> NewPayout {
+    NewPayout {
+        external_id: PayoutId::new("po_test").unwrap(),
+        source: "stripe".to_string(),
+        kind: PayoutKind::Payout,
+        money: Money::new(MoneyAmount::new(50_000).unwrap(), Currency::USD),
+        status,
+        amount_reversed: MoneyAmount::new(0).unwrap(),
+        destination: Some("ba_1".into()),
+        arrival_date: None,
+        failure_code: None,
+        failure_message: None,
+        metadata: serde_json::json!({}),
+        event_id: EventId::new(event_id).unwrap(),
+        event_type: format!("payout.{}", status.as_str()),
+        provider_ts,
+        request_id: None,
+        raw_event: serde_json::json!({"id": event_id}),
+    }
+}
+
+// ── 84. payout_lifecycle_is_audited ────────────────────────────────────────
+
+#[tokio::test]
+async fn payout_lifecycle_is_audited() {
+    let pool = setup_pool("fin_sync_test_payout").await;
+    let status = || async {
+        get_payout(&pool, "po_test")
+            .await
+            .unwrap()
+            .map(|p| p.status)
+    };
+
+    let created = payout("evt_po_1", PayoutStatus::Pending, 1000);
+    let r = process_payout_event(&pool, &created, "test").await.unwrap();
+    assert!(matches!(r, ProcessResult::Created(_)));
+    assert_eq!(status().await.as_deref(), Some("pending"));
+
+    // Redelivery of the same event.
+    let r = process_payout_event(&pool, &created, "test").await.unwrap();
+    assert!(matches!(r, ProcessResult::Duplicate));
+
+    let r = process_payout_event(&pool, &payout("evt_po_2", PayoutStatus::Paid, 1002), "test")
+        .await
+        .unwrap();
+    assert!(matches!(r, ProcessResult::Updated(_)));
+
+    // An update sent before it was paid, arriving late.
+    let r = process_payout_event(
+        &pool,
+        &payout("evt_po_3", PayoutStatus::Pending, 1001),
+        "test",
+    )
+    .await
+    .unwrap();
+    assert!(matches!(r, ProcessResult::StaleIgnored(_)));
+    assert_eq!(status().await.as_deref(), Some("paid"));
+
+    // A paid payout can't be cancelled, but the bank can return it.
+    let r = process_payout_event(
+        &pool,
+        &payout("evt_po_4", PayoutStatus::Cancelled, 1003),
+        "test",
+    )
+    .await
+    .unwrap();
+    assert!(matches!(r, ProcessResult::Anomaly(_)));
+    let mut failed = payout("evt_po_5", PayoutStatus::Failed, 1004);
+    failed.failure_code = Some("account_closed".into());
+    let r = process_payout_event(&pool, &failed, "test").await.unwrap();
+    assert!(matches!(r, ProcessResult::Updated(_)));
+
+    let stored = get_payout(&pool, "po_test").await.unwrap().unwrap();
+    assert_eq!(stored.status, "failed");
+    assert_eq!(stored.failure_code.as_deref(), Some("account_closed"));
+    assert_eq!(stored.last_event_id, "evt_po_5");
+
+    let actions: Vec<_> = get_audit_entries(&pool, "po_test")
+        .await
+        .into_iter()
+        .map(|e| (e.action, e.detail["anomaly"].as_bool().unwrap_or(false)))
+        .collect();
+    assert_eq!(
+        actions,
+        [
+            ("created".to_string(), false),
+            ("status_changed".to_string(), false),
+            ("event_received".to_string(), true),
+            ("status_changed".to_string(), false),
+        ]
+    );
+
+    let filters = PayoutFilters {
+        status: Some("failed".into()),
+        ..Default::default()
+    };
+    assert_eq!(list_payouts(&pool, &filters).await.unwrap().len(), 1);
+    let filters = PayoutFilters {
+        status: Some("in_transit".into()),
+        ..Default::default()
+    };
+    assert!(list_payouts(&pool, &filters).await.is_err());
+}
+
+// ── 103. same_second_payout_events_are_ordered_by_id_and_request ───────────
+
+#[tokio::test]
+async fn same_second_payout_events_are_ordered_by_id_and_request() {
+    let pool = setup_pool("fin_sync_test_payout").await;
+    let burst = |event_id: &str, status, request_id: Option<&str>| NewPayout {
+        external_id: PayoutId::new("po_burst").unwrap(),
+        request_id: request_id.map(Into::into),
+        ..payout(event_id, status, 2000)
+    };
+
+    let r = process_payout_event(
+        &pool,
+        &burst("evt_bu_1", PayoutStatus::Pending, None),
+        "test",
+    )
+    .await
+    .unwrap();
+    assert!(matches!(r, ProcessResult::Created(_)));
+    let r = process_payout_event(&pool, &burst("evt_bu_3", PayoutStatus::Paid, None), "test")
+        .await
+        .unwrap();
+    assert!(matches!(r, ProcessResult::Updated(_)));
+
+    // Delivered late, but issued before the paid event in the same second:
+    // stale, not an anomaly.
+    let r = process_payout_event(
+        &pool,
+        &burst("evt_bu_2", PayoutStatus::Pending, None),
+        "test",
+    )
+    .await
+    .unwrap();
+    assert!(matches!(r, ProcessResult::StaleIgnored(_)));
+
+    // Once two API requests race within the second, the ids can't order
+    // them and the state machine decides.
+    let raced = burst("evt_bu_4", PayoutStatus::Paid, Some("req_1"));
+    process_payout_event(&pool, &raced, "test").await.unwrap();
+    let late = burst("evt_bu_0", PayoutStatus::Pending, Some("req_2"));
+    let r = process_payout_event(&pool, &late, "test").await.unwrap();
+    assert!(matches!(r, ProcessResult::Anomaly(_)));
+
+    let stored = get_payout(&pool, "po_burst").await.unwrap().unwrap();
+    assert_eq!(
+        (stored.status.as_str(), stored.last_event_id.as_str()),
+        ("paid", "evt_bu_4")
+    );
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use common::*;
+use fin_sync::adapters::paypal::client::{PAYPAL_SOURCE, convert_capture, convert_refund};
+use fin_sync::domain::id::{EventId, ExternalId};
+use fin_sync::domain::payment::{PaymentTrigger, ProcessResult};
+use fin_sync::services::payment::pipeline::process_fetched_payment;
+
+fn trigger(event_id: &str, event_type: &str, fetched_id: &str, ts: i64) -> PaymentTrigger {
+    PaymentTrigger {
+        source: PAYPAL_SOURCE.into(),
+        tenant_id: None,
+        event_id: EventId::new(event_id).unwrap(),
+        event_type: event_type.into(),
+        external_id: ExternalId::new(fetched_id).unwrap(),
+        raw_event: serde_json::json!({"id": event_id}),
+        provider_ts: ts,
+    }
+}
+
+fn capture(status: &str) -> serde_json::Value {
+    serde_json::json!({
+        "id": "5O190127TN364715T",
+        "status": status,
+        "amount": {"currency_code": "EUR", "value": "42.50"},
+        "custom_id": "order-9",
+    })
+}
+
+// ── 55. paypal_capture_and_refund_run_through_pipeline ─────────────────────
+
+#[tokio::test]
+async fn paypal_capture_and_refund_run_through_pipeline() {
+    let pool = setup_pool("fin_sync_test_paypal").await;
+    let cap_id = "pp_cap_5O190127TN364715T";
+
+    let events = [
+        ("evt_pp_WH-1", "PAYMENT.CAPTURE.PENDING", "PENDING", 1000),
+        (
+            "evt_pp_WH-2",
+            "PAYMENT.CAPTURE.COMPLETED",
+            "COMPLETED",
+            2000,
+        ),
+    ];
+    for (evt, event_type, status, ts) in events {
+        let fetched = convert_capture(&capture(status)).unwrap();
+        process_fetched_payment(
+            &pool,
+            fetched,
+            trigger(evt, event_type, cap_id, ts),
+            "webhook:paypal",
+        )
+        .await
+        .unwrap();
+    }
+    let row = get_payment(&pool, cap_id).await.unwrap();
+    assert_eq!(row.status, "succeeded");
+    assert_eq!((row.amount, row.currency.as_str()), (4250, "eur"));
+    assert_eq!(row.direction, "inbound");
+
+    let refund = convert_refund(&serde_json::json!({
+        "id": "1JU08902781691411",
+        "status": "COMPLETED",
+        "amount": {"currency_code": "EUR", "value": "12.50"},
+        "links": [{"rel": "up", "href": "https://api-m.paypal.com/v2/payments/captures/5O190127TN364715T"}],
+    }))
+    .unwrap();
+    let refund_trigger = trigger(
+        "evt_pp_WH-3",
+        "PAYMENT.CAPTURE.REFUNDED",
+        "pp_ref_1JU08902781691411",
+        3000,
+    );
+    let result = process_fetched_payment(&pool, refund.clone(), refund_trigger, "webhook:paypal")
+        .await
+        .unwrap();
+    assert!(matches!(result, ProcessResult::Created(_)));
+
+    // Redelivery of the same PayPal event is deduped.
+    let again = process_fetched_payment(
+        &pool,
+        refund,
+        trigger(
+            "evt_pp_WH-3",
+            "PAYMENT.CAPTURE.REFUNDED",
+            "pp_ref_1JU08902781691411",
+            3000,
+        ),
+        "webhook:paypal",
+    )
+    .await
+    .unwrap();
+    assert!(matches!(again, ProcessResult::Duplicate));
+
+    let refund_row = get_payment(&pool, "pp_ref_1JU08902781691411")
+        .await
+        .unwrap();
+    assert_eq!(refund_row.status, "refunded");
+    assert_eq!(refund_row.parent_external_id.as_deref(), Some(cap_id));
+    let source: String = sqlx::query_scalar("SELECT source FROM payments WHERE external_id = $1")
+        .bind(cap_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(source, "paypal");
+    assert_eq!(count_audit_entries(&pool, cap_id).await, 2);
+}
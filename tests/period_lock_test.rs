@@ -0,0 +1,239 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use common::*;
+use fin_sync::domain::id::{EventId, ExternalId};
+use fin_sync::domain::money::{Currency, Money, MoneyAmount};
+use fin_sync::domain::payment::{
+    NewPayment, NewPaymentParams, PaymentDirection, PaymentStatus, ProcessResult,
+};
+use fin_sync::domain::period::{CloseRequest, EodCutoff};
+use fin_sync::error::PipelineError;
+use fin_sync::infra::settings::Settings;
+use fin_sync::services::payment::pipeline::{process_payment_event, process_payment_event_dry_run};
+use fin_sync::services::period::{self, Resolution};
+use sqlx::PgPool;
+
+fn event(external_id: &str, event_id: &str, status: PaymentStatus, provider_ts: i64) -> NewPayment {
+    NewPayment::new(NewPaymentParams {
+        external_id: ExternalId::new(external_id).unwrap(),
+        source: "stripe".to_string(),
+        event_type: format!("payment_intent.{}", status.as_str()),
+        direction: PaymentDirection::Inbound,
+        money: Money::new(MoneyAmount::new(5000).unwrap(), Currency::USD),
+        status,
+        metadata: serde_json::json!({}),
+        raw_event: serde_json::json!({"id": event_id}),
+        last_event_id: EventId::new(event_id).unwrap(),
+        parent_external_id: None,
+        provider_ts,
+        failure: None,
+        authorized_amount: None,
+        receipt: None,
+        tenant_id: None,
+        customer: None,
+    })
+}
+
+async fn ledger_entries(pool: &PgPool, external_id: &str) -> i64 {
+    sqlx::query_scalar(
+        "SELECT COUNT(*) FROM ledger_entries l JOIN payments p ON p.id = l.payment_id \
+         WHERE p.external_id = $1",
+    )
+    .bind(external_id)
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+async fn actions(pool: &PgPool, external_id: &str) -> Vec<String> {
+    get_audit_entries(pool, external_id)
+        .await
+        .into_iter()
+        .map(|e| e.action)
+        .collect()
+}
+
+// ── 124. late_events_in_a_closed_period_are_held_for_adjustment ───────────
+
+#[tokio::test]
+async fn late_events_in_a_closed_period_are_held_for_adjustment() {
+    let pool = setup_pool("fin_sync_test_period_lock").await;
+    let settings = Settings::new(pool.clone());
+
+    // The business day closed ended at midnight UTC, three days ago.
+    let business_date = (Utc::now() - Duration::days(4)).date_naive();
+    let end = EodCutoff::default().end_of(business_date).timestamp();
+    for id in ["pi_pl_1", "pi_pl_2", "pi_pl_3"] {
+        let created = event(
+            id,
+            &format!("evt_{id}_a"),
+            PaymentStatus::Pending,
+            end - 3600,
+        );
+        process_payment_event(&pool, &created, "test")
+            .await
+            .unwrap();
+    }
+
+    // The cutoff is a setting; a bad one is refused.
+    let bad = EodCutoff {
+        hour: 0,
+        utc_offset_minutes: 0,
+    };
+    assert!(period::set_cutoff(&settings, &bad, "ops").await.is_err());
+
+    let request = CloseRequest {
+        business_date,
+        reason: "Q1 filed".into(),
+    };
+    let close = period::close(&pool, &settings, &request, "admin:ana")
+        .await
+        .unwrap();
+    assert_eq!(close.closed_through.timestamp(), end);
+
+    // Closes go forward only, and only over days that are done.
+    let again = period::close(&pool, &settings, &request, "admin:ana").await;
+    assert!(matches!(again, Err(PipelineError::Validation(_))));
+    let tomorrow = CloseRequest {
+        business_date: (Utc::now() + Duration::days(1)).date_naive(),
+        reason: "early".into(),
+    };
+    let early = period::close(&pool, &settings, &tomorrow, "admin:ana").await;
+    assert!(matches!(early, Err(PipelineError::Validation(_))));
+
+    // A late event dated in the closed period moves nothing and posts
+    // nothing; it's held for review, and audited as such.
+    let late = event("pi_pl_1", "evt_pl_1_b", PaymentStatus::Succeeded, end - 60);
+    let dry_run = process_payment_event_dry_run(&pool, &late).await.unwrap();
+    assert_eq!(
+        (dry_run.result, dry_run.audit_action.as_deref()),
+        ("diverted", Some("event_diverted"))
+    );
+    assert!(dry_run.ledger.is_empty());
+    let result = process_payment_event(&pool, &late, "test").await.unwrap();
+    assert!(matches!(result, ProcessResult::Diverted(_)));
+    let payment = get_payment(&pool, "pi_pl_1").await.unwrap();
+    assert_eq!(
+        (payment.status.as_str(), payment.last_event_id.as_str()),
+        ("pending", "evt_pi_pl_1_a")
+    );
+    assert_eq!(ledger_entries(&pool, "pi_pl_1").await, 0);
+    let entries = get_audit_entries(&pool, "pi_pl_1").await;
+    let diverted = entries.last().unwrap();
+    assert_eq!(diverted.action, "event_diverted");
+    assert_eq!(diverted.detail["incoming_status"], "succeeded");
+    let pending = period::adjustments(&pool, Some("pending"), None)
+        .await
+        .unwrap();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(diverted.detail["adjustment_id"], pending[0].id.to_string());
+    assert_eq!(
+        (
+            pending[0].from_status.as_str(),
+            pending[0].to_status.as_str()
+        ),
+        ("pending", "succeeded")
+    );
+
+    // An event dated after the close applies as usual.
+    let current = event("pi_pl_2", "evt_pl_2_b", PaymentStatus::Failed, end + 60);
+    let result = process_payment_event(&pool, &current, "test")
+        .await
+        .unwrap();
+    assert!(matches!(result, ProcessResult::Updated(_)));
+
+    // Applying the adjustment makes the change now, in the open period.
+    let applied = period::apply_adjustment(&pool, pending[0].id, "late capture", "admin:ana")
+        .await
+        .unwrap();
+    assert!(matches!(&applied, Some(Resolution::Resolved(a)) if a.status == "applied"));
+    assert_eq!(
+        get_payment(&pool, "pi_pl_1").await.unwrap().status,
+        "succeeded"
+    );
+    assert!(ledger_entries(&pool, "pi_pl_1").await > 0);
+    let again = period::apply_adjustment(&pool, pending[0].id, "twice", "admin:ana")
+        .await
+        .unwrap();
+    assert!(matches!(again, Some(Resolution::AlreadySettled(_))));
+    assert_eq!(
+        actions(&pool, "pi_pl_1").await,
+        [
+            "created",
+            "event_diverted",
+            "status_changed",
+            "period_adjustment_applied"
+        ]
+    );
+
+    // One dismissed stays as the filed books show it.
+    let late = event("pi_pl_3", "evt_pl_3_b", PaymentStatus::Failed, end - 60);
+    let result = process_payment_event(&pool, &late, "test").await.unwrap();
+    assert!(matches!(result, ProcessResult::Diverted(_)));
+    let held = period::adjustments(&pool, Some("pending"), None)
+        .await
+        .unwrap();
+    let dismissed = period::dismiss_adjustment(&pool, held[0].id, "already refunded", "ops")
+        .await
+        .unwrap();
+    assert!(matches!(dismissed, Some(Resolution::Resolved(a)) if a.status == "dismissed"));
+    assert_eq!(
+        get_payment(&pool, "pi_pl_3").await.unwrap().status,
+        "pending"
+    );
+
+    // A payment first seen through a late event is recorded at pending,
+    // posting nothing, and its settlement held like any other move.
+    let first = event("pi_pl_4", "evt_pl_4_a", PaymentStatus::Succeeded, end - 120);
+    let result = process_payment_event(&pool, &first, "test").await.unwrap();
+    assert!(matches!(result, ProcessResult::Diverted(_)));
+    assert_eq!(
+        get_payment(&pool, "pi_pl_4").await.unwrap().status,
+        "pending"
+    );
+    assert_eq!(ledger_entries(&pool, "pi_pl_4").await, 0);
+    assert_eq!(actions(&pool, "pi_pl_4").await, ["event_diverted"]);
+    let held = period::adjustments(&pool, Some("pending"), None)
+        .await
+        .unwrap();
+    assert!(held[0].on_create);
+    assert_eq!(held[0].external_id, "pi_pl_4");
+    period::apply_adjustment(&pool, held[0].id, "late capture", "admin:ana")
+        .await
+        .unwrap();
+    assert_eq!(
+        get_payment(&pool, "pi_pl_4").await.unwrap().status,
+        "succeeded"
+    );
+    assert!(ledger_entries(&pool, "pi_pl_4").await > 0);
+
+    // One first seen pending holds nothing to divert.
+    let pending_first = event("pi_pl_5", "evt_pl_5_a", PaymentStatus::Pending, end - 120);
+    let result = process_payment_event(&pool, &pending_first, "test")
+        .await
+        .unwrap();
+    assert!(matches!(result, ProcessResult::Created(_)));
+
+    // Reopened, the period takes late events again.
+    let reopened = period::reopen(&pool, "restatement", "admin:ana")
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(reopened.reopened_at.is_some());
+    let status = period::status(&pool, &settings).await.unwrap();
+    assert_eq!(status.closed_through, None);
+    let late = event("pi_pl_3", "evt_pl_3_c", PaymentStatus::Failed, end - 30);
+    let result = process_payment_event(&pool, &late, "test").await.unwrap();
+    assert!(matches!(result, ProcessResult::Updated(_)));
+    assert!(
+        period::reopen(&pool, "nothing left", "admin:ana")
+            .await
+            .unwrap()
+            .is_none()
+    );
+    assert_eq!(
+        actions(&pool, &business_date.to_string()).await,
+        ["period_closed", "period_reopened"]
+    );
+}
@@ -8,15 +8,21 @@ fn arb_status() -> impl Strategy<Value = PaymentStatus> {
         Just(PaymentStatus::Succeeded),
         Just(PaymentStatus::Failed),
         Just(PaymentStatus::Refunded),
+        Just(PaymentStatus::Disputed),
+        Just(PaymentStatus::DisputeWon),
+        Just(PaymentStatus::DisputeLost),
+        Just(PaymentStatus::Expired),
+        Just(PaymentStatus::Cancelled),
     ]
 }
 
 proptest! {
-    /// Terminal states (Succeeded, Failed, Refunded) can never transition to anything.
+    /// Terminal states (Succeeded, Failed, Refunded, DisputeWon, DisputeLost, Expired,
+    /// Cancelled) can never transition to anything.
     #[test]
     fn terminal_states_reject_all_transitions(target in arb_status()) {
         use PaymentStatus::*;
-        for terminal in [Succeeded, Failed, Refunded] {
+        for terminal in [Succeeded, Failed, Refunded, DisputeWon, DisputeLost, Expired, Cancelled] {
             prop_assert!(!terminal.can_transition_to(&target));
         }
     }
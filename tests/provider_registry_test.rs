@@ -0,0 +1,100 @@
+mod common;
+
+use common::*;
+use fin_sync::domain::job::{JobLane, JobPriority};
+use fin_sync::domain::provider::ProviderRegistry;
+use fin_sync::infra::postgres::job_repo;
+use fin_sync::infra::settings::Settings;
+use fin_sync::services::config::RuntimeConfigHandle;
+use fin_sync::services::worker::{WorkerIdentity, run_worker};
+use std::{sync::Arc, time::Duration};
+
+async fn source_and_actor(pool: &sqlx::PgPool, external_id: &str) -> Option<(String, String)> {
+    sqlx::query_as(
+        "SELECT p.source, a.actor FROM payments p JOIN audit_log a ON a.entity_id = p.id WHERE p.external_id = $1",
+    )
+    .bind(external_id)
+    .fetch_optional(pool)
+    .await
+    .unwrap()
+}
+
+// ── 56. worker_routes_jobs_by_source ───────────────────────────────────────
+
+#[tokio::test]
+async fn worker_routes_jobs_by_source() {
+    let pool = setup_pool("fin_sync_test_registry").await;
+    let mut providers = ProviderRegistry::default();
+    providers.register(Arc::new(StubProvider::default()));
+    providers.register(Arc::new(StubProvider {
+        source: "paypal",
+        ..Default::default()
+    }));
+
+    let jobs = [
+        ("stripe", "evt_reg_1", "pi_reg_1"),
+        ("paypal", "evt_pp_reg_2", "pp_cap_REG2"),
+        ("adyen", "evt_reg_3", "pi_reg_3"),
+    ];
+    for (source, event_id, object_id) in jobs {
+        let raw = serde_json::json!({"id": event_id});
+        job_repo::enqueue(
+            &pool,
+            source,
+            None,
+            event_id,
+            object_id,
+            "test.event",
+            1000,
+            &raw,
+            JobPriority::Normal,
+        )
+        .await
+        .unwrap();
+    }
+
+    let identity = WorkerIdentity {
+        hostname: "pod-a".into(),
+        instance_id: "0000abcd".into(),
+    };
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+    let worker = tokio::spawn(run_worker(
+        pool.clone(),
+        Settings::new(pool.clone()),
+        providers,
+        RuntimeConfigHandle::default(),
+        identity,
+        JobLane::Standard,
+        shutdown_rx,
+    ));
+    let mut routed = None;
+    for _ in 0..50 {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let stripe = source_and_actor(&pool, "pi_reg_1").await;
+        let paypal = source_and_actor(&pool, "pp_cap_REG2").await;
+        if let (Some(s), Some(p)) = (stripe, paypal) {
+            routed = Some((s, p));
+            break;
+        }
+    }
+    shutdown_tx.send(true).unwrap();
+    worker.await.unwrap();
+
+    let (stripe, paypal) = routed.expect("jobs were not processed");
+    assert_eq!(stripe, ("stripe".into(), "worker:stripe".into()));
+    assert_eq!(paypal, ("paypal".into(), "worker:paypal".into()));
+
+    // No provider for the source: the job is kept for retry, not dropped,
+    // and shows which worker last failed it.
+    let (status, attempts, error, claimed_by): (String, i32, Option<String>, Option<String>) =
+        sqlx::query_as(
+            "SELECT status, attempts, last_error, claimed_by FROM payment_jobs WHERE event_id = 'evt_reg_3'",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!((status.as_str(), attempts), ("pending", 1));
+    assert_eq!(claimed_by.as_deref(), Some("pod-a/0000abcd"));
+    assert!(error.unwrap().contains("adyen"));
+    assert_eq!(count_payments(&pool, "pi_reg_3").await, 0);
+}
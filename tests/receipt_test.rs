@@ -0,0 +1,77 @@
+mod common;
+
+use common::*;
+use fin_sync::domain::id::{EventId, ExternalId};
+use fin_sync::domain::money::{Currency, Money, MoneyAmount};
+use fin_sync::domain::payment::{
+    NewPayment, NewPaymentParams, PaymentDirection, PaymentReceipt, PaymentStatus,
+};
+use fin_sync::services::payment::lookup::get_payment_by_id;
+use fin_sync::services::payment::pipeline::process_payment_event;
+
+fn with_receipt(
+    event_id: &str,
+    status: PaymentStatus,
+    provider_ts: i64,
+    receipt: Option<PaymentReceipt>,
+) -> NewPayment {
+    NewPayment::new(NewPaymentParams {
+        external_id: ExternalId::new("pi_receipt").unwrap(),
+        source: "stripe".to_string(),
+        event_type: format!("payment_intent.{}", status.as_str()),
+        direction: PaymentDirection::Inbound,
+        money: Money::new(MoneyAmount::new(5000).unwrap(), Currency::USD),
+        status,
+        metadata: serde_json::json!({}),
+        raw_event: serde_json::json!({"id": event_id}),
+        last_event_id: EventId::new(event_id).unwrap(),
+        parent_external_id: None,
+        provider_ts,
+        failure: None,
+        authorized_amount: None,
+        receipt,
+        tenant_id: None,
+        customer: None,
+    })
+}
+
+// ── 81. receipt_details_fill_in_and_persist ────────────────────────────────
+
+#[tokio::test]
+async fn receipt_details_fill_in_and_persist() {
+    let pool = setup_pool("fin_sync_test_receipt").await;
+    let stored = || async {
+        get_payment_by_id(&pool, ExternalId::new("pi_receipt").unwrap())
+            .await
+            .unwrap()
+            .unwrap()
+            .receipt
+    };
+
+    // The intent arrives before its charge: only its own descriptor is known.
+    let descriptor = PaymentReceipt::from_parts(Some("ORDER 42".into()), None, None);
+    let p = with_receipt("evt_rcpt_1", PaymentStatus::Pending, 1000, descriptor);
+    process_payment_event(&pool, &p, "test").await.unwrap();
+    assert_eq!(
+        stored()
+            .await
+            .and_then(|r| r.statement_descriptor)
+            .as_deref(),
+        Some("ORDER 42")
+    );
+
+    // The charge reports what the bank shows, and the receipt.
+    let charged = PaymentReceipt::from_parts(
+        Some("ACME* ORDER 42".into()),
+        Some("j***@example.com".into()),
+        Some("https://pay.stripe.com/receipts/x".into()),
+    );
+    let p = with_receipt("evt_rcpt_2", PaymentStatus::Pending, 1001, charged.clone());
+    process_payment_event(&pool, &p, "test").await.unwrap();
+    assert_eq!(stored().await, charged);
+
+    // A later event without receipt details doesn't erase them.
+    let p = with_receipt("evt_rcpt_3", PaymentStatus::Succeeded, 1002, None);
+    process_payment_event(&pool, &p, "test").await.unwrap();
+    assert_eq!(stored().await, charged);
+}
@@ -0,0 +1,182 @@
+mod common;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use common::*;
+use fin_sync::domain::alert::{Alert, AlertSink};
+use fin_sync::domain::error::PortError;
+use fin_sync::domain::payment::PaymentStatus;
+use fin_sync::domain::reconciliation::DiscrepancyKind;
+use fin_sync::services::notify::Notifier;
+use fin_sync::services::payment::pipeline::process_payment_event;
+use fin_sync::services::reconciliation::{execute_and_notify, reconcile, start_run};
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
+
+fn window() -> (DateTime<Utc>, DateTime<Utc>) {
+    let until = Utc::now();
+    (until - TimeDelta::hours(24), until)
+}
+
+// ── 38. reconcile_records_each_discrepancy ─────────────────────────────────
+
+#[tokio::test]
+async fn reconcile_records_each_discrepancy() {
+    let pool = setup_pool("fin_sync_test_reconciliation").await;
+    for (id, evt, status) in [
+        ("pi_rec_match", "evt_rec_1", PaymentStatus::Succeeded),
+        ("pi_rec_status", "evt_rec_2", PaymentStatus::Pending),
+        ("pi_rec_amount", "evt_rec_3", PaymentStatus::Succeeded),
+    ] {
+        let p = make_payment(id, evt, status, 1000);
+        process_payment_event(&pool, &p, "test").await.unwrap();
+    }
+
+    let provider = StubProvider::listing(vec![
+        fetched_payment("pi_rec_match", PaymentStatus::Succeeded, 5000),
+        fetched_payment("pi_rec_status", PaymentStatus::Succeeded, 5000),
+        fetched_payment("pi_rec_amount", PaymentStatus::Succeeded, 4500),
+        fetched_payment("pi_rec_missing", PaymentStatus::Succeeded, 700),
+    ]);
+    let (since, until) = window();
+    let report = reconcile(&pool, &provider, since, until).await.unwrap();
+
+    assert_eq!(report.run.status, "completed");
+    assert_eq!(report.run.payments_checked, 4);
+    assert_eq!(report.run.discrepancies, 3);
+
+    let kind_of = |id: &str| {
+        report
+            .discrepancies
+            .iter()
+            .find(|d| d.external_id.as_deref() == Some(id))
+            .and_then(|d| d.kind)
+    };
+    assert_eq!(kind_of("pi_rec_match"), None);
+    assert_eq!(
+        kind_of("pi_rec_status"),
+        Some(DiscrepancyKind::StatusMismatch)
+    );
+    assert_eq!(
+        kind_of("pi_rec_amount"),
+        Some(DiscrepancyKind::AmountMismatch)
+    );
+    assert_eq!(
+        kind_of("pi_rec_missing"),
+        Some(DiscrepancyKind::MissingLocally)
+    );
+}
+
+// ── 39. reconcile_writes_audit_entries ─────────────────────────────────────
+
+#[tokio::test]
+async fn reconcile_writes_audit_entries() {
+    let pool = setup_pool("fin_sync_test_reconciliation").await;
+    let provider = StubProvider::listing(vec![fetched_payment(
+        "pi_rec_audit",
+        PaymentStatus::Pending,
+        100,
+    )]);
+    let (since, until) = window();
+    reconcile(&pool, &provider, since, until).await.unwrap();
+
+    let audits = get_audit_entries(&pool, "pi_rec_audit").await;
+    assert_eq!(audits.len(), 1);
+    assert_eq!(audits[0].action, "reconciliation_discrepancy");
+    assert_eq!(audits[0].detail["kind"], "missing_locally");
+}
+
+// ── 40. reconcile_provider_error_marks_run_failed ──────────────────────────
+
+#[tokio::test]
+async fn reconcile_provider_error_marks_run_failed() {
+    let pool = setup_pool("fin_sync_test_reconciliation").await;
+    let provider = StubProvider {
+        fail_from: Some(0),
+        ..Default::default()
+    };
+    let (since, until) = window();
+    assert!(reconcile(&pool, &provider, since, until).await.is_err());
+
+    let status: String = sqlx::query_scalar(
+        "SELECT status FROM reconciliation_runs WHERE error LIKE '%503%' ORDER BY started_at DESC LIMIT 1",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(status, "failed");
+}
+
+/// Keeps every alert it is sent.
+#[derive(Default)]
+struct RecordingSink {
+    alerts: Mutex<Vec<Alert>>,
+}
+
+impl AlertSink for RecordingSink {
+    fn name(&self) -> &'static str {
+        "recording"
+    }
+
+    fn send<'a>(
+        &'a self,
+        alert: &'a Alert,
+    ) -> Pin<Box<dyn Future<Output = Result<(), PortError>> + Send + 'a>> {
+        self.alerts.lock().unwrap().push(alert.clone());
+        Box::pin(async { Ok(()) })
+    }
+}
+
+// ── 59. finished_runs_deliver_a_summary ────────────────────────────────────
+
+#[tokio::test]
+async fn finished_runs_deliver_a_summary() {
+    let pool = setup_pool("fin_sync_test_reconciliation").await;
+    let p = make_payment("pi_sum_amount", "evt_sum_1", PaymentStatus::Succeeded, 1000);
+    process_payment_event(&pool, &p, "test").await.unwrap();
+
+    let sink = Arc::new(RecordingSink::default());
+    let notifier = Notifier::new("https://fin-sync.example.com/").with_sink(sink.clone());
+    let (since, until) = window();
+
+    let provider = StubProvider::listing(vec![
+        fetched_payment("pi_sum_amount", PaymentStatus::Succeeded, 5600),
+        fetched_payment("pi_sum_missing", PaymentStatus::Succeeded, 900),
+    ]);
+    let run_id = start_run(&pool, "stripe", since, until).await.unwrap();
+    execute_and_notify(&pool, &provider, &notifier, run_id, since, until)
+        .await
+        .unwrap();
+
+    let broken = StubProvider {
+        fail_from: Some(0),
+        ..Default::default()
+    };
+    let failed_id = start_run(&pool, "stripe", since, until).await.unwrap();
+    assert!(
+        execute_and_notify(&pool, &broken, &notifier, failed_id, since, until)
+            .await
+            .is_err()
+    );
+
+    let alerts = sink.alerts.lock().unwrap();
+    assert_eq!(alerts.len(), 2);
+    let summary = &alerts[0];
+    assert_eq!(summary.kind, "reconciliation_summary");
+    assert_eq!(
+        summary.subject,
+        "Reconciliation stripe: 0% matched, 2 unmatched"
+    );
+    assert!(summary.text.contains("missing_locally (1): pi_sum_missing"));
+    assert!(summary.text.contains("amount_mismatch (1): pi_sum_amount"));
+    assert_eq!(summary.detail["deltas"]["usd"], 1500);
+    assert_eq!(
+        summary.detail["report_url"],
+        format!("https://fin-sync.example.com/admin/reconciliations/{run_id}")
+    );
+
+    assert_eq!(alerts[1].subject, "Reconciliation stripe failed");
+    assert!(alerts[1].text.contains("503"));
+}
@@ -0,0 +1,192 @@
+mod common;
+
+use common::*;
+use fin_sync::domain::id::{EventId, ExternalId, TenantId};
+use fin_sync::domain::money::{Currency, Money, MoneyAmount};
+use fin_sync::domain::payment::{
+    NewPayment, NewPaymentParams, PaymentDirection, PaymentStatus, ProcessResult,
+};
+use fin_sync::services::payment::pipeline::process_payment_event;
+use fin_sync::testing::{make_partial_refund, make_payment, make_refund};
+use std::time::Duration;
+
+/// A refund of 5.00 EUR against `parent`.
+fn eur_refund(external_id: &str, event_id: &str, parent: &str) -> NewPayment {
+    NewPayment::new(NewPaymentParams {
+        external_id: ExternalId::new(external_id).unwrap(),
+        source: "stripe".to_string(),
+        event_type: "charge.refund.updated".to_string(),
+        direction: PaymentDirection::Outbound,
+        money: Money::new(MoneyAmount::new(500).unwrap(), Currency::EUR),
+        status: PaymentStatus::Refunded,
+        metadata: serde_json::json!({}),
+        raw_event: serde_json::json!({"id": event_id}),
+        last_event_id: EventId::new(event_id).unwrap(),
+        parent_external_id: Some(ExternalId::new(parent).unwrap()),
+        provider_ts: 1300,
+        failure: None,
+        authorized_amount: None,
+        receipt: None,
+        tenant_id: None,
+        customer: None,
+    })
+}
+
+/// The `refund_unlinked` anomaly among a refund's audit entries.
+fn unlinked(entries: &[AuditRow]) -> &AuditRow {
+    entries
+        .iter()
+        .find(|e| e.action == "refund_unlinked")
+        .expect("no refund_unlinked entry")
+}
+
+// ── 120. refunds_are_checked_against_their_parent ──────────────────────────
+
+#[tokio::test]
+async fn refunds_are_checked_against_their_parent() {
+    let pool = setup_pool("fin_sync_test_refund_link").await;
+    let apply = |payment: NewPayment| {
+        let pool = pool.clone();
+        async move {
+            process_payment_event(&pool, &payment, "test")
+                .await
+                .unwrap()
+        }
+    };
+    apply(make_payment(
+        "pi_rl_1",
+        "evt_rl_p1",
+        PaymentStatus::Succeeded,
+        1000,
+    ))
+    .await;
+    let parent = get_payment(&pool, "pi_rl_1").await.unwrap();
+
+    // A refund of a USD payment in USD links to it, on every entry.
+    apply(make_refund(
+        "re_rl_1",
+        "evt_rl_1",
+        PaymentStatus::Pending,
+        1100,
+        "pi_rl_1",
+    ))
+    .await;
+    apply(make_refund(
+        "re_rl_1",
+        "evt_rl_2",
+        PaymentStatus::Refunded,
+        1200,
+        "pi_rl_1",
+    ))
+    .await;
+    let entries = get_audit_entries(&pool, "re_rl_1").await;
+    let actions: Vec<_> = entries.iter().map(|e| e.action.as_str()).collect();
+    assert_eq!(actions, ["created", "status_changed"]);
+    for entry in &entries {
+        assert_eq!(entry.detail["parent_id"], parent.id.to_string());
+    }
+
+    // One in another currency is recorded, and flagged.
+    let result = apply(eur_refund("re_rl_2", "evt_rl_3", "pi_rl_1")).await;
+    assert!(matches!(result, ProcessResult::Created(_)));
+    let refund = get_payment(&pool, "re_rl_2").await.unwrap();
+    let entries = get_audit_entries(&pool, "re_rl_2").await;
+    let anomaly = unlinked(&entries);
+    assert_eq!(anomaly.entity_id, Some(refund.id));
+    assert_eq!(anomaly.event_id.as_deref(), Some("refund_link:evt_rl_3"));
+    assert_eq!(
+        anomaly.detail,
+        serde_json::json!({
+            "reason": "currency_mismatch",
+            "parent_external_id": "pi_rl_1",
+            "parent_id": parent.id.to_string(),
+            "currency": "eur",
+            "parent_currency": "usd",
+            "anomaly": true,
+        })
+    );
+
+    // A refund whose parent isn't here: flagged, and the parent fetched.
+    apply(make_refund(
+        "re_rl_3",
+        "evt_rl_4",
+        PaymentStatus::Refunded,
+        1400,
+        "pi_rl_gone",
+    ))
+    .await;
+    let entries = get_audit_entries(&pool, "re_rl_3").await;
+    assert_eq!(unlinked(&entries).detail["reason"], "orphan");
+    let fetches: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM followup_jobs WHERE payload->>'external_id' = 'pi_rl_gone'",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(fetches, 1);
+
+    // A refund of a refund has nothing to refund.
+    apply(make_refund(
+        "re_rl_4",
+        "evt_rl_5",
+        PaymentStatus::Refunded,
+        1500,
+        "re_rl_1",
+    ))
+    .await;
+    let entries = get_audit_entries(&pool, "re_rl_4").await;
+    assert_eq!(unlinked(&entries).detail["reason"], "parent_not_refundable");
+}
+
+// ── 134. a_refund_takes_its_parents_own_lock ───────────────────────────────
+
+#[tokio::test]
+async fn a_refund_takes_its_parents_own_lock() {
+    let pool = setup_pool("fin_sync_test_refund_link").await;
+    // A connected account's payment, refunded through the platform's feed.
+    let parent = NewPayment::new(NewPaymentParams {
+        external_id: ExternalId::new("pi_rl_acct").unwrap(),
+        source: "stripe".to_string(),
+        event_type: "payment_intent.succeeded".to_string(),
+        direction: PaymentDirection::Inbound,
+        money: Money::new(MoneyAmount::new(5000).unwrap(), Currency::USD),
+        status: PaymentStatus::Succeeded,
+        metadata: serde_json::json!({}),
+        raw_event: serde_json::json!({"id": "evt_rl_acct_1", "account": "acct_rl"}),
+        last_event_id: EventId::new("evt_rl_acct_1").unwrap(),
+        parent_external_id: None,
+        provider_ts: 1000,
+        failure: None,
+        authorized_amount: None,
+        receipt: None,
+        tenant_id: Some(TenantId::new("acct_rl").unwrap()),
+        customer: None,
+    });
+    process_payment_event(&pool, &parent, "test").await.unwrap();
+
+    // The account's own event for the parent is mid-flight.
+    let mut held = pool.begin().await.unwrap();
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtextextended('acct_rl:pi_rl_acct', 0))")
+        .execute(&mut *held)
+        .await
+        .unwrap();
+
+    let refund = make_partial_refund(
+        "re_rl_acct",
+        "evt_rl_acct_2",
+        PaymentStatus::Refunded,
+        1100,
+        "pi_rl_acct",
+        2000,
+    );
+    let pending = tokio::spawn({
+        let pool = pool.clone();
+        async move { process_payment_event(&pool, &refund, "test").await }
+    });
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert!(!pending.is_finished(), "refund didn't wait on its parent");
+
+    held.commit().await.unwrap();
+    let result = pending.await.unwrap().unwrap();
+    assert!(matches!(result, ProcessResult::Created(_)));
+}
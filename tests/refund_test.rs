@@ -0,0 +1,218 @@
+mod common;
+
+use common::*;
+use fin_sync::domain::id::ExternalId;
+use fin_sync::domain::manual::{IdempotencyKey, ManualOutcome, ManualPaymentRequest};
+use fin_sync::domain::money::{Currency, MoneyAmount};
+use fin_sync::domain::payment::{PaymentDirection, PaymentStatus};
+use fin_sync::error::PipelineError;
+use fin_sync::services::payment::lookup::get_payment_detail;
+use fin_sync::services::payment::manual::submit_manual_payment;
+use fin_sync::services::payment::pipeline::process_payment_event;
+use fin_sync::services::payment::refund::{check_refund_amount, get_refundable_balance};
+
+// ── 30. refundable_balance_accounts_for_partial_refunds ─────────────────────
+
+#[tokio::test]
+async fn refundable_balance_accounts_for_partial_refunds() {
+    let pool = setup_pool("fin_sync_test_refund").await;
+    let pi = make_payment("pi_rb_1", "evt_rb_1", PaymentStatus::Succeeded, 1000);
+    process_payment_event(&pool, &pi, "test").await.unwrap();
+
+    let settled = make_partial_refund(
+        "re_rb_1",
+        "evt_rb_2",
+        PaymentStatus::Refunded,
+        1100,
+        "pi_rb_1",
+        1000,
+    );
+    let in_flight = make_partial_refund(
+        "re_rb_2",
+        "evt_rb_3",
+        PaymentStatus::Pending,
+        1200,
+        "pi_rb_1",
+        1500,
+    );
+    let failed = make_partial_refund(
+        "re_rb_3",
+        "evt_rb_4",
+        PaymentStatus::Failed,
+        1300,
+        "pi_rb_1",
+        700,
+    );
+    for p in [&settled, &in_flight, &failed] {
+        process_payment_event(&pool, p, "test").await.unwrap();
+    }
+
+    let balance = get_refundable_balance(&pool, ExternalId::new("pi_rb_1").unwrap())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(balance.amount, 5000);
+    assert_eq!(balance.refunded, 1000);
+    assert_eq!(balance.pending_refunds, 1500);
+    assert_eq!(balance.refundable, 2500);
+}
+
+// ── 31. refundable_balance_unknown_payment_is_none ──────────────────────────
+
+#[tokio::test]
+async fn refundable_balance_unknown_payment_is_none() {
+    let pool = setup_pool("fin_sync_test_refund").await;
+    let balance = get_refundable_balance(&pool, ExternalId::new("pi_rb_missing").unwrap())
+        .await
+        .unwrap();
+    assert!(balance.is_none());
+}
+
+// ── 32. refundable_balance_rejects_refund_rows ──────────────────────────────
+
+#[tokio::test]
+async fn refundable_balance_rejects_refund_rows() {
+    let pool = setup_pool("fin_sync_test_refund").await;
+    let r = make_refund(
+        "re_rb_only",
+        "evt_rb_only",
+        PaymentStatus::Pending,
+        1000,
+        "pi_x",
+    );
+    process_payment_event(&pool, &r, "test").await.unwrap();
+
+    let result = get_refundable_balance(&pool, ExternalId::new("re_rb_only").unwrap()).await;
+    assert!(result.is_err());
+}
+
+// ── 33. check_refund_amount_blocks_over_refund ──────────────────────────────
+
+#[tokio::test]
+async fn check_refund_amount_blocks_over_refund() {
+    let pool = setup_pool("fin_sync_test_refund").await;
+    let pi = make_payment("pi_rb_guard", "evt_rb_g1", PaymentStatus::Succeeded, 1000);
+    process_payment_event(&pool, &pi, "test").await.unwrap();
+    let r = make_partial_refund(
+        "re_rb_guard",
+        "evt_rb_g2",
+        PaymentStatus::Refunded,
+        1100,
+        "pi_rb_guard",
+        4000,
+    );
+    process_payment_event(&pool, &r, "test").await.unwrap();
+
+    let id = ExternalId::new("pi_rb_guard").unwrap();
+    let mut tx = pool.begin().await.unwrap();
+    let ok = check_refund_amount(&mut tx, id.clone(), MoneyAmount::new(1000).unwrap()).await;
+    assert_eq!(ok.unwrap().refundable, 1000);
+    tx.rollback().await.unwrap();
+
+    let mut tx = pool.begin().await.unwrap();
+    let over = check_refund_amount(&mut tx, id, MoneyAmount::new(1001).unwrap()).await;
+    assert!(over.is_err());
+    tx.rollback().await.unwrap();
+}
+
+// ── 58. over_refund_is_flagged_on_the_parent ────────────────────────────────
+
+#[tokio::test]
+async fn over_refund_is_flagged_on_the_parent() {
+    let pool = setup_pool("fin_sync_test_refund").await;
+    let pi = make_payment("pi_rb_over", "evt_rb_o1", PaymentStatus::Succeeded, 1000);
+    process_payment_event(&pool, &pi, "test").await.unwrap();
+
+    let refunds = [
+        ("re_rb_over_1", "evt_rb_o2", PaymentStatus::Refunded, 3000),
+        ("re_rb_over_2", "evt_rb_o3", PaymentStatus::Pending, 1500),
+        ("re_rb_over_3", "evt_rb_o4", PaymentStatus::Pending, 1000),
+    ];
+    for (i, (id, evt, status, cents)) in refunds.into_iter().enumerate() {
+        let r = make_partial_refund(id, evt, status, 1100 + i as i64, "pi_rb_over", cents);
+        process_payment_event(&pool, &r, "worker:stripe")
+            .await
+            .unwrap();
+    }
+
+    // Only the third refund pushed the total (5500) past the amount (5000).
+    let flagged: Vec<_> = get_audit_entries(&pool, "pi_rb_over")
+        .await
+        .into_iter()
+        .filter(|e| e.action == "over_refunded")
+        .collect();
+    assert_eq!(flagged.len(), 1);
+    assert_eq!(flagged[0].detail["refund_external_id"], "re_rb_over_3");
+    assert_eq!(flagged[0].detail["refunded"], 3000);
+    assert_eq!(flagged[0].detail["pending_refunds"], 2500);
+
+    let detail = get_payment_detail(&pool, ExternalId::new("pi_rb_over").unwrap())
+        .await
+        .unwrap()
+        .unwrap();
+    let refunds = detail
+        .refunds
+        .expect("inbound payments carry refund totals");
+    assert!(refunds.over_refunded);
+    assert_eq!(refunds.refundable, 0);
+
+    let refund = get_payment_detail(&pool, ExternalId::new("re_rb_over_1").unwrap())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(refund.refunds.is_none());
+}
+
+// ── 133. initiated_refunds_are_checked_one_at_a_time ────────────────────────
+
+#[tokio::test]
+async fn initiated_refunds_are_checked_one_at_a_time() {
+    let pool = setup_pool("fin_sync_test_refund").await;
+    let pi = make_payment("pi_rb_race", "evt_rb_r1", PaymentStatus::Succeeded, 1000);
+    process_payment_event(&pool, &pi, "test").await.unwrap();
+
+    // Two tools each refund 3000 of the 5000 at once; only one fits.
+    let initiate = |n: u32| {
+        let pool = pool.clone();
+        async move {
+            let request = ManualPaymentRequest {
+                external_id: ExternalId::new(format!("mp_rb_race_{n}")).unwrap(),
+                direction: PaymentDirection::Outbound,
+                amount: 3000,
+                currency: Currency::USD,
+                status: PaymentStatus::Pending,
+                parent_external_id: Some(ExternalId::new("pi_rb_race").unwrap()),
+                metadata: serde_json::json!({}),
+            };
+            let key = IdempotencyKey::new(format!("rb-race-{n}")).unwrap();
+            submit_manual_payment(&pool, &request, &key, "manual:support").await
+        }
+    };
+    let (first, second) = tokio::join!(initiate(1), initiate(2));
+    let outcomes = [first, second];
+    assert_eq!(
+        outcomes
+            .iter()
+            .filter(|o| matches!(o, Ok(ManualOutcome::Created(_))))
+            .count(),
+        1
+    );
+    assert_eq!(
+        outcomes
+            .iter()
+            .filter(|o| matches!(o, Err(PipelineError::Validation(_))))
+            .count(),
+        1
+    );
+
+    // The refused one left nothing behind, and the balance holds.
+    let refunds =
+        count_payments(&pool, "mp_rb_race_1").await + count_payments(&pool, "mp_rb_race_2").await;
+    assert_eq!(refunds, 1);
+    let balance = get_refundable_balance(&pool, ExternalId::new("pi_rb_race").unwrap())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!((balance.pending_refunds, balance.refundable), (3000, 2000));
+    assert!(!balance.over_refunded);
+}
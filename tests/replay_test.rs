@@ -0,0 +1,330 @@
+mod common;
+
+use common::*;
+use fin_sync::adapters::paypal::client::{PAYPAL_SOURCE, PaypalProvider, convert_event};
+use fin_sync::domain::id::EventId;
+use fin_sync::domain::payment::{PassthroughEvent, PaymentStatus, PaymentTrigger};
+use fin_sync::domain::provider::ProviderRegistry;
+use fin_sync::domain::replay::{ReplayRequest, SimulateRequest};
+use fin_sync::services::payment::pipeline::{handle_passthrough, process_fetched_payment};
+use fin_sync::services::replay::{replay, replay_stored_event, simulate_event};
+use std::sync::Arc;
+
+/// A `PAYMENT.CAPTURE.*` webhook as PayPal sends it.
+fn capture_event(id: &str, event_type: &str, status: &str) -> serde_json::Value {
+    serde_json::json!({
+        "id": id,
+        "event_type": event_type,
+        "create_time": "2026-03-01T10:00:00Z",
+        "resource": {
+            "id": "8MC585209K746392H",
+            "status": status,
+            "amount": {"currency_code": "USD", "value": "10.00"},
+        },
+    })
+}
+
+async fn receive(pool: &sqlx::PgPool, event: serde_json::Value, ts: i64) {
+    let event_type = event["event_type"].as_str().unwrap().to_string();
+    let fetched = convert_event(&event_type, &event["resource"])
+        .unwrap()
+        .unwrap();
+    let trigger = PaymentTrigger {
+        source: PAYPAL_SOURCE.into(),
+        tenant_id: None,
+        event_id: EventId::new(format!("evt_pp_{}", event["id"].as_str().unwrap())).unwrap(),
+        event_type,
+        external_id: fetched.external_id.clone(),
+        raw_event: event,
+        provider_ts: ts,
+    };
+    process_fetched_payment(pool, fetched, trigger, "webhook:paypal")
+        .await
+        .unwrap();
+}
+
+fn paypal_providers() -> ProviderRegistry {
+    let mut providers = ProviderRegistry::default();
+    providers.register(Arc::new(PaypalProvider::new(
+        "http://127.0.0.1:9",
+        "client",
+        "secret",
+        "webhook",
+    )));
+    providers
+}
+
+async fn schema_exists(pool: &sqlx::PgPool, schema: &str) -> bool {
+    sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM pg_namespace WHERE nspname = $1)")
+        .bind(schema)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+// ── 70. sandbox_replay_diffs_against_production ────────────────────────────
+
+#[tokio::test]
+async fn sandbox_replay_diffs_against_production() {
+    let pool = setup_pool("fin_sync_test_replay").await;
+    let cap_id = "pp_cap_8MC585209K746392H";
+    receive(
+        &pool,
+        capture_event("WH-R1", "PAYMENT.CAPTURE.PENDING", "PENDING"),
+        1000,
+    )
+    .await;
+    receive(
+        &pool,
+        capture_event("WH-R2", "PAYMENT.CAPTURE.COMPLETED", "COMPLETED"),
+        2000,
+    )
+    .await;
+    handle_passthrough(
+        &pool,
+        &PassthroughEvent {
+            external_id: None,
+            source: PAYPAL_SOURCE.into(),
+            tenant_id: None,
+            event_id: EventId::new("evt_pp_WH-R3").unwrap(),
+            event_type: "CHECKOUT.ORDER.APPROVED".into(),
+            provider_ts: 3000,
+            raw_payload: serde_json::json!({"id": "WH-R3"}),
+            actor: "webhook:paypal".into(),
+        },
+    )
+    .await
+    .unwrap();
+    // What an older pipeline bug might have left behind.
+    sqlx::query("UPDATE payments SET amount = 100 WHERE external_id = $1")
+        .bind(cap_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let providers = paypal_providers();
+
+    let report = replay(
+        &pool,
+        &providers,
+        ReplayRequest {
+            object_ids: vec![cap_id.into()],
+            event_ids: vec!["evt_pp_WH-R3".into()],
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(report.selected, 3);
+    assert_eq!((report.replayed.created, report.replayed.updated), (1, 1));
+    assert_eq!(report.skipped.len(), 1);
+    assert_eq!(report.skipped[0].event_id, "evt_pp_WH-R3");
+    assert_eq!(report.diffs.len(), 1);
+    assert_eq!(report.diffs[0].external_id, cap_id);
+    let fields = &report.diffs[0].fields;
+    assert_eq!(fields.len(), 1);
+    assert_eq!(fields[0].field, "amount");
+    assert_eq!(
+        (&fields[0].production, &fields[0].sandbox),
+        (&100.into(), &1000.into())
+    );
+    assert!(!report.kept);
+    assert!(!schema_exists(&pool, &report.schema).await);
+
+    // Production is only read.
+    let row = get_payment(&pool, cap_id).await.unwrap();
+    assert_eq!(row.amount, 100);
+    assert_eq!(count_audit_entries(&pool, cap_id).await, 2);
+
+    // A kept schema holds the replayed rows for inspection.
+    let kept = replay(
+        &pool,
+        &providers,
+        ReplayRequest {
+            event_ids: vec!["evt_pp_WH-R1".into()],
+            keep: true,
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert!(kept.kept);
+    let status: String = sqlx::query_scalar(&format!(
+        "SELECT status FROM {}.payments WHERE external_id = $1",
+        kept.schema
+    ))
+    .bind(cap_id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(status, "pending");
+    sqlx::query(&format!("DROP SCHEMA {} CASCADE", kept.schema))
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let unbounded = replay(&pool, &providers, ReplayRequest::default()).await;
+    assert!(unbounded.is_err());
+}
+
+// ── 75. stored_event_replays_into_production ───────────────────────────────
+
+#[tokio::test]
+async fn stored_event_replays_into_production() {
+    let pool = setup_pool("fin_sync_test_replay").await;
+    let cap_id = "pp_cap_2GG279541U471931P";
+    let mut event = capture_event("WH-P1", "PAYMENT.CAPTURE.COMPLETED", "COMPLETED");
+    event["resource"]["id"] = "2GG279541U471931P".into();
+    receive(&pool, event, 1000).await;
+    // As if a mapping bug had read the capture as still pending.
+    sqlx::query("UPDATE payments SET status = 'pending' WHERE external_id = $1")
+        .bind(cap_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let providers = paypal_providers();
+    let replayed = replay_stored_event(&pool, &providers, "evt_pp_WH-P1", "admin:ops")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(replayed.result, "updated");
+    assert_eq!(replayed.payment.unwrap().status, PaymentStatus::Succeeded);
+
+    // Marked, and beside the original entry rather than in its place.
+    let entries: Vec<(String, String, String, serde_json::Value)> = sqlx::query_as(
+        "SELECT event_id, action, actor, detail FROM audit_log \
+         WHERE external_id = $1 ORDER BY created_at, id",
+    )
+    .bind(cap_id)
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].0, "evt_pp_WH-P1");
+    let (event_id, action, actor, detail) = &entries[1];
+    assert!(event_id.starts_with("replay:"));
+    assert_eq!(
+        (action.as_str(), actor.as_str()),
+        ("status_changed", "admin:ops")
+    );
+    assert_eq!(detail["replayed"], true);
+    assert_eq!(detail["replayed_event_id"], "evt_pp_WH-P1");
+
+    // Replaying again changes nothing but is still on record.
+    let again = replay_stored_event(&pool, &providers, "evt_pp_WH-P1", "admin:ops")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(again.result, "unchanged");
+    let last_action: String = sqlx::query_scalar(
+        "SELECT action FROM audit_log WHERE external_id = $1 ORDER BY created_at DESC, id DESC LIMIT 1",
+    )
+    .bind(cap_id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(last_action, "event_replayed");
+    assert_eq!(count_audit_entries(&pool, cap_id).await, 3);
+
+    let missing = replay_stored_event(&pool, &providers, "evt_pp_nope", "admin:ops")
+        .await
+        .unwrap();
+    assert!(missing.is_none());
+}
+
+// ── 112. simulated_events_change_nothing ───────────────────────────────────
+
+#[tokio::test]
+async fn simulated_events_change_nothing() {
+    let pool = setup_pool("fin_sync_test_replay").await;
+    let cap_id = "pp_cap_5TY05013RG002845M";
+    let mut pending = capture_event("WH-S1", "PAYMENT.CAPTURE.PENDING", "PENDING");
+    pending["resource"]["id"] = "5TY05013RG002845M".into();
+    receive(&pool, pending, 1000).await;
+    let providers = paypal_providers();
+
+    let snapshot = async || -> (i64, i64, i64, String) {
+        sqlx::query_as(
+            "SELECT (SELECT COUNT(*) FROM provider_events), (SELECT COUNT(*) FROM audit_log), \
+                    (SELECT COUNT(*) FROM ledger_entries), \
+                    (SELECT status FROM payments WHERE external_id = $1)",
+        )
+        .bind(cap_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap()
+    };
+    let before = snapshot().await;
+
+    // Resending a stored event stops at dedup; a replay would change nothing.
+    let resent = simulate_event(
+        &pool,
+        &providers,
+        SimulateRequest {
+            event_id: "evt_pp_WH-S1".into(),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!((resent.result, resent.decision), ("duplicate", "unchanged"));
+    assert_eq!(resent.external_id, cap_id);
+    assert_eq!(resent.audit_action.as_deref(), Some("status_unchanged"));
+
+    // An event not received yet would settle the capture and post to the ledger.
+    let mut completed = capture_event("WH-S2", "PAYMENT.CAPTURE.COMPLETED", "COMPLETED");
+    completed["resource"]["id"] = "5TY05013RG002845M".into();
+    let settle = simulate_event(
+        &pool,
+        &providers,
+        SimulateRequest {
+            event_id: "evt_pp_WH-S2".into(),
+            payload: Some(completed),
+            source: Some("paypal".into()),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(settle.result, "updated");
+    assert_eq!(settle.current_status, Some(PaymentStatus::Pending));
+    assert_eq!(settle.incoming_status, PaymentStatus::Succeeded);
+    assert_eq!(settle.audit_action.as_deref(), Some("status_changed"));
+    assert!(settle.anomalies.is_empty());
+    let lines = serde_json::to_value(&settle.ledger).unwrap();
+    assert_eq!(
+        lines,
+        serde_json::json!([
+            {"account": "cash", "side": "debit", "amount": 1000},
+            {"account": "revenue", "side": "credit", "amount": 1000},
+        ])
+    );
+
+    // Nothing was written by either.
+    assert_eq!(snapshot().await, before);
+
+    let missing = simulate_event(
+        &pool,
+        &providers,
+        SimulateRequest {
+            event_id: "evt_pp_nope".into(),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert!(missing.is_none());
+    let sourceless = SimulateRequest {
+        event_id: "evt_pp_WH-S3".into(),
+        payload: Some(capture_event(
+            "WH-S3",
+            "PAYMENT.CAPTURE.COMPLETED",
+            "COMPLETED",
+        )),
+        ..Default::default()
+    };
+    assert!(simulate_event(&pool, &providers, sourceless).await.is_err());
+}
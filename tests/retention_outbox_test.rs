@@ -0,0 +1,117 @@
+mod common;
+
+use common::*;
+use fin_sync::domain::outbox::OutboxQuery;
+use fin_sync::domain::payment::PaymentStatus;
+use fin_sync::domain::retention::{RetainedTable, RetentionPolicy};
+use fin_sync::infra::config::{ExportConfig, RetentionConfig};
+use fin_sync::infra::export_store::ExportStore;
+use fin_sync::infra::postgres::{audit_relay_repo, hook_repo};
+use fin_sync::services::outbox::{commit_offset, poll_events};
+use fin_sync::services::payment::pipeline::process_payment_event;
+use fin_sync::services::retention;
+use sqlx::PgPool;
+use std::time::Duration;
+
+async fn count(pool: &PgPool, sql: &str) -> i64 {
+    sqlx::query_scalar(sql).fetch_one(pool).await.unwrap()
+}
+
+// ── 132. audit_entries_outlive_retention_until_every_outbox_has_them ───────
+
+#[tokio::test]
+async fn audit_entries_outlive_retention_until_every_outbox_has_them() {
+    let pool = setup_pool("fin_sync_test_retention_outbox").await;
+    audit_relay_repo::enable(&pool).await.unwrap();
+    hook_repo::subscribe(&pool, &["ops".to_string()])
+        .await
+        .unwrap();
+    commit_offset(&pool, "erp", 0).await.unwrap();
+
+    for (event, status, ts) in [
+        ("evt_rto_1", PaymentStatus::Pending, 1000),
+        ("evt_rto_2", PaymentStatus::Succeeded, 2000),
+    ] {
+        let payment = make_payment("pi_rto", event, status, ts);
+        process_payment_event(&pool, &payment, "test")
+            .await
+            .unwrap();
+    }
+    sqlx::query(
+        "UPDATE audit_log SET created_at = now() - interval '100 days' \
+         WHERE external_id = 'pi_rto'",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    let entries = count_audit_entries(&pool, "pi_rto").await;
+    assert!(entries >= 2);
+
+    let dir = std::env::temp_dir().join(format!("archives-{}", uuid::Uuid::new_v4()));
+    let store = ExportStore::new(&ExportConfig {
+        dir,
+        s3: None,
+        scheduled: Vec::new(),
+    });
+    let config = RetentionConfig {
+        policies: vec![RetentionPolicy {
+            table: RetainedTable::AuditLog,
+            max_age_days: 90,
+        }],
+        dry_run: false,
+        batch_size: 100,
+        max_batches: 10,
+    };
+    let prune = || retention::run(&pool, &store, &config, false, "scheduler");
+
+    // Unshipped, undelivered and unread: nothing is due.
+    let report = prune().await.unwrap();
+    assert_eq!(report.rows(), 0);
+    assert_eq!(count_audit_entries(&pool, "pi_rto").await, entries);
+
+    // Shipped to the audit DB, but a hook still waits on the transitions.
+    let mut tx = pool.begin().await.unwrap();
+    let ids: Vec<uuid::Uuid> = sqlx::query_scalar("SELECT audit_id FROM audit_outbox")
+        .fetch_all(&mut *tx)
+        .await
+        .unwrap();
+    audit_relay_repo::mark_shipped(&mut tx, &ids).await.unwrap();
+    tx.commit().await.unwrap();
+    assert_eq!(prune().await.unwrap().rows(), 0);
+    assert_eq!(count_audit_entries(&pool, "pi_rto").await, entries);
+
+    // Delivered too, but the feed consumer hasn't read them.
+    let mut tx = pool.begin().await.unwrap();
+    let seqs: Vec<i64> = sqlx::query_scalar("SELECT seq FROM hook_outbox")
+        .fetch_all(&mut *tx)
+        .await
+        .unwrap();
+    assert!(!seqs.is_empty());
+    hook_repo::complete(&mut tx, &seqs).await.unwrap();
+    tx.commit().await.unwrap();
+    assert_eq!(prune().await.unwrap().rows(), 0);
+    assert_eq!(count_audit_entries(&pool, "pi_rto").await, entries);
+
+    // Once read, they go, and their feed rows with them but for the head,
+    // which numbering carries on from.
+    let page = poll_events(
+        &pool,
+        OutboxQuery {
+            consumer: Some("erp".into()),
+            ..Default::default()
+        },
+        Duration::ZERO,
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    commit_offset(&pool, "erp", page.last_seq).await.unwrap();
+    let report = prune().await.unwrap();
+    assert_eq!(report.rows(), entries as u64);
+    assert_eq!(count_audit_entries(&pool, "pi_rto").await, 0);
+    let head = "SELECT COALESCE(max(seq), 0) FROM outbox_events";
+    assert_eq!(count(&pool, head).await, page.last_seq);
+    let orphaned = "SELECT COUNT(*) FROM outbox_events o \
+                    WHERE NOT EXISTS (SELECT 1 FROM audit_log a WHERE a.id = o.audit_id)";
+    assert_eq!(count(&pool, orphaned).await, 1);
+}
@@ -0,0 +1,235 @@
+mod common;
+
+use chrono::{TimeDelta, Utc};
+use common::*;
+use fin_sync::domain::payment::PaymentStatus;
+use fin_sync::domain::retention::{RetainedTable, RetentionPolicy};
+use fin_sync::domain::rollup::{BucketSize, RollupKind};
+use fin_sync::error::PipelineError;
+use fin_sync::infra::config::{ExportConfig, RetentionConfig};
+use fin_sync::infra::export_store::ExportStore;
+use fin_sync::services::payment::pipeline::process_payment_event;
+use fin_sync::services::retention;
+use fin_sync::services::rollup::recompute_window;
+use serde_json::Value;
+use sqlx::PgPool;
+
+async fn count(pool: &PgPool, sql: &str) -> i64 {
+    sqlx::query_scalar(sql).fetch_one(pool).await.unwrap()
+}
+
+/// The NDJSON rows of a compressed archive file.
+fn read_archive(location: &str) -> Vec<Value> {
+    let body = zstd::stream::decode_all(std::fs::File::open(location).unwrap()).unwrap();
+    String::from_utf8(body)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect()
+}
+
+// ── 116. aged_rows_are_archived_then_pruned ────────────────────────────────
+
+#[tokio::test]
+async fn aged_rows_are_archived_then_pruned() {
+    let pool = setup_pool("fin_sync_test_retention").await;
+    for (event, status, ts) in [
+        ("evt_ret_1", PaymentStatus::Pending, 1000),
+        ("evt_ret_2", PaymentStatus::Succeeded, 2000),
+    ] {
+        let payment = make_payment("pi_ret_old", event, status, ts);
+        process_payment_event(&pool, &payment, "test")
+            .await
+            .unwrap();
+    }
+    let recent = make_payment("pi_ret_new", "evt_ret_3", PaymentStatus::Pending, 3000);
+    process_payment_event(&pool, &recent, "test").await.unwrap();
+    // Two dispute events as old: one for a dispute not yet recorded, which
+    // is kept, and one stored before `disputes` was, which the dispute
+    // rollup records from it and which then goes like any other.
+    sqlx::query(
+        "INSERT INTO provider_events (source, event_id, object_id, event_type, provider_ts, payload)
+         VALUES ('stripe', 'evt_ret_dp', 'pi_ret_dispute', 'charge.dispute.created', 1000,
+                 '\\x00'::bytea || convert_to('{\"id\": \"evt_ret_dp\"}', 'UTF8'))",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    let opened = Utc::now() - TimeDelta::days(40);
+    let dispute = serde_json::json!({
+        "id": "evt_ret_dp_closed",
+        "type": "charge.dispute.closed",
+        "data": {"object": {
+            "id": "dp_ret_closed", "object": "dispute", "created": opened.timestamp(),
+            "currency": "usd", "amount": 1500, "status": "lost",
+            "balance_transactions": [{"fee": 1500}],
+        }},
+    });
+    sqlx::query(
+        "INSERT INTO provider_events
+             (source, event_id, object_id, event_type, provider_ts, payload, received_at)
+         VALUES ('stripe', 'evt_ret_dp_closed', 'dp_ret_closed', 'charge.dispute.closed', 1000,
+                 '\\x00'::bytea || convert_to($1::text, 'UTF8'), now() - interval '35 days')",
+    )
+    .bind(dispute.to_string())
+    .execute(&pool)
+    .await
+    .unwrap();
+    let now = Utc::now();
+    let recompute_disputes = || {
+        recompute_window(
+            &pool,
+            RollupKind::DisputeStats,
+            BucketSize::Day,
+            now - TimeDelta::days(45),
+            now,
+        )
+    };
+    recompute_disputes().await.unwrap();
+    // The old payment's events arrived 40 days ago, its audit trail 100.
+    sqlx::query(
+        "UPDATE provider_events SET received_at = now() - interval '40 days' \
+         WHERE object_id IN ('pi_ret_old', 'pi_ret_dispute')",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query(
+        "UPDATE audit_log SET created_at = now() - interval '100 days' \
+         WHERE external_id = 'pi_ret_old'",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    let old_audit = count_audit_entries(&pool, "pi_ret_old").await;
+    assert!(old_audit >= 2);
+
+    let dir = std::env::temp_dir().join(format!("archives-{}", uuid::Uuid::new_v4()));
+    let store = ExportStore::new(&ExportConfig {
+        dir: dir.clone(),
+        s3: None,
+        scheduled: Vec::new(),
+    });
+    let mut config = RetentionConfig {
+        policies: vec![
+            RetentionPolicy {
+                table: RetainedTable::ProviderEvents,
+                max_age_days: 30,
+            },
+            RetentionPolicy {
+                table: RetainedTable::AuditLog,
+                max_age_days: 90,
+            },
+        ],
+        dry_run: true,
+        batch_size: 1,
+        max_batches: 1,
+    };
+    let events = "SELECT COUNT(*) FROM provider_events \
+                  WHERE object_id LIKE 'pi_ret_%' OR object_id LIKE 'dp_ret_%'";
+
+    // A dry run counts what is due and touches nothing but the audit log.
+    let report = retention::run(&pool, &store, &config, true, "admin:test")
+        .await
+        .unwrap();
+    assert!(report.dry_run);
+    assert_eq!(report.tables[0].rows, 3);
+    assert_eq!(report.tables[1].rows, old_audit as u64);
+    assert!(report.tables.iter().all(|t| t.batches.is_empty()));
+    assert_eq!(count(&pool, events).await, 5);
+    assert_eq!(count_audit_entries(&pool, "pi_ret_old").await, old_audit);
+    let dry_runs = "SELECT COUNT(*) FROM audit_log WHERE action = 'retention_dry_run'";
+    assert_eq!(count(&pool, dry_runs).await, 1);
+    assert!(!dir.exists());
+
+    // One batch per table per run here: the oldest row goes first, and the
+    // rest waits for the next run.
+    config.dry_run = false;
+    let report = retention::run(&pool, &store, &config, false, "admin:test")
+        .await
+        .unwrap();
+    assert!(report.tables.iter().all(|t| t.rows == 1 && t.more));
+    let first = read_archive(&report.tables[0].batches[0].location);
+    assert_eq!(first.len(), 1);
+    assert_eq!(first[0]["event_id"], "evt_ret_1");
+    assert_eq!(first[0]["payload"]["id"], "evt_ret_1");
+    assert_eq!(count(&pool, events).await, 4);
+
+    config.max_batches = 10;
+    let report = retention::run(&pool, &store, &config, false, "scheduler")
+        .await
+        .unwrap();
+    assert_eq!(report.tables[0].rows, 2);
+    assert_eq!(report.tables[1].rows, old_audit as u64 - 1);
+    assert!(report.tables.iter().all(|t| !t.more));
+    let rest = read_archive(&report.tables[0].batches[0].location);
+    assert_eq!(rest[0]["event_id"], "evt_ret_2");
+    let audit_rows: Vec<Value> = report.tables[1]
+        .batches
+        .iter()
+        .flat_map(|b| read_archive(&b.location))
+        .collect();
+    assert!(audit_rows.iter().all(|r| r["external_id"] == "pi_ret_old"));
+
+    // Recent rows and the unrecorded dispute's event stay; each archived
+    // batch left an audit entry naming its file.
+    assert_eq!(count(&pool, events).await, 2);
+    assert_eq!(count_audit_entries(&pool, "pi_ret_old").await, 0);
+    assert!(count_audit_entries(&pool, "pi_ret_new").await > 0);
+    let archived: Vec<(String, Value)> = sqlx::query_as(
+        "SELECT actor, detail FROM audit_log WHERE action = 'retention_archived' \
+         ORDER BY created_at",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(archived.len() as i64, 3 + old_audit);
+    assert_eq!(archived[0].0, "admin:test");
+    assert_eq!(archived[0].1["table"], "provider_events");
+    assert!(
+        archived[0].1["location"]
+            .as_str()
+            .unwrap()
+            .ends_with(".ndjson.zst")
+    );
+    let files = std::fs::read_dir(&dir).unwrap().count();
+    assert_eq!(files, archived.len());
+
+    // Nothing left: a run finds nothing and records nothing.
+    let report = retention::run(&pool, &store, &config, false, "scheduler")
+        .await
+        .unwrap();
+    assert_eq!(report.rows(), 0);
+    let report = retention::run(&pool, &store, &config, true, "scheduler")
+        .await
+        .unwrap();
+    assert_eq!(report.rows(), 0);
+    assert_eq!(count(&pool, dry_runs).await, 1);
+
+    // Event counts aren't rebuilt for the pruned days, which would come out
+    // empty; later ones and rollups over other tables still are.
+    let pruned = recompute_window(
+        &pool,
+        RollupKind::EventTypeStats,
+        BucketSize::Day,
+        now - TimeDelta::days(45),
+        now,
+    )
+    .await;
+    assert!(matches!(pruned, Err(PipelineError::Validation(_))));
+    let recent = now - TimeDelta::days(2);
+    recompute_window(
+        &pool,
+        RollupKind::EventTypeStats,
+        BucketSize::Day,
+        recent,
+        now,
+    )
+    .await
+    .unwrap();
+    // The pruned dispute still counts, from its recorded state.
+    recompute_disputes().await.unwrap();
+    let lost = "SELECT COALESCE(SUM(lost_count), 0)::bigint FROM dispute_stats \
+                WHERE bucket_size = 'day' AND currency = 'usd'";
+    assert_eq!(count(&pool, lost).await, 1);
+}
@@ -0,0 +1,239 @@
+mod common;
+
+use chrono::{TimeDelta, Utc};
+use common::*;
+use fin_sync::domain::id::{EventId, ExternalId};
+use fin_sync::domain::money::{Currency, Money, MoneyAmount};
+use fin_sync::domain::payment::{
+    NewPayment, NewPaymentParams, PassthroughEvent, PaymentDirection, PaymentStatus,
+};
+use fin_sync::domain::rollup::{BucketSize, RollupKind, RollupSpec};
+use fin_sync::services::payment::pipeline::{handle_passthrough, process_payment_event};
+use fin_sync::services::report::get_dispute_report;
+use fin_sync::services::rollup::{recompute_window, run_incremental};
+use sqlx::PgPool;
+
+fn payment_from(source: &str, external_id: &str, event_id: &str, cents: i64) -> NewPayment {
+    NewPayment::new(NewPaymentParams {
+        external_id: ExternalId::new(external_id).unwrap(),
+        source: source.into(),
+        event_type: "payment_intent.succeeded".into(),
+        direction: PaymentDirection::Inbound,
+        money: Money::new(MoneyAmount::new(cents).unwrap(), Currency::USD),
+        status: PaymentStatus::Succeeded,
+        metadata: serde_json::json!({}),
+        raw_event: serde_json::json!({"id": event_id}),
+        last_event_id: EventId::new(event_id).unwrap(),
+        parent_external_id: None,
+        provider_ts: 1000,
+        failure: None,
+        authorized_amount: None,
+        receipt: None,
+        tenant_id: None,
+        customer: None,
+    })
+}
+
+async fn summary_for(pool: &PgPool, source: &str) -> Option<(i64, i64)> {
+    sqlx::query_as::<_, (i64, i64)>(
+        "SELECT payment_count, total_amount FROM daily_summaries WHERE source = $1 AND bucket_size = 'day'",
+    )
+    .bind(source)
+    .fetch_optional(pool)
+    .await
+    .expect("query failed")
+}
+
+/// `charge.dispute.*` event as Stripe sends it, with one balance
+/// transaction per fee movement.
+fn dispute_event(
+    event_id: &str,
+    dispute_id: &str,
+    status: &str,
+    fees: &[i64],
+    provider_ts: i64,
+) -> PassthroughEvent {
+    let created = Utc::now().timestamp();
+    let txns: Vec<_> = fees.iter().map(|f| serde_json::json!({"fee": f})).collect();
+    PassthroughEvent {
+        external_id: None,
+        source: "stripe".into(),
+        tenant_id: None,
+        event_id: EventId::new(event_id).unwrap(),
+        event_type: "charge.dispute.updated".into(),
+        provider_ts,
+        raw_payload: serde_json::json!({
+            "id": event_id,
+            "type": "charge.dispute.updated",
+            "data": {"object": {
+                "id": dispute_id,
+                "object": "dispute",
+                "amount": 2000,
+                "currency": "usd",
+                "created": created,
+                "status": status,
+                "balance_transactions": txns,
+                "payment_method_details": {"card": {"brand": "rollup_brand"}},
+            }}
+        }),
+        actor: "test".into(),
+    }
+}
+
+// ── 34. incremental_rollup_aggregates_payments ─────────────────────────────
+
+#[tokio::test]
+async fn incremental_rollup_aggregates_payments() {
+    let pool = setup_pool("fin_sync_test_rollup").await;
+    for (i, cents) in [1000, 2500].into_iter().enumerate() {
+        let p = payment_from(
+            "rollup_inc",
+            &format!("pi_rollup_inc_{i}"),
+            &format!("evt_rollup_inc_{i}"),
+            cents,
+        );
+        process_payment_event(&pool, &p, "test").await.unwrap();
+    }
+
+    let spec = RollupSpec {
+        kind: RollupKind::DailySummaries,
+        bucket: BucketSize::Day,
+        lookback: TimeDelta::days(1),
+        retention: TimeDelta::days(30),
+    };
+    let run = run_incremental(&pool, &spec, Utc::now()).await.unwrap();
+    assert!(run.rows_written >= 1);
+    assert_eq!(summary_for(&pool, "rollup_inc").await, Some((2, 3500)));
+
+    // Re-running is idempotent: buckets are replaced, not added to.
+    run_incremental(&pool, &spec, Utc::now()).await.unwrap();
+    assert_eq!(summary_for(&pool, "rollup_inc").await, Some((2, 3500)));
+}
+
+// ── 35. recompute_window_picks_up_data_fixes ───────────────────────────────
+
+#[tokio::test]
+async fn recompute_window_picks_up_data_fixes() {
+    let pool = setup_pool("fin_sync_test_rollup").await;
+    let p = payment_from("rollup_fix", "pi_rollup_fix", "evt_rollup_fix", 4000);
+    process_payment_event(&pool, &p, "test").await.unwrap();
+
+    let now = Utc::now();
+    let from = now - TimeDelta::hours(1);
+    recompute_window(
+        &pool,
+        RollupKind::DailySummaries,
+        BucketSize::Day,
+        from,
+        now,
+    )
+    .await
+    .unwrap();
+    assert_eq!(summary_for(&pool, "rollup_fix").await, Some((1, 4000)));
+
+    sqlx::query("UPDATE payments SET amount = 4200 WHERE external_id = 'pi_rollup_fix'")
+        .execute(&pool)
+        .await
+        .unwrap();
+    let run = recompute_window(
+        &pool,
+        RollupKind::DailySummaries,
+        BucketSize::Day,
+        from,
+        now,
+    )
+    .await
+    .unwrap();
+    assert_eq!(run.window.from, BucketSize::Day.truncate(from));
+    assert_eq!(summary_for(&pool, "rollup_fix").await, Some((1, 4200)));
+}
+
+// ── 36. recompute_window_rejects_empty_range ───────────────────────────────
+
+#[tokio::test]
+async fn recompute_window_rejects_empty_range() {
+    let pool = setup_pool("fin_sync_test_rollup").await;
+    let now = Utc::now();
+    let result = recompute_window(
+        &pool,
+        RollupKind::EventTypeStats,
+        BucketSize::Hour,
+        now,
+        now,
+    )
+    .await;
+    assert!(result.is_err());
+}
+
+// ── 37. retention_purges_old_buckets ───────────────────────────────────────
+
+#[tokio::test]
+async fn retention_purges_old_buckets() {
+    let pool = setup_pool("fin_sync_test_rollup").await;
+    sqlx::query(
+        "INSERT INTO event_type_stats (bucket_size, bucket_start, event_type, event_count)
+         VALUES ('hour', now() - interval '30 days', 'rollup.retention', 7)",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let spec = RollupSpec {
+        kind: RollupKind::EventTypeStats,
+        bucket: BucketSize::Hour,
+        lookback: TimeDelta::zero(),
+        retention: TimeDelta::days(14),
+    };
+    let run = run_incremental(&pool, &spec, Utc::now()).await.unwrap();
+    assert!(run.rows_purged >= 1);
+
+    let left: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM event_type_stats WHERE event_type = 'rollup.retention'",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(left, 0);
+}
+
+// ── 44. dispute_stats_use_latest_dispute_state ─────────────────────────────
+
+#[tokio::test]
+async fn dispute_stats_use_latest_dispute_state() {
+    let pool = setup_pool("fin_sync_test_rollup").await;
+    let events = [
+        dispute_event("evt_dp_won_1", "dp_won", "needs_response", &[1500], 1000),
+        dispute_event("evt_dp_won_2", "dp_won", "won", &[1500, -1500], 2000),
+        dispute_event("evt_dp_lost_1", "dp_lost", "lost", &[1500], 1000),
+        dispute_event("evt_dp_open_1", "dp_open", "under_review", &[1500], 1000),
+    ];
+    for e in &events {
+        handle_passthrough(&pool, e).await.unwrap();
+    }
+
+    let now = Utc::now();
+    recompute_window(
+        &pool,
+        RollupKind::DisputeStats,
+        BucketSize::Month,
+        now - TimeDelta::hours(1),
+        now,
+    )
+    .await
+    .unwrap();
+
+    let report = get_dispute_report(&pool, None, None, now).await.unwrap();
+    let line = report
+        .months
+        .iter()
+        .find(|l| l.card_brand == "rollup_brand")
+        .expect("rollup_brand bucket");
+    assert_eq!(line.month, BucketSize::Month.truncate(now));
+    assert_eq!(line.currency, "usd");
+    assert_eq!(line.dispute_count, 3);
+    assert_eq!(line.disputed_amount, 6000);
+    // The won dispute's fee was reversed, so only two fees remain.
+    assert_eq!(line.fee_amount, 3000);
+    assert_eq!((line.won_count, line.lost_count), (1, 1));
+    assert_eq!(line.win_rate, Some(0.5));
+}
@@ -0,0 +1,170 @@
+mod common;
+
+use common::*;
+use fin_sync::domain::budget::{ApiOperation, BudgetPeriod};
+use fin_sync::domain::event_gap::GapKind;
+use fin_sync::domain::export::{ExportFormat, ExportKind};
+use fin_sync::domain::job::{AttemptOutcome, JobLane, JobPriority, JobStatus};
+use fin_sync::domain::ledger::{LedgerAccount, Side};
+use fin_sync::domain::money::Currency;
+use fin_sync::domain::payment::{PaymentDirection, PaymentStatus};
+use fin_sync::domain::payout::{PayoutKind, PayoutStatus};
+use fin_sync::domain::retention::RetainedTable;
+use fin_sync::infra::auth::{Role, Scope};
+
+fn sorted<'a>(values: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut values: Vec<String> = values.into_iter().map(str::to_string).collect();
+    values.sort();
+    values
+}
+
+// ── 107. rust_enums_match_check_constraints ────────────────────────────────
+
+#[tokio::test]
+async fn rust_enums_match_check_constraints() {
+    let pool = setup_pool("fin_sync_test_schema_drift").await;
+
+    // Every enum stored as text lists exactly the values its column's check
+    // constraint allows: a variant without a migration, or a migration
+    // without a variant, fails here rather than at the first write.
+    let enums: [(&str, &str, Vec<String>); 18] = [
+        (
+            "payments",
+            "status",
+            sorted(PaymentStatus::ALL.iter().map(|s| s.as_str())),
+        ),
+        (
+            "payments",
+            "direction",
+            sorted(PaymentDirection::ALL.iter().map(|d| d.as_str())),
+        ),
+        (
+            "payment_jobs",
+            "status",
+            sorted(JobStatus::ALL.map(JobStatus::as_str)),
+        ),
+        (
+            "payment_jobs",
+            "lane",
+            sorted(JobLane::ALL.map(JobLane::as_str)),
+        ),
+        (
+            "followup_jobs",
+            "status",
+            sorted(JobStatus::ALL.map(JobStatus::as_str)),
+        ),
+        (
+            "job_attempts",
+            "outcome",
+            sorted(AttemptOutcome::ALL.map(AttemptOutcome::as_str)),
+        ),
+        (
+            "payouts",
+            "kind",
+            sorted(PayoutKind::ALL.map(PayoutKind::as_str)),
+        ),
+        (
+            "payouts",
+            "status",
+            sorted(PayoutStatus::ALL.map(PayoutStatus::as_str)),
+        ),
+        (
+            "event_gaps",
+            "kind",
+            sorted(GapKind::ALL.map(GapKind::as_str)),
+        ),
+        (
+            "ledger_lines",
+            "account",
+            sorted(LedgerAccount::ALL.map(LedgerAccount::as_str)),
+        ),
+        ("ledger_lines", "side", sorted(Side::ALL.map(Side::as_str))),
+        ("api_keys", "role", sorted(Role::ALL.map(Role::as_str))),
+        ("api_keys", "scopes", sorted(Scope::ALL.map(Scope::as_str))),
+        (
+            "exports",
+            "kind",
+            sorted(ExportKind::ALL.map(ExportKind::as_str)),
+        ),
+        (
+            "exports",
+            "format",
+            sorted(ExportFormat::ALL.map(ExportFormat::extension)),
+        ),
+        (
+            "provider_api_usage",
+            "operation",
+            sorted(ApiOperation::ALL.map(ApiOperation::as_str)),
+        ),
+        (
+            "provider_api_usage",
+            "period",
+            sorted(BudgetPeriod::ALL.map(BudgetPeriod::as_str)),
+        ),
+        (
+            "retention_horizons",
+            "table_name",
+            sorted(RetainedTable::ALL.map(RetainedTable::as_str)),
+        ),
+    ];
+    for (table, column, variants) in enums {
+        assert_eq!(
+            allowed_values(&pool, table, column).await,
+            variants,
+            "{table}.{column} drifted from its Rust enum"
+        );
+        let expected = if column == "scopes" { "ARRAY" } else { "text" };
+        assert_eq!(
+            column_type(&pool, table, column).await,
+            expected,
+            "{table}.{column}"
+        );
+    }
+
+    // Every currency the domain accepts is storable, in every currency column.
+    for (table, column) in [
+        ("payments", "currency"),
+        ("payouts", "currency"),
+        ("payment_captures", "currency"),
+        ("ledger_entries", "currency"),
+    ] {
+        assert_eq!(column_type(&pool, table, column).await, "text");
+        for currency in Currency::all() {
+            assert!(
+                check_accepts(&pool, table, column, currency.as_str()).await,
+                "{table}.{column} rejects {currency}"
+            );
+        }
+        assert!(!check_accepts(&pool, table, column, "USD").await);
+    }
+
+    // Priorities are a range rather than a list.
+    assert_eq!(
+        column_type(&pool, "payment_jobs", "priority").await,
+        "smallint"
+    );
+    for priority in JobPriority::ALL {
+        let value = priority.value().to_string();
+        assert!(check_accepts(&pool, "payment_jobs", "priority", &value).await);
+    }
+    let max = JobPriority::ALL
+        .map(JobPriority::value)
+        .into_iter()
+        .max()
+        .unwrap();
+    assert!(!check_accepts(&pool, "payment_jobs", "priority", &(max + 1).to_string()).await);
+
+    // Money is counted in minor units, as i64.
+    for (table, column) in [
+        ("payments", "amount"),
+        ("payouts", "amount"),
+        ("payment_captures", "amount"),
+        ("ledger_lines", "amount"),
+    ] {
+        assert_eq!(
+            column_type(&pool, table, column).await,
+            "bigint",
+            "{table}.{column}"
+        );
+    }
+}
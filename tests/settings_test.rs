@@ -0,0 +1,94 @@
+mod common;
+
+use common::*;
+use fin_sync::infra::settings::{SettingKey, Settings, run_settings_listener};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+struct Cutoff {
+    hour: u32,
+    timezone: String,
+}
+
+const CUTOFF: SettingKey<Cutoff> = SettingKey::new("test.cutoff");
+const CUTOFF_AS_NUMBER: SettingKey<i64> = SettingKey::new("test.cutoff");
+
+fn cutoff(hour: u32) -> Cutoff {
+    Cutoff {
+        hour,
+        timezone: "UTC".into(),
+    }
+}
+
+// ── 121. settings_are_typed_cached_and_shared ──────────────────────────────
+
+#[tokio::test]
+async fn settings_are_typed_cached_and_shared() {
+    let pool = setup_pool("fin_sync_test_settings").await;
+    let here = Settings::new(pool.clone());
+    let there = Settings::new(pool.clone());
+
+    assert_eq!(here.get(&CUTOFF).await.unwrap(), None);
+    assert_eq!(
+        here.get_or_default(&CUTOFF).await.unwrap(),
+        Cutoff::default()
+    );
+    assert_eq!(there.get(&CUTOFF).await.unwrap(), None);
+
+    // A write is read back at once where it was made, and audited.
+    assert_eq!(here.set(&CUTOFF, &cutoff(17), "ops").await.unwrap(), 1);
+    assert_eq!(here.get(&CUTOFF).await.unwrap(), Some(cutoff(17)));
+    let entries = get_audit_entries(&pool, "test.cutoff").await;
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].action, "setting_changed");
+    assert_eq!(entries[0].detail["version"], 1);
+    assert_eq!(entries[0].detail["previous"], serde_json::Value::Null);
+    assert_eq!(entries[0].detail["value"]["hour"], 17);
+
+    // Another replica goes by its cache until it hears of the change.
+    assert_eq!(there.get(&CUTOFF).await.unwrap(), None);
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+    let listener = tokio::spawn(run_settings_listener(there.clone(), shutdown_rx));
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(there.get(&CUTOFF).await.unwrap(), Some(cutoff(17)));
+
+    let mut changes = there.subscribe();
+    assert_eq!(here.set(&CUTOFF, &cutoff(18), "ops").await.unwrap(), 2);
+    let key = tokio::time::timeout(Duration::from_secs(5), changes.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(key, "test.cutoff");
+    assert_eq!(there.get(&CUTOFF).await.unwrap(), Some(cutoff(18)));
+
+    // A value of the wrong shape is an error, not a default.
+    assert!(here.get(&CUTOFF_AS_NUMBER).await.is_err());
+
+    // Deleting is audited too, and the key reads as unset everywhere.
+    assert!(here.delete(&CUTOFF, "ops").await.unwrap());
+    assert!(!here.delete(&CUTOFF, "ops").await.unwrap());
+    let key = tokio::time::timeout(Duration::from_secs(5), changes.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(key, "test.cutoff");
+    assert_eq!(here.get(&CUTOFF).await.unwrap(), None);
+    assert_eq!(there.get(&CUTOFF).await.unwrap(), None);
+    let actions: Vec<_> = get_audit_entries(&pool, "test.cutoff")
+        .await
+        .into_iter()
+        .map(|e| e.action)
+        .collect();
+    assert_eq!(
+        actions,
+        ["setting_changed", "setting_changed", "setting_deleted"]
+    );
+
+    // Keys the table wouldn't take are refused before it's asked.
+    let bad: SettingKey<bool> = SettingKey::new("Not A Key");
+    assert!(here.set(&bad, &true, "ops").await.is_err());
+
+    shutdown_tx.send(true).unwrap();
+    listener.await.unwrap();
+}
@@ -0,0 +1,122 @@
+mod common;
+
+use common::*;
+use fin_sync::domain::config::RuntimeConfig;
+use fin_sync::domain::id::ExternalId;
+use fin_sync::domain::payment::PaymentStatus;
+use fin_sync::domain::shadow::{ShadowMismatchKind, ShadowStage};
+use fin_sync::services::config::{RuntimeConfigHandle, update_config};
+use fin_sync::services::payment::lookup::get_payment_by_id;
+use fin_sync::services::payment::pipeline::process_payment_event;
+use fin_sync::services::shadow::{backfill, verify};
+
+async fn set_stage(pool: &sqlx::PgPool, handle: &RuntimeConfigHandle, stage: ShadowStage) {
+    let next = RuntimeConfig {
+        payments_v2: stage,
+        ..Default::default()
+    };
+    update_config(pool, handle, next, "admin:ops")
+        .await
+        .unwrap();
+}
+
+async fn count_v2(pool: &sqlx::PgPool) -> i64 {
+    sqlx::query_scalar("SELECT COUNT(*) FROM payments_v2")
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn payments_v2_is_mirrored_backfilled_verified_then_read() {
+    let pool = setup_pool("fin_sync_test_shadow").await;
+    let handle = RuntimeConfigHandle::default();
+
+    // Stage off: only `payments` is written, and backfill refuses.
+    let old = make_payment("pi_shadow_old", "evt_sh_1", PaymentStatus::Pending, 100);
+    process_payment_event(&pool, &old, "test").await.unwrap();
+    assert_eq!(count_v2(&pool).await, 0);
+    assert!(backfill(&pool).await.is_err());
+
+    // Reads can't move to the new table before it is written.
+    let skip = RuntimeConfig {
+        payments_v2: ShadowStage::ReadNew,
+        ..Default::default()
+    };
+    assert!(
+        update_config(&pool, &handle, skip, "admin:ops")
+            .await
+            .is_err()
+    );
+
+    // Dual write: new payments and updates land in both tables.
+    set_stage(&pool, &handle, ShadowStage::DualWrite).await;
+    let new = make_payment("pi_shadow_new", "evt_sh_2", PaymentStatus::Pending, 100);
+    process_payment_event(&pool, &new, "test").await.unwrap();
+    let new = make_payment("pi_shadow_new", "evt_sh_3", PaymentStatus::Succeeded, 200);
+    process_payment_event(&pool, &new, "test").await.unwrap();
+    let (status, ts): (String, i64) = sqlx::query_as(
+        "SELECT status, last_event_ts FROM payments_v2 WHERE external_id = 'pi_shadow_new'",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!((status.as_str(), ts), ("succeeded", 200));
+
+    // The payment from before dual write is missing until backfilled.
+    let report = verify(&pool).await.unwrap();
+    assert_eq!((report.checked, report.missing_in_new), (2, 1));
+    assert_eq!(report.mismatches[0].external_id, "pi_shadow_old");
+    assert_eq!(backfill(&pool).await.unwrap(), 1);
+    assert_eq!(backfill(&pool).await.unwrap(), 0);
+    assert!(verify(&pool).await.unwrap().is_clean());
+
+    // A diverging row is reported with the columns that differ.
+    sqlx::query(
+        "UPDATE payments_v2 SET amount_minor = 1, tenant_id = 'acct_shadow' \
+         WHERE external_id = 'pi_shadow_old'",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    let report = verify(&pool).await.unwrap();
+    assert_eq!(report.differing, 1);
+    assert_eq!(report.mismatches[0].kind, ShadowMismatchKind::Differs);
+    assert_eq!(report.mismatches[0].columns, ["amount", "tenant_id"]);
+
+    // Read new: lookups and the pipeline's state come from payments_v2.
+    set_stage(&pool, &handle, ShadowStage::ReadNew).await;
+    let id = ExternalId::new("pi_shadow_old").unwrap();
+    let view = get_payment_by_id(&pool, id.clone()).await.unwrap().unwrap();
+    assert_eq!(view.amount, 1);
+    assert_eq!(view.tenant_id.unwrap().as_str(), "acct_shadow");
+    sqlx::query("UPDATE payments_v2 SET tenant_id = NULL WHERE external_id = 'pi_shadow_old'")
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("UPDATE payments_v2 SET status = 'failed' WHERE external_id = 'pi_shadow_old'")
+        .execute(&pool)
+        .await
+        .unwrap();
+    let late = make_payment("pi_shadow_old", "evt_sh_4", PaymentStatus::Succeeded, 300);
+    process_payment_event(&pool, &late, "test").await.unwrap();
+    // failed -> succeeded is refused, so `payments` keeps its pending status.
+    assert_eq!(
+        get_payment(&pool, "pi_shadow_old").await.unwrap().status,
+        "pending"
+    );
+
+    // Back off, a step at a time: reads return to `payments` at once.
+    let skip = RuntimeConfig {
+        payments_v2: ShadowStage::Off,
+        ..Default::default()
+    };
+    assert!(
+        update_config(&pool, &handle, skip, "admin:ops")
+            .await
+            .is_err()
+    );
+    set_stage(&pool, &handle, ShadowStage::DualWrite).await;
+    let view = get_payment_by_id(&pool, id).await.unwrap().unwrap();
+    assert_eq!(view.amount, 5000);
+}
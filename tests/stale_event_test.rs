@@ -0,0 +1,187 @@
+mod common;
+
+use common::*;
+use fin_sync::domain::id::{EventId, ExternalId};
+use fin_sync::domain::money::{Currency, Money, MoneyAmount};
+use fin_sync::domain::payment::{
+    NewPayment, NewPaymentParams, PaymentDirection, PaymentFailure, PaymentStatus, ProcessResult,
+};
+use fin_sync::services::payment::pipeline::process_payment_event;
+use serde_json::Value;
+use sqlx::PgPool;
+
+/// A `payment_failed` event for `pi_se_1`, declined with `code`.
+fn failed(event_id: &str, provider_ts: i64, code: &str) -> NewPayment {
+    NewPayment::new(NewPaymentParams {
+        external_id: ExternalId::new("pi_se_1").unwrap(),
+        source: "stripe".to_string(),
+        event_type: "payment_intent.payment_failed".to_string(),
+        direction: PaymentDirection::Inbound,
+        money: Money::new(MoneyAmount::new(5000).unwrap(), Currency::USD),
+        status: PaymentStatus::Failed,
+        metadata: serde_json::json!({}),
+        raw_event: serde_json::json!({"id": event_id}),
+        last_event_id: EventId::new(event_id).unwrap(),
+        parent_external_id: None,
+        provider_ts,
+        failure: Some(PaymentFailure {
+            code: Some(code.into()),
+            ..Default::default()
+        }),
+        authorized_amount: None,
+        receipt: None,
+        tenant_id: None,
+        customer: None,
+    })
+}
+
+/// An event for `pi_se_2` in `status` at second 3000, made by API request
+/// `request` if given.
+fn burst(event_id: &str, status: PaymentStatus, request: Option<&str>) -> NewPayment {
+    NewPayment::new(NewPaymentParams {
+        external_id: ExternalId::new("pi_se_2").unwrap(),
+        source: "stripe".to_string(),
+        event_type: "payment_intent.updated".to_string(),
+        direction: PaymentDirection::Inbound,
+        money: Money::new(MoneyAmount::new(5000).unwrap(), Currency::USD),
+        status,
+        metadata: serde_json::json!({}),
+        raw_event: serde_json::json!({"id": event_id, "request": {"id": request}}),
+        last_event_id: EventId::new(event_id).unwrap(),
+        parent_external_id: None,
+        provider_ts: 3000,
+        failure: None,
+        authorized_amount: None,
+        receipt: None,
+        tenant_id: None,
+        customer: None,
+    })
+}
+
+/// The action and detail of the audit entry `event_id` wrote.
+async fn audit(pool: &PgPool, event_id: &str) -> (String, Value) {
+    sqlx::query_as("SELECT action, detail FROM audit_log WHERE event_id = $1")
+        .bind(event_id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+// ── 118. unchanged_and_stale_events_are_told_apart ─────────────────────────
+
+#[tokio::test]
+async fn unchanged_and_stale_events_are_told_apart() {
+    let pool = setup_pool("fin_sync_test_stale_event").await;
+    let created = process_payment_event(&pool, &failed("evt_se1", 1000, "card_declined"), "test")
+        .await
+        .unwrap();
+    assert!(matches!(created, ProcessResult::Created(_)));
+
+    // A later event repeating the status: unchanged, and it's the last event.
+    let later = failed("evt_se2", 2000, "insufficient_funds");
+    let result = process_payment_event(&pool, &later, "test").await.unwrap();
+    assert!(matches!(result, ProcessResult::Unchanged(_)));
+    let (action, detail) = audit(&pool, "evt_se2").await;
+    assert_eq!(action, "status_unchanged");
+    assert_eq!(detail["status"], "failed");
+    let payment = get_payment(&pool, "pi_se_1").await.unwrap();
+    assert_eq!(payment.last_event_id, "evt_se2");
+
+    // An earlier one arriving late: stale, ignored, and nothing moves.
+    let early = failed("evt_se3", 1500, "expired_card");
+    let result = process_payment_event(&pool, &early, "test").await.unwrap();
+    assert!(matches!(result, ProcessResult::StaleIgnored(_)));
+    let (action, detail) = audit(&pool, "evt_se3").await;
+    assert_eq!(action, "event_stale_ignored");
+    assert_eq!(
+        (&detail["provider_ts"], &detail["last_provider_ts"]),
+        (&1500.into(), &2000.into())
+    );
+    assert!(detail.get("superseded").is_none());
+    let (last_event_id, last_provider_ts, failure_code): (String, i64, Option<String>) =
+        sqlx::query_as(
+            "SELECT last_event_id, last_provider_ts, failure_code FROM payments \
+             WHERE external_id = 'pi_se_1'",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(
+        (
+            last_event_id.as_str(),
+            last_provider_ts,
+            failure_code.as_deref()
+        ),
+        ("evt_se2", 2000, Some("insufficient_funds"))
+    );
+
+    // Both are on record for the dashboards.
+    let actions: Vec<(String, i64)> = sqlx::query_as(
+        "SELECT action, COUNT(*) FROM audit_log WHERE external_id = 'pi_se_1' \
+         GROUP BY action ORDER BY action",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        actions,
+        [
+            ("created".into(), 1),
+            ("event_stale_ignored".into(), 1),
+            ("status_unchanged".into(), 1),
+        ]
+    );
+}
+
+// ── 130. same_second_events_are_ordered_by_event_id_and_request ───────────
+
+#[tokio::test]
+async fn same_second_events_are_ordered_by_event_id_and_request() {
+    let pool = setup_pool("fin_sync_test_stale_event").await;
+    let process = |payment: NewPayment| {
+        let pool = pool.clone();
+        async move {
+            process_payment_event(&pool, &payment, "test")
+                .await
+                .unwrap()
+        }
+    };
+    let created = process(burst("evt_ss_1", PaymentStatus::Pending, Some("req_1"))).await;
+    assert!(matches!(created, ProcessResult::Created(_)));
+    let advanced = process(burst("evt_ss_3", PaymentStatus::Succeeded, Some("req_1"))).await;
+    assert!(matches!(advanced, ProcessResult::Updated(_)));
+    let stored: (String, String, Option<String>) = sqlx::query_as(
+        "SELECT status, last_event_id, last_request_id FROM payments \
+         WHERE external_id = 'pi_se_2'",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        stored,
+        ("succeeded".into(), "evt_ss_3".into(), Some("req_1".into()))
+    );
+
+    // Raised in the same second but before the last event applied: stale,
+    // whether it would move the status back or repeat it.
+    let late = process(burst("evt_ss_2", PaymentStatus::Pending, None)).await;
+    assert!(matches!(late, ProcessResult::StaleIgnored(_)));
+    let (action, detail) = audit(&pool, "evt_ss_2").await;
+    assert_eq!(action, "event_stale_ignored");
+    assert_eq!(detail["last_event_id"], "evt_ss_3");
+    let repeat = process(burst("evt_ss_25", PaymentStatus::Succeeded, Some("req_1"))).await;
+    assert!(matches!(repeat, ProcessResult::StaleIgnored(_)));
+
+    // Another request racing the last one can't be ordered by id: the state
+    // machine decides, as before.
+    let raced = process(burst("evt_ss_0", PaymentStatus::Pending, Some("req_2"))).await;
+    assert!(matches!(raced, ProcessResult::Anomaly(_)));
+    // And one after the last is applied.
+    let next = process(burst("evt_ss_4", PaymentStatus::Succeeded, None)).await;
+    assert!(matches!(next, ProcessResult::Unchanged(_)));
+    let payment = get_payment(&pool, "pi_se_2").await.unwrap();
+    assert_eq!(
+        (payment.status.as_str(), payment.last_event_id.as_str()),
+        ("succeeded", "evt_ss_4")
+    );
+}
@@ -0,0 +1,206 @@
+mod common;
+
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use common::*;
+use fin_sync::adapters::stripe::client::StripeProvider;
+use fin_sync::domain::config::{RuntimeConfig, VersionedConfig};
+use fin_sync::domain::error::PortError;
+use fin_sync::domain::id::ExternalId;
+use fin_sync::domain::job::{JobLane, JobPriority};
+use fin_sync::domain::provider::{PaymentProvider, ProviderRegistry};
+use fin_sync::infra::postgres::job_repo;
+use fin_sync::infra::settings::Settings;
+use fin_sync::services::config::RuntimeConfigHandle;
+use fin_sync::services::worker::{WorkerIdentity, run_worker};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Requests seen per path.
+type Calls = Arc<Mutex<HashMap<String, usize>>>;
+
+fn stripe_error(status: StatusCode, headers: &[(&'static str, &'static str)]) -> Response {
+    let mut response = (
+        status,
+        Json(serde_json::json!({"error": {"type": "api_error", "message": "stand-in"}})),
+    )
+        .into_response();
+    for (name, value) in headers {
+        response.headers_mut().insert(*name, value.parse().unwrap());
+    }
+    response
+}
+
+fn payment_intent(id: &str) -> serde_json::Value {
+    serde_json::json!({
+        "id": id,
+        "object": "payment_intent",
+        "amount": 1500,
+        "amount_capturable": 0,
+        "amount_received": 1500,
+        "capture_method": "automatic",
+        "confirmation_method": "automatic",
+        "created": 1_700_000_000,
+        "currency": "usd",
+        "livemode": false,
+        "metadata": {},
+        "payment_method_types": ["card"],
+        "status": "succeeded",
+        "latest_charge": null,
+    })
+}
+
+/// The Stripe API, for the objects below:
+/// - `pi_flaky`: rate limited (retry in 1s), then a 500, then found;
+/// - `pi_slow`: rate limited for longer than is worth waiting;
+/// - `pi_fatal`: a 500 Stripe says not to retry;
+/// - `re_gone`: 404; `dp_bad`: 400.
+async fn stripe_api(calls: Calls) -> String {
+    async fn object(
+        State(calls): State<Calls>,
+        Path((kind, id)): Path<(String, String)>,
+        headers: HeaderMap,
+    ) -> Response {
+        let seen = {
+            let mut calls = calls.lock().unwrap();
+            let n = calls.entry(id.clone()).or_default();
+            *n += 1;
+            *n
+        };
+        if headers["authorization"] != "Bearer sk_test_api"
+            || !headers.contains_key("stripe-version")
+        {
+            return stripe_error(StatusCode::UNAUTHORIZED, &[]);
+        }
+        match (kind.as_str(), id.as_str(), seen) {
+            ("payment_intents", "pi_flaky", 1) => {
+                stripe_error(StatusCode::TOO_MANY_REQUESTS, &[("retry-after", "1")])
+            }
+            ("payment_intents", "pi_flaky", 2) => {
+                stripe_error(StatusCode::INTERNAL_SERVER_ERROR, &[])
+            }
+            ("payment_intents", "pi_flaky", _) => Json(payment_intent(&id)).into_response(),
+            ("payment_intents", "pi_slow", _) => {
+                stripe_error(StatusCode::TOO_MANY_REQUESTS, &[("retry-after", "120")])
+            }
+            ("payment_intents", "pi_fatal", _) => stripe_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                &[("stripe-should-retry", "false")],
+            ),
+            ("refunds", "re_gone", _) => stripe_error(StatusCode::NOT_FOUND, &[]),
+            _ => stripe_error(StatusCode::BAD_REQUEST, &[]),
+        }
+    }
+
+    let app = Router::new()
+        .route("/v1/{kind}/{id}", get(object))
+        .with_state(calls);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    url
+}
+
+// ── 99. stripe_requests_retry_transient_errors_only ────────────────────────
+
+#[tokio::test]
+async fn stripe_requests_retry_transient_errors_only() {
+    let pool = setup_pool("fin_sync_test_stripe_api").await;
+    let calls = Calls::default();
+    let base = stripe_api(calls.clone()).await;
+    let stripe = Arc::new(StripeProvider::new("sk_test_api").with_api_base(&base));
+    let fetch = |id: &'static str| {
+        let stripe = stripe.clone();
+        async move { stripe.fetch_payment(&ExternalId::new(id).unwrap()).await }
+    };
+    let calls_to = |id: &str| calls.lock().unwrap().get(id).copied().unwrap_or(0);
+
+    // Waits out the rate limit as told, backs off after the 500.
+    let started = Instant::now();
+    let payment = fetch("pi_flaky").await.unwrap();
+    assert_eq!(payment.money.amount().cents(), 1500);
+    assert_eq!(calls_to("pi_flaky"), 3);
+    assert!(started.elapsed() >= Duration::from_millis(1400));
+
+    // Errors that asking again won't fix are returned at once.
+    assert!(matches!(
+        fetch("pi_slow").await,
+        Err(PortError::Unavailable(_))
+    ));
+    assert!(matches!(
+        fetch("pi_fatal").await,
+        Err(PortError::Unavailable(_))
+    ));
+    assert!(matches!(fetch("re_gone").await, Err(PortError::Missing(_))));
+    assert!(matches!(fetch("dp_bad").await, Err(PortError::Rejected(_))));
+    for id in ["pi_slow", "pi_fatal", "re_gone", "dp_bad"] {
+        assert_eq!(calls_to(id), 1, "{id}");
+    }
+
+    // The worker dead-letters a rejected job instead of retrying it.
+    let raw = serde_json::json!({"id": "evt_rejected"});
+    job_repo::enqueue(
+        &pool,
+        "stripe",
+        None,
+        "evt_rejected",
+        "dp_bad",
+        "charge.dispute.created",
+        1000,
+        &raw,
+        JobPriority::Normal,
+    )
+    .await
+    .unwrap();
+    let mut providers = ProviderRegistry::default();
+    providers.register(stripe.clone());
+    let config = RuntimeConfigHandle::new(VersionedConfig {
+        version: 0,
+        config: RuntimeConfig::default(),
+    });
+    let identity = WorkerIdentity {
+        hostname: "pod-s".into(),
+        instance_id: "0000d00d".into(),
+    };
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+    let worker = tokio::spawn(run_worker(
+        pool.clone(),
+        Settings::new(pool.clone()),
+        providers,
+        config,
+        identity,
+        JobLane::Standard,
+        shutdown_rx,
+    ));
+    let mut job = None;
+    for _ in 0..30 {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        job = sqlx::query_as::<_, (String, i32, Option<String>)>(
+            "SELECT status, attempts, last_error FROM payment_jobs WHERE event_id = 'evt_rejected'",
+        )
+        .fetch_optional(&pool)
+        .await
+        .unwrap()
+        .filter(|(status, _, _)| status != "pending" && status != "processing");
+        if job.is_some() {
+            break;
+        }
+    }
+    shutdown_tx.send(true).unwrap();
+    worker.await.unwrap();
+    let (status, attempts, last_error) = job.expect("job was not finished");
+    assert_eq!((status.as_str(), attempts), ("failed", 1));
+    assert!(
+        last_error
+            .unwrap()
+            .starts_with("rejected by provider: Stripe API: 400")
+    );
+}
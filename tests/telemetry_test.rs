@@ -0,0 +1,135 @@
+mod common;
+
+use common::*;
+use fin_sync::domain::job::{JobLane, JobPriority};
+use fin_sync::domain::provider::ProviderRegistry;
+use fin_sync::infra::postgres::job_repo;
+use fin_sync::infra::settings::Settings;
+use fin_sync::services::config::RuntimeConfigHandle;
+use fin_sync::services::worker::{WorkerIdentity, run_worker};
+use opentelemetry::trace::{TraceId, TracerProvider as _};
+use opentelemetry_sdk::trace::{InMemorySpanExporter, SdkTracerProvider, SpanData};
+use sqlx::PgPool;
+use std::{sync::Arc, time::Duration};
+use tracing::Instrument;
+use tracing_subscriber::layer::SubscriberExt;
+
+async fn enqueue(pool: &PgPool, event_id: &str, object_id: &str) {
+    job_repo::enqueue(
+        pool,
+        "stripe",
+        None,
+        event_id,
+        object_id,
+        "payment_intent.succeeded",
+        1000,
+        &serde_json::json!({"id": event_id}),
+        JobPriority::Normal,
+    )
+    .await
+    .unwrap()
+    .unwrap();
+}
+
+async fn traceparent(pool: &PgPool, event_id: &str) -> Option<String> {
+    sqlx::query_scalar("SELECT traceparent FROM payment_jobs WHERE event_id = $1")
+        .bind(event_id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+/// The finished span named `name` whose `attribute` is `value`.
+fn find_span<'a>(spans: &'a [SpanData], name: &str, attribute: &str, value: &str) -> &'a SpanData {
+    spans
+        .iter()
+        .find(|s| {
+            s.name == name
+                && s.attributes
+                    .iter()
+                    .any(|kv| kv.key.as_str() == attribute && kv.value.as_str() == value)
+        })
+        .unwrap_or_else(|| panic!("no {name} span with {attribute}={value}"))
+}
+
+fn trace_of(span: &SpanData) -> TraceId {
+    span.span_context.trace_id()
+}
+
+// ── 108. queued_jobs_continue_the_webhook_trace ────────────────────────────
+
+#[tokio::test]
+async fn queued_jobs_continue_the_webhook_trace() {
+    let exporter = InMemorySpanExporter::default();
+    let tracer_provider = SdkTracerProvider::builder()
+        .with_simple_exporter(exporter.clone())
+        .build();
+    let layer = tracing_opentelemetry::layer().with_tracer(tracer_provider.tracer("test"));
+    tracing::subscriber::set_global_default(tracing_subscriber::registry().with(layer)).unwrap();
+    let pool = setup_pool("fin_sync_test_telemetry").await;
+
+    // Queued outside any request, the job's trace starts at the enqueue.
+    enqueue(&pool, "evt_trace_0", "pi_trace_0").await;
+    assert!(traceparent(&pool, "evt_trace_0").await.is_some());
+
+    // Queued while handling a webhook, the job records the request's trace.
+    enqueue(&pool, "evt_trace_1", "pi_trace_1")
+        .instrument(tracing::info_span!("webhook", event_id = "evt_trace_1"))
+        .await;
+    let stored = traceparent(&pool, "evt_trace_1").await.unwrap();
+    let spans = exporter.get_finished_spans().unwrap();
+    let webhook = find_span(&spans, "webhook", "event_id", "evt_trace_1");
+    assert!(stored.starts_with(&format!("00-{}-", trace_of(webhook))));
+    let queued = find_span(&spans, "enqueue", "external_id", "pi_trace_1");
+    assert_eq!(trace_of(queued), trace_of(webhook));
+
+    let mut providers = ProviderRegistry::default();
+    providers.register(Arc::new(StubProvider::default()));
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+    let worker = tokio::spawn(run_worker(
+        pool.clone(),
+        Settings::new(pool.clone()),
+        providers,
+        RuntimeConfigHandle::default(),
+        WorkerIdentity {
+            hostname: "pod-t".into(),
+            instance_id: "00007ace".into(),
+        },
+        JobLane::Standard,
+        shutdown_rx,
+    ));
+    for _ in 0..50 {
+        if get_payment(&pool, "pi_trace_0").await.is_some()
+            && get_payment(&pool, "pi_trace_1").await.is_some()
+        {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    shutdown_tx.send(true).unwrap();
+    worker.await.unwrap();
+
+    // The worker's processing is part of the webhook's trace, below the
+    // span that queued it, with the ids on every span.
+    let spans = exporter.get_finished_spans().unwrap();
+    let job = find_span(&spans, "job", "event_id", "evt_trace_1");
+    assert_eq!(trace_of(job), trace_of(webhook));
+    assert_eq!(job.parent_span_id, queued.span_context.span_id());
+    for (name, attribute, value) in [
+        ("pipeline", "event_id", "evt_trace_1"),
+        ("pipeline", "external_id", "pi_trace_1"),
+        ("insert_provider_event", "event_id", "evt_trace_1"),
+        ("insert_payment", "external_id", "pi_trace_1"),
+    ] {
+        let span = find_span(&spans, name, attribute, value);
+        assert_eq!(trace_of(span), trace_of(webhook), "{name} left the trace");
+    }
+
+    // The other job has a trace of its own.
+    let other = find_span(&spans, "enqueue", "external_id", "pi_trace_0");
+    assert_ne!(trace_of(other), trace_of(webhook));
+    let job = find_span(&spans, "job", "event_id", "evt_trace_0");
+    assert_eq!(trace_of(job), trace_of(other));
+    let pipeline = find_span(&spans, "pipeline", "external_id", "pi_trace_0");
+    assert_eq!(trace_of(pipeline), trace_of(other));
+}
@@ -0,0 +1,87 @@
+mod common;
+
+use common::*;
+use fin_sync::domain::id::ExternalId;
+use fin_sync::domain::vector::{TestVector, VectorOutcome};
+use fin_sync::services::payment::pipeline::process_payment_event;
+use fin_sync::services::vectors::export_anomaly_vectors;
+use sqlx::PgPool;
+
+/// Every vector in `tests/vectors/*.json`.
+fn corpus() -> Vec<TestVector> {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/vectors");
+    let mut files: Vec<_> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    files.sort();
+    files
+        .iter()
+        .flat_map(|path| {
+            let body = std::fs::read_to_string(path).unwrap();
+            serde_json::from_str::<Vec<TestVector>>(&body)
+                .unwrap_or_else(|e| panic!("{}: {e}", path.display()))
+        })
+        .collect()
+}
+
+/// Run `vector` against a payment of its own; one line per mismatch.
+async fn run(pool: &PgPool, vector: &TestVector, tag: &str) -> Vec<String> {
+    let object = ExternalId::new(format!("{}{tag}", vector.object_prefix())).unwrap();
+    let mut failures = Vec::new();
+    for (event, payment) in vector
+        .events
+        .iter()
+        .zip(vector.payments(&object, tag).unwrap())
+    {
+        let result = process_payment_event(pool, &payment, "vector")
+            .await
+            .unwrap();
+        if !event.expect.matches(&result) {
+            failures.push(format!(
+                "{}: event {} ({}) expected {:?}, got {}",
+                vector.name,
+                event.event,
+                event.status,
+                event.expect,
+                result.as_str()
+            ));
+        }
+    }
+    let status = get_payment(pool, object.as_str()).await.map(|p| p.status);
+    if status.as_deref() != Some(vector.final_status.as_str()) {
+        failures.push(format!(
+            "{}: ended {status:?}, expected {}",
+            vector.name, vector.final_status
+        ));
+    }
+    failures
+}
+
+// ── 80. state_machine_vectors_hold ─────────────────────────────────────────
+
+#[tokio::test]
+async fn state_machine_vectors_hold() {
+    let pool = setup_pool("fin_sync_test_vectors").await;
+    let corpus = corpus();
+    assert!(!corpus.is_empty());
+
+    let mut failures = Vec::new();
+    for (i, vector) in corpus.iter().enumerate() {
+        failures.extend(run(&pool, vector, &format!("vec_{i}")).await);
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+
+    // Vectors exported from the anomalies just produced replay the same way.
+    let exported = export_anomaly_vectors(&pool, Some(200)).await.unwrap();
+    let with_anomaly = corpus
+        .iter()
+        .filter(|v| v.events.iter().any(|e| e.expect == VectorOutcome::Anomaly))
+        .count();
+    assert_eq!(exported.len(), with_anomaly);
+    for (i, vector) in exported.iter().enumerate() {
+        failures.extend(run(&pool, vector, &format!("exp_{i}")).await);
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
@@ -0,0 +1,134 @@
+mod common;
+
+use chrono::{TimeDelta, Utc};
+use common::*;
+use fin_sync::domain::config::RuntimeConfig;
+use fin_sync::domain::webhook::SignedDelivery;
+use fin_sync::error::PipelineError;
+use fin_sync::infra::postgres::webhook_repo;
+use fin_sync::services::config::{RuntimeConfigHandle, update_config};
+use fin_sync::services::webhook_guard::{admit, release};
+
+fn delivery(signature: &str, age: TimeDelta) -> SignedDelivery {
+    SignedDelivery {
+        source: "stripe",
+        signature: signature.into(),
+        signed_at: Utc::now() - age,
+    }
+}
+
+fn rejection(result: Result<(), PipelineError>) -> String {
+    match result {
+        Err(PipelineError::WebhookReplay(reason)) => reason,
+        other => panic!("expected a replay rejection, got {other:?}"),
+    }
+}
+
+// ── 57. replayed_and_stale_webhooks_are_rejected ───────────────────────────
+
+#[tokio::test]
+async fn replayed_and_stale_webhooks_are_rejected() {
+    let pool = setup_pool("fin_sync_test_webhook_replay").await;
+    let config = RuntimeConfigHandle::default();
+
+    let fresh = delivery("t=1,v1=fresh", TimeDelta::seconds(5));
+    admit(&pool, &config, &fresh).await.unwrap();
+    assert_eq!(rejection(admit(&pool, &config, &fresh).await), "replayed");
+
+    // Same signature from another provider is a different delivery.
+    let paypal = SignedDelivery {
+        source: "paypal",
+        ..fresh.clone()
+    };
+    admit(&pool, &config, &paypal).await.unwrap();
+
+    // Past the max age even though the signature was never seen.
+    let stale = delivery("t=2,v1=stale", TimeDelta::hours(2));
+    assert_eq!(rejection(admit(&pool, &config, &stale).await), "stale");
+
+    // A delivery that failed downstream can be redelivered.
+    let failed = delivery("t=3,v1=failed", TimeDelta::seconds(5));
+    admit(&pool, &config, &failed).await.unwrap();
+    release(&pool, &failed).await;
+    admit(&pool, &config, &failed).await.unwrap();
+
+    let old = delivery("t=4,v1=old", TimeDelta::hours(3));
+    assert!(webhook_repo::remember(&pool, &old).await.unwrap());
+    let cutoff = Utc::now() - config.current().config.webhook_max_age();
+    assert_eq!(webhook_repo::prune(&pool, cutoff).await.unwrap(), 1);
+}
+
+// ── 102. replay_rejections_are_audited_within_configured_window ───────────
+
+#[tokio::test]
+async fn replay_rejections_are_audited_within_configured_window() {
+    let pool = setup_pool("fin_sync_test_webhook_replay").await;
+    let config = RuntimeConfigHandle::default();
+    let next = RuntimeConfig {
+        webhook_max_age_secs: 600,
+        ..Default::default()
+    };
+    update_config(&pool, &config, next, "admin:ops")
+        .await
+        .unwrap();
+
+    // Ahead of our clock by more than the default window, but within the
+    // configured one.
+    let ahead = delivery("t=11,v1=ahead", TimeDelta::minutes(-9));
+    admit(&pool, &config, &ahead).await.unwrap();
+
+    let stale = delivery("t=12,v1=stale", TimeDelta::minutes(11));
+    assert_eq!(rejection(admit(&pool, &config, &stale).await), "stale");
+    let future = delivery("t=13,v1=future", TimeDelta::minutes(-11));
+    assert_eq!(rejection(admit(&pool, &config, &future).await), "future");
+    assert_eq!(rejection(admit(&pool, &config, &ahead).await), "replayed");
+
+    // Replaying them again only counts; each signature is audited once.
+    for _ in 0..3 {
+        for d in [&stale, &future, &ahead] {
+            assert!(admit(&pool, &config, d).await.is_err());
+        }
+    }
+    let counts: Vec<i32> = sqlx::query_scalar(
+        "SELECT rejections FROM webhook_signatures WHERE signature LIKE 't=1_,%' ORDER BY signature",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(counts, [4, 4, 4]);
+    // Still being replayed, the stale one outlives the prune that would
+    // otherwise forget it and audit it anew.
+    webhook_repo::prune(&pool, Utc::now() - TimeDelta::minutes(10))
+        .await
+        .unwrap();
+    assert_eq!(rejection(admit(&pool, &config, &stale).await), "stale");
+
+    let rows: Vec<(String, String, Option<String>, serde_json::Value)> = sqlx::query_as(
+        "SELECT action, actor, source, detail FROM audit_log
+         WHERE entity_type = 'webhook' AND detail->>'signature' LIKE 't=1_,%'
+         ORDER BY id",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    let rejections: Vec<_> = rows
+        .iter()
+        .map(|(action, actor, source, detail)| {
+            assert_eq!(action, "replay_rejected");
+            assert_eq!(actor, "webhook:stripe");
+            assert_eq!(source.as_deref(), Some("stripe"));
+            (
+                detail["rejection"].as_str().unwrap(),
+                detail["signature"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        rejections,
+        [
+            ("stale", "t=12,v1=stale"),
+            ("future", "t=13,v1=future"),
+            ("replayed", "t=11,v1=ahead"),
+        ]
+    );
+}
@@ -0,0 +1,316 @@
+mod common;
+
+use axum::{
+    Json, Router,
+    body::Bytes,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use common::*;
+use fin_sync::adapters::stripe::{client::StripeProvider, router::EventRouter};
+use fin_sync::domain::webhook_endpoint::SetupAction;
+use fin_sync::error::PipelineError;
+use fin_sync::infra::settings::Settings;
+use fin_sync::services::webhook_setup::{self, WEBHOOK_SECRETS};
+use serde_json::{Value, json};
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+const URL: &str = "https://fin-sync.example.com/webhook";
+
+#[derive(Default)]
+struct Account {
+    endpoints: BTreeMap<String, Value>,
+    created: usize,
+    posts_without_key: usize,
+}
+
+type Stripe = Arc<Mutex<Account>>;
+
+/// `application/x-www-form-urlencoded` pairs, in order.
+fn form(body: &[u8]) -> Vec<(String, String)> {
+    let decode = |s: &str| {
+        let s = s.replace('+', " ");
+        let bytes = s.as_bytes();
+        let mut out = Vec::new();
+        let mut i = 0;
+        while i < bytes.len() {
+            if bytes[i] == b'%' {
+                out.push(u8::from_str_radix(&s[i + 1..i + 3], 16).unwrap());
+                i += 3;
+            } else {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+        String::from_utf8(out).unwrap()
+    };
+    std::str::from_utf8(body)
+        .unwrap()
+        .split('&')
+        .filter_map(|kv| kv.split_once('='))
+        .map(|(k, v)| (decode(k), decode(v)))
+        .collect()
+}
+
+/// Apply a create or update form to `endpoint`.
+fn apply(endpoint: &mut Value, body: &[u8]) {
+    let pairs = form(body);
+    let events: Vec<_> = pairs
+        .iter()
+        .filter(|(k, _)| k == "enabled_events[]")
+        .map(|(_, v)| v.clone())
+        .collect();
+    for (key, value) in &pairs {
+        match key.as_str() {
+            "url" => endpoint["url"] = value.clone().into(),
+            "disabled" if value == "false" => endpoint["status"] = "enabled".into(),
+            _ => {}
+        }
+    }
+    if !events.is_empty() {
+        endpoint["enabled_events"] = events.into();
+    }
+}
+
+fn not_found() -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({"error": {"type": "invalid_request_error", "message": "no such endpoint"}})),
+    )
+        .into_response()
+}
+
+/// The webhook endpoint API of one Stripe account, starting with `we_0`,
+/// a disabled endpoint for `URL` set up by hand.
+async fn stripe_api(account: Stripe) -> String {
+    async fn list(State(account): State<Stripe>) -> Json<Value> {
+        let account = account.lock().unwrap();
+        let data: Vec<_> = account.endpoints.values().cloned().collect();
+        Json(
+            json!({"object": "list", "data": data, "has_more": false, "url": "/v1/webhook_endpoints"}),
+        )
+    }
+    async fn create(State(account): State<Stripe>, headers: HeaderMap, body: Bytes) -> Json<Value> {
+        let mut account = account.lock().unwrap();
+        if !headers.contains_key("idempotency-key") {
+            account.posts_without_key += 1;
+        }
+        account.created += 1;
+        let id = format!("we_{}", account.created);
+        let mut endpoint = json!({
+            "id": id, "object": "webhook_endpoint", "status": "enabled",
+            "url": "", "enabled_events": [],
+        });
+        apply(&mut endpoint, &body);
+        account.endpoints.insert(id.clone(), endpoint.clone());
+        endpoint["secret"] = format!("whsec_test_{}", account.created).into();
+        Json(endpoint)
+    }
+    async fn one(State(account): State<Stripe>, Path(id): Path<String>) -> Response {
+        match account.lock().unwrap().endpoints.get(&id) {
+            Some(endpoint) => Json(endpoint.clone()).into_response(),
+            None => not_found(),
+        }
+    }
+    async fn update(
+        State(account): State<Stripe>,
+        Path(id): Path<String>,
+        headers: HeaderMap,
+        body: Bytes,
+    ) -> Response {
+        let mut account = account.lock().unwrap();
+        if !headers.contains_key("idempotency-key") {
+            account.posts_without_key += 1;
+        }
+        match account.endpoints.get_mut(&id) {
+            Some(endpoint) => {
+                apply(endpoint, &body);
+                Json(endpoint.clone()).into_response()
+            }
+            None => not_found(),
+        }
+    }
+    async fn delete(State(account): State<Stripe>, Path(id): Path<String>) -> Response {
+        match account.lock().unwrap().endpoints.remove(&id) {
+            Some(_) => Json(json!({"id": id, "deleted": true})).into_response(),
+            None => not_found(),
+        }
+    }
+
+    account.lock().unwrap().endpoints.insert(
+        "we_0".into(),
+        json!({
+            "id": "we_0", "object": "webhook_endpoint", "status": "disabled",
+            "url": URL, "enabled_events": ["payment_intent.succeeded"],
+        }),
+    );
+    let app = Router::new()
+        .route("/v1/webhook_endpoints", get(list).post(create))
+        .route(
+            "/v1/webhook_endpoints/{id}",
+            get(one).post(update).delete(delete),
+        )
+        .with_state(account);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    url
+}
+
+// ── 122. webhook_endpoint_is_registered_and_its_secret_rotated ────────────
+
+#[tokio::test]
+async fn webhook_endpoint_is_registered_and_its_secret_rotated() {
+    let pool = setup_pool("fin_sync_test_webhook_setup").await;
+    let account = Stripe::default();
+    let base = stripe_api(account.clone()).await;
+    let stripe = StripeProvider::new("sk_test_setup").with_api_base(&base);
+    let settings = Settings::new(pool.clone()).with_sealing_key(Some(sealing_key()));
+    let target = webhook_setup::target(URL, &EventRouter::default());
+
+    // Nothing to rotate before the first setup.
+    let early =
+        webhook_setup::rotate_secret(&settings, &stripe, &target, Duration::ZERO, "ops").await;
+    assert!(matches!(early, Err(PipelineError::Validation(_))));
+
+    // The endpoint already there for the URL is taken over and enabled,
+    // sending the routed events; its secret is the configured one.
+    let setup = webhook_setup::sync_endpoint(&settings, &stripe, &target, "ops")
+        .await
+        .unwrap();
+    assert_eq!(
+        (
+            setup.action,
+            setup.endpoint_id.as_str(),
+            setup.secret_version
+        ),
+        (SetupAction::Adopted, "we_0", 1)
+    );
+    assert_eq!(setup.enabled_events, target.enabled_events);
+    assert!(setup.enabled_events.contains(&"payout.paid".to_string()));
+    assert!(!setup.enabled_events.contains(&"charge.updated".to_string()));
+    assert_eq!(
+        account.lock().unwrap().endpoints["we_0"]["status"],
+        "enabled"
+    );
+    let secrets = settings.get(&WEBHOOK_SECRETS).await.unwrap().unwrap();
+    assert_eq!(secrets.current().unwrap().secret, None);
+
+    // Again: nothing to do.
+    let again = webhook_setup::sync_endpoint(&settings, &stripe, &target, "ops")
+        .await
+        .unwrap();
+    assert_eq!(again.action, SetupAction::Unchanged);
+
+    // Rotating creates a second endpoint and stores its secret; the first
+    // keeps delivering through the overlap.
+    let rotation =
+        webhook_setup::rotate_secret(&settings, &stripe, &target, Duration::from_secs(1), "ops")
+            .await
+            .unwrap();
+    assert_eq!(
+        (
+            rotation.endpoint_id.as_str(),
+            rotation.secret_version,
+            rotation.previous_endpoint_id.as_str()
+        ),
+        ("we_1", 2, "we_0")
+    );
+    let secrets = Settings::new(pool.clone())
+        .with_sealing_key(Some(sealing_key()))
+        .get(&WEBHOOK_SECRETS)
+        .await
+        .unwrap()
+        .unwrap();
+    let current = secrets.current().unwrap();
+    assert_eq!(
+        (current.version, current.secret.as_deref()),
+        (2, Some("whsec_test_1"))
+    );
+    assert_eq!(
+        secrets.versions[0].retires_at,
+        Some(rotation.previous_retires_at)
+    );
+    assert_eq!(account.lock().unwrap().endpoints.len(), 2);
+
+    // Past the overlap, the next setup deletes the old endpoint.
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    let setup = webhook_setup::sync_endpoint(&settings, &stripe, &target, "ops")
+        .await
+        .unwrap();
+    assert_eq!(
+        (setup.action, setup.retired.as_slice()),
+        (SetupAction::Unchanged, ["we_0".to_string()].as_slice())
+    );
+    assert!(!account.lock().unwrap().endpoints.contains_key("we_0"));
+
+    // Routing changes reach the endpoint.
+    let routes = EventRouter::with_overrides(&["payout.*=ignore".parse().unwrap()]);
+    let narrower = webhook_setup::target(URL, &routes);
+    let setup = webhook_setup::sync_endpoint(&settings, &stripe, &narrower, "ops")
+        .await
+        .unwrap();
+    assert_eq!(setup.action, SetupAction::Updated);
+    let events = account.lock().unwrap().endpoints["we_1"]["enabled_events"].clone();
+    assert!(!events.as_array().unwrap().contains(&"payout.paid".into()));
+
+    // Every write was idempotent, audited, and no secret was written there.
+    assert_eq!(account.lock().unwrap().posts_without_key, 0);
+    let actions: Vec<String> = sqlx::query_scalar(
+        "SELECT action FROM audit_log WHERE entity_type = 'webhook_endpoint' ORDER BY created_at",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        actions,
+        [
+            "webhook_endpoint_adopted",
+            "webhook_secret_rotated",
+            "webhook_endpoint_retired",
+            "webhook_endpoint_updated",
+        ]
+    );
+    let leaked: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM audit_log WHERE detail::text LIKE '%whsec_%'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(leaked, 0);
+
+    // The stored secrets are sealed: unreadable in the table, and without
+    // the key.
+    let stored: String =
+        sqlx::query_scalar("SELECT value::text FROM settings WHERE key = 'stripe.webhook_secrets'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert!(stored.contains("sealed") && !stored.contains("whsec_"));
+    let unkeyed = Settings::new(pool.clone());
+    assert!(unkeyed.get(&WEBHOOK_SECRETS).await.is_err());
+    let refused = webhook_setup::rotate_secret(&unkeyed, &stripe, &target, Duration::ZERO, "ops");
+    assert!(refused.await.is_err());
+
+    // Rotations racing on two replicas both land: the second waits for the
+    // first's write instead of overwriting it.
+    let other = Settings::new(pool.clone()).with_sealing_key(Some(sealing_key()));
+    let (a, b) = tokio::join!(
+        webhook_setup::rotate_secret(&settings, &stripe, &target, Duration::ZERO, "a"),
+        webhook_setup::rotate_secret(&other, &stripe, &target, Duration::ZERO, "b"),
+    );
+    let mut versions = [a.unwrap().secret_version, b.unwrap().secret_version];
+    versions.sort();
+    // Read from a store of its own: either replica's cache may still hold
+    // its own write until the other's change notification arrives.
+    let fresh = Settings::new(pool.clone()).with_sealing_key(Some(sealing_key()));
+    let secrets = fresh.get(&WEBHOOK_SECRETS).await.unwrap().unwrap();
+    let stored: Vec<_> = secrets.versions.iter().map(|v| v.version).collect();
+    assert!(stored.ends_with(&versions), "{stored:?} {versions:?}");
+    assert_eq!(versions[1], versions[0] + 1);
+}
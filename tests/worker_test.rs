@@ -0,0 +1,1460 @@
+mod common;
+
+use chrono::{DateTime, Utc};
+use common::*;
+use fin_sync::adapters::stripe::client::StripeProvider;
+use fin_sync::domain::config::{RuntimeConfig, VersionedConfig};
+use fin_sync::domain::error::PortError;
+use fin_sync::domain::id::ExternalId;
+use fin_sync::domain::job::{JobLane, JobPriority};
+use fin_sync::domain::payment::PaymentStatus;
+use fin_sync::domain::provider::{
+    FetchedPayment, ListCursor, PaymentPage, PaymentProvider, ProviderRegistry,
+};
+use fin_sync::error::PipelineError;
+use fin_sync::infra::postgres::job_repo;
+use fin_sync::infra::settings::Settings;
+use fin_sync::retry::Clock;
+use fin_sync::services::config::RuntimeConfigHandle;
+use fin_sync::services::jobs::{get_event_status, get_job_detail};
+use fin_sync::services::payment::pipeline::process_payment_event;
+use fin_sync::services::reconciliation::list_review;
+use fin_sync::services::worker::{WorkerIdentity, run_worker, run_worker_with_clock};
+use fin_sync::testing::StripeEventFixture;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::{future::Future, pin::Pin, sync::Arc, time::Duration};
+
+/// Takes 200ms per fetch and records the most fetches ever in flight.
+#[derive(Default)]
+struct SlowProvider {
+    in_flight: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+impl PaymentProvider for SlowProvider {
+    fn source(&self) -> &'static str {
+        "stripe"
+    }
+
+    fn fetch_payment(
+        &self,
+        id: &ExternalId,
+    ) -> Pin<Box<dyn Future<Output = Result<FetchedPayment, PortError>> + Send + '_>> {
+        let fetched = fetched_payment(id.as_str(), PaymentStatus::Succeeded, 900);
+        Box::pin(async move {
+            let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            Ok(fetched)
+        })
+    }
+
+    fn list_payments(
+        &self,
+        _since: DateTime<Utc>,
+        _until: DateTime<Utc>,
+        _cursor: Option<ListCursor>,
+    ) -> Pin<Box<dyn Future<Output = Result<PaymentPage, PortError>> + Send + '_>> {
+        Box::pin(async { Err(PortError::Unavailable("not used".into())) })
+    }
+}
+
+/// Fails the first fetch of `pi_flaky` with a provider error; everything
+/// else succeeds straight away.
+#[derive(Default)]
+struct FlakyProvider {
+    flaky_fetches: AtomicUsize,
+}
+
+impl PaymentProvider for FlakyProvider {
+    fn source(&self) -> &'static str {
+        "stripe"
+    }
+
+    fn fetch_payment(
+        &self,
+        id: &ExternalId,
+    ) -> Pin<Box<dyn Future<Output = Result<FetchedPayment, PortError>> + Send + '_>> {
+        let first_flaky =
+            id.as_str() == "pi_flaky" && self.flaky_fetches.fetch_add(1, Ordering::SeqCst) == 0;
+        let fetched = fetched_payment(id.as_str(), PaymentStatus::Succeeded, 900);
+        Box::pin(async move {
+            if first_flaky {
+                return Err(PortError::Unavailable("Stripe API: 503".into()));
+            }
+            Ok(fetched)
+        })
+    }
+
+    fn list_payments(
+        &self,
+        _since: DateTime<Utc>,
+        _until: DateTime<Utc>,
+        _cursor: Option<ListCursor>,
+    ) -> Pin<Box<dyn Future<Output = Result<PaymentPage, PortError>> + Send + '_>> {
+        Box::pin(async { Err(PortError::Unavailable("not used".into())) })
+    }
+}
+
+/// Reads webhook objects as Stripe does, but answers and counts fetches as
+/// `StubProvider` (900 cents, succeeded).
+struct PayloadProvider {
+    stripe: StripeProvider,
+    counting: StubProvider,
+}
+
+impl PaymentProvider for PayloadProvider {
+    fn source(&self) -> &'static str {
+        "stripe"
+    }
+
+    fn fetch_payment(
+        &self,
+        id: &ExternalId,
+    ) -> Pin<Box<dyn Future<Output = Result<FetchedPayment, PortError>> + Send + '_>> {
+        self.counting.fetch_payment(id)
+    }
+
+    fn list_payments(
+        &self,
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+        cursor: Option<ListCursor>,
+    ) -> Pin<Box<dyn Future<Output = Result<PaymentPage, PortError>> + Send + '_>> {
+        self.counting.list_payments(since, until, cursor)
+    }
+
+    fn payment_from_payload(
+        &self,
+        payload: &serde_json::Value,
+    ) -> Result<Option<FetchedPayment>, PortError> {
+        self.stripe.payment_from_payload(payload)
+    }
+}
+
+/// Doesn't have anything: every fetch is a 404.
+struct GoneProvider;
+
+impl PaymentProvider for GoneProvider {
+    fn source(&self) -> &'static str {
+        "stripe"
+    }
+
+    fn fetch_payment(
+        &self,
+        id: &ExternalId,
+    ) -> Pin<Box<dyn Future<Output = Result<FetchedPayment, PortError>> + Send + '_>> {
+        let id = id.clone();
+        Box::pin(async move {
+            Err(PortError::Missing(format!(
+                "Stripe API: No such payment_intent: '{id}'"
+            )))
+        })
+    }
+
+    fn list_payments(
+        &self,
+        _since: DateTime<Utc>,
+        _until: DateTime<Utc>,
+        _cursor: Option<ListCursor>,
+    ) -> Pin<Box<dyn Future<Output = Result<PaymentPage, PortError>> + Send + '_>> {
+        Box::pin(async { Err(PortError::Unavailable("not used".into())) })
+    }
+}
+
+/// Never answers; counts the fetches it was asked for.
+#[derive(Default)]
+struct HangingProvider {
+    started: AtomicUsize,
+}
+
+impl PaymentProvider for HangingProvider {
+    fn source(&self) -> &'static str {
+        "stripe"
+    }
+
+    fn fetch_payment(
+        &self,
+        _id: &ExternalId,
+    ) -> Pin<Box<dyn Future<Output = Result<FetchedPayment, PortError>> + Send + '_>> {
+        self.started.fetch_add(1, Ordering::SeqCst);
+        Box::pin(std::future::pending())
+    }
+
+    fn list_payments(
+        &self,
+        _since: DateTime<Utc>,
+        _until: DateTime<Utc>,
+        _cursor: Option<ListCursor>,
+    ) -> Pin<Box<dyn Future<Output = Result<PaymentPage, PortError>> + Send + '_>> {
+        Box::pin(async { Err(PortError::Unavailable("not used".into())) })
+    }
+}
+
+/// Each test runs a worker, which would claim the other test's jobs.
+/// Records the worker's pauses and cuts each one to 5ms.
+#[derive(Clone, Default)]
+struct RecordingClock {
+    pauses: Arc<Mutex<Vec<u64>>>,
+}
+
+impl RecordingClock {
+    fn pauses(&self) -> Vec<u64> {
+        self.pauses.lock().unwrap().clone()
+    }
+}
+
+impl Clock for RecordingClock {
+    fn sleep(&self, wait: Duration) -> impl Future<Output = ()> + Send {
+        self.pauses.lock().unwrap().push(wait.as_millis() as u64);
+        tokio::time::sleep(Duration::from_millis(5))
+    }
+}
+
+static ONE_WORKER: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+fn worker_config(config: RuntimeConfig) -> RuntimeConfigHandle {
+    RuntimeConfigHandle::new(VersionedConfig { version: 0, config })
+}
+
+// ── 68. worker_wakes_on_job_notification ───────────────────────────────────
+
+#[tokio::test]
+async fn worker_wakes_on_job_notification() {
+    let _worker = ONE_WORKER.lock().await;
+    let pool = setup_pool("fin_sync_test_worker").await;
+
+    // Polling alone would leave the job waiting a minute.
+    let config = worker_config(RuntimeConfig {
+        worker_poll_interval_ms: 60_000,
+        ..Default::default()
+    });
+    let identity = WorkerIdentity {
+        hostname: "pod-b".into(),
+        instance_id: "0000beef".into(),
+    };
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+    let worker = tokio::spawn(run_worker(
+        pool.clone(),
+        Settings::new(pool.clone()),
+        ProviderRegistry::default(),
+        config,
+        identity,
+        JobLane::Standard,
+        shutdown_rx,
+    ));
+    // Let the worker finish its first (empty) poll and go to sleep.
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let raw = serde_json::json!({"id": "evt_wake_1"});
+    job_repo::enqueue(
+        &pool,
+        "stripe",
+        None,
+        "evt_wake_1",
+        "pi_wake_1",
+        "test.event",
+        1000,
+        &raw,
+        JobPriority::Normal,
+    )
+    .await
+    .unwrap();
+
+    // With no providers registered the worker fails the job, which is
+    // enough to show it was claimed.
+    let mut claimed_by: Option<String> = None;
+    for _ in 0..30 {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        claimed_by = sqlx::query_scalar(
+            "SELECT claimed_by FROM payment_jobs WHERE event_id = 'evt_wake_1' AND attempts > 0",
+        )
+        .fetch_optional(&pool)
+        .await
+        .unwrap()
+        .flatten();
+        if claimed_by.is_some() {
+            break;
+        }
+    }
+    shutdown_tx.send(true).unwrap();
+    worker.await.unwrap();
+    assert_eq!(
+        claimed_by.as_deref(),
+        Some("pod-b/0000beef"),
+        "job was not picked up before the poll interval"
+    );
+}
+
+// ── 69. worker_processes_claimed_jobs_concurrently ─────────────────────────
+
+#[tokio::test]
+async fn worker_processes_claimed_jobs_concurrently() {
+    let _worker = ONE_WORKER.lock().await;
+    let pool = setup_pool("fin_sync_test_worker").await;
+    for n in 1..=6 {
+        let event_id = format!("evt_par_{n}");
+        let raw = serde_json::json!({"id": event_id});
+        job_repo::enqueue(
+            &pool,
+            "stripe",
+            None,
+            &event_id,
+            &format!("pi_par_{n}"),
+            "test.event",
+            1000,
+            &raw,
+            JobPriority::Normal,
+        )
+        .await
+        .unwrap();
+    }
+
+    let provider = Arc::new(SlowProvider::default());
+    let mut providers = ProviderRegistry::default();
+    providers.register(provider.clone());
+    let config = worker_config(RuntimeConfig {
+        worker_concurrency: 3,
+        ..Default::default()
+    });
+    let identity = WorkerIdentity {
+        hostname: "pod-c".into(),
+        instance_id: "0000cafe".into(),
+    };
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+    let worker = tokio::spawn(run_worker(
+        pool.clone(),
+        Settings::new(pool.clone()),
+        providers,
+        config,
+        identity,
+        JobLane::Standard,
+        shutdown_rx,
+    ));
+
+    let mut done = 0;
+    for _ in 0..50 {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        done = sqlx::query_scalar::<_, i64>(
+            "SELECT count(*) FROM payment_jobs WHERE event_id LIKE 'evt_par_%' AND status = 'completed'",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        if done == 6 {
+            break;
+        }
+    }
+    shutdown_tx.send(true).unwrap();
+    worker.await.unwrap();
+
+    assert_eq!(done, 6);
+    // Never more than the configured limit, but more than one at a time.
+    assert_eq!(provider.peak.load(Ordering::SeqCst), 3);
+}
+
+// ── 74. job_attempts_record_each_try ───────────────────────────────────────
+
+#[tokio::test]
+async fn job_attempts_record_each_try() {
+    let _worker = ONE_WORKER.lock().await;
+    let pool = setup_pool("fin_sync_test_worker").await;
+    let raw = serde_json::json!({"id": "evt_flaky_1"});
+    job_repo::enqueue(
+        &pool,
+        "stripe",
+        None,
+        "evt_flaky_1",
+        "pi_flaky",
+        "payment_intent.succeeded",
+        1000,
+        &raw,
+        JobPriority::Normal,
+    )
+    .await
+    .unwrap();
+    let job_id: uuid::Uuid =
+        sqlx::query_scalar("SELECT id FROM payment_jobs WHERE event_id = 'evt_flaky_1'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+
+    let mut providers = ProviderRegistry::default();
+    providers.register(Arc::new(FlakyProvider::default()));
+    // The retry is due 2s after the failure; poll often enough to catch it.
+    let config = worker_config(RuntimeConfig {
+        worker_poll_interval_ms: 200,
+        worker_idle_poll_max_ms: 200,
+        ..Default::default()
+    });
+    let identity = WorkerIdentity {
+        hostname: "pod-d".into(),
+        instance_id: "0000f00d".into(),
+    };
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+    let worker = tokio::spawn(run_worker(
+        pool.clone(),
+        Settings::new(pool.clone()),
+        providers,
+        config,
+        identity,
+        JobLane::Standard,
+        shutdown_rx,
+    ));
+
+    let mut detail = None;
+    for _ in 0..50 {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let d = get_job_detail(&pool, job_id).await.unwrap().unwrap();
+        if d.job.status == "completed" {
+            detail = Some(d);
+            break;
+        }
+    }
+    shutdown_tx.send(true).unwrap();
+    worker.await.unwrap();
+
+    let detail = detail.expect("job completed on retry");
+    let history: Vec<_> = detail
+        .history
+        .iter()
+        .map(|a| (a.attempt, a.outcome.as_deref(), a.error.as_deref()))
+        .collect();
+    assert_eq!(
+        history,
+        [
+            (1, Some("failed"), Some("provider: Stripe API: 503")),
+            (2, Some("succeeded"), None),
+        ]
+    );
+    assert!(detail.history.iter().all(|a| a.worker == "pod-d/0000f00d"));
+    assert!(
+        detail
+            .history
+            .iter()
+            .all(|a| a.finished_at.is_some_and(|f| f >= a.started_at))
+    );
+}
+
+// ── 82. worker_fetches_each_object_once_per_batch ──────────────────────────
+
+#[tokio::test]
+async fn worker_fetches_each_object_once_per_batch() {
+    let _worker = ONE_WORKER.lock().await;
+    let pool = setup_pool("fin_sync_test_worker").await;
+    for n in 1..=6 {
+        let event_id = format!("evt_batch_{n}");
+        let raw = serde_json::json!({"id": event_id});
+        job_repo::enqueue(
+            &pool,
+            "stripe",
+            None,
+            &event_id,
+            &format!("pi_batch_{}", n % 2),
+            "payment_intent.succeeded",
+            1000 + n as i64,
+            &raw,
+            JobPriority::Normal,
+        )
+        .await
+        .unwrap();
+    }
+
+    let provider = Arc::new(StubProvider::default());
+    let mut providers = ProviderRegistry::default();
+    providers.register(provider.clone());
+    let config = worker_config(RuntimeConfig {
+        worker_batch_size: 10,
+        ..Default::default()
+    });
+    let identity = WorkerIdentity {
+        hostname: "pod-e".into(),
+        instance_id: "0000babe".into(),
+    };
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+    let worker = tokio::spawn(run_worker(
+        pool.clone(),
+        Settings::new(pool.clone()),
+        providers,
+        config,
+        identity,
+        JobLane::Standard,
+        shutdown_rx,
+    ));
+
+    let mut done = 0;
+    for _ in 0..50 {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        done = sqlx::query_scalar::<_, i64>(
+            "SELECT count(*) FROM payment_jobs WHERE event_id LIKE 'evt_batch_%' AND status = 'completed'",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        if done == 6 {
+            break;
+        }
+    }
+    shutdown_tx.send(true).unwrap();
+    worker.await.unwrap();
+
+    assert_eq!(done, 6);
+    // One batch, two objects: two fetches, not six.
+    assert_eq!(provider.calls(), 2);
+    for id in ["pi_batch_0", "pi_batch_1"] {
+        assert_eq!(get_payment(&pool, id).await.unwrap().status, "succeeded");
+    }
+}
+
+// ── 87. refund_lane_skips_the_standard_backlog ─────────────────────────────
+
+#[tokio::test]
+async fn refund_lane_skips_the_standard_backlog() {
+    let _worker = ONE_WORKER.lock().await;
+    let pool = setup_pool("fin_sync_test_worker").await;
+    for n in 1..=5 {
+        let event_id = format!("evt_backlog_{n}");
+        let raw = serde_json::json!({"id": event_id});
+        job_repo::enqueue(
+            &pool,
+            "stripe",
+            None,
+            &event_id,
+            &format!("pi_backlog_{n}"),
+            "payment_intent.succeeded",
+            1000,
+            &raw,
+            JobPriority::Normal,
+        )
+        .await
+        .unwrap();
+    }
+
+    let mut providers = ProviderRegistry::default();
+    providers.register(Arc::new(StubProvider::default()));
+    // Only a notification for its own lane gets the worker going in time.
+    let config = worker_config(RuntimeConfig {
+        refund_worker_poll_interval_ms: 60_000,
+        ..Default::default()
+    });
+    let identity = WorkerIdentity {
+        hostname: "pod-f".into(),
+        instance_id: "0000d00d".into(),
+    };
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+    let worker = tokio::spawn(run_worker(
+        pool.clone(),
+        Settings::new(pool.clone()),
+        providers,
+        config,
+        identity,
+        JobLane::Refund,
+        shutdown_rx,
+    ));
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let raw = serde_json::json!({"id": "evt_lane_re"});
+    job_repo::enqueue(
+        &pool,
+        "stripe",
+        None,
+        "evt_lane_re",
+        "re_lane_1",
+        "refund.updated",
+        1000,
+        &raw,
+        JobPriority::Normal,
+    )
+    .await
+    .unwrap();
+
+    let mut claimed_by: Option<String> = None;
+    for _ in 0..30 {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        claimed_by = sqlx::query_scalar(
+            "SELECT claimed_by FROM payment_jobs WHERE event_id = 'evt_lane_re' AND status <> 'pending'",
+        )
+        .fetch_optional(&pool)
+        .await
+        .unwrap()
+        .flatten();
+        if claimed_by.is_some() {
+            break;
+        }
+    }
+    shutdown_tx.send(true).unwrap();
+    worker.await.unwrap();
+
+    assert_eq!(claimed_by.as_deref(), Some("pod-f/0000d00d"));
+    let lanes: Vec<(String, String)> = sqlx::query_as(
+        "SELECT lane, status FROM payment_jobs
+         WHERE event_id LIKE 'evt_backlog_%' OR event_id = 'evt_lane_re'
+         ORDER BY lane, event_id",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(lanes.len(), 6);
+    assert_eq!(lanes[0].0, "refund");
+    assert!(
+        lanes[1..]
+            .iter()
+            .all(|(lane, status)| lane == "standard" && status == "pending"),
+        "{lanes:?}"
+    );
+    // Leave no backlog for the other tests' standard workers.
+    sqlx::query("DELETE FROM payment_jobs WHERE event_id LIKE 'evt_backlog_%'")
+        .execute(&pool)
+        .await
+        .unwrap();
+}
+
+// ── 89. orphan_refund_has_its_parent_fetched_once ──────────────────────────
+
+#[tokio::test]
+async fn orphan_refund_has_its_parent_fetched_once() {
+    let _worker = ONE_WORKER.lock().await;
+    let pool = setup_pool("fin_sync_test_worker").await;
+    let provider = Arc::new(StubProvider::default());
+    let mut providers = ProviderRegistry::default();
+    providers.register(provider.clone());
+    let config = worker_config(RuntimeConfig {
+        worker_poll_interval_ms: 60_000,
+        ..Default::default()
+    });
+    let identity = WorkerIdentity {
+        hostname: "pod-g".into(),
+        instance_id: "0000f00d".into(),
+    };
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+    let worker = tokio::spawn(run_worker(
+        pool.clone(),
+        Settings::new(pool.clone()),
+        providers,
+        config,
+        identity,
+        JobLane::Standard,
+        shutdown_rx,
+    ));
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    // Two refunds of a payment we haven't seen: one follow-up between them,
+    // queued with the first refund.
+    for n in 1..=2 {
+        let refund = make_refund(
+            &format!("re_orphan_{n}"),
+            &format!("evt_orphan_{n}"),
+            PaymentStatus::Succeeded,
+            1000,
+            "pi_orphan_parent",
+        );
+        process_payment_event(&pool, &refund, "test").await.unwrap();
+    }
+
+    let mut parent = None;
+    for _ in 0..30 {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        parent = get_payment(&pool, "pi_orphan_parent").await;
+        if parent.is_some() {
+            break;
+        }
+    }
+    shutdown_tx.send(true).unwrap();
+    worker.await.unwrap();
+
+    assert_eq!(parent.expect("parent was not fetched").status, "succeeded");
+    assert_eq!(provider.calls(), 1);
+    let followups: Vec<(String, String, Option<String>)> = sqlx::query_as(
+        "SELECT kind, status, claimed_by FROM followup_jobs
+         WHERE dedup_key = 'fetch_parent:stripe:pi_orphan_parent'",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        followups,
+        vec![(
+            "fetch_parent".to_string(),
+            "completed".to_string(),
+            Some("pod-g/0000f00d".to_string())
+        )]
+    );
+    let created: (String, String) = sqlx::query_as(
+        "SELECT actor, event_id FROM audit_log
+         WHERE external_id = 'pi_orphan_parent' AND action = 'created'",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        created,
+        (
+            "followup:stripe".to_string(),
+            "evt_parent_pi_orphan_parent".to_string()
+        )
+    );
+}
+
+// ── 93. missing_provider_objects_complete_without_retries ──────────────────
+
+#[tokio::test]
+async fn missing_provider_objects_complete_without_retries() {
+    let _worker = ONE_WORKER.lock().await;
+    let pool = setup_pool("fin_sync_test_worker").await;
+    let known = make_payment("pi_gone_1", "evt_gone_0", PaymentStatus::Pending, 1000);
+    process_payment_event(&pool, &known, "test").await.unwrap();
+    // Two deliveries for the payment we have, one for an object we never saw.
+    for (event_id, object_id) in [
+        ("evt_gone_1", "pi_gone_1"),
+        ("evt_gone_2", "pi_gone_2"),
+        ("evt_gone_3", "pi_gone_1"),
+    ] {
+        let raw = serde_json::json!({"id": event_id});
+        job_repo::enqueue(
+            &pool,
+            "stripe",
+            None,
+            event_id,
+            object_id,
+            "payment_intent.succeeded",
+            2000,
+            &raw,
+            JobPriority::Normal,
+        )
+        .await
+        .unwrap();
+    }
+
+    let mut providers = ProviderRegistry::default();
+    providers.register(Arc::new(GoneProvider));
+    let config = worker_config(RuntimeConfig {
+        worker_poll_interval_ms: 200,
+        ..Default::default()
+    });
+    let identity = WorkerIdentity {
+        hostname: "pod-h".into(),
+        instance_id: "0000f00d".into(),
+    };
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+    let worker = tokio::spawn(run_worker(
+        pool.clone(),
+        Settings::new(pool.clone()),
+        providers,
+        config,
+        identity,
+        JobLane::Standard,
+        shutdown_rx,
+    ));
+    let mut jobs: Vec<(String, String, i32)> = Vec::new();
+    for _ in 0..30 {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        jobs = sqlx::query_as(
+            "SELECT event_id, status, attempts FROM payment_jobs
+             WHERE event_id LIKE 'evt_gone_%' ORDER BY event_id",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        if jobs.iter().all(|(_, status, _)| status == "completed") {
+            break;
+        }
+    }
+    shutdown_tx.send(true).unwrap();
+    worker.await.unwrap();
+
+    // Completed on the first attempt, with the outcome on the attempt.
+    assert!(
+        jobs.iter()
+            .all(|(_, status, attempts)| status == "completed" && *attempts == 0),
+        "{jobs:?}"
+    );
+    let outcomes: Vec<(Option<String>, Option<String>)> = sqlx::query_as(
+        "SELECT a.outcome, a.error FROM job_attempts a
+         JOIN payment_jobs j ON j.id = a.job_id
+         WHERE j.event_id LIKE 'evt_gone_%' ORDER BY j.event_id",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(outcomes.len(), 3);
+    assert!(outcomes.iter().all(|(o, e)| {
+        o.as_deref() == Some("provider_missing")
+            && e.as_deref()
+                .is_some_and(|e| e.contains("No such payment_intent"))
+    }));
+
+    // Each delivery is on the audit trail; only the payment we have is
+    // flagged, and only once.
+    let audit: Vec<(String, String, bool)> = sqlx::query_as(
+        "SELECT event_id, actor, entity_id IS NOT NULL FROM audit_log
+         WHERE action = 'provider_missing' ORDER BY event_id",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    let worker_actor = "worker:stripe".to_string();
+    assert_eq!(
+        audit,
+        [
+            (
+                "provider_missing:evt_gone_1".into(),
+                worker_actor.clone(),
+                true
+            ),
+            (
+                "provider_missing:evt_gone_2".into(),
+                worker_actor.clone(),
+                false
+            ),
+            ("provider_missing:evt_gone_3".into(), worker_actor, true),
+        ]
+    );
+    let review = list_review(&pool, None).await.unwrap();
+    let flagged: Vec<_> = review
+        .iter()
+        .map(|d| (d.external_id.as_deref(), d.kind.map(|k| k.as_str())))
+        .collect();
+    assert_eq!(flagged, [(Some("pi_gone_1"), Some("missing_at_provider"))]);
+}
+
+// ── 94. shutdown_drains_then_releases_claimed_jobs ─────────────────────────
+
+#[tokio::test]
+async fn shutdown_drains_then_releases_claimed_jobs() {
+    let _worker = ONE_WORKER.lock().await;
+    let pool = setup_pool("fin_sync_test_worker").await;
+    let identity = WorkerIdentity {
+        hostname: "pod-i".into(),
+        instance_id: "0000d1a1".into(),
+    };
+    let enqueue = |event_id: &'static str, object_id: &'static str| {
+        let pool = pool.clone();
+        async move {
+            let raw = serde_json::json!({"id": event_id});
+            job_repo::enqueue(
+                &pool,
+                "stripe",
+                None,
+                event_id,
+                object_id,
+                "test.event",
+                1000,
+                &raw,
+                JobPriority::Normal,
+            )
+            .await
+            .unwrap();
+        }
+    };
+
+    // A job in flight when shutdown comes is finished before the worker
+    // returns.
+    enqueue("evt_drain_1", "pi_drain_1").await;
+    let slow = Arc::new(SlowProvider::default());
+    let mut providers = ProviderRegistry::default();
+    providers.register(slow.clone());
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+    let worker = tokio::spawn(run_worker(
+        pool.clone(),
+        Settings::new(pool.clone()),
+        providers,
+        worker_config(RuntimeConfig::default()),
+        identity.clone(),
+        JobLane::Standard,
+        shutdown_rx,
+    ));
+    for _ in 0..100 {
+        if slow.in_flight.load(Ordering::SeqCst) > 0 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    shutdown_tx.send(true).unwrap();
+    tokio::time::timeout(Duration::from_secs(10), worker)
+        .await
+        .expect("worker did not drain")
+        .unwrap();
+    let status: String =
+        sqlx::query_scalar("SELECT status FROM payment_jobs WHERE event_id = 'evt_drain_1'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(status, "completed");
+
+    // One still stuck when the drain timeout runs out is put back at once,
+    // not left for the reaper.
+    enqueue("evt_drain_2", "pi_drain_2").await;
+    let hanging = Arc::new(HangingProvider::default());
+    let mut providers = ProviderRegistry::default();
+    providers.register(hanging.clone());
+    let config = worker_config(RuntimeConfig {
+        worker_drain_timeout_secs: 1,
+        ..Default::default()
+    });
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+    let worker = tokio::spawn(run_worker(
+        pool.clone(),
+        Settings::new(pool.clone()),
+        providers,
+        config,
+        identity,
+        JobLane::Standard,
+        shutdown_rx,
+    ));
+    for _ in 0..100 {
+        if hanging.started.load(Ordering::SeqCst) > 0 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    shutdown_tx.send(true).unwrap();
+    let stopping = tokio::time::Instant::now();
+    tokio::time::timeout(Duration::from_secs(10), worker)
+        .await
+        .expect("worker did not give up on the stuck job")
+        .unwrap();
+    assert!(stopping.elapsed() >= Duration::from_secs(1));
+
+    let (status, attempts): (String, i32) =
+        sqlx::query_as("SELECT status, attempts FROM payment_jobs WHERE event_id = 'evt_drain_2'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!((status.as_str(), attempts), ("pending", 0));
+    let attempt: (String, Option<String>, Option<String>) = sqlx::query_as(
+        "SELECT a.worker, a.outcome, a.error FROM job_attempts a
+         JOIN payment_jobs j ON j.id = a.job_id
+         WHERE j.event_id = 'evt_drain_2'",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        attempt,
+        (
+            "pod-i/0000d1a1".into(),
+            Some("abandoned".into()),
+            Some("released on shutdown".into())
+        )
+    );
+
+    // Leave nothing for the other tests' workers to hang on.
+    sqlx::query("DELETE FROM payment_jobs WHERE event_id = 'evt_drain_2'")
+        .execute(&pool)
+        .await
+        .unwrap();
+}
+
+// ── 96. claims_follow_priority_then_due_time ───────────────────────────────
+
+#[tokio::test]
+async fn claims_follow_priority_then_due_time() {
+    let _worker = ONE_WORKER.lock().await;
+    let pool = setup_pool("fin_sync_test_worker").await;
+    // Enqueued noise first, as a backlog would be. One object each, so
+    // claims aren't held back by a job of the same object in hand.
+    for (event_id, priority) in [
+        ("evt_prio_low_1", JobPriority::Low),
+        ("evt_prio_low_2", JobPriority::Low),
+        ("evt_prio_normal", JobPriority::Normal),
+        ("evt_prio_high", JobPriority::High),
+    ] {
+        let raw = serde_json::json!({"id": event_id});
+        job_repo::enqueue(
+            &pool,
+            "stripe",
+            None,
+            event_id,
+            &event_id.replace("evt_", "pi_"),
+            "test.event",
+            1000,
+            &raw,
+            priority,
+        )
+        .await
+        .unwrap();
+    }
+
+    let mut order = Vec::new();
+    for _ in 0..4 {
+        let mut tx = pool.begin().await.unwrap();
+        let jobs = job_repo::claim(&mut tx, JobLane::Standard, 1, "pod-j/0000abcd")
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+        order.extend(jobs.into_iter().map(|j| j.event_id));
+    }
+    assert_eq!(
+        order,
+        [
+            "evt_prio_high",
+            "evt_prio_normal",
+            "evt_prio_low_1",
+            "evt_prio_low_2"
+        ]
+    );
+
+    sqlx::query("DELETE FROM payment_jobs WHERE event_id LIKE 'evt_prio_%'")
+        .execute(&pool)
+        .await
+        .unwrap();
+}
+
+// ── 98. worker_reuses_fetches_for_older_events ─────────────────────────────
+
+#[tokio::test]
+async fn worker_reuses_fetches_for_older_events() {
+    let _worker = ONE_WORKER.lock().await;
+    let pool = setup_pool("fin_sync_test_worker").await;
+    // A burst of events sent a minute ago, then one sent after any fetch
+    // the worker could make.
+    let now = Utc::now().timestamp();
+    for (event_id, provider_ts) in [
+        ("evt_cache_1", now - 60),
+        ("evt_cache_2", now - 60),
+        ("evt_cache_3", now - 59),
+        ("evt_cache_4", now + 5),
+    ] {
+        let raw = serde_json::json!({"id": event_id});
+        job_repo::enqueue(
+            &pool,
+            "stripe",
+            None,
+            event_id,
+            "pi_cache",
+            "payment_intent.succeeded",
+            provider_ts,
+            &raw,
+            JobPriority::Normal,
+        )
+        .await
+        .unwrap();
+    }
+
+    let provider = Arc::new(StubProvider::default());
+    let mut providers = ProviderRegistry::default();
+    providers.register(provider.clone());
+    // One job per batch, so every job after the first could be a refetch.
+    let config = worker_config(RuntimeConfig {
+        worker_batch_size: 1,
+        ..Default::default()
+    });
+    let identity = WorkerIdentity {
+        hostname: "pod-k".into(),
+        instance_id: "0000cace".into(),
+    };
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+    let worker = tokio::spawn(run_worker(
+        pool.clone(),
+        Settings::new(pool.clone()),
+        providers,
+        config,
+        identity,
+        JobLane::Standard,
+        shutdown_rx,
+    ));
+
+    let mut done = 0;
+    for _ in 0..50 {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        done = sqlx::query_scalar::<_, i64>(
+            "SELECT count(*) FROM payment_jobs WHERE event_id LIKE 'evt_cache_%' AND status = 'completed'",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        if done == 4 {
+            break;
+        }
+    }
+    shutdown_tx.send(true).unwrap();
+    worker.await.unwrap();
+
+    assert_eq!(done, 4);
+    // The first fetch serves the two older events after it; the newer
+    // event is fetched again.
+    assert_eq!(provider.calls(), 2);
+}
+
+// ── 105. event_status_reports_the_queued_job_until_done ────────────────────
+
+#[tokio::test]
+async fn event_status_reports_the_queued_job_until_done() {
+    let _worker = ONE_WORKER.lock().await;
+    let pool = setup_pool("fin_sync_test_worker").await;
+    let enqueue = |source: &'static str| {
+        let pool = pool.clone();
+        async move {
+            job_repo::enqueue(
+                &pool,
+                source,
+                None,
+                "evt_token_1",
+                "pi_token",
+                "payment_intent.succeeded",
+                1000,
+                &serde_json::json!({"id": "evt_token_1"}),
+                JobPriority::Normal,
+            )
+            .await
+            .unwrap()
+        }
+    };
+    let token = enqueue("stripe").await.expect("new job");
+    assert_eq!(enqueue("stripe").await, None);
+
+    let status = get_event_status(&pool, "evt_token_1", None, None)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(status.token, token);
+    assert_eq!((status.status.as_str(), status.done), ("pending", false));
+    assert_eq!(status.result, None);
+    assert!(
+        get_event_status(&pool, "evt_token_missing", None, None)
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    let mut providers = ProviderRegistry::default();
+    providers.register(Arc::new(StubProvider::default()));
+    let identity = WorkerIdentity {
+        hostname: "pod-l".into(),
+        instance_id: "000070ce".into(),
+    };
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+    let worker = tokio::spawn(run_worker(
+        pool.clone(),
+        Settings::new(pool.clone()),
+        providers,
+        worker_config(RuntimeConfig::default()),
+        identity,
+        JobLane::Standard,
+        shutdown_rx,
+    ));
+
+    let mut done = None;
+    for _ in 0..50 {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let status = get_event_status(&pool, "evt_token_1", Some("stripe"), None)
+            .await
+            .unwrap()
+            .unwrap();
+        if status.done {
+            done = Some(status);
+            break;
+        }
+    }
+    shutdown_tx.send(true).unwrap();
+    worker.await.unwrap();
+
+    // Done means the payment is there to be read.
+    let done = done.expect("job processed");
+    assert_eq!(done.token, token);
+    assert_eq!(done.status, "completed");
+    assert_eq!(done.result.as_deref(), Some("created"));
+    assert_eq!(done.outcome.as_deref(), Some("succeeded"));
+    let payment_id: uuid::Uuid =
+        sqlx::query_scalar("SELECT id FROM payments WHERE external_id = 'pi_token'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(done.payment_id, Some(payment_id));
+
+    // The same id from another provider needs the source spelled out.
+    let paypal = enqueue("paypal").await.expect("another source's job");
+    let err = get_event_status(&pool, "evt_token_1", None, None)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, PipelineError::Validation(_)));
+    sqlx::query("DELETE FROM payment_jobs WHERE id = $1")
+        .bind(paypal)
+        .execute(&pool)
+        .await
+        .unwrap();
+}
+
+// ── 113. idle_worker_backs_off_and_resets_on_work ──────────────────────────
+
+#[tokio::test]
+async fn idle_worker_backs_off_and_resets_on_work() {
+    let _worker = ONE_WORKER.lock().await;
+    let pool = setup_pool("fin_sync_test_worker").await;
+    let mut providers = ProviderRegistry::default();
+    providers.register(Arc::new(StubProvider::default()));
+    let config = worker_config(RuntimeConfig {
+        worker_poll_interval_ms: 100,
+        worker_idle_poll_max_ms: 800,
+        ..Default::default()
+    });
+    let identity = WorkerIdentity {
+        hostname: "pod-m".into(),
+        instance_id: "0000aced".into(),
+    };
+    let clock = RecordingClock::default();
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+    let worker = tokio::spawn(run_worker_with_clock(
+        pool.clone(),
+        Settings::new(pool.clone()),
+        providers,
+        config,
+        identity,
+        JobLane::Standard,
+        shutdown_rx,
+        clock.clone(),
+    ));
+
+    // An empty queue: the pause doubles up to the max and stays there.
+    for _ in 0..100 {
+        if clock.pauses().len() >= 6 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(clock.pauses()[..6], [100, 200, 400, 800, 800, 800]);
+
+    // Work brings it back to the poll interval, then it backs off again.
+    for i in 1..=3 {
+        job_repo::enqueue(
+            &pool,
+            "stripe",
+            None,
+            &format!("evt_idle_{i}"),
+            &format!("pi_idle_{i}"),
+            "payment_intent.succeeded",
+            1000,
+            &serde_json::json!({"id": format!("evt_idle_{i}")}),
+            JobPriority::Normal,
+        )
+        .await
+        .unwrap();
+    }
+    // Where the pause first drops back to 100ms, and what follows the
+    // wakeups that found work.
+    let reset = |pauses: &[u64]| pauses[1..].iter().position(|&p| p == 100).map(|i| i + 1);
+    let after_work = |pauses: &[u64]| -> Vec<u64> {
+        let Some(start) = reset(pauses) else {
+            return Vec::new();
+        };
+        let rest = pauses[start..].iter().copied();
+        rest.skip_while(|&p| p == 100).collect()
+    };
+    let mut pauses = Vec::new();
+    for _ in 0..100 {
+        pauses = clock.pauses();
+        if after_work(&pauses).contains(&800) {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    shutdown_tx.send(true).unwrap();
+    worker.await.unwrap();
+
+    let pending: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM payment_jobs WHERE status <> 'completed'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(pending, 0);
+    let reset = reset(&pauses).expect("pause reset after work");
+    assert!(pauses[3..reset].iter().all(|&p| p == 800), "{pauses:?}");
+    assert_eq!(after_work(&pauses)[..3], [200, 400, 800], "{pauses:?}");
+}
+
+// ── 125. claims_take_whole_objects_one_worker_at_a_time ────────────────────
+
+#[tokio::test]
+async fn claims_take_whole_objects_one_worker_at_a_time() {
+    let _worker = ONE_WORKER.lock().await;
+    let pool = setup_pool("fin_sync_test_worker").await;
+    let enqueue = |event_id: &'static str, object_id: &'static str, provider_ts: i64| {
+        let pool = pool.clone();
+        async move {
+            let raw = serde_json::json!({"id": event_id});
+            job_repo::enqueue(
+                &pool,
+                "stripe",
+                None,
+                event_id,
+                object_id,
+                "test.event",
+                provider_ts,
+                &raw,
+                JobPriority::Normal,
+            )
+            .await
+            .unwrap();
+        }
+    };
+    let claim = |pool: &sqlx::PgPool, limit: i64, worker: &'static str| {
+        let pool = pool.clone();
+        async move {
+            let mut tx = pool.begin().await.unwrap();
+            let jobs = job_repo::claim(&mut tx, JobLane::Standard, limit, worker)
+                .await
+                .unwrap();
+            tx.commit().await.unwrap();
+            jobs.into_iter().map(|j| j.event_id).collect::<Vec<_>>()
+        }
+    };
+    enqueue("evt_grp_a3", "pi_grp_a", 3000).await;
+    enqueue("evt_grp_a1", "pi_grp_a", 1000).await;
+    enqueue("evt_grp_b1", "pi_grp_b", 1000).await;
+    enqueue("evt_grp_a2", "pi_grp_a", 2000).await;
+
+    // The first object's jobs come together, oldest event first, ahead of
+    // the second object even though one of its jobs is older. Cut short
+    // by the limit, it is its oldest events that go.
+    assert_eq!(
+        claim(&pool, 2, "pod-k/0000aaaa").await,
+        ["evt_grp_a1", "evt_grp_a2"]
+    );
+    // While they're in hand, the rest of that object waits.
+    assert_eq!(claim(&pool, 10, "pod-k/0000bbbb").await, ["evt_grp_b1"]);
+    assert!(claim(&pool, 10, "pod-k/0000bbbb").await.is_empty());
+    for event_id in ["evt_grp_a1", "evt_grp_a2"] {
+        let id: uuid::Uuid = sqlx::query_scalar("SELECT id FROM payment_jobs WHERE event_id = $1")
+            .bind(event_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        job_repo::discard(&pool, id, "test").await.unwrap();
+    }
+    assert_eq!(claim(&pool, 10, "pod-k/0000bbbb").await, ["evt_grp_a3"]);
+
+    // Past the first object, objects that don't fit whole are left out.
+    enqueue("evt_grp_d1", "pi_grp_d", 1000).await;
+    enqueue("evt_grp_e1", "pi_grp_e", 1100).await;
+    enqueue("evt_grp_e2", "pi_grp_e", 1200).await;
+    assert_eq!(claim(&pool, 2, "pod-k/0000aaaa").await, ["evt_grp_d1"]);
+    assert_eq!(
+        claim(&pool, 2, "pod-k/0000bbbb").await,
+        ["evt_grp_e1", "evt_grp_e2"]
+    );
+
+    // Two workers claiming at once don't split an object either.
+    enqueue("evt_grp_c1", "pi_grp_c", 1000).await;
+    enqueue("evt_grp_c2", "pi_grp_c", 2000).await;
+    let mut first = pool.begin().await.unwrap();
+    let jobs = job_repo::claim(&mut first, JobLane::Standard, 1, "pod-k/0000aaaa")
+        .await
+        .unwrap();
+    assert_eq!(jobs.len(), 1);
+    assert!(claim(&pool, 10, "pod-k/0000bbbb").await.is_empty());
+    first.rollback().await.unwrap();
+    assert_eq!(
+        claim(&pool, 10, "pod-k/0000bbbb").await,
+        ["evt_grp_c1", "evt_grp_c2"]
+    );
+
+    sqlx::query("DELETE FROM payment_jobs WHERE event_id LIKE 'evt_grp_%'")
+        .execute(&pool)
+        .await
+        .unwrap();
+}
+
+// ── 129. complete_webhook_objects_apply_without_a_fetch ────────────────────
+
+#[tokio::test]
+async fn complete_webhook_objects_apply_without_a_fetch() {
+    let _worker = ONE_WORKER.lock().await;
+    let pool = setup_pool("fin_sync_test_worker").await;
+    let fixtures = [
+        // Complete, and of a type applied as sent.
+        StripeEventFixture::payment_intent("pi_payload_1", "processing")
+            .with_event_id("evt_payload_1"),
+        StripeEventFixture::payment_intent("pi_payload_1", "succeeded")
+            .with_event_id("evt_payload_2")
+            .with_created(1_700_000_100),
+        // Truncated: fetched anyway.
+        StripeEventFixture::payment_intent("pi_payload_2", "succeeded")
+            .with_event_id("evt_payload_3")
+            .with_object_field("amount", serde_json::Value::Null),
+        // Complete, but of a type that's always fetched.
+        StripeEventFixture::payment_intent("pi_payload_3", "canceled")
+            .with_event_id("evt_payload_4"),
+    ];
+    for fixture in &fixtures {
+        let raw = fixture.to_json();
+        job_repo::enqueue(
+            &pool,
+            "stripe",
+            None,
+            fixture.event_id(),
+            raw["data"]["object"]["id"].as_str().unwrap(),
+            raw["type"].as_str().unwrap(),
+            raw["created"].as_i64().unwrap(),
+            &raw,
+            JobPriority::Normal,
+        )
+        .await
+        .unwrap();
+    }
+
+    let provider = Arc::new(PayloadProvider {
+        stripe: StripeProvider::new("sk_test_payload").with_api_base("http://127.0.0.1:9"),
+        counting: StubProvider::default(),
+    });
+    let mut providers = ProviderRegistry::default();
+    providers.register(provider.clone());
+    let config = worker_config(RuntimeConfig {
+        payload_event_types: vec![
+            "payment_intent.processing".into(),
+            "payment_intent.succeeded".into(),
+        ],
+        ..Default::default()
+    });
+    let identity = WorkerIdentity {
+        hostname: "pod-k".into(),
+        instance_id: "0000fee1".into(),
+    };
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+    let worker = tokio::spawn(run_worker(
+        pool.clone(),
+        Settings::new(pool.clone()),
+        providers,
+        config,
+        identity,
+        JobLane::Standard,
+        shutdown_rx,
+    ));
+
+    let mut done = 0;
+    for _ in 0..50 {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        done = sqlx::query_scalar::<_, i64>(
+            "SELECT count(*) FROM payment_jobs WHERE event_id LIKE 'evt_payload_%' AND status = 'completed'",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        if done == 4 {
+            break;
+        }
+    }
+    shutdown_tx.send(true).unwrap();
+    worker.await.unwrap();
+    assert_eq!(done, 4);
+
+    assert_eq!(provider.counting.calls(), 2);
+    let stored = |id: &'static str| {
+        let pool = pool.clone();
+        async move {
+            sqlx::query_as::<_, (String, i64)>(
+                "SELECT status, amount FROM payments WHERE external_id = $1",
+            )
+            .bind(id)
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+        }
+    };
+    // The event's own object, both events applied in turn.
+    assert_eq!(stored("pi_payload_1").await, ("succeeded".into(), 1500));
+    // The fetched state.
+    assert_eq!(stored("pi_payload_2").await, ("succeeded".into(), 900));
+    assert_eq!(stored("pi_payload_3").await, ("succeeded".into(), 900));
+}